@@ -0,0 +1,318 @@
+//! Throughput and allocation benchmarks for lexing, building syntax trees
+//! from markup, parsing and JSONPath query parsing, with and without a query
+//! cache.
+//!
+//! Run with `cargo bench`, optionally with a filter, like
+//! `cargo bench -- parse/large`. Each case is measured three times by
+//! Criterion: for wall-clock time and throughput over the source text, then
+//! for the number of heap allocations and the number of bytes allocated by
+//! one iteration, under the `allocations/` and `allocated-bytes/` groups.
+//! Criterion keeps results under `target/criterion` and reports changes
+//! since the last run on the same machine.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use _liquid2::lexer::Lexer;
+use _liquid2::parser::LiquidParser;
+use _liquid2::query::cache::QueryCache;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting allocations as it goes.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A Criterion measurement of heap use, read from `CountingAllocator`.
+enum Heap {
+    /// The number of allocations and reallocations.
+    Allocations,
+    /// The total size of allocations and reallocations, in bytes.
+    Bytes,
+}
+
+impl Heap {
+    fn counter(&self) -> &'static AtomicUsize {
+        match self {
+            Heap::Allocations => &ALLOCATIONS,
+            Heap::Bytes => &ALLOCATED_BYTES,
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Heap::Allocations => "allocs",
+            Heap::Bytes => "B",
+        }
+    }
+}
+
+impl Measurement for Heap {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        self.counter().load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        self.counter().load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, a: &usize, b: &usize) -> usize {
+        a + b
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        self
+    }
+}
+
+impl ValueFormatter for Heap {
+    fn scale_values(&self, _typical: f64, _values: &mut [f64]) -> &'static str {
+        self.unit()
+    }
+
+    /// Heap use per byte of source text.
+    fn scale_throughputs(
+        &self,
+        _typical: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let bytes = match throughput {
+            Throughput::Bytes(n) | Throughput::BytesDecimal(n) | Throughput::Elements(n) => *n,
+        };
+        for value in values.iter_mut() {
+            *value /= bytes as f64;
+        }
+        match self {
+            Heap::Allocations => "allocs/B",
+            Heap::Bytes => "B/B",
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        self.unit()
+    }
+}
+
+const SMALL: &str = r#"<h1>{{ product.title | escape }}</h1>
+{% if product.available %}
+  <p class="price">{{ product.price | times: 1.2 | round: 2 | prepend: '$' }}</p>
+{% else %}
+  <p>Sold out</p>
+{% endif %}
+<ul>
+  {% for tag in product.tags limit: 5 %}
+    <li>{{ forloop.index }}: {{ tag | upcase }}</li>
+  {% endfor %}
+</ul>
+{% assign total = cart.items | map: 'price' | sum %}
+{% liquid
+  if total > 100
+    echo 'Free shipping'
+  endif
+%}"#;
+
+/// A page made of many copies of _SMALL_ and other common markup.
+fn large() -> String {
+    let mut source = String::new();
+    for i in 0..200 {
+        source.push_str(SMALL);
+        source.push_str(&format!(
+            "\n{{% case section_{i}.type %}}{{% when 'banner', 'hero' %}}\
+             {{% render 'banner' with section_{i} as section %}}\
+             {{% else %}}{{{{ section_{i}.html }}}}{{% endcase %}}\n\
+             {{% comment %}}section {i}{{% endcomment %}}\n"
+        ));
+    }
+    source
+}
+
+/// Conditions and loops nested _depth_ deep.
+fn nested(depth: usize) -> String {
+    let mut source = String::new();
+    for i in 0..depth {
+        if i % 2 == 0 {
+            source.push_str(&format!("{{% if a{i} and b{i} %}}"));
+        } else {
+            source.push_str(&format!("{{% for x{i} in y{i} %}}"));
+        }
+    }
+    source.push_str("{{ x }}");
+    for i in (0..depth).rev() {
+        source.push_str(if i % 2 == 0 {
+            "{% endif %}"
+        } else {
+            "{% endfor %}"
+        });
+    }
+    source
+}
+
+/// Output statements with long filter chains and keyword arguments.
+fn filters() -> String {
+    let mut source = String::new();
+    for i in 0..500 {
+        source.push_str(&format!(
+            "{{{{ items[{i}].name | downcase | replace: 'a', 'b' | split: ',' \
+             | sort: 'title' | where: 'available', true | first | default: 'none', \
+             allow_false: true | truncate: 20, '...' | append: suffix }}}}\n"
+        ));
+    }
+    source
+}
+
+const QUERIES: [(&str, &str); 5] = [
+    ("shorthand", "$.store.book[0].title"),
+    ("bracketed", "$['store']['book'][*]['author']"),
+    ("slice", "$.store.book[-3::2]"),
+    (
+        "filter",
+        "$..book[?@.price < 10 && (@.category == 'fiction' || !@.isbn)]",
+    ),
+    (
+        "functions",
+        "$.store.book[?length(@.tags) > 2 && match(@.author, '[A-Z].*')]",
+    ),
+];
+
+/// Every case, in groups named _prefix_ followed by what they measure.
+fn benches<M: Measurement>(c: &mut Criterion<M>, prefix: &str) {
+    let templates = [
+        ("small", SMALL.to_owned()),
+        ("large", large()),
+        ("nested", nested(100)),
+        ("filters", filters()),
+    ];
+
+    let lexer = Lexer::new();
+    let mut group = c.benchmark_group(format!("{prefix}tokenize"));
+    for (name, source) in &templates {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| lexer.tokenize(black_box(source)).unwrap())
+        });
+    }
+    group.finish();
+
+    let parser = LiquidParser::new();
+    let mut group = c.benchmark_group(format!("{prefix}parse"));
+    for (name, source) in &templates {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| parser.parse(black_box(source)).unwrap())
+        });
+    }
+    group.finish();
+
+    // Parsing without tokenizing, to see how much of `parse` is spent
+    // building the syntax tree.
+    let mut group = c.benchmark_group(format!("{prefix}build"));
+    for (name, source) in &templates {
+        let markup = lexer.tokenize(source).unwrap();
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| parser.parse_markup(black_box(&markup)).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{prefix}validate"));
+    for (name, source) in &templates {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(*name, |b| b.iter(|| parser.validate(black_box(source))));
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{prefix}query"));
+    for (name, path) in QUERIES {
+        group.throughput(Throughput::Bytes(path.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| lexer.parse_jsonpath_query(black_box(path)).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut cached = Lexer::new();
+    cached.query_parser.cache = Some(Arc::new(QueryCache::new(QUERIES.len())));
+    let mut group = c.benchmark_group(format!("{prefix}query/cached"));
+    for (name, path) in QUERIES {
+        group.throughput(Throughput::Bytes(path.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| cached.parse_query_shared(black_box(path)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn time(c: &mut Criterion) {
+    benches(c, "");
+}
+
+fn allocations(c: &mut Criterion<Heap>) {
+    benches(c, "allocations/");
+}
+
+fn allocated_bytes(c: &mut Criterion<Heap>) {
+    benches(c, "allocated-bytes/");
+}
+
+/// Heap use is the same on every iteration, so a few short samples are
+/// enough.
+fn heap(measurement: Heap) -> Criterion<Heap> {
+    Criterion::default()
+        .with_measurement(measurement)
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(10))
+        .measurement_time(Duration::from_millis(100))
+}
+
+criterion_group!(timed, time);
+criterion_group! {
+    name = heap_allocations;
+    config = heap(Heap::Allocations);
+    targets = allocations
+}
+criterion_group! {
+    name = heap_bytes;
+    config = heap(Heap::Bytes);
+    targets = allocated_bytes
+}
+criterion_main!(timed, heap_allocations, heap_bytes);
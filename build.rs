@@ -0,0 +1,42 @@
+//! Fingerprint the crate's source, so the on-disk template cache can refuse
+//! files written by a build with a different syntax tree layout.
+//!
+//! Any change to the source might change the layout of the syntax tree, so
+//! all of it is hashed rather than guessing which files matter. A changed
+//! fingerprint only means cached templates are parsed again.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+
+    let mut files = Vec::new();
+    collect(Path::new("src"), &mut files);
+    files.sort();
+
+    // 64-bit FNV-1a, which is stable between builds and platforms, unlike
+    // the standard library's hashers.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for file in &files {
+        let name = file.to_string_lossy().replace('\\', "/");
+        let content = fs::read(file).expect("readable source file");
+        for byte in name.bytes().chain([0]).chain(content) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    println!("cargo:rustc-env=LIQUID2_SCHEMA_FINGERPRINT={hash:016x}");
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("readable source directory") {
+        let path = entry.expect("readable directory entry").path();
+        if path.is_dir() {
+            collect(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
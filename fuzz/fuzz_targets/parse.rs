@@ -0,0 +1,10 @@
+#![no_main]
+
+use _liquid2::parser::LiquidParser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let parser = LiquidParser::new();
+    let _ = parser.parse(source);
+    let _ = parser.parse_expression(source);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use _liquid2::lexer::{Lexer, QueryExtensions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|path: &str| {
+    let _ = Lexer::new().parse_query(path);
+    let _ = Lexer::new().parse_jsonpath_query(path);
+    let _ = Lexer::with_query_extensions(QueryExtensions::strict()).parse_jsonpath_query(path);
+});
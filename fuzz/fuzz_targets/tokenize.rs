@@ -0,0 +1,15 @@
+#![no_main]
+
+use _liquid2::lexer::Lexer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let lexer = Lexer::new();
+    let markup = lexer.tokenize(source);
+    // The scanner used by `tokenize` must agree with the grammar.
+    assert_eq!(
+        format!("{markup:?}"),
+        format!("{:?}", lexer.tokenize_grammar(source))
+    );
+    for _ in lexer.iter_tokens(source) {}
+});
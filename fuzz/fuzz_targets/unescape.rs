@@ -0,0 +1,9 @@
+#![no_main]
+
+use _liquid2::span::Span;
+use _liquid2::unescape::unescape;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|value: &str| {
+    let _ = unescape(value, &Span::new(0, value.len()));
+});
@@ -1,6 +1,9 @@
 from __future__ import annotations
 
 from enum import Enum
+from typing import Callable
+from typing import Any
+from typing import Mapping
 from typing import TypeAlias
 
 class Whitespace(Enum):
@@ -9,6 +12,31 @@ class Whitespace(Enum):
     Smart = ...
     Default = ...
 
+class IdentifierPolicy(Enum):
+    Unicode = ...
+    Ascii = ...
+    Xid = ...
+    Shopify = ...
+
+class SmartTrim(Enum):
+    Newlines = ...
+    Collapse = ...
+    Shopify = ...
+
+class RawSpans:
+    @property
+    def open(self) -> tuple[int, int]: ...
+    @property
+    def content(self) -> tuple[int, int]: ...
+    @property
+    def close(self) -> tuple[int, int]: ...
+
+class CommentKind(Enum):
+    Hash = ...
+    Block = ...
+    Inline = ...
+    Doc = ...
+
 class Markup:
     class Content:
         __match_args__ = ("text", "span")
@@ -18,16 +46,18 @@ class Markup:
         def span(self) -> tuple[int, int]: ...
 
     class Raw:
-        __match_args__ = ("wc", "text", "span")
+        __match_args__ = ("wc", "text", "span", "spans")
         @property
         def wc(self) -> tuple[Whitespace, Whitespace, Whitespace, Whitespace]: ...
         @property
         def text(self) -> str: ...
         @property
         def span(self) -> tuple[int, int]: ...
+        @property
+        def spans(self) -> RawSpans | None: ...
 
     class Comment:
-        __match_args__ = ("wc", "hashes", "text", "span")
+        __match_args__ = ("wc", "hashes", "text", "kind", "span")
         @property
         def wc(self) -> tuple[Whitespace, Whitespace]: ...
         @property
@@ -35,6 +65,8 @@ class Markup:
         @property
         def text(self) -> str: ...
         @property
+        def kind(self) -> CommentKind: ...
+        @property
         def span(self) -> tuple[int, int]: ...
 
     class Output:
@@ -68,9 +100,19 @@ class Markup:
         @property
         def span(self) -> tuple[int, int]: ...
 
+    class Error:
+        __match_args__ = ("text", "span")
+        @property
+        def text(self) -> str: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
     class EOI:
         pass
 
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
 class Token:
     class True_:  # noqa: N801
         __match_args__ = ("span",)
@@ -187,6 +229,16 @@ class Token:
         @property
         def span(self) -> tuple[int, int]: ...
 
+    class Elvis:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Arrow:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
     class Comma:
         __match_args__ = ("span",)
         @property
@@ -236,12 +288,14 @@ class Token:
         def span(self) -> tuple[int, int]: ...
 
     class RangeLiteral:
-        __match_args__ = ("start", "stop", "span")
+        __match_args__ = ("start", "stop", "step", "span")
         @property
         def start(self) -> RangeArgument: ...
         @property
         def stop(self) -> RangeArgument: ...
         @property
+        def step(self) -> RangeArgument | None: ...
+        @property
         def span(self) -> tuple[int, int]: ...
 
     class Query:
@@ -251,6 +305,13 @@ class Token:
         @property
         def span(self) -> tuple[int, int]: ...
 
+    @property
+    def kind(self) -> str: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
 class RangeArgument:
     class StringLiteral:
         __match_args__ = ("value", "span")
@@ -280,6 +341,9 @@ class RangeArgument:
         @property
         def span(self) -> tuple[int, int]: ...
 
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
 class ComparisonOperator(Enum):
     Eq = ...
     Ne = ...
@@ -447,15 +511,1263 @@ class Query:
     @property
     def segments(self) -> list[Segment]: ...
     def as_word(self) -> None | str: ...
+    def find(self, data: Any) -> list[QueryNode]: ...
+    def complexity(self) -> int: ...
+    def normalize(self) -> Query: ...
+    def canonical(self) -> str: ...
+    def to_shorthand(self) -> str: ...
+
+class QueryNode:
+    def value(self) -> Any: ...
+    def path(self) -> str: ...
+    @property
+    def location(self) -> list[str | int]: ...
 
-def tokenize(source: str) -> list[Markup]: ...
-def dump(source: str) -> None: ...
-def dump_query(path: str) -> None: ...
-def parse_query(path: str) -> Query: ...
-def parse_jsonpath_query(path: str) -> Query: ...
-def unescape_string(s: str) -> str: ...
+class Template:
+    @property
+    def nodes(self) -> list[Node]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(json: str) -> Template: ...
+    def analyze(self) -> TemplateAnalysis: ...
+    def references(self, name: str) -> list[Reference]: ...
+    def references_at(self, offset: int) -> list[Reference]: ...
+    def semantic_model(self) -> SemanticModel: ...
+    def estimate(
+        self,
+        *,
+        collection_size: int | None = None,
+        value_size: int | None = None,
+        partial_size: int | None = None,
+        default_loop_limit: int | None = None,
+    ) -> RenderEstimate: ...
+    def optimized(self) -> Template: ...
+    def metadata(self) -> TemplateMetadata | None: ...
+    def to_string_with_source_map(self) -> tuple[str, SourceMap]: ...
+
+class ConcreteTemplate:
+    @property
+    def source(self) -> str: ...
+    @property
+    def template(self) -> Template: ...
+    def text(self, span: tuple[int, int]) -> str: ...
+    def to_source(self) -> str: ...
+    def edit(self, span: tuple[int, int], text: str) -> None: ...
+
+class TemplateMetadata:
+    @property
+    def description(self) -> str: ...
+    @property
+    def params(self) -> list[ParamDoc]: ...
+    @property
+    def examples(self) -> list[str]: ...
+
+class ParamDoc:
+    @property
+    def name(self) -> str: ...
+    @property
+    def type_(self) -> str | None: ...
+    @property
+    def required(self) -> bool: ...
+    @property
+    def description(self) -> str: ...
+
+class Variable:
+    @property
+    def name(self) -> str: ...
+    @property
+    def path(self) -> Query: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class Identifier:
+    @property
+    def name(self) -> str: ...
+    @property
+    def tag(self) -> str: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class ReferenceKind(Enum):
+    Read = ...
+    Assign = ...
+    Capture = ...
+    Increment = ...
+    Decrement = ...
+    LoopVariable = ...
+
+class Reference:
+    @property
+    def name(self) -> str: ...
+    @property
+    def kind(self) -> ReferenceKind: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class Definedness(Enum):
+    Local = ...
+    Assigned = ...
+    Counter = ...
+    PossiblyUndefined = ...
+    Global = ...
+
+class NameUse:
+    @property
+    def name(self) -> str: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+    @property
+    def definedness(self) -> Definedness: ...
+    @property
+    def definitions(self) -> list[tuple[int, int]]: ...
+
+class SemanticModel:
+    @property
+    def uses(self) -> list[NameUse]: ...
+    def possibly_undefined(self) -> list[NameUse]: ...
+    def use_at(self, offset: int) -> NameUse | None: ...
+    def __len__(self) -> int: ...
+
+class LoopEstimate:
+    @property
+    def span(self) -> tuple[int, int]: ...
+    @property
+    def iterations(self) -> int: ...
+    @property
+    def total(self) -> int: ...
+    @property
+    def bounded(self) -> bool: ...
+
+class RenderEstimate:
+    @property
+    def output_size(self) -> int: ...
+    @property
+    def iterations(self) -> int: ...
+    @property
+    def loops(self) -> list[LoopEstimate]: ...
+
+class TemplateAnalysis:
+    @property
+    def variables(self) -> list[Variable]: ...
+    @property
+    def globals(self) -> list[Variable]: ...
+    @property
+    def assigned(self) -> list[Identifier]: ...
+    @property
+    def used_before_definition(self) -> list[Variable]: ...
+
+class Node:
+    class Content:
+        __match_args__ = ("text", "span")
+        @property
+        def text(self) -> str: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Raw:
+        __match_args__ = ("wc", "text", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace, Whitespace, Whitespace]: ...
+        @property
+        def text(self) -> str: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Comment:
+        __match_args__ = ("wc", "hashes", "text", "kind", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def hashes(self) -> str: ...
+        @property
+        def text(self) -> str: ...
+        @property
+        def kind(self) -> CommentKind: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class DocTag:
+        __match_args__ = ("wc", "text", "doc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def text(self) -> str: ...
+        @property
+        def doc(self) -> TemplateMetadata: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Output:
+        __match_args__ = ("wc", "expression", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def expression(self) -> FilteredExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class AssignTag:
+        __match_args__ = ("wc", "identifier", "identifier_span", "expression", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def identifier(self) -> str: ...
+        @property
+        def identifier_span(self) -> tuple[int, int]: ...
+        @property
+        def expression(self) -> FilteredExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class CaptureTag:
+        __match_args__ = (
+            "wc",
+            "identifier",
+            "identifier_span",
+            "block",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def identifier(self) -> str: ...
+        @property
+        def identifier_span(self) -> tuple[int, int]: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class CaseTag:
+        __match_args__ = ("wc", "expression", "whens", "default", "end_wc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def expression(self) -> Primitive: ...
+        @property
+        def whens(self) -> list[WhenTag]: ...
+        @property
+        def default(self) -> ElseTag | None: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class CycleTag:
+        __match_args__ = ("wc", "name", "args", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> Primitive | None: ...
+        @property
+        def args(self) -> list[Primitive]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class DecrementTag:
+        __match_args__ = ("wc", "name", "name_span", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def name_span(self) -> tuple[int, int]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class IncrementTag:
+        __match_args__ = ("wc", "name", "name_span", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def name_span(self) -> tuple[int, int]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class EchoTag:
+        __match_args__ = ("wc", "expression", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def expression(self) -> FilteredExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class ForTag:
+        __match_args__ = (
+            "wc",
+            "name",
+            "value_name",
+            "name_spans",
+            "iterable",
+            "limit",
+            "offset",
+            "reversed",
+            "block",
+            "default",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def value_name(self) -> str | None: ...
+        @property
+        def name_spans(self) -> list[tuple[int, int]]: ...
+        @property
+        def iterable(self) -> Primitive: ...
+        @property
+        def limit(self) -> Primitive | None: ...
+        @property
+        def offset(self) -> Primitive | None: ...
+        @property
+        def reversed(self) -> bool: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def default(self) -> ElseTag | None: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class TablerowTag:
+        __match_args__ = (
+            "wc",
+            "name",
+            "name_span",
+            "iterable",
+            "cols",
+            "limit",
+            "offset",
+            "block",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def name_span(self) -> tuple[int, int]: ...
+        @property
+        def iterable(self) -> Primitive: ...
+        @property
+        def cols(self) -> Primitive | None: ...
+        @property
+        def limit(self) -> Primitive | None: ...
+        @property
+        def offset(self) -> Primitive | None: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class BreakTag:
+        __match_args__ = ("wc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class ContinueTag:
+        __match_args__ = ("wc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class IfTag:
+        __match_args__ = (
+            "wc",
+            "condition",
+            "block",
+            "alternatives",
+            "default",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def condition(self) -> BooleanExpression: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def alternatives(self) -> list[ElsifTag]: ...
+        @property
+        def default(self) -> ElseTag | None: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class UnlessTag:
+        __match_args__ = (
+            "wc",
+            "condition",
+            "block",
+            "alternatives",
+            "default",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def condition(self) -> BooleanExpression: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def alternatives(self) -> list[ElsifTag]: ...
+        @property
+        def default(self) -> ElseTag | None: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
 
-class PyLiquidError(Exception): ...
+    class IfchangedTag:
+        __match_args__ = ("wc", "block", "end_wc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class IncludeTag:
+        __match_args__ = ("wc", "target", "repeat", "variable", "alias", "args", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def target(self) -> Primitive: ...
+        @property
+        def repeat(self) -> bool: ...
+        @property
+        def variable(self) -> Primitive | None: ...
+        @property
+        def alias(self) -> str | None: ...
+        @property
+        def args(self) -> list[KeywordArgument]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class RenderTag:
+        __match_args__ = (
+            "wc",
+            "target",
+            "dynamic",
+            "repeat",
+            "variable",
+            "alias",
+            "args",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def target(self) -> Primitive: ...
+        @property
+        def dynamic(self) -> bool: ...
+        @property
+        def repeat(self) -> bool: ...
+        @property
+        def variable(self) -> Primitive | None: ...
+        @property
+        def alias(self) -> str | None: ...
+        @property
+        def args(self) -> list[KeywordArgument]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class LiquidTag:
+        __match_args__ = ("wc", "block", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class ExtendsTag:
+        __match_args__ = ("wc", "name", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class BlockTag:
+        __match_args__ = (
+            "wc",
+            "name",
+            "scoped",
+            "required",
+            "block",
+            "end_wc",
+            "named_end",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def scoped(self) -> bool: ...
+        @property
+        def required(self) -> bool: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def named_end(self) -> bool: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class MacroTag:
+        __match_args__ = ("wc", "name", "params", "block", "end_wc", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def params(self) -> list[Parameter]: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class CallTag:
+        __match_args__ = ("wc", "name", "args", "span")
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def args(self) -> list[CommonArgument]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class TranslateTag:
+        __match_args__ = (
+            "wc",
+            "name",
+            "args",
+            "block",
+            "plural",
+            "end_wc",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def args(self) -> list[KeywordArgument]: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def plural(self) -> PluralTag | None: ...
+        @property
+        def end_wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class TagExtension:
+        __match_args__ = (
+            "wc",
+            "name",
+            "expression",
+            "args",
+            "block",
+            "branches",
+            "end",
+            "span",
+        )
+        @property
+        def wc(self) -> tuple[Whitespace, Whitespace]: ...
+        @property
+        def name(self) -> str: ...
+        @property
+        def expression(self) -> list[Token]: ...
+        @property
+        def args(self) -> list[CommonArgument] | None: ...
+        @property
+        def block(self) -> list[Node]: ...
+        @property
+        def branches(self) -> list[ExtensionBranch]: ...
+        @property
+        def end(self) -> ExtensionEnd | None: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+class ElsifTag:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def condition(self) -> BooleanExpression: ...
+    @property
+    def block(self) -> list[Node]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class ElseTag:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def block(self) -> list[Node]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class PluralTag:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def block(self) -> list[Node]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class WhenTag:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def args(self) -> list[Primitive]: ...
+    @property
+    def block(self) -> list[Node]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class ExtensionBranch:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def expression(self) -> list[Token]: ...
+    @property
+    def block(self) -> list[Node]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class ExtensionEnd:
+    @property
+    def wc(self) -> tuple[Whitespace, Whitespace]: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class FilteredExpression:
+    @property
+    def left(self) -> Primitive: ...
+    @property
+    def filters(self) -> list[Filter] | None: ...
+    @property
+    def condition(self) -> InlineCondition | None: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class InlineCondition:
+    @property
+    def expr(self) -> BooleanExpression: ...
+    @property
+    def alternative(self) -> Primitive | None: ...
+    @property
+    def alternative_filters(self) -> list[Filter] | None: ...
+    @property
+    def alternative_condition(self) -> InlineCondition | None: ...
+    @property
+    def tail_filters(self) -> list[Filter] | None: ...
+    @property
+    def elvis(self) -> bool: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class Filter:
+    @property
+    def name(self) -> str: ...
+    @property
+    def args(self) -> list[CommonArgument] | None: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class CommonArgument:
+    @property
+    def value(self) -> Primitive: ...
+    @property
+    def name(self) -> str | None: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class KeywordArgument:
+    @property
+    def name(self) -> str: ...
+    @property
+    def value(self) -> Primitive: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class Parameter:
+    @property
+    def name(self) -> str: ...
+    @property
+    def default(self) -> Primitive | None: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+
+class Primitive:
+    class TrueLiteral:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class FalseLiteral:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class NullLiteral:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Empty:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Blank:
+        __match_args__ = ("span",)
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Integer:
+        __match_args__ = ("value", "span")
+        @property
+        def value(self) -> int: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Float:
+        __match_args__ = ("value", "span")
+        @property
+        def value(self) -> float: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class StringLiteral:
+        __match_args__ = ("value", "span")
+        @property
+        def value(self) -> str: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Range:
+        __match_args__ = ("start", "stop", "step", "span")
+        @property
+        def start(self) -> Primitive: ...
+        @property
+        def stop(self) -> Primitive: ...
+        @property
+        def step(self) -> Primitive | None: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Query:
+        __match_args__ = ("path", "span")
+        @property
+        def path(self) -> Query: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Lambda:
+        __match_args__ = ("params", "expr", "span")
+        @property
+        def params(self) -> list[str]: ...
+        @property
+        def expr(self) -> BooleanExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+class BooleanOperator(Enum):
+    And = ...
+    Or = ...
+
+class CompareOperator(Enum):
+    Eq = ...
+    Ne = ...
+    Ge = ...
+    Gt = ...
+    Le = ...
+    Lt = ...
+    Contains = ...
+    In = ...
+
+class BooleanExpression:
+    class Primitive:
+        __match_args__ = ("expr",)
+        @property
+        def expr(self) -> Primitive: ...
+
+    class LogicalNot:
+        __match_args__ = ("expr", "span")
+        @property
+        def expr(self) -> BooleanExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Logical:
+        __match_args__ = ("left", "operator", "right", "span")
+        @property
+        def left(self) -> BooleanExpression: ...
+        @property
+        def operator(self) -> BooleanOperator: ...
+        @property
+        def right(self) -> BooleanExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+    class Comparison:
+        __match_args__ = ("left", "operator", "right", "span")
+        @property
+        def left(self) -> BooleanExpression: ...
+        @property
+        def operator(self) -> CompareOperator: ...
+        @property
+        def right(self) -> BooleanExpression: ...
+        @property
+        def span(self) -> tuple[int, int]: ...
+
+class TagInfo:
+    @property
+    def name(self) -> str: ...
+    @property
+    def end(self) -> str | None: ...
+    @property
+    def intermediates(self) -> list[str]: ...
+    @property
+    def block(self) -> bool: ...
+
+class Grammar:
+    @property
+    def tags(self) -> list[TagInfo]: ...
+    @property
+    def tag_aliases(self) -> dict[str, str]: ...
+    @property
+    def reserved_words(self) -> list[str]: ...
+    @property
+    def operators(self) -> list[str]: ...
+    @property
+    def functions(self) -> list[str]: ...
+
+class CompletionKind(Enum):
+    Content = ...
+    Comment = ...
+    Raw = ...
+    TagName = ...
+    Output = ...
+    Tag = ...
+    Filter = ...
+    String = ...
+    Query = ...
+
+class CompletionContext:
+    @property
+    def kind(self) -> CompletionKind: ...
+    @property
+    def tag(self) -> str | None: ...
+    @property
+    def word(self) -> str: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+    @property
+    def path(self) -> str | None: ...
+
+class OutlineItem:
+    @property
+    def name(self) -> str: ...
+    @property
+    def expression(self) -> list[Token]: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+    @property
+    def block(self) -> bool: ...
+    @property
+    def branches(self) -> list[tuple[int, int]]: ...
+    @property
+    def end(self) -> tuple[int, int] | None: ...
+    @property
+    def children(self) -> list[OutlineItem]: ...
+    @property
+    def range(self) -> tuple[int, int]: ...
+    def defines(self) -> tuple[str, tuple[int, int]] | None: ...
+
+class MarkupIterator:
+    def __iter__(self) -> MarkupIterator: ...
+    def __next__(self) -> Markup: ...
+
+class TokenStream:
+    def __init__(
+        self, tokens: list[Token], span: tuple[int, int] | None = None
+    ) -> None: ...
+    @property
+    def position(self) -> int: ...
+    @property
+    def span(self) -> tuple[int, int]: ...
+    def peek(self, offset: int = 0) -> Token | None: ...
+    def next(self) -> Token | None: ...
+    def expect(self, kind: str) -> Token: ...
+    def eat_one_of(self, kinds: list[str]) -> Token | None: ...
+    def expect_eos(self) -> None: ...
+    def __iter__(self) -> TokenStream: ...
+    def __next__(self) -> Token: ...
+    def __len__(self) -> int: ...
+
+class Lexer:
+    def __init__(
+        self,
+        *,
+        extensions: QueryExtensions | None = None,
+        identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+        raw_spans: bool = False,
+        dialect: Dialect | None = None,
+        lax_ranges: bool = False,
+        max_descendant_segments: int | None = None,
+        max_filter_depth: int | None = None,
+        query_cache_size: int = 1024,
+    ) -> None: ...
+    def tokenize(
+        self, source: str, *, source_name: str | None = None
+    ) -> list[Markup]: ...
+    def iter_tokens(self, source: str) -> MarkupIterator: ...
+    def tokenize_lenient(
+        self, source: str, *, source_name: str | None = None
+    ) -> tuple[list[Markup], list[PyLiquidError]]: ...
+    def tokenize_many(self, sources: list[str]) -> list[list[Markup]]: ...
+    def completion_context(self, source: str, offset: int) -> CompletionContext: ...
+    def parse_query(self, path: str, *, source_name: str | None = None) -> Query: ...
+    def parse_jsonpath_query(
+        self, path: str, *, source_name: str | None = None
+    ) -> Query: ...
+
+class Parser:
+    def __init__(
+        self,
+        *,
+        extensions: QueryExtensions | None = None,
+        common_tags: bool = False,
+        strict_filters: bool = True,
+        max_depth: int | None = None,
+        max_nodes: int | None = None,
+        max_bytes: int | None = None,
+        identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+        dialect: Dialect | None = None,
+        lax_ranges: bool = False,
+        tag_aliases: dict[str, str] | None = None,
+    ) -> None: ...
+    def parse(self, source: str, *, source_name: str | None = None) -> Template: ...
+    def parse_many(self, sources: list[str]) -> list[Template]: ...
+    def parse_lossless(
+        self, source: str, *, source_name: str | None = None
+    ) -> ConcreteTemplate: ...
+    def diagnose(self, source: str, *, source_name: str | None = None) -> str: ...
+    def validate(self, source: str, *, source_name: str | None = None) -> str: ...
+    def parse_filtered_expression(
+        self, source: str | list[Token], *, source_name: str | None = None
+    ) -> FilteredExpression: ...
+    def parse_boolean_expression(
+        self, source: str | list[Token], *, source_name: str | None = None
+    ) -> BooleanExpression: ...
+    def parse_keyword_arguments(
+        self, source: str | list[Token], *, source_name: str | None = None
+    ) -> list[KeywordArgument]: ...
+    def grammar(self) -> Grammar: ...
+    def outline(self, source: str) -> list[OutlineItem]: ...
+
+class QueryExtensions:
+    def __init__(
+        self,
+        *,
+        singular_query_selector: bool = True,
+        implicit_root: bool = True,
+        hyphenated_names: bool = True,
+    ) -> None: ...
+    @staticmethod
+    def strict() -> QueryExtensions: ...
+    @property
+    def singular_query_selector(self) -> bool: ...
+    @property
+    def implicit_root(self) -> bool: ...
+    @property
+    def hyphenated_names(self) -> bool: ...
+
+class Dialect:
+    def __init__(
+        self,
+        *,
+        unknown_tags: bool = False,
+        diamond_ne: bool = True,
+        not_contains: bool = True,
+        multiline_strings: bool = True,
+        hash_comments: bool = True,
+        inline_conditions: bool = True,
+        lax_ranges: bool = False,
+        jekyll_include: bool = False,
+        dynamic_render: bool = False,
+        unreachable_branches: bool = False,
+    ) -> None: ...
+    @staticmethod
+    def liquid2() -> Dialect: ...
+    @staticmethod
+    def strict() -> Dialect: ...
+    @staticmethod
+    def lax() -> Dialect: ...
+    @staticmethod
+    def shopify() -> Dialect: ...
+    @staticmethod
+    def jekyll() -> Dialect: ...
+    @property
+    def unknown_tags(self) -> bool: ...
+    @property
+    def diamond_ne(self) -> bool: ...
+    @property
+    def not_contains(self) -> bool: ...
+    @property
+    def multiline_strings(self) -> bool: ...
+    @property
+    def hash_comments(self) -> bool: ...
+    @property
+    def inline_conditions(self) -> bool: ...
+    @property
+    def lax_ranges(self) -> bool: ...
+    @property
+    def jekyll_include(self) -> bool: ...
+    @property
+    def dynamic_render(self) -> bool: ...
+    @property
+    def unreachable_branches(self) -> bool: ...
+
+class LineIndex:
+    def __init__(self, source: str) -> None: ...
+    def line_col(self, offset: int) -> tuple[int, int]: ...
+    def span_to_line_col(
+        self, span: tuple[int, int]
+    ) -> tuple[tuple[int, int], tuple[int, int]]: ...
+    def __len__(self) -> int: ...
+
+class SourceMapping:
+    @property
+    def output(self) -> tuple[int, int]: ...
+    @property
+    def source(self) -> tuple[int, int]: ...
+    @property
+    def template(self) -> str | None: ...
+
+class TruncatedLoop:
+    @property
+    def span(self) -> tuple[int, int]: ...
+    @property
+    def template(self) -> str | None: ...
+
+class SourceMap:
+    @property
+    def mappings(self) -> list[SourceMapping]: ...
+    def source_at(self, offset: int) -> SourceMapping | None: ...
+    def outputs(
+        self, span: tuple[int, int], template: str | None = None
+    ) -> list[tuple[int, int]]: ...
+    def __len__(self) -> int: ...
+
+def tokenize(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+    raw_spans: bool = False,
+    dialect: Dialect | None = None,
+    lax_ranges: bool = False,
+) -> list[Markup]: ...
+def tokenize_lenient(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    raw_spans: bool = False,
+) -> tuple[list[Markup], list[PyLiquidError]]: ...
+def completion_context(
+    source: str, offset: int, *, extensions: QueryExtensions | None = None
+) -> CompletionContext: ...
+def iter_tokens(
+    source: str, *, extensions: QueryExtensions | None = None
+) -> MarkupIterator: ...
+def tokenize_many(
+    sources: list[str], *, extensions: QueryExtensions | None = None
+) -> list[list[Markup]]: ...
+def dump(source: str) -> None: ...
+def dump_query(path: str) -> None: ...
+def parse(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    common_tags: bool = False,
+    strict_filters: bool = True,
+    max_depth: int | None = None,
+    max_nodes: int | None = None,
+    max_bytes: int | None = None,
+    identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+    dialect: Dialect | None = None,
+    lax_ranges: bool = False,
+) -> Template: ...
+def parse_many(
+    sources: list[str],
+    *,
+    extensions: QueryExtensions | None = None,
+    common_tags: bool = False,
+    strict_filters: bool = True,
+    max_depth: int | None = None,
+    max_nodes: int | None = None,
+    max_bytes: int | None = None,
+    identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+    dialect: Dialect | None = None,
+    lax_ranges: bool = False,
+) -> list[Template]: ...
+def parse_lossless(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+) -> ConcreteTemplate: ...
+def render(
+    source: str,
+    data: Mapping[str, Any] | None = None,
+    *,
+    templates: Mapping[str, str] | None = None,
+    source_name: str | None = None,
+    trim: Whitespace = Whitespace.Plus,
+    smart: SmartTrim = SmartTrim.Newlines,
+    default_loop_limit: int | None = None,
+) -> str: ...
+def render_with_source_map(
+    source: str,
+    data: Mapping[str, Any] | None = None,
+    *,
+    templates: Mapping[str, str] | None = None,
+    source_name: str | None = None,
+    trim: Whitespace = Whitespace.Plus,
+    smart: SmartTrim = SmartTrim.Newlines,
+    default_loop_limit: int | None = None,
+) -> tuple[str, SourceMap]: ...
+def render_with_truncated_loops(
+    source: str,
+    data: Mapping[str, Any] | None = None,
+    *,
+    templates: Mapping[str, str] | None = None,
+    source_name: str | None = None,
+    trim: Whitespace = Whitespace.Plus,
+    smart: SmartTrim = SmartTrim.Newlines,
+    default_loop_limit: int | None = None,
+) -> tuple[str, list[TruncatedLoop]]: ...
+def resolve_whitespace(
+    source: str,
+    *,
+    trim: Whitespace = Whitespace.Plus,
+    smart: SmartTrim = SmartTrim.Newlines,
+) -> list[Markup]: ...
+def diagnose(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+) -> str: ...
+def validate(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    common_tags: bool = False,
+    strict_filters: bool = True,
+    dialect: Dialect | None = None,
+) -> str: ...
+def lint(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    disable: list[str] | None = None,
+    filters: list[str] | None = None,
+    dialect: Dialect | None = None,
+) -> str: ...
+def audit(
+    source: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    denied_tags: list[str] | None = None,
+    denied_filters: list[str] | None = None,
+    max_iterations: int | None = None,
+    max_depth: int | None = None,
+    dynamic_partials: bool = False,
+) -> str: ...
+def parse_query(
+    path: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    identifiers: IdentifierPolicy = IdentifierPolicy.Unicode,
+) -> Query: ...
+def parse_jsonpath_query(
+    path: str,
+    *,
+    extensions: QueryExtensions | None = None,
+    source_name: str | None = None,
+    strict: bool = False,
+    max_descendant_segments: int | None = None,
+    max_filter_depth: int | None = None,
+) -> Query: ...
+def parse_filtered_expression(
+    source: str | list[Token], *, source_name: str | None = None
+) -> FilteredExpression: ...
+def parse_boolean_expression(
+    source: str | list[Token], *, source_name: str | None = None
+) -> BooleanExpression: ...
+def parse_keyword_arguments(
+    source: str | list[Token], *, source_name: str | None = None
+) -> list[KeywordArgument]: ...
+def unescape_string(s: str, quote: str = '"') -> str: ...
+def span_to_line_col(
+    source: str, span: tuple[int, int]
+) -> tuple[tuple[int, int], tuple[int, int]]: ...
+def grammar() -> Grammar: ...
+def outline(
+    source: str, *, extensions: QueryExtensions | None = None
+) -> list[OutlineItem]: ...
+def register_function(
+    name: str,
+    param_types: list[str],
+    return_type: str,
+    func: Callable[..., Any] | None = None,
+) -> None: ...
+
+class PyLiquidError(Exception):
+    span: tuple[int, int] | None
 class LiquidTypeError(PyLiquidError): ...
 class LiquidSyntaxError(PyLiquidError): ...
 class LiquidNameError(PyLiquidError): ...
@@ -494,6 +1806,8 @@ TokenT: TypeAlias = (
     | Token.Colon
     | Token.Pipe
     | Token.DoublePipe
+    | Token.Elvis
+    | Token.Arrow
     | Token.Comma
     | Token.LeftParen
     | Token.RightParen
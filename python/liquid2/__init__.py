@@ -2,6 +2,7 @@
 from typing import TYPE_CHECKING
 
 from _liquid2 import Markup
+from _liquid2 import QueryExtensions
 from _liquid2 import Token
 from _liquid2 import Whitespace
 from .ast import Node
@@ -18,6 +19,7 @@ __all__ = [
     "Environment",
     "Markup",
     "Node",
+    "QueryExtensions",
     "RenderContext",
     "StrictDefaultUndefined",
     "StrictUndefined",
@@ -221,32 +221,45 @@ class FloatLiteral(Literal[float]):
 
 
 class RangeLiteral(Expression):
-    __slots__ = ("start", "stop")
+    __slots__ = ("start", "stop", "step")
 
-    def __init__(self, token: TokenT, start: Expression, stop: Expression):
+    def __init__(
+        self,
+        token: TokenT,
+        start: Expression,
+        stop: Expression,
+        step: Expression | None = None,
+    ):
         super().__init__(token=token)
         self.start = start
         self.stop = stop
+        self.step = step
 
     def __eq__(self, other: object) -> bool:
         return (
             isinstance(other, RangeLiteral)
             and self.start == other.start
             and self.stop == other.stop
+            and self.step == other.step
         )
 
     def __str__(self) -> str:
+        if self.step:
+            return f"({self.start}..{self.stop}..{self.step})"
         return f"({self.start}..{self.stop})"
 
     def __hash__(self) -> int:
-        return hash((self.start, self.stop))
+        return hash((self.start, self.stop, self.step))
 
     def __sizeof__(self) -> int:
         return (
-            super().__sizeof__() + sys.getsizeof(self.start) + sys.getsizeof(self.stop)
+            super().__sizeof__()
+            + sys.getsizeof(self.start)
+            + sys.getsizeof(self.stop)
+            + sys.getsizeof(self.step)
         )
 
-    def _make_range(self, start: Any, stop: Any) -> range:
+    def _make_range(self, start: Any, stop: Any, step: Any = 1) -> range:
         try:
             start = to_int(start)
         except ValueError:
@@ -257,24 +270,34 @@ class RangeLiteral(Expression):
         except ValueError:
             stop = 0
 
-        # Descending ranges don't work
-        if start > stop:
+        try:
+            step = to_int(step)
+        except ValueError:
+            step = 1
+
+        # Descending ranges don't work, and neither do negative steps
+        if start > stop or step < 1:
             return range(0)
 
-        return range(start, stop + 1)
+        return range(start, stop + 1, step)
 
     def evaluate(self, context: RenderContext) -> range:
         return self._make_range(
-            self.start.evaluate(context), self.stop.evaluate(context)
+            self.start.evaluate(context),
+            self.stop.evaluate(context),
+            self.step.evaluate(context) if self.step else 1,
         )
 
     async def evaluate_async(self, context: RenderContext) -> range:
         return self._make_range(
             await self.start.evaluate_async(context),
             await self.stop.evaluate_async(context),
+            await self.step.evaluate_async(context) if self.step else 1,
         )
 
     def children(self) -> list[Expression]:
+        if self.step:
+            return [self.start, self.stop, self.step]
         return [self.start, self.stop]
 
 
@@ -369,8 +392,13 @@ def parse_primitive(token: TokenT | None) -> Expression:  # noqa: PLR0911
             if value == "blank":
                 return Blank(token=token)
             return Query(token, compile(parse_query(value)))
-        case Token.RangeLiteral(start, stop):
-            return RangeLiteral(token, parse_primitive(start), parse_primitive(stop))
+        case Token.RangeLiteral(start, stop, step):
+            return RangeLiteral(
+                token,
+                parse_primitive(start),
+                parse_primitive(stop),
+                parse_primitive(step) if step else None,
+            )
         case Token.StringLiteral(value) | RangeArgument.StringLiteral(value):
             return StringLiteral(token, value)
         case Token.IntegerLiteral(value) | RangeArgument.IntegerLiteral(value):
@@ -738,8 +766,13 @@ def parse_boolean_primitive(  # noqa: PLR0912
                 left = Blank(token=token)
             else:
                 left = Query(token, compile(parse_query(value)))
-        case Token.RangeLiteral(start, stop):
-            left = RangeLiteral(token, parse_primitive(start), parse_primitive(stop))
+        case Token.RangeLiteral(start, stop, step):
+            left = RangeLiteral(
+                token,
+                parse_primitive(start),
+                parse_primitive(stop),
+                parse_primitive(step) if step else None,
+            )
         case Token.StringLiteral(value):
             left = StringLiteral(token, value)
         case Token.IntegerLiteral(value):
@@ -1203,6 +1236,13 @@ class LoopExpression(Expression):
     ) -> tuple[Iterator[object], int]:
         offset_key = f"{self.identifier}-{self.iterable}"
 
+        default_limit = context.env.default_loop_limit
+        limited_by_default = default_limit is not None and (
+            limit is None or limit > default_limit
+        )
+        if limited_by_default:
+            limit = default_limit
+
         if limit is None and offset is None:
             context.stopindex(key=offset_key, index=length)
             if self.reversed:
@@ -1217,6 +1257,8 @@ class LoopExpression(Expression):
             length = max(length - offset, 0)
 
         if limit is not None:
+            if limited_by_default and length > limit:
+                context.truncated_loops.append(self.token)
             length = min(length, limit)
 
         stop = offset + length if offset else length
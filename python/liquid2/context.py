@@ -52,6 +52,7 @@ class RenderContext:
         "env",
         "tag_namespace",
         "loops",
+        "truncated_loops",
     )
 
     def __init__(
@@ -96,6 +97,12 @@ class RenderContext:
         # As stack of forloop objects. Used for populating forloop.parentloop.
         self.loops: list[ForLoop] = []
 
+        # Loops cut short by the environment's `default_loop_limit`. Shared with
+        # copies of this context, so callers can inspect it after rendering.
+        self.truncated_loops: list[TokenT] = (
+            parent.truncated_loops if parent else []
+        )
+
     def assign(self, key: str, val: object) -> None:
         """Add _key_ to the local namespace with value _val_."""
         self.locals[key] = val
@@ -12,6 +12,7 @@ from _liquid2 import LiquidExtensionError as _LiquidExtensionError
 from _liquid2 import LiquidNameError as _LiquidNameError
 from _liquid2 import LiquidSyntaxError as _LiquidSyntaxError
 from _liquid2 import LiquidTypeError as _LiquidTypeError
+from _liquid2 import QueryExtensions
 from _liquid2 import Whitespace
 from _liquid2 import tokenize
 
@@ -47,6 +48,12 @@ class Environment:
     # raised.
     loop_iteration_limit: ClassVar[int | None] = None
 
+    # Maximum number of items a single loop will iterate over, applied when a
+    # template does not give a smaller `limit` argument. Loops truncated by this
+    # limit are recorded in `RenderContext.truncated_loops` and returned by
+    # `Template.render_with_truncated_loops()`.
+    default_loop_limit: ClassVar[int | None] = None
+
     # Maximum number of bytes (according to sys.getsizeof) allowed in a template's
     # local namespace before a LocalNamespaceLimitError is raised. We only count the
     # size of the namespaces values, not the size of keys/names.
@@ -56,6 +63,11 @@ class Environment:
     # raising an OutputStreamLimitError.
     output_stream_limit: ClassVar[int | None] = None
 
+    # Non-standard JSONPath syntax allowed in template queries. Use
+    # `QueryExtensions.strict()` to accept RFC 9535 queries only. Single names,
+    # like `foo`, are always valid variables.
+    query_extensions: ClassVar[QueryExtensions] = QueryExtensions()
+
     template_class = Template
 
     def __init__(
@@ -79,12 +91,20 @@ class Environment:
         # TODO: limits
         # TODO: template_class
 
-    def parse(self, source: str) -> list[Node]:
-        """Compile template source text and return an abstract syntax tree."""
+    def parse(self, source: str, *, name: str | None = None) -> list[Node]:
+        """Compile template source text and return an abstract syntax tree.
+
+        If _name_ is given, syntax errors are attributed to the template with
+        that name.
+        """
         # TODO: pass tokens to exceptions
         # XXX:
         try:
-            return self.parser.parse(tokenize(source))
+            return self.parser.parse(
+                tokenize(
+                    source, extensions=self.query_extensions, source_name=name
+                )
+            )
         except _LiquidSyntaxError as err:
             raise LiquidSyntaxError(err, token=None) from err
         except _LiquidTypeError as err:
@@ -104,7 +124,7 @@ class Environment:
         """Create a template from a string."""
         return self.template_class(
             self,
-            self.parse(source),
+            self.parse(source, name=name),
             name=name,
             path=path,
             global_data=global_context_data,
@@ -2,6 +2,10 @@ from _liquid2 import parse_query
 
 from .environment import JSONValue
 from .environment import _JSONPathEnvironment
+from .explain import BranchTrace
+from .explain import QueryTrace
+from .explain import SegmentTrace
+from .explain import SelectorTrace
 from .node import JSONPathNode
 from .node import JSONPathNodeList
 from .query import JSONPathQuery as Query
@@ -13,6 +17,10 @@ __all__ = [
     "JSONPathNode",
     "JSONPathNodeList",
     "JSONValue",
+    "QueryTrace",
+    "SegmentTrace",
+    "SelectorTrace",
+    "BranchTrace",
 ]
 
 
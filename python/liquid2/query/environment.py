@@ -10,6 +10,7 @@ from typing import Sequence
 from _liquid2 import FilterExpression as _FilterExpression
 from _liquid2 import Segment as _Segment
 from _liquid2 import Selector as _Selector
+from _liquid2 import register_function
 
 from . import function_extensions
 from .filter_expressions import BooleanLiteral
@@ -105,6 +106,19 @@ class _JSONPathEnvironment:
         self.function_extensions["search"] = function_extensions.Search()
         self.function_extensions["value"] = function_extensions.Value()
 
+    def add_function_extension(self, name: str, function: FilterFunction) -> None:
+        """Make _function_ available to filters as _name_.
+
+        The function's signature is also registered with the query parser, so
+        calls to it are type checked when a query is parsed.
+        """
+        self.function_extensions[name] = function
+        register_function(
+            name,
+            [t.name.lower() for t in function.arg_types],
+            function.return_type.name.lower(),
+        )
+
     def _parse_segment(self, segment: _Segment) -> JSONPathSegment:
         match segment:
             case _Segment.Child(selectors):
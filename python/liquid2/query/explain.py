@@ -0,0 +1,239 @@
+"""Trace JSONPath query evaluation, segment by segment."""
+
+from __future__ import annotations
+
+import random
+from dataclasses import dataclass
+from dataclasses import field
+from time import perf_counter
+from typing import TYPE_CHECKING
+from typing import Iterable
+from typing import Mapping
+from typing import Sequence
+
+from .exceptions import JSONPathTypeError
+from .filter_expressions import ComparisonExpression
+from .filter_expressions import Expression
+from .filter_expressions import FilterContext
+from .filter_expressions import FilterExpression
+from .filter_expressions import LogicalExpression
+from .filter_expressions import PrefixExpression
+from .filter_expressions import _compare
+from .filter_expressions import _is_truthy
+from .node import JSONPathNode
+from .node import JSONPathNodeList
+from .segments import JSONPathRecursiveDescentSegment
+from .selectors import Filter
+
+if TYPE_CHECKING:
+    from .environment import JSONValue
+    from .query import JSONPathQuery
+    from .segments import JSONPathSegment
+    from .selectors import JSONPathSelector
+
+
+@dataclass
+class BranchTrace:
+    """Outcomes of one boolean sub expression of a filter selector.
+
+    Attributes:
+        expression: The sub expression, as a string.
+        true: The number of times the expression evaluated to `true`.
+        false: The number of times the expression evaluated to `false`.
+    """
+
+    expression: str
+    true: int = 0
+    false: int = 0
+
+
+@dataclass
+class SelectorTrace:
+    """Counts for a single selector.
+
+    Attributes:
+        selector: The selector, as a string.
+        visited: The number of nodes this selector was applied to. For filter
+            selectors, this is the number of array elements or object values the
+            filter expression was evaluated against.
+        matched: The number of nodes this selector produced.
+        branches: For filter selectors, the outcome of each boolean sub expression,
+            in the order they appear in the filter.
+    """
+
+    selector: str
+    visited: int = 0
+    matched: int = 0
+    branches: list[BranchTrace] = field(default_factory=list)
+
+
+@dataclass
+class SegmentTrace:
+    """Counts for a single segment.
+
+    Attributes:
+        segment: The segment, as a string.
+        visited: The number of nodes this segment's selectors were applied to. For
+            descendant segments, this includes every descendant visited.
+        matched: The number of nodes this segment produced.
+        elapsed: Time spent resolving this segment, in seconds.
+        selectors: A trace for each of this segment's selectors.
+    """
+
+    segment: str
+    visited: int = 0
+    matched: int = 0
+    elapsed: float = 0.0
+    selectors: list[SelectorTrace] = field(default_factory=list)
+
+
+@dataclass
+class QueryTrace:
+    """The result of `JSONPathQuery.explain()`.
+
+    Attributes:
+        query: The query, as a string.
+        segments: A trace for each of the query's segments.
+        nodes: The nodes matched by the query, as would be returned by `find()`.
+    """
+
+    query: str
+    segments: list[SegmentTrace]
+    nodes: JSONPathNodeList
+
+    def __str__(self) -> str:
+        lines = [f"{self.query} ({len(self.nodes)} nodes)"]
+        for segment in self.segments:
+            lines.append(
+                f"  {segment.segment}: visited {segment.visited}, "
+                f"matched {segment.matched}, {segment.elapsed * 1000:.3f}ms"
+            )
+            for selector in segment.selectors:
+                lines.append(
+                    f"    {selector.selector}: visited {selector.visited}, "
+                    f"matched {selector.matched}"
+                )
+                lines.extend(
+                    f"      {branch.expression}: "
+                    f"true {branch.true}, false {branch.false}"
+                    for branch in selector.branches
+                )
+        return "\n".join(lines)
+
+
+def explain(query: JSONPathQuery, value: JSONValue) -> QueryTrace:
+    """Apply _query_ to _value_, counting nodes visited and matched as we go."""
+    nodes = JSONPathNodeList([JSONPathNode(value=value, location=(), root=value)])
+    segments: list[SegmentTrace] = []
+
+    for segment in query.segments:
+        trace = _SegmentTracer(segment)
+        start = perf_counter()
+        nodes = JSONPathNodeList(trace.resolve(nodes))
+        trace.trace.elapsed = perf_counter() - start
+        trace.trace.matched = len(nodes)
+        segments.append(trace.trace)
+
+    return QueryTrace(query=str(query), segments=segments, nodes=nodes)
+
+
+class _SegmentTracer:
+    def __init__(self, segment: JSONPathSegment) -> None:
+        self.segment = segment
+        self.selectors = [_SelectorTracer(selector) for selector in segment.selectors]
+        self.trace = SegmentTrace(
+            segment=str(segment),
+            selectors=[selector.trace for selector in self.selectors],
+        )
+
+    def resolve(self, nodes: Iterable[JSONPathNode]) -> Iterable[JSONPathNode]:
+        for node in nodes:
+            descendants: Iterable[JSONPathNode] = (
+                self.segment._visit(node)  # noqa: SLF001
+                if isinstance(self.segment, JSONPathRecursiveDescentSegment)
+                else (node,)
+            )
+
+            for _node in descendants:
+                self.trace.visited += 1
+                for selector in self.selectors:
+                    yield from selector.resolve(_node)
+
+
+class _SelectorTracer:
+    def __init__(self, selector: JSONPathSelector) -> None:
+        self.selector = selector
+        self.trace = SelectorTrace(selector=str(selector))
+
+        # Branch counters keyed by expression identity, in pre-order.
+        self.branches: dict[int, BranchTrace] = {}
+        if isinstance(selector, Filter):
+            self._collect_branches(selector.expression)
+            self.trace.branches = list(self.branches.values())
+
+    def _collect_branches(self, expression: Expression) -> None:
+        # The filter's own outcome is already counted by `matched`.
+        if isinstance(
+            expression, (LogicalExpression, ComparisonExpression, PrefixExpression)
+        ):
+            self.branches[id(expression)] = BranchTrace(expression=str(expression))
+        for child in expression.children():
+            self._collect_branches(child)
+
+    def resolve(self, node: JSONPathNode) -> Iterable[JSONPathNode]:
+        if isinstance(self.selector, Filter):
+            yield from self._filter(self.selector, node)
+            return
+
+        self.trace.visited += 1
+        for _node in self.selector.resolve(node):
+            self.trace.matched += 1
+            yield _node
+
+    def _filter(self, selector: Filter, node: JSONPathNode) -> Iterable[JSONPathNode]:
+        if isinstance(node.value, Mapping):
+            members: Iterable[tuple[int | str, object]] = node.value.items()
+            if selector.env.nondeterministic:
+                _members = list(members)
+                random.shuffle(_members)
+                members = _members
+        elif isinstance(node.value, Sequence):
+            members = enumerate(node.value)
+        else:
+            return
+
+        for key, val in members:
+            self.trace.visited += 1
+            context = FilterContext(env=selector.env, current=val, root=node.root)
+            try:
+                if self._evaluate(selector.expression, context):
+                    self.trace.matched += 1
+                    yield node.new_child(val, key)
+            except JSONPathTypeError as err:
+                if not err.token:
+                    err.token = selector.token
+                raise
+
+    def _evaluate(self, expression: Expression, context: FilterContext) -> object:
+        """Evaluate _expression_ like `Expression.evaluate`, recording outcomes."""
+        if isinstance(expression, FilterExpression):
+            rv: object = _is_truthy(self._evaluate(expression.expression, context))
+        elif isinstance(expression, LogicalExpression):
+            rv = _compare(
+                self._evaluate(expression.left, context),
+                expression.operator,
+                self._evaluate(expression.right, context),
+            )
+        elif isinstance(expression, PrefixExpression) and expression.operator == "!":
+            rv = not _is_truthy(self._evaluate(expression.right, context))
+        else:
+            rv = expression.evaluate(context)
+
+        branch = self.branches.get(id(expression))
+        if branch:
+            if _is_truthy(rv):
+                branch.true += 1
+            else:
+                branch.false += 1
+
+        return rv
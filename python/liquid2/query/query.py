@@ -8,6 +8,7 @@ from typing import Iterator
 from typing import TypeAlias
 from typing import Union
 
+from .explain import explain
 from .filter_expressions import Expression
 from .filter_expressions import FilterQuery
 from .node import JSONPathNode
@@ -23,6 +24,7 @@ if TYPE_CHECKING:
 
     from .environment import JSONValue
     from .environment import _JSONPathEnvironment
+    from .explain import QueryTrace
     from .segments import JSONPathSegment
 
 
@@ -129,6 +131,21 @@ class JSONPathQuery:
 
     apply = find
 
+    def explain(self, value: JSONValue) -> QueryTrace:
+        """Apply this query to _value_ and return a trace of its evaluation.
+
+        The trace records how many nodes each segment and selector visited and
+        matched, and how often each branch of a filter expression was true or
+        false. Use it to find the parts of a query that do the most work.
+
+        Arguments:
+            value: JSON-like data to query, as you'd get from `json.load`.
+
+        Returns:
+            A `QueryTrace`, including the same nodes `find()` would return.
+        """
+        return explain(self, value)
+
     def find_one(self, value: JSONValue) -> JSONPathNode | None:
         """Return the first node from applying this query to _value_.
 
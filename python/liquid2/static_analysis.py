@@ -17,9 +17,13 @@ from .builtin import Identifier
 from .builtin import Query as QueryExpression
 from .builtin import StringLiteral
 from .builtin import TernaryFilteredExpression
+from .builtin.comments import CommentNode
+from .builtin.content import ContentNode
 from .builtin.tags.case_tag import MultiExpressionBlockNode
 from .builtin.tags.extends_tag import BlockNode as InheritanceBlockNode
+from .builtin.tags.extends_tag import ExtendsNode
 from .builtin.tags.extends_tag import _BlockStackItem
+from .builtin.tags.extends_tag import find_inheritance_nodes
 from .builtin.tags.extends_tag import stack_blocks
 from .context import RenderContext
 from .exceptions import StopRender
@@ -99,6 +103,59 @@ class TemplateAnalysis:
     tags: dict[str, list[Span]]
 
 
+@dataclass(frozen=True, kw_only=True)
+class InheritanceDiagnostic:
+    """A problem found while resolving a template inheritance chain.
+
+    Args:
+        kind: One of `"missing_required"`, `"orphaned_block"` or `"unreachable"`.
+        message: A human readable description of the problem.
+        span: The location of the offending tag or block.
+    """
+
+    kind: Literal["missing_required", "orphaned_block", "unreachable"]
+    message: str
+    span: Span
+
+    def __str__(self) -> str:
+        return f"{self.message} ({self.span})"
+
+
+@dataclass(frozen=True, kw_only=True)
+class InheritanceAnalysis:
+    """The result of resolving `extends` and `block` tags statically, using
+    `Template.analyze_inheritance()`.
+
+    Args:
+        chain: Names of templates in the inheritance chain, starting with the
+            analyzed template and ending with the base template.
+        blocks: The location of every definition of each block, ordered from the
+            analyzed template to the base template.
+        resolved_blocks: For each block that is rendered, the location of the
+            definition that will be rendered in its place. Together with the base
+            template, this describes the effective merged template.
+        overrides: A mapping of block definitions to the ancestor definition each
+            one overrides.
+        orphaned_blocks: Block definitions that are never rendered, because no
+            block with the same name is output by the effective merged template.
+        missing_required: Required blocks that are rendered without being
+            overridden, and the location of the block that needed overriding.
+        unreachable: Locations of content that can never be rendered. That is
+            markup following an `extends` tag outside of any block, and overridden
+            block definitions that are not reached with `block.super`.
+        diagnostics: Problems found while resolving the inheritance chain.
+    """
+
+    chain: list[str]
+    blocks: dict[str, list[Span]]
+    resolved_blocks: dict[str, Span]
+    overrides: dict[Span, Span]
+    orphaned_blocks: dict[str, list[Span]]
+    missing_required: dict[str, Span]
+    unreachable: list[Span]
+    diagnostics: list[InheritanceDiagnostic]
+
+
 class _TemplateCounter:
     """Count references to variable names in a Liquid template.
 
@@ -752,7 +809,7 @@ class _InheritanceChainCounter(_TemplateCounter):
         if not self.parent_block_stack_item:
             return
 
-        if self._contains_super(expression):
+        if _contains_super(expression):
             template = self._make_template(self.parent_block_stack_item)
             scope: dict[str, object] = {ident: None for ident in self.template_locals}
             refs = _InheritanceChainCounter(
@@ -774,7 +831,7 @@ class _InheritanceChainCounter(_TemplateCounter):
         if not self.parent_block_stack_item:
             return
 
-        if self._contains_super(expression):
+        if _contains_super(expression):
             template = self._make_template(self.parent_block_stack_item)
             scope: dict[str, object] = {ident: None for ident in self.template_locals}
             refs = await _InheritanceChainCounter(
@@ -788,25 +845,6 @@ class _InheritanceChainCounter(_TemplateCounter):
 
             self._update_reference_counters(refs)
 
-    def _contains_super(self, expression: Expression) -> bool:
-        if (
-            isinstance(expression, QueryExpression)
-            and expression.path.head() == "block"
-            and expression.path.tail() == "super"
-        ):
-            return True
-
-        # XXX: TODO: TernaryFilteredExpression
-
-        if isinstance(expression, FilteredExpression) and (
-            isinstance(expression.left, QueryExpression)
-            and expression.left.path.head() == "block"
-            and expression.left.path.tail() == "super"
-        ):
-            return True
-
-        return any(self._contains_super(expr) for expr in expression.children())
-
     def _analyze_block(self, block: InheritanceBlockNode) -> None:
         block_stacks: dict[str, list[_BlockStackItem]] = (
             self.stack_context.tag_namespace["extends"]
@@ -857,6 +895,218 @@ class _InheritanceChainCounter(_TemplateCounter):
         )
 
 
+class _InheritanceResolver:
+    """Resolve `extends` and `block` tags in a template inheritance chain without
+    rendering it.
+
+    Args:
+        template: The leaf template of the inheritance chain to resolve.
+    """
+
+    def __init__(self, template: Template) -> None:
+        self.template = template
+
+        # Template names and templates, from leaf to base.
+        self.chain: list[tuple[str, Template]] = []
+
+        # Block definitions by block name, from leaf to base.
+        self.definitions: dict[str, list[tuple[Span, InheritanceBlockNode]]] = (
+            defaultdict(list)
+        )
+
+        # Names of rendered blocks and the definition that will be rendered.
+        self.resolved: dict[str, Span] = {}
+
+        # Block definitions that are rendered, directly or with `block.super`.
+        self.reached: set[Span] = set()
+
+        self.missing_required: dict[str, Span] = {}
+        self.unreachable: list[Span] = []
+        self.diagnostics: list[InheritanceDiagnostic] = []
+
+    def resolve(self) -> InheritanceAnalysis:
+        """Load every template in the inheritance chain and resolve its blocks."""
+        parent_name = self._push(self.template)
+        while parent_name:
+            parent = self.template.env.get_template(parent_name, tag="extends")
+            parent_name = self._push(parent)
+        return self._resolve()
+
+    async def resolve_async(self) -> InheritanceAnalysis:
+        """An async version of `_InheritanceResolver.resolve()`."""
+        parent_name = self._push(self.template)
+        while parent_name:
+            parent = await self.template.env.get_template_async(
+                parent_name, tag="extends"
+            )
+            parent_name = self._push(parent)
+        return self._resolve()
+
+    def _push(self, template: Template) -> str | None:
+        """Add _template_ to the end of the chain and return its parent's name."""
+        template_name = template.name or "<string>"
+        extends, blocks = find_inheritance_nodes(template)
+
+        if len(extends) > 1:
+            raise TemplateInheritanceError(
+                "too many 'extends' tags",
+                token=extends[1].token,
+                filename=template_name,
+            )
+
+        seen_block_names: set[str] = set()
+        for block in blocks:
+            if block.name in seen_block_names:
+                raise TemplateInheritanceError(
+                    f"duplicate block {block.name}",
+                    token=block.token,
+                    filename=template_name,
+                )
+            seen_block_names.add(block.name)
+            self.definitions[block.name].append(
+                (Span.from_token(template_name, block.token), block)
+            )
+
+        self.chain.append((template_name, template))
+
+        if not extends:
+            return None
+
+        self._find_unreachable_content(template_name, template, extends[0])
+
+        parent_name = extends[0].name.value
+        if any(name == parent_name for name, _ in self.chain):
+            raise TemplateInheritanceError(
+                f"circular extends {parent_name!r}",
+                token=extends[0].token,
+                filename=template_name,
+            )
+
+        return parent_name
+
+    def _find_unreachable_content(
+        self, template_name: str, template: Template, extends: ExtendsNode
+    ) -> None:
+        # Rendering stops at the `extends` tag, so anything after it that is not
+        # inside a block will never be output.
+        if extends not in template.nodes:
+            return
+
+        for node in template.nodes[template.nodes.index(extends) + 1 :]:
+            if isinstance(node, (InheritanceBlockNode, CommentNode)) or (
+                isinstance(node, ContentNode) and not node.text.strip()
+            ):
+                continue
+
+            span = Span.from_token(template_name, node.token)
+            self.unreachable.append(span)
+            self.diagnostics.append(
+                InheritanceDiagnostic(
+                    kind="unreachable",
+                    message="unreachable content after 'extends'",
+                    span=span,
+                )
+            )
+
+    def _resolve(self) -> InheritanceAnalysis:
+        _, base = self.chain[-1]
+        for node in base.nodes:
+            self._visit(node)
+
+        overrides: dict[Span, Span] = {}
+        orphaned_blocks: dict[str, list[Span]] = defaultdict(list)
+
+        for name, definitions in self.definitions.items():
+            for (span, _), (parent_span, _) in zip(definitions, definitions[1:]):
+                overrides[span] = parent_span
+
+            for span, _ in definitions:
+                if span in self.reached:
+                    continue
+
+                if name in self.resolved:
+                    self.unreachable.append(span)
+                    self.diagnostics.append(
+                        InheritanceDiagnostic(
+                            kind="unreachable",
+                            message=f"block {name!r} is overridden and never rendered",
+                            span=span,
+                        )
+                    )
+                else:
+                    orphaned_blocks[name].append(span)
+                    self.diagnostics.append(
+                        InheritanceDiagnostic(
+                            kind="orphaned_block",
+                            message=f"block {name!r} is never rendered",
+                            span=span,
+                        )
+                    )
+
+        return InheritanceAnalysis(
+            chain=[name for name, _ in self.chain],
+            blocks={
+                name: [span for span, _ in definitions]
+                for name, definitions in self.definitions.items()
+            },
+            resolved_blocks=self.resolved,
+            overrides=overrides,
+            orphaned_blocks=dict(orphaned_blocks),
+            missing_required=self.missing_required,
+            unreachable=self.unreachable,
+            diagnostics=self.diagnostics,
+        )
+
+    def _visit(self, node: Node) -> None:
+        if isinstance(node, InheritanceBlockNode):
+            self._visit_block(node.name)
+            return
+
+        for child in node.children():
+            if child.node:
+                self._visit(child.node)
+
+    def _visit_block(self, name: str) -> None:
+        if name in self.resolved:
+            return
+
+        definitions = self.definitions[name]
+        span, block = definitions[0]
+        self.resolved[name] = span
+
+        if block.required:
+            self.missing_required[name] = span
+            self.diagnostics.append(
+                InheritanceDiagnostic(
+                    kind="missing_required",
+                    message=f"block {name!r} must be overridden",
+                    span=span,
+                )
+            )
+
+        # Follow `block.super` up the chain for as long as it is used.
+        for span, block in definitions:
+            self.reached.add(span)
+            self._visit(block.block)
+            if not _uses_super(block.block):
+                break
+
+
+def _uses_super(node: Node) -> bool:
+    """Return `True` if _node_ renders its parent block with `block.super`."""
+    for child in node.children():
+        if child.expression and _contains_super(child.expression):
+            return True
+        # `block.super` inside a nested block refers to the nested block's parent.
+        if (
+            child.node
+            and not isinstance(child.node, InheritanceBlockNode)
+            and _uses_super(child.node)
+        ):
+            return True
+    return False
+
+
 class References:
     """Collect references for Template.analyze and friends."""
 
@@ -876,3 +1126,23 @@ class References:
         """Incorporate references from another References."""
         self.queries.extend(refs.queries)
         self.filters.extend(refs.filters)
+
+
+def _contains_super(expression: Expression) -> bool:
+    if (
+        isinstance(expression, QueryExpression)
+        and expression.path.head() == "block"
+        and expression.path.tail() == "super"
+    ):
+        return True
+
+    # XXX: TODO: TernaryFilteredExpression
+
+    if isinstance(expression, FilteredExpression) and (
+        isinstance(expression.left, QueryExpression)
+        and expression.left.path.head() == "block"
+        and expression.left.path.tail() == "super"
+    ):
+        return True
+
+    return any(_contains_super(expr) for expr in expression.children())
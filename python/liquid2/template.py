@@ -12,13 +12,17 @@ from .context import RenderContext
 from .exceptions import LiquidInterrupt
 from .exceptions import LiquidSyntaxError
 from .exceptions import StopRender
+from .static_analysis import InheritanceAnalysis
 from .static_analysis import TemplateAnalysis
+from .static_analysis import _InheritanceResolver
 from .static_analysis import _TemplateCounter
 from .utils import ReadOnlyChainMap
 
 if TYPE_CHECKING:
     from pathlib import Path
 
+    from liquid2 import TokenT
+
     from .ast import Node
     from .environment import Environment
     from .loader import UpToDate
@@ -75,6 +79,35 @@ class Template:
         await self.render_with_context_async(context, buf)
         return buf.getvalue()
 
+    def render_with_truncated_loops(
+        self, *args: Any, **kwargs: Any
+    ) -> tuple[str, list[TokenT]]:
+        """Render this template, also returning loops cut short by the environment.
+
+        Loops that render fewer items than they would have without the
+        environment's `default_loop_limit` are returned in the order they were
+        rendered, including loops in partial templates.
+        """
+        buf = StringIO()
+        context = RenderContext(
+            self,
+            global_data=self.make_globals(dict(*args, **kwargs)),
+        )
+        self.render_with_context(context, buf)
+        return buf.getvalue(), context.truncated_loops
+
+    async def render_with_truncated_loops_async(
+        self, *args: Any, **kwargs: Any
+    ) -> tuple[str, list[TokenT]]:
+        """An async version of `render_with_truncated_loops`."""
+        buf = StringIO()
+        context = RenderContext(
+            self,
+            global_data=self.make_globals(dict(*args, **kwargs)),
+        )
+        await self.render_with_context_async(context, buf)
+        return buf.getvalue(), context.truncated_loops
+
     def render_with_context(
         self,
         context: RenderContext,
@@ -195,3 +228,23 @@ class Template:
             filters=dict(refs.filters),
             tags=dict(refs.tags),
         )
+
+    def analyze_inheritance(self) -> InheritanceAnalysis:
+        """Statically resolve `extends` and `block` tags in this template's
+        inheritance chain.
+
+        Every template in the chain is loaded, but nothing is rendered. Problems
+        like required blocks that are not overridden, blocks that are never output
+        and content that can not be reached are reported in the result's
+        `diagnostics` rather than raised.
+
+        Raises:
+            TemplateInheritanceError: If the chain is circular, or if a template
+                contains more than one `extends` tag or duplicate block names.
+            TemplateNotFound: If a parent template can not be loaded.
+        """
+        return _InheritanceResolver(self).resolve()
+
+    async def analyze_inheritance_async(self) -> InheritanceAnalysis:
+        """An async version of `analyze_inheritance`."""
+        return await _InheritanceResolver(self).resolve_async()
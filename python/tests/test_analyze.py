@@ -0,0 +1,79 @@
+"""Test finding variables read and assigned by a template."""
+
+from typing import Sequence
+
+from _liquid2 import Identifier
+from _liquid2 import TemplateAnalysis
+from _liquid2 import Variable
+from _liquid2 import parse
+
+
+def analyze(source: str) -> TemplateAnalysis:
+    """Parse and analyze _source_."""
+    return parse(source).analyze()
+
+
+def names(variables: Sequence[Variable | Identifier]) -> list[str]:
+    """Return the name of each variable or identifier in _variables_."""
+    return [v.name for v in variables]
+
+
+def test_output_variables() -> None:
+    analysis = analyze("{{ user.name }} {{ 'x' }} {{ a | default: b }}")
+    assert names(analysis.variables) == ["user", "a", "b"]
+    assert names(analysis.globals) == ["user", "a", "b"]
+    assert analysis.variables[0].span == (3, 12)
+    assert len(analysis.variables[0].path.segments) == 2
+
+
+def test_nested_queries() -> None:
+    analysis = analyze("{{ a[b.c] }}{% if x[?@.y == $.z] %}{% endif %}")
+    assert names(analysis.variables) == ["a", "b", "x", "z"]
+
+
+def test_assigned() -> None:
+    source = (
+        "{% assign x = y %}{% capture c %}{{ x }}{% endcapture %}"
+        "{% increment n %}{% decrement m %}{% for i in c %}{% endfor %}"
+    )
+    analysis = analyze(source)
+    assert [(i.name, i.tag) for i in analysis.assigned] == [
+        ("x", "assign"),
+        ("c", "capture"),
+        ("n", "increment"),
+        ("m", "decrement"),
+        ("i", "for"),
+    ]
+    assert names(analysis.globals) == ["y"]
+    assert analysis.used_before_definition == []
+
+
+def test_used_before_definition() -> None:
+    analysis = analyze("{{ x }}{% assign x = 1 %}{{ x }}")
+    assert names(analysis.used_before_definition) == ["x"]
+    assert analysis.globals == []
+
+
+def test_capture_is_defined_after_its_block() -> None:
+    analysis = analyze("{% capture c %}{{ c }}{% endcapture %}")
+    assert names(analysis.used_before_definition) == ["c"]
+
+
+def test_loop_variables_are_local() -> None:
+    analysis = analyze(
+        "{% for item in items %}{{ item.a }}{{ forloop.index }}{% endfor %}{{ item }}"
+    )
+    assert names(analysis.variables) == ["items", "item", "forloop", "item"]
+    assert names(analysis.globals) == ["items"]
+    assert names(analysis.used_before_definition) == ["item"]
+
+
+def test_macro_parameters_are_local() -> None:
+    analysis = analyze("{% macro m a, b: c %}{{ a }}{{ b }}{{ d }}{% endmacro %}")
+    assert names(analysis.globals) == ["c", "d"]
+
+
+def test_liquid_tag() -> None:
+    analysis = analyze("{% liquid\nassign x = y\necho x\n%}")
+    assert names(analysis.globals) == ["y"]
+    assert names(analysis.assigned) == ["x"]
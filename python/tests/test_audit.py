@@ -0,0 +1,93 @@
+"""Test auditing templates against a hosting platform's policy."""
+
+import json
+
+from _liquid2 import audit
+
+
+def findings(source: str, **kwargs: object) -> list[dict[str, object]]:
+    """Return the audit diagnostics for _source_."""
+    return json.loads(audit(source, **kwargs))  # type: ignore
+
+
+def codes(source: str, **kwargs: object) -> list[str]:
+    """Return the code of each audit diagnostic for _source_."""
+    return [d["code"] for d in findings(source, **kwargs)]  # type: ignore
+
+
+def test_clean_template() -> None:
+    source = "{% for x in (1..10) %}{% render 'item', x: x %}{% endfor %}"
+    assert codes(source) == []
+
+
+def test_denied_tags() -> None:
+    source = "{% include 'a' %}{% if x %}{% include 'b' %}{% endif %}"
+    (first, second) = findings(source, denied_tags=["include"])
+    assert first["code"] == "denied-tag"
+    assert first["severity"] == "error"
+    assert first["message"] == "the 'include' tag is not allowed"
+    assert first["start"]["offset"] == 0  # type: ignore
+    assert second["start"]["offset"] == 27  # type: ignore
+
+
+def test_denied_tags_in_liquid_tag() -> None:
+    source = "{% liquid\n  if x\n    cycle 'a', 'b'\n  endif %}"
+    assert codes(source, denied_tags=["cycle", "liquid"]) == [
+        "denied-tag",
+        "denied-tag",
+    ]
+
+
+def test_denied_filters() -> None:
+    source = "{{ a | upcase }}{% assign b = a | split: ',' | join: x | upcase %}"
+    (finding,) = findings(source, denied_filters=["join"])
+    assert finding["code"] == "denied-filter"
+    assert finding["message"] == "the 'join' filter is not allowed"
+    assert codes(source, denied_filters=["upcase"]) == ["denied-filter"] * 2
+
+
+def test_dynamic_partials() -> None:
+    source = "{% include name %}{% include 'ok' %}{% include x.y %}"
+    assert codes(source) == ["dynamic-partial", "dynamic-partial"]
+    assert codes(source, dynamic_partials=True) == []
+
+
+def test_excessive_range() -> None:
+    (finding,) = findings("{{ (1..100000) | join }}")
+    assert finding["code"] == "excessive-iterations"
+    assert finding["message"] == "range could build 100000 items, the limit is 10000"
+    assert codes("{{ (1..100000) | join }}", max_iterations=100000) == []
+    assert codes("{{ (1..n) | join }}") == []
+
+
+def test_excessive_loop() -> None:
+    source = "{% for x in (1..20000) %}{% endfor %}"
+    (finding,) = findings(source)
+    assert finding["code"] == "excessive-iterations"
+    assert finding["start"]["offset"] == 0  # type: ignore
+    assert codes("{% for x in items limit: 20000 %}{% endfor %}") == [
+        "excessive-iterations"
+    ]
+    assert codes("{% for x in items limit: 20 %}{% endfor %}") == []
+
+
+def test_nested_loops_multiply() -> None:
+    source = (
+        "{% for x in (1..200) %}{% tablerow y in (1..100) %}"
+        "{{ (1..5) | join }}{% endtablerow %}{% endfor %}"
+    )
+    diagnostics = findings(source)
+    assert [d["message"] for d in diagnostics] == [
+        "loop could render its block 20000 times, the limit is 10000",
+        "range could build 100000 items, the limit is 10000",
+    ]
+
+
+def test_excessive_nesting() -> None:
+    source = "{% if a %}" * 4 + "{% endif %}" * 4
+    assert codes(source, max_depth=3) == ["excessive-nesting"]
+    assert codes(source, max_depth=4) == []
+
+
+def test_syntax_error() -> None:
+    assert codes("{% for %}") == ["syntax-error"]
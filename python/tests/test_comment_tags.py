@@ -0,0 +1,151 @@
+"""Test parsing comment tags."""
+
+import json
+
+import pytest
+from _liquid2 import CommentKind
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import Node
+from _liquid2 import Whitespace
+from _liquid2 import diagnose
+from _liquid2 import iter_tokens
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+
+def test_block_comment() -> None:
+    source = "a {% comment %} x {% endcomment %} b"
+    markup = tokenize(source)
+    comment = markup[1]
+    assert isinstance(comment, Markup.Comment)
+    assert comment.kind == CommentKind.Block
+    assert comment.text == " x "
+    assert comment.hashes == ""
+    assert comment.span == (2, 34)
+    assert str(comment) == "{% comment %} x {% endcomment %}"
+    assert render(source) == "a  b"
+
+
+def test_block_comment_node() -> None:
+    source = "{% comment %}x{% endcomment %}"
+    template = parse(source)
+    node = template.nodes[0]
+    assert isinstance(node, Node.Comment)
+    assert node.kind == CommentKind.Block
+    assert str(template) == source
+
+
+def test_block_comment_without_spaces() -> None:
+    markup = tokenize("{%comment%}x{%endcomment%}")
+    assert isinstance(markup[0], Markup.Comment)
+    assert markup[0].text == "x"
+
+
+def test_block_comment_contains_markup() -> None:
+    source = "{% comment %}{% if %}{{ .. }}{# {% endcomment %}"
+    assert render(source) == ""
+
+
+def test_nested_block_comments() -> None:
+    source = (
+        "a {% comment %} x {% comment %} y {% endcomment %} z "
+        "{% endcomment %} b"
+    )
+    markup = tokenize(source)
+    assert len(markup) == 4  # noqa: PLR2004
+    assert isinstance(markup[1], Markup.Comment)
+    assert markup[1].text == " x {% comment %} y {% endcomment %} z "
+    assert render(source) == "a  b"
+
+
+def test_block_comment_whitespace_control() -> None:
+    source = "a {%- comment %} x {% endcomment -%} b"
+    markup = tokenize(source)
+    assert isinstance(markup[1], Markup.Comment)
+    assert markup[1].wc == (Whitespace.Minus, Whitespace.Minus)
+    assert render(source) == "ab"
+
+
+def test_inner_whitespace_control_is_ignored() -> None:
+    assert render("a {% comment -%} x {%- endcomment %} b") == "a  b"
+
+
+@pytest.mark.parametrize(
+    "source",
+    [
+        "{% comment %} x",
+        "{% comment %}{% comment %}{% endcomment %}",
+    ],
+)
+def test_unclosed_block_comment(source: str) -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endcomment'"):
+        parse(source)
+
+
+def test_unexpected_endcomment() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unexpected tag 'endcomment'"):
+        parse("{% endcomment %}")
+
+
+def test_hash_comment_kind() -> None:
+    markup = tokenize("{# x #}")
+    assert isinstance(markup[0], Markup.Comment)
+    assert markup[0].kind == CommentKind.Hash
+
+
+def test_inline_comment() -> None:
+    source = "a {%- # x -%} b"
+    markup = tokenize(source)
+    comment = markup[1]
+    assert isinstance(comment, Markup.Comment)
+    assert comment.kind == CommentKind.Inline
+    assert comment.text == " x"
+    assert comment.span == (2, 13)
+    assert str(comment) == "{%- # x -%}"
+    assert str(parse(source)) == source
+    assert render(source) == "ab"
+
+
+def test_inline_comment_without_spaces() -> None:
+    markup = tokenize("{%#x%}")
+    assert isinstance(markup[0], Markup.Comment)
+    assert markup[0].text == "x"
+
+
+def test_multiline_inline_comment() -> None:
+    source = "{% # a\n  # b\n%}"
+    markup = tokenize(source)
+    assert isinstance(markup[0], Markup.Comment)
+    assert markup[0].text == " a\n  # b"
+    assert render(source) == ""
+
+
+def test_multiline_inline_comment_must_comment_every_line() -> None:
+    source = "{% # a\n  b\n%}"
+    with pytest.raises(LiquidSyntaxError, match="must start with a '#'"):
+        tokenize(source)
+
+    (diagnostic,) = json.loads(diagnose(source))
+    start = diagnostic["start"]["offset"]
+    end = diagnostic["end"]["offset"]
+    assert source[start:end] == "b"
+
+
+def test_line_comments_in_liquid_tags() -> None:
+    source = "{% liquid\n# a\n  # b\necho 1\n# c %}"
+    (tag, _) = tokenize(source)
+    assert isinstance(tag, Markup.Lines)
+    comments = [s for s in tag.statements if isinstance(s, Markup.Comment)]
+    assert [c.text for c in comments] == [" a", " b", " c"]
+    assert all(c.kind == CommentKind.Inline for c in comments)
+    assert str(tag) == "{% liquid # a\n# b\necho 1\n# c %}"
+    assert render(source) == "1"
+
+
+def test_iter_comment_tokens() -> None:
+    source = "a {% comment %}x{% endcomment %}{% # y %}b"
+    assert [str(m) for m in iter_tokens(source)] == [
+        str(m) for m in tokenize(source)
+    ]
@@ -0,0 +1,63 @@
+"""Test parsing unknown tags with common argument syntax."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import Whitespace
+from _liquid2 import parse
+
+
+def test_unknown_tags_are_errors_by_default() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unknown tag 'foo'"):
+        parse("{% foo %}")
+
+
+def test_inline_common_tag() -> None:
+    template = parse("{% foo a, 'b', c: 1 %}", common_tags=True)
+    node = template.nodes[0]
+    assert isinstance(node, Node.TagExtension)
+    assert node.name == "foo"
+    assert [str(arg) for arg in node.args] == ["a", "'b'", "c:1"]
+    assert node.block == []
+    assert node.end is None
+
+
+def test_block_common_tag() -> None:
+    source = "{% foo %}a{% foo %}b{% endfoo %}{% endfoo %}"
+    template = parse(source, common_tags=True)
+    assert len(template.nodes) == 1
+    node = template.nodes[0]
+    assert isinstance(node, Node.TagExtension)
+    assert node.end is not None
+    assert node.end.name == "endfoo"
+    assert isinstance(node.block[1], Node.TagExtension)
+    assert str(template) == source
+
+
+def test_common_tag_in_liquid_tag() -> None:
+    template = parse("{% liquid\nfoo x\necho x\nendfoo %}", common_tags=True)
+    node = template.nodes[0]
+    assert isinstance(node, Node.LiquidTag)
+    assert isinstance(node.block[0], Node.TagExtension)
+    assert isinstance(node.block[0].block[0], Node.EchoTag)
+
+
+def test_common_tag_arguments_must_be_common_arguments() -> None:
+    with pytest.raises(LiquidSyntaxError, match="found 'in'"):
+        parse("{% foo x in y %}", common_tags=True)
+
+
+def test_stray_end_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unexpected tag 'endfoo'"):
+        parse("{% endfoo %}", common_tags=True)
+
+
+def test_common_tag_line_whitespace_control() -> None:
+    source = "{%- liquid\n  foo x\n  echo x\n  endfoo\n-%}"
+    template = parse(source, common_tags=True)
+    node = template.nodes[0].block[0]
+    assert isinstance(node, Node.TagExtension)
+    assert node.wc == (Whitespace.Default, Whitespace.Default)
+    assert node.end is not None
+    assert node.end.wc == (Whitespace.Default, Whitespace.Default)
+    assert str(template) == "{%- liquid\n  foo x\n    echo x\n  endfoo\n-%}"
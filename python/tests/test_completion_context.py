@@ -0,0 +1,64 @@
+"""Test describing what could be typed at a cursor position."""
+
+import pytest
+from _liquid2 import CompletionKind
+from _liquid2 import Lexer
+from _liquid2 import completion_context
+
+CASES = [
+    ("Hello, ", CompletionKind.Content, None, ""),
+    ("{{ x }} after", CompletionKind.Content, None, ""),
+    ("{{ ", CompletionKind.Output, None, ""),
+    ("{{ product.ti", CompletionKind.Query, None, "ti"),
+    ("{{- product", CompletionKind.Query, None, "product"),
+    ("{{ x | ", CompletionKind.Filter, None, ""),
+    ("{{ x | up", CompletionKind.Filter, None, "up"),
+    ("{{ 'abc", CompletionKind.String, None, "abc"),
+    ("{% ", CompletionKind.TagName, None, ""),
+    ("{% ass", CompletionKind.TagName, None, "ass"),
+    ("{% if x ", CompletionKind.Tag, "if", ""),
+    ("{% if x and y.z", CompletionKind.Query, "if", "z"),
+    ("{% echo x | d", CompletionKind.Filter, "echo", "d"),
+    ('{% include "foo', CompletionKind.String, "include", "foo"),
+    ("{# a comment", CompletionKind.Comment, None, ""),
+    ("{% # a comment", CompletionKind.Comment, None, ""),
+    ("{% liquid\n  assign x = 1\n  ec", CompletionKind.TagName, None, "ec"),
+    ("{% liquid\n  echo x | up", CompletionKind.Filter, "echo", "up"),
+]
+
+
+@pytest.mark.parametrize(("source", "kind", "tag", "word"), CASES)
+def test_completion_context(
+    source: str, kind: CompletionKind, tag: str | None, word: str
+) -> None:
+    context = completion_context(source, len(source.encode()))
+    assert context.kind == kind
+    assert context.tag == tag
+    assert context.word == word
+
+
+def test_cursor_before_the_end_of_the_source() -> None:
+    source = "{% if a %}{{ b | upcase }}{% endif %}"
+    context = completion_context(source, source.index("case"))
+    assert context.kind == CompletionKind.Filter
+    assert context.word == "up"
+    assert source[slice(*context.span)] == "up"
+
+
+def test_query_path() -> None:
+    source = "{{ product.variants[0].ti"
+    context = completion_context(source, len(source))
+    assert context.path == "product.variants[0].ti"
+    assert context.span == (len(source) - 2, len(source))
+
+
+def test_raw_blocks() -> None:
+    source = "{% raw %}{{ x {% endraw %}"
+    context = completion_context(source, source.index("x"))
+    assert context.kind == CompletionKind.Raw
+
+
+def test_lexer_method() -> None:
+    source = "{{ x | "
+    context = Lexer().completion_context(source, len(source))
+    assert context.kind == CompletionKind.Filter
@@ -0,0 +1,42 @@
+"""Test parsing and rendering cycle tags."""
+
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def test_cycle_without_a_group_name() -> None:
+    (tag,) = parse("{% cycle 'a', 'b' %}").nodes
+    assert isinstance(tag, Node.CycleTag)
+    assert tag.name is None
+    assert str(tag) == "{% cycle 'a', 'b' %}"
+
+
+def test_string_literal_group_name() -> None:
+    (tag,) = parse("{% cycle 'x': 'a', 'b' %}").nodes
+    assert isinstance(tag, Node.CycleTag)
+    assert isinstance(tag.name, Primitive.StringLiteral)
+    assert str(tag) == "{% cycle 'x': 'a', 'b' %}"
+
+
+def test_variable_group_name() -> None:
+    (tag,) = parse("{% cycle group: 'a', 'b' %}").nodes
+    assert isinstance(tag, Node.CycleTag)
+    assert isinstance(tag.name, Primitive.Query)
+    assert str(tag) == "{% cycle group: 'a', 'b' %}"
+
+
+def test_render_variable_group_names() -> None:
+    source = (
+        "{% cycle g1: 'a', 'b', 'c' %}"
+        "{% cycle g2: 'a', 'b', 'c' %}"
+        "{% cycle g1: 'a', 'b', 'c' %}"
+    )
+    assert render(source, {"g1": "x", "g2": "y"}) == "aab"
+    assert render(source, {"g1": "x", "g2": "x"}) == "abc"
+
+
+def test_group_names_are_analyzed() -> None:
+    analysis = parse("{% cycle group.name: a, 'b' %}").analyze()
+    assert [var.name for var in analysis.globals] == ["group", "a"]
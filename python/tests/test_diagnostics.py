@@ -0,0 +1,35 @@
+"""Test JSON diagnostics for templates that fail to parse."""
+
+import json
+
+from _liquid2 import diagnose
+
+
+def test_valid_template() -> None:
+    assert json.loads(diagnose("Hello, {{ you }}!")) == []
+
+
+def test_syntax_error() -> None:
+    diagnostics = json.loads(diagnose("Hello\n{{ you | }}", source_name="a.liquid"))
+    assert len(diagnostics) == 1
+    diagnostic = diagnostics[0]
+    assert diagnostic["code"] == "syntax-error"
+    assert diagnostic["severity"] == "error"
+    assert diagnostic["message"] == "expected a filter name, found end of expression"
+    assert diagnostic["source_name"] == "a.liquid"
+    assert diagnostic["start"] == {"offset": 6, "line": 2, "column": 1}
+    assert diagnostic["end"] == {"offset": 17, "line": 2, "column": 12}
+    assert diagnostic["notes"] == []
+
+
+def test_columns_count_characters() -> None:
+    (diagnostic,) = json.loads(diagnose("日本{% endif %}"))
+    assert diagnostic["start"] == {"offset": 6, "line": 1, "column": 3}
+
+
+def test_mismatched_end_tag_notes_where_the_block_was_opened() -> None:
+    (diagnostic,) = json.loads(diagnose("{% for x in y %}\n{% endif %}"))
+    assert diagnostic["message"] == "expected 'else' or 'endfor', found 'endif'"
+    (note,) = diagnostic["notes"]
+    assert note["message"] == "the block was opened here"
+    assert note["start"] == {"offset": 0, "line": 1, "column": 1}
@@ -0,0 +1,124 @@
+"""Test parsing with strict, lax and Shopify-compatible dialects."""
+
+import pytest
+from _liquid2 import Dialect
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import LiquidTypeError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+
+def test_default_dialect_is_liquid2() -> None:
+    dialect = Dialect()
+    assert dialect.diamond_ne
+    assert dialect.hash_comments
+    assert not dialect.unknown_tags
+    assert not dialect.lax_ranges
+
+
+def test_diamond_ne() -> None:
+    parse("{% if a <> b %}x{% endif %}", dialect=Dialect.shopify())
+    with pytest.raises(LiquidSyntaxError, match="'diamond_ne'"):
+        parse("{% if a <> b %}x{% endif %}", dialect=Dialect.strict())
+
+
+def test_not_contains() -> None:
+    source = "{% if 'abc' not contains 'z' %}yes{% endif %}"
+    assert render(source) == "yes"
+    with pytest.raises(LiquidSyntaxError, match="'not_contains'"):
+        parse(source, dialect=Dialect.shopify())
+
+
+def test_not_contains_binds_tighter_than_and() -> None:
+    source = "{% if 'abc' not contains 'a' and true %}yes{% else %}no{% endif %}"
+    assert render(source) == "no"
+
+
+def test_multiline_strings() -> None:
+    source = "{{ 'a\nb' }}"
+    tokenize(source)
+    with pytest.raises(LiquidSyntaxError, match="'multiline_strings'"):
+        tokenize(source, dialect=Dialect(multiline_strings=False))
+
+
+def test_hash_comments() -> None:
+    source = "{# note #}hello"
+    tokenize(source)
+    with pytest.raises(LiquidSyntaxError, match="'hash_comments'"):
+        tokenize(source, dialect=Dialect.shopify())
+
+
+def test_inline_conditions() -> None:
+    for source in ["{{ a if b else c }}", "{{ a ?: b }}"]:
+        parse(source)
+        with pytest.raises(LiquidSyntaxError, match="'inline_conditions'"):
+            parse(source, dialect=Dialect.shopify())
+
+
+def test_unknown_tags() -> None:
+    source = "{% shout 'hello' %}"
+    with pytest.raises(LiquidSyntaxError, match="unknown tag"):
+        parse(source)
+    (node,) = parse(source, dialect=Dialect.lax()).nodes
+    assert isinstance(node, Node.TagExtension)
+
+
+def test_lax_ranges() -> None:
+    source = "{% for i in (1.5..3) %}{{ i }}{% endfor %}"
+    with pytest.raises(LiquidTypeError):
+        parse(source)
+    parse(source, dialect=Dialect.shopify())
+    parse(source, dialect=Dialect.lax())
+
+
+def test_lax_ranges_argument_overrides_dialect() -> None:
+    parse("{{ (1.5..3) }}", dialect=Dialect.strict(), lax_ranges=True)
+
+
+def test_jekyll_include_with_bare_file_name() -> None:
+    source = '{% include nav/bar.html title="Home" count=page.n %}'
+    (node,) = parse(source, dialect=Dialect.jekyll()).nodes
+    assert isinstance(node, Node.IncludeTag)
+    assert isinstance(node.target, Primitive.StringLiteral)
+    assert node.target.value == "nav/bar.html"
+    assert [arg.name for arg in node.args] == ["title", "count"]
+
+
+def test_jekyll_include_file_name_that_looks_like_a_query() -> None:
+    (node,) = parse("{% include footer.html %}", dialect=Dialect.jekyll()).nodes
+    assert isinstance(node, Node.IncludeTag)
+    assert isinstance(node.target, Primitive.StringLiteral)
+    assert node.target.value == "footer.html"
+
+    (node,) = parse("{% include footer.html %}").nodes
+    assert isinstance(node, Node.IncludeTag)
+    assert isinstance(node.target, Primitive.Query)
+
+
+def test_jekyll_include_is_disabled_by_default() -> None:
+    with pytest.raises(LiquidSyntaxError, match="'jekyll_include'"):
+        parse("{% include nav/bar.html %}")
+
+
+def test_dynamic_render_target() -> None:
+    source = "{% render page.partial, title: 'Home' %}"
+    with pytest.raises(LiquidSyntaxError, match="as a string literal"):
+        parse(source)
+
+    (node,) = parse(source, dialect=Dialect.lax()).nodes
+    assert isinstance(node, Node.RenderTag)
+    assert node.dynamic
+    assert isinstance(node.target, Primitive.Query)
+    assert node.target.path.canonical() == "$['page']['partial']"
+    assert str(node) == "{% render page.partial, title:'Home' %}"
+
+
+def test_static_render_target_is_not_dynamic() -> None:
+    (node,) = parse("{% render 'nav' %}", dialect=Dialect.lax()).nodes
+    assert isinstance(node, Node.RenderTag)
+    assert not node.dynamic
+    assert isinstance(node.target, Primitive.StringLiteral)
+    assert node.target.value == "nav"
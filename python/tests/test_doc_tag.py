@@ -0,0 +1,89 @@
+"""Test parsing the doc tag."""
+
+import pytest
+from _liquid2 import CommentKind
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import Node
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+SOURCE = """\
+{%- doc -%}
+  Renders a product card.
+
+  @param {string} title - The product title.
+  @param {number} [count] - An optional
+    quantity.
+  @example
+  {% render 'card', title: product.title %}
+{%- enddoc -%}
+{{ title }}"""
+
+
+def test_doc_tag() -> None:
+    template = parse(SOURCE)
+    node = template.nodes[0]
+    assert isinstance(node, Node.DocTag)
+    assert node.span == (0, SOURCE.index("\n{{"))
+
+    doc = node.doc
+    assert doc.description == "Renders a product card."
+    assert [p.name for p in doc.params] == ["title", "count"]
+    assert [p.type_ for p in doc.params] == ["string", "number"]
+    assert [p.required for p in doc.params] == [True, False]
+    assert doc.params[1].description == "An optional quantity."
+    assert doc.examples == ["{% render 'card', title: product.title %}"]
+
+
+def test_doc_tag_markup() -> None:
+    markup = tokenize("{% doc %}x{% enddoc %}")
+    assert isinstance(markup[0], Markup.Comment)
+    assert markup[0].kind == CommentKind.Doc
+    assert markup[0].text == "x"
+    assert str(markup[0]) == "{% doc %}x{% enddoc %}"
+
+
+def test_doc_tag_renders_nothing() -> None:
+    assert render(SOURCE, {"title": "Shoe"}) == "Shoe"
+
+
+def test_doc_tag_round_trip() -> None:
+    source = "a {%- doc %}\n  @param x\n{% enddoc -%} b"
+    assert str(parse(source)) == source
+
+
+def test_template_metadata() -> None:
+    metadata = parse("Hello\n" + SOURCE).metadata()
+    assert metadata is not None
+    assert metadata.description == "Renders a product card."
+
+
+def test_doc_tag_takes_precedence_over_header_comment() -> None:
+    template = parse("{# a #}{% doc %}b{% enddoc %}")
+    metadata = template.metadata()
+    assert metadata is not None
+    assert metadata.description == "b"
+
+
+def test_description_tag() -> None:
+    source = "{% doc %}@description A card.\n@param x{% enddoc %}"
+    metadata = parse(source).metadata()
+    assert metadata is not None
+    assert metadata.description == "A card."
+    assert [p.name for p in metadata.params] == ["x"]
+
+
+def test_undocumented_template() -> None:
+    assert parse("{{ x }}").metadata() is None
+
+
+def test_unclosed_doc_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'enddoc'"):
+        parse("{% doc %} x")
+
+
+def test_unexpected_enddoc() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unexpected tag 'enddoc'"):
+        parse("{% enddoc %}")
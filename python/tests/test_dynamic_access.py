@@ -0,0 +1,76 @@
+"""Test bracketed, dynamic variable access like `hash[var]`."""
+
+from __future__ import annotations
+
+from typing import TYPE_CHECKING
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import Segment
+from _liquid2 import Selector
+from _liquid2 import Token
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+if TYPE_CHECKING:
+    from _liquid2 import Query
+
+
+def dynamic_key(query: Query) -> Query:
+    """Return the singular query selecting the last segment of _query_."""
+    segment = query.segments[-1]
+    assert isinstance(segment, Segment.Child)
+    (selector,) = segment.selectors
+    assert isinstance(selector, Selector.SingularQuery)
+    return selector.query
+
+
+def test_lex_dynamic_key() -> None:
+    output = tokenize("{{ products[collection.handle] }}")[0]
+    assert isinstance(output, Markup.Output)
+    (token,) = output.expression
+    assert isinstance(token, Token.Query)
+    assert token.path.canonical() == "$['products'][$['collection']['handle']]"
+    assert dynamic_key(token.path).canonical() == "$['collection']['handle']"
+
+
+def test_parse_dynamic_key() -> None:
+    (node,) = parse("{{ hash[var] }}").nodes
+    assert isinstance(node, Node.Output)
+    left = node.expression.left
+    assert isinstance(left, Primitive.Query)
+    assert len(left.path.segments) == 2  # noqa: PLR2004
+    assert dynamic_key(left.path).canonical() == "$['var']"
+
+
+def test_parse_nested_dynamic_keys() -> None:
+    (node,) = parse("{{ a[b[c]] }}").nodes
+    assert isinstance(node, Node.Output)
+    left = node.expression.left
+    assert isinstance(left, Primitive.Query)
+    inner = dynamic_key(left.path)
+    assert inner.canonical() == "$['b'][$['c']]"
+    assert dynamic_key(inner).canonical() == "$['c']"
+
+
+def test_render_dynamic_key() -> None:
+    data = {"products": {"shoes": "boots"}, "collection": {"handle": "shoes"}}
+    assert render("{{ products[collection.handle] }}", data) == "boots"
+
+
+def test_render_nested_dynamic_keys() -> None:
+    data = {"a": {"y": {"z": 1}}, "b": {"x": "y"}, "c": "x"}
+    assert render("{{ a[b[c]].z }}", data) == "1"
+
+
+def test_render_missing_dynamic_key() -> None:
+    assert render("{{ hash[var] }}", {"hash": {"a": 1}}) == ""
+
+
+def test_unbalanced_dynamic_key() -> None:
+    with pytest.raises(LiquidSyntaxError):
+        parse("{{ a[b[c] }}")
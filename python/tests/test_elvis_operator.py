@@ -0,0 +1,74 @@
+"""Test parsing and rendering the `?:` operator."""
+
+from _liquid2 import BooleanExpression
+from _liquid2 import Markup
+from _liquid2 import Node
+from _liquid2 import Token
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+
+def test_tokenize_elvis_operator() -> None:
+    output = tokenize("{{ a ?: b }}")[0]
+    assert isinstance(output, Markup.Output)
+    assert isinstance(output.expression[1], Token.Elvis)
+
+
+def test_elvis_is_an_inline_condition() -> None:
+    (node,) = parse("{{ a ?: b }}").nodes
+    assert isinstance(node, Node.Output)
+    condition = node.expression.condition
+    assert condition is not None
+    assert isinstance(condition.expr, BooleanExpression.Primitive)
+    assert str(condition.expr) == "a"
+    assert str(condition.alternative) == "b"
+    assert condition.elvis
+    assert condition.alternative_condition is None
+    assert str(node) == "{{ a ?: b }}"
+
+
+def test_render_truthy_left() -> None:
+    assert render("{{ a ?: b }}", {"a": "x", "b": "y"}) == "x"
+
+
+def test_render_falsy_left() -> None:
+    assert render("{{ a ?: b }}", {"a": False, "b": "y"}) == "y"
+
+
+def test_render_undefined_left() -> None:
+    assert render("{{ a ?: 'default' }}", {}) == "default"
+
+
+def test_elvis_with_filters() -> None:
+    source = "{{ a | upcase ?: b | downcase || append: '!' }}"
+    assert render(source, {"a": "x", "b": "Y"}) == "X!"
+    assert render(source, {"b": "Y"}) == "y!"
+
+
+def test_chained_elvis_is_right_associative() -> None:
+    (node,) = parse("{{ a ?: b ?: 'c' }}").nodes
+    assert isinstance(node, Node.Output)
+    condition = node.expression.condition
+    assert condition is not None
+    assert str(condition.expr) == "a"
+    chained = condition.alternative_condition
+    assert chained is not None
+    assert str(chained.expr) == "b"
+    assert str(chained.alternative) == "'c'"
+    assert str(node) == "{{ a ?: b ?: 'c' }}"
+
+
+def test_render_chained_elvis() -> None:
+    source = "{{ a ?: b ?: 'c' }}"
+    assert render(source, {"a": "x", "b": "y"}) == "x"
+    assert render(source, {"b": "y"}) == "y"
+    assert render(source, {}) == "c"
+
+
+def test_chained_elvis_with_filters() -> None:
+    source = "{{ a | upcase ?: b | downcase ?: 'C' | downcase || append: '!' }}"
+    assert str(parse(source)) == source
+    assert render(source, {"a": "x", "b": "Y"}) == "X!"
+    assert render(source, {"b": "Y"}) == "y!"
+    assert render(source, {}) == "c!"
@@ -0,0 +1,100 @@
+"""Test static estimates of output size and loop iterations."""
+
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def test_content_and_literals() -> None:
+    estimate = parse("Hello, {{ 'World' }}{{ 42 }}{{ true }}{{ nil }}!").estimate()
+    assert estimate.output_size == len("Hello, World42true!")
+    assert estimate.iterations == 0
+    assert estimate.loops == []
+
+
+def test_unknown_values() -> None:
+    template = parse("{{ name }}{{ 'a' | upcase }}{{ a if b }}")
+    assert template.estimate().output_size == 300
+    assert template.estimate(value_size=10).output_size == 30
+
+
+def test_literal_range_loop() -> None:
+    source = "{% for x in (1..10) %}ab{% endfor %}"
+    estimate = parse(source).estimate()
+    assert estimate.output_size == 20
+    assert estimate.iterations == 10
+    (loop,) = estimate.loops
+    assert loop.span == (0, len(source))
+    assert loop.iterations == 10
+    assert loop.bounded is True
+
+
+def test_limit_and_offset() -> None:
+    estimate = parse("{% for x in (1..10) limit: 3 %}a{% endfor %}").estimate()
+    assert estimate.iterations == 3
+    estimate = parse("{% for x in (1..10) offset: 8 %}a{% endfor %}").estimate()
+    assert estimate.iterations == 2
+    estimate = parse("{% for x in items limit: 5 %}a{% endfor %}").estimate()
+    assert estimate.iterations == 5
+    assert estimate.loops[0].bounded is True
+
+
+def test_unknown_collections() -> None:
+    template = parse("{% for x in items %}a{% else %}empty{% endfor %}")
+    estimate = template.estimate(collection_size=7)
+    assert estimate.output_size == 7
+    assert estimate.loops[0].bounded is False
+    assert template.estimate(collection_size=2).output_size == 5
+
+
+def test_nested_loops() -> None:
+    source = "{% for x in (1..10) %}{% for y in (1..5) %}a{% endfor %}{% endfor %}"
+    estimate = parse(source).estimate()
+    assert estimate.output_size == 50
+    assert [(loop.iterations, loop.total) for loop in estimate.loops] == [
+        (10, 10),
+        (5, 50),
+    ]
+    assert estimate.iterations == 60
+
+
+def test_largest_branch() -> None:
+    source = "{% if a %}abc{% elsif b %}abcdef{% else %}a{% endif %}"
+    assert parse(source).estimate().output_size == 6
+    source = "{% case a %}{% when 1 %}ab{% when 2 %}abcd{% endcase %}"
+    assert parse(source).estimate().output_size == 4
+
+
+def test_captures_and_macros() -> None:
+    source = (
+        "{% capture x %}abcd{% endcapture %}{{ x }}{{ x.size }}"
+        "{% macro 'm' %}abc{% endmacro %}{% call 'm' %}{% call 'm' %}"
+    )
+    assert parse(source).estimate(value_size=1).output_size == 4 + 1 + 6
+
+
+def test_partials() -> None:
+    source = "{% include 'a' %}{% render 'b' for items %}"
+    estimate = parse(source).estimate(partial_size=10, collection_size=3)
+    assert estimate.output_size == 40
+
+
+def test_tablerow() -> None:
+    source = "{% tablerow x in (1..2) %}a{% endtablerow %}"
+    assert parse(source).estimate().output_size >= len(render(source))
+
+
+def test_default_loop_limit() -> None:
+    template = parse(
+        "{% for x in items %}a{% endfor %}"
+        "{% for x in (1..10) limit: y %}b{% endfor %}"
+        "{% tablerow x in items limit: 2 %}c{% endtablerow %}"
+    )
+    estimate = template.estimate(collection_size=100, default_loop_limit=5)
+    assert [(loop.iterations, loop.bounded) for loop in estimate.loops] == [
+        (5, True),
+        (5, True),
+        (2, True),
+    ]
+    assert estimate.iterations == 12
+    unlimited = template.estimate(collection_size=100)
+    assert [loop.bounded for loop in unlimited.loops] == [False, True, True]
@@ -0,0 +1,55 @@
+"""Test checking filter names and arguments at parse time."""
+
+import pytest
+from _liquid2 import LiquidTypeError
+from _liquid2 import lint
+from _liquid2 import parse
+
+
+def test_standard_filters_are_accepted() -> None:
+    source = (
+        "{{ a | upcase | append: 'b' | truncate: 5, '..' | default: 'x', "
+        "allow_false: true }}"
+    )
+    parse(source)
+
+
+def test_unknown_filter() -> None:
+    with pytest.raises(LiquidTypeError, match="unknown filter 'nosuchthing'"):
+        parse("{{ a | upcase | nosuchthing }}")
+
+
+def test_unknown_filter_in_inline_condition() -> None:
+    with pytest.raises(LiquidTypeError, match="unknown filter 'nope'"):
+        parse("{{ a if b else c | nope }}")
+    with pytest.raises(LiquidTypeError, match="unknown filter 'nope'"):
+        parse("{{ a if b else c || nope }}")
+
+
+def test_missing_argument() -> None:
+    with pytest.raises(LiquidTypeError, match="'append' expects 1 argument, found 0"):
+        parse("{{ a | append }}")
+
+
+def test_too_many_arguments() -> None:
+    with pytest.raises(
+        LiquidTypeError, match="'truncate' expects at most 2 arguments, found 3"
+    ):
+        parse("{% assign x = a | truncate: 1, 2, 3 %}")
+
+
+def test_unexpected_keyword_argument() -> None:
+    with pytest.raises(
+        LiquidTypeError, match="'upcase' got an unexpected keyword argument 'x'"
+    ):
+        parse("{% echo a | upcase: x: 1 %}")
+
+
+def test_lenient_filters() -> None:
+    parse("{{ a | nosuchthing: 1, 2 }}", strict_filters=False)
+    with pytest.raises(LiquidTypeError, match="'append' expects 1 argument"):
+        parse("{{ a | append }}", strict_filters=False)
+
+
+def test_lint_reports_unknown_filters() -> None:
+    assert "undefined-filter" in lint("{{ a | nosuchthing }}")
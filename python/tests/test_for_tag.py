@@ -0,0 +1,87 @@
+"""Test parsing and rendering for loops."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import PyLiquidError
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def test_parse_one_loop_variable() -> None:
+    (tag,) = parse("{% for x in items %}{% endfor %}").nodes
+    assert isinstance(tag, Node.ForTag)
+    assert tag.name == "x"
+    assert tag.value_name is None
+
+
+def test_parse_key_value_loop_variables() -> None:
+    source = "{% for k, v in obj limit:2 %}{{ k }}={{ v }}{% endfor %}"
+    (tag,) = parse(source).nodes
+    assert isinstance(tag, Node.ForTag)
+    assert tag.name == "k"
+    assert tag.value_name == "v"
+    assert str(tag) == source
+
+
+def test_render_key_value_pairs() -> None:
+    source = "{% for k, v in obj %}{{ k }}={{ v }};{% endfor %}"
+    assert render(source, {"obj": {"a": 1, "b": 2}}) == "a=1;b=2;"
+
+
+def test_render_array_of_pairs() -> None:
+    source = "{% for k, v in pairs %}{{ k }}={{ v }};{% endfor %}"
+    assert render(source, {"pairs": [["a", 1], ["b", 2]]}) == "a=1;b=2;"
+
+
+def test_items_that_are_not_pairs_have_a_null_value() -> None:
+    source = "{% for k, v in items %}{{ k }}={{ v }};{% endfor %}"
+    assert render(source, {"items": [1, 2]}) == "1=;2=;"
+
+
+def test_loop_variables_are_scoped() -> None:
+    source = "{% for k, v in obj %}{{ k }}{{ v }}{% endfor %}"
+    analysis = parse(source).analyze()
+    assert [var.name for var in analysis.globals] == ["obj"]
+    assert [ident.name for ident in analysis.assigned] == ["k", "v"]
+
+
+def test_missing_value_name() -> None:
+    with pytest.raises(LiquidSyntaxError):
+        parse("{% for k, in obj %}{% endfor %}")
+
+
+def test_huge_range_with_a_limit() -> None:
+    source = "{% for i in (1..9223372036854775807) limit: 3 %}{{ i }}{% endfor %}"
+    assert render(source) == "123"
+
+
+def test_huge_range_with_a_step_and_an_offset() -> None:
+    source = (
+        "{% for i in (0..9223372036854775807..1000000000000000000) offset: 8 %}"
+        "{{ i }},{% endfor %}"
+    )
+    assert render(source) == "8000000000000000000,9000000000000000000,"
+
+
+def test_reversed_range_with_a_limit() -> None:
+    source = "{% for i in (1..1000000000000) limit: 3 reversed %}{{ i }}{% endfor %}"
+    assert render(source) == "321"
+
+
+def test_range_with_a_step_and_offset_continue() -> None:
+    source = (
+        "{% for i in (1..20..3) limit: 2 %}{{ i }},{% endfor %}"
+        "{% for i in (1..20..3) offset: continue %}{{ i }},{% endfor %}"
+    )
+    assert render(source) == "1,4,7,10,13,16,19,"
+
+
+def test_huge_range_outside_a_loop() -> None:
+    with pytest.raises(PyLiquidError, match="the limit is"):
+        render("{% assign r = (1..9223372036854775807) %}")
+
+
+def test_forloop_name() -> None:
+    source = "{% for a in b %}{{ forloop.name }}{% endfor %}"
+    assert render(source, {"b": [1]}) == "a-b"
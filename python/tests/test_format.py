@@ -0,0 +1,110 @@
+"""Test that syntax trees format as templates that parse to the same tree."""
+
+import json
+import operator
+from dataclasses import dataclass
+from dataclasses import field
+from typing import Any
+
+import pytest
+from _liquid2 import parse
+from _liquid2 import render
+
+
+@dataclass
+class Case:
+    """Test helper class."""
+
+    name: str
+    template: str
+    data: dict[str, Any] = field(default_factory=dict)
+    templates: dict[str, str] | None = None
+    result: str | None = None
+    invalid: bool | None = None
+    tags: list[str] = field(default_factory=list)
+
+
+FILENAME = "python/tests/liquid2-compliance-test-suite/cts.json"
+
+
+def valid_cases() -> list[Case]:
+    with open(FILENAME, encoding="utf8") as fd:
+        data = json.load(fd)
+    return [Case(**case) for case in data["tests"] if not case.get("invalid")]
+
+
+@pytest.mark.parametrize("case", valid_cases(), ids=operator.attrgetter("name"))
+def test_round_trip(case: Case) -> None:
+    formatted = str(parse(case.template))
+    assert str(parse(formatted)) == formatted
+
+    # The Rust renderer doesn't support template inheritance.
+    if not case.name.startswith("tags, extends, "):
+        assert render(formatted, case.data, templates=case.templates) == render(
+            case.template, case.data, templates=case.templates
+        )
+
+
+def test_whole_floats_keep_their_fractional_part() -> None:
+    assert str(parse("{{ 1.0 }}")) == "{{ 1.0 }}"
+    assert str(parse("{{ -2.0 }}")) == "{{ -2.0 }}"
+    assert render(str(parse("{{ 1.0 }}"))) == "1.0"
+
+
+def test_quotes_and_backslashes_are_escaped() -> None:
+    source = r"""{{ "it's" }}{{ 'a\\b' }}{{ "a\nb" }}"""
+    formatted = str(parse(source))
+    assert formatted == r"{{ 'it\'s' }}{{ 'a\\b' }}{{ 'a\nb' }}"
+    assert render(formatted) == render(source) == "it'sa\\ba\nb"
+
+
+def test_names_that_are_not_identifiers_are_bracketed() -> None:
+    formatted = str(parse('{{ ["a b"] }}'))
+    assert formatted == "{{ ['a b'] }}"
+    assert render(formatted, {"a b": "x"}) == "x"
+
+
+def test_names_that_are_keywords_are_bracketed() -> None:
+    source = '{{ ["true"] }}{{ ["empty"] }}{{ ["nil"] }}'
+    formatted = str(parse(source))
+    assert formatted == "{{ ['true'] }}{{ ['empty'] }}{{ ['nil'] }}"
+    assert render(formatted, {"true": 1, "empty": 2, "nil": 3}) == "123"
+
+
+def test_identifiers_use_shorthand() -> None:
+    assert str(parse("{{ a }}{{ _b }}")) == "{{ a }}{{ _b }}"
+
+
+def test_tag_names_are_escaped() -> None:
+    source = "{% macro \"it's\" %}x{% endmacro %}{% call \"it's\" %}"
+    formatted = str(parse(source))
+    assert formatted == r"{% macro 'it\'s' %}x{% endmacro %}{% call 'it\'s' %}"
+    assert render(formatted) == "x"
+    assert str(parse(str(parse("{% extends \"it's\" %}")))) == r"{% extends 'it\'s' %}"
+
+
+IDIOMATIC_TEMPLATES = [
+    "{{ a.b }}",
+    "{{ a[b.c] }}",
+    "{{ a[0].b | default: 'x' }}",
+    "{{ a['b c'].d }}",
+    "{{ product.images[0].src | append: '?w=100' }}",
+    "{% assign x = a.b | first %}",
+    "{% if a and b or c %}x{% elsif not d %}y{% else %}z{% endif %}",
+    "{% if (a or b) and c %}x{% endif %}",
+    "{% unless a contains 'b' %}x{% endunless %}",
+    "{% for x in (1..a.size) limit:2 reversed %}{{ x }}{% endfor %}",
+    "{% case a.b %}{% when 1, 2 %}x{% else %}y{% endcase %}",
+    "{% render 'card', product:product %}",
+    "{% include 'card' with a.b as c %}",
+    "{{ items | where: x => x.price > 10 }}",
+    "{{ a.title ?: b.title ?: 'Untitled' | upcase }}",
+    "{%- if a -%}\n  {{- a.b -}}\n{%- endif -%}",
+    "{% liquid\n  assign x = a.b\n  if x\n    echo x\n  else\n    # nothing\n  endif\n%}",
+    "<ul>\n  {% liquid\n    for item in items\n      echo item.title\n    endfor\n  %}\n</ul>",
+]
+
+
+@pytest.mark.parametrize("source", IDIOMATIC_TEMPLATES)
+def test_idiomatic_templates_are_unchanged(source: str) -> None:
+    assert str(parse(source)) == source
@@ -0,0 +1,75 @@
+"""Test identifier policies."""
+
+import json
+
+import pytest
+from _liquid2 import IdentifierPolicy
+from _liquid2 import LiquidNameError
+from _liquid2 import diagnose
+from _liquid2 import parse
+from _liquid2 import parse_query
+from _liquid2 import tokenize
+
+VALID = [
+    # (source, policy)
+    ("{% assign abc = 1 %}", IdentifierPolicy.Ascii),
+    ("{% assign a_b-c2 = 1 %}", IdentifierPolicy.Ascii),
+    ("{% assign héllo = 1 %}", IdentifierPolicy.Unicode),
+    ("{% assign héllo = 1 %}", IdentifierPolicy.Xid),
+    ("{% assign _x = 1 %}", IdentifierPolicy.Xid),
+    ("{% assign a☺ = 1 %}", IdentifierPolicy.Unicode),
+    ("{% assign 1abc = 1 %}", IdentifierPolicy.Shopify),
+    ("{% capture 2x %}{% endcapture %}", IdentifierPolicy.Shopify),
+    ("{% for 3x in y %}{% endfor %}", IdentifierPolicy.Shopify),
+    ("{{ x.1a }}", IdentifierPolicy.Shopify),
+    ("{{ x.é }}", IdentifierPolicy.Xid),
+    ("{{ 1e5 }}{{ 10 }}{{ 1.5 }}", IdentifierPolicy.Ascii),
+]
+
+INVALID = [
+    # (source, policy, name)
+    ("{% assign 1abc = 1 %}", IdentifierPolicy.Unicode, "1abc"),
+    ("{% assign 1abc = 1 %}", IdentifierPolicy.Xid, "1abc"),
+    ("{% assign héllo = 1 %}", IdentifierPolicy.Ascii, "héllo"),
+    ("{% assign a☺ = 1 %}", IdentifierPolicy.Xid, "a☺"),
+    ("{% capture héllo %}{% endcapture %}", IdentifierPolicy.Ascii, "héllo"),
+    ("{% for héllo in y %}{% endfor %}", IdentifierPolicy.Ascii, "héllo"),
+    ("{{ x.é }}", IdentifierPolicy.Ascii, "é"),
+    ("{{ x.1a }}", IdentifierPolicy.Unicode, "1a"),
+    ("{{ x.a☺ }}", IdentifierPolicy.Xid, "a☺"),
+]
+
+
+@pytest.mark.parametrize(("source", "policy"), VALID)
+def test_valid_identifiers(source: str, policy: IdentifierPolicy) -> None:
+    parse(source, identifiers=policy)
+
+
+@pytest.mark.parametrize(("source", "policy", "name"), INVALID)
+def test_invalid_identifiers(source: str, policy: IdentifierPolicy, name: str) -> None:
+    with pytest.raises(LiquidNameError, match=f"invalid identifier `{name}`"):
+        parse(source, identifiers=policy)
+
+
+def test_tokenize_checks_identifiers() -> None:
+    with pytest.raises(LiquidNameError):
+        tokenize("{{ x.é }}", identifiers=IdentifierPolicy.Ascii)
+
+
+def test_query_member_names() -> None:
+    parse_query("$.héllo", identifiers=IdentifierPolicy.Xid)
+    with pytest.raises(LiquidNameError):
+        parse_query("$.héllo", identifiers=IdentifierPolicy.Ascii)
+
+
+def test_numbers_are_not_identifiers() -> None:
+    template = parse("{{ 10 | plus: 1e2 }}", identifiers=IdentifierPolicy.Shopify)
+    assert str(template) == "{{ 10 | plus: 100 }}"
+
+
+def test_error_span() -> None:
+    source = "{% assign 1abc = 1 %}"
+    (diagnostic,) = json.loads(diagnose(source))
+    start = diagnostic["start"]["offset"]
+    end = diagnostic["end"]["offset"]
+    assert source[start:end] == "1abc"
@@ -0,0 +1,58 @@
+"""Test parsing and rendering ifchanged tags."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import grammar
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def test_parse_ifchanged_tag() -> None:
+    source = "{% ifchanged -%} {{ x }} {%- endifchanged %}"
+    (tag,) = parse(source).nodes
+    assert isinstance(tag, Node.IfchangedTag)
+    assert [type(node).__name__ for node in tag.block] == [
+        "Node_Content",
+        "Node_Output",
+        "Node_Content",
+    ]
+    assert tag.span == (0, len(source))
+    assert str(tag) == source
+
+
+def test_ifchanged_in_liquid_tag() -> None:
+    (liquid_tag,) = parse("{% liquid\nifchanged\necho x\nendifchanged %}").nodes
+    assert isinstance(liquid_tag, Node.LiquidTag)
+    (tag,) = liquid_tag.block
+    assert isinstance(tag, Node.IfchangedTag)
+
+
+def test_render_ifchanged() -> None:
+    source = "{% for x in items %}{% ifchanged %}{{ x }}{% endifchanged %}{% endfor %}"
+    assert render(source, {"items": [1, 1, 2, 2, 1, 3]}) == "1213"
+
+
+def test_ifchanged_output_is_shared() -> None:
+    source = "{% ifchanged %}a{% endifchanged %}{% ifchanged %}a{% endifchanged %}"
+    assert render(source) == "a"
+
+
+def test_ifchanged_is_a_builtin_tag() -> None:
+    tags = {tag.name: tag for tag in grammar().tags}
+    assert tags["ifchanged"].end == "endifchanged"
+
+
+def test_ifchanged_takes_no_arguments() -> None:
+    with pytest.raises(LiquidSyntaxError):
+        parse("{% ifchanged x %}{% endifchanged %}")
+
+
+def test_missing_end_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endifchanged'"):
+        parse("{% ifchanged %}{{ x }}")
+
+
+def test_unexpected_end_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unexpected tag 'endifchanged'"):
+        parse("{% endifchanged %}")
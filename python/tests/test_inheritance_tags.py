@@ -0,0 +1,60 @@
+"""Test parsing template inheritance tags."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import parse
+
+
+def test_extends_tag() -> None:
+    template = parse("{% extends 'base.html' %}")
+    node = template.nodes[0]
+    assert isinstance(node, Node.ExtendsTag)
+    assert node.name == "base.html"
+    assert str(template) == "{% extends 'base.html' %}"
+
+
+def test_block_tag() -> None:
+    source = "{% block content %}hello{% endblock %}"
+    template = parse(source)
+    node = template.nodes[0]
+    assert isinstance(node, Node.BlockTag)
+    assert node.name == "content"
+    assert not node.required
+    assert not node.scoped
+    assert isinstance(node.block[0], Node.Content)
+    assert node.span == (0, len(source))
+    assert str(template) == source
+
+
+def test_block_tag_modifiers() -> None:
+    source = "{% block content scoped required %}{% endblock content %}"
+    node = parse(source).nodes[0]
+    assert isinstance(node, Node.BlockTag)
+    assert node.required
+    assert node.scoped
+    assert node.named_end
+    assert str(node) == source
+
+
+def test_nested_blocks() -> None:
+    source = "{% block a %}{% block b %}{% endblock b %}{% endblock a %}"
+    node = parse(source).nodes[0]
+    assert isinstance(node, Node.BlockTag)
+    assert isinstance(node.block[0], Node.BlockTag)
+    assert node.block[0].name == "b"
+
+
+def test_mismatched_endblock_name() -> None:
+    with pytest.raises(LiquidSyntaxError, match="found 'endblock b'"):
+        parse("{% block a %}{% endblock b %}")
+
+
+def test_missing_endblock() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endblock'"):
+        parse("{% block a %}")
+
+
+def test_unknown_block_argument() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'scoped' or 'required'"):
+        parse("{% block a required required %}{% endblock %}")
@@ -0,0 +1,46 @@
+"""Test tokenizing templates one piece of markup at a time."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import iter_tokens
+from _liquid2 import tokenize
+
+SOURCES = [
+    "",
+    "Hello",
+    "Hello, {{ you | upcase }}!",
+    "{% if x %}a{% else %}b{% endif %}",
+    "{%- raw -%}{{ x }}{% endraw %} {# note #}",
+    "{% liquid\n  assign x = 1\n  echo x\n%}after",
+    "é{{ 'ü' }}{% for x in (1..3) %}{{ $.a[?@.b] }}{% endfor %}",
+]
+
+
+def spans(markup: list[Markup]) -> list[object]:
+    rv: list[object] = []
+    for item in markup:
+        rv.append(getattr(item, "span", None))
+        match item:
+            case Markup.Output(expression=tokens) | Markup.Tag(expression=tokens):
+                rv.extend(token.span for token in tokens or [])
+            case Markup.Lines(statements=statements):
+                rv.extend(spans(statements))
+    return rv
+
+
+@pytest.mark.parametrize("source", SOURCES)
+def test_same_as_tokenize(source: str) -> None:
+    want = tokenize(source)
+    got = list(iter_tokens(source))
+    assert [str(m) for m in got] == [str(m) for m in want]
+    assert spans(got) == spans(want)
+
+
+def test_lazy() -> None:
+    it = iter_tokens("a{{ b }}{{ c")
+    assert str(next(it)) == "a"
+    assert str(next(it)) == "{{ b }}"
+    with pytest.raises(LiquidSyntaxError):
+        next(it)
+    assert list(it) == []
@@ -71,4 +71,4 @@ def test_invalid_selectors(case: Case) -> None:
     with pytest.raises(
         (_LiquidExtensionError, _LiquidNameError, _LiquidSyntaxError, _LiquidTypeError)
     ):
-        compile(parse_jsonpath_query(case.selector))
+        compile(parse_jsonpath_query(case.selector, strict=True))
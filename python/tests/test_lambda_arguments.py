@@ -0,0 +1,74 @@
+"""Test parsing and rendering arrow function filter arguments."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import LiquidTypeError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+from _liquid2 import render
+
+DATA = {
+    "items": [
+        {"title": "a", "price": 5},
+        {"title": "b", "price": 20},
+        {"title": "c", "price": 12},
+    ]
+}
+
+
+def test_parse_lambda_argument() -> None:
+    (node,) = parse("{{ items | where: item => item.price > 10 }}").nodes
+    assert isinstance(node, Node.Output)
+    filters = node.expression.filters
+    assert filters is not None
+    (arg,) = filters[0].args
+    assert isinstance(arg.value, Primitive.Lambda)
+    assert arg.value.params == ["item"]
+    assert str(arg.value) == "item => item.price > 10"
+
+
+def test_parse_lambda_with_two_parameters() -> None:
+    (node,) = parse("{{ items | map: (item, i) => i }}").nodes
+    assert isinstance(node, Node.Output)
+    filters = node.expression.filters
+    assert filters is not None
+    (arg,) = filters[0].args
+    assert isinstance(arg.value, Primitive.Lambda)
+    assert arg.value.params == ["item", "i"]
+    assert str(arg.value) == "(item, i) => i"
+
+
+def test_too_many_parameters() -> None:
+    with pytest.raises(LiquidSyntaxError, match="at most 2 parameters"):
+        parse("{{ items | map: (a, b, c) => a }}")
+
+
+def test_where_with_a_lambda() -> None:
+    source = "{{ items | where: item => item.price > 10 | map: 'title' | join }}"
+    assert render(source, DATA) == "b c"
+
+
+def test_map_with_a_lambda() -> None:
+    source = "{{ items | map: item => item.price | join: ',' }}"
+    assert render(source, DATA) == "5,20,12"
+
+
+def test_sort_with_a_lambda() -> None:
+    source = "{{ items | sort: item => item.price | map: 'title' | join }}"
+    assert render(source, DATA) == "a c b"
+
+
+def test_lambda_index_parameter() -> None:
+    source = "{{ items | where: (item, i) => i < 2 | map: 'title' | join }}"
+    assert render(source, DATA) == "a b"
+
+
+def test_lambda_parameters_are_not_globals() -> None:
+    analysis = parse("{{ items | where: item => item.price > limit }}").analyze()
+    assert [var.name for var in analysis.globals] == ["items", "limit"]
+
+
+def test_filter_without_lambda_support() -> None:
+    with pytest.raises(LiquidTypeError, match="does not accept a lambda"):
+        render("{{ items | append: item => item }}", DATA)
@@ -175,6 +175,31 @@ TEST_CASES = [
         source="{% liquid break %}",
         want="{% liquid break %}",
     ),
+    Case(
+        name="text with lone braces",
+        source="a { b } {c} {",
+        want="a { b } {c} {",
+    ),
+    Case(
+        name="tag without expression, whitespace",
+        source="{%-\n\tbreak\r\n~%}",
+        want="{%- break ~%}",
+    ),
+    Case(
+        name="raw block, no spaces",
+        source="{%raw%}{{ a }}{%endraw%}",
+        want="{% raw %}{{ a }}{% endraw %}",
+    ),
+    Case(
+        name="raw block, first endraw closes",
+        source="{% raw %}{% endrawx %}{% endraw %}{% endraw %}",
+        want="{% raw %}{% endrawx %}{% endraw %}{% endraw %}",
+    ),
+    Case(
+        name="raw tag without endraw",
+        source="{% raw %}a",
+        want="{% raw %}a",
+    ),
 ]
 
 
@@ -0,0 +1,47 @@
+"""Test converting byte offsets to line and column numbers."""
+
+import operator
+from dataclasses import dataclass
+
+import pytest
+from _liquid2 import LineIndex
+from _liquid2 import span_to_line_col
+from _liquid2 import tokenize
+
+
+@dataclass
+class Case:
+    name: str
+    source: str
+    offset: int
+    want: tuple[int, int]
+
+
+TEST_CASES: list[Case] = [
+    Case(name="start of source", source="abc", offset=0, want=(1, 1)),
+    Case(name="first line", source="abc\ndef", offset=2, want=(1, 3)),
+    Case(name="newline", source="abc\ndef", offset=3, want=(1, 4)),
+    Case(name="second line", source="abc\ndef", offset=4, want=(2, 1)),
+    Case(name="end of source", source="abc\ndef", offset=7, want=(2, 4)),
+    Case(name="past end of source", source="abc\ndef", offset=99, want=(2, 4)),
+    Case(name="empty lines", source="\n\n\nx", offset=3, want=(4, 1)),
+    Case(name="multibyte", source="é\nüx", offset=5, want=(2, 2)),
+    Case(name="inside multibyte", source="aé", offset=2, want=(1, 2)),
+]
+
+
+@pytest.mark.parametrize("case", TEST_CASES, ids=operator.attrgetter("name"))
+def test_line_col(case: Case) -> None:
+    assert LineIndex(case.source).line_col(case.offset) == case.want
+
+
+def test_span_to_line_col() -> None:
+    source = "Hello\n{{ you }}!"
+    token = tokenize(source)[1]
+    assert span_to_line_col(source, token.span) == ((2, 1), (2, 10))
+    assert LineIndex(source).span_to_line_col(token.span) == ((2, 1), (2, 10))
+
+
+def test_line_count() -> None:
+    assert len(LineIndex("")) == 1
+    assert len(LineIndex("a\nb\n")) == 3
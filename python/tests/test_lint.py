@@ -0,0 +1,73 @@
+"""Test lint rules for templates that parse successfully."""
+
+import json
+
+from _liquid2 import lint
+
+
+def codes(source: str, **kwargs: object) -> list[str]:
+    """Return the code of each lint diagnostic for _source_."""
+    return [d["code"] for d in json.loads(lint(source, **kwargs))]  # type: ignore
+
+
+def test_clean_template() -> None:
+    assert codes("Hello, {{ you | upcase }}!") == []
+
+
+def test_undefined_filter() -> None:
+    (diagnostic,) = json.loads(lint("{{ a | upcase | nosuchthing: 1 }}"))
+    assert diagnostic["code"] == "undefined-filter"
+    assert diagnostic["severity"] == "warning"
+    assert diagnostic["message"] == "unknown filter 'nosuchthing'"
+    assert diagnostic["start"]["offset"] == 16
+
+
+def test_undefined_filter_in_nested_blocks() -> None:
+    source = (
+        "{% for x in y %}{% if x %}"
+        "{{ a if b else c | nope || upcase }}"
+        "{% endif %}{% endfor %}"
+    )
+    assert codes(source) == ["undefined-filter"]
+
+
+def test_extra_filters() -> None:
+    assert codes("{{ a | money }}", filters=["money"]) == []
+
+
+def test_deep_nesting() -> None:
+    source = "{% if a %}" * 7 + "{% endif %}" * 7
+    assert codes(source) == ["deep-nesting"]
+    assert codes("{% if a %}" * 5 + "{% endif %}" * 5) == []
+
+
+def test_unused_capture() -> None:
+    assert codes("{% capture x %}hi{% endcapture %}") == ["unused-capture"]
+    assert codes("{% capture x %}hi{% endcapture %}{{ x }}") == []
+
+
+def test_reserved_assign() -> None:
+    assert codes("{% assign forloop = 1 %}{{ forloop }}") == ["reserved-assign"]
+
+
+def test_ineffective_whitespace_control() -> None:
+    assert codes("a{{- b }}") == ["ineffective-whitespace-control"]
+    assert codes("a {{- b }}") == []
+    assert codes("{{ b -}}c") == ["ineffective-whitespace-control"]
+    assert codes("{{ b ~}}\nc") == []
+
+
+def test_possibly_undefined() -> None:
+    source = "{% if a %}{% assign x = 1 %}{% endif %}{{ x }}"
+    assert codes(source) == ["possibly-undefined"]
+    assert codes("{% assign x = 1 %}{% if a %}{{ x }}{% endif %}") == []
+
+
+def test_disable_rules() -> None:
+    assert codes("a{{- b | nope }}", disable=["undefined-filter"]) == [
+        "ineffective-whitespace-control"
+    ]
+
+
+def test_syntax_error() -> None:
+    assert codes("{% if %}") == ["syntax-error"]
@@ -0,0 +1,159 @@
+"""Test the environment's default loop limit."""
+
+import asyncio
+from io import StringIO
+
+import pytest
+from _liquid2 import render_with_truncated_loops
+from liquid2 import DictLoader
+from liquid2 import Environment
+from liquid2 import RenderContext
+
+
+class LimitedEnvironment(Environment):
+    """An environment with a default loop limit of 3."""
+
+    default_loop_limit = 3
+
+
+@pytest.fixture
+def env() -> Environment:
+    return LimitedEnvironment()
+
+
+def render(
+    env: Environment, source: str, **data: object
+) -> tuple[str, RenderContext]:
+    """Render _source_ and return the result along with its render context."""
+    template = env.from_string(source)
+    context = RenderContext(template, global_data=data)
+    buf = StringIO()
+    template.render_with_context(context, buf)
+    return buf.getvalue(), context
+
+
+def test_no_default_limit() -> None:
+    source = "{% for x in (1..5) %}{{ x }}{% endfor %}"
+    assert Environment().from_string(source).render() == "12345"
+
+
+def test_loop_is_truncated(env: Environment) -> None:
+    result, context = render(env, "{% for x in (1..5) %}{{ x }}{% endfor %}")
+    assert result == "123"
+    assert len(context.truncated_loops) == 1
+
+
+def test_short_loops_are_not_truncated(env: Environment) -> None:
+    result, context = render(env, "{% for x in (1..3) %}{{ x }}{% endfor %}")
+    assert result == "123"
+    assert context.truncated_loops == []
+
+
+def test_smaller_template_limit_wins(env: Environment) -> None:
+    result, context = render(env, "{% for x in (1..5) limit:2 %}{{ x }}{% endfor %}")
+    assert result == "12"
+    assert context.truncated_loops == []
+
+
+def test_larger_template_limit_is_capped(env: Environment) -> None:
+    result, context = render(env, "{% for x in (1..5) limit:4 %}{{ x }}{% endfor %}")
+    assert result == "123"
+    assert len(context.truncated_loops) == 1
+
+
+def test_limit_applies_after_offset(env: Environment) -> None:
+    source = "{% for x in (1..9) offset:2 %}{{ x }}{% endfor %}"
+    result, context = render(env, source)
+    assert result == "345"
+    assert len(context.truncated_loops) == 1
+
+
+def test_truncation_in_partial_templates_is_recorded() -> None:
+    env = LimitedEnvironment(
+        loader=DictLoader({"part": "{% for x in y %}{{ x }}{% endfor %}"})
+    )
+    source = "{% render 'part', y: items %}"
+    result, context = render(env, source, items=list(range(9)))
+    assert result == "012"
+    assert len(context.truncated_loops) == 1
+
+
+def test_render_with_truncated_loops(env: Environment) -> None:
+    source = "{% for x in (1..5) %}{{ x }}{% endfor %}{% for x in (1..2) %}{% endfor %}"
+    template = env.from_string(source)
+    result, truncated = template.render_with_truncated_loops()
+    assert result == "123"
+    assert [token.span[0] for token in truncated] == [source.index("x in (1..5)")]
+
+
+def test_render_with_truncated_loops_async(env: Environment) -> None:
+    template = env.from_string("{% for x in y %}{{ x }}{% endfor %}")
+
+    async def coro() -> tuple[str, list[object]]:
+        return await template.render_with_truncated_loops_async(y=[1, 2, 3, 4])
+
+    result, truncated = asyncio.run(coro())
+    assert result == "123"
+    assert len(truncated) == 1
+
+
+def test_render_with_truncated_loops_without_a_limit() -> None:
+    template = Environment().from_string("{% for x in (1..5) %}{{ x }}{% endfor %}")
+    assert template.render_with_truncated_loops() == ("12345", [])
+
+
+def test_builtin_renderer_for_loop() -> None:
+    source = "{% for x in (1..5) %}{{ x }}{% endfor %}"
+    result, (truncated,) = render_with_truncated_loops(source, default_loop_limit=3)
+    assert result == "123"
+    assert truncated.span == (0, len(source))
+    assert truncated.template is None
+
+
+def test_builtin_renderer_limits() -> None:
+    def truncate(source: str) -> tuple[str, int]:
+        result, truncated = render_with_truncated_loops(
+            source, {"items": list(range(1, 10))}, default_loop_limit=3
+        )
+        return result, len(truncated)
+
+    assert truncate("{% for x in items limit:2 %}{{ x }}{% endfor %}") == ("12", 0)
+    assert truncate("{% for x in items limit:4 %}{{ x }}{% endfor %}") == ("123", 1)
+    assert truncate("{% for x in items offset:6 %}{{ x }}{% endfor %}") == ("789", 0)
+    assert truncate("{% for x in items offset:5 %}{{ x }}{% endfor %}") == ("678", 1)
+    assert truncate("{% for x in items reversed %}{{ x }}{% endfor %}") == ("321", 1)
+
+
+def test_builtin_renderer_continues_after_truncated_loops() -> None:
+    source = (
+        "{% for x in (1..5) %}{{ x }}{% endfor %}-"
+        "{% for x in (1..5) offset:continue %}{{ x }}{% endfor %}"
+    )
+    result, truncated = render_with_truncated_loops(source, default_loop_limit=3)
+    assert result == "123-45"
+    assert len(truncated) == 1
+
+
+def test_builtin_renderer_tablerow() -> None:
+    source = "{% tablerow x in (1..5) cols:2 %}{{ x }}{% endtablerow %}"
+    result, (truncated,) = render_with_truncated_loops(source, default_loop_limit=3)
+    assert result == (
+        '<tr class="row1">\n<td class="col1">1</td><td class="col2">2</td></tr>\n'
+        '<tr class="row2"><td class="col1">3</td></tr>\n'
+    )
+    assert truncated.span == (0, len(source))
+
+    source = "{% tablerow x in (1..5) limit:2 %}{{ x }}{% endtablerow %}"
+    _, truncated = render_with_truncated_loops(source, default_loop_limit=3)
+    assert truncated == []
+
+
+def test_builtin_renderer_truncation_in_partials() -> None:
+    source = "{% render 'part' %}"
+    result, (truncated,) = render_with_truncated_loops(
+        source,
+        templates={"part": "{% for x in (1..9) %}{{ x }}{% endfor %}"},
+        default_loop_limit=2,
+    )
+    assert result == "12"
+    assert truncated.template == "part"
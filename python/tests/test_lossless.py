@@ -0,0 +1,79 @@
+"""Test lossless parsing and concrete syntax trees."""
+
+import json
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import parse_lossless
+
+FILENAME = "python/tests/liquid2-compliance-test-suite/cts.json"
+
+SOURCE = "{% if x -%}\n  {{- a | slice: 1 ,  2  -}}\n{%- endif %}!"
+
+
+def valid_templates() -> list[str]:
+    with open(FILENAME, encoding="utf8") as fd:
+        data = json.load(fd)
+    return [case["template"] for case in data["tests"] if not case.get("invalid")]
+
+
+@pytest.mark.parametrize("source", valid_templates())
+def test_round_trip_compliance_templates(source: str) -> None:
+    template = parse_lossless(source)
+    assert template.to_source() == source
+    assert str(template) == source
+
+
+def test_round_trip_preserves_spacing_and_whitespace_control() -> None:
+    template = parse_lossless(SOURCE)
+    assert template.source == SOURCE
+    assert template.to_source() == SOURCE
+    assert str(template.template) != SOURCE
+
+
+def test_node_text() -> None:
+    template = parse_lossless(SOURCE)
+    if_tag, content = template.template.nodes
+    assert isinstance(if_tag, Node.IfTag)
+    assert template.text(if_tag.span) == SOURCE[:-1]
+    assert template.text(content.span) == "!"
+
+    output = if_tag.block[1]
+    assert isinstance(output, Node.Output)
+    assert template.text(output.span) == "{{- a | slice: 1 ,  2  -}}"
+
+
+def test_argument_separators() -> None:
+    template = parse_lossless(SOURCE)
+    output = template.template.nodes[0].block[1]
+    assert isinstance(output, Node.Output)
+    first, second = output.expression.filters[0].args
+    assert template.text(first.span) == "1"
+    assert template.text(second.span) == "2"
+    assert template.text((first.span[1], second.span[0])) == " ,  "
+
+
+def test_text_out_of_range() -> None:
+    template = parse_lossless(SOURCE)
+    with pytest.raises(LiquidSyntaxError, match="span is out of range"):
+        template.text((0, 100))
+
+
+def test_edit() -> None:
+    template = parse_lossless(SOURCE)
+    template.edit((6, 7), "y")
+    assert template.source == SOURCE.replace("x", "y", 1)
+    assert template.to_source() == template.source
+
+
+def test_invalid_edit_leaves_template_unchanged() -> None:
+    template = parse_lossless(SOURCE)
+    with pytest.raises(LiquidSyntaxError, match="missing end tag"):
+        template.edit((41, 53), "")
+    assert template.to_source() == SOURCE
+
+
+def test_syntax_errors() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endif'"):
+        parse_lossless("{% if x %}")
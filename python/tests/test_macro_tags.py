@@ -0,0 +1,52 @@
+"""Test parsing macro and call tags."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+
+
+def test_macro_tag() -> None:
+    source = "{% macro 'greet' you, greeting:'Hello' %}{{ greeting }}{% endmacro %}"
+    template = parse(source)
+    node = template.nodes[0]
+    assert isinstance(node, Node.MacroTag)
+    assert node.name == "greet"
+    assert [param.name for param in node.params] == ["you", "greeting"]
+    assert node.params[0].default is None
+    assert isinstance(node.params[1].default, Primitive.StringLiteral)
+    assert node.span == (0, len(source))
+    assert str(template) == source
+
+
+def test_macro_without_parameters() -> None:
+    template = parse("{% macro m %}x{% endmacro %}")
+    node = template.nodes[0]
+    assert isinstance(node, Node.MacroTag)
+    assert node.params == []
+    assert str(template) == "{% macro 'm' %}x{% endmacro %}"
+
+
+def test_call_tag() -> None:
+    template = parse("{% call 'greet' 'World', greeting: 'Hi' %}")
+    node = template.nodes[0]
+    assert isinstance(node, Node.CallTag)
+    assert node.name == "greet"
+    assert [arg.name for arg in node.args] == [None, "greeting"]
+    assert str(template) == "{% call 'greet' 'World', greeting:'Hi' %}"
+
+
+def test_duplicate_parameter() -> None:
+    with pytest.raises(LiquidSyntaxError, match="duplicate parameter 'a'"):
+        parse("{% macro m a, a %}{% endmacro %}")
+
+
+def test_invalid_parameter() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected a parameter name"):
+        parse("{% macro m 'a' %}{% endmacro %}")
+
+
+def test_missing_endmacro() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endmacro'"):
+        parse("{% macro m %}")
@@ -0,0 +1,77 @@
+"""Test simplifying templates before rendering."""
+
+from _liquid2 import Node
+from _liquid2 import parse
+
+
+def optimize(source: str) -> str:
+    """Parse and optimize _source_, returning the optimized markup."""
+    return str(parse(source).optimized())
+
+
+def test_constant_if_is_replaced_by_its_branch() -> None:
+    assert optimize("a{% if true %}b{% else %}c{% endif %}d") == "abd"
+    assert optimize("a{% if false %}b{% else %}c{% endif %}d") == "acd"
+    assert optimize("a{% if false %}b{% endif %}d") == "ad"
+
+
+def test_constant_elsif() -> None:
+    source = "{% if false %}a{% elsif 1 > 2 %}b{% elsif 'x' %}c{% endif %}"
+    assert optimize(source) == "c"
+
+
+def test_constant_unless() -> None:
+    assert optimize("{% unless false %}a{% else %}b{% endunless %}") == "a"
+    assert optimize("{% unless true %}a{% endunless %}") == ""
+
+
+def test_variable_condition_is_kept() -> None:
+    source = "{% if x %}a{% else %}b{% endif %}"
+    assert optimize(source) == source
+
+
+def test_constant_output_is_rendered() -> None:
+    assert optimize("a {{ 'b' }} {{ 1 }} {{ nil }}c") == "a b 1 c"
+
+
+def test_constant_filtered_output_is_rendered() -> None:
+    assert optimize("{{ 'b' | upcase | append: 'c' }}") == "Bc"
+
+
+def test_date_filter_is_not_folded() -> None:
+    source = "{{ 'now' | date: '%Y' }}"
+    assert optimize(source) == source
+
+
+def test_comments_are_removed() -> None:
+    assert optimize("a{# note #}b{## x ##}c") == "abc"
+
+
+def test_adjacent_content_is_merged() -> None:
+    nodes = parse("a{{ 'b' }}c").optimized().nodes
+    assert len(nodes) == 1
+    assert isinstance(nodes[0], Node.Content)
+    assert nodes[0].text == "abc"
+
+
+def test_nested_blocks_are_optimized() -> None:
+    source = "{% for x in y %}{% if true %}a{% endif %}{% endfor %}"
+    assert optimize(source) == "{% for x in y %}a{% endfor %}"
+
+
+def test_whitespace_control_is_preserved() -> None:
+    source = "a {%- if true %} b {% endif %} c"
+    assert optimize(source) == source
+    source = "a {% if true %}{{- 'b' }}{% endif %} c"
+    assert optimize(source) == source
+    source = "a {% if true %} b {% endif %}{{- x }}"
+    assert optimize(source) == source
+
+
+def test_branches_with_inheritance_tags_are_kept() -> None:
+    source = "{% extends 'base' %}{% if false %}{% block b %}x{% endblock %}{% endif %}"
+    assert optimize(source) == source
+    source = "{% unless true %}{% if x %}{% extends 'base' %}{% endif %}{% endunless %}"
+    assert optimize(source) == source
+    source = "{% if true %}a{% else %}{% block b %}{% endblock %}{% endif %}"
+    assert optimize(source) == source
@@ -0,0 +1,83 @@
+"""Test template outlines computed from markup."""
+
+from _liquid2 import Parser
+from _liquid2 import outline
+
+SOURCE = """\
+{% assign x = 1 %}
+{% if x %}
+  {% for a in b %}{{ a }}{% endfor %}
+{% else %}
+  {% capture y %}hi{% endcapture %}
+{% endif %}
+{% liquid
+  assign z = 2
+  echo z %}
+{% macro 'm' %}{% endmacro %}"""
+
+
+def test_top_level_tags() -> None:
+    items = outline(SOURCE)
+    assert [item.name for item in items] == ["assign", "if", "liquid", "macro"]
+
+
+def test_nested_blocks() -> None:
+    item = outline(SOURCE)[1]
+    assert item.block is True
+    assert [child.name for child in item.children] == ["for", "capture"]
+    assert SOURCE[slice(*item.span)] == "{% if x %}"
+    assert item.end is not None
+    assert SOURCE[slice(*item.end)] == "{% endif %}"
+    assert [SOURCE[slice(*span)] for span in item.branches] == ["{% else %}"]
+    assert SOURCE[slice(*item.range)].startswith("{% if x %}")
+    assert SOURCE[slice(*item.range)].endswith("{% endif %}")
+
+
+def test_inline_tags() -> None:
+    item = outline(SOURCE)[0]
+    assert item.block is False
+    assert item.end is None
+    assert item.children == []
+    assert item.range == item.span
+
+
+def test_defined_names() -> None:
+    items = outline(SOURCE)
+    defines = items[0].defines()
+    assert defines is not None
+    assert defines[0] == "x"
+    assert SOURCE[slice(*defines[1])] == "x"
+    assert items[3].defines() is not None
+    assert items[3].defines()[0] == "m"  # type: ignore
+    assert items[1].defines() is None
+
+
+def test_liquid_tag_statements() -> None:
+    item = outline(SOURCE)[2]
+    assert [child.name for child in item.children] == ["assign", "echo"]
+
+
+def test_unclosed_blocks() -> None:
+    source = "{% if a %}{% for x in y %}{% unless b %}{% endif %}{% endfor %}"
+    (item,) = outline(source)
+    assert item.end is not None
+    (child,) = item.children
+    assert child.name == "for"
+    assert child.end is None
+    assert child.range[1] == source.index("{% endif %}")
+
+
+def test_stray_end_tags_are_ignored() -> None:
+    assert outline("{% endif %}{% endfor %}") == []
+
+
+def test_invalid_markup_is_skipped() -> None:
+    source = "{% if a %}{{ x ! }}\n{% endif %}"
+    (item,) = outline(source)
+    assert item.end is not None
+
+
+def test_tag_aliases() -> None:
+    parser = Parser()
+    items = parser.outline("{% if a %}{% elsif b %}{% endif %}")
+    assert len(items[0].branches) == 1
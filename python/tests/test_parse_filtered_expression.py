@@ -0,0 +1,75 @@
+"""Test the Rust filtered expression parser."""
+
+import operator
+from dataclasses import dataclass
+
+import pytest
+from _liquid2 import BooleanExpression
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Primitive
+from _liquid2 import parse_filtered_expression
+
+
+@dataclass
+class Case:
+    """Test case helper."""
+
+    name: str
+    source: str
+    want: str
+
+
+TEST_CASES = [
+    Case(
+        name="literal",
+        source="'hello'",
+        want="'hello'",
+    ),
+    Case(
+        name="filter with arguments",
+        source="x | join: ', '",
+        want="x | join: ', '",
+    ),
+    Case(
+        name="leading and trailing whitespace",
+        source="  x  ",
+        want="x",
+    ),
+    Case(
+        name="range",
+        source="(1..x)",
+        want="(1..x)",
+    ),
+    Case(
+        name="inline condition with alternative and tail filters",
+        source="x | upcase if y else z | downcase || strip",
+        want="x | upcase if y else z | downcase || strip",
+    ),
+]
+
+
+@pytest.mark.parametrize("case", TEST_CASES, ids=operator.attrgetter("name"))
+def test_parse_filtered_expression(case: Case) -> None:
+    """Test that expressions round-trip through the parser."""
+    assert str(parse_filtered_expression(case.source)) == case.want
+
+
+def test_filtered_expression_nodes() -> None:
+    """Test that parsed expressions expose their nodes and spans."""
+    expr = parse_filtered_expression("x | default: 'anon' if user")
+
+    assert expr.span == (0, 27)
+    assert isinstance(expr.left, Primitive.Query)
+    assert expr.left.span == (0, 1)
+    assert expr.filters is not None
+    assert [f.name for f in expr.filters] == ["default"]
+    assert expr.condition is not None
+    assert isinstance(expr.condition.expr, BooleanExpression.Primitive)
+    assert expr.condition.alternative is None
+
+
+@pytest.mark.parametrize("source", ["", "x |", "x y"])
+def test_invalid_filtered_expression(source: str) -> None:
+    """Test that invalid expressions raise a syntax error."""
+    with pytest.raises(LiquidSyntaxError):
+        parse_filtered_expression(source)
@@ -0,0 +1,53 @@
+"""Test limits on template nesting depth, node count and length."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def nested(depth: int) -> str:
+    return "{% if x %}" * depth + "a" + "{% endif %}" * depth
+
+
+def test_no_limits_by_default() -> None:
+    parse(nested(50))
+
+
+def test_deeply_nested_templates() -> None:
+    source = "{% if true %}" * 10_000 + "a" + "{% endif %}" * 10_000
+    parse(source)
+    assert render(source) == "a"
+
+
+def test_max_depth() -> None:
+    parse(nested(3), max_depth=3)
+    with pytest.raises(LiquidSyntaxError, match="nested more than 3 deep"):
+        parse(nested(4), max_depth=3)
+
+
+def test_max_depth_counts_liquid_tags() -> None:
+    source = "{% liquid\nif x\necho 'a'\nendif %}"
+    parse(source, max_depth=2)
+    with pytest.raises(LiquidSyntaxError):
+        parse(source, max_depth=1)
+
+
+def test_max_nodes() -> None:
+    source = "a{{ b }}{% if c %}d{% endif %}"
+    parse(source, max_nodes=4)
+    with pytest.raises(LiquidSyntaxError, match="more than 3 nodes"):
+        parse(source, max_nodes=3)
+
+
+def test_max_nodes_counts_liquid_tag_lines() -> None:
+    source = "{% liquid\necho a\necho b %}"
+    parse(source, max_nodes=3)
+    with pytest.raises(LiquidSyntaxError):
+        parse(source, max_nodes=2)
+
+
+def test_max_bytes() -> None:
+    parse("hello", max_bytes=5)
+    with pytest.raises(LiquidSyntaxError, match="6 bytes long"):
+        parse("héllo", max_bytes=5)
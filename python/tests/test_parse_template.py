@@ -0,0 +1,81 @@
+"""Test the Rust template parser."""
+
+import json
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import Template
+from _liquid2 import parse
+
+
+def test_parse_returns_a_template() -> None:
+    template = parse("Hello, {{ you }}!")
+    assert isinstance(template, Template)
+    assert str(template) == "Hello, {{ you }}!"
+    assert len(template.nodes) == 3  # noqa: PLR2004
+
+
+def test_match_nodes() -> None:
+    template = parse("{% for x in (1..3) %}{{ x }}{% else %}none{% endfor %}")
+    match template.nodes:
+        case [Node.ForTag(name="x", iterable=Primitive.Range(), default=default)]:
+            assert default is not None
+            assert [str(node) for node in default.block] == ["none"]
+        case _:
+            pytest.fail(f"unexpected nodes {template.nodes!r}")
+
+
+def test_nested_blocks() -> None:
+    template = parse("{% if a %}{% unless b %}c{% endunless %}{% elsif d %}{% endif %}")
+    (if_tag,) = template.nodes
+    assert isinstance(if_tag, Node.IfTag)
+    (unless_tag,) = if_tag.block
+    assert isinstance(unless_tag, Node.UnlessTag)
+    assert str(unless_tag) == "{% unless b %}c{% endunless %}"
+    assert str(if_tag.alternatives[0].condition) == "d"
+
+
+def test_spans() -> None:
+    template = parse("ab{{ c }}")
+    assert [node.span for node in template.nodes] == [(0, 2), (2, 9)]
+
+
+def test_syntax_error() -> None:
+    with pytest.raises(LiquidSyntaxError, match="in 'page.liquid' at line 2$"):
+        parse("{% if a %}\n{% endfor %}", source_name="page.liquid")
+
+
+
+MISMATCHED_END_TAGS = [
+    ("{% if x %}a{% endfor %}", "expected 'elsif', 'else' or 'endif', found 'endfor'"),
+    ("{% for x in y %}{% endif %}", "expected 'else' or 'endfor', found 'endif'"),
+    (
+        "{% if x %}{% else %}{% elsif y %}{% endif %}",
+        "unexpected 'elsif' tag after 'else'",
+    ),
+]
+
+
+@pytest.mark.parametrize("case", MISMATCHED_END_TAGS)
+def test_mismatched_end_tag(case: tuple[str, str]) -> None:
+    source, message = case
+    with pytest.raises(LiquidSyntaxError, match=message):
+        parse(source)
+
+
+def test_json_round_trip() -> None:
+    source = (
+        "{% for x in y limit:2 %}{{ x | upcase }}"
+        "{% else %}{{ $.a[?@.b] }}{% endfor %}"
+    )
+    template = parse(source)
+    data = json.loads(template.to_json())
+    assert "ForTag" in data["nodes"][0]
+    assert str(Template.from_json(template.to_json())) == str(template)
+
+
+def test_invalid_json() -> None:
+    with pytest.raises(ValueError, match="EOF while parsing"):
+        Template.from_json('{"nodes": [')
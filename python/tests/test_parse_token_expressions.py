@@ -0,0 +1,68 @@
+"""Test parsing tag expression tokens from Python."""
+
+import pytest
+from _liquid2 import BooleanExpression
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import Token
+from _liquid2 import parse
+from _liquid2 import parse_boolean_expression
+from _liquid2 import parse_filtered_expression
+from _liquid2 import parse_keyword_arguments
+from _liquid2 import tokenize
+
+
+def tag_tokens(source: str) -> list[Token]:
+    tag, _ = tokenize(source)
+    assert isinstance(tag, Markup.Tag)
+    assert tag.expression is not None
+    return tag.expression
+
+
+def test_filtered_expression_from_tokens() -> None:
+    tokens = tag_tokens("{% box a.b | append: 'c' | upcase %}")
+    expression = parse_filtered_expression(tokens)
+    assert str(expression) == "a.b | append: 'c' | upcase"
+    assert expression.span == (7, 33)
+
+
+def test_boolean_expression_from_tokens() -> None:
+    tokens = tag_tokens("{% when_all a > 1 and not b %}")
+    expression = parse_boolean_expression(tokens)
+    assert isinstance(expression, BooleanExpression.Logical)
+    assert str(expression) == "a > 1 and not b"
+
+
+def test_keyword_arguments_from_tokens() -> None:
+    tokens = tag_tokens("{% box width: 3, title: page.title %}")
+    args = parse_keyword_arguments(tokens)
+    assert [arg.name for arg in args] == ["width", "title"]
+    assert isinstance(args[0].value, Primitive.Integer)
+    assert isinstance(args[1].value, Primitive.Query)
+
+
+def test_tokens_from_a_tag_extension() -> None:
+    source = "{% box a: 1, b: 'c' %}{% endbox %}"
+    (node,) = parse(source, common_tags=True).nodes
+    assert isinstance(node, Node.TagExtension)
+    args = parse_keyword_arguments(node.expression)
+    assert [arg.name for arg in args] == ["a", "b"]
+
+
+def test_parse_from_source_text() -> None:
+    assert str(parse_boolean_expression("a or b")) == "a or b"
+    assert [arg.name for arg in parse_keyword_arguments("x: 1")] == ["x"]
+
+
+def test_leftover_tokens() -> None:
+    tokens = tag_tokens("{% box a b %}")
+    with pytest.raises(LiquidSyntaxError):
+        parse_filtered_expression(tokens)
+
+
+def test_empty_token_list() -> None:
+    with pytest.raises(LiquidSyntaxError, match="end of expression"):
+        parse_boolean_expression([])
+    assert parse_keyword_arguments([]) == []
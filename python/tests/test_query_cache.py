@@ -0,0 +1,35 @@
+"""Test caching queries parsed by a lexer."""
+
+import pytest
+from _liquid2 import Lexer
+from _liquid2 import LiquidNameError
+from _liquid2 import parse_query
+
+
+def test_cached_queries_are_equal() -> None:
+    lexer = Lexer(query_cache_size=2)
+    for _ in range(3):
+        for path in ("a.b", "a['c'][0]", "$.d[?@.e > 1]", "a.b"):
+            query = lexer.parse_query(path)
+            assert query == parse_query(path)
+            assert str(query) == str(parse_query(path))
+
+
+def test_cached_queries_are_independent() -> None:
+    lexer = Lexer()
+    first = lexer.parse_query("a.b")
+    second = lexer.parse_query("a.b")
+    assert first == second
+    assert first is not second
+
+
+def test_errors_are_not_cached() -> None:
+    lexer = Lexer()
+    for _ in range(2):
+        with pytest.raises(LiquidNameError):
+            lexer.parse_query("$.a[?nosuchfunc(@)]")
+
+
+def test_cache_disabled() -> None:
+    lexer = Lexer(query_cache_size=0)
+    assert lexer.parse_query("a.b") == lexer.parse_query("a.b")
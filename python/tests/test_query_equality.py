@@ -0,0 +1,54 @@
+"""Test normalizing and comparing queries."""
+
+from _liquid2 import parse_jsonpath_query
+
+
+def test_queries_compare_structurally() -> None:
+    assert parse_jsonpath_query("$.a[0]") == parse_jsonpath_query("$['a'][0]")
+    assert parse_jsonpath_query("$.a") == parse_jsonpath_query("$[ 'a' ]")
+    assert parse_jsonpath_query("$.a") != parse_jsonpath_query("$.b")
+    assert parse_jsonpath_query("$.a") != parse_jsonpath_query("$..a")
+
+
+def test_segments_and_selectors_compare_structurally() -> None:
+    a = parse_jsonpath_query("$.a[?@.b > 1]")
+    b = parse_jsonpath_query("$['a'][? @['b']>1]")
+    assert a.segments == b.segments
+    assert a.segments[1].selectors[0] == b.segments[1].selectors[0]
+
+
+def test_normalize_slices() -> None:
+    a = parse_jsonpath_query("$[1:5]").normalize()
+    b = parse_jsonpath_query("$[1:5:1]").normalize()
+    assert a == b
+    assert a.canonical() == "$[1:5:1]"
+    assert parse_jsonpath_query("$[1:5]") != parse_jsonpath_query("$[1:5:1]")
+
+
+def test_normalize_negative_zero() -> None:
+    a = parse_jsonpath_query("$[?@.a == -0.0]").normalize()
+    b = parse_jsonpath_query("$[?@.a == 0.0]").normalize()
+    assert a == b
+    assert a.canonical() == "$[?@['a'] == 0]"
+
+
+def test_negative_fractions() -> None:
+    query = parse_jsonpath_query("$[?@.a == -0.5]")
+    assert query.canonical() == "$[?@['a'] == -0.5]"
+
+
+def test_normalize_double_negation() -> None:
+    a = parse_jsonpath_query("$[?!(!@.a)]").normalize()
+    assert a == parse_jsonpath_query("$[?@.a]").normalize()
+    assert a.canonical() == "$[?@['a']]"
+
+
+def test_normalize_nested_queries() -> None:
+    a = parse_jsonpath_query("$[?@[0:2] && $.b[?!(!@.c)]]").normalize()
+    assert a.canonical() == "$[?(@[0:2:1] && $['b'][?@['c']])]"
+
+
+def test_normalize_drops_end_of_input() -> None:
+    query = parse_jsonpath_query("$.a")
+    assert len(query.segments) == 2
+    assert len(query.normalize().segments) == 1
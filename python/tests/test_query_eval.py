@@ -0,0 +1,136 @@
+"""Test evaluating JSONPath queries against data."""
+
+from typing import Any
+
+import pytest
+from _liquid2 import LiquidTypeError
+from _liquid2 import parse_jsonpath_query
+
+DATA = {
+    "a": [0, 1, 2, 3, 4, 5],
+    "b": {"c": [{"d": 1}, {"d": None}, {"e": "x"}], "d": 2},
+    "s": "ab",
+}
+
+
+def find(query: str, data: Any = DATA) -> list[object]:
+    """Return the values of nodes selected by _query_."""
+    return [node.value() for node in parse_jsonpath_query(query).find(data)]
+
+
+def paths(query: str, data: Any = DATA) -> list[str]:
+    """Return the normalized paths of nodes selected by _query_."""
+    return [node.path() for node in parse_jsonpath_query(query).find(data)]
+
+
+@pytest.mark.parametrize(
+    ("query", "want"),
+    [
+        ("$.a[::-1]", [5, 4, 3, 2, 1, 0]),
+        ("$.a[::-2]", [5, 3, 1]),
+        ("$.a[4:1:-1]", [4, 3, 2]),
+        ("$.a[-1:-4:-1]", [5, 4, 3]),
+        ("$.a[1:4:-1]", []),
+        ("$.a[:2:-1]", [5, 4, 3]),
+        ("$.a[10::-2]", [5, 3, 1]),
+        ("$.a[::0]", []),
+        ("$.a[1:3]", [1, 2]),
+        ("$.a[-2:]", [4, 5]),
+        ("$.s[::-1]", []),
+    ],
+)
+def test_slices(query: str, want: list[object]) -> None:
+    assert find(query) == want
+
+
+def test_descendant_segments() -> None:
+    assert paths("$..d") == [
+        "$['b']['d']",
+        "$['b']['c'][0]['d']",
+        "$['b']['c'][1]['d']",
+    ]
+    assert find("$..d") == [2, 1, None]
+    assert find("$..[0]") == [0, {"d": 1}]
+    assert find("$.b..e") == ["x"]
+    assert find("$..nosuchthing") == []
+    assert len(find("$..*")) == len(find("$..[*]"))
+
+
+def test_descendant_wildcard_order() -> None:
+    data = {"o": {"j": 1, "k": 2}, "a": [5, [6]]}
+    assert paths("$..*", data) == [
+        "$['o']",
+        "$['a']",
+        "$['o']['j']",
+        "$['o']['k']",
+        "$['a'][0]",
+        "$['a'][1]",
+        "$['a'][1][0]",
+    ]
+
+
+@pytest.mark.parametrize(
+    ("query", "want"),
+    [
+        ("$.b.c[?@.d == null]", [{"d": None}]),
+        ("$.b.c[?@.d != null]", [{"d": 1}, {"e": "x"}]),
+        ("$.b.c[?@.d == @.f]", [{"e": "x"}]),
+        ("$.b.c[?@.d != @.f]", [{"d": 1}, {"d": None}]),
+        ("$.b.c[?@.f < 1]", []),
+        ("$.b.c[?@.f <= @.g]", [{"d": 1}, {"d": None}, {"e": "x"}]),
+        ("$.b.c[?@.d < null]", []),
+        ("$.b.c[?@.d <= null]", [{"d": None}]),
+        ("$.b.c[?@.d]", [{"d": 1}, {"d": None}]),
+        ("$.b.c[?!@.d]", [{"e": "x"}]),
+    ],
+)
+def test_comparisons_with_null_and_absent_values(
+    query: str, want: list[object]
+) -> None:
+    assert find(query) == want
+
+
+def test_count_function() -> None:
+    assert find("$[?count(@.*) == 2]", {"x": [1, 2], "y": [1], "z": 1}) == [[1, 2]]
+    assert find("$.b[?count(@..d) > 1]") == [[{"d": 1}, {"d": None}, {"e": "x"}]]
+
+
+def test_length_function() -> None:
+    data = [{"v": "abc"}, {"v": [1, 2, 3]}, {"v": {"a": 1}}, {"v": 3}, {}]
+    assert find("$[?length(@.v) == 3]", data) == [{"v": "abc"}, {"v": [1, 2, 3]}]
+    assert find("$[?length(@.v) == 1]", data) == [{"v": {"a": 1}}]
+    # The length of a number or nothing is nothing.
+    assert find("$[?length(@.v) == length(@.nosuchthing)]", data) == [
+        {"v": 3},
+        {},
+    ]
+    assert find("$[?length('abé') == 3]", [1]) == [1]
+
+
+def test_match_function() -> None:
+    data = ["ab", "abc", "xab", 1]
+    assert find("$[?match(@, 'ab')]", data) == ["ab"]
+    assert find("$[?match(@, 'a.*')]", data) == ["ab", "abc"]
+    assert find("$[?match(@.x, 'a.*')]", data) == []
+    assert find("$[?match(@, @)]", data) == ["ab", "abc", "xab"]
+
+
+def test_invalid_regular_expression() -> None:
+    with pytest.raises(LiquidTypeError, match="unterminated character class"):
+        find("$[?match(@, 'a[')]", ["a["])
+
+
+def test_search_function() -> None:
+    data = ["ab", "abc", "xab", "a^b$", 1]
+    assert find("$[?search(@, 'ab')]", data) == ["ab", "abc", "xab"]
+    assert find("$[?search(@, 'b.')]", data) == ["abc", "a^b$"]
+    assert find("$[?search(@, 1)]", data) == []
+    # I-Regexp has no anchors, so `^` and `$` match themselves.
+    assert find("$[?search(@, '^b')]", data) == ["a^b$"]
+    assert find("$[?search(@, 'b$')]", data) == ["a^b$"]
+
+
+def test_value_function() -> None:
+    data = [{"a": 1}, {"a": [1]}, {"b": {"a": 1}}, {"b": {"a": 1, "c": {"a": 1}}}]
+    assert find("$[?value(@.a) == 1]", data) == [{"a": 1}]
+    assert find("$[?value(@..a) == 1]", data) == [{"a": 1}, {"b": {"a": 1}}]
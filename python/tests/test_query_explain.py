@@ -0,0 +1,46 @@
+"""Test JSONPath query evaluation traces."""
+
+from _liquid2 import parse_query
+from liquid2.query import compile
+
+DATA = {
+    "users": [
+        {"name": "Sue", "score": 100, "admin": True},
+        {"name": "John", "score": 86},
+        {"name": "Sally", "score": 84, "admin": False},
+        {"name": "Jane", "score": 55},
+    ]
+}
+
+
+def test_explain_counts_nodes() -> None:
+    query = compile(parse_query("$.users[*].name"))
+    trace = query.explain(DATA)
+
+    assert trace.nodes.values() == query.find(DATA).values()
+    assert [(s.visited, s.matched) for s in trace.segments] == [
+        (1, 1),
+        (1, 4),
+        (4, 4),
+    ]
+
+
+def test_explain_filter_branches() -> None:
+    query = compile(parse_query("$.users[?@.score > 80 && @.admin == true].name"))
+    trace = query.explain(DATA)
+
+    assert trace.nodes.values() == ["Sue"]
+
+    selector = trace.segments[1].selectors[0]
+    assert selector.visited == 4  # noqa: PLR2004
+    assert selector.matched == 1
+    assert [(b.true, b.false) for b in selector.branches] == [(1, 3), (3, 1), (1, 3)]
+
+
+def test_explain_descendant_segment() -> None:
+    query = compile(parse_query("$..name"))
+    trace = query.explain(DATA)
+
+    assert trace.nodes.values() == query.find(DATA).values()
+    assert trace.segments[0].visited == 6  # noqa: PLR2004
+    assert trace.segments[0].matched == 4  # noqa: PLR2004
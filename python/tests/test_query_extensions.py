@@ -0,0 +1,81 @@
+"""Test per-environment toggles for non-standard query syntax."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError as _LiquidSyntaxError
+from _liquid2 import parse_jsonpath_query
+from liquid2 import Environment
+from liquid2 import QueryExtensions
+from liquid2.exceptions import LiquidSyntaxError
+
+
+class StrictQueryEnvironment(Environment):
+    """An environment that only accepts RFC 9535 queries."""
+
+    query_extensions = QueryExtensions.strict()
+
+
+class NoSingularQuerySelectorEnvironment(Environment):
+    """An environment without embedded singular query selectors."""
+
+    query_extensions = QueryExtensions(singular_query_selector=False)
+
+
+class NoImplicitRootEnvironment(Environment):
+    """An environment that requires a root identifier for multi-segment queries."""
+
+    query_extensions = QueryExtensions(implicit_root=False)
+
+
+DATA = {"a": {"b": "c"}, "x": {"c": "hello"}}
+
+
+def test_all_extensions_enabled_by_default() -> None:
+    env = Environment()
+    assert env.from_string("{{ x[a.b] }}").render(**DATA) == "hello"
+
+
+def test_strict_queries_with_root_identifier() -> None:
+    env = StrictQueryEnvironment()
+    assert env.from_string("{{ $.x.c }}").render(**DATA) == "hello"
+
+
+def test_strict_queries_allow_single_names() -> None:
+    env = StrictQueryEnvironment()
+    assert env.from_string("{{ a | size }}").render(**DATA) == "1"
+
+
+def test_implicit_root_disabled() -> None:
+    env = StrictQueryEnvironment()
+    with pytest.raises(LiquidSyntaxError, match="'implicit_root' query extension"):
+        env.from_string("{{ x.c }}")
+
+
+def test_singular_query_selector_disabled() -> None:
+    env = NoSingularQuerySelectorEnvironment()
+    assert env.from_string("{{ x.c }}").render(**DATA) == "hello"
+    with pytest.raises(
+        LiquidSyntaxError, match="'singular_query_selector' query extension"
+    ):
+        env.from_string("{{ x[a.b] }}")
+
+
+def test_implicit_root_singular_query_selector_disabled() -> None:
+    env = NoImplicitRootEnvironment()
+    assert env.from_string("{{ $.x[$.a.b] }}").render(**DATA) == "hello"
+    with pytest.raises(LiquidSyntaxError, match="'implicit_root' query extension"):
+        env.from_string("{{ $.x[a.b] }}")
+
+
+def test_hyphenated_names_disabled() -> None:
+    env = StrictQueryEnvironment()
+    assert env.from_string("{{ $.x['a-b'] }}").render(**DATA) == ""
+    with pytest.raises(LiquidSyntaxError, match="'hyphenated_names' query extension"):
+        env.from_string("{{ $.x.a-b }}")
+
+
+def test_strict_jsonpath_query() -> None:
+    assert len(parse_jsonpath_query("$.a..b-c").segments) == 3
+    assert len(parse_jsonpath_query("$.a['b']", strict=True).segments) == 3
+    for path in ("a.b", "$.a[$.b]", "$.a.b-c", "$..b-c"):
+        with pytest.raises(_LiquidSyntaxError, match="query extension"):
+            parse_jsonpath_query(path, strict=True)
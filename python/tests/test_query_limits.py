@@ -0,0 +1,44 @@
+"""Test query complexity scores and parser limits."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import parse_jsonpath_query
+
+
+def complexity(path: str) -> int:
+    return parse_jsonpath_query(path).complexity()
+
+
+def test_names_and_indices_are_cheap() -> None:
+    assert complexity("$") == 0
+    assert complexity("$.a.b[0]") == 3  # noqa: PLR2004
+
+
+def test_complexity_ordering() -> None:
+    assert complexity("$.a.b") < complexity("$.a.*")
+    assert complexity("$.a.*") < complexity("$..a")
+    assert complexity("$..a") < complexity("$..a..b")
+    assert complexity("$.a[1:3]") < complexity("$.a[1:]")
+    assert complexity("$.a[?@.b]") < complexity("$.a[?@..b]")
+    assert complexity("$.*") < complexity("$.*.*")
+
+
+def test_max_descendant_segments() -> None:
+    path = "$..a[?@..b]"
+    parse_jsonpath_query(path, max_descendant_segments=2)
+    with pytest.raises(LiquidSyntaxError, match="descendant segments"):
+        parse_jsonpath_query(path, max_descendant_segments=1)
+
+
+def test_max_filter_depth() -> None:
+    path = "$.a[?@.b[?@.c[?@.d]]]"
+    assert parse_jsonpath_query(path).complexity() > 0
+    parse_jsonpath_query(path, max_filter_depth=3)
+    with pytest.raises(LiquidSyntaxError, match="nested 3 deep"):
+        parse_jsonpath_query(path, max_filter_depth=2)
+
+
+def test_filter_depth_counts_function_arguments() -> None:
+    path = "$.a[?count(@.b[?@.c]) > 1]"
+    with pytest.raises(LiquidSyntaxError):
+        parse_jsonpath_query(path, max_filter_depth=1)
@@ -0,0 +1,29 @@
+"""Test nodes returned by evaluating queries."""
+
+from _liquid2 import parse_jsonpath_query
+
+DATA = {"a": [{"b": 1}, {"b": 2}], "it's": {"c\n": True}}
+
+
+def test_find_returns_values_and_normalized_paths() -> None:
+    nodes = parse_jsonpath_query("$.a[*].b").find(DATA)
+    assert [node.value() for node in nodes] == [1, 2]
+    assert [node.path() for node in nodes] == ["$['a'][0]['b']", "$['a'][1]['b']"]
+
+
+def test_node_location() -> None:
+    (node,) = parse_jsonpath_query("$.a[-1]").find(DATA)
+    assert node.location == ["a", 1]
+    assert node.value() == {"b": 2}
+    assert str(node) == "$['a'][1]"
+
+
+def test_paths_are_escaped() -> None:
+    (node,) = parse_jsonpath_query("$..*[?@ == true]").find(DATA)
+    assert node.path() == "$['it\\'s']['c\\n']"
+    (same,) = parse_jsonpath_query(node.path()).find(DATA)
+    assert same.value() is True
+
+
+def test_no_matches() -> None:
+    assert parse_jsonpath_query("$.nosuchthing").find(DATA) == []
@@ -0,0 +1,66 @@
+"""Test I-Regexp validation and evaluation of match() and search()."""
+
+import pytest
+from _liquid2 import LiquidTypeError
+from _liquid2 import parse_jsonpath_query
+
+DATA = ["abc", "a\nc", "a-c", "a^c", "x1", "é", "ab"]
+
+INVALID_PATTERNS = [
+    r"a{2,1}",
+    r"a**",
+    r"a*?",
+    r"(?:a)",
+    r"(?=a)",
+    r"\d",
+    r"\w+",
+    r"(a)\1",
+    r"[]",
+    r"[a",
+    r"(a",
+    r"a)",
+    r"[b-a]",
+    r"\p{Xx}",
+    r"a{,2}",
+]
+
+
+@pytest.mark.parametrize("pattern", INVALID_PATTERNS)
+def test_invalid_literal_patterns_fail_at_parse_time(pattern: str) -> None:
+    query = f"$[?match(@, '{pattern}')]".replace("\\", "\\\\")
+    with pytest.raises(LiquidTypeError):
+        parse_jsonpath_query(query)
+
+
+def test_invalid_pattern_from_data_is_false() -> None:
+    query = parse_jsonpath_query("$.items[?search(@, $.pattern)]")
+    assert query.find({"items": ["a"], "pattern": "a**"}) == []
+
+
+def test_dot_does_not_match_line_terminators() -> None:
+    nodes = parse_jsonpath_query("$[?match(@, 'a.c')]").find(DATA)
+    assert [node.value() for node in nodes] == ["abc", "a-c", "a^c"]
+
+
+def test_caret_is_a_literal() -> None:
+    nodes = parse_jsonpath_query("$[?search(@, '^c')]").find(DATA)
+    assert [node.value() for node in nodes] == ["a^c"]
+
+
+def test_match_is_anchored() -> None:
+    nodes = parse_jsonpath_query("$[?match(@, 'a.')]").find(DATA)
+    assert [node.value() for node in nodes] == ["ab"]
+
+
+def test_character_classes_and_categories() -> None:
+    nodes = parse_jsonpath_query("$[?match(@, '[a-z]\\\\p{Nd}')]").find(DATA)
+    assert [node.value() for node in nodes] == ["x1"]
+    nodes = parse_jsonpath_query("$[?match(@, '\\\\p{L}')]").find(DATA)
+    assert [node.value() for node in nodes] == ["é"]
+    nodes = parse_jsonpath_query("$[?match(@, 'a[-^]c')]").find(DATA)
+    assert [node.value() for node in nodes] == ["a-c", "a^c"]
+
+
+def test_range_quantifiers() -> None:
+    nodes = parse_jsonpath_query("$[?match(@, '[a-c]{2,}')]").find(DATA)
+    assert [node.value() for node in nodes] == ["abc", "ab"]
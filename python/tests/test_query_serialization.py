@@ -0,0 +1,28 @@
+"""Test formatting queries in canonical and shorthand notation."""
+
+import pytest
+from _liquid2 import parse_jsonpath_query
+
+
+@pytest.mark.parametrize(
+    ("path", "canonical", "shorthand"),
+    [
+        ("$.a.b[1]", "$['a']['b'][1]", "$.a.b[1]"),
+        ("$['a b'].c", "$['a b']['c']", "$['a b'].c"),
+        ("$.a-b", "$['a-b']", "$['a-b']"),
+        ("$.a[*]..b..*", "$['a'][*]..['b']..[*]", "$.a.*..b..*"),
+        ("$['a','b'][0:2]", "$['a', 'b'][0:2:1]", "$['a', 'b'][0:2:1]"),
+        ("$[?@.a == 'x'].b", "$[?@['a'] == \"x\"]['b']", "$[?@.a == \"x\"].b"),
+        ("$[\"it's\"]", "$['it\\'s']", "$['it\\'s']"),
+        ("$['\\n']", "$['\\n']", "$['\\n']"),
+    ],
+)
+def test_serialize_query(path: str, canonical: str, shorthand: str) -> None:
+    query = parse_jsonpath_query(path)
+    assert query.canonical() == canonical
+    assert query.to_shorthand() == shorthand
+
+
+def test_canonical_queries_round_trip() -> None:
+    query = parse_jsonpath_query("$['a\\'b\\\\c'][?@['x\"y'] == \"\\\"\"]")
+    assert parse_jsonpath_query(query.canonical()).canonical() == query.canonical()
@@ -0,0 +1,67 @@
+"""Test validation of range literal arguments."""
+
+import pytest
+from _liquid2 import LiquidTypeError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import tokenize
+
+
+def test_integer_and_variable_arguments() -> None:
+    source = "{% for x in (1..n) %}{{ x }}{% endfor %}"
+    assert render(source, {"n": 3}) == "123"
+
+
+def test_negative_endpoints() -> None:
+    source = "{% for x in (-3..-1) %}{{ x }},{% endfor %}"
+    (tag,) = parse(source).nodes
+    assert isinstance(tag, Node.ForTag)
+    assert isinstance(tag.iterable, Primitive.Range)
+    assert isinstance(tag.iterable.start, Primitive.Integer)
+    assert tag.iterable.start.value == -3  # noqa: PLR2004
+    assert str(tag) == source
+    assert render(source) == "-3,-2,-1,"
+
+
+def test_query_endpoints() -> None:
+    source = "{% for x in (a.b..items[0]) %}{{ x }}{% endfor %}"
+    assert render(source, {"a": {"b": 2}, "items": [4]}) == "234"
+    (tag,) = parse(source).nodes
+    assert isinstance(tag, Node.ForTag)
+    assert isinstance(tag.iterable, Primitive.Range)
+    assert isinstance(tag.iterable.start, Primitive.Query)
+    assert isinstance(tag.iterable.stop, Primitive.Query)
+    assert str(tag.iterable) == "(a.b..items[0])"
+    assert str(parse(str(tag))) == str(tag)
+
+
+def test_string_arguments_containing_integers() -> None:
+    source = "{% for x in ('1'..\"3\") %}{{ x }}{% endfor %}"
+    assert render(source) == "123"
+
+
+def test_string_arguments_must_contain_integers() -> None:
+    with pytest.raises(LiquidTypeError, match="found 'a'"):
+        parse("{% for x in ('a'..3) %}{% endfor %}")
+
+
+def test_float_arguments_are_rejected() -> None:
+    with pytest.raises(LiquidTypeError, match="found 1.5"):
+        tokenize("{% for x in (1.5..3) %}{% endfor %}")
+
+
+def test_float_steps_are_rejected() -> None:
+    with pytest.raises(LiquidTypeError, match="found 0.5"):
+        parse("{{ (1..3..0.5) }}")
+
+
+def test_lax_ranges_truncate_floats() -> None:
+    template = parse("{% for x in (1.5..3.9) %}{{ x }}{% endfor %}", lax_ranges=True)
+    assert str(template) == "{% for x in (1..3) %}{{ x }}{% endfor %}"
+
+
+def test_lax_ranges_still_reject_strings() -> None:
+    with pytest.raises(LiquidTypeError):
+        parse("{% for x in ('1.5'..3) %}{% endfor %}", lax_ranges=True)
@@ -0,0 +1,74 @@
+"""Test range literal expressions."""
+
+import operator
+from dataclasses import dataclass
+
+import pytest
+from liquid2 import Environment
+from liquid2.builtin import FilteredExpression
+from liquid2.builtin.output import OutputNode
+
+
+@dataclass
+class Case:
+    """Test case helper."""
+
+    name: str
+    source: str
+    want: str
+
+
+TEST_CASES = [
+    Case(
+        name="range without a step",
+        source="{{ (1..5) | join: ',' }}",
+        want="1,2,3,4,5",
+    ),
+    Case(
+        name="range with a step",
+        source="{{ (1..10..3) | join: ',' }}",
+        want="1,4,7,10",
+    ),
+    Case(
+        name="step does not land on stop",
+        source="{{ (1..10..4) | join: ',' }}",
+        want="1,5,9",
+    ),
+    Case(
+        name="step from a variable",
+        source="{% assign s = 2 %}{{ (0..6 .. s) | join: ',' }}",
+        want="0,2,4,6",
+    ),
+    Case(
+        name="zero step is an empty range",
+        source="{{ (1..5..0) | join: ',' }}",
+        want="",
+    ),
+    Case(
+        name="negative step is an empty range",
+        source="{{ (1..5..-1) | join: ',' }}",
+        want="",
+    ),
+    Case(
+        name="for loop over a stepped range",
+        source="{% for i in (2..8..2) %}{{ i }} {% endfor %}",
+        want="2 4 6 8 ",
+    ),
+]
+
+
+@pytest.mark.parametrize("case", TEST_CASES, ids=operator.attrgetter("name"))
+def test_range_literals(case: Case) -> None:
+    """Test that range literals render."""
+    env = Environment()
+    assert env.from_string(case.source).render() == case.want
+
+
+def test_range_literal_string() -> None:
+    """Test that stepped ranges are written back with their step."""
+    env = Environment()
+    template = env.from_string("{{ (1..10..3) }}")
+    node = template.nodes[0]
+    assert isinstance(node, OutputNode)
+    assert isinstance(node.expression, FilteredExpression)
+    assert str(node.expression.left) == "(1..10..3)"
@@ -0,0 +1,53 @@
+"""Test tokenizing raw tags."""
+
+from _liquid2 import Markup
+from _liquid2 import iter_tokens
+from _liquid2 import tokenize
+from _liquid2 import tokenize_lenient
+
+SOURCE = "a {%- raw %}{{ x }}{% endraw -%} b"
+
+
+def test_raw_spans_are_not_recorded_by_default() -> None:
+    raw = tokenize(SOURCE)[1]
+    assert isinstance(raw, Markup.Raw)
+    assert raw.text == "{{ x }}"
+    assert raw.spans is None
+
+
+def test_raw_spans() -> None:
+    raw = tokenize(SOURCE, raw_spans=True)[1]
+    assert isinstance(raw, Markup.Raw)
+    assert raw.spans is not None
+    assert raw.span == (2, 32)
+
+    open_start, open_end = raw.spans.open
+    content_start, content_end = raw.spans.content
+    close_start, close_end = raw.spans.close
+    assert SOURCE[open_start:open_end] == "{%- raw %}"
+    assert SOURCE[content_start:content_end] == "{{ x }}"
+    assert SOURCE[close_start:close_end] == "{% endraw -%}"
+
+
+def test_empty_raw_block() -> None:
+    source = "{% raw %}{% endraw %}"
+    raw = tokenize(source, raw_spans=True)[0]
+    assert isinstance(raw, Markup.Raw)
+    assert raw.spans is not None
+    assert raw.spans.content == (9, 9)
+
+
+def test_lenient_raw_spans() -> None:
+    source = "{{ ! }}" + SOURCE
+    markup, errors = tokenize_lenient(source, raw_spans=True)
+    assert len(errors) == 1
+    raw = markup[2]
+    assert isinstance(raw, Markup.Raw)
+    assert raw.spans is not None
+    start, end = raw.spans.content
+    assert source[start:end] == "{{ x }}"
+
+
+def test_raw_block_round_trip() -> None:
+    (_, raw, _, _) = iter_tokens(SOURCE)
+    assert str(raw) == "{%- raw %}{{ x }}{% endraw -%}"
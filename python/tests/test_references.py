@@ -0,0 +1,114 @@
+"""Test finding references to a variable, for renaming."""
+
+from _liquid2 import Reference
+from _liquid2 import ReferenceKind
+from _liquid2 import parse
+
+
+def texts(source: str, references: list[Reference]) -> list[str]:
+    """Return the source text at each of _references_."""
+    return [source[slice(*reference.span)] for reference in references]
+
+
+def test_reads_and_assignments() -> None:
+    source = "{% assign x = x | plus: 1 %}{{ x.y }}{{ y.x }}"
+    references = parse(source).references("x")
+    assert texts(source, references) == ["x", "x", "x"]
+    assert [r.kind for r in references] == [
+        ReferenceKind.Assign,
+        ReferenceKind.Read,
+        ReferenceKind.Read,
+    ]
+    assert [r.span for r in references] == [(10, 11), (14, 15), (31, 32)]
+
+
+def test_capture_increment_and_decrement() -> None:
+    source = (
+        "{% capture 'x' %}{{ x }}{% endcapture %}"
+        "{% increment x %}{% decrement x %}"
+    )
+    references = parse(source).references("x")
+    assert texts(source, references) == ["x", "x", "x", "x"]
+    assert [r.kind for r in references] == [
+        ReferenceKind.Capture,
+        ReferenceKind.Read,
+        ReferenceKind.Increment,
+        ReferenceKind.Decrement,
+    ]
+
+
+def test_queries_in_brackets_and_arguments() -> None:
+    source = "{{ a[x] }}{{ ['x'] }}{{ b | default: x }}{% if x %}{% endif %}"
+    references = parse(source).references("x")
+    assert texts(source, references) == ["x", "x", "x", "x"]
+
+
+def test_loop_variables_are_not_global() -> None:
+    source = "{{ item }}{% for item in items %}{{ item }}{% endfor %}{{ item }}"
+    references = parse(source).references("item")
+    assert [r.span for r in references] == [(3, 7), (58, 62)]
+
+
+def test_loop_variable_references() -> None:
+    source = "{{ item }}{% for item in items %}{{ item.title }}{% endfor %}"
+    references = parse(source).references_at(source.index("item.title"))
+    assert texts(source, references) == ["item", "item"]
+    assert [r.kind for r in references] == [
+        ReferenceKind.LoopVariable,
+        ReferenceKind.Read,
+    ]
+    assert references[0].span == (17, 21)
+
+
+def test_key_value_loop_variables() -> None:
+    source = "{% for k, v in m %}{{ k }}={{ v }}{% endfor %}"
+    template = parse(source)
+    assert texts(source, template.references_at(source.index("v }}"))) == [
+        "v",
+        "v",
+    ]
+    assert template.references_at(source.index("k,"))[1].span == (22, 23)
+
+
+def test_nested_loops_shadow() -> None:
+    source = "{% for x in a %}{% for x in x %}{{ x }}{% endfor %}{{ x }}{% endfor %}"
+    template = parse(source)
+    inner = template.references_at(source.index("{{ x }}") + 3)
+    assert [r.span for r in inner] == [(23, 24), (35, 36)]
+    outer = template.references_at(7)
+    assert [r.span for r in outer] == [(7, 8), (28, 29), (54, 55)]
+
+
+def test_forloop_is_local() -> None:
+    source = "{% for x in a %}{{ forloop.index }}{% endfor %}{{ forloop }}"
+    assert [r.span for r in parse(source).references("forloop")] == [(50, 57)]
+
+
+def test_assign_in_loop_is_global() -> None:
+    source = "{% for x in a %}{% assign x = 1 %}{% endfor %}{{ x }}"
+    references = parse(source).references("x")
+    assert [r.kind for r in references] == [ReferenceKind.Assign, ReferenceKind.Read]
+
+
+def test_tablerow_variable() -> None:
+    source = "{% tablerow cell in cells %}{{ cell }}{% endtablerow %}"
+    references = parse(source).references_at(source.index("cell }}"))
+    assert texts(source, references) == ["cell", "cell"]
+    assert references[0].kind == ReferenceKind.LoopVariable
+
+
+def test_macro_parameters_are_local() -> None:
+    source = "{% macro 'm' x %}{{ x }}{% endmacro %}{{ x }}"
+    template = parse(source)
+    assert [r.span for r in template.references("x")] == [(41, 42)]
+    assert [r.span for r in template.references_at(20)] == [(20, 21)]
+
+
+def test_no_reference_at_offset() -> None:
+    assert parse("hello {{ 'x' }}").references_at(2) == []
+
+
+def test_references_in_liquid_tag() -> None:
+    source = "{% liquid\n  assign x = 1\n  echo x\n%}"
+    references = parse(source).references("x")
+    assert texts(source, references) == ["x", "x"]
@@ -0,0 +1,105 @@
+"""Test registering custom JSONPath function extensions."""
+
+import pytest
+from _liquid2 import LiquidExtensionError
+from _liquid2 import LiquidTypeError
+from _liquid2 import parse_jsonpath_query
+from _liquid2 import parse_query
+from _liquid2 import register_function
+from _liquid2 import render
+from liquid2.query import DEFAULT_ENV
+from liquid2.query import find
+from liquid2.query.function_extensions import ExpressionType
+from liquid2.query.function_extensions import FilterFunction
+
+
+class IsEven(FilterFunction):
+    """A test function extension."""
+
+    arg_types = [ExpressionType.VALUE]
+    return_type = ExpressionType.LOGICAL
+
+    def __call__(self, obj: object) -> bool:
+        """Return `True` if _obj_ is an even integer."""
+        return isinstance(obj, int) and obj % 2 == 0
+
+
+DEFAULT_ENV.add_function_extension("is_even", IsEven())
+register_function("first_of", ["nodes", "value"], "value")
+register_function(
+    "starts_with",
+    ["value", "value"],
+    "logical",
+    lambda s, prefix: isinstance(s, str) and s.startswith(prefix),
+)
+register_function("upper", ["value"], "value", lambda s: s.upper())
+register_function(
+    "evens", ["nodes"], "nodes", lambda values: [v for v in values if v % 2 == 0]
+)
+register_function("broken", ["value"], "logical", lambda _: 1 / 0)
+
+
+def test_registered_function() -> None:
+    data = {"a": [1, 2, 3, 4]}
+    assert find("$.a[?is_even(@)]", data).values() == [2, 4]
+
+
+def test_registered_function_arity() -> None:
+    with pytest.raises(LiquidTypeError):
+        parse_query("$.a[?is_even(@, 1)]")
+
+
+def test_registered_function_result_must_be_well_typed() -> None:
+    with pytest.raises(LiquidTypeError):
+        parse_query("$.a[?is_even(@) == true]")
+
+
+def test_registered_function_param_types() -> None:
+    parse_query("$.a[?first_of(@.*, 1) == 1]")
+    with pytest.raises(LiquidTypeError):
+        parse_query("$.a[?first_of(@.*, @.*) == 1]")
+
+
+def test_unknown_expression_type() -> None:
+    with pytest.raises(LiquidTypeError, match="unknown expression type"):
+        register_function("nope", ["string"], "value")
+
+
+def test_evaluate_registered_function() -> None:
+    query = parse_jsonpath_query("$.a[?starts_with(@, 'ba')]")
+    nodes = query.find({"a": ["foo", "bar", "baz", 1]})
+    assert [node.value() for node in nodes] == ["bar", "baz"]
+
+
+def test_evaluate_registered_value_function() -> None:
+    query = parse_jsonpath_query("$.a[?upper(@.b) == 'X']")
+    nodes = query.find({"a": [{"b": "x"}, {"b": "y"}]})
+    assert [node.path() for node in nodes] == ["$['a'][0]"]
+
+
+def test_evaluate_registered_nodes_function() -> None:
+    query = parse_jsonpath_query("$[?count(evens(@.*)) == 2]")
+    nodes = query.find([[1, 2, 4], [2, 3]])
+    assert [node.value() for node in nodes] == [[1, 2, 4]]
+
+
+def test_exceptions_in_registered_functions_are_raised() -> None:
+    with pytest.raises(LiquidExtensionError, match="ZeroDivisionError"):
+        parse_jsonpath_query("$[?broken(@)]").find([1, 2])
+
+
+def test_exceptions_in_registered_functions_fail_rendering() -> None:
+    with pytest.raises(LiquidExtensionError, match=r"broken\(\) raised"):
+        render("{{ a[?broken(@)] }}", {"a": [1, 2]})
+
+
+def test_registered_implementation_replaces_standard_function() -> None:
+    query = parse_jsonpath_query("$[?length(@) == 1]")
+    assert [node.value() for node in query.find(["a", "bb"])] == ["a"]
+    register_function("length", ["value"], "value", lambda _: 1)
+    try:
+        query = parse_jsonpath_query("$[?length(@) == 1]")
+        assert [node.value() for node in query.find(["a", "bb"])] == ["a", "bb"]
+    finally:
+        register_function("length", ["value"], "value")
+    assert [node.value() for node in query.find(["a", "bb"])] == ["a"]
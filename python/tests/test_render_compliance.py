@@ -0,0 +1,64 @@
+"""Test the Rust renderer against the compliance test suite."""
+
+import json
+import operator
+from dataclasses import dataclass
+from dataclasses import field
+from typing import Any
+
+import pytest
+from _liquid2 import PyLiquidError
+from _liquid2 import render
+
+
+@dataclass
+class Case:
+    """Test helper class."""
+
+    name: str
+    template: str
+    data: dict[str, Any] = field(default_factory=dict)
+    templates: dict[str, str] | None = None
+    result: str | None = None
+    invalid: bool | None = None
+    tags: list[str] = field(default_factory=list)
+
+
+FILENAME = "python/tests/liquid2-compliance-test-suite/cts.json"
+
+# Cycle group names can be any variable, including a path.
+SKIP = {
+    "tags, cycle, name is a path",
+}
+
+
+def cases() -> list[Case]:
+    with open(FILENAME, encoding="utf8") as fd:
+        data = json.load(fd)
+    # Filters are tested in test_rust_filters.py, and the Rust renderer
+    # doesn't support template inheritance.
+    return [
+        Case(**case)
+        for case in data["tests"]
+        if not case["name"].startswith(("filters, ", "tags, extends, "))
+        and case["name"] not in SKIP
+    ]
+
+
+def valid_cases() -> list[Case]:
+    return [case for case in cases() if not case.invalid]
+
+
+def invalid_cases() -> list[Case]:
+    return [case for case in cases() if case.invalid]
+
+
+@pytest.mark.parametrize("case", valid_cases(), ids=operator.attrgetter("name"))
+def test_render(case: Case) -> None:
+    assert render(case.template, case.data, templates=case.templates) == case.result
+
+
+@pytest.mark.parametrize("case", invalid_cases(), ids=operator.attrgetter("name"))
+def test_invalid_render(case: Case) -> None:
+    with pytest.raises(PyLiquidError):
+        render(case.template, case.data, templates=case.templates)
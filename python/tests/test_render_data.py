@@ -0,0 +1,37 @@
+"""Test converting Python template data for the built-in renderer."""
+
+from dataclasses import dataclass
+
+from _liquid2 import render
+
+
+@dataclass
+class User:
+    """A plain Python object, read lazily by the renderer."""
+
+    name: str
+
+    def __str__(self) -> str:
+        return f"User({self.name})"
+
+
+def test_primitives() -> None:
+    data = {"a": None, "b": True, "c": 42, "d": 1.5, "e": "x"}
+    assert render("{{ a }}|{{ b }}|{{ c }}|{{ d }}|{{ e }}", data) == "|true|42|1.5|x"
+
+
+def test_lists_tuples_and_dicts() -> None:
+    data = {"a": [1, 2], "b": (3, 4), "c": {"d": {"e": "f"}}}
+    assert render("{{ a | join: ',' }} {{ b[1] }} {{ c.d.e }}", data) == "1,2 4 f"
+
+
+def test_no_data() -> None:
+    assert render("{{ a }}b") == "b"
+
+
+def test_integers_equal_floats() -> None:
+    assert render("{% if a == 1.0 %}yes{% endif %}", {"a": 1}) == "yes"
+
+
+def test_other_objects_are_output_as_strings() -> None:
+    assert render("{{ user }}", {"user": User("Sue")}) == "User(Sue)"
@@ -0,0 +1,44 @@
+"""Test concise `repr()` of markup, tokens, queries and syntax trees."""
+
+from _liquid2 import Markup
+from _liquid2 import Token
+from _liquid2 import parse
+from _liquid2 import parse_query
+from _liquid2 import tokenize
+
+
+def test_markup_repr() -> None:
+    assert repr(tokenize("Hi {{ a }}")) == (
+        "[Markup.Content('Hi ', span=(0, 3)), "
+        "Markup.Output('{{ a }}', span=(3, 10)), Markup.EOI()]"
+    )
+
+
+def test_token_repr() -> None:
+    tag, _ = tokenize("{% box a, 'b' %}")
+    assert isinstance(tag, Markup.Tag)
+    assert repr(tag.expression) == (
+        "[Token.Word('a', span=(7, 8)), Token.Comma(',', span=(8, 9)), "
+        "Token.StringLiteral(\"'b'\", span=(11, 12))]"
+    )
+    assert repr(Token.True_(span=(0, 4))) == "Token.True_('true', span=(0, 4))"
+
+
+def test_query_repr() -> None:
+    query = parse_query("$.a[?@.b > 1]")
+    assert repr(query) == "Query(\"$['a'][?@['b'] > 1]\")"
+    assert repr(query.segments[0]) == "Segment.Child(\"['a']\")"
+    assert repr(query.segments[0].selectors[0]) == "Selector.Name(\"'a'\")"
+
+
+def test_syntax_tree_repr() -> None:
+    template = parse("{% if x %}a{% else %}b{% endif %}{{ y | upcase }}")
+    assert repr(template) == (
+        "Template('{% if x %}a{% else %}b{% endif %}{{ y | upcase }}')"
+    )
+    if_tag, output = template.nodes
+    assert repr(if_tag) == (
+        "Node.IfTag('{% if x %}a{% else %}b{% endif %}', span=(0, 33))"
+    )
+    assert repr(if_tag.default) == "ElseTag('{% else %}b', span=(11, 21))"
+    assert repr(output.expression) == "FilteredExpression('y | upcase', span=(36, 46))"
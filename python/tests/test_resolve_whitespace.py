@@ -0,0 +1,65 @@
+"""Test whitespace resolution of template text."""
+
+import pytest
+from _liquid2 import Markup
+from _liquid2 import SmartTrim
+from _liquid2 import Whitespace
+from _liquid2 import render
+from _liquid2 import resolve_whitespace
+
+CASES = [
+    # (source, trim, smart, expected)
+    ("a {{ x }} b", Whitespace.Plus, SmartTrim.Newlines, "a  b"),
+    ("a {{- x -}} b", Whitespace.Plus, SmartTrim.Newlines, "ab"),
+    ("a \n{{~ x ~}}\n b", Whitespace.Plus, SmartTrim.Newlines, "a  b"),
+    ("a \n\n{{~ x ~}}\n\n b", Whitespace.Plus, SmartTrim.Collapse, "a\n\nb"),
+    ("a  {{~ x ~}}  b", Whitespace.Plus, SmartTrim.Collapse, "a    b"),
+    ("a \n{{~ x ~}}\n b", Whitespace.Plus, SmartTrim.Shopify, "ab"),
+    ("a \n{{ x }}\n b", Whitespace.Minus, SmartTrim.Newlines, "ab"),
+    ("a \n{{ x }}\n b", Whitespace.Smart, SmartTrim.Newlines, "a  b"),
+    ("a \n{{ x }}\n b", Whitespace.Smart, SmartTrim.Collapse, "a\n\nb"),
+    ("a \n{{+ x +}}\n b", Whitespace.Minus, SmartTrim.Newlines, "a \n\n b"),
+    (
+        " \n {%~ if true ~%} \n {%~ endif ~%}",
+        Whitespace.Plus,
+        SmartTrim.Collapse,
+        "\n\n",
+    ),
+]
+
+
+def content(markup: list[Markup]) -> str:
+    return "".join(m.text for m in markup if isinstance(m, Markup.Content))
+
+
+@pytest.mark.parametrize(("source", "trim", "smart", "expected"), CASES)
+def test_resolve_whitespace(
+    source: str, trim: Whitespace, smart: SmartTrim, expected: str
+) -> None:
+    markup = resolve_whitespace(source, trim=trim, smart=smart)
+    assert content(markup) == expected
+
+
+@pytest.mark.parametrize(("source", "trim", "smart", "expected"), CASES)
+def test_render_agrees(
+    source: str, trim: Whitespace, smart: SmartTrim, expected: str
+) -> None:
+    assert render(source, trim=trim, smart=smart) == expected
+
+
+def test_spans_are_unchanged() -> None:
+    source = "a \n{{- x -}}\n b"
+    resolved = resolve_whitespace(source)
+    assert [m.span for m in resolved[:-1]] == [(0, 3), (3, 12), (12, 15)]
+    assert [m.text for m in resolved if isinstance(m, Markup.Content)] == ["a", "b"]
+
+
+def test_text_after_raw() -> None:
+    source = "{% raw %} a {% endraw ~%}\n b"
+    assert content(resolve_whitespace(source)) == " b"
+    assert render(source) == " a  b"
+
+
+def test_default_policy_is_plus() -> None:
+    source = "a \n{{ x }}\n b"
+    assert content(resolve_whitespace(source)) == "a \n\n b"
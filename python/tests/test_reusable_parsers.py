@@ -0,0 +1,144 @@
+"""Test lexers and parsers that are configured once and reused."""
+
+import json
+from concurrent.futures import ThreadPoolExecutor
+
+import pytest
+from _liquid2 import IdentifierPolicy
+from _liquid2 import Lexer
+from _liquid2 import LiquidNameError
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import Parser
+from _liquid2 import QueryExtensions
+from _liquid2 import parse
+from _liquid2 import parse_many
+from _liquid2 import parse_query
+from _liquid2 import register_function
+from _liquid2 import tokenize
+
+SOURCE = "Hello, {{ you | upcase }}!{% if a %}{% raw %}{{ b }}{% endraw %}{% endif %}"
+
+
+def test_lexer_tokenize() -> None:
+    assert Lexer().tokenize(SOURCE) == tokenize(SOURCE)
+
+
+def test_lexer_options() -> None:
+    lexer = Lexer(raw_spans=True, identifiers=IdentifierPolicy.Ascii)
+    raw = lexer.tokenize(SOURCE)[4]
+    assert isinstance(raw, Markup.Raw)
+    assert raw.spans is not None
+
+    with pytest.raises(LiquidNameError):
+        lexer.tokenize("{{ café }}")
+
+
+def test_lexer_source_name() -> None:
+    with pytest.raises(LiquidSyntaxError, match="a.liquid"):
+        Lexer().tokenize("{{ a | }", source_name="a.liquid")
+
+
+def test_lexer_iter_tokens() -> None:
+    assert list(Lexer().iter_tokens(SOURCE)) == tokenize(SOURCE)
+
+
+def test_lexer_tokenize_many() -> None:
+    assert Lexer().tokenize_many([SOURCE, "a"]) == [tokenize(SOURCE), tokenize("a")]
+
+
+def test_lexer_tokenize_lenient() -> None:
+    markup, errors = Lexer().tokenize_lenient("x {{ y")
+    assert isinstance(markup[1], Markup.Error)
+    assert len(errors) == 1
+
+
+def test_lexer_query_extensions() -> None:
+    lexer = Lexer(extensions=QueryExtensions.strict())
+    assert lexer.parse_query("$['a']") == parse_query("$.a")
+    with pytest.raises(LiquidSyntaxError):
+        lexer.parse_query("a.b")
+
+
+def test_lexer_query_limits() -> None:
+    lexer = Lexer(max_descendant_segments=1)
+    lexer.parse_jsonpath_query("$..a")
+    with pytest.raises(LiquidSyntaxError):
+        lexer.parse_jsonpath_query("$..a..b")
+
+
+def test_parser_parse() -> None:
+    assert str(Parser().parse(SOURCE)) == str(parse(SOURCE))
+
+
+def test_parser_parse_many() -> None:
+    sources = [SOURCE, "{{ a }}", "b"]
+    templates = Parser().parse_many(sources)
+    assert [str(t) for t in templates] == [str(parse(s)) for s in sources]
+
+    with pytest.raises(LiquidSyntaxError, match="unknown tag 'foo'"):
+        Parser().parse_many(["a", "{% foo %}", "{% bar %}"])
+
+
+def test_parse_many() -> None:
+    sources = [SOURCE, "{% foo a %}"]
+    templates = parse_many(sources, common_tags=True)
+    assert [str(t) for t in templates] == [str(parse(s, common_tags=True)) for s in sources]
+
+    with pytest.raises(LiquidSyntaxError):
+        parse_many(sources)
+
+
+def test_parser_tag_aliases() -> None:
+    source = "{% if a %}x{% elseif b %}y{% endif %}"
+    parser = Parser(tag_aliases={"elseif": "elsif"})
+    assert str(parser.parse(source)) == "{% if a %}x{% elsif b %}y{% endif %}"
+    assert parser.grammar().tag_aliases == {"elseif": "elsif"}
+
+    with pytest.raises(LiquidSyntaxError):
+        Parser().parse(source)
+
+
+def test_parser_options() -> None:
+    with pytest.raises(LiquidSyntaxError):
+        Parser().parse("{% foo a %}")
+    assert Parser(common_tags=True).parse("{% foo a %}")
+
+    with pytest.raises(LiquidSyntaxError):
+        Parser(max_depth=1).parse("{% if a %}{% if b %}{% endif %}{% endif %}")
+
+
+def test_parser_validate() -> None:
+    parser = Parser(strict_filters=False)
+    assert json.loads(parser.validate("{{ a | nosuchfilter }}")) == []
+    assert json.loads(parser.diagnose("{{ a | nosuchfilter }}")) == []
+    (diagnostic,) = json.loads(Parser().validate("{{ a | nosuchfilter }}"))
+    assert diagnostic["code"] == "type-error"
+
+
+def test_parser_expressions() -> None:
+    parser = Parser()
+    assert str(parser.parse_filtered_expression("a | upcase")) == "a | upcase"
+    assert str(parser.parse_boolean_expression("a and b")) == "a and b"
+    assert len(parser.parse_keyword_arguments("a: 1, b: x")) == 2  # noqa: PLR2004
+
+
+def test_parser_shared_between_threads() -> None:
+    parser = Parser()
+    sources = [f"{{{{ a{i} }}}}{{% if b %}}{i}{{% endif %}}" for i in range(100)]
+    with ThreadPoolExecutor(max_workers=4) as executor:
+        templates = list(executor.map(parser.parse, sources))
+    assert [str(t) for t in templates] == [str(parse(s)) for s in sources]
+
+
+def test_registered_functions() -> None:
+    path = "$.a[?reusable_parsers_fn(@)]"
+    lexer = Lexer()
+    with pytest.raises(LiquidNameError):
+        parse_query(path)
+
+    register_function("reusable_parsers_fn", ["value"], "logical")
+
+    assert parse_query(path) == Lexer().parse_query(path)
+    with pytest.raises(LiquidNameError):
+        lexer.parse_query(path)
@@ -0,0 +1,65 @@
+"""Test the Rust renderer's standard filters against the compliance test suite."""
+
+import json
+import operator
+from dataclasses import dataclass
+from dataclasses import field
+from typing import Any
+
+import pytest
+from _liquid2 import PyLiquidError
+from _liquid2 import render
+
+
+@dataclass
+class Case:
+    """Test helper class."""
+
+    name: str
+    template: str
+    data: dict[str, Any] = field(default_factory=dict)
+    templates: dict[str, str] | None = None
+    result: str | None = None
+    invalid: bool | None = None
+    tags: list[str] = field(default_factory=list)
+
+
+FILENAME = "python/tests/liquid2-compliance-test-suite/cts.json"
+
+# Invalid cases that rely on data the test suite doesn't include.
+SKIP = {
+    "filters, map, array containing a non object",
+    "filters, map, left value not an array",
+    "filters, sort, incompatible types",
+    "filters, sum, properties arguments with non-hash items",
+    "filters, where, left value is not an array",
+}
+
+
+def cases() -> list[Case]:
+    with open(FILENAME, encoding="utf8") as fd:
+        data = json.load(fd)
+    return [
+        Case(**case)
+        for case in data["tests"]
+        if case["name"].startswith("filters, ") and case["name"] not in SKIP
+    ]
+
+
+def valid_cases() -> list[Case]:
+    return [case for case in cases() if not case.invalid]
+
+
+def invalid_cases() -> list[Case]:
+    return [case for case in cases() if case.invalid]
+
+
+@pytest.mark.parametrize("case", valid_cases(), ids=operator.attrgetter("name"))
+def test_filters(case: Case) -> None:
+    assert render(case.template, case.data) == case.result
+
+
+@pytest.mark.parametrize("case", invalid_cases(), ids=operator.attrgetter("name"))
+def test_invalid_filters(case: Case) -> None:
+    with pytest.raises(PyLiquidError):
+        render(case.template, case.data)
@@ -0,0 +1,168 @@
+"""Test the scope-aware model of where variables get their values."""
+
+from _liquid2 import Definedness
+from _liquid2 import NameUse
+from _liquid2 import parse
+
+
+def uses(source: str) -> list[tuple[str, Definedness]]:
+    """Return the name and definedness of each variable read by _source_."""
+    return [(u.name, u.definedness) for u in parse(source).semantic_model().uses]
+
+
+def undefined(source: str) -> list[NameUse]:
+    """Return reads of possibly undefined variables in _source_."""
+    return parse(source).semantic_model().possibly_undefined()
+
+
+def test_globals_and_assignments() -> None:
+    source = (
+        "{{ a }}{% assign b = a %}{{ b }}"
+        "{% capture c %}{{ b }}{% endcapture %}{{ c }}"
+    )
+    assert uses(source) == [
+        ("a", Definedness.Global),
+        ("a", Definedness.Global),
+        ("b", Definedness.Assigned),
+        ("b", Definedness.Assigned),
+        ("c", Definedness.Assigned),
+    ]
+
+
+def test_assign_reads_before_assigning() -> None:
+    assert uses("{% assign x = x | plus: 1 %}") == [("x", Definedness.Global)]
+
+
+def test_assigned_in_one_branch() -> None:
+    source = "{% if a %}{% assign x = 1 %}{% endif %}{{ x }}"
+    (name_use,) = undefined(source)
+    assert name_use.name == "x"
+    assert name_use.span == (42, 43)
+    assert name_use.definitions == [(20, 21)]
+
+
+def test_assigned_in_every_branch() -> None:
+    source = (
+        "{% if a %}{% assign x = 1 %}"
+        "{% elsif b %}{% assign x = 2 %}"
+        "{% else %}{% assign x = 3 %}{% endif %}{{ x }}"
+    )
+    assert undefined(source) == []
+    (name_use,) = parse(source).semantic_model().uses[-1:]
+    assert name_use.definedness == Definedness.Assigned
+    assert len(name_use.definitions) == 3
+
+
+def test_case_without_else() -> None:
+    source = (
+        "{% case a %}{% when 1 %}{% assign x = 1 %}"
+        "{% when 2 %}{% assign x = 2 %}{% endcase %}{{ x }}"
+    )
+    assert [u.name for u in undefined(source)] == ["x"]
+    default = "{% else %}{% assign x = 3 %}{% endcase %}"
+    source = source.replace("{% endcase %}", default)
+    assert undefined(source) == []
+
+
+def test_reassigned_after_branch() -> None:
+    source = "{% if a %}{% assign x = 1 %}{% endif %}{% assign x = 2 %}{{ x }}"
+    assert uses(source)[-1] == ("x", Definedness.Assigned)
+    assert parse(source).semantic_model().uses[-1].definitions == [(49, 50)]
+
+
+def test_loop_variables_are_local() -> None:
+    source = "{% for x in xs %}{{ x }}{{ forloop.index }}{% endfor %}{{ x }}"
+    assert uses(source) == [
+        ("xs", Definedness.Global),
+        ("x", Definedness.Local),
+        ("forloop", Definedness.Local),
+        ("x", Definedness.Global),
+    ]
+
+
+def test_assigned_in_loop_block() -> None:
+    source = "{% for x in xs %}{% assign y = x %}{% endfor %}{{ y }}"
+    assert [u.name for u in undefined(source)] == ["y"]
+
+
+def test_assigned_in_loop_and_else() -> None:
+    source = (
+        "{% for x in xs %}{% assign y = x %}"
+        "{% else %}{% assign y = 0 %}{% endfor %}{{ y }}"
+    )
+    assert undefined(source) == []
+
+
+def test_assigned_by_previous_iteration() -> None:
+    source = "{% for x in xs %}{{ y }}{% assign y = x %}{% endfor %}"
+    assert uses(source)[1] == ("y", Definedness.PossiblyUndefined)
+
+
+def test_assigned_after_break() -> None:
+    source = (
+        "{% for x in xs %}{% if x %}{% break %}{% endif %}"
+        "{% assign y = x %}{{ y }}{% endfor %}"
+    )
+    assert uses(source)[-1] == ("y", Definedness.Assigned)
+
+    source = (
+        "{% for x in xs %}{% if x %}{% break %}{% endif %}{% assign y = x %}"
+        "{% else %}{% assign y = 0 %}{% endfor %}{{ y }}"
+    )
+    assert uses(source)[-1] == ("y", Definedness.PossiblyUndefined)
+    source = source.replace("{% break %}", "{% assign y = 1 %}{% break %}")
+    assert uses(source)[-1] == ("y", Definedness.Assigned)
+
+
+def test_counters() -> None:
+    source = "{% increment n %}{{ n }}{% assign n = 5 %}{{ n }}"
+    assert uses(source) == [
+        ("n", Definedness.Counter),
+        ("n", Definedness.Assigned),
+    ]
+
+    source = "{% if a %}{% decrement n %}{% endif %}{{ n }}"
+    assert uses(source) == [
+        ("a", Definedness.Global),
+        ("n", Definedness.PossiblyUndefined),
+    ]
+
+
+def test_counter_under_conditional_assign() -> None:
+    source = "{% increment n %}{% if a %}{% assign n = 1 %}{% endif %}{{ n }}"
+    (name_use,) = parse(source).semantic_model().uses[-1:]
+    assert name_use.definedness == Definedness.Counter
+    assert len(name_use.definitions) == 2
+
+
+def test_macro_blocks_are_isolated() -> None:
+    source = (
+        "{% assign x = 1 %}"
+        "{% macro 'm' p %}{{ x }}{{ p }}{% assign y = 2 %}{% endmacro %}"
+        "{{ y }}"
+    )
+    assert uses(source) == [
+        ("x", Definedness.Global),
+        ("p", Definedness.Local),
+        ("y", Definedness.Global),
+    ]
+
+
+def test_render_arguments() -> None:
+    source = "{% if a %}{% assign x = 1 %}{% endif %}{% render 'p', y: x %}"
+    assert [u.name for u in undefined(source)] == ["x"]
+
+
+def test_lambda_parameters() -> None:
+    source = "{{ items | where: i => i.x }}"
+    assert uses(source) == [("items", Definedness.Global), ("i", Definedness.Local)]
+
+
+def test_use_at() -> None:
+    source = "{% if a %}{% assign x = 1 %}{% endif %}{{ x.y }}"
+    model = parse(source).semantic_model()
+    name_use = model.use_at(source.rindex("x"))
+    assert name_use is not None
+    assert name_use.definedness == Definedness.PossiblyUndefined
+    assert model.use_at(0) is None
+    assert len(model) == 2
@@ -0,0 +1,76 @@
+"""Test source maps from output back to template markup."""
+
+from _liquid2 import parse
+from _liquid2 import render
+from _liquid2 import render_with_source_map
+
+SOURCE = "Hello, {{ you }}!{% for x in (1..3) %}<{{ x }}>{% endfor %}"
+
+
+def text(source: str, span: tuple[int, int]) -> str:
+    return source[span[0] : span[1]]
+
+
+def test_output_is_unchanged() -> None:
+    output, _ = render_with_source_map(SOURCE, {"you": "World"})
+    assert output == render(SOURCE, {"you": "World"})
+
+
+def test_map_output_to_nodes() -> None:
+    output, source_map = render_with_source_map(SOURCE, {"you": "World"})
+    mapping = source_map.source_at(output.index("World"))
+    assert mapping is not None
+    assert text(output, mapping.output) == "World"
+    assert text(SOURCE, mapping.source) == "{{ you }}"
+    assert mapping.template is None
+
+
+def test_innermost_node() -> None:
+    output, source_map = render_with_source_map(SOURCE, {"you": "World"})
+    mapping = source_map.source_at(output.index("2"))
+    assert mapping is not None
+    assert text(SOURCE, mapping.source) == "{{ x }}"
+
+
+def test_block_tags_contain_their_blocks() -> None:
+    output, source_map = render_with_source_map(SOURCE, {"you": "World"})
+    span = (SOURCE.index("{% for"), len(SOURCE))
+    assert [text(output, s) for s in source_map.outputs(span)] == ["<1><2><3>"]
+
+
+def test_loops_write_many_ranges() -> None:
+    output, source_map = render_with_source_map(SOURCE, {"you": "World"})
+    start = SOURCE.index("{{ x }}")
+    outputs = source_map.outputs((start, start + len("{{ x }}")))
+    assert [text(output, s) for s in outputs] == ["1", "2", "3"]
+
+
+def test_nothing_written() -> None:
+    output, source_map = render_with_source_map("{% assign x = 1 %}{{ y }}")
+    assert output == ""
+    assert len(source_map) == 0
+    assert source_map.source_at(0) is None
+
+
+def test_captured_output_maps_to_output_tag() -> None:
+    source = "{% capture x %}abc{% endcapture %}{{ x }}"
+    output, source_map = render_with_source_map(source)
+    assert output == "abc"
+    assert [text(source, m.source) for m in source_map.mappings] == ["{{ x }}"]
+
+
+def test_macros() -> None:
+    source = "{% macro 'm' %}<{{ 1 }}>{% endmacro %}{% call 'm' %}"
+    output, source_map = render_with_source_map(source)
+    assert output == "<1>"
+    mapping = source_map.source_at(1)
+    assert mapping is not None
+    assert text(source, mapping.source) == "{{ 1 }}"
+
+
+def test_serialized_template() -> None:
+    template = parse(SOURCE)
+    output, source_map = template.to_string_with_source_map()
+    assert output == str(template)
+    for mapping in source_map.mappings:
+        assert text(output, mapping.output) == text(SOURCE, mapping.source)
@@ -0,0 +1,30 @@
+"""Test attributing syntax errors to named templates."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError as _LiquidSyntaxError
+from _liquid2 import parse_jsonpath_query
+from _liquid2 import tokenize
+from liquid2 import Environment
+from liquid2.exceptions import LiquidSyntaxError
+
+
+def test_tokenize_without_source_name() -> None:
+    with pytest.raises(_LiquidSyntaxError) as info:
+        tokenize("{% if x %}\n{{ }")
+    assert "at line" not in str(info.value)
+
+
+def test_tokenize_with_source_name() -> None:
+    with pytest.raises(_LiquidSyntaxError, match="in 'layout.liquid' at line 2$"):
+        tokenize("{% if x %}\n{{ }", source_name="layout.liquid")
+
+
+def test_parse_jsonpath_query_with_source_name() -> None:
+    with pytest.raises(_LiquidSyntaxError, match="in 'query' at line 1$"):
+        parse_jsonpath_query("$.a[", source_name="query")
+
+
+def test_from_string_with_name() -> None:
+    env = Environment()
+    with pytest.raises(LiquidSyntaxError, match="in 'card.liquid' at line 3$"):
+        env.from_string("a\nb\n{{ x }", name="card.liquid")
@@ -18,6 +18,7 @@ from liquid2.static_analysis import Span
 
 if TYPE_CHECKING:
     from liquid2 import Template
+    from liquid2.static_analysis import InheritanceAnalysis
     from liquid2.static_analysis import TemplateAnalysis
 
 
@@ -878,3 +879,121 @@ def test_analyze_super() -> None:
             "upcase": _Span(35, 41, template_name="base"),
         },
     )
+
+
+def test_analyze_inheritance() -> None:
+    loader = DictLoader(
+        {
+            "base": (
+                "{% block head required %}{% endblock %}"
+                "{% block content %}base{% endblock %}"
+                "{% block footer %}footer{% endblock %}"
+            ),
+            "other": (
+                "{% extends 'base' %}"
+                "{% block content %}{{ block.super }}other{% endblock %}"
+                "{% block sidebar %}{% endblock %}"
+            ),
+            "some": (
+                "{% extends 'other' %}{{ x }}"
+                "{% block content %}some{% endblock %}"
+            ),
+        }
+    )
+
+    env = Environment(loader=loader)
+    analysis = env.get_template("some").analyze_inheritance()
+
+    assert analysis.chain == ["some", "other", "base"]
+    assert analysis.blocks == {
+        "content": [
+            Span("some", 28, 47),
+            Span("other", 20, 39),
+            Span("base", 39, 58),
+        ],
+        "sidebar": [Span("other", 75, 94)],
+        "head": [Span("base", 0, 25)],
+        "footer": [Span("base", 76, 94)],
+    }
+    assert analysis.resolved_blocks == {
+        "head": Span("base", 0, 25),
+        "content": Span("some", 28, 47),
+        "footer": Span("base", 76, 94),
+    }
+    assert analysis.overrides == {
+        Span("some", 28, 47): Span("other", 20, 39),
+        Span("other", 20, 39): Span("base", 39, 58),
+    }
+    assert analysis.orphaned_blocks == {
+        "sidebar": [Span("other", 75, 94)]
+    }
+    assert analysis.missing_required == {"head": Span("base", 0, 25)}
+    assert analysis.unreachable == [
+        Span("some", 21, 28),
+        Span("other", 20, 39),
+        Span("base", 39, 58),
+    ]
+    assert [d.kind for d in analysis.diagnostics] == [
+        "unreachable",
+        "missing_required",
+        "unreachable",
+        "unreachable",
+        "orphaned_block",
+    ]
+
+
+def test_analyze_inheritance_super() -> None:
+    loader = DictLoader(
+        {
+            "base": "{% block content %}base{% endblock %}",
+            "other": (
+                "{% extends 'base' %}"
+                "{% block content %}{{ block.super }}other{% endblock %}"
+            ),
+            "some": (
+                "{% extends 'other' %}\n"
+                "{% block content %}{{ block.super }}some{% endblock %}\n"
+            ),
+        }
+    )
+
+    env = Environment(loader=loader)
+    analysis = env.get_template("some").analyze_inheritance()
+
+    assert analysis.resolved_blocks == {
+        "content": Span("some", 22, 41),
+    }
+    assert analysis.unreachable == []
+    assert analysis.diagnostics == []
+
+
+def test_analyze_inheritance_without_extends(env: Environment) -> None:
+    template = env.from_string("{% block content required %}{% endblock %}")
+    analysis = template.analyze_inheritance()
+
+    assert analysis.chain == ["<string>"]
+    assert analysis.overrides == {}
+    assert analysis.missing_required == {"content": Span("<string>", 0, 28)}
+    assert str(analysis.diagnostics[0]) == (
+        "block 'content' must be overridden (<string>[0:28])"
+    )
+
+
+def test_analyze_inheritance_recursive_extends() -> None:
+    loader = DictLoader(
+        {
+            "some": "{% extends 'other' %}",
+            "other": "{% extends 'some' %}",
+        }
+    )
+    env = Environment(loader=loader)
+    template = env.get_template("some")
+
+    with pytest.raises(TemplateInheritanceError):
+        template.analyze_inheritance()
+
+    async def coro(template: Template) -> InheritanceAnalysis:
+        return await template.analyze_inheritance_async()
+
+    with pytest.raises(TemplateInheritanceError):
+        asyncio.run(coro(template))
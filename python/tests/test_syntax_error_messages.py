@@ -0,0 +1,81 @@
+"""Test that lexer syntax errors are described in Liquid terms."""
+
+import operator
+from dataclasses import dataclass
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import parse_jsonpath_query
+from _liquid2 import tokenize
+
+
+@dataclass
+class Case:
+    """Test case helper."""
+
+    name: str
+    source: str
+    want: str
+
+
+TEMPLATE_CASES = [
+    Case(
+        name="unclosed output statement",
+        source="{{ x ",
+        want=(
+            "expected an operator, a keyword, a range, a name, a number or '}}', "
+            "found end of input"
+        ),
+    ),
+    Case(
+        name="unclosed tag",
+        source="Hello {% if x ",
+        want=(
+            "expected an operator, a keyword, a range, a name, a number or '%}', "
+            "found end of input"
+        ),
+    ),
+    Case(
+        name="unclosed comment",
+        source="{# hello",
+        want="unclosed comment, expected '#}'",
+    ),
+    Case(
+        name="empty bracketed selection",
+        source="{{ a[] }}",
+        want=(
+            "expected '*', an index, a slice, a filter selector or a query, "
+            "found ']'"
+        ),
+    ),
+]
+
+QUERY_CASES = [
+    Case(
+        name="missing comparison operand",
+        source="$.a[?@.b ==]",
+        want=(
+            "expected a number, a query, 'true', 'false', 'null' or a function call, "
+            "found ']'"
+        ),
+    ),
+    Case(
+        name="descendant segment without a selector",
+        source="$..",
+        want="expected '*', '[' or a name, found end of input",
+    ),
+]
+
+
+@pytest.mark.parametrize("case", TEMPLATE_CASES, ids=operator.attrgetter("name"))
+def test_template_syntax_errors(case: Case) -> None:
+    with pytest.raises(LiquidSyntaxError) as err:
+        tokenize(case.source)
+    assert str(err.value) == case.want
+
+
+@pytest.mark.parametrize("case", QUERY_CASES, ids=operator.attrgetter("name"))
+def test_query_syntax_errors(case: Case) -> None:
+    with pytest.raises(LiquidSyntaxError) as err:
+        parse_jsonpath_query(case.source)
+    assert str(err.value) == case.want
@@ -0,0 +1,122 @@
+"""Test parsing and rendering tablerow tags."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import Primitive
+from _liquid2 import parse
+from _liquid2 import render
+
+
+def test_parse_tablerow_tag() -> None:
+    source = "{% tablerow x in items cols:2, limit: 3 offset:1 %}{% endtablerow %}"
+    (tag,) = parse(source).nodes
+    assert isinstance(tag, Node.TablerowTag)
+    assert tag.name == "x"
+    assert isinstance(tag.cols, Primitive.Integer)
+    assert str(tag.cols) == "2"
+    assert str(tag.limit) == "3"
+    assert str(tag.offset) == "1"
+    assert tag.span == (0, len(source))
+    assert str(tag) == (
+        "{% tablerow x in items cols:2 limit:3 offset:1 %}{% endtablerow %}"
+    )
+
+
+def test_parse_tablerow_tag_without_parameters() -> None:
+    (tag,) = parse("{% tablerow x in (1..3) %}{% endtablerow %}").nodes
+    assert isinstance(tag, Node.TablerowTag)
+    assert isinstance(tag.iterable, Primitive.Range)
+    assert tag.cols is None
+    assert tag.limit is None
+    assert tag.offset is None
+
+
+def test_tablerow_in_liquid_tag() -> None:
+    source = "{% liquid\ntablerow x in items\necho x\nendtablerow %}"
+    (liquid_tag,) = parse(source).nodes
+    assert isinstance(liquid_tag, Node.LiquidTag)
+    (tag,) = liquid_tag.block
+    assert isinstance(tag, Node.TablerowTag)
+
+
+def test_render_tablerow() -> None:
+    source = "{% tablerow x in items cols:2 %}{{ x }}{% endtablerow %}"
+    assert render(source, {"items": [1, 2, 3]}) == (
+        '<tr class="row1">\n'
+        '<td class="col1">1</td><td class="col2">2</td></tr>\n'
+        '<tr class="row2"><td class="col1">3</td></tr>\n'
+    )
+
+
+def test_render_tablerow_without_cols() -> None:
+    source = "{% tablerow x in items %}{{ x }}{% endtablerow %}"
+    assert render(source, {"items": [1, 2]}) == (
+        '<tr class="row1">\n<td class="col1">1</td><td class="col2">2</td></tr>\n'
+    )
+
+
+def test_render_empty_tablerow() -> None:
+    source = "{% tablerow x in items %}{{ x }}{% endtablerow %}"
+    assert render(source, {"items": []}) == '<tr class="row1">\n</tr>\n'
+
+
+def test_render_tablerow_limit_and_offset() -> None:
+    source = "{% tablerow x in (1..5) limit:2 offset:2 %}{{ x }}{% endtablerow %}"
+    assert render(source) == (
+        '<tr class="row1">\n<td class="col1">3</td><td class="col2">4</td></tr>\n'
+    )
+
+
+def test_render_tablerow_over_a_huge_range() -> None:
+    source = "{% tablerow x in (1..9223372036854775807) limit:2 %}{{ x }}{% endtablerow %}"
+    assert render(source) == (
+        '<tr class="row1">\n<td class="col1">1</td><td class="col2">2</td></tr>\n'
+    )
+
+
+def test_tablerowloop() -> None:
+    source = (
+        "{% tablerow x in (1..3) cols:2 %}"
+        "{{ tablerowloop.index }}-{{ tablerowloop.row }}-{{ tablerowloop.col }}"
+        "-{{ tablerowloop.col_first }}-{{ tablerowloop.col_last }}"
+        "{% endtablerow %}"
+    )
+    assert render(source) == (
+        '<tr class="row1">\n'
+        '<td class="col1">1-1-1-true-false</td>'
+        '<td class="col2">2-1-2-false-true</td></tr>\n'
+        '<tr class="row2"><td class="col1">3-2-1-true-false</td></tr>\n'
+    )
+
+
+def test_break_out_of_tablerow() -> None:
+    source = (
+        "{% tablerow x in (1..3) %}"
+        "{% if x == 2 %}{% break %}{% endif %}{{ x }}"
+        "{% endtablerow %}"
+    )
+    assert render(source) == (
+        '<tr class="row1">\n<td class="col1">1</td><td class="col2"></td></tr>\n'
+    )
+
+
+def test_tablerow_variables_are_scoped() -> None:
+    source = "{% tablerow x in items %}{{ x }}{{ tablerowloop.col }}{% endtablerow %}"
+    analysis = parse(source).analyze()
+    assert [var.name for var in analysis.globals] == ["items"]
+
+
+def test_missing_end_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endtablerow'"):
+        parse("{% tablerow x in items %}{{ x }}")
+
+
+def test_unexpected_end_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="unexpected tag 'endtablerow'"):
+        parse("{% endtablerow %}")
+
+
+def test_unknown_parameter() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'cols', 'limit' or 'offset'"):
+        parse("{% tablerow x in items reversed %}{% endtablerow %}")
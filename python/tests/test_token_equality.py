@@ -0,0 +1,49 @@
+"""Test structural equality and hashing of markup and tokens."""
+
+from _liquid2 import Markup
+from _liquid2 import Token
+from _liquid2 import tokenize
+
+
+def expression(source: str) -> list[Token]:
+    tag, _ = tokenize(source)
+    assert isinstance(tag, Markup.Tag)
+    return tag.expression or []
+
+
+def test_tokens_ignore_spans() -> None:
+    assert Token.Word(value="a", span=(0, 1)) == Token.Word(value="a", span=(5, 6))
+    assert Token.Word(value="a", span=(0, 1)) != Token.Word(value="b", span=(0, 1))
+    assert Token.Comma(span=(0, 1)) == Token.Comma(span=(3, 4))
+    assert Token.Comma(span=(0, 1)) != Token.Colon(span=(0, 1))
+
+
+def test_compare_token_streams() -> None:
+    assert expression("{% box a, 1.5 %}") == expression("{%   box   a ,1.5 %}")
+    assert expression("{% box a, 1 %}") != expression("{% box a, 1.0 %}")
+    assert expression("{% box x.y %}") == expression("{% box x['y'] %}")
+
+
+def test_compare_ranges() -> None:
+    (a,) = expression("{% box (1..n) %}")
+    (b,) = expression("{% box ( 1 .. n ) %}")
+    (c,) = expression("{% box (1..m) %}")
+    assert isinstance(a, Token.RangeLiteral)
+    assert isinstance(b, Token.RangeLiteral)
+    assert a == b
+    assert a != c
+    assert a.start == b.start
+    assert hash(a.stop) == hash(b.stop)
+
+
+def test_compare_markup() -> None:
+    assert tokenize("{{ a | upcase }}") == tokenize("{{a|upcase}}")
+    assert tokenize("{{- a }}") != tokenize("{{ a }}")
+    assert tokenize("{% liquid\necho a\n%}") == tokenize("{%   liquid echo a %}")
+
+
+def test_hash_tokens() -> None:
+    tokens = expression("{% box a, b, a %}")
+    assert len(set(tokens)) == 3  # noqa: PLR2004
+    assert hash(tokens[0]) == hash(tokens[4])
+    assert {tokenize("x")[0]: 1}[tokenize("x")[0]] == 1
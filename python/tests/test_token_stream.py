@@ -0,0 +1,84 @@
+"""Test the token stream cursor for custom tags."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import LiquidTypeError
+from _liquid2 import Markup
+from _liquid2 import Token
+from _liquid2 import TokenStream
+from _liquid2 import tokenize
+
+
+def stream(source: str) -> TokenStream:
+    tag, _ = tokenize(source)
+    assert isinstance(tag, Markup.Tag)
+    return TokenStream(tag.expression or [], tag.span)
+
+
+def test_peek_and_next() -> None:
+    tokens = stream("{% box a, b %}")
+    assert tokens.position == 0
+    assert len(tokens) == 3  # noqa: PLR2004
+    assert str(tokens.peek()) == "a"
+    assert str(tokens.peek(1)) == ","
+    assert tokens.peek(3) is None
+    assert str(tokens.next()) == "a"
+    assert tokens.position == 1
+    assert [str(token) for token in tokens] == [",", "b"]
+    assert tokens.next() is None
+
+
+def test_token_kind() -> None:
+    tokens = stream("{% box a: 'b' %}")
+    assert [token.kind for token in tokens] == ["Word", "Colon", "StringLiteral"]
+
+
+def test_expect() -> None:
+    tokens = stream("{% box a: 1 %}")
+    token = tokens.expect("Word")
+    assert isinstance(token, Token.Word)
+    assert token.value == "a"
+    tokens.expect("Colon")
+    tokens.expect("IntegerLiteral")
+    tokens.expect_eos()
+
+
+def test_expect_error_points_at_the_token() -> None:
+    tokens = stream("{% box a 1 %}")
+    tokens.next()
+    with pytest.raises(LiquidSyntaxError, match="expected ':', found '1'") as err:
+        tokens.expect("Colon")
+    assert err.value.span == (9, 10)
+    assert tokens.position == 1
+
+
+def test_expect_at_end_of_stream_points_at_the_tag() -> None:
+    tokens = stream("{% box %}")
+    with pytest.raises(
+        LiquidSyntaxError, match="expected a name, found end of expression"
+    ) as err:
+        tokens.expect("Word")
+    assert err.value.span == (0, 9)
+
+
+def test_eat_one_of() -> None:
+    tokens = stream("{% box , a %}")
+    assert tokens.eat_one_of(["Pipe", "Word"]) is None
+    comma = tokens.eat_one_of(["Comma", "Colon"])
+    assert isinstance(comma, Token.Comma)
+    assert tokens.position == 1
+
+
+def test_expect_eos() -> None:
+    tokens = stream("{% box a b %}")
+    tokens.next()
+    with pytest.raises(LiquidSyntaxError, match="expected end of expression"):
+        tokens.expect_eos()
+
+
+def test_unknown_kind() -> None:
+    tokens = stream("{% box a %}")
+    with pytest.raises(LiquidTypeError, match="unknown token kind 'Name'"):
+        tokens.expect("Name")
+    with pytest.raises(LiquidTypeError, match="unknown token kind 'Name'"):
+        tokens.eat_one_of(["Name"])
@@ -0,0 +1,61 @@
+"""Test tokenizing templates that contain syntax errors."""
+
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Markup
+from _liquid2 import tokenize_lenient
+
+
+def test_valid_template() -> None:
+    markup, errors = tokenize_lenient("Hello, {{ you }}!")
+    assert errors == []
+    assert [str(m) for m in markup] == ["Hello, ", "{{ you }}", "!", ""]
+
+
+def test_unclosed_output() -> None:
+    markup, errors = tokenize_lenient("x {{ y")
+    assert len(errors) == 1
+    assert isinstance(errors[0], LiquidSyntaxError)
+    assert isinstance(markup[1], Markup.Error)
+    assert markup[1].text == "{{ y"
+    assert markup[1].span == (2, 6)
+
+
+def test_markup_after_an_error() -> None:
+    source = "a{% if %}b{{ $.c[ }}d{{ e }}"
+    markup, errors = tokenize_lenient(source)
+    assert len(errors) == 1
+    assert [type(m).__name__ for m in markup] == [
+        "Markup_Content",
+        "Markup_Tag",
+        "Markup_Content",
+        "Markup_Error",
+        "Markup_Content",
+        "Markup_Output",
+        "Markup_EOI",
+    ]
+    assert all(source[m.span[0] : m.span[1]] == str(m) for m in markup[:5])
+
+
+def test_unclosed_comment_consumes_the_rest() -> None:
+    markup, errors = tokenize_lenient("a{# oops %}b{{ c }}")
+    assert len(errors) == 1
+    assert [str(m) for m in markup] == ["a", "{# oops %}b{{ c }}", ""]
+
+
+def test_invalid_query() -> None:
+    markup, errors = tokenize_lenient("{{ $.a[?nosuchfunc()] }}{{ b }}")
+    assert len(errors) == 1
+    assert errors[0].__class__.__name__ == "LiquidNameError"
+    assert isinstance(markup[0], Markup.Error)
+    assert isinstance(markup[1], Markup.Output)
+
+
+def test_multiple_errors() -> None:
+    source = "{{ $.a[ }}ok{{ $.b[ }}"
+    markup, errors = tokenize_lenient(source, source_name="page.liquid")
+    assert len(errors) == 2  # noqa: PLR2004
+    assert all("page.liquid" in str(err) for err in errors)
+    assert [m.text for m in markup if isinstance(m, Markup.Error)] == [
+        "{{ $.a[ }}",
+        "{{ $.b[ }}",
+    ]
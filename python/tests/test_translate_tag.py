@@ -0,0 +1,48 @@
+"""Test parsing the translate tag."""
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import parse
+
+
+def test_translate_tag() -> None:
+    source = (
+        "{% translate you:name, count:n %}Hello, {{ you }}!"
+        "{% plural %}Hello, {{ you }}s!{% endtranslate %}"
+    )
+    template = parse(source)
+    node = template.nodes[0]
+    assert isinstance(node, Node.TranslateTag)
+    assert node.name == "translate"
+    assert [arg.name for arg in node.args] == ["you", "count"]
+    assert isinstance(node.block[1], Node.Output)
+    assert node.plural is not None
+    assert isinstance(node.plural.block[0], Node.Content)
+    assert node.span == (0, len(source))
+    assert str(template) == source
+
+
+def test_short_translate_tag() -> None:
+    template = parse("{% t %}Hello{% endt %}")
+    node = template.nodes[0]
+    assert isinstance(node, Node.TranslateTag)
+    assert node.name == "t"
+    assert node.args == []
+    assert node.plural is None
+    assert str(template) == "{% t %}Hello{% endt %}"
+
+
+def test_end_tag_must_match_start_tag() -> None:
+    with pytest.raises(LiquidSyntaxError, match="found 'endtranslate'"):
+        parse("{% t %}Hello{% endtranslate %}")
+
+
+def test_only_one_plural_block() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected 'endtranslate'"):
+        parse("{% translate %}a{% plural %}b{% plural %}c{% endtranslate %}")
+
+
+def test_translate_arguments_are_keyword_arguments() -> None:
+    with pytest.raises(LiquidSyntaxError, match="expected a keyword argument"):
+        parse("{% translate 'hello' %}{% endtranslate %}")
@@ -0,0 +1,96 @@
+"""Test unescaping of string literals."""
+
+import json
+
+import pytest
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import diagnose
+from _liquid2 import unescape_string
+
+VALID = [
+    ("", ""),
+    ("abc", "abc"),
+    ('\\"', '"'),
+    ("\\\\", "\\"),
+    ("\\/", "/"),
+    ("\\b", "\b"),
+    ("\\f", "\f"),
+    ("\\n", "\n"),
+    ("\\r", "\r"),
+    ("\\t", "\t"),
+    ("\\u0041", "A"),
+    ("\\u00e9", "é"),
+    ("\\u00E9", "é"),
+    ("\\u001F", "\x1f"),
+    ("\\uFFFF", "\uffff"),
+    ("\\uD83D\\uDE00", "😀"),
+    ("\\uDBFF\\uDFFF", "\U0010ffff"),
+    ("\\uD800\\uDC00", "\U00010000"),
+    ("a\\nb\\tc", "a\nb\tc"),
+    ("é\\u0041é", "éAé"),
+]
+
+
+@pytest.mark.parametrize(("value", "want"), VALID)
+def test_unescape(value: str, want: str) -> None:
+    assert unescape_string(value) == want
+
+
+def test_quotes() -> None:
+    assert unescape_string("\\'", quote="'") == "'"
+    with pytest.raises(LiquidSyntaxError, match="unknown escape sequence"):
+        unescape_string("\\'")
+    with pytest.raises(LiquidSyntaxError, match="unknown escape sequence"):
+        unescape_string('\\"', quote="'")
+
+
+INVALID = [
+    ("a\\", "incomplete escape sequence"),
+    ("\\", "incomplete escape sequence"),
+    ("\\u", "incomplete escape sequence"),
+    ("\\u1", "incomplete escape sequence"),
+    ("\\u12", "incomplete escape sequence"),
+    ("\\u123", "incomplete escape sequence"),
+    ("\\u12x4", "invalid escape sequence"),
+    ("\\u+123", "invalid escape sequence"),
+    ("\\u-123", "invalid escape sequence"),
+    ("\\u 123", "invalid escape sequence"),
+    ("\\u0é\u00e9", "invalid escape sequence"),
+    ("\\uD800\\u0é\u00e9", "invalid escape sequence"),
+    ("\\x41", "unknown escape sequence"),
+    ("\\é", "unknown escape sequence"),
+    ("\\u0000", "invalid character"),
+    ("\\u0001", "invalid character"),
+    ("\\uDC00", "unexpected low surrogate"),
+    ("\\uDFFF", "unexpected low surrogate"),
+    ("\\uD800", "expected a low surrogate"),
+    ("\\uD800a", "expected a low surrogate"),
+    ("\\uD800\\n", "expected a low surrogate"),
+    ("\\uD800\\u", "incomplete escape sequence"),
+    ("\\uD800\\uDC0", "incomplete escape sequence"),
+    ("\\uD800\\u0041", "expected a low surrogate"),
+    ("\\uD800\\uD800", "expected a low surrogate"),
+    ("\\uDBFF\\uDBFF\\uDC00", "expected a low surrogate"),
+]
+
+
+@pytest.mark.parametrize(("value", "message"), INVALID)
+def test_malformed_escapes_are_errors(value: str, message: str) -> None:
+    with pytest.raises(LiquidSyntaxError, match=message):
+        unescape_string(value)
+
+
+def test_error_span_is_the_escape_sequence() -> None:
+    source = '{{ "ab\\u0001cd" }}'
+    (diagnostic,) = json.loads(diagnose(source))
+    start = diagnostic["start"]["offset"]
+    end = diagnostic["end"]["offset"]
+    assert source[start:end] == "\\u0001"
+
+
+def test_error_span_in_single_quoted_name_selector() -> None:
+    source = "{{ x['\\'\\'\\u0001'] }}"
+    (diagnostic,) = json.loads(diagnose(source))
+    start = diagnostic["start"]["offset"]
+    end = diagnostic["end"]["offset"]
+    assert source[start:end] == "\\u0001"
@@ -0,0 +1,80 @@
+"""Test rejecting or warning about branches that can never be reached."""
+
+import json
+
+import pytest
+from _liquid2 import Dialect
+from _liquid2 import LiquidSyntaxError
+from _liquid2 import Node
+from _liquid2 import lint
+from _liquid2 import parse
+
+UNREACHABLE = [
+    ("{% if a %}b{% else %}c{% else %}d{% endif %}", "duplicate 'else' tag"),
+    ("{% unless a %}b{% else %}c{% else %}d{% endunless %}", "duplicate 'else' tag"),
+    (
+        "{% if a %}b{% else %}c{% elsif d %}e{% endif %}",
+        "unexpected 'elsif' tag after 'else'",
+    ),
+    ("{% for a in b %}c{% else %}d{% else %}e{% endfor %}", "duplicate 'else' tag"),
+    (
+        "{% case a %}{% when 1 %}b{% else %}c{% else %}d{% endcase %}",
+        "duplicate 'else' tag",
+    ),
+    (
+        "{% case a %}{% when 'b', 'c', 'b' %}d{% endcase %}",
+        "duplicate 'when' value 'b'",
+    ),
+    ("{% case a %}{% when 1 or 1 %}b{% endcase %}", "duplicate 'when' value 1"),
+]
+
+
+@pytest.mark.parametrize("case", UNREACHABLE)
+def test_unreachable_branch_is_a_syntax_error(case: tuple[str, str]) -> None:
+    source, message = case
+    with pytest.raises(LiquidSyntaxError, match=message):
+        parse(source)
+
+
+@pytest.mark.parametrize("case", UNREACHABLE)
+def test_unreachable_branch_is_a_warning_in_lax_mode(case: tuple[str, str]) -> None:
+    source, message = case
+    parse(source, dialect=Dialect.lax())
+    (diagnostic,) = json.loads(lint(source, dialect=Dialect.lax()))
+    assert diagnostic["code"] == "unreachable-branch"
+    assert diagnostic["severity"] == "warning"
+    assert diagnostic["message"] == message
+
+
+def test_error_points_at_the_offending_branch() -> None:
+    source = "{% if a %}b{% else %}c{% else %}d{% endif %}"
+    (diagnostic,) = json.loads(lint(source))
+    assert diagnostic["start"]["offset"] == 22  # noqa: PLR2004
+    (note,) = diagnostic["notes"]
+    assert note["message"] == "the 'else' tag is here"
+    assert note["start"]["offset"] == 11  # noqa: PLR2004
+
+
+def test_unreachable_branches_are_dropped_in_lax_mode() -> None:
+    source = "{% if a %}b{% else %}c{% elsif d %}e{% else %}f{% endif %}"
+    (node,) = parse(source, dialect=Dialect.lax()).nodes
+    assert isinstance(node, Node.IfTag)
+    assert node.alternatives == []
+    assert node.default is not None
+    assert str(node) == "{% if a %}b{% else %}c{% endif %}"
+
+
+def test_unreachable_branches_must_still_be_valid() -> None:
+    source = "{% if a %}b{% else %}c{% elsif %}e{% endif %}"
+    with pytest.raises(LiquidSyntaxError, match="expected"):
+        parse(source, dialect=Dialect.lax())
+
+
+def test_repeated_values_in_different_when_tags_are_allowed() -> None:
+    parse("{% case a %}{% when 'b' %}c{% when 'b' %}d{% endcase %}")
+
+
+def test_unreachable_branch_warnings_can_be_disabled() -> None:
+    source = "{% if a %}b{% else %}c{% else %}d{% endif %}"
+    diagnostics = lint(source, dialect=Dialect.lax(), disable=["unreachable-branch"])
+    assert json.loads(diagnostics) == []
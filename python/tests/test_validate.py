@@ -0,0 +1,137 @@
+"""Test validating templates without building a syntax tree."""
+
+import json
+from typing import Any
+
+import pytest
+from _liquid2 import Dialect
+from _liquid2 import LiquidTypeError
+from _liquid2 import parse
+from _liquid2 import validate
+
+
+def messages(source: str, **kwargs: Any) -> list[str]:
+    return [d["message"] for d in json.loads(validate(source, **kwargs))]
+
+
+def test_valid_template() -> None:
+    source = (
+        "{% for x in y %}{% if x %}{{ x | upcase }}{% else %}-{% endif %}{% endfor %}"
+        "{% liquid\nif a\necho a | size\nendif %}"
+    )
+    assert messages(source) == []
+
+
+def test_grammar_error() -> None:
+    (diagnostic,) = json.loads(validate("{{ a | }", source_name="a.liquid"))
+    assert diagnostic["code"] == "syntax-error"
+    assert diagnostic["source_name"] == "a.liquid"
+
+
+def test_missing_end_tag() -> None:
+    (diagnostic,) = json.loads(validate("{% if a %}\n{% for x in y %}{% endfor %}"))
+    assert diagnostic["message"] == "missing end tag, expected 'endif'"
+    assert diagnostic["start"] == {"offset": 0, "line": 1, "column": 1}
+
+
+def test_mismatched_end_tag_notes_where_the_block_was_opened() -> None:
+    _, diagnostic = json.loads(validate("{% for x in y %}\n{% endif %}"))
+    assert diagnostic["message"] == "expected 'else' or 'endfor', found 'endif'"
+    (note,) = diagnostic["notes"]
+    assert note["message"] == "the block was opened here"
+
+
+def test_unexpected_end_tag() -> None:
+    assert messages("{% endif %}") == ["unexpected tag 'endif'"]
+
+
+def test_reports_every_error() -> None:
+    source = "{{ a | nosuchfilter }}{% nosuchtag %}{{ b | upcase | nope }}"
+    assert messages(source) == [
+        "unknown filter 'nosuchfilter'",
+        "unknown tag 'nosuchtag'",
+        "unknown filter 'nope'",
+    ]
+
+
+def test_unknown_filters_are_allowed_if_not_strict() -> None:
+    assert messages("{{ a | nosuchfilter }}", strict_filters=False) == []
+
+
+def test_function_arity() -> None:
+    assert messages("{{ a[?length(@.b, 1) > 1] }}") == [
+        "length() takes 1 argument but 2 were given"
+    ]
+
+
+def test_filter_arguments() -> None:
+    assert messages("{{ a | upcase: 1 }}") == ["'upcase' expects 0 arguments, found 1"]
+    assert messages("{% assign x = a | truncate: 1, 2, 3 %}") == [
+        "'truncate' expects at most 2 arguments, found 3"
+    ]
+    assert messages("{% liquid\necho a | upcase: x: 1 %}") == [
+        "'upcase' got an unexpected keyword argument 'x'"
+    ]
+    assert messages("{{ a | append }}", strict_filters=False) == [
+        "'append' expects 1 argument, found 0"
+    ]
+
+
+VALID_FILTER_CALLS = [
+    "{{ a | append: 'b' | truncate: 5, '..' }}",
+    "{{ a | default: 'x', allow_false: true }}",
+    "{{ a | default: 'x', allow_false = true }}",
+    "{{ a | append: b, }}",
+    "{{ a | where: x => x.b > 1 and x.c, | first }}",
+    "{{ a | where: (x, i) => i < 3 }}",
+    "{{ a | slice: 1 if b else c | upcase }}",
+    "{{ a | append: (1..3) }}",
+]
+
+INVALID_FILTER_CALLS = [
+    "{{ a | upcase: 1 }}",
+    "{{ a | append }}",
+    "{{ a | append: }}",
+    "{{ a | append: 'b', 'c' }}",
+    "{{ a | upcase: x => x.b > 1 }}",
+    "{{ a | sort: (x, y) => x < y, z }}",
+    "{{ a | default: 'x', nope: true }}",
+    "{{ a if b else c | upcase: 1 }}",
+    "{{ a if b else c || append }}",
+]
+
+
+def test_filter_arguments_agree_with_parse() -> None:
+    for source in VALID_FILTER_CALLS:
+        parse(source)
+        assert messages(source) == [], source
+
+    for source in INVALID_FILTER_CALLS:
+        with pytest.raises(LiquidTypeError) as err:
+            parse(source)
+        assert messages(source) == [err.value.args[0]], source
+
+
+def test_blocks_in_liquid_tags_must_be_closed_in_the_same_tag() -> None:
+    source = "{% liquid\nif a\necho a %}{% endif %}"
+    assert messages(source) == [
+        "missing end tag, expected 'endif'",
+        "unexpected tag 'endif'",
+    ]
+
+
+def test_unknown_tags() -> None:
+    source = "{% box %}{{ a }}{% endbox %}{% note 'x' %}"
+    assert messages(source) == [
+        "unknown tag 'box'",
+        "unknown tag 'endbox'",
+        "unknown tag 'note'",
+    ]
+    assert messages(source, common_tags=True) == []
+    assert messages(source, dialect=Dialect.lax()) == []
+
+
+def test_jekyll_include() -> None:
+    source = "{% include nav/bar.html %}"
+    assert messages(source) == ["the 'jekyll_include' dialect option is disabled"]
+    assert messages(source, dialect=Dialect.jekyll()) == []
@@ -0,0 +1,599 @@
+//! Find the variables a template reads and the names it assigns, without
+//! rendering it.
+//!
+//! Nodes are visited in document order. A name assigned with `assign`,
+//! `capture`, `increment` or `decrement` is defined from the end of its tag
+//! onwards, whether or not the tag is inside a conditional block. Loop
+//! variables, macro parameters and translation arguments are local to their
+//! blocks.
+//!
+//! `Template::references` and `Template::references_at` find every place a
+//! name is read or assigned, for renaming a variable or finding its usages.
+//! Names that are local to a block are told apart from global names of the
+//! same name, so renaming `item` in `{% for item in items %}` leaves an
+//! `item` read outside the loop alone.
+
+use std::collections::HashSet;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::ast::{
+    BooleanExpression, CommonArgument, FilteredExpression, InlineCondition, KeywordArgument, Node,
+    Primitive, Template,
+};
+use crate::query::{FilterExpression, Query, Segment, Selector};
+use crate::span::Span;
+
+/// A variable read by a template.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone)]
+pub struct Variable {
+    /// The name at the root of the query, like `user` in `user.name`.
+    pub name: String,
+    pub path: Query,
+    pub span: Span,
+}
+
+/// A name assigned by a template.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone)]
+pub struct Identifier {
+    pub name: String,
+    /// The name of the tag that made the assignment, like `assign` or `for`.
+    pub tag: String,
+    pub span: Span,
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Default)]
+pub struct TemplateAnalysis {
+    /// Every variable read by the template, in order of appearance. Queries
+    /// that don't start with a name, like `$[0]`, are not included.
+    pub variables: Vec<Variable>,
+    /// Variables that must be provided by the caller. These are read but
+    /// never assigned by the template, and are not local to a block.
+    pub globals: Vec<Variable>,
+    /// Every name assigned by the template, in order of appearance.
+    pub assigned: Vec<Identifier>,
+    /// Variables read before the template assigns them.
+    pub used_before_definition: Vec<Variable>,
+}
+
+/// How a reference uses its name.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    /// The root name of a query.
+    Read,
+    /// The target of an `assign` tag.
+    Assign,
+    /// The name of a `capture` tag.
+    Capture,
+    /// The name of an `increment` tag.
+    Increment,
+    /// The name of a `decrement` tag.
+    Decrement,
+    /// A `for` or `tablerow` loop variable.
+    LoopVariable,
+}
+
+/// A place where a template reads or assigns a name.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    /// The span of the name itself, excluding any quotes, ready to be
+    /// replaced when renaming.
+    pub span: Span,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Reference {
+    fn __repr__(&self) -> String {
+        format!(
+            "Reference({:?}, kind={:?}, span=({}, {}))",
+            self.name, self.kind, self.span.start, self.span.end
+        )
+    }
+}
+
+impl Template {
+    /// Report the variables read and the names assigned by this template.
+    pub fn analyze(&self) -> TemplateAnalysis {
+        let mut analyzer = Analyzer::default();
+        analyzer.nodes(&self.nodes);
+
+        let mut analysis = analyzer.analysis;
+        let assigned: HashSet<&str> = analysis
+            .assigned
+            .iter()
+            .map(|identifier| identifier.name.as_str())
+            .collect();
+        let (early, globals) = analyzer
+            .undefined
+            .into_iter()
+            .partition(|variable| assigned.contains(variable.name.as_str()));
+        analysis.used_before_definition = early;
+        analysis.globals = globals;
+        analysis
+    }
+
+    /// Every reference to the global variable _name_, in order of
+    /// appearance. Reads of loop variables, macro parameters and other block
+    /// locals that happen to share the name are not included.
+    pub fn references(&self, name: &str) -> Vec<Reference> {
+        self.all_references()
+            .into_iter()
+            .filter(|(local, reference)| local.is_none() && reference.name == name)
+            .map(|(_, reference)| reference)
+            .collect()
+    }
+
+    /// Every reference to the variable named at byte _offset_, including the
+    /// one at _offset_, in order of appearance. Returns an empty list if
+    /// there's no name at _offset_.
+    pub fn references_at(&self, offset: usize) -> Vec<Reference> {
+        let references = self.all_references();
+        let Some((local, name)) = references
+            .iter()
+            .find(|(_, reference)| reference.span.start <= offset && offset <= reference.span.end)
+            .map(|(local, reference)| (*local, reference.name.clone()))
+        else {
+            return Vec::new();
+        };
+
+        references
+            .into_iter()
+            .filter(|(other, reference)| *other == local && reference.name == name)
+            .map(|(_, reference)| reference)
+            .collect()
+    }
+
+    /// Every reference in this template, in order of appearance, with the
+    /// id of the local it refers to.
+    fn all_references(&self) -> Vec<(Option<usize>, Reference)> {
+        let mut analyzer = Analyzer::default();
+        analyzer.nodes(&self.nodes);
+        let mut references = analyzer.references;
+        references.sort_by_key(|(_, reference)| reference.span.start);
+        references
+    }
+}
+
+#[derive(Default)]
+struct Analyzer {
+    analysis: TemplateAnalysis,
+    /// Names assigned so far.
+    defined: HashSet<String>,
+    /// Names local to the blocks we're in, innermost last, each with an id
+    /// that tells it apart from other locals of the same name.
+    scopes: Vec<Vec<(String, usize)>>,
+    /// The number of locals seen so far.
+    locals: usize,
+    /// Reads of names that were neither local nor defined at the time.
+    undefined: Vec<Variable>,
+    /// Every reference, with the id of the local it refers to, or `None`
+    /// for references to global names.
+    references: Vec<(Option<usize>, Reference)>,
+}
+
+impl Analyzer {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Content { .. }
+            | Node::Raw { .. }
+            | Node::Comment { .. }
+            | Node::DocTag { .. }
+            | Node::BreakTag { .. }
+            | Node::ContinueTag { .. }
+            | Node::ExtendsTag { .. } => (),
+            Node::Output { expression, .. } | Node::EchoTag { expression, .. } => {
+                self.filtered_expression(expression)
+            }
+            Node::AssignTag {
+                identifier,
+                identifier_span,
+                expression,
+                span,
+                ..
+            } => {
+                self.filtered_expression(expression);
+                self.assign(identifier, "assign", *span);
+                self.reference(identifier, ReferenceKind::Assign, *identifier_span);
+            }
+            Node::CaptureTag {
+                identifier,
+                identifier_span,
+                block,
+                span,
+                ..
+            } => {
+                self.reference(identifier, ReferenceKind::Capture, *identifier_span);
+                self.nodes(block);
+                self.assign(identifier, "capture", *span);
+            }
+            Node::CaseTag {
+                expression,
+                whens,
+                default,
+                ..
+            } => {
+                self.primitive(expression);
+                for when in whens {
+                    when.args.iter().for_each(|arg| self.primitive(arg));
+                    self.nodes(&when.block);
+                }
+                if let Some(default) = default {
+                    self.nodes(&default.block);
+                }
+            }
+            Node::CycleTag { name, args, .. } => {
+                name.iter().for_each(|p| self.primitive(p));
+                args.iter().for_each(|arg| self.primitive(arg));
+            }
+            Node::DecrementTag {
+                name,
+                name_span,
+                span,
+                ..
+            } => {
+                self.assign(name, "decrement", *span);
+                self.reference(name, ReferenceKind::Decrement, *name_span);
+            }
+            Node::IncrementTag {
+                name,
+                name_span,
+                span,
+                ..
+            } => {
+                self.assign(name, "increment", *span);
+                self.reference(name, ReferenceKind::Increment, *name_span);
+            }
+            Node::ForTag {
+                name,
+                value_name,
+                name_spans,
+                iterable,
+                limit,
+                offset,
+                block,
+                default,
+                span,
+                ..
+            } => {
+                self.primitive(iterable);
+                limit.iter().for_each(|p| self.primitive(p));
+                offset.iter().for_each(|p| self.primitive(p));
+
+                let mut names = vec![name.to_owned()];
+                names.extend(value_name.iter().cloned());
+                let mut locals = self.locals(names);
+                for ((name, id), name_span) in locals.iter().zip(name_spans) {
+                    self.analysis.assigned.push(Identifier {
+                        name: name.to_owned(),
+                        tag: "for".to_owned(),
+                        span: *span,
+                    });
+                    self.references.push((
+                        Some(*id),
+                        Reference {
+                            name: name.to_owned(),
+                            kind: ReferenceKind::LoopVariable,
+                            span: *name_span,
+                        },
+                    ));
+                }
+
+                locals.extend(self.locals(["forloop".to_owned()]));
+                self.scoped(locals, block);
+                if let Some(default) = default {
+                    self.nodes(&default.block);
+                }
+            }
+            Node::TablerowTag {
+                name,
+                name_span,
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                span,
+                ..
+            } => {
+                self.primitive(iterable);
+                cols.iter().for_each(|p| self.primitive(p));
+                limit.iter().for_each(|p| self.primitive(p));
+                offset.iter().for_each(|p| self.primitive(p));
+                self.analysis.assigned.push(Identifier {
+                    name: name.to_owned(),
+                    tag: "tablerow".to_owned(),
+                    span: *span,
+                });
+                let locals = self.locals([name.to_owned(), "tablerowloop".to_owned()]);
+                self.references.push((
+                    Some(locals[0].1),
+                    Reference {
+                        name: name.to_owned(),
+                        kind: ReferenceKind::LoopVariable,
+                        span: *name_span,
+                    },
+                ));
+                self.scoped(locals, block);
+            }
+            Node::IfTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                ..
+            }
+            | Node::UnlessTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                ..
+            } => {
+                self.boolean_expression(condition);
+                self.nodes(block);
+                for alternative in alternatives {
+                    self.boolean_expression(&alternative.condition);
+                    self.nodes(&alternative.block);
+                }
+                if let Some(default) = default {
+                    self.nodes(&default.block);
+                }
+            }
+            Node::IncludeTag {
+                target,
+                variable,
+                args,
+                ..
+            } => {
+                self.primitive(target);
+                variable.iter().for_each(|p| self.primitive(p));
+                self.keyword_arguments(args);
+            }
+            Node::RenderTag {
+                target,
+                variable,
+                args,
+                ..
+            } => {
+                self.primitive(target);
+                variable.iter().for_each(|p| self.primitive(p));
+                self.keyword_arguments(args);
+            }
+            Node::LiquidTag { block, .. }
+            | Node::BlockTag { block, .. }
+            | Node::IfchangedTag { block, .. } => self.nodes(block),
+            Node::MacroTag { params, block, .. } => {
+                for param in params {
+                    param.default.iter().for_each(|p| self.primitive(p));
+                }
+                let locals = self.locals(params.iter().map(|param| param.name.to_owned()));
+                self.scoped(locals, block);
+            }
+            Node::CallTag { args, .. } => self.common_arguments(args),
+            Node::TranslateTag {
+                args,
+                block,
+                plural,
+                ..
+            } => {
+                self.keyword_arguments(args);
+                let locals = self.locals(args.iter().map(|arg| arg.name.to_owned()));
+                self.scoped(locals.clone(), block);
+                if let Some(plural) = plural {
+                    self.scoped(locals, &plural.block);
+                }
+            }
+            Node::TagExtension {
+                args,
+                block,
+                branches,
+                ..
+            } => {
+                if let Some(args) = args {
+                    self.common_arguments(args);
+                }
+                self.nodes(block);
+                for branch in branches {
+                    self.nodes(&branch.block);
+                }
+            }
+        }
+    }
+
+    /// Give each of _names_ a new local id.
+    fn locals(&mut self, names: impl IntoIterator<Item = String>) -> Vec<(String, usize)> {
+        names
+            .into_iter()
+            .map(|name| {
+                self.locals += 1;
+                (name, self.locals)
+            })
+            .collect()
+    }
+
+    fn scoped(&mut self, locals: Vec<(String, usize)>, block: &[Node]) {
+        self.scopes.push(locals);
+        self.nodes(block);
+        self.scopes.pop();
+    }
+
+    /// The id of the innermost local called _name_, or `None` if _name_ is
+    /// not local.
+    fn local(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .flatten()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, id)| *id)
+    }
+
+    /// Record a reference to the global _name_. Assignments always set a
+    /// global, even inside a block with a local of the same name.
+    fn reference(&mut self, name: &str, kind: ReferenceKind, span: Span) {
+        self.references.push((
+            None,
+            Reference {
+                name: name.to_owned(),
+                kind,
+                span,
+            },
+        ));
+    }
+
+    fn assign(&mut self, name: &str, tag: &str, span: Span) {
+        self.analysis.assigned.push(Identifier {
+            name: name.to_owned(),
+            tag: tag.to_owned(),
+            span,
+        });
+        self.defined.insert(name.to_owned());
+    }
+
+    fn filtered_expression(&mut self, expression: &FilteredExpression) {
+        self.primitive(&expression.left);
+        for filter in expression.filters.iter().flatten() {
+            self.common_arguments(filter.args.iter().flatten());
+        }
+        for condition in expression.condition.iter().flat_map(InlineCondition::chain) {
+            self.boolean_expression(&condition.expr);
+            condition.alternative.iter().for_each(|p| self.primitive(p));
+            for filter in condition
+                .alternative_filters
+                .iter()
+                .chain(condition.tail_filters.iter())
+                .flatten()
+            {
+                self.common_arguments(filter.args.iter().flatten());
+            }
+        }
+    }
+
+    fn common_arguments<'a>(&mut self, args: impl IntoIterator<Item = &'a CommonArgument>) {
+        for arg in args {
+            self.primitive(&arg.value);
+        }
+    }
+
+    fn keyword_arguments(&mut self, args: &[KeywordArgument]) {
+        for arg in args {
+            self.primitive(&arg.value);
+        }
+    }
+
+    fn boolean_expression(&mut self, expression: &BooleanExpression) {
+        match expression {
+            BooleanExpression::Primitive { expr } => self.primitive(expr),
+            BooleanExpression::LogicalNot { expr, .. } => self.boolean_expression(expr),
+            BooleanExpression::Logical { left, right, .. }
+            | BooleanExpression::Comparison { left, right, .. } => {
+                self.boolean_expression(left);
+                self.boolean_expression(right);
+            }
+        }
+    }
+
+    fn primitive(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Range {
+                start, stop, step, ..
+            } => {
+                self.primitive(start);
+                self.primitive(stop);
+                step.iter().for_each(|p| self.primitive(p));
+            }
+            Primitive::Query { path, span } => self.query(path, *span),
+            Primitive::Lambda { params, expr, .. } => {
+                let locals = self.locals(params.iter().cloned());
+                self.scopes.push(locals);
+                self.boolean_expression(expr);
+                self.scopes.pop();
+            }
+            _ => (),
+        }
+    }
+
+    fn query(&mut self, path: &Query, span: Span) {
+        if let Some((name, name_span)) = root_name(path) {
+            let variable = Variable {
+                name: name.to_owned(),
+                path: path.clone(),
+                span,
+            };
+            let local = self.local(name);
+            if local.is_none() && !self.defined.contains(name) {
+                self.undefined.push(variable.clone());
+            }
+            self.analysis.variables.push(variable);
+            self.references.push((
+                local,
+                Reference {
+                    name: name.to_owned(),
+                    kind: ReferenceKind::Read,
+                    span: name_span,
+                },
+            ));
+        }
+        self.segments(&path.segments);
+    }
+
+    /// Visit queries embedded in _segments_, like `b.c` in `a[b.c]`.
+    fn segments(&mut self, segments: &[Segment]) {
+        for segment in segments {
+            if let Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } = segment
+            {
+                for selector in selectors {
+                    match selector {
+                        Selector::SingularQuery { query, span } => self.query(query, *span),
+                        Selector::Filter { expression, .. } => self.filter_expression(expression),
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+
+    fn filter_expression(&mut self, expression: &FilterExpression) {
+        match expression {
+            FilterExpression::Not { expression, .. } => self.filter_expression(expression),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                self.filter_expression(left);
+                self.filter_expression(right);
+            }
+            FilterExpression::RootQuery { query, span } => self.query(query, *span),
+            FilterExpression::RelativeQuery { query, .. } => self.segments(&query.segments),
+            FilterExpression::Function { args, .. } => {
+                args.iter().for_each(|arg| self.filter_expression(arg))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Return the name of the first segment of _path_, if it is a single name,
+/// and the span of that name.
+pub(crate) fn root_name(path: &Query) -> Option<(&str, Span)> {
+    match path.segments.first() {
+        Some(Segment::Child { selectors, .. }) => match selectors.as_slice() {
+            [Selector::Name { name, span }] => Some((name, *span)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,2361 @@
+//! Liquid template syntax tree
+//!
+
+// PyO3 generates a Python constructor for each `Node` variant, taking every
+// field as an argument.
+#![allow(clippy::too_many_arguments)]
+
+use std::fmt::{self, Write};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python")]
+use crate::analysis::{Reference, TemplateAnalysis};
+#[cfg(feature = "python")]
+use crate::estimate::{EstimateOptions, RenderEstimate};
+#[cfg(feature = "python")]
+use crate::json::{self, JsonError};
+use crate::markup::{CommentKind, Token};
+use crate::metadata::TemplateMetadata;
+use crate::parser::{
+    PRECEDENCE_LOGICAL_AND, PRECEDENCE_LOGICAL_OR, PRECEDENCE_MEMBERSHIP, PRECEDENCE_RELATIONAL,
+};
+#[cfg(feature = "python")]
+use crate::python::repr;
+use crate::query::{write_quoted, FilterExpression, Query, Segment, Selector};
+#[cfg(feature = "python")]
+use crate::semantic::SemanticModel;
+use crate::shared::SharedVec;
+use crate::source_map::{Mapping, SourceMap};
+use crate::span::Span;
+use crate::whitespace::Whitespace;
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub nodes: SharedVec<Node>,
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_nodes(&mut MarkupWriter::new(f), &self.nodes, false)
+    }
+}
+
+impl Template {
+    /// Write this template as markup, along with a source map from the
+    /// markup back to the spans of the nodes it was written from.
+    pub fn to_string_with_source_map(&self) -> (String, SourceMap) {
+        let mut rv = String::new();
+        let mut writer = MarkupWriter::new(&mut rv);
+        writer.mappings = Some(Vec::new());
+        write_nodes(&mut writer, &self.nodes, false).unwrap();
+        let mappings = writer.mappings.take().unwrap_or_default();
+        (rv, SourceMap::new(mappings))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Template {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        repr(slf.as_any(), &slf.get().to_string(), None)
+    }
+
+    /// Serialize this template's syntax tree as JSON.
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> Result<String, JsonError> {
+        Ok(json::to_json(self)?)
+    }
+
+    /// Rebuild a syntax tree from the output of `to_json`.
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(json: &str) -> Result<Template, JsonError> {
+        Ok(json::from_json(json)?)
+    }
+
+    #[pyo3(name = "to_string_with_source_map")]
+    fn py_to_string_with_source_map(&self) -> (String, SourceMap) {
+        self.to_string_with_source_map()
+    }
+
+    /// Report the variables read and the names assigned by this template.
+    #[pyo3(name = "analyze")]
+    fn py_analyze(&self) -> TemplateAnalysis {
+        self.analyze()
+    }
+
+    /// Every reference to the global variable _name_.
+    #[pyo3(name = "references")]
+    fn py_references(&self, name: &str) -> Vec<Reference> {
+        self.references(name)
+    }
+
+    /// Every reference to the variable named at byte _offset_.
+    #[pyo3(name = "references_at")]
+    fn py_references_at(&self, offset: usize) -> Vec<Reference> {
+        self.references_at(offset)
+    }
+
+    /// Model where each variable read by this template gets its value.
+    #[pyo3(name = "semantic_model")]
+    fn py_semantic_model(&self) -> SemanticModel {
+        self.semantic_model()
+    }
+
+    /// Estimate the most output and loop iterations rendering this template
+    /// could produce. Sizes that aren't given default to those of
+    /// `EstimateOptions::default()`.
+    #[pyo3(
+        name = "estimate",
+        signature = (
+            *,
+            collection_size=None,
+            value_size=None,
+            partial_size=None,
+            default_loop_limit=None,
+        )
+    )]
+    fn py_estimate(
+        &self,
+        collection_size: Option<u64>,
+        value_size: Option<u64>,
+        partial_size: Option<u64>,
+        default_loop_limit: Option<u64>,
+    ) -> RenderEstimate {
+        let default = EstimateOptions::default();
+        self.estimate(&EstimateOptions {
+            collection_size: collection_size.unwrap_or(default.collection_size),
+            value_size: value_size.unwrap_or(default.value_size),
+            partial_size: partial_size.unwrap_or(default.partial_size),
+            default_loop_limit,
+        })
+    }
+
+    /// Return a simplified copy of this template that renders the same
+    /// output with default settings.
+    #[pyo3(name = "optimized")]
+    fn py_optimized(&self) -> Template {
+        self.optimized()
+    }
+
+    /// Return this template's documentation, or `None` if it isn't
+    /// documented.
+    #[pyo3(name = "metadata")]
+    fn py_metadata(&self) -> Option<TemplateMetadata> {
+        self.metadata()
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Node {
+    Content {
+        text: String,
+        span: Span,
+    },
+    Raw {
+        wc: (Whitespace, Whitespace, Whitespace, Whitespace),
+        text: String,
+        span: Span,
+    },
+    Comment {
+        wc: (Whitespace, Whitespace),
+        hashes: String,
+        text: String,
+        kind: CommentKind,
+        span: Span,
+    },
+    /// A `{% doc %}` block, with its _text_ parsed into _doc_.
+    DocTag {
+        wc: (Whitespace, Whitespace),
+        text: String,
+        doc: TemplateMetadata,
+        span: Span,
+    },
+    Output {
+        wc: (Whitespace, Whitespace),
+        expression: FilteredExpression,
+        span: Span,
+    },
+    AssignTag {
+        wc: (Whitespace, Whitespace),
+        identifier: String,
+        identifier_span: Span,
+        expression: FilteredExpression,
+        span: Span,
+    },
+    CaptureTag {
+        wc: (Whitespace, Whitespace),
+        identifier: String,
+        /// The span of the identifier, without quotes if it was quoted.
+        identifier_span: Span,
+        block: SharedVec<Node>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    CaseTag {
+        wc: (Whitespace, Whitespace),
+        expression: Primitive,
+        whens: Vec<WhenTag>,
+        default: Option<ElseTag>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    CycleTag {
+        wc: (Whitespace, Whitespace),
+        name: Option<Primitive>,
+        args: Vec<Primitive>,
+        span: Span,
+    },
+    DecrementTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        name_span: Span,
+        span: Span,
+    },
+    IncrementTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        name_span: Span,
+        span: Span,
+    },
+    EchoTag {
+        wc: (Whitespace, Whitespace),
+        expression: FilteredExpression,
+        span: Span,
+    },
+    ForTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        /// The second loop variable in `for key, value in ...`.
+        value_name: Option<String>,
+        /// The spans of _name_ and _value_name_.
+        name_spans: Vec<Span>,
+        iterable: Primitive,
+        limit: Option<Primitive>,
+        offset: Option<Primitive>,
+        reversed: bool,
+        block: SharedVec<Node>,
+        default: Option<ElseTag>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    TablerowTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        name_span: Span,
+        iterable: Primitive,
+        cols: Option<Primitive>,
+        limit: Option<Primitive>,
+        offset: Option<Primitive>,
+        block: SharedVec<Node>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    BreakTag {
+        wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    ContinueTag {
+        wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    IfTag {
+        wc: (Whitespace, Whitespace),
+        condition: BooleanExpression,
+        block: SharedVec<Node>,
+        alternatives: Vec<ElsifTag>,
+        default: Option<ElseTag>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    UnlessTag {
+        wc: (Whitespace, Whitespace),
+        condition: BooleanExpression,
+        block: SharedVec<Node>,
+        alternatives: Vec<ElsifTag>,
+        default: Option<ElseTag>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    IfchangedTag {
+        wc: (Whitespace, Whitespace),
+        block: SharedVec<Node>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    IncludeTag {
+        wc: (Whitespace, Whitespace),
+        target: Primitive,
+        repeat: bool,
+        variable: Option<Primitive>,
+        alias: Option<String>,
+        args: Vec<KeywordArgument>,
+        span: Span,
+    },
+    RenderTag {
+        wc: (Whitespace, Whitespace),
+        target: Primitive,
+        /// `true` if _target_ is not a string literal, which is only allowed
+        /// with the `dynamic_render` dialect option.
+        dynamic: bool,
+        repeat: bool,
+        variable: Option<Primitive>,
+        alias: Option<String>,
+        args: Vec<KeywordArgument>,
+        span: Span,
+    },
+    LiquidTag {
+        wc: (Whitespace, Whitespace),
+        block: SharedVec<Node>,
+        span: Span,
+    },
+    ExtendsTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        span: Span,
+    },
+    BlockTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        /// `true` if the block can see variables from its enclosing scope
+        /// when it is overridden by a child template.
+        scoped: bool,
+        /// `true` if the block must be overridden by a child template.
+        required: bool,
+        block: SharedVec<Node>,
+        end_wc: (Whitespace, Whitespace),
+        /// `true` if the end tag repeats the block's name, like
+        /// `{% endblock content %}`.
+        named_end: bool,
+        span: Span,
+    },
+    MacroTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        params: Vec<Parameter>,
+        block: SharedVec<Node>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    CallTag {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        args: Vec<CommonArgument>,
+        span: Span,
+    },
+    TranslateTag {
+        wc: (Whitespace, Whitespace),
+        /// The name used to open the tag, `translate` or `t`.
+        name: String,
+        args: Vec<KeywordArgument>,
+        block: SharedVec<Node>,
+        plural: Option<PluralTag>,
+        end_wc: (Whitespace, Whitespace),
+        span: Span,
+    },
+    /// A tag registered with the parser's `TagRegistry`, or an unknown tag
+    /// when the parser accepts common tags. The expression of a registered
+    /// tag is left unparsed. Common tags also have their expression parsed
+    /// as `args`.
+    TagExtension {
+        wc: (Whitespace, Whitespace),
+        name: String,
+        expression: Vec<Token>,
+        args: Option<Vec<CommonArgument>>,
+        block: SharedVec<Node>,
+        branches: Vec<ExtensionBranch>,
+        end: Option<ExtensionEnd>,
+        span: Span,
+    },
+}
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Content { span, .. }
+            | Node::Raw { span, .. }
+            | Node::Comment { span, .. }
+            | Node::DocTag { span, .. }
+            | Node::Output { span, .. }
+            | Node::AssignTag { span, .. }
+            | Node::CaptureTag { span, .. }
+            | Node::CaseTag { span, .. }
+            | Node::CycleTag { span, .. }
+            | Node::DecrementTag { span, .. }
+            | Node::IncrementTag { span, .. }
+            | Node::EchoTag { span, .. }
+            | Node::ForTag { span, .. }
+            | Node::TablerowTag { span, .. }
+            | Node::BreakTag { span, .. }
+            | Node::ContinueTag { span, .. }
+            | Node::IfTag { span, .. }
+            | Node::UnlessTag { span, .. }
+            | Node::IfchangedTag { span, .. }
+            | Node::IncludeTag { span, .. }
+            | Node::RenderTag { span, .. }
+            | Node::LiquidTag { span, .. }
+            | Node::ExtendsTag { span, .. }
+            | Node::BlockTag { span, .. }
+            | Node::MacroTag { span, .. }
+            | Node::CallTag { span, .. }
+            | Node::TranslateTag { span, .. }
+            | Node::TagExtension { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Node {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span()))
+    }
+}
+
+impl Node {
+    /// Write this node as template markup. When `line` is true, tags are
+    /// written as `liquid` tag line statements, without delimiters.
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        match self {
+            Node::Content { text, .. } => f.write_str(text),
+            Node::Raw { wc, text, .. } => write!(
+                f,
+                "{{%{} raw {}%}}{}{{%{} endraw {}%}}",
+                wc.0, wc.1, text, wc.2, wc.3
+            ),
+            Node::Comment {
+                wc,
+                hashes,
+                text,
+                kind,
+                ..
+            } => match kind {
+                CommentKind::Inline if line => {
+                    f.write_indent()?;
+                    writeln!(f, "#{text}")
+                }
+                CommentKind::Inline => write!(f, "{{%{} #{} {}%}}", wc.0, text, wc.1),
+                CommentKind::Hash => {
+                    write!(f, "{{{}{}{}{}{}}}", hashes, wc.0, text, wc.1, hashes)
+                }
+                CommentKind::Block => write!(
+                    f,
+                    "{{%{} comment %}}{}{{% endcomment {}%}}",
+                    wc.0, text, wc.1
+                ),
+                CommentKind::Doc => {
+                    write!(f, "{{%{} doc %}}{}{{% enddoc {}%}}", wc.0, text, wc.1)
+                }
+            },
+            Node::DocTag { wc, text, .. } => {
+                write!(f, "{{%{} doc %}}{}{{% enddoc {}%}}", wc.0, text, wc.1)
+            }
+            Node::Output { wc, expression, .. } => {
+                write!(f, "{{{{{} {} {}}}}}", wc.0, expression, wc.1)
+            }
+            Node::AssignTag {
+                wc,
+                identifier,
+                expression,
+                ..
+            } => write_tag(
+                f,
+                line,
+                wc,
+                format_args!("assign {identifier} = {expression}"),
+            ),
+            Node::CaptureTag {
+                wc,
+                identifier,
+                block,
+                end_wc,
+                ..
+            } => {
+                write_tag(f, line, wc, format_args!("capture {identifier}"))?;
+                write_block(f, block, line)?;
+                write_tag(f, line, end_wc, format_args!("endcapture"))
+            }
+            Node::CaseTag {
+                wc,
+                expression,
+                whens,
+                default,
+                end_wc,
+                ..
+            } => {
+                write_tag(f, line, wc, format_args!("case {expression}"))?;
+                for when in whens {
+                    when.write_markup(f, line)?;
+                }
+                if let Some(default) = default {
+                    default.write_markup(f, line)?;
+                }
+                write_tag(f, line, end_wc, format_args!("endcase"))
+            }
+            Node::CycleTag { wc, name, args, .. } => {
+                let args = join(args, ", ");
+                if let Some(name) = name {
+                    write_tag(f, line, wc, format_args!("cycle {name}: {args}"))
+                } else {
+                    write_tag(f, line, wc, format_args!("cycle {args}"))
+                }
+            }
+            Node::DecrementTag { wc, name, .. } => {
+                write_tag(f, line, wc, format_args!("decrement {name}"))
+            }
+            Node::IncrementTag { wc, name, .. } => {
+                write_tag(f, line, wc, format_args!("increment {name}"))
+            }
+            Node::EchoTag { wc, expression, .. } => {
+                write_tag(f, line, wc, format_args!("echo {expression}"))
+            }
+            Node::ForTag {
+                wc,
+                name,
+                value_name,
+                iterable,
+                limit,
+                offset,
+                reversed,
+                block,
+                default,
+                end_wc,
+                ..
+            } => {
+                let mut expr = match value_name {
+                    Some(value_name) => format!("{name}, {value_name} in {iterable}"),
+                    None => format!("{name} in {iterable}"),
+                };
+                if let Some(limit) = limit {
+                    expr.push_str(&format!(" limit:{limit}"));
+                }
+                if let Some(offset) = offset {
+                    expr.push_str(&format!(" offset:{offset}"));
+                }
+                if *reversed {
+                    expr.push_str(" reversed");
+                }
+
+                write_tag(f, line, wc, format_args!("for {expr}"))?;
+                write_block(f, block, line)?;
+                if let Some(default) = default {
+                    default.write_markup(f, line)?;
+                }
+                write_tag(f, line, end_wc, format_args!("endfor"))
+            }
+            Node::TablerowTag {
+                wc,
+                name,
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                end_wc,
+                ..
+            } => {
+                let mut expr = format!("{name} in {iterable}");
+                if let Some(cols) = cols {
+                    expr.push_str(&format!(" cols:{cols}"));
+                }
+                if let Some(limit) = limit {
+                    expr.push_str(&format!(" limit:{limit}"));
+                }
+                if let Some(offset) = offset {
+                    expr.push_str(&format!(" offset:{offset}"));
+                }
+
+                write_tag(f, line, wc, format_args!("tablerow {expr}"))?;
+                write_block(f, block, line)?;
+                write_tag(f, line, end_wc, format_args!("endtablerow"))
+            }
+            Node::BreakTag { wc, .. } => write_tag(f, line, wc, format_args!("break")),
+            Node::ContinueTag { wc, .. } => write_tag(f, line, wc, format_args!("continue")),
+            Node::IfTag {
+                wc,
+                condition,
+                block,
+                alternatives,
+                default,
+                end_wc,
+                ..
+            } => {
+                write_tag(f, line, wc, format_args!("if {condition}"))?;
+                write_block(f, block, line)?;
+                for alternative in alternatives {
+                    alternative.write_markup(f, line)?;
+                }
+                if let Some(default) = default {
+                    default.write_markup(f, line)?;
+                }
+                write_tag(f, line, end_wc, format_args!("endif"))
+            }
+            Node::UnlessTag {
+                wc,
+                condition,
+                block,
+                alternatives,
+                default,
+                end_wc,
+                ..
+            } => {
+                write_tag(f, line, wc, format_args!("unless {condition}"))?;
+                write_block(f, block, line)?;
+                for alternative in alternatives {
+                    alternative.write_markup(f, line)?;
+                }
+                if let Some(default) = default {
+                    default.write_markup(f, line)?;
+                }
+                write_tag(f, line, end_wc, format_args!("endunless"))
+            }
+            Node::IfchangedTag {
+                wc, block, end_wc, ..
+            } => {
+                write_tag(f, line, wc, format_args!("ifchanged"))?;
+                write_block(f, block, line)?;
+                write_tag(f, line, end_wc, format_args!("endifchanged"))
+            }
+            Node::IncludeTag {
+                wc,
+                target,
+                repeat,
+                variable,
+                alias,
+                args,
+                ..
+            } => write_tag(
+                f,
+                line,
+                wc,
+                format_args!(
+                    "include {}{}",
+                    target,
+                    partial_args(*repeat, variable, alias, args)
+                ),
+            ),
+            Node::RenderTag {
+                wc,
+                target,
+                repeat,
+                variable,
+                alias,
+                args,
+                ..
+            } => write_tag(
+                f,
+                line,
+                wc,
+                format_args!(
+                    "render {}{}",
+                    target,
+                    partial_args(*repeat, variable, alias, args)
+                ),
+            ),
+            Node::LiquidTag { wc, block, .. } => {
+                if block.is_empty() {
+                    write!(f, "{{%{} liquid {}%}}", wc.0, wc.1)
+                } else {
+                    // Line statements are indented one level more than the
+                    // line the tag starts on.
+                    let margin = f.margin.clone();
+                    writeln!(f, "{{%{} liquid", wc.0)?;
+                    let indent = std::mem::replace(&mut f.indent, margin.clone());
+                    write_block(f, block, true)?;
+                    f.indent = indent;
+                    write!(f, "{margin}{}%}}", wc.1)
+                }
+            }
+            Node::ExtendsTag { wc, name, .. } => {
+                write_tag(f, line, wc, format_args!("extends {}", Quoted(name)))
+            }
+            Node::BlockTag {
+                wc,
+                name,
+                scoped,
+                required,
+                block,
+                end_wc,
+                named_end,
+                ..
+            } => {
+                write_tag(
+                    f,
+                    line,
+                    wc,
+                    format_args!(
+                        "block {name}{}{}",
+                        if *scoped { " scoped" } else { "" },
+                        if *required { " required" } else { "" }
+                    ),
+                )?;
+                write_block(f, block, line)?;
+                if *named_end {
+                    write_tag(f, line, end_wc, format_args!("endblock {name}"))
+                } else {
+                    write_tag(f, line, end_wc, format_args!("endblock"))
+                }
+            }
+            Node::MacroTag {
+                wc,
+                name,
+                params,
+                block,
+                end_wc,
+                ..
+            } => {
+                if params.is_empty() {
+                    write_tag(f, line, wc, format_args!("macro {}", Quoted(name)))?;
+                } else {
+                    write_tag(
+                        f,
+                        line,
+                        wc,
+                        format_args!("macro {} {}", Quoted(name), join(params, ", ")),
+                    )?;
+                }
+                write_block(f, block, line)?;
+                write_tag(f, line, end_wc, format_args!("endmacro"))
+            }
+            Node::CallTag { wc, name, args, .. } => {
+                if args.is_empty() {
+                    write_tag(f, line, wc, format_args!("call {}", Quoted(name)))
+                } else {
+                    write_tag(
+                        f,
+                        line,
+                        wc,
+                        format_args!("call {} {}", Quoted(name), join(args, ", ")),
+                    )
+                }
+            }
+            Node::TranslateTag {
+                wc,
+                name,
+                args,
+                block,
+                plural,
+                end_wc,
+                ..
+            } => {
+                if args.is_empty() {
+                    write_tag(f, line, wc, format_args!("{name}"))?;
+                } else {
+                    write_tag(f, line, wc, format_args!("{name} {}", join(args, ", ")))?;
+                }
+                write_block(f, block, line)?;
+                if let Some(plural) = plural {
+                    plural.write_markup(f, line)?;
+                }
+                write_tag(f, line, end_wc, format_args!("end{name}"))
+            }
+            Node::TagExtension {
+                wc,
+                name,
+                expression,
+                block,
+                branches,
+                end,
+                ..
+            } => {
+                write_tag(
+                    f,
+                    line,
+                    wc,
+                    format_args!("{name}{}", tag_expression(expression)),
+                )?;
+                write_block(f, block, line)?;
+                for branch in branches {
+                    branch.write_markup(f, line)?;
+                }
+                if let Some(end) = end {
+                    write_tag(f, line, &end.wc, format_args!("{}", end.name))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A writer for template markup that counts the bytes written, and records
+/// the output of each node when building a source map.
+struct MarkupWriter<'a> {
+    out: &'a mut dyn fmt::Write,
+    len: usize,
+    mappings: Option<Vec<Mapping>>,
+    /// The whitespace at the start of the line being written.
+    margin: String,
+    /// Whether only whitespace has been written on the current line.
+    blank: bool,
+    /// The whitespace written before each `liquid` tag line statement.
+    indent: String,
+}
+
+/// How much further each block in a `liquid` tag is indented.
+const INDENT: &str = "  ";
+
+impl<'a> MarkupWriter<'a> {
+    fn new(out: &'a mut dyn fmt::Write) -> Self {
+        Self {
+            out,
+            len: 0,
+            mappings: None,
+            margin: String::new(),
+            blank: true,
+            indent: String::new(),
+        }
+    }
+
+    fn write_indent(&mut self) -> fmt::Result {
+        let indent = self.indent.clone();
+        self.write_str(&indent)
+    }
+
+    fn record(&mut self, start: usize, source: Span) {
+        if let Some(mappings) = &mut self.mappings {
+            if self.len > start {
+                mappings.push(Mapping {
+                    output: Span::new(start, self.len),
+                    source,
+                    template: None,
+                });
+            }
+        }
+    }
+}
+
+impl fmt::Write for MarkupWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let line = match s.rfind('\n') {
+            Some(i) => {
+                self.margin.clear();
+                self.blank = true;
+                &s[i + 1..]
+            }
+            None => s,
+        };
+        if self.blank {
+            let text = line.trim_start();
+            self.margin.push_str(&line[..line.len() - text.len()]);
+            self.blank = text.is_empty();
+        }
+
+        self.len += s.len();
+        self.out.write_str(s)
+    }
+}
+
+fn write_tag(
+    f: &mut MarkupWriter<'_>,
+    line: bool,
+    wc: &(Whitespace, Whitespace),
+    expr: fmt::Arguments<'_>,
+) -> fmt::Result {
+    if line {
+        f.write_indent()?;
+        writeln!(f, "{expr}")
+    } else {
+        write!(f, "{{%{} {} {}%}}", wc.0, expr, wc.1)
+    }
+}
+
+fn write_nodes(f: &mut MarkupWriter<'_>, nodes: &[Node], line: bool) -> fmt::Result {
+    for node in nodes {
+        let start = f.len;
+        node.write_markup(f, line)?;
+        f.record(start, node.span());
+    }
+    Ok(())
+}
+
+/// Write the nodes in a tag's block, indented one level more than the tag
+/// if they are line statements.
+fn write_block(f: &mut MarkupWriter<'_>, nodes: &[Node], line: bool) -> fmt::Result {
+    if !line {
+        return write_nodes(f, nodes, false);
+    }
+    f.indent.push_str(INDENT);
+    let rv = write_nodes(f, nodes, true);
+    f.indent.truncate(f.indent.len() - INDENT.len());
+    rv
+}
+
+fn join<T: fmt::Display>(items: &[T], sep: &str) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>()
+        .join(sep)
+}
+
+/// Format the tokens of an extension tag's expression, with a leading space
+/// if there are any.
+pub(crate) fn tag_expression(tokens: &[Token]) -> String {
+    let mut rv = String::new();
+    let mut previous: Option<&Token> = None;
+    for token in tokens {
+        let space = !matches!(
+            token,
+            Token::Comma { .. } | Token::Colon { .. } | Token::RightParen { .. }
+        ) && !matches!(previous, Some(Token::LeftParen { .. }));
+        if space {
+            rv.push(' ');
+        }
+        rv.push_str(&token.to_string());
+        previous = Some(token);
+    }
+    rv
+}
+
+fn partial_args(
+    repeat: bool,
+    variable: &Option<Primitive>,
+    alias: &Option<String>,
+    args: &[KeywordArgument],
+) -> String {
+    let mut rv = String::new();
+
+    if let Some(variable) = variable {
+        rv.push_str(&format!(
+            " {} {}",
+            if repeat { "for" } else { "with" },
+            variable
+        ));
+        if let Some(alias) = alias {
+            rv.push_str(&format!(" as {alias}"));
+        }
+    }
+
+    if !args.is_empty() {
+        rv.push_str(", ");
+        rv.push_str(&join(args, ", "));
+    }
+
+    rv
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElsifTag {
+    pub wc: (Whitespace, Whitespace),
+    pub condition: BooleanExpression,
+    pub block: SharedVec<Node>,
+    pub span: Span,
+}
+
+impl ElsifTag {
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        write_tag(f, line, &self.wc, format_args!("elsif {}", self.condition))?;
+        write_block(f, &self.block, line)
+    }
+}
+
+impl fmt::Display for ElsifTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ElsifTag {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+/// An intermediate tag inside an extension tag's block, and the nodes that
+/// follow it.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionBranch {
+    pub wc: (Whitespace, Whitespace),
+    pub name: String,
+    pub expression: Vec<Token>,
+    pub block: SharedVec<Node>,
+    pub span: Span,
+}
+
+impl ExtensionBranch {
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        write_tag(
+            f,
+            line,
+            &self.wc,
+            format_args!("{}{}", self.name, tag_expression(&self.expression)),
+        )?;
+        write_block(f, &self.block, line)
+    }
+}
+
+impl fmt::Display for ExtensionBranch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ExtensionBranch {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+/// The tag that closed an extension tag's block.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionEnd {
+    pub wc: (Whitespace, Whitespace),
+    pub name: String,
+    pub span: Span,
+}
+
+impl fmt::Display for ExtensionEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tag(
+            &mut MarkupWriter::new(f),
+            false,
+            &self.wc,
+            format_args!("{}", self.name),
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ExtensionEnd {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElseTag {
+    pub wc: (Whitespace, Whitespace),
+    pub block: SharedVec<Node>,
+    pub span: Span,
+}
+
+impl ElseTag {
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        write_tag(f, line, &self.wc, format_args!("else"))?;
+        write_block(f, &self.block, line)
+    }
+}
+
+impl fmt::Display for ElseTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ElseTag {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+/// The plural form of a `translate` tag's message.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluralTag {
+    pub wc: (Whitespace, Whitespace),
+    pub block: SharedVec<Node>,
+    pub span: Span,
+}
+
+impl PluralTag {
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        write_tag(f, line, &self.wc, format_args!("plural"))?;
+        write_block(f, &self.block, line)
+    }
+}
+
+impl fmt::Display for PluralTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PluralTag {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhenTag {
+    pub wc: (Whitespace, Whitespace),
+    pub args: Vec<Primitive>,
+    pub block: SharedVec<Node>,
+    pub span: Span,
+}
+
+impl WhenTag {
+    fn write_markup(&self, f: &mut MarkupWriter<'_>, line: bool) -> fmt::Result {
+        write_tag(
+            f,
+            line,
+            &self.wc,
+            format_args!("when {}", join(&self.args, ", ")),
+        )?;
+        write_block(f, &self.block, line)
+    }
+}
+
+impl fmt::Display for WhenTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_markup(&mut MarkupWriter::new(f), false)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl WhenTag {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredExpression {
+    pub left: Primitive,
+    pub filters: Option<Vec<Filter>>,
+    pub condition: Option<InlineCondition>,
+    pub span: Span,
+}
+
+impl fmt::Display for FilteredExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.left)?;
+        if let Some(filters) = &self.filters {
+            write_filters(f, " | ", filters)?;
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " {condition}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FilteredExpression {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+fn write_filters(f: &mut fmt::Formatter<'_>, delim: &str, filters: &[Filter]) -> fmt::Result {
+    for filter in filters {
+        write!(f, "{delim}{filter}")?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineCondition {
+    pub expr: BooleanExpression,
+    pub alternative: Option<Primitive>,
+    pub alternative_filters: Option<Vec<Filter>>,
+    /// The condition of a chained `?:` that follows _alternative_ and its
+    /// filters, testing _alternative_. `a ?: b ?: c` is `a ?: (b ?: c)`.
+    pub alternative_condition: Option<Box<InlineCondition>>,
+    pub tail_filters: Option<Vec<Filter>>,
+    /// `true` if this condition was written with the `?:` operator, which
+    /// tests the expression's left value. `a ?: b` is equivalent to
+    /// `a if a else b`.
+    pub elvis: bool,
+    pub span: Span,
+}
+
+impl InlineCondition {
+    /// This condition followed by the conditions of chained `?:` operators.
+    pub fn chain(&self) -> impl Iterator<Item = &InlineCondition> {
+        std::iter::successors(Some(self), |condition| {
+            condition.alternative_condition.as_deref()
+        })
+    }
+}
+
+impl fmt::Display for InlineCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.elvis {
+            f.write_str("?:")?;
+        } else {
+            write!(f, "if {}", self.expr)?;
+        }
+        if let Some(alternative) = &self.alternative {
+            f.write_str(if self.elvis { " " } else { " else " })?;
+            write!(f, "{alternative}")?;
+            if let Some(filters) = &self.alternative_filters {
+                write_filters(f, " | ", filters)?;
+            }
+        }
+        if let Some(condition) = &self.alternative_condition {
+            write!(f, " {condition}")?;
+        }
+        if let Some(filters) = &self.tail_filters {
+            write_filters(f, " || ", filters)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl InlineCondition {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Filter {
+    pub name: String,
+    pub args: Option<Vec<CommonArgument>>,
+    pub span: Span,
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.args {
+            Some(args) if !args.is_empty() => write!(f, "{}: {}", self.name, join(args, ", ")),
+            _ => f.write_str(&self.name),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Filter {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+/// A positional or keyword argument to a filter.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommonArgument {
+    pub value: Primitive,
+    pub name: Option<String>,
+    pub span: Span,
+}
+
+impl fmt::Display for CommonArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{}:{}", name, self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CommonArgument {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+/// A macro parameter, with an optional default value.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Primitive>,
+    pub span: Span,
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.default {
+            Some(default) => write!(f, "{}:{}", self.name, default),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Parameter {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordArgument {
+    pub name: String,
+    pub value: Primitive,
+    pub span: Span,
+}
+
+impl fmt::Display for KeywordArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.value)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl KeywordArgument {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Primitive {
+    TrueLiteral {
+        span: Span,
+    },
+    FalseLiteral {
+        span: Span,
+    },
+    NullLiteral {
+        span: Span,
+    },
+    Empty {
+        span: Span,
+    },
+    Blank {
+        span: Span,
+    },
+    Integer {
+        value: i64,
+        span: Span,
+    },
+    Float {
+        value: f64,
+        span: Span,
+    },
+    StringLiteral {
+        value: String,
+        span: Span,
+    },
+    Range {
+        start: Box<Primitive>,
+        stop: Box<Primitive>,
+        step: Option<Box<Primitive>>,
+        span: Span,
+    },
+    Query {
+        path: Query,
+        span: Span,
+    },
+    /// An arrow function filter argument, like `item => item.price > 10`.
+    /// The first parameter is bound to each item and the optional second
+    /// parameter to the item's index.
+    Lambda {
+        params: Vec<String>,
+        expr: Box<BooleanExpression>,
+        span: Span,
+    },
+}
+
+impl Primitive {
+    pub fn span(&self) -> Span {
+        match self {
+            Primitive::TrueLiteral { span }
+            | Primitive::FalseLiteral { span }
+            | Primitive::NullLiteral { span }
+            | Primitive::Empty { span }
+            | Primitive::Blank { span }
+            | Primitive::Integer { span, .. }
+            | Primitive::Float { span, .. }
+            | Primitive::StringLiteral { span, .. }
+            | Primitive::Range { span, .. }
+            | Primitive::Query { span, .. }
+            | Primitive::Lambda { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Primitive::TrueLiteral { .. } => f.write_str("true"),
+            Primitive::FalseLiteral { .. } => f.write_str("false"),
+            Primitive::NullLiteral { .. } => f.write_str("null"),
+            Primitive::Empty { .. } => f.write_str("empty"),
+            Primitive::Blank { .. } => f.write_str("blank"),
+            Primitive::Integer { value, .. } => write!(f, "{value}"),
+            // Keep the fractional part of whole numbers, so they are read
+            // back as floats.
+            Primitive::Float { value, .. } if value.fract() == 0.0 => write!(f, "{value:.1}"),
+            Primitive::Float { value, .. } => write!(f, "{value}"),
+            Primitive::StringLiteral { value, .. } => Quoted(value).fmt(f),
+            Primitive::Range {
+                start,
+                stop,
+                step: Some(step),
+                ..
+            } => write!(f, "({start}..{stop}..{step})"),
+            Primitive::Range { start, stop, .. } => write!(f, "({start}..{stop})"),
+            Primitive::Query { path, .. } => path.fmt_implicit_root(f),
+            Primitive::Lambda { params, expr, .. } => match params.as_slice() {
+                [param] => write!(f, "{param} => {expr}"),
+                params => write!(f, "({}) => {expr}", params.join(", ")),
+            },
+        }
+    }
+}
+
+/// Formats a string as a single-quoted string literal, escaping quotes,
+/// backslashes and control characters.
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_quoted(f, self.0, '\'')
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Primitive {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span()))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BooleanExpression {
+    Primitive {
+        expr: Primitive,
+    },
+    LogicalNot {
+        expr: Box<BooleanExpression>,
+        span: Span,
+    },
+    Logical {
+        left: Box<BooleanExpression>,
+        operator: BooleanOperator,
+        right: Box<BooleanExpression>,
+        span: Span,
+    },
+    Comparison {
+        left: Box<BooleanExpression>,
+        operator: CompareOperator,
+        right: Box<BooleanExpression>,
+        span: Span,
+    },
+}
+
+impl BooleanExpression {
+    pub fn span(&self) -> Span {
+        match self {
+            BooleanExpression::Primitive { expr } => expr.span(),
+            BooleanExpression::LogicalNot { span, .. }
+            | BooleanExpression::Logical { span, .. }
+            | BooleanExpression::Comparison { span, .. } => *span,
+        }
+    }
+
+    /// How tightly this expression's operator binds. `not` applies to
+    /// everything after it, so it binds loosest of all.
+    fn precedence(&self) -> u8 {
+        match self {
+            BooleanExpression::Primitive { .. } => u8::MAX,
+            BooleanExpression::LogicalNot { .. } => 0,
+            BooleanExpression::Logical {
+                operator: BooleanOperator::Or,
+                ..
+            } => PRECEDENCE_LOGICAL_OR,
+            BooleanExpression::Logical {
+                operator: BooleanOperator::And,
+                ..
+            } => PRECEDENCE_LOGICAL_AND,
+            BooleanExpression::Comparison {
+                operator: CompareOperator::Contains | CompareOperator::In,
+                ..
+            } => PRECEDENCE_MEMBERSHIP,
+            BooleanExpression::Comparison { .. } => PRECEDENCE_RELATIONAL,
+        }
+    }
+
+    /// Write _operand_, the left or right operand of this expression, with
+    /// parentheses only if it would be parsed differently without them.
+    /// Binary operators group to the right.
+    fn fmt_operand(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        operand: &BooleanExpression,
+        left: bool,
+    ) -> fmt::Result {
+        let grouped = match operand {
+            BooleanExpression::LogicalNot { .. } => left,
+            _ if left => operand.precedence() <= self.precedence(),
+            _ => operand.precedence() < self.precedence(),
+        };
+        if grouped {
+            write!(f, "({operand})")
+        } else {
+            write!(f, "{operand}")
+        }
+    }
+}
+
+impl fmt::Display for BooleanExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BooleanExpression::Primitive { expr } => write!(f, "{expr}"),
+            BooleanExpression::LogicalNot { expr, .. } => write!(f, "not {expr}"),
+            BooleanExpression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.fmt_operand(f, left, true)?;
+                write!(f, " {operator} ")?;
+                self.fmt_operand(f, right, false)
+            }
+            BooleanExpression::Comparison {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.fmt_operand(f, left, true)?;
+                write!(f, " {operator} ")?;
+                self.fmt_operand(f, right, false)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl BooleanExpression {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span()))
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOperator {
+    And,
+    Or,
+}
+
+impl fmt::Display for BooleanOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BooleanOperator::And => f.write_str("and"),
+            BooleanOperator::Or => f.write_str("or"),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl BooleanOperator {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOperator {
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Contains,
+    In,
+}
+
+impl fmt::Display for CompareOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompareOperator::Eq => f.write_str("=="),
+            CompareOperator::Ne => f.write_str("!="),
+            CompareOperator::Ge => f.write_str(">="),
+            CompareOperator::Gt => f.write_str(">"),
+            CompareOperator::Le => f.write_str("<="),
+            CompareOperator::Lt => f.write_str("<"),
+            CompareOperator::Contains => f.write_str("contains"),
+            CompareOperator::In => f.write_str("in"),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CompareOperator {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Read-only traversal of a syntax tree.
+///
+/// Every method has a default implementation that visits the item's
+/// children, using the matching `walk_*` function. Override the methods for
+/// the items you're interested in, calling `walk_*` from your override to
+/// keep descending.
+pub trait Visitor {
+    fn visit_template(&mut self, template: &Template) {
+        self.visit_block(&template.nodes);
+    }
+
+    fn visit_block(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit_node(node);
+        }
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    fn visit_filtered_expression(&mut self, expression: &FilteredExpression) {
+        walk_filtered_expression(self, expression);
+    }
+
+    fn visit_filter(&mut self, filter: &Filter) {
+        walk_filter(self, filter);
+    }
+
+    fn visit_boolean_expression(&mut self, expression: &BooleanExpression) {
+        walk_boolean_expression(self, expression);
+    }
+
+    fn visit_primitive(&mut self, primitive: &Primitive) {
+        walk_primitive(self, primitive);
+    }
+
+    fn visit_query(&mut self, query: &Query) {
+        walk_query(self, query);
+    }
+
+    fn visit_filter_expression(&mut self, expression: &FilterExpression) {
+        walk_filter_expression(self, expression);
+    }
+}
+
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    match node {
+        Node::Content { .. }
+        | Node::Raw { .. }
+        | Node::Comment { .. }
+        | Node::DocTag { .. }
+        | Node::DecrementTag { .. }
+        | Node::IncrementTag { .. }
+        | Node::BreakTag { .. }
+        | Node::ContinueTag { .. }
+        | Node::ExtendsTag { .. } => (),
+        Node::Output { expression, .. }
+        | Node::EchoTag { expression, .. }
+        | Node::AssignTag { expression, .. } => visitor.visit_filtered_expression(expression),
+        Node::CaptureTag { block, .. }
+        | Node::LiquidTag { block, .. }
+        | Node::BlockTag { block, .. }
+        | Node::IfchangedTag { block, .. } => visitor.visit_block(block),
+        Node::CaseTag {
+            expression,
+            whens,
+            default,
+            ..
+        } => {
+            visitor.visit_primitive(expression);
+            for when in whens {
+                when.args
+                    .iter()
+                    .for_each(|arg| visitor.visit_primitive(arg));
+                visitor.visit_block(&when.block);
+            }
+            if let Some(default) = default {
+                visitor.visit_block(&default.block);
+            }
+        }
+        Node::CycleTag { name, args, .. } => {
+            name.iter().for_each(|p| visitor.visit_primitive(p));
+            args.iter().for_each(|arg| visitor.visit_primitive(arg));
+        }
+        Node::ForTag {
+            iterable,
+            limit,
+            offset,
+            block,
+            default,
+            ..
+        } => {
+            visitor.visit_primitive(iterable);
+            limit.iter().for_each(|p| visitor.visit_primitive(p));
+            offset.iter().for_each(|p| visitor.visit_primitive(p));
+            visitor.visit_block(block);
+            if let Some(default) = default {
+                visitor.visit_block(&default.block);
+            }
+        }
+        Node::TablerowTag {
+            iterable,
+            cols,
+            limit,
+            offset,
+            block,
+            ..
+        } => {
+            visitor.visit_primitive(iterable);
+            cols.iter().for_each(|p| visitor.visit_primitive(p));
+            limit.iter().for_each(|p| visitor.visit_primitive(p));
+            offset.iter().for_each(|p| visitor.visit_primitive(p));
+            visitor.visit_block(block);
+        }
+        Node::IfTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        }
+        | Node::UnlessTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        } => {
+            visitor.visit_boolean_expression(condition);
+            visitor.visit_block(block);
+            for alternative in alternatives {
+                visitor.visit_boolean_expression(&alternative.condition);
+                visitor.visit_block(&alternative.block);
+            }
+            if let Some(default) = default {
+                visitor.visit_block(&default.block);
+            }
+        }
+        Node::IncludeTag {
+            target,
+            variable,
+            args,
+            ..
+        } => {
+            visitor.visit_primitive(target);
+            variable.iter().for_each(|p| visitor.visit_primitive(p));
+            args.iter()
+                .for_each(|arg| visitor.visit_primitive(&arg.value));
+        }
+        Node::RenderTag {
+            target,
+            variable,
+            args,
+            ..
+        } => {
+            visitor.visit_primitive(target);
+            variable.iter().for_each(|p| visitor.visit_primitive(p));
+            args.iter()
+                .for_each(|arg| visitor.visit_primitive(&arg.value));
+        }
+        Node::MacroTag { params, block, .. } => {
+            for param in params {
+                param
+                    .default
+                    .iter()
+                    .for_each(|p| visitor.visit_primitive(p));
+            }
+            visitor.visit_block(block);
+        }
+        Node::CallTag { args, .. } => args
+            .iter()
+            .for_each(|arg| visitor.visit_primitive(&arg.value)),
+        Node::TranslateTag {
+            args,
+            block,
+            plural,
+            ..
+        } => {
+            args.iter()
+                .for_each(|arg| visitor.visit_primitive(&arg.value));
+            visitor.visit_block(block);
+            if let Some(plural) = plural {
+                visitor.visit_block(&plural.block);
+            }
+        }
+        Node::TagExtension {
+            args,
+            block,
+            branches,
+            ..
+        } => {
+            for arg in args.iter().flatten() {
+                visitor.visit_primitive(&arg.value);
+            }
+            visitor.visit_block(block);
+            for branch in branches {
+                visitor.visit_block(&branch.block);
+            }
+        }
+    }
+}
+
+pub fn walk_filtered_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    expression: &FilteredExpression,
+) {
+    visitor.visit_primitive(&expression.left);
+    expression
+        .filters
+        .iter()
+        .flatten()
+        .for_each(|f| visitor.visit_filter(f));
+    for condition in expression.condition.iter().flat_map(InlineCondition::chain) {
+        visitor.visit_boolean_expression(&condition.expr);
+        if let Some(alternative) = &condition.alternative {
+            visitor.visit_primitive(alternative);
+        }
+        for filter in condition
+            .alternative_filters
+            .iter()
+            .chain(condition.tail_filters.iter())
+            .flatten()
+        {
+            visitor.visit_filter(filter);
+        }
+    }
+}
+
+pub fn walk_filter<V: Visitor + ?Sized>(visitor: &mut V, filter: &Filter) {
+    for arg in filter.args.iter().flatten() {
+        visitor.visit_primitive(&arg.value);
+    }
+}
+
+pub fn walk_boolean_expression<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    expression: &BooleanExpression,
+) {
+    match expression {
+        BooleanExpression::Primitive { expr } => visitor.visit_primitive(expr),
+        BooleanExpression::LogicalNot { expr, .. } => visitor.visit_boolean_expression(expr),
+        BooleanExpression::Logical { left, right, .. }
+        | BooleanExpression::Comparison { left, right, .. } => {
+            visitor.visit_boolean_expression(left);
+            visitor.visit_boolean_expression(right);
+        }
+    }
+}
+
+pub fn walk_primitive<V: Visitor + ?Sized>(visitor: &mut V, primitive: &Primitive) {
+    match primitive {
+        Primitive::Range {
+            start, stop, step, ..
+        } => {
+            visitor.visit_primitive(start);
+            visitor.visit_primitive(stop);
+            if let Some(step) = step {
+                visitor.visit_primitive(step);
+            }
+        }
+        Primitive::Query { path, .. } => visitor.visit_query(path),
+        Primitive::Lambda { expr, .. } => visitor.visit_boolean_expression(expr),
+        _ => (),
+    }
+}
+
+pub fn walk_query<V: Visitor + ?Sized>(visitor: &mut V, query: &Query) {
+    for segment in &query.segments {
+        if let Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } = segment {
+            for selector in selectors {
+                match selector {
+                    Selector::SingularQuery { query, .. } => visitor.visit_query(query),
+                    Selector::Filter { expression, .. } => {
+                        visitor.visit_filter_expression(expression)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_filter_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &FilterExpression) {
+    match expression {
+        FilterExpression::Not { expression, .. } => visitor.visit_filter_expression(expression),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            visitor.visit_filter_expression(left);
+            visitor.visit_filter_expression(right);
+        }
+        FilterExpression::RelativeQuery { query, .. }
+        | FilterExpression::RootQuery { query, .. } => visitor.visit_query(query),
+        FilterExpression::Function { args, .. } => args
+            .iter()
+            .for_each(|arg| visitor.visit_filter_expression(arg)),
+        _ => (),
+    }
+}
+
+/// In-place rewriting of a syntax tree.
+///
+/// Like `Visitor`, but with mutable access, so implementations can replace
+/// nodes and expressions, or add and remove nodes from a block in
+/// `transform_block`. Default implementations call the matching `walk_*_mut`
+/// function.
+pub trait Transformer {
+    fn transform_template(&mut self, template: &mut Template) {
+        self.transform_block(&mut template.nodes);
+    }
+
+    fn transform_block(&mut self, nodes: &mut Vec<Node>) {
+        for node in nodes {
+            self.transform_node(node);
+        }
+    }
+
+    fn transform_node(&mut self, node: &mut Node) {
+        walk_node_mut(self, node);
+    }
+
+    fn transform_filtered_expression(&mut self, expression: &mut FilteredExpression) {
+        walk_filtered_expression_mut(self, expression);
+    }
+
+    fn transform_filter(&mut self, filter: &mut Filter) {
+        walk_filter_mut(self, filter);
+    }
+
+    fn transform_boolean_expression(&mut self, expression: &mut BooleanExpression) {
+        walk_boolean_expression_mut(self, expression);
+    }
+
+    fn transform_primitive(&mut self, primitive: &mut Primitive) {
+        walk_primitive_mut(self, primitive);
+    }
+
+    fn transform_query(&mut self, query: &mut Query) {
+        walk_query_mut(self, query);
+    }
+
+    fn transform_filter_expression(&mut self, expression: &mut FilterExpression) {
+        walk_filter_expression_mut(self, expression);
+    }
+}
+
+pub fn walk_node_mut<T: Transformer + ?Sized>(transformer: &mut T, node: &mut Node) {
+    match node {
+        Node::Content { .. }
+        | Node::Raw { .. }
+        | Node::Comment { .. }
+        | Node::DocTag { .. }
+        | Node::DecrementTag { .. }
+        | Node::IncrementTag { .. }
+        | Node::BreakTag { .. }
+        | Node::ContinueTag { .. }
+        | Node::ExtendsTag { .. } => (),
+        Node::Output { expression, .. }
+        | Node::EchoTag { expression, .. }
+        | Node::AssignTag { expression, .. } => {
+            transformer.transform_filtered_expression(expression)
+        }
+        Node::CaptureTag { block, .. }
+        | Node::LiquidTag { block, .. }
+        | Node::BlockTag { block, .. }
+        | Node::IfchangedTag { block, .. } => transformer.transform_block(block),
+        Node::CaseTag {
+            expression,
+            whens,
+            default,
+            ..
+        } => {
+            transformer.transform_primitive(expression);
+            for when in whens {
+                when.args
+                    .iter_mut()
+                    .for_each(|arg| transformer.transform_primitive(arg));
+                transformer.transform_block(&mut when.block);
+            }
+            if let Some(default) = default {
+                transformer.transform_block(&mut default.block);
+            }
+        }
+        Node::CycleTag { name, args, .. } => {
+            name.iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            args.iter_mut()
+                .for_each(|arg| transformer.transform_primitive(arg));
+        }
+        Node::ForTag {
+            iterable,
+            limit,
+            offset,
+            block,
+            default,
+            ..
+        } => {
+            transformer.transform_primitive(iterable);
+            limit
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            offset
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            transformer.transform_block(block);
+            if let Some(default) = default {
+                transformer.transform_block(&mut default.block);
+            }
+        }
+        Node::TablerowTag {
+            iterable,
+            cols,
+            limit,
+            offset,
+            block,
+            ..
+        } => {
+            transformer.transform_primitive(iterable);
+            cols.iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            limit
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            offset
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            transformer.transform_block(block);
+        }
+        Node::IfTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        }
+        | Node::UnlessTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        } => {
+            transformer.transform_boolean_expression(condition);
+            transformer.transform_block(block);
+            for alternative in alternatives {
+                transformer.transform_boolean_expression(&mut alternative.condition);
+                transformer.transform_block(&mut alternative.block);
+            }
+            if let Some(default) = default {
+                transformer.transform_block(&mut default.block);
+            }
+        }
+        Node::IncludeTag {
+            target,
+            variable,
+            args,
+            ..
+        } => {
+            transformer.transform_primitive(target);
+            variable
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            args.iter_mut()
+                .for_each(|arg| transformer.transform_primitive(&mut arg.value));
+        }
+        Node::RenderTag {
+            target,
+            variable,
+            args,
+            ..
+        } => {
+            transformer.transform_primitive(target);
+            variable
+                .iter_mut()
+                .for_each(|p| transformer.transform_primitive(p));
+            args.iter_mut()
+                .for_each(|arg| transformer.transform_primitive(&mut arg.value));
+        }
+        Node::MacroTag { params, block, .. } => {
+            for param in params {
+                param
+                    .default
+                    .iter_mut()
+                    .for_each(|p| transformer.transform_primitive(p));
+            }
+            transformer.transform_block(block);
+        }
+        Node::CallTag { args, .. } => args
+            .iter_mut()
+            .for_each(|arg| transformer.transform_primitive(&mut arg.value)),
+        Node::TranslateTag {
+            args,
+            block,
+            plural,
+            ..
+        } => {
+            args.iter_mut()
+                .for_each(|arg| transformer.transform_primitive(&mut arg.value));
+            transformer.transform_block(block);
+            if let Some(plural) = plural {
+                transformer.transform_block(&mut plural.block);
+            }
+        }
+        Node::TagExtension {
+            args,
+            block,
+            branches,
+            ..
+        } => {
+            for arg in args.iter_mut().flatten() {
+                transformer.transform_primitive(&mut arg.value);
+            }
+            transformer.transform_block(block);
+            for branch in branches {
+                transformer.transform_block(&mut branch.block);
+            }
+        }
+    }
+}
+
+pub fn walk_filtered_expression_mut<T: Transformer + ?Sized>(
+    transformer: &mut T,
+    expression: &mut FilteredExpression,
+) {
+    transformer.transform_primitive(&mut expression.left);
+    expression
+        .filters
+        .iter_mut()
+        .flatten()
+        .for_each(|f| transformer.transform_filter(f));
+    let mut next = expression.condition.as_mut();
+    while let Some(condition) = next {
+        transformer.transform_boolean_expression(&mut condition.expr);
+        if let Some(alternative) = &mut condition.alternative {
+            transformer.transform_primitive(alternative);
+        }
+        for filter in condition
+            .alternative_filters
+            .iter_mut()
+            .chain(condition.tail_filters.iter_mut())
+            .flatten()
+        {
+            transformer.transform_filter(filter);
+        }
+        next = condition.alternative_condition.as_deref_mut();
+    }
+}
+
+pub fn walk_filter_mut<T: Transformer + ?Sized>(transformer: &mut T, filter: &mut Filter) {
+    for arg in filter.args.iter_mut().flatten() {
+        transformer.transform_primitive(&mut arg.value);
+    }
+}
+
+pub fn walk_boolean_expression_mut<T: Transformer + ?Sized>(
+    transformer: &mut T,
+    expression: &mut BooleanExpression,
+) {
+    match expression {
+        BooleanExpression::Primitive { expr } => transformer.transform_primitive(expr),
+        BooleanExpression::LogicalNot { expr, .. } => {
+            transformer.transform_boolean_expression(expr)
+        }
+        BooleanExpression::Logical { left, right, .. }
+        | BooleanExpression::Comparison { left, right, .. } => {
+            transformer.transform_boolean_expression(left);
+            transformer.transform_boolean_expression(right);
+        }
+    }
+}
+
+pub fn walk_primitive_mut<T: Transformer + ?Sized>(transformer: &mut T, primitive: &mut Primitive) {
+    match primitive {
+        Primitive::Range {
+            start, stop, step, ..
+        } => {
+            transformer.transform_primitive(start);
+            transformer.transform_primitive(stop);
+            if let Some(step) = step {
+                transformer.transform_primitive(step);
+            }
+        }
+        Primitive::Query { path, .. } => transformer.transform_query(path),
+        Primitive::Lambda { expr, .. } => transformer.transform_boolean_expression(expr),
+        _ => (),
+    }
+}
+
+pub fn walk_query_mut<T: Transformer + ?Sized>(transformer: &mut T, query: &mut Query) {
+    for segment in &mut query.segments {
+        if let Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } = segment {
+            for selector in selectors {
+                match selector {
+                    Selector::SingularQuery { query, .. } => transformer.transform_query(query),
+                    Selector::Filter { expression, .. } => {
+                        transformer.transform_filter_expression(expression)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_filter_expression_mut<T: Transformer + ?Sized>(
+    transformer: &mut T,
+    expression: &mut FilterExpression,
+) {
+    match expression {
+        FilterExpression::Not { expression, .. } => {
+            transformer.transform_filter_expression(expression)
+        }
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            transformer.transform_filter_expression(left);
+            transformer.transform_filter_expression(right);
+        }
+        FilterExpression::RelativeQuery { query, .. }
+        | FilterExpression::RootQuery { query, .. } => transformer.transform_query(query),
+        FilterExpression::Function { args, .. } => args
+            .iter_mut()
+            .for_each(|arg| transformer.transform_filter_expression(arg)),
+        _ => (),
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'py> pyo3::FromPyObject<'py> for Box<Primitive> {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        ob.extract::<Primitive>().map(Box::new)
+    }
+}
+
+#[cfg(feature = "python")]
+impl pyo3::IntoPy<pyo3::PyObject> for Box<Primitive> {
+    fn into_py(self, py: pyo3::Python<'_>) -> pyo3::PyObject {
+        (*self).into_py(py)
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'py> pyo3::FromPyObject<'py> for Box<BooleanExpression> {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        ob.extract::<BooleanExpression>().map(Box::new)
+    }
+}
+
+#[cfg(feature = "python")]
+impl pyo3::IntoPy<pyo3::PyObject> for Box<BooleanExpression> {
+    fn into_py(self, py: pyo3::Python<'_>) -> pyo3::PyObject {
+        (*self).into_py(py)
+    }
+}
+
+#[cfg(feature = "python")]
+impl pyo3::IntoPy<pyo3::PyObject> for Box<InlineCondition> {
+    fn into_py(self, py: pyo3::Python<'_>) -> pyo3::PyObject {
+        (*self).into_py(py)
+    }
+}
@@ -0,0 +1,318 @@
+//! Check templates for constructs that a hosting platform might not want to
+//! render on behalf of its users.
+//!
+//! An `AuditPolicy` says which tags and filters are denied and how much work
+//! a template may ask for, then `AuditPolicy::audit` reports every finding
+//! in a parsed template, so a platform can reject a template when it's
+//! uploaded rather than when it's rendered.
+//!
+//! Loop and range bounds are only known when they are written as literals.
+//! Templates that loop over data can still be expensive to render, so an
+//! audit is not a substitute for render-time limits.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::ast::{
+    walk_filter, walk_node, walk_primitive, Filter, Node, Primitive, Template, Visitor,
+};
+use crate::lint::blocks;
+use crate::markup::CommentKind;
+use crate::span::Span;
+
+/// Something in a template that an `AuditPolicy` doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// A stable, kebab-case identifier for the kind of finding, like
+    /// `denied-tag`.
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+/// What templates are allowed to do.
+#[derive(Debug, Clone)]
+pub struct AuditPolicy {
+    /// Names of tags that templates may not use. Aliases are reported under
+    /// their canonical name.
+    pub denied_tags: HashSet<String>,
+    /// Names of filters that templates may not use.
+    pub denied_filters: HashSet<String>,
+    /// The most items a range literal may have, and the most times a loop
+    /// with a literal range or literal `limit` may render its block,
+    /// counting iterations of enclosing loops.
+    pub max_iterations: u64,
+    /// The most levels deep blocks may be nested.
+    pub max_depth: usize,
+    /// Whether `include` tags may name their template with an expression
+    /// instead of a string literal. The `render` tag always needs a string
+    /// literal.
+    pub dynamic_partials: bool,
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        Self {
+            denied_tags: HashSet::new(),
+            denied_filters: HashSet::new(),
+            max_iterations: 10_000,
+            max_depth: 10,
+            dynamic_partials: false,
+        }
+    }
+}
+
+impl AuditPolicy {
+    /// Report everything in _template_ that this policy doesn't allow, in
+    /// source order.
+    pub fn audit(&self, template: &Template) -> Vec<Finding> {
+        let mut auditor = Auditor {
+            policy: self,
+            depth: 0,
+            iterations: 1,
+            findings: Vec::new(),
+        };
+        auditor.visit_template(template);
+        let mut findings = auditor.findings;
+        findings.sort_by_key(|finding| (finding.span.start, finding.span.end));
+        findings
+    }
+}
+
+struct Auditor<'a> {
+    policy: &'a AuditPolicy,
+    /// How many blocks deep the current node is.
+    depth: usize,
+    /// The most times the current node could be rendered, from the literal
+    /// bounds of enclosing loops.
+    iterations: u64,
+    findings: Vec<Finding>,
+}
+
+impl Auditor<'_> {
+    fn report(&mut self, code: &'static str, message: String, span: Span) {
+        self.findings.push(Finding {
+            code,
+            message,
+            span,
+        });
+    }
+
+    /// Visit the parts of a `for` or `tablerow` tag, rendering _block_ once
+    /// for each item in _iterable_.
+    fn loop_tag(
+        &mut self,
+        iterable: &Primitive,
+        limit: Option<&Primitive>,
+        others: &[&Option<Primitive>],
+        block: &[Node],
+        span: Span,
+    ) {
+        // A range is built in full before `limit` is applied.
+        let length = range_length(iterable);
+        let bound = length.or_else(|| match limit {
+            Some(Primitive::Integer { value, .. }) => Some((*value).max(0) as u64),
+            _ => None,
+        });
+
+        if length.is_none() {
+            self.visit_primitive(iterable);
+        }
+        limit.iter().for_each(|p| self.visit_primitive(p));
+        others
+            .iter()
+            .copied()
+            .flatten()
+            .for_each(|p| self.visit_primitive(p));
+
+        let iterations = self.iterations;
+        if let Some(bound) = bound {
+            self.iterations = iterations.saturating_mul(bound);
+            if self.iterations > self.policy.max_iterations {
+                self.report(
+                    "excessive-iterations",
+                    format!(
+                        "loop could render its block {} times, the limit is {}",
+                        self.iterations, self.policy.max_iterations
+                    ),
+                    span,
+                );
+            }
+        }
+        self.visit_block(block);
+        self.iterations = iterations;
+    }
+}
+
+impl Visitor for Auditor<'_> {
+    fn visit_template(&mut self, template: &Template) {
+        for node in &template.nodes {
+            self.visit_node(node);
+        }
+    }
+
+    fn visit_block(&mut self, nodes: &[Node]) {
+        self.depth += 1;
+        for node in nodes {
+            self.visit_node(node);
+        }
+        self.depth -= 1;
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        if let Some(name) = tag_name(node) {
+            if self.policy.denied_tags.contains(name) {
+                self.report(
+                    "denied-tag",
+                    format!("the '{name}' tag is not allowed"),
+                    node.span(),
+                );
+            }
+        }
+
+        // Report only the outermost block that is too deep.
+        if self.depth == self.policy.max_depth && !blocks(node).is_empty() {
+            self.report(
+                "excessive-nesting",
+                format!(
+                    "blocks are nested more than {} levels deep",
+                    self.policy.max_depth
+                ),
+                node.span(),
+            );
+        }
+
+        match node {
+            Node::IncludeTag { target, span, .. }
+                if !self.policy.dynamic_partials
+                    && !matches!(target, Primitive::StringLiteral { .. }) =>
+            {
+                self.report(
+                    "dynamic-partial",
+                    "partial template names must be string literals".to_owned(),
+                    *span,
+                );
+                walk_node(self, node);
+            }
+            Node::ForTag {
+                iterable,
+                limit,
+                offset,
+                block,
+                default,
+                span,
+                ..
+            } => {
+                self.loop_tag(iterable, limit.as_ref(), &[offset], block, *span);
+                if let Some(default) = default {
+                    self.visit_block(&default.block);
+                }
+            }
+            Node::TablerowTag {
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                span,
+                ..
+            } => self.loop_tag(iterable, limit.as_ref(), &[cols, offset], block, *span),
+            _ => walk_node(self, node),
+        }
+    }
+
+    fn visit_filter(&mut self, filter: &Filter) {
+        if self.policy.denied_filters.contains(&filter.name) {
+            self.report(
+                "denied-filter",
+                format!("the '{}' filter is not allowed", filter.name),
+                filter.span,
+            );
+        }
+        walk_filter(self, filter);
+    }
+
+    fn visit_primitive(&mut self, primitive: &Primitive) {
+        if let (Primitive::Range { span, .. }, Some(length)) = (primitive, range_length(primitive))
+        {
+            let items = self.iterations.saturating_mul(length);
+            if items > self.policy.max_iterations {
+                self.report(
+                    "excessive-iterations",
+                    format!(
+                        "range could build {items} items, the limit is {}",
+                        self.policy.max_iterations
+                    ),
+                    *span,
+                );
+            }
+        }
+        walk_primitive(self, primitive);
+    }
+}
+
+/// The number of items in _primitive_ if it is a range with literal bounds.
+pub(crate) fn range_length(primitive: &Primitive) -> Option<u64> {
+    let Primitive::Range {
+        start, stop, step, ..
+    } = primitive
+    else {
+        return None;
+    };
+
+    let (Primitive::Integer { value: start, .. }, Primitive::Integer { value: stop, .. }) =
+        (start.as_ref(), stop.as_ref())
+    else {
+        return None;
+    };
+
+    let step = match step.as_deref() {
+        None => 1,
+        Some(Primitive::Integer { value, .. }) => *value,
+        Some(_) => return None,
+    };
+
+    if step < 1 || stop < start {
+        return Some(0);
+    }
+    Some(((*stop as i128 - *start as i128) / step as i128 + 1) as u64)
+}
+
+/// The canonical name of the tag that produced _node_, or `None` for
+/// content, output statements and `{# ... #}` comments.
+fn tag_name(node: &Node) -> Option<&str> {
+    Some(match node {
+        Node::Content { .. } | Node::Output { .. } => return None,
+        Node::Comment { kind, .. } => match kind {
+            CommentKind::Block => "comment",
+            CommentKind::Doc => "doc",
+            CommentKind::Hash | CommentKind::Inline => return None,
+        },
+        Node::Raw { .. } => "raw",
+        Node::DocTag { .. } => "doc",
+        Node::AssignTag { .. } => "assign",
+        Node::CaptureTag { .. } => "capture",
+        Node::CaseTag { .. } => "case",
+        Node::CycleTag { .. } => "cycle",
+        Node::DecrementTag { .. } => "decrement",
+        Node::IncrementTag { .. } => "increment",
+        Node::EchoTag { .. } => "echo",
+        Node::ForTag { .. } => "for",
+        Node::TablerowTag { .. } => "tablerow",
+        Node::BreakTag { .. } => "break",
+        Node::ContinueTag { .. } => "continue",
+        Node::IfTag { .. } => "if",
+        Node::UnlessTag { .. } => "unless",
+        Node::IfchangedTag { .. } => "ifchanged",
+        Node::IncludeTag { .. } => "include",
+        Node::RenderTag { .. } => "render",
+        Node::LiquidTag { .. } => "liquid",
+        Node::ExtendsTag { .. } => "extends",
+        Node::BlockTag { .. } => "block",
+        Node::MacroTag { .. } => "macro",
+        Node::CallTag { .. } => "call",
+        Node::TranslateTag { .. } => "translate",
+        Node::TagExtension { name, .. } => name,
+    })
+}
@@ -0,0 +1,313 @@
+//! Command line tools for tokenizing, parsing and validating templates.
+//!
+//! ```text
+//! liquid2 tokenize [--debug] [--dialect NAME] [FILE...]
+//! liquid2 parse [--debug] [--dialect NAME] [--common-tags] [--any-filter] [FILE...]
+//! liquid2 check [--json] [--fast] [--dialect NAME] [--common-tags] [--any-filter] [PATH...]
+//! liquid2 fmt [--check] [--dialect NAME] [--common-tags] [--any-filter] [FILE...]
+//! ```
+//!
+//! Templates are read from standard input if no files are given, or for a
+//! file called `-`. `check` searches directories for `.liquid` files.
+//!
+//! The exit status is 0 on success, 1 if any template has a syntax error, or
+//! would be changed by `fmt --check`, and 2 for usage and I/O errors.
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use _liquid2::diagnostics::Diagnostic;
+use _liquid2::dialect::Dialect;
+use _liquid2::errors::LiquidError;
+use _liquid2::json;
+use _liquid2::parser::LiquidParser;
+use _liquid2::span::LineIndex;
+
+const USAGE: &str = "usage: liquid2 <tokenize|parse|check|fmt> [options] [FILE...]
+
+commands:
+  tokenize       print markup for each template
+  parse          print the syntax tree for each template
+  check          report syntax errors, searching directories for .liquid files
+  fmt            print each template reformatted from its syntax tree
+
+options:
+  --debug        print Rust debug output instead of JSON (tokenize, parse)
+  --json         print diagnostics as a JSON array (check)
+  --check        print nothing and fail if any template would change (fmt)
+  --fast         check templates without building syntax trees (check)
+  --dialect NAME accept liquid2 (the default), strict, lax, shopify or jekyll syntax
+  --common-tags  parse unknown tags as generic tags instead of failing
+  --any-filter   accept filters that are not standard Liquid filters";
+
+enum Command {
+    Tokenize,
+    Parse,
+    Check,
+    Fmt,
+}
+
+struct Options {
+    command: Command,
+    debug: bool,
+    json: bool,
+    check: bool,
+    fast: bool,
+    dialect: Dialect,
+    common_tags: bool,
+    any_filter: bool,
+    paths: Vec<String>,
+}
+
+/// A template read from a file or standard input.
+struct Input {
+    name: String,
+    source: String,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("liquid2: {msg}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let inputs = match read_inputs(&options) {
+        Ok(inputs) => inputs,
+        Err(msg) => {
+            eprintln!("liquid2: {msg}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut parser = LiquidParser {
+        common_tags: options.common_tags,
+        ..LiquidParser::with_dialect(options.dialect)
+    };
+    parser.filters.strict = !options.any_filter;
+
+    let ok = match options.command {
+        Command::Tokenize => inputs
+            .iter()
+            .all(|input| print_result(parser.lexer.tokenize(&input.source), input, options.debug)),
+        Command::Parse => inputs
+            .iter()
+            .all(|input| print_result(parser.parse(&input.source), input, options.debug)),
+        Command::Check => check(&parser, &inputs, options.json, options.fast),
+        Command::Fmt => inputs
+            .iter()
+            .all(|input| match parser.parse(&input.source) {
+                Ok(template) if options.check => {
+                    let unchanged = template.to_string() == input.source;
+                    if !unchanged {
+                        eprintln!("{} would be reformatted", input.name);
+                    }
+                    unchanged
+                }
+                Ok(template) => {
+                    print!("{template}");
+                    true
+                }
+                Err(err) => {
+                    report(&err, input);
+                    false
+                }
+            }),
+    };
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let command = match args.next().as_deref() {
+        Some("tokenize") => Command::Tokenize,
+        Some("parse") => Command::Parse,
+        Some("check") => Command::Check,
+        Some("fmt") => Command::Fmt,
+        Some("-h" | "--help") | None => return Err("expected a command".to_owned()),
+        Some(other) => return Err(format!("unknown command '{other}'")),
+    };
+
+    let mut options = Options {
+        command,
+        debug: false,
+        json: false,
+        check: false,
+        fast: false,
+        dialect: Dialect::default(),
+        common_tags: false,
+        any_filter: false,
+        paths: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debug" => options.debug = true,
+            "--json" => options.json = true,
+            "--check" => options.check = true,
+            "--fast" => options.fast = true,
+            "--dialect" => {
+                let name = args
+                    .next()
+                    .ok_or("expected a dialect name after '--dialect'")?;
+                options.dialect =
+                    Dialect::named(&name).ok_or_else(|| format!("unknown dialect '{name}'"))?;
+            }
+            "--common-tags" => options.common_tags = true,
+            "--any-filter" => options.any_filter = true,
+            "-" => options.paths.push(arg),
+            flag if flag.starts_with('-') => return Err(format!("unknown option '{flag}'")),
+            _ => options.paths.push(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+fn read_inputs(options: &Options) -> Result<Vec<Input>, String> {
+    if options.paths.is_empty() {
+        return Ok(vec![read_stdin()?]);
+    }
+
+    let mut inputs = Vec::new();
+    for path in &options.paths {
+        if path == "-" {
+            inputs.push(read_stdin()?);
+            continue;
+        }
+
+        let path = Path::new(path);
+        if path.is_dir() && matches!(options.command, Command::Check) {
+            let mut files = Vec::new();
+            find_templates(path, &mut files).map_err(|err| format!("{}: {err}", path.display()))?;
+            files.sort();
+            for file in files {
+                inputs.push(read_file(&file)?);
+            }
+        } else {
+            inputs.push(read_file(path)?);
+        }
+    }
+    Ok(inputs)
+}
+
+fn read_stdin() -> Result<Input, String> {
+    let mut source = String::new();
+    io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|err| format!("<stdin>: {err}"))?;
+    Ok(Input {
+        name: "<stdin>".to_owned(),
+        source,
+    })
+}
+
+fn read_file(path: &Path) -> Result<Input, String> {
+    let source =
+        std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(Input {
+        name: path.display().to_string(),
+        source,
+    })
+}
+
+/// Collect `.liquid` files under _dir_ into _files_.
+fn find_templates(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_templates(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "liquid") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Print _result_ as JSON or debug output, or report its error. Returns
+/// `true` if there was no error.
+fn print_result<T: Serialize + Debug>(
+    result: Result<T, LiquidError>,
+    input: &Input,
+    debug: bool,
+) -> bool {
+    match result {
+        Ok(value) if debug => {
+            println!("{value:#?}");
+            true
+        }
+        Ok(value) => match json::to_json(&value) {
+            Ok(json) => {
+                println!("{json}");
+                true
+            }
+            Err(err) => {
+                eprintln!("{}: {err}", input.name);
+                false
+            }
+        },
+        Err(err) => {
+            report(&err, input);
+            false
+        }
+    }
+}
+
+/// Report errors in _inputs_. If _fast_ is true, templates are checked with
+/// `LiquidParser::validate` instead of being parsed.
+fn check(parser: &LiquidParser, inputs: &[Input], as_json: bool, fast: bool) -> bool {
+    let mut diagnostics = Vec::new();
+    for input in inputs {
+        let errors = if fast {
+            parser.validate(&input.source)
+        } else {
+            parser.parse(&input.source).err().into_iter().collect()
+        };
+        for err in errors {
+            if !as_json {
+                report(&err, input);
+            }
+            let err = err.with_source_name(&input.name);
+            diagnostics.push(Diagnostic::from_error(&err, &input.source));
+        }
+    }
+
+    if as_json {
+        match json::to_json(&diagnostics) {
+            Ok(json) => println!("{json}"),
+            Err(err) => eprintln!("liquid2: {err}"),
+        }
+    }
+    diagnostics.is_empty()
+}
+
+/// Print _err_ to standard error as `name:line:column: message`.
+fn report(err: &LiquidError, input: &Input) {
+    match err.span {
+        Some(span) => {
+            let (line, col) = LineIndex::new(&input.source).line_col(span.start);
+            eprintln!("{}:{line}:{col}: {}", input.name, err.msg);
+        }
+        None => eprintln!("{}: {}", input.name, err.msg),
+    }
+
+    for note in &err.notes {
+        match note.span {
+            Some(span) => {
+                let (line, col) = LineIndex::new(&input.source).line_col(span.start);
+                eprintln!("{}:{line}:{col}: note: {}", input.name, note.msg);
+            }
+            None => eprintln!("{}: note: {}", input.name, note.msg),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Caching of parsed templates, in memory and on disk.
+//!
+//! `TemplateCache` keeps recently used templates, along with the markup they
+//! were parsed from, so templates that are used over and over, like the
+//! partials of a large site, are only parsed once.
+//!
+//! On disk, cached templates are stored in a small header followed by the
+//! bincode encoded syntax tree. The header records a fingerprint of the
+//! source this crate was built from, along with its version, and
+//! `Template::load` refuses files written by any other build, since bincode
+//! can't tell when the shape of the syntax tree has changed.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Template;
+use crate::errors::LiquidError;
+use crate::loader::{FileSystemLoader, Loader};
+use crate::markup::Markup;
+use crate::parser::LiquidParser;
+
+const MAGIC: &[u8; 4] = b"LQ2T";
+
+/// A hash of this crate's source, computed by the build script, which
+/// changes whenever the layout of the syntax tree might have.
+const SCHEMA_FINGERPRINT: &str = env!("LIQUID2_SCHEMA_FINGERPRINT");
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    schema_fingerprint: String,
+    crate_version: String,
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// The file is not a template cache, or it is corrupt.
+    Format(String),
+    /// The file was written by an incompatible build.
+    Version {
+        found: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(err) => write!(f, "template cache i/o error: {err}"),
+            CacheError::Format(msg) => write!(f, "invalid template cache: {msg}"),
+            CacheError::Version { found, expected } => write!(
+                f,
+                "template cache version mismatch, found {found}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(err: io::Error) -> Self {
+        CacheError::Io(err)
+    }
+}
+
+impl From<bincode::Error> for CacheError {
+    fn from(err: bincode::Error) -> Self {
+        CacheError::Format(err.to_string())
+    }
+}
+
+fn header() -> Header {
+    Header {
+        schema_fingerprint: SCHEMA_FINGERPRINT.to_owned(),
+        crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+    }
+}
+
+impl Template {
+    /// Encode this template, including its versioned header.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CacheError> {
+        let mut buf = MAGIC.to_vec();
+        bincode::serialize_into(&mut buf, &header())?;
+        bincode::serialize_into(&mut buf, self)?;
+        Ok(buf)
+    }
+
+    /// Decode a template previously encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CacheError> {
+        let mut rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| CacheError::Format("missing magic number".to_owned()))?;
+
+        let found: Header = bincode::deserialize_from(&mut rest)?;
+        let expected = header();
+        if found.schema_fingerprint != expected.schema_fingerprint
+            || found.crate_version != expected.crate_version
+        {
+            return Err(CacheError::Version {
+                found: format!("{}/{}", found.crate_version, found.schema_fingerprint),
+                expected: format!("{}/{}", expected.crate_version, expected.schema_fingerprint),
+            });
+        }
+
+        Ok(bincode::deserialize(rest)?)
+    }
+
+    /// Write this template to _path_.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CacheError> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a template previously written with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CacheError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+/// Identifies a template in a `TemplateCache`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    /// The template's source text. We keep the whole text, rather than a
+    /// hash of it, so different templates can never share an entry.
+    Source(String),
+    /// A template name and the modification time of the file it was read
+    /// from, so edited files are parsed again.
+    Named { name: String, mtime: SystemTime },
+}
+
+impl CacheKey {
+    pub fn source(source: &str) -> Self {
+        CacheKey::Source(source.to_owned())
+    }
+
+    pub fn named(name: &str, mtime: SystemTime) -> Self {
+        CacheKey::Named {
+            name: name.to_owned(),
+            mtime,
+        }
+    }
+}
+
+/// A template and the markup it was parsed from.
+#[derive(Debug, Clone)]
+pub struct CachedTemplate {
+    pub markup: Arc<Vec<Markup>>,
+    pub template: Arc<Template>,
+}
+
+/// An in-memory cache of parsed templates that holds at most `capacity`
+/// templates, evicting the least recently used template when it is full.
+#[derive(Debug, Clone)]
+pub struct TemplateCache {
+    capacity: usize,
+    /// Entries in order of use, least recently used first.
+    entries: IndexMap<CacheKey, CachedTemplate>,
+    hits: usize,
+    misses: usize,
+}
+
+impl TemplateCache {
+    /// A cache holding up to _capacity_ templates. A capacity of zero
+    /// disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: IndexMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of lookups that did and did not find a cached template.
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits, self.misses)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Return the template cached under _key_, marking it as recently used.
+    pub fn get(&mut self, key: &CacheKey) -> Option<CachedTemplate> {
+        match self.entries.get_index_of(key) {
+            Some(index) => {
+                self.hits += 1;
+                let last = self.entries.len() - 1;
+                self.entries.move_index(index, last);
+                Some(self.entries[last].clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache _entry_ under _key_, evicting the least recently used template
+    /// if the cache is full.
+    pub fn insert(&mut self, key: CacheKey, entry: CachedTemplate) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.shift_remove(&key);
+        while self.entries.len() >= self.capacity {
+            self.entries.shift_remove_index(0);
+        }
+        self.entries.insert(key, entry);
+    }
+
+    pub fn remove(&mut self, key: &CacheKey) -> Option<CachedTemplate> {
+        self.entries.shift_remove(key)
+    }
+
+    /// Tokenize and parse _source_ with _parser_, unless a template with
+    /// the same source text is already cached.
+    ///
+    /// The cache doesn't know which parser a template came from, so use a
+    /// separate cache for each parser configuration.
+    pub fn parse(
+        &mut self,
+        parser: &LiquidParser,
+        source: &str,
+    ) -> Result<CachedTemplate, LiquidError> {
+        self.get_or_parse(
+            CacheKey::source(source),
+            parser,
+            || Ok(source.to_owned()),
+            None,
+        )
+    }
+
+    /// Load and parse the template called _name_ with _loader_, unless the
+    /// template's file hasn't changed since it was cached.
+    pub fn load(
+        &mut self,
+        parser: &LiquidParser,
+        loader: &FileSystemLoader,
+        name: &str,
+    ) -> Result<CachedTemplate, LiquidError> {
+        let path = loader
+            .resolve(name)
+            .ok_or_else(|| LiquidError::not_found(name))?;
+
+        match fs::metadata(&path).and_then(|meta| meta.modified()) {
+            Ok(mtime) => self.get_or_parse(
+                CacheKey::named(name, mtime),
+                parser,
+                || loader.get_source(name),
+                Some(name),
+            ),
+            // Without a modification time we can't tell if the file has
+            // changed, so fall back to its content.
+            Err(_) => {
+                let source = loader.get_source(name)?;
+                let key = CacheKey::Source(source.clone());
+                self.get_or_parse(key, parser, || Ok(source), Some(name))
+            }
+        }
+    }
+
+    fn get_or_parse(
+        &mut self,
+        key: CacheKey,
+        parser: &LiquidParser,
+        source: impl FnOnce() -> Result<String, LiquidError>,
+        name: Option<&str>,
+    ) -> Result<CachedTemplate, LiquidError> {
+        if let Some(entry) = self.get(&key) {
+            return Ok(entry);
+        }
+
+        let source = source()?;
+        let parsed = parser
+            .lexer
+            .tokenize(&source)
+            .and_then(|markup| Ok((parser.parse_markup(&markup)?, markup)));
+        let (template, markup) = match (parsed, name) {
+            (Ok(parsed), _) => parsed,
+            (Err(err), Some(name)) => return Err(err.with_source(name, &source)),
+            (Err(err), None) => return Err(err),
+        };
+
+        let entry = CachedTemplate {
+            markup: Arc::new(markup),
+            template: Arc::new(template),
+        };
+        self.insert(key, entry.clone());
+        Ok(entry)
+    }
+}
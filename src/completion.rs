@@ -0,0 +1,232 @@
+//! What could be typed at a cursor position, for autocompletion.
+//!
+//! The template is tokenized leniently to find the markup around the cursor,
+//! then the text of that markup up to the cursor is scanned by hand, because
+//! markup being typed is rarely complete enough to tokenize.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::lexer::Lexer;
+use crate::markup::Markup;
+use crate::pest_errors::floor_char_boundary;
+use crate::span::Span;
+
+/// The kind of thing the cursor is in.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    /// Template text, outside of any markup.
+    Content,
+    /// A comment, including the text of `comment` and `doc` blocks.
+    Comment,
+    /// The text of a `raw` block.
+    Raw,
+    /// The name of a tag, or of a statement in a `liquid` tag.
+    TagName,
+    /// An output statement, between expressions.
+    Output,
+    /// A tag's expression, between expressions.
+    Tag,
+    /// The name of a filter, after a pipe.
+    Filter,
+    /// A string literal.
+    String,
+    /// A variable name or path.
+    Query,
+}
+
+/// What the cursor is in, and the partial word before it.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionContext {
+    pub kind: CompletionKind,
+    /// The name of the tag or `liquid` tag statement the cursor is in, or
+    /// `None` outside of tags and while typing a tag's name.
+    pub tag: Option<String>,
+    /// Name characters before the cursor, or the text of a string literal up
+    /// to the cursor.
+    pub word: String,
+    /// The span of _word_, ending at the cursor.
+    pub span: Span,
+    /// For a query, the whole path up to the cursor, like `product.ti` when
+    /// _word_ is `ti`.
+    pub path: Option<String>,
+}
+
+impl CompletionContext {
+    fn new(kind: CompletionKind, offset: usize) -> Self {
+        Self {
+            kind,
+            tag: None,
+            word: String::new(),
+            span: Span::new(offset, offset),
+            path: None,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CompletionContext {
+    fn __repr__(&self) -> String {
+        format!(
+            "CompletionContext(kind={:?}, tag={:?}, word={:?})",
+            self.kind, self.tag, self.word
+        )
+    }
+}
+
+impl Lexer {
+    /// Describe what could be typed at byte _offset_ in _source_.
+    pub fn completion_context(&self, source: &str, offset: usize) -> CompletionContext {
+        let offset = floor_char_boundary(source, offset.min(source.len()));
+        let (markup, _) = self.tokenize_lenient(source);
+
+        // Markup the cursor is strictly inside of, or markup that could not
+        // be tokenized running up to the cursor.
+        let enclosing = markup.iter().find(|item| match item {
+            Markup::Error { span, .. } => span.start < offset && offset <= span.end,
+            Markup::Content { .. } | Markup::EOI {} => false,
+            item => item
+                .span()
+                .is_some_and(|span| span.start < offset && offset < span.end),
+        });
+
+        match enclosing {
+            None => CompletionContext::new(CompletionKind::Content, offset),
+            Some(Markup::Comment { .. }) => CompletionContext::new(CompletionKind::Comment, offset),
+            Some(Markup::Raw { .. }) => CompletionContext::new(CompletionKind::Raw, offset),
+            Some(item) => {
+                let start = item.span().unwrap().start;
+                let open = ["{{", "{%", "{#"]
+                    .iter()
+                    .filter_map(|delim| source[start..offset].rfind(delim))
+                    .max()
+                    .map_or(start, |i| start + i);
+                markup_context(source, open, offset)
+            }
+        }
+    }
+}
+
+/// The context at _offset_, in the markup starting at _open_.
+fn markup_context(source: &str, open: usize, offset: usize) -> CompletionContext {
+    let text = &source[open..offset];
+    if text.len() < 2 || text.contains("}}") || text.contains("%}") || text.contains("#}") {
+        return CompletionContext::new(CompletionKind::Content, offset);
+    }
+
+    let body = open + 2 + text[2..].len() - text[2..].trim_start_matches(['-', '+', '~']).len();
+    match &text[..2] {
+        "{#" => CompletionContext::new(CompletionKind::Comment, offset),
+        "{{" => expression_context(source, body, offset, None, CompletionKind::Output),
+        _ => tag_context(source, body, offset),
+    }
+}
+
+/// The context at _offset_ in a tag, or a statement in a `liquid` tag,
+/// starting at _start_.
+fn tag_context(source: &str, start: usize, offset: usize) -> CompletionContext {
+    let text = &source[start..offset];
+    let name_start = start + text.len() - text.trim_start().len();
+    if source[name_start..offset].starts_with('#') {
+        return CompletionContext::new(CompletionKind::Comment, offset);
+    }
+
+    let name_end = name_start
+        + source[name_start..offset]
+            .find(|c: char| !is_name_char(c))
+            .unwrap_or(offset - name_start);
+    let name = &source[name_start..name_end];
+
+    if name_end == offset {
+        return CompletionContext {
+            word: name.to_owned(),
+            span: Span::new(name_start, offset),
+            ..CompletionContext::new(CompletionKind::TagName, offset)
+        };
+    }
+
+    if name == "liquid" {
+        let line = source[name_end..offset]
+            .rfind('\n')
+            .map(|i| name_end + i + 1);
+        if let Some(line) = line {
+            return tag_context(source, line, offset);
+        }
+    }
+
+    expression_context(
+        source,
+        name_end,
+        offset,
+        Some(name.to_owned()),
+        CompletionKind::Tag,
+    )
+}
+
+/// The context at _offset_ in an expression starting at _start_, where
+/// _kind_ is the context between expressions.
+fn expression_context(
+    source: &str,
+    start: usize,
+    offset: usize,
+    tag: Option<String>,
+    kind: CompletionKind,
+) -> CompletionContext {
+    let text = &source[start..offset];
+
+    let mut quote: Option<(char, usize)> = None;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some((q, _)) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some((c, start + i + 1)),
+            _ => (),
+        }
+    }
+
+    if let Some((_, string_start)) = quote {
+        return CompletionContext {
+            tag,
+            word: source[string_start..offset].to_owned(),
+            span: Span::new(string_start, offset),
+            ..CompletionContext::new(CompletionKind::String, offset)
+        };
+    }
+
+    let word_start = offset - (text.len() - text.trim_end_matches(is_name_char).len());
+    let path_start = offset
+        - (text.len()
+            - text
+                .trim_end_matches(|c| is_name_char(c) || matches!(c, '.' | '[' | ']' | '$' | '@'))
+                .len());
+    let word = &source[word_start..offset];
+    let path = &source[path_start..offset];
+
+    let context = CompletionContext {
+        tag,
+        word: word.to_owned(),
+        span: Span::new(word_start, offset),
+        ..CompletionContext::new(kind, offset)
+    };
+
+    if source[start..path_start].trim_end().ends_with('|') {
+        CompletionContext {
+            kind: CompletionKind::Filter,
+            ..context
+        }
+    } else if path.is_empty() || path.starts_with(|c: char| c.is_ascii_digit()) {
+        context
+    } else {
+        CompletionContext {
+            kind: CompletionKind::Query,
+            path: Some(path.to_owned()),
+            ..context
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
@@ -0,0 +1,131 @@
+//! Lossless syntax trees, for formatters and refactoring tools.
+//!
+//! `Template`'s `Display` implementation normalizes spacing and drops
+//! whitespace control inside block delimiters. A `ConcreteTemplate` keeps
+//! the source text alongside its syntax tree, so the exact text of every
+//! node, expression and argument, separators included, is available through
+//! its span, and the template can be written back out unchanged.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::ast::{Node, Template};
+use crate::errors::LiquidError;
+use crate::incremental::Edit;
+use crate::parser::LiquidParser;
+use crate::span::Span;
+
+/// A syntax tree and the source text it was parsed from.
+#[cfg_attr(feature = "python", pyclass)]
+#[derive(Debug, Clone)]
+pub struct ConcreteTemplate {
+    source: String,
+    template: Template,
+}
+
+impl LiquidParser {
+    /// Parse _source_ into a syntax tree that can reproduce it exactly, such
+    /// that `parse_lossless(source)?.to_source() == source`.
+    pub fn parse_lossless(&self, source: &str) -> Result<ConcreteTemplate, LiquidError> {
+        ConcreteTemplate::new(source.to_owned(), self.parse(source)?)
+    }
+}
+
+impl ConcreteTemplate {
+    fn new(source: String, template: Template) -> Result<Self, LiquidError> {
+        // Top-level nodes must cover the source without gaps or overlaps.
+        let mut end = 0;
+        for node in &template.nodes {
+            let span = node.span();
+            if span.start != end {
+                return Err(LiquidError::syntax(format!(
+                    "can't represent template losslessly, expected a node at byte {end}"
+                ))
+                .with_span(span));
+            }
+            end = span.end;
+        }
+
+        if end != source.len() {
+            return Err(LiquidError::syntax(format!(
+                "can't represent template losslessly, expected a node at byte {end}"
+            )));
+        }
+
+        Ok(Self { source, template })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn template(&self) -> &Template {
+        &self.template
+    }
+
+    /// Return the source text at _span_, exactly as written.
+    pub fn text(&self, span: Span) -> &str {
+        &self.source[span.start..span.end]
+    }
+
+    /// Return the source text of _node_, including any nested blocks and
+    /// end tags.
+    pub fn node_text(&self, node: &Node) -> &str {
+        self.text(node.span())
+    }
+
+    /// Reassemble the source text from the text of each top-level node.
+    pub fn to_source(&self) -> String {
+        self.template
+            .nodes
+            .iter()
+            .map(|node| self.node_text(node))
+            .collect()
+    }
+
+    /// Apply _edit_ to the source text and incrementally reparse it with
+    /// _parser_. On error, the template is left unchanged.
+    pub fn edit(&mut self, parser: &LiquidParser, edit: &Edit) -> Result<(), LiquidError> {
+        let template = parser.reparse(&self.template, &self.source, edit)?;
+        *self = Self::new(edit.apply(&self.source), template)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ConcreteTemplate {
+    #[getter(source)]
+    fn py_source(&self) -> String {
+        self.source.clone()
+    }
+
+    #[getter(template)]
+    fn py_template(&self) -> Template {
+        self.template.clone()
+    }
+
+    #[pyo3(name = "text")]
+    fn py_text(&self, span: Span) -> Result<String, LiquidError> {
+        self.source
+            .get(span.start..span.end)
+            .map(str::to_owned)
+            .ok_or_else(|| LiquidError::syntax("span is out of range".to_string()).with_span(span))
+    }
+
+    #[pyo3(name = "to_source")]
+    fn py_to_source(&self) -> String {
+        self.to_source()
+    }
+
+    /// Replace the text at _span_ with _text_ and reparse, with the
+    /// standard query extensions.
+    #[pyo3(name = "edit")]
+    fn py_edit(&mut self, span: Span, text: String) -> Result<(), LiquidError> {
+        self.edit(&crate::python::default_parser(), &Edit { span, text })
+    }
+
+    fn __str__(&self) -> String {
+        self.to_source()
+    }
+}
@@ -0,0 +1,141 @@
+//! Machine-readable error reports, for editors, linters and other tools that
+//! would otherwise have to scrape error messages.
+//!
+//! A `Diagnostic` serializes to JSON like
+//! `{"code": "syntax-error", "severity": "error", "message": ..., "start":
+//! {"offset": 3, "line": 1, "column": 4}, ...}`.
+
+use serde::Serialize;
+
+use crate::audit::Finding;
+use crate::errors::{LiquidError, LiquidErrorType};
+use crate::lint::Lint;
+use crate::span::{LineIndex, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A location in source text. Lines and columns start at 1, and columns
+/// count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    fn new(index: &LineIndex, offset: usize) -> Self {
+        let (line, column) = index.line_col(offset);
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+/// Extra information about a diagnostic, optionally pointing at another
+/// location in the same source text.
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedNote {
+    pub message: String,
+    pub start: Option<Position>,
+    pub end: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// A stable identifier for the kind of problem, like `syntax-error`.
+    pub code: &'static str,
+    pub severity: Severity,
+    /// The error message, without the template name or line number.
+    pub message: String,
+    pub source_name: Option<String>,
+    pub start: Option<Position>,
+    pub end: Option<Position>,
+    pub notes: Vec<RelatedNote>,
+}
+
+impl Diagnostic {
+    /// Describe _err_, which occurred in _source_.
+    pub fn from_error(err: &LiquidError, source: &str) -> Self {
+        let index = LineIndex::new(source);
+        let (start, end) = positions(&index, err.span);
+        Self {
+            code: code(&err.kind),
+            severity: Severity::Error,
+            message: err.msg.clone(),
+            source_name: err.source_name.clone(),
+            start,
+            end,
+            notes: err
+                .notes
+                .iter()
+                .map(|note| {
+                    let (start, end) = positions(&index, note.span);
+                    RelatedNote {
+                        message: note.msg.clone(),
+                        start,
+                        end,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Describe a problem found by a lint rule in _source_. The rule's name
+    /// is used as the diagnostic's code.
+    pub fn from_lint(lint: &Lint, source: &str, source_name: Option<&str>) -> Self {
+        let (start, end) = positions(&LineIndex::new(source), Some(lint.span));
+        Self {
+            code: lint.rule,
+            severity: Severity::Warning,
+            message: lint.message.clone(),
+            source_name: source_name.map(str::to_owned),
+            start,
+            end,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Describe something an audit policy doesn't allow in _source_. The
+    /// finding's code is used as the diagnostic's code.
+    pub fn from_finding(finding: &Finding, source: &str, source_name: Option<&str>) -> Self {
+        let (start, end) = positions(&LineIndex::new(source), Some(finding.span));
+        Self {
+            code: finding.code,
+            severity: Severity::Error,
+            message: finding.message.clone(),
+            source_name: source_name.map(str::to_owned),
+            start,
+            end,
+            notes: Vec::new(),
+        }
+    }
+}
+
+fn positions(index: &LineIndex, span: Option<Span>) -> (Option<Position>, Option<Position>) {
+    match span {
+        Some(span) => (
+            Some(Position::new(index, span.start)),
+            Some(Position::new(index, span.end)),
+        ),
+        None => (None, None),
+    }
+}
+
+pub(crate) fn code(kind: &LiquidErrorType) -> &'static str {
+    match kind {
+        LiquidErrorType::LexerError => "lexer-error",
+        LiquidErrorType::SyntaxError => "syntax-error",
+        LiquidErrorType::TypeError => "type-error",
+        LiquidErrorType::NameError => "name-error",
+        LiquidErrorType::ExtError => "extension-error",
+        LiquidErrorType::TemplateNotFound => "template-not-found",
+    }
+}
@@ -0,0 +1,206 @@
+//! Groups of syntax that differ between Liquid2 and classic Shopify Liquid.
+//!
+//! A `Dialect` is shared by a lexer and the parser that owns it, so one
+//! codebase can parse Liquid2 templates and classic Shopify templates
+//! deterministically. `Dialect::default()` is Liquid2.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::errors::LiquidError;
+use crate::span::Span;
+
+/// Syntax accepted by a lexer and parser. Each option can be toggled
+/// independently, or a whole profile chosen with `liquid2()`, `strict()`,
+/// `lax()`, `shopify()` or `jekyll()`.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// Parse unknown tags as common tags, like `common_tags` on
+    /// `LiquidParser`, instead of failing.
+    pub unknown_tags: bool,
+    /// Allow `<>` as an alternative to `!=`.
+    pub diamond_ne: bool,
+    /// Allow `a not contains b`, meaning `not (a contains b)`.
+    pub not_contains: bool,
+    /// Allow string literals in tags and output statements to span lines.
+    pub multiline_strings: bool,
+    /// Allow `{# ... #}` comments.
+    pub hash_comments: bool,
+    /// Allow inline conditions, like `{{ a if b else c }}` and `{{ a ?: b }}`.
+    pub inline_conditions: bool,
+    /// Truncate float range endpoints to integers, instead of rejecting
+    /// them.
+    pub lax_ranges: bool,
+    /// Read the first argument of an `include` tag as a bare file name, like
+    /// Jekyll's `{% include nav/bar.html key="value" %}`.
+    pub jekyll_include: bool,
+    /// Allow variables as `render` tag targets, like `{% render name %}`.
+    /// Such tags are flagged as `dynamic`.
+    pub dynamic_render: bool,
+    /// Accept branches that can never be reached, like a second `else` tag
+    /// or duplicate `when` values, reporting them as warnings instead of
+    /// syntax errors.
+    pub unreachable_branches: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self::liquid2()
+    }
+}
+
+impl Dialect {
+    /// Liquid2 syntax.
+    pub fn liquid2() -> Self {
+        Self {
+            unknown_tags: false,
+            diamond_ne: true,
+            not_contains: true,
+            multiline_strings: true,
+            hash_comments: true,
+            inline_conditions: true,
+            lax_ranges: false,
+            jekyll_include: false,
+            dynamic_render: false,
+            unreachable_branches: false,
+        }
+    }
+
+    /// Liquid2 syntax without legacy alternatives, like `<>`.
+    pub fn strict() -> Self {
+        Self {
+            diamond_ne: false,
+            ..Self::liquid2()
+        }
+    }
+
+    /// Everything accepted by any other dialect, except for Jekyll's
+    /// `include` syntax, which changes the meaning of `{% include a.b %}`.
+    pub fn lax() -> Self {
+        Self {
+            unknown_tags: true,
+            lax_ranges: true,
+            dynamic_render: true,
+            unreachable_branches: true,
+            ..Self::liquid2()
+        }
+    }
+
+    /// Classic Shopify Liquid syntax.
+    pub fn shopify() -> Self {
+        Self {
+            unknown_tags: false,
+            diamond_ne: true,
+            not_contains: false,
+            multiline_strings: true,
+            hash_comments: false,
+            inline_conditions: false,
+            lax_ranges: true,
+            jekyll_include: false,
+            dynamic_render: false,
+            unreachable_branches: true,
+        }
+    }
+
+    /// Shopify syntax with Jekyll's `include` tag.
+    pub fn jekyll() -> Self {
+        Self {
+            jekyll_include: true,
+            ..Self::shopify()
+        }
+    }
+
+    /// The profile called _name_, one of `"liquid2"`, `"strict"`, `"lax"`,
+    /// `"shopify"` or `"jekyll"`.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "liquid2" => Some(Self::liquid2()),
+            "strict" => Some(Self::strict()),
+            "lax" => Some(Self::lax()),
+            "shopify" => Some(Self::shopify()),
+            "jekyll" => Some(Self::jekyll()),
+            _ => None,
+        }
+    }
+}
+
+/// An error for syntax disallowed by the dialect option called _name_.
+pub(crate) fn disallowed(name: &str, span: Span) -> LiquidError {
+    LiquidError::syntax(format!("the '{name}' dialect option is disabled")).with_span(span)
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Dialect {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        unknown_tags=false,
+        diamond_ne=true,
+        not_contains=true,
+        multiline_strings=true,
+        hash_comments=true,
+        inline_conditions=true,
+        lax_ranges=false,
+        jekyll_include=false,
+        dynamic_render=false,
+        unreachable_branches=false,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        unknown_tags: bool,
+        diamond_ne: bool,
+        not_contains: bool,
+        multiline_strings: bool,
+        hash_comments: bool,
+        inline_conditions: bool,
+        lax_ranges: bool,
+        jekyll_include: bool,
+        dynamic_render: bool,
+        unreachable_branches: bool,
+    ) -> Self {
+        Self {
+            unknown_tags,
+            diamond_ne,
+            not_contains,
+            multiline_strings,
+            hash_comments,
+            inline_conditions,
+            lax_ranges,
+            jekyll_include,
+            dynamic_render,
+            unreachable_branches,
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "liquid2")]
+    fn py_liquid2() -> Self {
+        Self::liquid2()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "strict")]
+    fn py_strict() -> Self {
+        Self::strict()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "lax")]
+    fn py_lax() -> Self {
+        Self::lax()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "shopify")]
+    fn py_shopify() -> Self {
+        Self::shopify()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "jekyll")]
+    fn py_jekyll() -> Self {
+        Self::jekyll()
+    }
+}
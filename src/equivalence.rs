@@ -0,0 +1,123 @@
+//! Compare templates for equivalence, ignoring differences that can not
+//! change their output.
+
+use crate::ast::{Node, Template};
+use crate::incremental::Spans;
+use crate::span::Span;
+
+impl Template {
+    /// Return a copy of this template with comments removed and adjacent
+    /// text content merged.
+    ///
+    /// Whitespace inside markup and the quoting style of string literals
+    /// are already discarded by the parser, so two templates that differ
+    /// only in those respects, or in their comments, normalize to the same
+    /// markup.
+    pub fn normalized(&self) -> Template {
+        let mut nodes = self.nodes.clone();
+        normalize_nodes(&mut nodes);
+        Template { nodes }
+    }
+
+    /// Return `true` if this template and _other_ have the same syntax tree
+    /// after normalization, ignoring source positions.
+    pub fn is_equivalent(&self, other: &Template) -> bool {
+        matches!(
+            (self.structure(), other.structure()),
+            (Ok(a), Ok(b)) if a == b
+        )
+    }
+
+    /// The normalized syntax tree with every span cleared, encoded so that
+    /// trees can be compared field by field.
+    fn structure(&self) -> bincode::Result<Vec<u8>> {
+        let mut nodes = self.normalized().nodes;
+        nodes.for_each_span(&mut |span| *span = Span::default());
+        bincode::serialize(&nodes)
+    }
+}
+
+fn normalize_nodes(nodes: &mut Vec<Node>) {
+    nodes.retain(|node| !matches!(node, Node::Comment { .. } | Node::DocTag { .. }));
+
+    let mut merged: Vec<Node> = Vec::with_capacity(nodes.len());
+    for mut node in nodes.drain(..) {
+        normalize_node(&mut node);
+        match (merged.last_mut(), node) {
+            (
+                Some(Node::Content { text, span }),
+                Node::Content {
+                    text: next,
+                    span: next_span,
+                },
+            ) => {
+                text.push_str(&next);
+                *span = Span::new(span.start, next_span.end);
+            }
+            (_, node) => merged.push(node),
+        }
+    }
+
+    merged.retain(|node| !matches!(node, Node::Content { text, .. } if text.is_empty()));
+    *nodes = merged;
+}
+
+fn normalize_node(node: &mut Node) {
+    match node {
+        Node::CaptureTag { block, .. }
+        | Node::LiquidTag { block, .. }
+        | Node::BlockTag { block, .. }
+        | Node::MacroTag { block, .. }
+        | Node::TablerowTag { block, .. }
+        | Node::IfchangedTag { block, .. } => normalize_nodes(block),
+        Node::CaseTag { whens, default, .. } => {
+            for when in whens {
+                normalize_nodes(&mut when.block);
+            }
+            if let Some(default) = default {
+                normalize_nodes(&mut default.block);
+            }
+        }
+        Node::ForTag { block, default, .. } => {
+            normalize_nodes(block);
+            if let Some(default) = default {
+                normalize_nodes(&mut default.block);
+            }
+        }
+        Node::IfTag {
+            block,
+            alternatives,
+            default,
+            ..
+        }
+        | Node::UnlessTag {
+            block,
+            alternatives,
+            default,
+            ..
+        } => {
+            normalize_nodes(block);
+            for alternative in alternatives {
+                normalize_nodes(&mut alternative.block);
+            }
+            if let Some(default) = default {
+                normalize_nodes(&mut default.block);
+            }
+        }
+        Node::TranslateTag { block, plural, .. } => {
+            normalize_nodes(block);
+            if let Some(plural) = plural {
+                normalize_nodes(&mut plural.block);
+            }
+        }
+        Node::TagExtension {
+            block, branches, ..
+        } => {
+            normalize_nodes(block);
+            for branch in branches {
+                normalize_nodes(&mut branch.block);
+            }
+        }
+        _ => (),
+    }
+}
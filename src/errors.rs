@@ -1,8 +1,9 @@
 use std::fmt;
 
-use pyo3::create_exception;
-use pyo3::exceptions::PyException;
-use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+use crate::span::Span;
 
 #[derive(Debug)]
 pub enum LiquidErrorType {
@@ -11,23 +12,86 @@ pub enum LiquidErrorType {
     TypeError,
     NameError,
     ExtError,
+    TemplateNotFound,
 }
 
 #[derive(Debug)]
 pub struct LiquidError {
     pub kind: LiquidErrorType,
     pub msg: String,
+    pub span: Option<Span>,
+    /// The name of the template the error occurred in, if known.
+    pub source_name: Option<String>,
+    /// The line number, starting at 1, of the start of `span` in the named
+    /// template.
+    pub line: Option<usize>,
+    /// Related information, like where an unclosed block was opened.
+    pub notes: Vec<Note>,
+}
+
+/// Extra information attached to an error, optionally pointing at another
+/// location in the source text.
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub msg: String,
+    pub span: Option<Span>,
 }
 
 impl LiquidError {
     pub fn new(error: LiquidErrorType, msg: String) -> Self {
-        Self { kind: error, msg }
+        Self {
+            kind: error,
+            msg,
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Attach the location of the offending source text to this error.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Attach a related note to this error.
+    pub fn with_note(mut self, msg: &str, span: Option<Span>) -> Self {
+        self.notes.push(Note {
+            msg: msg.to_owned(),
+            span,
+        });
+        self
+    }
+
+    /// Record the name of the template this error occurred in, unless it has
+    /// already been set by a nested template.
+    pub fn with_source_name(mut self, name: &str) -> Self {
+        if self.source_name.is_none() {
+            self.source_name = Some(name.to_owned());
+        }
+        self
+    }
+
+    /// Record the name of the template this error occurred in, along with
+    /// the line number of its span in _source_, unless they have already
+    /// been set by a nested template.
+    pub fn with_source(mut self, name: &str, source: &str) -> Self {
+        if self.source_name.is_none() {
+            self.source_name = Some(name.to_owned());
+            self.line = self.span.map(|span| span.line_col(source).0);
+        }
+        self
     }
 
     pub fn syntax(msg: String) -> Self {
         Self {
             kind: LiquidErrorType::SyntaxError,
             msg,
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
         }
     }
 
@@ -35,6 +99,10 @@ impl LiquidError {
         Self {
             kind: LiquidErrorType::TypeError,
             msg,
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
         }
     }
 
@@ -42,6 +110,21 @@ impl LiquidError {
         Self {
             kind: LiquidErrorType::NameError,
             msg,
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn not_found(name: &str) -> Self {
+        Self {
+            kind: LiquidErrorType::TemplateNotFound,
+            msg: format!("template not found '{name}'"),
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
         }
     }
 
@@ -49,12 +132,17 @@ impl LiquidError {
         Self {
             kind: LiquidErrorType::ExtError,
             msg,
+            span: None,
+            source_name: None,
+            line: None,
+            notes: Vec::new(),
         }
     }
 }
 
 impl std::error::Error for LiquidError {}
 
+#[cfg(feature = "python")]
 create_exception!(
     _liquid2,
     PyLiquidError,
@@ -62,6 +150,7 @@ create_exception!(
     "Base exception for all Liquid errors."
 );
 
+#[cfg(feature = "python")]
 create_exception!(
     _liquid2,
     LiquidTypeError,
@@ -69,6 +158,7 @@ create_exception!(
     "Liquid type error."
 );
 
+#[cfg(feature = "python")]
 create_exception!(
     _liquid2,
     LiquidSyntaxError,
@@ -76,6 +166,7 @@ create_exception!(
     "Liquid syntax error."
 );
 
+#[cfg(feature = "python")]
 create_exception!(
     _liquid2,
     LiquidNameError,
@@ -83,6 +174,7 @@ create_exception!(
     "Liquid name error."
 );
 
+#[cfg(feature = "python")]
 create_exception!(
     _liquid2,
     LiquidExtensionError,
@@ -90,22 +182,35 @@ create_exception!(
     "Liquid function extension error."
 );
 
+#[cfg(feature = "python")]
 impl std::convert::From<LiquidError> for PyErr {
     fn from(err: LiquidError) -> Self {
         use LiquidErrorType::*;
-        match err.kind {
+        let py_err = match err.kind {
             // TODO: improve error messages
             TypeError => LiquidTypeError::new_err(err.to_string()),
             SyntaxError => LiquidSyntaxError::new_err(err.to_string()),
             NameError => LiquidNameError::new_err(err.to_string()),
             ExtError => LiquidExtensionError::new_err(err.to_string()),
             _ => PyLiquidError::new_err(err.to_string()),
-        }
+        };
+
+        // Expose the error's location as `span`, or `None` if it doesn't
+        // have one.
+        Python::with_gil(|py| {
+            // Setting an attribute on a new exception instance can't fail.
+            let _ = py_err.value_bound(py).setattr("span", err.span);
+        });
+        py_err
     }
 }
 
 impl fmt::Display for LiquidError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.msg)
+        match (&self.source_name, self.line) {
+            (Some(name), Some(line)) => write!(f, "{} in '{}' at line {}", self.msg, name, line),
+            (Some(name), None) => write!(f, "{} in '{}'", self.msg, name),
+            (None, _) => write!(f, "{}", self.msg),
+        }
     }
 }
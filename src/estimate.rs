@@ -0,0 +1,360 @@
+//! Worst-case estimates of how much output a template renders and how many
+//! times its loops run, worked out without rendering it.
+//!
+//! Loops over range literals, loops with a literal `limit`, and every loop
+//! when there's an `EstimateOptions::default_loop_limit`, run a known number
+//! of times at most. Loops over anything else are assumed to run
+//! `EstimateOptions::collection_size` times. Output statements count the
+//! length of a literal, or `EstimateOptions::value_size` bytes when their
+//! value depends on render-time data or filters. Conditional blocks count
+//! their largest branch, and `break` and `continue` are ignored, so the
+//! estimate is an upper bound as long as the configured sizes are.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::ast::{FilteredExpression, Node, Primitive, Template};
+use crate::audit::range_length;
+use crate::query::{Segment, Selector};
+use crate::span::Span;
+
+/// Sizes assumed for things that aren't known until render time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EstimateOptions {
+    /// The number of items in collections that aren't range literals.
+    pub collection_size: u64,
+    /// The length, in bytes, of values that depend on render-time data.
+    pub value_size: u64,
+    /// The length, in bytes, of the output of each partial template
+    /// rendered with `include` or `render`.
+    pub partial_size: u64,
+    /// The renderer's default loop limit, which caps the iterations of every
+    /// loop that doesn't give a smaller `limit`.
+    pub default_loop_limit: Option<u64>,
+}
+
+impl Default for EstimateOptions {
+    fn default() -> Self {
+        Self {
+            collection_size: 100,
+            value_size: 100,
+            partial_size: 1000,
+            default_loop_limit: None,
+        }
+    }
+}
+
+/// The most times a loop's block could be rendered.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopEstimate {
+    /// The span of the `for` or `tablerow` tag, including its block.
+    pub span: Span,
+    /// Iterations each time the loop is rendered.
+    pub iterations: u64,
+    /// Iterations over the whole render, counting iterations of enclosing
+    /// loops.
+    pub total: u64,
+    /// Whether _iterations_ comes from a range literal, a literal `limit` or
+    /// `EstimateOptions::default_loop_limit`, rather than
+    /// `EstimateOptions::collection_size`.
+    pub bounded: bool,
+}
+
+/// A template's worst-case output size and loop iterations.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderEstimate {
+    /// The most bytes the template could output.
+    pub output_size: u64,
+    /// The most loop iterations over the whole render, for all loops.
+    pub iterations: u64,
+    /// Every `for` and `tablerow` loop, in order of appearance.
+    pub loops: Vec<LoopEstimate>,
+}
+
+impl Template {
+    /// Estimate the most output and loop iterations rendering this template
+    /// could produce, using _options_ for sizes that aren't known until
+    /// render time.
+    pub fn estimate(&self, options: &EstimateOptions) -> RenderEstimate {
+        let mut estimator = Estimator {
+            options,
+            multiplier: 1,
+            loops: Vec::new(),
+            captures: HashMap::new(),
+            macros: HashMap::new(),
+        };
+        let output_size = estimator.nodes(&self.nodes);
+        let mut loops = estimator.loops;
+        loops.sort_by_key(|estimate| estimate.span.start);
+        RenderEstimate {
+            output_size,
+            iterations: loops
+                .iter()
+                .fold(0, |total, estimate| total.saturating_add(estimate.total)),
+            loops,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl LoopEstimate {
+    fn __repr__(&self) -> String {
+        format!(
+            "LoopEstimate(span=({}, {}), iterations={}, total={}, bounded={})",
+            self.span.start,
+            self.span.end,
+            self.iterations,
+            self.total,
+            if self.bounded { "True" } else { "False" }
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RenderEstimate {
+    fn __repr__(&self) -> String {
+        format!(
+            "RenderEstimate(output_size={}, iterations={})",
+            self.output_size, self.iterations
+        )
+    }
+}
+
+struct Estimator<'a> {
+    options: &'a EstimateOptions,
+    /// The most times the current node could be rendered, from enclosing
+    /// loops.
+    multiplier: u64,
+    loops: Vec<LoopEstimate>,
+    /// The most output of each `capture` block seen so far.
+    captures: HashMap<String, u64>,
+    /// The most output of each macro defined so far.
+    macros: HashMap<String, u64>,
+}
+
+impl Estimator<'_> {
+    /// The most output from rendering _nodes_ once.
+    fn nodes(&mut self, nodes: &[Node]) -> u64 {
+        nodes
+            .iter()
+            .fold(0, |size, node| size.saturating_add(self.node(node)))
+    }
+
+    /// The most output from rendering the largest of _blocks_ once.
+    fn largest<'b>(&mut self, blocks: impl IntoIterator<Item = &'b [Node]>) -> u64 {
+        blocks
+            .into_iter()
+            .map(|block| self.nodes(block))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn node(&mut self, node: &Node) -> u64 {
+        match node {
+            Node::Content { text, .. } | Node::Raw { text, .. } => text.len() as u64,
+            Node::Output { expression, .. } | Node::EchoTag { expression, .. } => {
+                self.filtered_expression(expression)
+            }
+            Node::Comment { .. }
+            | Node::DocTag { .. }
+            | Node::AssignTag { .. }
+            | Node::BreakTag { .. }
+            | Node::ContinueTag { .. }
+            | Node::ExtendsTag { .. } => 0,
+            Node::CaptureTag {
+                identifier, block, ..
+            } => {
+                let size = self.nodes(block);
+                self.captures.insert(identifier.to_owned(), size);
+                0
+            }
+            Node::CaseTag { whens, default, .. } => self.largest(
+                whens
+                    .iter()
+                    .map(|when| when.block.as_slice())
+                    .chain(default.iter().map(|default| default.block.as_slice())),
+            ),
+            Node::IfTag {
+                block,
+                alternatives,
+                default,
+                ..
+            }
+            | Node::UnlessTag {
+                block,
+                alternatives,
+                default,
+                ..
+            } => self.largest(
+                std::iter::once(block.as_slice())
+                    .chain(alternatives.iter().map(|alt| alt.block.as_slice()))
+                    .chain(default.iter().map(|default| default.block.as_slice())),
+            ),
+            Node::CycleTag { args, .. } => args
+                .iter()
+                .map(|arg| self.primitive(arg))
+                .max()
+                .unwrap_or(0),
+            // The longest 64-bit integer.
+            Node::DecrementTag { .. } | Node::IncrementTag { .. } => {
+                i64::MIN.to_string().len() as u64
+            }
+            Node::ForTag {
+                iterable,
+                limit,
+                offset,
+                block,
+                default,
+                span,
+                ..
+            } => {
+                let (iterations, body) = self.loop_block(iterable, limit, offset, block, *span);
+                let default = default
+                    .as_ref()
+                    .map_or(0, |default| self.nodes(&default.block));
+                iterations.saturating_mul(body).max(default)
+            }
+            Node::TablerowTag {
+                iterable,
+                limit,
+                offset,
+                block,
+                span,
+                ..
+            } => {
+                let (iterations, body) = self.loop_block(iterable, limit, offset, block, *span);
+                // With one column, every cell starts a new row.
+                let cell = format!(
+                    "<td class=\"col{iterations}\"></td></tr>\n<tr class=\"row{iterations}\">"
+                )
+                .len() as u64;
+                let rows = "<tr class=\"row1\">\n</tr>\n".len() as u64;
+                iterations
+                    .saturating_mul(body.saturating_add(cell))
+                    .saturating_add(rows)
+            }
+            Node::IncludeTag { variable, .. } => self.partial(variable.is_some()),
+            Node::RenderTag {
+                repeat, variable, ..
+            } => self.partial(*repeat && variable.is_some()),
+            Node::LiquidTag { block, .. }
+            | Node::BlockTag { block, .. }
+            | Node::IfchangedTag { block, .. } => self.nodes(block),
+            Node::MacroTag { name, block, .. } => {
+                let size = self.nodes(block);
+                self.macros.insert(name.to_owned(), size);
+                0
+            }
+            Node::CallTag { name, .. } => self
+                .macros
+                .get(name)
+                .copied()
+                .unwrap_or(self.options.value_size),
+            Node::TranslateTag { block, plural, .. } => self.largest(
+                std::iter::once(block.as_slice())
+                    .chain(plural.iter().map(|plural| plural.block.as_slice())),
+            ),
+            // Custom tags are assumed to output a value and render each of
+            // their blocks once.
+            Node::TagExtension {
+                block, branches, ..
+            } => {
+                let mut size = self.nodes(block).saturating_add(self.options.value_size);
+                for branch in branches {
+                    size = size.saturating_add(self.nodes(&branch.block));
+                }
+                size
+            }
+        }
+    }
+
+    /// Record a loop over _iterable_ and return the most times it could run,
+    /// along with the most output from rendering _block_ once.
+    fn loop_block(
+        &mut self,
+        iterable: &Primitive,
+        limit: &Option<Primitive>,
+        offset: &Option<Primitive>,
+        block: &[Node],
+        span: Span,
+    ) -> (u64, u64) {
+        let length = range_length(iterable);
+        let offset = match offset {
+            Some(Primitive::Integer { value, .. }) => (*value).max(0) as u64,
+            _ => 0,
+        };
+        let limit = match limit {
+            Some(Primitive::Integer { value, .. }) => Some((*value).max(0) as u64),
+            _ => None,
+        };
+        // A `limit` that isn't a literal could be larger than the default.
+        let limit = match (limit, self.options.default_loop_limit) {
+            (Some(limit), Some(default)) => Some(limit.min(default)),
+            (limit, default) => limit.or(default),
+        };
+
+        let available = length.map_or(self.options.collection_size, |length| {
+            length.saturating_sub(offset)
+        });
+        let iterations = limit.map_or(available, |limit| limit.min(available));
+        let total = self.multiplier.saturating_mul(iterations);
+        self.loops.push(LoopEstimate {
+            span,
+            iterations,
+            total,
+            bounded: length.is_some() || limit.is_some(),
+        });
+
+        let multiplier = std::mem::replace(&mut self.multiplier, total);
+        let body = self.nodes(block);
+        self.multiplier = multiplier;
+        (iterations, body)
+    }
+
+    /// The most output from a partial template, rendered once for each item
+    /// in a collection if _repeat_ is true.
+    fn partial(&self, repeat: bool) -> u64 {
+        if repeat {
+            self.options
+                .partial_size
+                .saturating_mul(self.options.collection_size)
+        } else {
+            self.options.partial_size
+        }
+    }
+
+    fn filtered_expression(&self, expression: &FilteredExpression) -> u64 {
+        if expression.filters.is_some() || expression.condition.is_some() {
+            return self.options.value_size;
+        }
+        self.primitive(&expression.left)
+    }
+
+    /// The most output from _primitive_, without any filters.
+    fn primitive(&self, primitive: &Primitive) -> u64 {
+        match primitive {
+            Primitive::TrueLiteral { .. } => 4,
+            Primitive::FalseLiteral { .. } => 5,
+            Primitive::NullLiteral { .. } | Primitive::Empty { .. } | Primitive::Blank { .. } => 0,
+            Primitive::Integer { value, .. } => value.to_string().len() as u64,
+            Primitive::Float { value, .. } => value.to_string().len() as u64,
+            Primitive::StringLiteral { value, .. } => value.len() as u64,
+            // A variable set by `capture`, without a path.
+            Primitive::Query { path, .. } => match path.segments.as_slice() {
+                [Segment::Child { selectors, .. }] => match selectors.as_slice() {
+                    [Selector::Name { name, .. }] => self.captures.get(name).copied(),
+                    _ => None,
+                },
+                _ => None,
+            }
+            .unwrap_or(self.options.value_size),
+            _ => self.options.value_size,
+        }
+    }
+}
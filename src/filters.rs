@@ -0,0 +1,375 @@
+//! Filter signatures, used to check filter names and arguments at parse time,
+//! and implementations of the standard filters, used by the renderer.
+//!
+//! Each registered filter lists its positional and keyword parameters. The
+//! parser rejects filters that are called with too few or too many
+//! positional arguments, or with a keyword it doesn't accept. Unknown
+//! filters are rejected too, unless the registry's `strict` flag is off.
+//!
+//! Filter functions follow the Python engine's built-in filters, so
+//! templates render the same with either.
+
+pub mod array;
+pub mod math;
+pub mod misc;
+pub mod string;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+
+use crate::ast::Filter;
+use crate::errors::LiquidError;
+use crate::render::to_liquid_string;
+use crate::span::Span;
+
+/// The parameters accepted by a filter, not counting its left value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterSignature {
+    pub name: String,
+    /// Positional parameters that must be given.
+    pub required: Vec<String>,
+    /// Positional parameters that may be given after the required ones.
+    pub optional: Vec<String>,
+    /// Parameters that can only be given by name, like `allow_false` for
+    /// `default`.
+    pub keywords: Vec<String>,
+}
+
+impl FilterSignature {
+    pub fn new(name: &str, required: &[&str], optional: &[&str], keywords: &[&str]) -> Self {
+        let owned = |names: &[&str]| names.iter().map(|s| s.to_string()).collect();
+        Self {
+            name: name.to_owned(),
+            required: owned(required),
+            optional: owned(optional),
+            keywords: owned(keywords),
+        }
+    }
+
+    /// Return an error if _filter_'s arguments don't fit this signature.
+    pub fn check(&self, filter: &Filter) -> Result<(), LiquidError> {
+        let args = filter.args.as_deref().unwrap_or_default();
+        self.check_call(
+            args.iter().filter(|arg| arg.name.is_none()).count(),
+            args.iter().filter_map(|arg| arg.name.as_deref()),
+            filter.span,
+        )
+    }
+
+    /// Like `check`, for a call at _span_ with _positional_ positional
+    /// arguments and the keyword arguments named in _keywords_.
+    pub(crate) fn check_call<'a>(
+        &self,
+        positional: usize,
+        mut keywords: impl Iterator<Item = &'a str>,
+        span: Span,
+    ) -> Result<(), LiquidError> {
+        let min = self.required.len();
+        let max = min + self.optional.len();
+
+        let msg = if positional < min || positional > max {
+            let expected = if min == max {
+                plural(min, "argument")
+            } else if positional < min {
+                format!("at least {}", plural(min, "argument"))
+            } else {
+                format!("at most {}", plural(max, "argument"))
+            };
+            format!("'{}' expects {expected}, found {positional}", self.name)
+        } else if let Some(name) = keywords.find(|name| !self.keywords.iter().any(|k| k == name)) {
+            format!(
+                "'{}' got an unexpected keyword argument '{name}'",
+                self.name
+            )
+        } else {
+            return Ok(());
+        };
+
+        Err(LiquidError::typ(msg).with_span(span))
+    }
+}
+
+lazy_static! {
+    /// Signatures of the standard filters, shared by every standard registry
+    /// until it is changed.
+    static ref STANDARD_FILTERS: Arc<HashMap<String, FilterSignature>> = Arc::new(
+        STANDARD_SIGNATURES
+            .iter()
+            .map(|(name, required, optional, keywords)| {
+                (
+                    name.to_string(),
+                    FilterSignature::new(name, required, optional, keywords),
+                )
+            })
+            .collect()
+    );
+}
+
+/// Filters known to a parser, keyed by name.
+///
+/// Signatures are shared between clones of a registry, and copied the first
+/// time a clone registers or removes a filter.
+#[derive(Debug, Clone)]
+pub struct FilterRegistry {
+    filters: Arc<HashMap<String, FilterSignature>>,
+    /// Reject filters that are not registered. When `false`, only the
+    /// arguments of registered filters are checked.
+    pub strict: bool,
+}
+
+impl Default for FilterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterRegistry {
+    /// A strict registry without any filters.
+    pub fn new() -> Self {
+        Self {
+            filters: Arc::default(),
+            strict: true,
+        }
+    }
+
+    /// A strict registry with the standard Liquid filters.
+    pub fn standard() -> Self {
+        Self {
+            filters: Arc::clone(&STANDARD_FILTERS),
+            strict: true,
+        }
+    }
+
+    /// Register _signature_, replacing any filter with the same name.
+    pub fn register(&mut self, signature: FilterSignature) {
+        Arc::make_mut(&mut self.filters).insert(signature.name.clone(), signature);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<FilterSignature> {
+        Arc::make_mut(&mut self.filters).remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FilterSignature> {
+        self.filters.get(name)
+    }
+
+    /// Registered filters, sorted by name.
+    pub fn signatures(&self) -> Vec<&FilterSignature> {
+        let mut signatures: Vec<&FilterSignature> = self.filters.values().collect();
+        signatures.sort_by(|a, b| a.name.cmp(&b.name));
+        signatures
+    }
+
+    /// Return an error if _filter_ is unknown or its arguments don't fit its
+    /// signature.
+    pub fn check(&self, filter: &Filter) -> Result<(), LiquidError> {
+        match self.filters.get(&filter.name) {
+            Some(signature) => signature.check(filter),
+            None => self.check_unknown(&filter.name, filter.span),
+        }
+    }
+
+    /// Like `check`, for a call to the filter called _name_ at _span_, with
+    /// _positional_ positional arguments and the keyword arguments named in
+    /// _keywords_.
+    pub(crate) fn check_call<'a>(
+        &self,
+        name: &str,
+        positional: usize,
+        keywords: impl Iterator<Item = &'a str>,
+        span: Span,
+    ) -> Result<(), LiquidError> {
+        match self.filters.get(name) {
+            Some(signature) => signature.check_call(positional, keywords, span),
+            None => self.check_unknown(name, span),
+        }
+    }
+
+    fn check_unknown(&self, name: &str, span: Span) -> Result<(), LiquidError> {
+        if self.strict {
+            Err(LiquidError::typ(format!("unknown filter '{name}'")).with_span(span))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Evaluated filter arguments.
+#[derive(Debug, Clone, Default)]
+pub struct FilterArgs {
+    pub positional: Vec<Value>,
+    pub keywords: Map<String, Value>,
+}
+
+impl FilterArgs {
+    /// The positional argument at _index_, or `None` if it wasn't given.
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.positional.get(index)
+    }
+
+    pub fn keyword(&self, name: &str) -> Option<&Value> {
+        self.keywords.get(name)
+    }
+
+    /// The positional argument at _index_ as a string. Missing arguments and
+    /// `nil` are empty strings.
+    fn string(&self, index: usize) -> String {
+        self.get(index).map(to_liquid_string).unwrap_or_default()
+    }
+}
+
+/// A filter function, called with its left value and arguments.
+pub type FilterFn = fn(Value, &FilterArgs) -> Result<Value, LiquidError>;
+
+/// Return the implementation of the standard filter called _name_.
+pub fn standard_filter(name: &str) -> Option<FilterFn> {
+    let function: FilterFn = match name {
+        "abs" => math::abs,
+        "append" => string::append,
+        "at_least" => math::at_least,
+        "at_most" => math::at_most,
+        "capitalize" => string::capitalize,
+        "ceil" => math::ceil,
+        "compact" => array::compact,
+        "concat" => array::concat,
+        "date" => misc::date,
+        "default" => misc::default,
+        "divided_by" => math::divided_by,
+        "downcase" => string::downcase,
+        "escape" => string::escape,
+        "escape_once" => string::escape_once,
+        "first" => array::first,
+        "floor" => math::floor,
+        "join" => array::join,
+        "last" => array::last,
+        "lstrip" => string::lstrip,
+        "map" => array::map,
+        "minus" => math::minus,
+        "modulo" => math::modulo,
+        "newline_to_br" => string::newline_to_br,
+        "plus" => math::plus,
+        "prepend" => string::prepend,
+        "remove" => string::remove,
+        "remove_first" => string::remove_first,
+        "remove_last" => string::remove_last,
+        "replace" => string::replace,
+        "replace_first" => string::replace_first,
+        "replace_last" => string::replace_last,
+        "reverse" => array::reverse,
+        "round" => math::round,
+        "rstrip" => string::rstrip,
+        "size" => misc::size,
+        "slice" => string::slice,
+        "sort" => array::sort,
+        "sort_natural" => array::sort_natural,
+        "split" => string::split,
+        "strip" => string::strip,
+        "strip_html" => string::strip_html,
+        "strip_newlines" => string::strip_newlines,
+        "sum" => array::sum,
+        "times" => math::times,
+        "truncate" => string::truncate,
+        "truncatewords" => string::truncatewords,
+        "uniq" => array::uniq,
+        "upcase" => string::upcase,
+        "url_decode" => string::url_decode,
+        "url_encode" => string::url_encode,
+        "where" => array::where_,
+        _ => return None,
+    };
+    Some(function)
+}
+
+/// A filter function called with a lambda argument. It is given each item of
+/// its left value, as a sequence, and the lambda's result for each item.
+pub type LambdaFilterFn = fn(Vec<Value>, Vec<Value>) -> Result<Value, LiquidError>;
+
+/// Return the implementation of the standard filter called _name_ for lambda
+/// arguments, or `None` if it doesn't accept a lambda.
+pub fn standard_lambda_filter(name: &str) -> Option<LambdaFilterFn> {
+    let function: LambdaFilterFn = match name {
+        "map" => array::map_lambda,
+        "sort" => array::sort_lambda,
+        "where" => array::where_lambda,
+        _ => return None,
+    };
+    Some(function)
+}
+
+/// Parse _s_ as an integer, like Python's `int()`.
+fn parse_int(s: &str) -> Option<i64> {
+    s.trim().parse().ok()
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("{n} {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+type SignatureParts = (
+    &'static str,
+    &'static [&'static str],
+    &'static [&'static str],
+    &'static [&'static str],
+);
+
+/// Name, required, optional and keyword parameters of each standard filter.
+const STANDARD_SIGNATURES: [SignatureParts; 51] = [
+    ("abs", &[], &[], &[]),
+    ("append", &["string"], &[], &[]),
+    ("at_least", &["number"], &[], &[]),
+    ("at_most", &["number"], &[], &[]),
+    ("capitalize", &[], &[], &[]),
+    ("ceil", &[], &[], &[]),
+    ("compact", &[], &["key"], &[]),
+    ("concat", &["array"], &[], &[]),
+    ("date", &["format"], &[], &[]),
+    ("default", &[], &["default"], &["allow_false"]),
+    ("divided_by", &["number"], &[], &[]),
+    ("downcase", &[], &[], &[]),
+    ("escape", &[], &[], &[]),
+    ("escape_once", &[], &[], &[]),
+    ("first", &[], &[], &[]),
+    ("floor", &[], &[], &[]),
+    ("join", &[], &["separator"], &[]),
+    ("last", &[], &[], &[]),
+    ("lstrip", &[], &[], &[]),
+    ("map", &["key"], &[], &[]),
+    ("minus", &["number"], &[], &[]),
+    ("modulo", &["number"], &[], &[]),
+    ("newline_to_br", &[], &[], &[]),
+    ("plus", &["number"], &[], &[]),
+    ("prepend", &["string"], &[], &[]),
+    ("remove", &["string"], &[], &[]),
+    ("remove_first", &["string"], &[], &[]),
+    ("remove_last", &["string"], &[], &[]),
+    ("replace", &["string"], &["replacement"], &[]),
+    ("replace_first", &["string"], &["replacement"], &[]),
+    ("replace_last", &["string", "replacement"], &[], &[]),
+    ("reverse", &[], &[], &[]),
+    ("round", &[], &["digits"], &[]),
+    ("rstrip", &[], &[], &[]),
+    ("size", &[], &[], &[]),
+    ("slice", &["start"], &["length"], &[]),
+    ("sort", &[], &["key"], &[]),
+    ("sort_natural", &[], &["key"], &[]),
+    ("split", &["separator"], &[], &[]),
+    ("strip", &[], &[], &[]),
+    ("strip_html", &[], &[], &[]),
+    ("strip_newlines", &[], &[], &[]),
+    ("sum", &[], &["key"], &[]),
+    ("times", &["number"], &[], &[]),
+    ("truncate", &[], &["length", "ellipsis"], &[]),
+    ("truncatewords", &[], &["words", "ellipsis"], &[]),
+    ("uniq", &[], &["key"], &[]),
+    ("upcase", &[], &[], &[]),
+    ("url_decode", &[], &[], &[]),
+    ("url_encode", &[], &[], &[]),
+    ("where", &["key"], &["value"], &[]),
+];
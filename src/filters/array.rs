@@ -0,0 +1,290 @@
+//! Filters that operate on arrays.
+//!
+//! Most of these treat their left value as a sequence, like Shopify/Liquid's
+//! `InputIterator`. Nested arrays are flattened, `nil` is an empty sequence
+//! and any other value is a sequence of one item.
+
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+use super::math::{add, num_arg, Num};
+use super::FilterArgs;
+use crate::errors::LiquidError;
+use crate::render::{is_truthy, liquid_eq, to_liquid_string, type_name};
+
+/// How deep nested arrays are flattened.
+const FLATTEN_DEPTH: usize = 5;
+
+pub(crate) fn sequence(value: Value) -> Vec<Value> {
+    fn flatten(items: Vec<Value>, depth: usize, out: &mut Vec<Value>) {
+        for item in items {
+            match item {
+                Value::Array(nested) if depth > 0 => flatten(nested, depth - 1, out),
+                item => out.push(item),
+            }
+        }
+    }
+
+    match value {
+        Value::Array(items) => {
+            let mut out = Vec::with_capacity(items.len());
+            flatten(items, FLATTEN_DEPTH, &mut out);
+            out
+        }
+        Value::Null => Vec::new(),
+        value => vec![value],
+    }
+}
+
+/// The optional key argument of filters like `sort` and `uniq`, or `None` if
+/// it's missing or `nil`.
+fn key_arg(args: &FilterArgs) -> Option<String> {
+    match args.get(0) {
+        None | Some(Value::Null) => None,
+        Some(key) => Some(to_liquid_string(key)),
+    }
+}
+
+/// Look up _key_ in _item_, failing if _item_ can't have properties.
+fn property<'v>(item: &'v Value, key: &str) -> Result<Option<&'v Value>, LiquidError> {
+    match item {
+        Value::Object(obj) => Ok(obj.get(key)),
+        Value::Array(_) | Value::String(_) => Ok(None),
+        item => Err(LiquidError::typ(format!(
+            "can't read property '{key}' of {}",
+            type_name(item)
+        ))),
+    }
+}
+
+pub fn join(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let separator = args.get(0).map_or(" ".to_owned(), to_liquid_string);
+    let items: Vec<String> = sequence(left).iter().map(to_liquid_string).collect();
+    Ok(Value::String(items.join(&separator)))
+}
+
+pub fn first(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(match left {
+        Value::Array(items) => items.into_iter().next().unwrap_or_default(),
+        Value::Object(obj) => obj.into_iter().next().map_or(Value::Null, |(k, v)| {
+            Value::Array(vec![Value::String(k), v])
+        }),
+        _ => Value::Null,
+    })
+}
+
+pub fn last(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(match left {
+        Value::Array(mut items) => items.pop().unwrap_or_default(),
+        _ => Value::Null,
+    })
+}
+
+pub fn concat(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    match args.get(0) {
+        Some(Value::Array(other)) => {
+            let mut items = sequence(left);
+            items.extend(other.iter().cloned());
+            Ok(Value::Array(items))
+        }
+        other => Err(LiquidError::typ(format!(
+            "concat expected an array, found {}",
+            other.map_or("null", type_name)
+        ))),
+    }
+}
+
+pub fn map(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = args.string(0);
+    let items = sequence(left)
+        .iter()
+        .map(|item| Ok(property(item, &key)?.cloned().unwrap_or_default()))
+        .collect::<Result<_, LiquidError>>()?;
+    Ok(Value::Array(items))
+}
+
+pub fn reverse(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    let mut items = sequence(left);
+    items.reverse();
+    Ok(Value::Array(items))
+}
+
+pub fn sort(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let mut items = sequence(left);
+    let mut incompatible = None;
+
+    match key_arg(args) {
+        // Items without the key go last.
+        Some(key) => items.sort_by(|a, b| match (lookup(a, &key), lookup(b, &key)) {
+            (Some(a), Some(b)) => compare(a, b).unwrap_or_else(|| {
+                incompatible = Some((type_name(a), type_name(b)));
+                Ordering::Equal
+            }),
+            (a, b) => b.is_none().cmp(&a.is_none()).reverse(),
+        }),
+        None => items.sort_by(|a, b| {
+            compare(a, b).unwrap_or_else(|| {
+                incompatible = Some((type_name(a), type_name(b)));
+                Ordering::Equal
+            })
+        }),
+    }
+
+    match incompatible {
+        Some((a, b)) => Err(LiquidError::typ(format!(
+            "can't sort sequence, comparison of {a} with {b} failed"
+        ))),
+        None => Ok(Value::Array(items)),
+    }
+}
+
+/// Sort case-insensitively, comparing items as strings.
+pub fn sort_natural(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = key_arg(args);
+    let mut items: Vec<(Option<String>, Value)> = sequence(left)
+        .into_iter()
+        .map(|item| {
+            let value = match &key {
+                Some(key) => lookup(&item, key),
+                None => Some(&item),
+            };
+            let sort_key = value
+                .filter(|value| !value.is_null())
+                .map(|value| to_liquid_string(value).to_lowercase());
+            (sort_key, item)
+        })
+        .collect();
+
+    // Items without a value go last.
+    items.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    });
+    Ok(Value::Array(
+        items.into_iter().map(|(_, item)| item).collect(),
+    ))
+}
+
+fn lookup<'v>(item: &'v Value, key: &str) -> Option<&'v Value> {
+    item.as_object().and_then(|obj| obj.get(key))
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+pub fn where_(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = args.string(0);
+    let value = args.get(1).filter(|value| !value.is_null());
+
+    let mut items = Vec::new();
+    for item in sequence(left) {
+        let keep = match (property(&item, &key)?, value) {
+            (Some(property), Some(value)) => liquid_eq(property, value),
+            (Some(property), None) => is_truthy(property),
+            (None, _) => false,
+        };
+        if keep {
+            items.push(item);
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+/// `map` with a lambda, where _results_ are the lambda's result for each of
+/// _items_.
+pub fn map_lambda(_: Vec<Value>, results: Vec<Value>) -> Result<Value, LiquidError> {
+    Ok(Value::Array(results))
+}
+
+/// `sort` with a lambda, ordering _items_ by their _results_. Items with a
+/// `nil` result go last.
+pub fn sort_lambda(items: Vec<Value>, results: Vec<Value>) -> Result<Value, LiquidError> {
+    let mut pairs: Vec<(Value, Value)> = results.into_iter().zip(items).collect();
+    let mut incompatible = None;
+
+    pairs.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Value::Null, _) | (_, Value::Null) => b.is_null().cmp(&a.is_null()).reverse(),
+        (a, b) => compare(a, b).unwrap_or_else(|| {
+            incompatible = Some((type_name(a), type_name(b)));
+            Ordering::Equal
+        }),
+    });
+
+    match incompatible {
+        Some((a, b)) => Err(LiquidError::typ(format!(
+            "can't sort sequence, comparison of {a} with {b} failed"
+        ))),
+        None => Ok(Value::Array(
+            pairs.into_iter().map(|(_, item)| item).collect(),
+        )),
+    }
+}
+
+/// `where` with a lambda, keeping _items_ with a truthy result.
+pub fn where_lambda(items: Vec<Value>, results: Vec<Value>) -> Result<Value, LiquidError> {
+    Ok(Value::Array(
+        items
+            .into_iter()
+            .zip(results)
+            .filter(|(_, result)| is_truthy(result))
+            .map(|(item, _)| item)
+            .collect(),
+    ))
+}
+
+pub fn uniq(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = key_arg(args);
+    let mut seen: Vec<Option<Value>> = Vec::new();
+    let mut items = Vec::new();
+
+    for item in sequence(left) {
+        let identity = match &key {
+            Some(key) => property(&item, key)?.cloned(),
+            None => Some(item.clone()),
+        };
+        let duplicate = seen.iter().any(|other| match (other, &identity) {
+            (Some(a), Some(b)) => liquid_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        });
+        if !duplicate {
+            seen.push(identity);
+            items.push(item);
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+pub fn compact(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = key_arg(args);
+    let mut items = Vec::new();
+    for item in sequence(left) {
+        let value = match &key {
+            Some(key) => property(&item, key)?,
+            None => Some(&item),
+        };
+        if value.is_some_and(|value| !value.is_null()) {
+            items.push(item);
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+/// Add numbers and numeric strings. Anything else counts as zero.
+pub fn sum(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let key = key_arg(args);
+    let mut total = Num::Int(0);
+    for item in sequence(left) {
+        let value = match &key {
+            Some(key) => property(&item, key)?,
+            None => Some(&item),
+        };
+        total = add(total, num_arg(value, Some(Num::Int(0)))?);
+    }
+    Ok(total.into_value())
+}
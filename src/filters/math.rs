@@ -0,0 +1,291 @@
+//! Filters that operate on numbers.
+//!
+//! Left values and arguments that aren't numbers are cast to numbers, or
+//! treated as zero if they can't be. Arithmetic on integers gives an
+//! integer. Arithmetic involving a float is done in decimal, so `10.1 |
+//! minus: 2.2` is `7.9`, not `7.8999999999999995`.
+
+use serde_json::{Number, Value};
+
+use super::{parse_int, FilterArgs};
+use crate::errors::LiquidError;
+
+/// An integer or float, cast from a filter's left value or argument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+impl Num {
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            Num::Int(n) => n as f64,
+            Num::Float(n) => n,
+        }
+    }
+
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            Num::Int(n) => Value::from(n),
+            Num::Float(n) => Number::from_f64(n).map_or(Value::Null, Value::Number),
+        }
+    }
+
+    /// Python's `Decimal(str(n))`. Returns `None` for values too big or small to fit.
+    fn decimal(self) -> Option<Decimal> {
+        match self {
+            Num::Int(n) => Some((n as i128, 0)),
+            Num::Float(n) if n.is_finite() => {
+                let repr = n.to_string();
+                let (whole, fraction) = repr.split_once('.').unwrap_or((&repr, ""));
+                let digits: i128 = format!("{whole}{fraction}").parse().ok()?;
+                Some((digits, fraction.len() as u32))
+            }
+            Num::Float(_) => None,
+        }
+    }
+}
+
+/// Digits and a number of decimal places, so `(1234, 2)` is `12.34`.
+type Decimal = (i128, u32);
+
+/// Cast _value_ to a number, like the Python engine's `num_arg`. Returns
+/// _default_, or an error if there's no default, if _value_ isn't a number
+/// or a string representation of one.
+pub(crate) fn num_arg(value: Option<&Value>, default: Option<Num>) -> Result<Num, LiquidError> {
+    let num = match value {
+        Some(Value::Number(n)) => n.as_i64().map(Num::Int).or(n.as_f64().map(Num::Float)),
+        Some(Value::Bool(b)) => Some(Num::Int(*b as i64)),
+        Some(Value::String(s)) => parse_int(s)
+            .map(Num::Int)
+            .or_else(|| s.trim().parse().ok().map(Num::Float)),
+        _ => None,
+    };
+
+    match (num, default) {
+        (Some(num), _) => Ok(num),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(LiquidError::typ(match value {
+            Some(Value::String(s)) => format!("could not cast string '{s}' to a number"),
+            _ => "expected an int, float or string".to_owned(),
+        })),
+    }
+}
+
+fn num(value: &Value) -> Num {
+    num_arg(Some(value), Some(Num::Int(0))).unwrap_or(Num::Int(0))
+}
+
+fn arg(args: &FilterArgs) -> Num {
+    num_arg(args.get(0), Some(Num::Int(0))).unwrap_or(Num::Int(0))
+}
+
+/// Scale two decimals to the same number of decimal places.
+fn align((a, a_places): Decimal, (b, b_places): Decimal) -> Option<(i128, i128, u32)> {
+    let places = a_places.max(b_places);
+    let a = a.checked_mul(10i128.checked_pow(places - a_places)?)?;
+    let b = b.checked_mul(10i128.checked_pow(places - b_places)?)?;
+    Some((a, b, places))
+}
+
+fn from_decimal(digits: i128, places: u32) -> Num {
+    let sign = if digits < 0 { "-" } else { "" };
+    let digits = format!(
+        "{:0>width$}",
+        digits.unsigned_abs(),
+        width = places as usize + 1
+    );
+    let (whole, fraction) = digits.split_at(digits.len() - places as usize);
+    Num::Float(
+        format!("{sign}{whole}.{fraction}0")
+            .parse()
+            .unwrap_or(f64::NAN),
+    )
+}
+
+/// Apply _int_ to integers, and _decimal_ to decimals. If either overflows,
+/// fall back to _float_.
+fn arithmetic(
+    left: Num,
+    right: Num,
+    int: fn(i64, i64) -> Option<i64>,
+    decimal: fn(Decimal, Decimal) -> Option<Decimal>,
+    float: fn(f64, f64) -> f64,
+) -> Num {
+    if let (Num::Int(a), Num::Int(b)) = (left, right) {
+        if let Some(n) = int(a, b) {
+            return Num::Int(n);
+        }
+    }
+
+    match (left.decimal(), right.decimal()) {
+        (Some(a), Some(b)) => match decimal(a, b) {
+            Some((digits, places)) => from_decimal(digits, places),
+            None => Num::Float(float(left.as_f64(), right.as_f64())),
+        },
+        _ => Num::Float(float(left.as_f64(), right.as_f64())),
+    }
+}
+
+pub fn abs(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(match num(&left) {
+        Num::Int(n) => n
+            .checked_abs()
+            .map_or(Num::Float((n as f64).abs()), Num::Int),
+        Num::Float(n) => Num::Float(n.abs()),
+    }
+    .into_value())
+}
+
+pub fn at_least(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let (left, right) = (num(&left), arg(args));
+    Ok(if right.as_f64() > left.as_f64() {
+        right
+    } else {
+        left
+    }
+    .into_value())
+}
+
+pub fn at_most(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let (left, right) = (num(&left), arg(args));
+    Ok(if right.as_f64() < left.as_f64() {
+        right
+    } else {
+        left
+    }
+    .into_value())
+}
+
+pub fn ceil(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(to_int(num(&left), f64::ceil).into_value())
+}
+
+pub fn floor(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(to_int(num(&left), f64::floor).into_value())
+}
+
+/// Round _n_ to an integer with _f_, like Python's `math.ceil` and
+/// `math.floor`.
+fn to_int(n: Num, f: fn(f64) -> f64) -> Num {
+    match n {
+        Num::Int(_) => n,
+        Num::Float(n) => {
+            let rounded = f(n);
+            if rounded.abs() < i64::MAX as f64 {
+                Num::Int(rounded as i64)
+            } else {
+                Num::Float(rounded)
+            }
+        }
+    }
+}
+
+pub fn divided_by(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let (left, right) = (num(&left), arg(args));
+    if right.as_f64() == 0.0 {
+        return Err(LiquidError::typ(format!(
+            "divided_by: can't divide by {}",
+            right.into_value()
+        )));
+    }
+
+    Ok(match (left, right) {
+        // Floor division, rounding towards negative infinity.
+        (Num::Int(a), Num::Int(b)) => match (a.checked_div(b), a.checked_rem(b)) {
+            (Some(q), Some(r)) if r != 0 && (r < 0) != (b < 0) => Num::Int(q - 1),
+            (Some(q), _) => Num::Int(q),
+            _ => Num::Float((a as f64 / b as f64).floor()),
+        },
+        (a, b) => Num::Float(a.as_f64() / b.as_f64()),
+    }
+    .into_value())
+}
+
+pub fn minus(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(arithmetic(
+        num(&left),
+        arg(args),
+        i64::checked_sub,
+        |a, b| align(a, b).and_then(|(a, b, places)| Some((a.checked_sub(b)?, places))),
+        |a, b| a - b,
+    )
+    .into_value())
+}
+
+pub fn plus(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(add(num(&left), arg(args)).into_value())
+}
+
+pub(crate) fn add(left: Num, right: Num) -> Num {
+    arithmetic(
+        left,
+        right,
+        i64::checked_add,
+        |a, b| align(a, b).and_then(|(a, b, places)| Some((a.checked_add(b)?, places))),
+        |a, b| a + b,
+    )
+}
+
+pub fn times(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(arithmetic(
+        num(&left),
+        arg(args),
+        i64::checked_mul,
+        |(a, a_places), (b, b_places)| Some((a.checked_mul(b)?, a_places + b_places)),
+        |a, b| a * b,
+    )
+    .into_value())
+}
+
+pub fn modulo(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let (left, right) = (num(&left), arg(args));
+    if right.as_f64() == 0.0 {
+        return Err(LiquidError::typ(format!(
+            "modulo: can't divide by {}",
+            right.into_value()
+        )));
+    }
+
+    Ok(arithmetic(
+        left,
+        right,
+        // The result takes the sign of the divisor for integers, and the
+        // sign of the dividend for decimals, like Python.
+        |a, b| {
+            let r = a.checked_rem(b)?;
+            Some(if r != 0 && (r < 0) != (b < 0) {
+                r + b
+            } else {
+                r
+            })
+        },
+        |a, b| align(a, b).and_then(|(a, b, places)| Some((a.checked_rem(b)?, places))),
+        |a, b| a % b,
+    )
+    .into_value())
+}
+
+pub fn round(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let left = num(&left);
+    let digits = match args.get(0) {
+        None | Some(Value::Null) => 0,
+        Some(value) => match num_arg(Some(value), None) {
+            Ok(Num::Int(n)) => n,
+            Ok(Num::Float(n)) => n as i64,
+            Err(_) => 0,
+        },
+    };
+
+    Ok(match left {
+        _ if digits < 0 => Num::Int(0),
+        Num::Int(_) => left,
+        Num::Float(n) if digits == 0 => to_int(Num::Float(n), f64::round_ties_even),
+        Num::Float(n) => {
+            let places = digits.min(17) as usize;
+            Num::Float(format!("{n:.places$}").parse().unwrap_or(n))
+        }
+    }
+    .into_value())
+}
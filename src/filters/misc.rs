@@ -0,0 +1,95 @@
+//! Filters that don't fit anywhere else.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value;
+
+use super::FilterArgs;
+use crate::errors::LiquidError;
+use crate::render::to_liquid_string;
+
+/// The number of items in an array or object, or characters in a string.
+/// Anything else has a size of zero.
+pub fn size(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    Ok(Value::from(match &left {
+        Value::Array(items) => items.len(),
+        Value::Object(obj) => obj.len(),
+        Value::String(s) => s.chars().count(),
+        _ => 0,
+    }))
+}
+
+/// Replace `nil`, `false` and empty values with a default. Numbers, including
+/// zero, are never replaced. `false` is kept if `allow_false: true` is given.
+pub fn default(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let allow_false = matches!(args.keyword("allow_false"), Some(Value::Bool(true)));
+    let empty = match &left {
+        Value::Null => true,
+        Value::Bool(b) => !b && !allow_false,
+        Value::String(s) => s.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(obj) => obj.is_empty(),
+        Value::Number(_) => false,
+    };
+
+    Ok(if empty {
+        args.get(0)
+            .cloned()
+            .unwrap_or_else(|| Value::String(String::new()))
+    } else {
+        left
+    })
+}
+
+/// Format a date or timestamp with a strftime format string. Values that
+/// can't be parsed as a date are returned unchanged.
+pub fn date(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let format = match args.get(0) {
+        None | Some(Value::Null) => return Ok(Value::String(to_liquid_string(&left))),
+        Some(format) => to_liquid_string(format),
+    };
+
+    let datetime = match &left {
+        Value::Null => return Ok(Value::String(String::new())),
+        Value::Number(n) => n.as_i64().and_then(|n| DateTime::from_timestamp(n, 0)),
+        Value::String(s) => parse_datetime(s),
+        _ => None,
+    };
+
+    let Some(datetime) = datetime else {
+        return Ok(Value::String(to_liquid_string(&left)));
+    };
+
+    let items: Vec<Item> = StrftimeItems::new(&format).collect();
+    if items.contains(&Item::Error) {
+        return Err(LiquidError::typ(format!("invalid date format '{format}'")));
+    }
+    Ok(Value::String(
+        datetime.format_with_items(items.into_iter()).to_string(),
+    ))
+}
+
+fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if s == "now" || s == "today" {
+        return Some(Utc::now());
+    }
+
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return DateTime::from_timestamp(s.parse().ok()?, 0);
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+        return Some(datetime.and_utc());
+    }
+
+    ["%Y-%m-%d", "%B %d, %Y", "%b %d, %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+}
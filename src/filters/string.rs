@@ -0,0 +1,315 @@
+//! Filters that operate on strings.
+//!
+//! Left values that aren't strings are converted to strings first, so
+//! `{{ 5 | append: 'x' }}` is `5x`. Where the Python engine and Shopify/Liquid
+//! disagree, we follow Shopify/Liquid.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use super::{parse_int, FilterArgs};
+use crate::errors::LiquidError;
+use crate::render::to_liquid_string;
+
+lazy_static! {
+    static ref HTML: Regex =
+        Regex::new(r"(?s)<script.*?</script>|<!--.*?-->|<style.*?</style>|<.*?>").unwrap();
+    static ref ENTITY: Regex =
+        Regex::new(r"^&(?:[a-zA-Z][a-zA-Z0-9]*|#[0-9]+|#[xX][0-9a-fA-F]+);").unwrap();
+}
+
+fn map_string(value: Value, f: impl FnOnce(&str) -> String) -> Result<Value, LiquidError> {
+    Ok(Value::String(f(&to_liquid_string(&value))))
+}
+
+pub fn append(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.to_owned() + &args.string(0))
+}
+
+pub fn prepend(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| args.string(0) + s)
+}
+
+pub fn capitalize(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let mut chars = s.chars();
+        match chars.next() {
+            Some(first) => first
+                .to_uppercase()
+                .chain(chars.flat_map(char::to_lowercase))
+                .collect(),
+            None => String::new(),
+        }
+    })
+}
+
+pub fn downcase(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, str::to_lowercase)
+}
+
+pub fn upcase(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, str::to_uppercase)
+}
+
+pub fn escape(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let mut escaped = String::with_capacity(s.len());
+        for ch in s.chars() {
+            escape_char(ch, &mut escaped);
+        }
+        escaped
+    })
+}
+
+/// Like `escape`, but without escaping the `&` at the start of an existing
+/// character reference, like `&lt;` or `&#20;`.
+pub fn escape_once(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let mut escaped = String::with_capacity(s.len());
+        for (i, ch) in s.char_indices() {
+            if ch == '&' && ENTITY.is_match(&s[i..]) {
+                escaped.push(ch);
+            } else {
+                escape_char(ch, &mut escaped);
+            }
+        }
+        escaped
+    })
+}
+
+fn escape_char(ch: char, out: &mut String) {
+    match ch {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#x27;"),
+        ch => out.push(ch),
+    }
+}
+
+pub fn lstrip(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.trim_start().to_owned())
+}
+
+pub fn rstrip(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.trim_end().to_owned())
+}
+
+pub fn strip(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.trim().to_owned())
+}
+
+pub fn newline_to_br(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replace("\r\n", "\n").replace('\n', "<br />\n"))
+}
+
+pub fn strip_newlines(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replace("\r\n", "").replace('\n', ""))
+}
+
+pub fn strip_html(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| HTML.replace_all(s, "").into_owned())
+}
+
+pub fn remove(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replace(&args.string(0), ""))
+}
+
+pub fn remove_first(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replacen(&args.string(0), "", 1))
+}
+
+pub fn remove_last(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| replace_last_of(s, &args.string(0), ""))
+}
+
+pub fn replace(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replace(&args.string(0), &args.string(1)))
+}
+
+pub fn replace_first(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| s.replacen(&args.string(0), &args.string(1), 1))
+}
+
+pub fn replace_last(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let pattern = args.string(0);
+        if pattern.is_empty() {
+            s.to_owned() + &args.string(1)
+        } else {
+            replace_last_of(s, &pattern, &args.string(1))
+        }
+    })
+}
+
+fn replace_last_of(s: &str, pattern: &str, replacement: &str) -> String {
+    match s.rfind(pattern) {
+        Some(i) if !pattern.is_empty() => {
+            format!("{}{replacement}{}", &s[..i], &s[i + pattern.len()..])
+        }
+        _ => s.to_owned(),
+    }
+}
+
+pub fn slice(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let start = slice_arg(args.get(0))?;
+    let length = match args.get(1) {
+        None | Some(Value::Null) => 1,
+        value => slice_arg(value)?,
+    };
+
+    // A negative start and a length that reaches past the end of the
+    // sequence takes everything from start.
+    let stop = start.saturating_add(length);
+    let stop = if start < 0 && stop >= 0 {
+        None
+    } else {
+        Some(stop)
+    };
+
+    Ok(match left {
+        Value::Array(items) => {
+            let (start, stop) = bounds(items.len(), start, stop);
+            Value::Array(items[start..stop].to_vec())
+        }
+        left => {
+            let chars: Vec<char> = to_liquid_string(&left).chars().collect();
+            let (start, stop) = bounds(chars.len(), start, stop);
+            Value::String(chars[start..stop].iter().collect())
+        }
+    })
+}
+
+fn slice_arg(value: Option<&Value>) -> Result<i64, LiquidError> {
+    let found = match value {
+        Some(Value::Number(n)) if n.is_i64() => return Ok(n.as_i64().unwrap_or_default()),
+        Some(Value::String(s)) => match parse_int(s) {
+            Some(n) => return Ok(n),
+            None => "string",
+        },
+        Some(Value::Bool(b)) => return Ok(*b as i64),
+        Some(Value::Number(_)) => "float",
+        _ => "nil",
+    };
+    Err(LiquidError::typ(format!(
+        "slice expected an integer, found {found}"
+    )))
+}
+
+/// Resolve Python-style slice bounds against a sequence of length _len_.
+fn bounds(len: usize, start: i64, stop: Option<i64>) -> (usize, usize) {
+    let resolve = |i: i64| {
+        if i < 0 {
+            len.saturating_sub(i.unsigned_abs() as usize)
+        } else {
+            (i as usize).min(len)
+        }
+    };
+    let start = resolve(start);
+    let stop = stop.map_or(len, resolve);
+    (start, stop.max(start))
+}
+
+pub fn split(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let s = to_liquid_string(&left);
+    let separator = args.string(0);
+    let mut parts: Vec<Value> = if separator.is_empty() {
+        s.chars().map(|ch| Value::String(ch.to_string())).collect()
+    } else {
+        s.split(&separator)
+            .map(|part| Value::String(part.to_owned()))
+            .collect()
+    };
+
+    // Trailing empty strings are dropped, so `"" | split: ","` is empty.
+    while parts.last().is_some_and(|part| part == "") {
+        parts.pop();
+    }
+    Ok(Value::Array(parts))
+}
+
+pub fn truncate(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let length = int_arg(args.get(0), 50, "truncate")?;
+    let end = args.get(1).map_or("...".to_owned(), to_liquid_string);
+    let s = to_liquid_string(&left);
+
+    let chars: Vec<char> = s.chars().collect();
+    if length < 0 || chars.len() <= length as usize {
+        return Ok(Value::String(s));
+    }
+
+    let keep = (length as usize).saturating_sub(end.chars().count());
+    Ok(Value::String(
+        chars[..keep].iter().collect::<String>() + &end,
+    ))
+}
+
+pub fn truncatewords(left: Value, args: &FilterArgs) -> Result<Value, LiquidError> {
+    let count = int_arg(args.get(0), 15, "truncatewords")?.max(1) as usize;
+    let end = args.get(1).map_or("...".to_owned(), to_liquid_string);
+    let s = to_liquid_string(&left);
+
+    let words: Vec<&str> = s.split_whitespace().collect();
+    if words.len() <= count {
+        return Ok(Value::String(s));
+    }
+    Ok(Value::String(words[..count].join(" ") + &end))
+}
+
+/// Cast an optional integer argument, defaulting to _default_ if it's
+/// missing.
+fn int_arg(value: Option<&Value>, default: i64, filter: &str) -> Result<i64, LiquidError> {
+    match value {
+        None => Ok(default),
+        Some(Value::Number(n)) => Ok(n.as_i64().unwrap_or(n.as_f64().unwrap_or_default() as i64)),
+        Some(Value::String(s)) if parse_int(s).is_some() => Ok(parse_int(s).unwrap_or_default()),
+        Some(value) => Err(LiquidError::typ(format!(
+            "{filter} expected an integer, found '{}'",
+            to_liquid_string(value)
+        ))),
+    }
+}
+
+pub fn url_encode(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let mut encoded = String::with_capacity(s.len());
+        for byte in s.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'.' | b'-' | b'~' => {
+                    encoded.push(byte as char)
+                }
+                b' ' => encoded.push('+'),
+                byte => encoded.push_str(&format!("%{byte:02X}")),
+            }
+        }
+        encoded
+    })
+}
+
+pub fn url_decode(left: Value, _: &FilterArgs) -> Result<Value, LiquidError> {
+    map_string(left, |s| {
+        let bytes = s.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match (bytes[i], hex) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                (b'+', _) => decoded.push(b' '),
+                (byte, _) => decoded.push(byte),
+            }
+            i += 1;
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    })
+}
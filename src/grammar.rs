@@ -0,0 +1,117 @@
+//! A description of the syntax understood by a configured parser, for editors,
+//! linters and documentation generators.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::parser::SIMPLE_BLOCK_TAGS;
+
+/// Words the lexer always treats as keywords rather than names. This must be
+/// kept in sync with `reserved_word` in markup.pest.
+pub const RESERVED_WORDS: [&str; 15] = [
+    "true", "false", "and", "or", "in", "not", "contains", "nil", "null", "if", "else", "with",
+    "as", "for", "required",
+];
+
+/// Symbols and word operators available in tag and output expressions.
+pub const OPERATORS: [&str; 19] = [
+    "==", "!=", "<>", ">=", "<=", "=>", ">", "<", "?:", ":", "||", "|", ",", "(", ")", "=", "and",
+    "or", "contains",
+];
+
+/// Metadata about a tag understood by the parser.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone)]
+pub struct TagInfo {
+    pub name: String,
+    /// The name of the tag that closes this tag's block, or `None` if this
+    /// tag does not have a block.
+    pub end: Option<String>,
+    /// Tags that may appear between this tag and its end tag, like `else`.
+    pub intermediates: Vec<String>,
+}
+
+impl TagInfo {
+    fn inline(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            end: None,
+            intermediates: Vec::new(),
+        }
+    }
+
+    fn with_block(name: &str, intermediates: &[&str]) -> Self {
+        Self {
+            name: name.to_owned(),
+            end: Some(format!("end{name}")),
+            intermediates: intermediates.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Returns `true` if this tag has a block, or `false` otherwise.
+    pub fn block(&self) -> bool {
+        self.end.is_some()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TagInfo {
+    #[getter(block)]
+    fn py_block(&self) -> bool {
+        self.block()
+    }
+}
+
+/// Return metadata for the tags built in to the parser.
+pub fn builtin_tags() -> Vec<TagInfo> {
+    vec![
+        TagInfo::inline("assign"),
+        TagInfo::with_block("capture", &[]),
+        TagInfo::with_block("case", &["when", "else"]),
+        TagInfo::inline("cycle"),
+        TagInfo::inline("decrement"),
+        TagInfo::inline("increment"),
+        TagInfo::inline("echo"),
+        TagInfo::with_block("for", &["else"]),
+        TagInfo::with_block("tablerow", &[]),
+        TagInfo::inline("break"),
+        TagInfo::inline("continue"),
+        TagInfo::with_block("if", &["elsif", "else"]),
+        TagInfo::with_block("unless", &["elsif", "else"]),
+        TagInfo::inline("include"),
+        TagInfo::inline("render"),
+        TagInfo::inline("liquid"),
+        TagInfo::with_block("raw", &[]),
+        TagInfo::with_block("comment", &[]),
+        TagInfo::with_block("doc", &[]),
+        TagInfo::inline("extends"),
+        TagInfo::with_block("block", &[]),
+        TagInfo::with_block("macro", &[]),
+        TagInfo::inline("call"),
+        TagInfo::with_block("translate", &["plural"]),
+        TagInfo::with_block("t", &["plural"]),
+    ]
+    .into_iter()
+    .chain(
+        SIMPLE_BLOCK_TAGS
+            .iter()
+            .map(|(name, _)| TagInfo::with_block(name, &[])),
+    )
+    .collect()
+}
+
+/// The tag names, keywords, operators and filter expression functions known to
+/// a configured parser.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    pub tags: Vec<TagInfo>,
+    /// Alternative tag names, mapped to their canonical names.
+    pub tag_aliases: HashMap<String, String>,
+    pub reserved_words: Vec<String>,
+    pub operators: Vec<String>,
+    pub functions: Vec<String>,
+}
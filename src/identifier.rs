@@ -0,0 +1,60 @@
+//! Rules for the names of variables and object members.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::LiquidError;
+use crate::span::Span;
+
+/// Which characters are allowed in identifiers. Hyphens are governed
+/// separately, by the `hyphenated_names` query extension.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum IdentifierPolicy {
+    /// ASCII letters, digits and underscores, plus any non-ASCII character.
+    /// Identifiers can't start with a digit.
+    #[default]
+    Unicode,
+    /// ASCII letters, digits and underscores only.
+    Ascii,
+    /// Unicode identifiers as defined by UAX #31, starting with an
+    /// underscore or an `XID_Start` character and continuing with
+    /// `XID_Continue` characters.
+    Xid,
+    /// Like `Unicode`, but identifiers can start with digits, like
+    /// Shopify/Liquid.
+    Shopify,
+}
+
+impl IdentifierPolicy {
+    /// Check that _name_, found at _span_, is an identifier allowed by this
+    /// policy.
+    pub fn check(&self, name: &str, span: Span) -> Result<(), LiquidError> {
+        self.reason(name).map_or(Ok(()), |reason| {
+            Err(LiquidError::name(format!("invalid identifier `{name}`, {reason}")).with_span(span))
+        })
+    }
+
+    fn reason(&self, name: &str) -> Option<String> {
+        if *self != Self::Shopify && name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Some(String::from("identifiers can't start with a digit"));
+        }
+
+        match self {
+            Self::Ascii => name
+                .chars()
+                .find(|c| !c.is_ascii())
+                .map(|c| format!("'{c}' is not an ASCII character")),
+            Self::Xid => name.chars().enumerate().find_map(|(i, c)| {
+                let ok = match (i, c) {
+                    (_, '_' | '-') => true,
+                    (0, c) => unicode_ident::is_xid_start(c),
+                    (_, c) => unicode_ident::is_xid_continue(c),
+                };
+                (!ok).then(|| format!("'{c}' is not allowed in a Unicode identifier"))
+            }),
+            Self::Unicode | Self::Shopify => None,
+        }
+    }
+}
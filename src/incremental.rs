@@ -0,0 +1,671 @@
+//! Reparse a template after an edit, reusing unaffected top-level nodes.
+
+use crate::ast::{
+    BooleanExpression, CommonArgument, ElseTag, ElsifTag, ExtensionBranch, ExtensionEnd, Filter,
+    FilteredExpression, InlineCondition, KeywordArgument, Node, Parameter, PluralTag, Primitive,
+    Template, WhenTag,
+};
+use crate::errors::LiquidError;
+use crate::markup::{Markup, RangeArgument, Token};
+use crate::parser::LiquidParser;
+use crate::query::{FilterExpression, Query, Segment, Selector};
+use crate::span::Span;
+
+/// A replacement of the text at _span_ with _text_.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub span: Span,
+    pub text: String,
+}
+
+impl Edit {
+    /// Return _source_ with this edit applied.
+    pub fn apply(&self, source: &str) -> String {
+        let mut rv = String::with_capacity(source.len() + self.text.len());
+        rv.push_str(&source[..self.span.start]);
+        rv.push_str(&self.text);
+        rv.push_str(&source[self.span.end..]);
+        rv
+    }
+
+    fn delta(&self) -> isize {
+        self.text.len() as isize - (self.span.end - self.span.start) as isize
+    }
+}
+
+impl LiquidParser {
+    /// Parse the result of applying _edit_ to _source_, where _template_ was
+    /// parsed from _source_.
+    ///
+    /// Only top-level nodes touched by the edit, plus one neighbour either
+    /// side, are reparsed. Spans of the remaining nodes are moved to match
+    /// the new source. If the affected region does not parse on its own, the
+    /// whole of the new source is parsed instead.
+    pub fn reparse(
+        &self,
+        template: &Template,
+        source: &str,
+        edit: &Edit,
+    ) -> Result<Template, LiquidError> {
+        if edit.span.start > edit.span.end
+            || edit.span.end > source.len()
+            || !source.is_char_boundary(edit.span.start)
+            || !source.is_char_boundary(edit.span.end)
+        {
+            return Err(
+                LiquidError::syntax("edit is out of range".to_string()).with_span(edit.span)
+            );
+        }
+
+        let new_source = edit.apply(source);
+        let nodes = &template.nodes;
+
+        let first = nodes.iter().position(|n| n.span().end >= edit.span.start);
+        let last = nodes.iter().rposition(|n| n.span().start <= edit.span.end);
+
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) if first <= last => {
+                (first.saturating_sub(1), (last + 1).min(nodes.len() - 1))
+            }
+            _ => return self.parse(&new_source),
+        };
+
+        let delta = edit.delta();
+        let region_start = nodes[first].span().start;
+        let region_end = nodes[last].span().end.saturating_add_signed(delta);
+
+        if region_start > edit.span.start
+            || nodes[last].span().end < edit.span.end
+            || region_end > new_source.len()
+        {
+            return self.parse(&new_source);
+        }
+
+        let region = match self.parse(&new_source[region_start..region_end]) {
+            Ok(region) => region,
+            Err(_) => return self.parse(&new_source),
+        };
+
+        let mut rv = Vec::with_capacity(nodes.len() - (last - first + 1) + region.nodes.len());
+        rv.extend(nodes[..first].iter().cloned());
+        rv.extend(region.nodes.into_iter().map(|mut node| {
+            node.shift(region_start as isize);
+            node
+        }));
+        rv.extend(nodes[last + 1..].iter().cloned().map(|mut node| {
+            node.shift(delta);
+            node
+        }));
+
+        Ok(Template { nodes: rv.into() })
+    }
+}
+
+/// Every span in a syntax tree.
+pub(crate) trait Spans {
+    /// Call _f_ with each span in this tree.
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span));
+
+    /// Move every span in this tree by a fixed number of bytes.
+    fn shift(&mut self, delta: isize) {
+        self.for_each_span(&mut |span| *span = span.shifted(delta));
+    }
+}
+
+impl Spans for Span {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        f(self);
+    }
+}
+
+impl<T: Spans> Spans for Vec<T> {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        for item in self {
+            item.for_each_span(f);
+        }
+    }
+}
+
+impl<T: Spans> Spans for Option<T> {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        if let Some(item) = self {
+            item.for_each_span(f);
+        }
+    }
+}
+
+impl<T: Spans> Spans for Box<T> {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.as_mut().for_each_span(f);
+    }
+}
+
+impl Spans for Node {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Node::Content { span, .. }
+            | Node::Raw { span, .. }
+            | Node::Comment { span, .. }
+            | Node::DocTag { span, .. }
+            | Node::BreakTag { span, .. }
+            | Node::ContinueTag { span, .. }
+            | Node::ExtendsTag { span, .. } => span.for_each_span(f),
+            Node::DecrementTag {
+                name_span, span, ..
+            }
+            | Node::IncrementTag {
+                name_span, span, ..
+            } => {
+                name_span.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::Output {
+                expression, span, ..
+            }
+            | Node::EchoTag {
+                expression, span, ..
+            } => {
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::AssignTag {
+                identifier_span,
+                expression,
+                span,
+                ..
+            } => {
+                identifier_span.for_each_span(f);
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::CaptureTag {
+                identifier_span,
+                block,
+                span,
+                ..
+            } => {
+                identifier_span.for_each_span(f);
+                block.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::LiquidTag { block, span, .. }
+            | Node::BlockTag { block, span, .. }
+            | Node::IfchangedTag { block, span, .. } => {
+                block.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::CaseTag {
+                expression,
+                whens,
+                default,
+                span,
+                ..
+            } => {
+                expression.for_each_span(f);
+                whens.for_each_span(f);
+                default.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::CycleTag {
+                name, args, span, ..
+            } => {
+                name.for_each_span(f);
+                args.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::ForTag {
+                name_spans,
+                iterable,
+                limit,
+                offset,
+                block,
+                default,
+                span,
+                ..
+            } => {
+                name_spans.for_each_span(f);
+                iterable.for_each_span(f);
+                limit.for_each_span(f);
+                offset.for_each_span(f);
+                block.for_each_span(f);
+                default.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::TablerowTag {
+                name_span,
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                span,
+                ..
+            } => {
+                name_span.for_each_span(f);
+                iterable.for_each_span(f);
+                cols.for_each_span(f);
+                limit.for_each_span(f);
+                offset.for_each_span(f);
+                block.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::IfTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                span,
+                ..
+            }
+            | Node::UnlessTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                span,
+                ..
+            } => {
+                condition.for_each_span(f);
+                block.for_each_span(f);
+                alternatives.for_each_span(f);
+                default.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::IncludeTag {
+                target,
+                variable,
+                args,
+                span,
+                ..
+            } => {
+                target.for_each_span(f);
+                variable.for_each_span(f);
+                args.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::RenderTag {
+                target,
+                variable,
+                args,
+                span,
+                ..
+            } => {
+                target.for_each_span(f);
+                variable.for_each_span(f);
+                args.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::MacroTag {
+                params,
+                block,
+                span,
+                ..
+            } => {
+                params.for_each_span(f);
+                block.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::CallTag { args, span, .. } => {
+                args.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::TranslateTag {
+                args,
+                block,
+                plural,
+                span,
+                ..
+            } => {
+                args.for_each_span(f);
+                block.for_each_span(f);
+                plural.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Node::TagExtension {
+                expression,
+                args,
+                block,
+                branches,
+                end,
+                span,
+                ..
+            } => {
+                expression.for_each_span(f);
+                args.for_each_span(f);
+                block.for_each_span(f);
+                branches.for_each_span(f);
+                end.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
+
+impl Spans for ExtensionBranch {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.expression.for_each_span(f);
+        self.block.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for ExtensionEnd {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for Markup {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Markup::Content { span, .. }
+            | Markup::Comment { span, .. }
+            | Markup::Error { span, .. } => span.for_each_span(f),
+            Markup::Raw { span, spans, .. } => {
+                span.for_each_span(f);
+                if let Some(spans) = spans {
+                    spans.open.for_each_span(f);
+                    spans.content.for_each_span(f);
+                    spans.close.for_each_span(f);
+                }
+            }
+            Markup::Output {
+                expression, span, ..
+            } => {
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Markup::Tag {
+                expression, span, ..
+            } => {
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Markup::Lines {
+                statements, span, ..
+            } => {
+                statements.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Markup::EOI {} => (),
+        }
+    }
+}
+
+impl Spans for Token {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Token::RangeLiteral {
+                start,
+                stop,
+                step,
+                span,
+            } => {
+                start.for_each_span(f);
+                stop.for_each_span(f);
+                step.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Token::Query { path, span } => {
+                path.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Token::True_ { span }
+            | Token::False_ { span }
+            | Token::And { span }
+            | Token::Or { span }
+            | Token::In { span }
+            | Token::Not { span }
+            | Token::Contains { span }
+            | Token::Null { span }
+            | Token::If { span }
+            | Token::Else { span }
+            | Token::With { span }
+            | Token::Required { span }
+            | Token::As { span }
+            | Token::For { span }
+            | Token::Eq { span }
+            | Token::Ne { span }
+            | Token::Ge { span }
+            | Token::Gt { span }
+            | Token::Le { span }
+            | Token::Lt { span }
+            | Token::Colon { span }
+            | Token::Pipe { span }
+            | Token::DoublePipe { span }
+            | Token::Elvis { span }
+            | Token::Arrow { span }
+            | Token::Comma { span }
+            | Token::LeftParen { span }
+            | Token::RightParen { span }
+            | Token::Assign { span }
+            | Token::StringLiteral { span, .. }
+            | Token::IntegerLiteral { span, .. }
+            | Token::FloatLiteral { span, .. }
+            | Token::Word { span, .. } => span.for_each_span(f),
+        }
+    }
+}
+
+impl Spans for RangeArgument {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            RangeArgument::StringLiteral { span, .. }
+            | RangeArgument::IntegerLiteral { span, .. }
+            | RangeArgument::FloatLiteral { span, .. } => span.for_each_span(f),
+            RangeArgument::Query { path, span } => {
+                path.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
+
+impl Spans for ElsifTag {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.condition.for_each_span(f);
+        self.block.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for ElseTag {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.block.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for PluralTag {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.block.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for WhenTag {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.args.for_each_span(f);
+        self.block.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for FilteredExpression {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.left.for_each_span(f);
+        self.filters.for_each_span(f);
+        self.condition.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for InlineCondition {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.expr.for_each_span(f);
+        self.alternative.for_each_span(f);
+        self.alternative_filters.for_each_span(f);
+        if let Some(condition) = &mut self.alternative_condition {
+            condition.for_each_span(f);
+        }
+        self.tail_filters.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for Filter {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.args.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for CommonArgument {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.value.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for Parameter {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.default.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for KeywordArgument {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.value.for_each_span(f);
+        self.span.for_each_span(f);
+    }
+}
+
+impl Spans for Primitive {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Primitive::TrueLiteral { span }
+            | Primitive::FalseLiteral { span }
+            | Primitive::NullLiteral { span }
+            | Primitive::Empty { span }
+            | Primitive::Blank { span }
+            | Primitive::Integer { span, .. }
+            | Primitive::Float { span, .. }
+            | Primitive::StringLiteral { span, .. } => span.for_each_span(f),
+            Primitive::Range {
+                start,
+                stop,
+                step,
+                span,
+            } => {
+                start.for_each_span(f);
+                stop.for_each_span(f);
+                step.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Primitive::Query { path, span } => {
+                path.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Primitive::Lambda { expr, span, .. } => {
+                expr.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
+
+impl Spans for BooleanExpression {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            BooleanExpression::Primitive { expr } => expr.for_each_span(f),
+            BooleanExpression::LogicalNot { expr, span } => {
+                expr.for_each_span(f);
+                span.for_each_span(f);
+            }
+            BooleanExpression::Logical {
+                left, right, span, ..
+            }
+            | BooleanExpression::Comparison {
+                left, right, span, ..
+            } => {
+                left.for_each_span(f);
+                right.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
+
+impl Spans for Query {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        self.segments.for_each_span(f);
+    }
+}
+
+impl Spans for Segment {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Segment::Child { selectors, span } | Segment::Recursive { selectors, span } => {
+                selectors.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Segment::Eoi {} => (),
+        }
+    }
+}
+
+impl Spans for Selector {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            Selector::Name { span, .. }
+            | Selector::Index { span, .. }
+            | Selector::Slice { span, .. }
+            | Selector::Wild { span } => span.for_each_span(f),
+            Selector::Filter { expression, span } => {
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            Selector::SingularQuery { query, span } => {
+                query.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
+
+impl Spans for FilterExpression {
+    fn for_each_span(&mut self, f: &mut impl FnMut(&mut Span)) {
+        match self {
+            FilterExpression::True_ { span }
+            | FilterExpression::False_ { span }
+            | FilterExpression::Null { span }
+            | FilterExpression::StringLiteral { span, .. }
+            | FilterExpression::Int { span, .. }
+            | FilterExpression::Float { span, .. } => span.for_each_span(f),
+            FilterExpression::Not { expression, span } => {
+                expression.for_each_span(f);
+                span.for_each_span(f);
+            }
+            FilterExpression::Logical {
+                left, right, span, ..
+            }
+            | FilterExpression::Comparison {
+                left, right, span, ..
+            } => {
+                left.for_each_span(f);
+                right.for_each_span(f);
+                span.for_each_span(f);
+            }
+            FilterExpression::RelativeQuery { query, span }
+            | FilterExpression::RootQuery { query, span } => {
+                query.for_each_span(f);
+                span.for_each_span(f);
+            }
+            FilterExpression::Function { args, span, .. } => {
+                args.for_each_span(f);
+                span.for_each_span(f);
+            }
+        }
+    }
+}
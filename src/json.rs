@@ -0,0 +1,37 @@
+//! Export parse trees as JSON, for other tools and for round-trip tests.
+//!
+//! Templates, markup, tokens and queries all serialize with serde's default
+//! externally tagged representation, so a node looks like
+//! `{"Output": {"wc": ["Default", "Default"], "expression": ..., "span": ...}}`.
+
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, PyErr};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Serialize _value_ as a JSON string.
+pub fn to_json<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(value)
+}
+
+/// Deserialize a value previously serialized with `to_json`.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> serde_json::Result<T> {
+    serde_json::from_str(json)
+}
+
+/// A JSON error, raised as `ValueError` in Python.
+#[derive(Debug)]
+pub struct JsonError(pub serde_json::Error);
+
+impl From<serde_json::Error> for JsonError {
+    fn from(err: serde_json::Error) -> Self {
+        JsonError(err)
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<JsonError> for PyErr {
+    fn from(err: JsonError) -> Self {
+        PyValueError::new_err(err.0.to_string())
+    }
+}
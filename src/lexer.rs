@@ -1,27 +1,146 @@
-use std::{collections::HashMap, ops::RangeInclusive};
+use std::{collections::HashMap, ops::RangeInclusive, str::FromStr, sync::Arc};
 
+use lazy_static::lazy_static;
+use pest::error::InputLocation;
 use pest::{iterators::Pair, iterators::Pairs, Parser};
 use pest_derive::Parser;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rayon::prelude::*;
 
+use crate::dialect::{disallowed, Dialect};
 use crate::errors::LiquidError;
-use crate::markup::{Markup, RangeArgument, Token, Whitespace};
+use crate::identifier::IdentifierPolicy;
+use crate::incremental::Spans;
+use crate::markup::{CommentKind, Markup, RangeArgument, RawSpans, Token};
+use crate::pest_errors;
+use crate::query::cache::QueryCache;
+use crate::query::eval::{FunctionExtension, FunctionExtensions, FunctionValue};
 use crate::query::{
-    ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector,
+    iregexp, ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector,
 };
+use crate::scanner;
+use crate::span::Span;
 use crate::unescape::unescape;
+use crate::whitespace::Whitespace;
 
 #[derive(Parser)]
 #[grammar = "markup.pest"]
-struct Liquid;
+pub(crate) struct Liquid;
+
+/// An iterator over markup, returned by `Lexer::iter_tokens`.
+pub struct MarkupIter<'a> {
+    lexer: &'a Lexer,
+    source: &'a str,
+    /// The start of the next piece of markup, or `None` when we're done.
+    pos: Option<usize>,
+}
+
+impl Iterator for MarkupIter<'_> {
+    type Item = Result<Markup, LiquidError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        advance(self.lexer, self.source, &mut self.pos)
+    }
+}
+
+/// A Python iterator over markup, tokenizing one piece of markup at a time.
+#[cfg(feature = "python")]
+#[pyclass]
+pub struct MarkupIterator {
+    lexer: Arc<Lexer>,
+    source: String,
+    pos: Option<usize>,
+}
+
+#[cfg(feature = "python")]
+impl MarkupIterator {
+    pub fn new(lexer: Arc<Lexer>, source: String) -> Self {
+        Self {
+            lexer,
+            source,
+            pos: Some(0),
+        }
+    }
+}
 
+#[cfg(feature = "python")]
+#[pymethods]
+impl MarkupIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Result<Option<Markup>, LiquidError> {
+        advance(&self.lexer, &self.source, &mut self.pos).transpose()
+    }
+}
+
+/// Tokenize the markup at _pos_, moving _pos_ past it, or to `None` after
+/// the end of the template or an error.
+fn advance(
+    lexer: &Lexer,
+    source: &str,
+    pos: &mut Option<usize>,
+) -> Option<Result<Markup, LiquidError>> {
+    match lexer.next_markup(source, (*pos)?) {
+        Ok((markup, end)) => {
+            *pos = if matches!(markup, Markup::EOI {}) {
+                None
+            } else {
+                Some(end)
+            };
+            Some(Ok(markup))
+        }
+        Err(err) => {
+            *pos = None;
+            Some(Err(err))
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Lexer {
     pub query_parser: QueryParser,
+    /// Record the spans of the tags and text inside `raw` blocks in
+    /// `Markup::Raw`.
+    pub raw_spans: bool,
+    /// Syntax accepted in addition to, or instead of, standard Liquid2.
+    pub dialect: Dialect,
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Lexer {
     pub fn new() -> Self {
         Lexer {
             query_parser: QueryParser::new(),
+            raw_spans: false,
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// Return a lexer that accepts _dialect_.
+    pub fn with_dialect(dialect: Dialect) -> Self {
+        Lexer {
+            dialect,
+            ..Lexer::new()
+        }
+    }
+
+    /// Return a lexer that only accepts the given non-standard query syntax.
+    pub fn with_query_extensions(extensions: QueryExtensions) -> Self {
+        Lexer {
+            query_parser: QueryParser {
+                extensions,
+                ..QueryParser::new()
+            },
+            raw_spans: false,
+            dialect: Dialect::default(),
         }
     }
 
@@ -30,23 +149,158 @@ impl Lexer {
         println!("{:#?}", elements);
     }
 
+    /// Tokenize _source_, ending with `Markup::EOI`.
+    ///
+    /// Text content and `raw` blocks are found by a hand-written scanner,
+    /// and other markup is tokenized by the grammar one piece at a time.
     pub fn tokenize(&self, source: &str) -> Result<Vec<Markup>, LiquidError> {
-        let pairs = Liquid::parse(Rule::markup, source)
-            .map_err(|err| LiquidError::syntax(err.to_string()))?;
+        // On error, tokenize the whole template with the grammar, which
+        // reports a syntax error anywhere in the template before other
+        // errors, like an invalid escape sequence.
+        self.iter_tokens(source)
+            .collect::<Result<Vec<_>, _>>()
+            .or_else(|_| self.tokenize_grammar(source))
+    }
+
+    /// Tokenize _source_ with the grammar alone, without the scanner used by
+    /// `tokenize`. Both produce the same markup and errors.
+    pub fn tokenize_grammar(&self, source: &str) -> Result<Vec<Markup>, LiquidError> {
+        let pairs = Liquid::parse(Rule::markup, source).map_err(|err| syntax_error(err, source))?;
 
         let tokens: Result<Vec<_>, _> = pairs.into_iter().map(|p| self.markup(p)).collect();
         tokens
     }
 
-    pub fn parse_query(&self, path: &str) -> Result<Query, LiquidError> {
+    /// Return an iterator over markup in _source_ that tokenizes one piece of
+    /// markup at a time, yielding the same items as `tokenize`. The iterator
+    /// is exhausted after the first error.
+    pub fn iter_tokens<'a>(&'a self, source: &'a str) -> MarkupIter<'a> {
+        MarkupIter {
+            lexer: self,
+            source,
+            pos: Some(0),
+        }
+    }
+
+    /// Tokenize the markup starting at byte _start_ of _source_, returning it
+    /// with the offset of the markup that follows it.
+    pub(crate) fn next_markup(
+        &self,
+        source: &str,
+        start: usize,
+    ) -> Result<(Markup, usize), LiquidError> {
+        if start >= source.len() {
+            return Ok((Markup::EOI {}, source.len()));
+        }
+
+        let end = scanner::content_end(source, start);
+        if end > start {
+            let markup = Markup::Content {
+                text: source[start..end].to_owned(),
+                span: Span::new(start, end),
+            };
+            return Ok((markup, end));
+        }
+
+        if let Some(raw) = scanner::raw_block(source, start) {
+            return Ok((self.raw_markup(source, &raw), raw.span.end));
+        }
+
+        if let Some(tag) = scanner::bare_tag(source, start) {
+            let markup = Markup::Tag {
+                wc: tag.wc,
+                name: source[tag.name.start..tag.name.end].to_owned(),
+                expression: None,
+                span: tag.span,
+            };
+            return Ok((markup, tag.span.end));
+        }
+
+        // Only an output statement can start with `{{`, and only a comment
+        // with `{#`, so there's no need to try every kind of markup.
+        let rule = match &source.as_bytes()[start..start + 2] {
+            b"{{" => Rule::output,
+            b"{#" => Rule::comment,
+            _ => Rule::_markup_next,
+        };
+
+        let pair = match Liquid::parse(rule, &source[start..]) {
+            Ok(mut pairs) if rule == Rule::_markup_next => {
+                pairs.next().unwrap().into_inner().next().unwrap()
+            }
+            Ok(mut pairs) => pairs.next().unwrap(),
+            // Report the error as `tokenize` would, with positions and
+            // context from the whole template.
+            Err(err) => {
+                return Err(self
+                    .tokenize_grammar(source)
+                    .err()
+                    .unwrap_or_else(|| syntax_error(err, &source[start..])))
+            }
+        };
+
+        let delta = start as isize;
+        let end = start + pair.as_span().end();
+        match self.markup(pair) {
+            Ok(mut markup) => {
+                markup.shift(delta);
+                Ok((markup, end))
+            }
+            Err(mut err) => {
+                err.span = err.span.map(|span| span.shifted(delta));
+                Err(err)
+            }
+        }
+    }
+
+    /// Tokenize each of _sources_ in parallel, returning results in the same
+    /// order as _sources_.
+    pub fn tokenize_many(&self, sources: &[String]) -> Vec<Result<Vec<Markup>, LiquidError>> {
+        sources.par_iter().map(|s| self.tokenize(s)).collect()
+    }
+
+    /// Tokenize a standalone expression, like the contents of an output
+    /// statement, without its surrounding delimiters.
+    pub fn tokenize_expression(&self, source: &str) -> Result<Vec<Token>, LiquidError> {
         let mut pairs =
-            Liquid::parse(Rule::query, path).map_err(|err| LiquidError::syntax(err.to_string()))?;
+            Liquid::parse(Rule::_expression, source).map_err(|err| syntax_error(err, source))?;
+        let tokens = pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .filter(|p| p.as_rule() != Rule::EOI)
+            .map(|p| self.parse_expr_token(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.check_expression(&tokens)?;
+        Ok(tokens)
+    }
+
+    pub fn parse_query(&self, path: &str) -> Result<Query, LiquidError> {
+        self.parse_query_shared(path).map(Arc::unwrap_or_clone)
+    }
+
+    /// Like `parse_query`, but return a query shared with the query parser's
+    /// cache, if it has one. Errors are not cached.
+    pub fn parse_query_shared(&self, path: &str) -> Result<Arc<Query>, LiquidError> {
+        let Some(cache) = &self.query_parser.cache else {
+            return self.parse_query_uncached(path).map(Arc::new);
+        };
+        if let Some(query) = cache.get(path) {
+            return Ok(query);
+        }
+        let query = Arc::new(self.parse_query_uncached(path)?);
+        cache.insert(path, Arc::clone(&query));
+        Ok(query)
+    }
+
+    fn parse_query_uncached(&self, path: &str) -> Result<Query, LiquidError> {
+        let mut pairs = Liquid::parse(Rule::query, path).map_err(|err| syntax_error(err, path))?;
         self.query_parser.parse(pairs.next().unwrap().into_inner())
     }
 
     pub fn parse_jsonpath_query(&self, path: &str) -> Result<Query, LiquidError> {
-        let mut pairs = Liquid::parse(Rule::_jsonpath, path)
-            .map_err(|err| LiquidError::syntax(err.to_string()))?;
+        let mut pairs =
+            Liquid::parse(Rule::_jsonpath, path).map_err(|err| syntax_error(err, path))?;
         self.query_parser.parse(pairs.next().unwrap().into_inner())
     }
 
@@ -55,23 +309,71 @@ impl Lexer {
         println!("{:#?}", pairs)
     }
 
-    fn markup(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
+    pub(crate) fn markup(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
+        let markup = self.markup_unchecked(pair)?;
+        self.check_dialect(&markup)?;
+        Ok(markup)
+    }
+
+    fn markup_unchecked(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
         match pair.as_rule() {
             Rule::content => self.parse_content(pair),
             Rule::raw => self.parse_raw(pair),
             Rule::comment => self.parse_comment(pair),
+            Rule::block_comment => self.parse_block_comment(pair, CommentKind::Block),
+            Rule::inline_comment => self.parse_inline_comment(pair),
+            Rule::doc => self.parse_block_comment(pair, CommentKind::Doc),
             Rule::output => self.parse_output(pair),
             Rule::tag => self.parse_tag(pair),
+            Rule::jekyll_include => self.parse_jekyll_include(pair),
             Rule::liquid_tag => self.parse_liquid(pair),
             Rule::EOI => Ok(Markup::EOI {}),
             _ => unreachable!(),
         }
     }
 
+    /// Reject _markup_ that uses syntax disallowed by our dialect and spans
+    /// more than one token.
+    fn check_dialect(&self, markup: &Markup) -> Result<(), LiquidError> {
+        match markup {
+            Markup::Comment {
+                kind: CommentKind::Hash,
+                span,
+                ..
+            } if !self.dialect.hash_comments => Err(disallowed("hash_comments", *span)),
+            Markup::Output { expression, .. } => self.check_expression(expression),
+            Markup::Tag {
+                expression: Some(expression),
+                ..
+            } => self.check_expression(expression),
+            Markup::Lines { statements, .. } => statements
+                .iter()
+                .try_for_each(|statement| self.check_dialect(statement)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_expression(&self, tokens: &[Token]) -> Result<(), LiquidError> {
+        if self.dialect.not_contains {
+            return Ok(());
+        }
+
+        match tokens
+            .windows(2)
+            .find(|pair| matches!(pair, [Token::Not { .. }, Token::Contains { .. }]))
+        {
+            Some([not, contains]) => Err(disallowed(
+                "not_contains",
+                Span::new(not.span().start, contains.span().end),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     fn parse_content(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
         let span = pair.as_span();
         Ok(Markup::Content {
-            span: (span.start(), span.end()),
+            span: span.into(),
             text: pair.as_str().to_owned(),
         })
     }
@@ -81,16 +383,42 @@ impl Lexer {
         let mut it = pair.into_inner();
         let wc_left = Whitespace::from_str(it.next().unwrap().as_str());
         let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
-        let text = it.next().unwrap().as_str().to_owned();
+        let content = it.next().unwrap();
         let end_wc_left = Whitespace::from_str(it.next().unwrap().as_str());
         let end_wc_right = Whitespace::from_str(it.next().unwrap().as_str());
+
+        let spans = self.raw_spans.then(|| {
+            let content = content.as_span();
+            RawSpans {
+                open: Span::new(span.start(), content.start()),
+                content: content.into(),
+                close: Span::new(content.end(), span.end()),
+            }
+        });
+
         Ok(Markup::Raw {
-            span: (span.start(), span.end()),
+            span: span.into(),
             wc: (wc_left, wc_right, end_wc_left, end_wc_right),
-            text,
+            text: content.as_str().to_owned(),
+            spans,
         })
     }
 
+    /// Like `parse_raw`, for a block found by the scanner.
+    fn raw_markup(&self, source: &str, raw: &scanner::RawBlock) -> Markup {
+        let content = raw.content;
+        Markup::Raw {
+            wc: raw.wc,
+            text: source[content.start..content.end].to_owned(),
+            span: raw.span,
+            spans: self.raw_spans.then(|| RawSpans {
+                open: Span::new(raw.span.start, content.start),
+                content,
+                close: Span::new(content.end, raw.span.end),
+            }),
+        }
+    }
+
     fn parse_comment(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
         let span = pair.as_span();
         let mut it = pair.into_inner();
@@ -100,10 +428,65 @@ impl Lexer {
         let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
 
         Ok(Markup::Comment {
-            span: (span.start(), span.end()),
+            span: span.into(),
             wc: (wc_left, wc_right),
             hashes,
             text,
+            kind: CommentKind::Hash,
+        })
+    }
+
+    /// Parse `comment` and `doc` blocks, which don't contain markup.
+    fn parse_block_comment(
+        &self,
+        pair: Pair<Rule>,
+        kind: CommentKind,
+    ) -> Result<Markup, LiquidError> {
+        let span = pair.as_span();
+        let mut it = pair.into_inner();
+        let wc_left = Whitespace::from_str(it.next().unwrap().as_str());
+        it.next(); // opening tag end
+        let text = it.next().unwrap().as_str().to_owned();
+        it.next(); // end tag start
+        let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
+
+        Ok(Markup::Comment {
+            span: span.into(),
+            wc: (wc_left, wc_right),
+            hashes: String::new(),
+            text,
+            kind,
+        })
+    }
+
+    fn parse_inline_comment(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
+        let span = pair.as_span();
+        let mut it = pair.into_inner();
+        let wc_left = Whitespace::from_str(it.next().unwrap().as_str());
+        let text = it.next().unwrap();
+        let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
+
+        // Like Shopify/Liquid, every line of an inline comment must be
+        // commented out.
+        let start = text.as_span().start();
+        for (i, _) in text.as_str().match_indices('\n') {
+            let line = text.as_str()[i + 1..].split('\n').next().unwrap();
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                let line_start = start + i + 1 + line.len() - line.trim_start().len();
+                return Err(LiquidError::syntax(
+                    "every line of an inline comment must start with a '#'".to_string(),
+                )
+                .with_span(Span::new(line_start, line_start + trimmed.len())));
+            }
+        }
+
+        Ok(Markup::Comment {
+            span: span.into(),
+            wc: (wc_left, wc_right),
+            hashes: "#".to_owned(),
+            text: text.as_str().to_owned(),
+            kind: CommentKind::Inline,
         })
     }
 
@@ -120,7 +503,7 @@ impl Lexer {
         let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
 
         Ok(Markup::Output {
-            span: (span.start(), span.end()),
+            span: span.into(),
             wc: (wc_left, wc_right),
             expression: tokens,
         })
@@ -142,16 +525,61 @@ impl Lexer {
             tokens = Some(tokens_);
         }
 
+        // A bare Jekyll file name that happens to lex as a name or query,
+        // like `footer.html`.
+        if name == "include" && self.dialect.jekyll_include {
+            if let Some(token @ (Token::Word { .. } | Token::Query { .. })) =
+                tokens.as_mut().and_then(|tokens| tokens.first_mut())
+            {
+                let token_span = token.span();
+                *token = Token::StringLiteral {
+                    value: span.as_str()
+                        [token_span.start - span.start()..token_span.end - span.start()]
+                        .to_owned(),
+                    span: token_span,
+                };
+            }
+        }
+
         let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
 
         Ok(Markup::Tag {
-            span: (span.start(), span.end()),
+            span: span.into(),
             name,
             wc: (wc_left, wc_right),
             expression: tokens,
         })
     }
 
+    /// Parse a Jekyll-style `include` tag, with a file name that isn't a
+    /// string literal, into an `include` tag with a string literal argument.
+    fn parse_jekyll_include(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
+        let span = self.as_span(&pair);
+        if !self.dialect.jekyll_include {
+            return Err(disallowed("jekyll_include", span));
+        }
+
+        let mut it = pair.into_inner();
+        let wc_left = Whitespace::from_str(it.next().unwrap().as_str());
+        let filename = it.next().unwrap();
+        let mut tokens = vec![Token::StringLiteral {
+            value: filename.as_str().to_owned(),
+            span: self.as_span(&filename),
+        }];
+
+        while it.peek().is_some_and(|p| p.as_rule() != Rule::WC) {
+            tokens.push(self.parse_expr_token(it.next().unwrap())?);
+        }
+
+        let wc_right = Whitespace::from_str(it.next().unwrap().as_str());
+        Ok(Markup::Tag {
+            span,
+            name: "include".to_owned(),
+            wc: (wc_left, wc_right),
+            expression: Some(tokens),
+        })
+    }
+
     fn parse_liquid(&self, pair: Pair<Rule>) -> Result<Markup, LiquidError> {
         let span = pair.as_span();
         let mut it = pair.into_inner();
@@ -168,7 +596,7 @@ impl Lexer {
             wc: (wc_left, wc_right),
             name: "liquid".to_owned(),
             statements,
-            span: (span.start(), span.end()),
+            span: span.into(),
         })
     }
 
@@ -186,11 +614,10 @@ impl Lexer {
                 let name = it.next().unwrap().as_str().to_owned();
                 let tokens: Result<Vec<_>, _> =
                     it.map(|token| self.parse_expr_token(token)).collect();
-                let expression =
-                    tokens.and_then(|v| if v.len() == 0 { Ok(None) } else { Ok(Some(v)) })?;
+                let expression = tokens.map(|v| if v.is_empty() { None } else { Some(v) })?;
 
                 Ok(Markup::Tag {
-                    span: (span.start(), span.end()),
+                    span: span.into(),
                     name,
                     wc: (Whitespace::Default, Whitespace::Default),
                     expression,
@@ -200,7 +627,8 @@ impl Lexer {
                 wc: (Whitespace::Default, Whitespace::Default),
                 hashes: "#".to_owned(),
                 text: pair.into_inner().next().unwrap().as_str().to_owned(),
-                span: (span.start(), span.end()),
+                kind: CommentKind::Inline,
+                span: span.into(),
             }),
             _ => unreachable!("{:#?}", pair),
         }
@@ -212,13 +640,17 @@ impl Lexer {
         Ok(match pair.as_rule() {
             Rule::symbol => match pair.as_str() {
                 "==" => Token::Eq { span },
-                "!=" | "<>" => Token::Ne { span },
+                "!=" => Token::Ne { span },
+                "<>" if self.dialect.diamond_ne => Token::Ne { span },
+                "<>" => return Err(disallowed("diamond_ne", span)),
                 ">=" => Token::Ge { span },
                 "<=" => Token::Le { span },
                 ">" => Token::Gt { span },
                 "<" => Token::Lt { span },
                 ":" => Token::Colon { span },
                 "||" => Token::DoublePipe { span },
+                "?:" => Token::Elvis { span },
+                "=>" => Token::Arrow { span },
                 "|" => Token::Pipe { span },
                 "," => Token::Comma { span },
                 "(" => Token::LeftParen { span },
@@ -243,13 +675,17 @@ impl Lexer {
                 "for" => Token::For { span },
                 _ => unreachable!(),
             },
-            Rule::multiline_double_quoted | Rule::double_quoted => Token::StringLiteral {
-                span,
-                value: unescape(pair.as_str(), &span)?,
-            },
-            Rule::multiline_single_quoted | Rule::single_quoted => Token::StringLiteral {
+            Rule::multiline_double_quoted | Rule::multiline_single_quoted
+                if !self.dialect.multiline_strings && pair.as_str().contains(['\n', '\r']) =>
+            {
+                return Err(disallowed("multiline_strings", span))
+            }
+            Rule::multiline_double_quoted
+            | Rule::double_quoted
+            | Rule::multiline_single_quoted
+            | Rule::single_quoted => Token::StringLiteral {
                 span,
-                value: unescape(&pair.as_str().replace("\\'", "'"), &span)?,
+                value: string_literal(&pair)?,
             },
             Rule::number => self.parse_number(pair)?,
             Rule::range => self.parse_range(pair)?,
@@ -257,10 +693,13 @@ impl Lexer {
                 span,
                 path: self.query_parser.parse(pair.into_inner())?,
             },
-            Rule::word => Token::Word {
-                span,
-                value: pair.as_str().to_owned(),
-            },
+            Rule::word => {
+                self.query_parser.identifiers.check(pair.as_str(), span)?;
+                Token::Word {
+                    span,
+                    value: pair.as_str().to_owned(),
+                }
+            }
             _ => unreachable!("{:#?}", pair),
         })
     }
@@ -304,17 +743,16 @@ impl Lexer {
         if is_float {
             Ok(Token::FloatLiteral {
                 span,
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| LiquidError::syntax(String::from("invalid float literal")))?,
+                value: n.parse::<f64>().map_err(|_| {
+                    LiquidError::syntax(String::from("invalid float literal")).with_span(span)
+                })?,
             })
         } else {
             Ok(Token::IntegerLiteral {
                 span,
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| LiquidError::syntax(String::from("invalid integer literal")))?
-                    as i64,
+                value: n.parse::<f64>().map_err(|_| {
+                    LiquidError::syntax(String::from("invalid integer literal")).with_span(span)
+                })? as i64,
             })
         }
     }
@@ -324,81 +762,324 @@ impl Lexer {
         let mut it = expr.into_inner();
         let start = self.parse_range_argument(it.next().unwrap())?;
         let stop = self.parse_range_argument(it.next().unwrap())?;
-        Ok(Token::RangeLiteral { span, start, stop })
+        let step = it
+            .next()
+            .map(|pair| self.parse_range_argument(pair))
+            .transpose()?;
+        Ok(Token::RangeLiteral {
+            span,
+            start,
+            stop,
+            step,
+        })
     }
 
+    /// Range endpoints and steps must be integers, or something that
+    /// might be an integer at render time. String literals must contain an
+    /// integer, and float literals are an error unless the dialect has
+    /// `lax_ranges` set.
     fn parse_range_argument(&self, pair: Pair<Rule>) -> Result<RangeArgument, LiquidError> {
         let span = self.as_span(&pair);
         match pair.as_rule() {
             Rule::number => match self.parse_number(pair)? {
-                Token::FloatLiteral { span, value } => {
-                    Ok(RangeArgument::FloatLiteral { span, value })
+                Token::FloatLiteral { span, value } if self.dialect.lax_ranges => {
+                    Ok(RangeArgument::IntegerLiteral {
+                        span,
+                        value: value.trunc() as i64,
+                    })
                 }
+                Token::FloatLiteral { span, value } => Err(LiquidError::typ(format!(
+                    "expected an integer range argument, found {value}"
+                ))
+                .with_span(span)),
                 Token::IntegerLiteral { span, value } => {
                     Ok(RangeArgument::IntegerLiteral { span, value })
                 }
                 _ => unreachable!(),
             },
-            Rule::query => Ok(RangeArgument::Query {
+            Rule::range_query => Ok(RangeArgument::Query {
                 span,
                 path: self.query_parser.parse(pair.into_inner())?,
             }),
-            Rule::string_literal | Rule::multiline_string_literal => {
-                Ok(RangeArgument::StringLiteral {
-                    span,
-                    value: pair.as_str().to_owned(),
-                })
+            Rule::multiline_double_quoted
+            | Rule::double_quoted
+            | Rule::multiline_single_quoted
+            | Rule::single_quoted => {
+                let value = string_literal(&pair)?;
+                if value.trim().parse::<i64>().is_err() {
+                    return Err(LiquidError::typ(format!(
+                        "expected an integer range argument, found '{value}'"
+                    ))
+                    .with_span(span));
+                }
+                Ok(RangeArgument::StringLiteral { span, value })
             }
             _ => unreachable!("{:#?}", pair),
         }
     }
 
-    fn as_span(&self, pair: &Pair<Rule>) -> (usize, usize) {
-        let _span = pair.as_span();
-        return (_span.start(), _span.end());
+    fn as_span(&self, pair: &Pair<Rule>) -> Span {
+        pair.as_span().into()
+    }
+}
+
+/// Unescape the contents of a quoted string, like a Liquid string literal or
+/// a JSONPath name selector. Liquid and JSONPath share the same escape
+/// sequences.
+fn string_literal(pair: &Pair<Rule>) -> Result<String, LiquidError> {
+    let quote = match pair.as_rule() {
+        Rule::single_quoted | Rule::multiline_single_quoted => '\'',
+        _ => '"',
+    };
+    unescape(pair.as_str(), quote, &pair.as_span().into())
+}
+
+/// Convert a pest error from parsing _source_ to a `LiquidError`, without
+/// exposing grammar rule names.
+pub(crate) fn syntax_error(err: pest::error::Error<Rule>, source: &str) -> LiquidError {
+    let span = match err.location {
+        InputLocation::Pos(pos) => Span::new(pos, pos),
+        InputLocation::Span((start, end)) => Span::new(start, end),
+    };
+    LiquidError::syntax(pest_errors::message(&err, source)).with_span(span)
+}
+
+/// Non-standard JSONPath syntax accepted in Liquid templates. Each extension
+/// can be disabled independently, down to strict RFC 9535 queries.
+///
+/// A single name, like `a`, is lexed as a word rather than a query, so is
+/// unaffected by `implicit_root`.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryExtensions {
+    /// Allow embedded queries as selectors, like `a[b.c]`.
+    pub singular_query_selector: bool,
+    /// Allow queries without a leading `$`, like `a.b`.
+    pub implicit_root: bool,
+    /// Allow hyphens in shorthand names, like `a.b-c`.
+    pub hyphenated_names: bool,
+}
+
+impl Default for QueryExtensions {
+    fn default() -> Self {
+        Self {
+            singular_query_selector: true,
+            implicit_root: true,
+            hyphenated_names: true,
+        }
+    }
+}
+
+impl QueryExtensions {
+    /// Every extension disabled.
+    pub fn strict() -> Self {
+        Self {
+            singular_query_selector: false,
+            implicit_root: false,
+            hyphenated_names: false,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl QueryExtensions {
+    #[new]
+    #[pyo3(signature = (*, singular_query_selector=true, implicit_root=true, hyphenated_names=true))]
+    fn py_new(singular_query_selector: bool, implicit_root: bool, hyphenated_names: bool) -> Self {
+        Self {
+            singular_query_selector,
+            implicit_root,
+            hyphenated_names,
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "strict")]
+    fn py_strict() -> Self {
+        Self::strict()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "QueryExtensions(singular_query_selector={}, implicit_root={}, hyphenated_names={})",
+            py_bool(self.singular_query_selector),
+            py_bool(self.implicit_root),
+            py_bool(self.hyphenated_names)
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
     }
 }
 
+fn disabled_extension(name: &str, span: Span) -> LiquidError {
+    LiquidError::syntax(format!("the '{name}' query extension is disabled")).with_span(span)
+}
+
+#[derive(Clone)]
 pub struct QueryParser {
     pub index_range: RangeInclusive<i64>,
-    pub functions: HashMap<String, FunctionSignature>,
+    /// Function signatures, shared between clones until one registers a
+    /// function.
+    pub functions: Arc<HashMap<String, FunctionSignature>>,
+    /// Implementations of functions registered with
+    /// `register_function_extension`, for evaluating queries with
+    /// `Query::find_with`. Shared between clones like _functions_.
+    pub function_extensions: Arc<FunctionExtensions>,
+    pub extensions: QueryExtensions,
+    /// Reject queries with more descendant segments than this.
+    pub max_descendant_segments: Option<usize>,
+    /// Reject queries with filters nested more deeply than this.
+    pub max_filter_depth: Option<usize>,
+    /// Characters allowed in shorthand member names and in words, like the
+    /// names given to `assign`, `capture` and `for`.
+    pub identifiers: IdentifierPolicy,
+    /// Queries parsed by `Lexer::parse_query`, keyed by path. Queries in
+    /// templates are not cached, as their spans depend on where they appear.
+    /// Clear the cache after changing other options.
+    pub cache: Option<Arc<QueryCache>>,
+}
+
+impl Default for QueryParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl QueryParser {
     pub fn new() -> Self {
         QueryParser {
             index_range: ((-2_i64).pow(53) + 1..=2_i64.pow(53) - 1),
-            functions: standard_functions(),
+            functions: Arc::clone(&STANDARD_FUNCTIONS),
+            function_extensions: Arc::default(),
+            extensions: QueryExtensions::default(),
+            max_descendant_segments: None,
+            max_filter_depth: None,
+            identifiers: IdentifierPolicy::default(),
+            cache: None,
         }
     }
 
+    /// Make a filter function called _name_ available to queries, or replace
+    /// the signature of an existing one. Calls to it are checked against
+    /// _param_types_ and _return_type_ like the standard functions.
+    ///
+    /// This clears the query cache, which might hold queries that call an
+    /// earlier signature.
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        param_types: Vec<ExpressionType>,
+        return_type: ExpressionType,
+    ) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        Arc::make_mut(&mut self.functions).insert(
+            name.to_owned(),
+            FunctionSignature {
+                param_types,
+                return_type,
+            },
+        );
+    }
+
+    /// Like `register_function`, but also make _func_ the implementation of
+    /// _name_, so queries calling it can be evaluated by `Query::find_with`
+    /// with this parser's `function_extensions`. An implementation for one
+    /// of the standard functions replaces the standard one.
+    pub fn register_function_extension<F>(
+        &mut self,
+        name: &str,
+        param_types: Vec<ExpressionType>,
+        return_type: ExpressionType,
+        func: F,
+    ) where
+        F: Fn(Vec<FunctionValue>) -> Result<FunctionValue, LiquidError> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.function_extensions).insert(
+            name.to_owned(),
+            FunctionExtension {
+                param_types: param_types.clone(),
+                return_type,
+                func: Arc::new(func),
+            },
+        );
+        self.register_function(name, param_types, return_type);
+    }
+
     pub fn parse(&self, segments: Pairs<Rule>) -> Result<Query, LiquidError> {
         let segments: Result<Vec<_>, _> = segments
             .map(|segment| self.parse_segment(segment))
             .collect();
 
-        Ok(Query {
-            segments: segments?,
-        })
+        let query = Query {
+            segments: segments?.into(),
+        };
+        self.check_limits(&query)?;
+        Ok(query)
+    }
+
+    fn check_limits(&self, query: &Query) -> Result<(), LiquidError> {
+        if let Some(limit) = self.max_descendant_segments {
+            let count = query.descendant_segments();
+            if count > limit {
+                return Err(LiquidError::syntax(format!(
+                    "query has {count} descendant segments, the limit is {limit}"
+                )));
+            }
+        }
+
+        if let Some(limit) = self.max_filter_depth {
+            let depth = query.filter_depth();
+            if depth > limit {
+                return Err(LiquidError::syntax(format!(
+                    "query filters are nested {depth} deep, the limit is {limit}"
+                )));
+            }
+        }
+
+        Ok(())
     }
 
     fn parse_segment(&self, segment: Pair<Rule>) -> Result<Segment, LiquidError> {
         let span = self.as_span(&segment);
+
+        if matches!(
+            segment.as_rule(),
+            Rule::implicit_root_segment | Rule::implicit_root_name_segment
+        ) && !self.extensions.implicit_root
+        {
+            return Err(disabled_extension("implicit_root", span));
+        }
+
         Ok(match segment.as_rule() {
             Rule::child_segment | Rule::implicit_root_segment => Segment::Child {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                selectors: self
+                    .parse_segment_inner(segment.into_inner().next().unwrap())?
+                    .into(),
                 span,
             },
             Rule::descendant_segment => Segment::Recursive {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                selectors: self
+                    .parse_segment_inner(segment.into_inner().next().unwrap())?
+                    .into(),
+                span,
+            },
+            Rule::name_segment
+            | Rule::index_segment
+            | Rule::implicit_root_name_segment
+            | Rule::singular_query_segment => Segment::Child {
+                selectors: vec![self.parse_selector(segment.into_inner().next().unwrap())?].into(),
                 span,
             },
-            Rule::name_segment | Rule::index_segment | Rule::implicit_root_name_segment => {
-                Segment::Child {
-                    selectors: vec![self.parse_selector(segment.into_inner().next().unwrap())?],
-                    span,
-                }
-            }
             Rule::EOI => Segment::Eoi {},
             _ => unreachable!("{:#?}", segment),
         })
@@ -415,25 +1096,30 @@ impl QueryParser {
                 seg?
             }
             Rule::wildcard_selector => vec![Selector::Wild { span }],
-            Rule::member_name_shorthand => vec![Selector::Name {
-                // for child_segment
-                name: segment.as_str().to_owned(),
-                span,
-            }],
+            // for child_segment
+            Rule::member_name_shorthand => vec![self.parse_shorthand_name(segment)?],
             _ => unreachable!(),
         })
     }
 
+    fn parse_shorthand_name(&self, name: Pair<Rule>) -> Result<Selector, LiquidError> {
+        let span = self.as_span(&name);
+        if !self.extensions.hyphenated_names && name.as_str().contains('-') {
+            return Err(disabled_extension("hyphenated_names", span));
+        }
+        self.identifiers.check(name.as_str(), span)?;
+        Ok(Selector::Name {
+            name: name.as_str().to_owned(),
+            span,
+        })
+    }
+
     fn parse_selector(&self, selector: Pair<Rule>) -> Result<Selector, LiquidError> {
         let span = self.as_span(&selector);
         // TODO: pass span to parse_*_selector?
         Ok(match selector.as_rule() {
-            Rule::double_quoted => Selector::Name {
-                name: unescape(selector.as_str(), &span)?,
-                span,
-            },
-            Rule::single_quoted => Selector::Name {
-                name: unescape(&selector.as_str().replace("\\'", "'"), &span)?,
+            Rule::double_quoted | Rule::single_quoted => Selector::Name {
+                name: string_literal(&selector)?,
                 span,
             },
             Rule::wildcard_selector => Selector::Wild { span },
@@ -443,11 +1129,8 @@ impl QueryParser {
                 span,
             },
             Rule::filter_selector => self.parse_filter_selector(selector)?,
-            Rule::member_name_shorthand => Selector::Name {
-                // for name_segment
-                name: selector.as_str().to_owned(),
-                span,
-            },
+            // for name_segment
+            Rule::member_name_shorthand => self.parse_shorthand_name(selector)?,
             Rule::singular_query_selector => self.parse_singular_query_selector(selector)?,
             _ => unreachable!("{:#?}", selector),
         })
@@ -488,6 +1171,15 @@ impl QueryParser {
 
     fn parse_singular_query_selector(&self, selector: Pair<Rule>) -> Result<Selector, LiquidError> {
         let span = self.as_span(&selector);
+
+        if !self.extensions.singular_query_selector {
+            return Err(disabled_extension("singular_query_selector", span));
+        }
+
+        if !selector.as_str().starts_with('$') && !self.extensions.implicit_root {
+            return Err(disabled_extension("implicit_root", span));
+        }
+
         let segments: Result<Vec<_>, _> = selector
             .into_inner()
             .map(|segment| self.parse_segment(segment))
@@ -495,7 +1187,7 @@ impl QueryParser {
 
         Ok(Selector::SingularQuery {
             query: Box::new(Query {
-                segments: segments?,
+                segments: segments?.into(),
             }),
             span,
         })
@@ -554,7 +1246,7 @@ impl QueryParser {
                 left: Box::new(and_expr),
                 operator: LogicalOperator::And,
                 right: Box::new(right),
-                span: span,
+                span,
             };
         }
 
@@ -619,12 +1311,8 @@ impl QueryParser {
         // TODO: pass span to parse_*?
         Ok(match expr.as_rule() {
             Rule::number => self.parse_number(expr)?,
-            Rule::double_quoted => FilterExpression::StringLiteral {
-                value: unescape(expr.as_str(), &span)?,
-                span,
-            },
-            Rule::single_quoted => FilterExpression::StringLiteral {
-                value: unescape(&expr.as_str().replace("\\'", "'"), &span)?,
+            Rule::double_quoted | Rule::single_quoted => FilterExpression::StringLiteral {
+                value: string_literal(&expr)?,
                 span,
             },
             Rule::true_literal => FilterExpression::True_ { span },
@@ -638,7 +1326,7 @@ impl QueryParser {
 
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -651,7 +1339,7 @@ impl QueryParser {
 
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -663,53 +1351,28 @@ impl QueryParser {
 
     fn parse_number(&self, expr: Pair<Rule>) -> Result<FilterExpression, LiquidError> {
         let span = self.as_span(&expr);
-        if expr.as_str() == "-0" {
-            return Ok(FilterExpression::Int { value: 0, span });
-        }
-
-        // TODO: change pest grammar to indicate positive or negative exponent?
-        let mut it = expr.into_inner();
-        let mut is_float = false;
-        let mut n = it.next().unwrap().as_str().to_string(); // int
+        let text = expr.as_str();
 
-        if let Some(pair) = it.next() {
-            match pair.as_rule() {
-                Rule::frac => {
-                    is_float = true;
-                    n.push_str(pair.as_str());
-                }
-                Rule::exp => {
-                    let exp_str = pair.as_str();
-                    if exp_str.contains('-') {
-                        is_float = true;
-                    }
-                    n.push_str(exp_str);
-                }
-                _ => unreachable!(),
-            }
-        }
-
-        if let Some(pair) = it.next() {
-            let exp_str = pair.as_str();
-            if exp_str.contains('-') {
-                is_float = true;
-            }
-            n.push_str(exp_str);
-        }
+        // A fraction or negative exponent makes a float. `-0` and `1e2` are
+        // integers.
+        let is_float = expr.into_inner().any(|pair| match pair.as_rule() {
+            Rule::frac => true,
+            Rule::exp => pair.as_str().contains('-'),
+            _ => false,
+        });
 
         if is_float {
             Ok(FilterExpression::Float {
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| LiquidError::syntax(String::from("invalid float literal")))?,
+                value: text.parse::<f64>().map_err(|_| {
+                    LiquidError::syntax(String::from("invalid float literal")).with_span(span)
+                })?,
                 span,
             })
         } else {
             Ok(FilterExpression::Int {
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| LiquidError::syntax(String::from("invalid integer literal")))?
-                    as i64,
+                value: text.parse::<f64>().map_err(|_| {
+                    LiquidError::syntax(String::from("invalid integer literal")).with_span(span)
+                })? as i64,
                 span,
             })
         }
@@ -741,7 +1404,7 @@ impl QueryParser {
 
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -754,7 +1417,7 @@ impl QueryParser {
 
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -782,12 +1445,8 @@ impl QueryParser {
         let span = self.as_span(&expr);
         Ok(match expr.as_rule() {
             Rule::number => self.parse_number(expr)?,
-            Rule::double_quoted => FilterExpression::StringLiteral {
-                value: unescape(expr.as_str(), &span)?,
-                span,
-            },
-            Rule::single_quoted => FilterExpression::StringLiteral {
-                value: unescape(&expr.as_str().replace("\\'", "'"), &span)?,
+            Rule::double_quoted | Rule::single_quoted => FilterExpression::StringLiteral {
+                value: string_literal(&expr)?,
                 span,
             },
             Rule::true_literal => FilterExpression::True_ { span },
@@ -801,7 +1460,7 @@ impl QueryParser {
 
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -814,7 +1473,7 @@ impl QueryParser {
 
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
-                        segments: segments?,
+                        segments: segments?.into(),
                     }),
                     span,
                 }
@@ -956,6 +1615,13 @@ impl QueryParser {
             }
         }
 
+        // literal patterns can be checked before evaluation
+        if matches!(func_name, "match" | "search") {
+            if let Some(FilterExpression::StringLiteral { value, .. }) = args.get(1) {
+                iregexp::validate(value)?;
+            }
+        }
+
         Ok(args)
     }
 
@@ -1001,24 +1667,45 @@ impl QueryParser {
         }
     }
 
-    fn as_span(&self, pair: &Pair<Rule>) -> (usize, usize) {
-        let _span = pair.as_span();
-        return (_span.start(), _span.end());
+    fn as_span(&self, pair: &Pair<Rule>) -> Span {
+        pair.as_span().into()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExpressionType {
     Logical,
     Nodes,
     Value,
 }
 
+impl FromStr for ExpressionType {
+    type Err = LiquidError;
+
+    /// Parse a type name as used by Python function extensions, like
+    /// `"value"` or `"ValueType"`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        match name.strip_suffix("type").unwrap_or(&name) {
+            "logical" => Ok(ExpressionType::Logical),
+            "nodes" => Ok(ExpressionType::Nodes),
+            "value" => Ok(ExpressionType::Value),
+            _ => Err(LiquidError::typ(format!("unknown expression type '{s}'"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct FunctionSignature {
     pub param_types: Vec<ExpressionType>,
     pub return_type: ExpressionType,
 }
 
+lazy_static! {
+    static ref STANDARD_FUNCTIONS: Arc<HashMap<String, FunctionSignature>> =
+        Arc::new(standard_functions());
+}
+
 pub fn standard_functions() -> HashMap<String, FunctionSignature> {
     let mut functions = HashMap::new();
 
@@ -0,0 +1,390 @@
+//! Find likely mistakes in templates that parse successfully.
+//!
+//! A `Linter` runs a list of rules over a syntax tree. Each rule implements
+//! `LintRule` and reports problems as `Lint`s, identified by the rule's name
+//! so they can be filtered or suppressed by callers.
+
+use std::collections::HashSet;
+
+use crate::ast::{walk_filter, Filter, Node, Template, Visitor};
+use crate::grammar::RESERVED_WORDS;
+use crate::render::{leading_wc, trailing_wc};
+use crate::span::Span;
+use crate::whitespace::Whitespace;
+
+/// Filters available in a standard Liquid environment.
+pub const STANDARD_FILTERS: [&str; 51] = [
+    "abs",
+    "append",
+    "at_least",
+    "at_most",
+    "capitalize",
+    "ceil",
+    "compact",
+    "concat",
+    "date",
+    "default",
+    "divided_by",
+    "downcase",
+    "escape",
+    "escape_once",
+    "first",
+    "floor",
+    "join",
+    "last",
+    "lstrip",
+    "map",
+    "minus",
+    "modulo",
+    "newline_to_br",
+    "plus",
+    "prepend",
+    "remove",
+    "remove_first",
+    "remove_last",
+    "replace",
+    "replace_first",
+    "replace_last",
+    "reverse",
+    "round",
+    "rstrip",
+    "size",
+    "slice",
+    "sort",
+    "sort_natural",
+    "split",
+    "strip",
+    "strip_html",
+    "strip_newlines",
+    "sum",
+    "times",
+    "truncate",
+    "truncatewords",
+    "uniq",
+    "upcase",
+    "url_decode",
+    "url_encode",
+    "where",
+];
+
+/// Names that have a special meaning in Liquid and should not be assigned.
+const SPECIAL_NAMES: [&str; 4] = ["empty", "blank", "forloop", "tablerowloop"];
+
+/// A problem found by a lint rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The name of the rule that found the problem.
+    pub rule: &'static str,
+    pub message: String,
+    pub span: Span,
+}
+
+pub trait LintRule: Send + Sync {
+    /// A stable, kebab-case name for this rule, like `unused-capture`.
+    fn name(&self) -> &'static str;
+
+    /// Append problems found in _template_ to _lints_.
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>);
+}
+
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Default for Linter {
+    /// A linter with every built-in rule, using default settings.
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                Box::new(UndefinedFilter::default()),
+                Box::new(DeepNesting::default()),
+                Box::new(UnusedCapture),
+                Box::new(ReservedAssign),
+                Box::new(IneffectiveWhitespaceControl),
+                Box::new(PossiblyUndefined),
+            ],
+        }
+    }
+}
+
+impl Linter {
+    /// A linter without any rules.
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Add _rule_, replacing any existing rule with the same name.
+    pub fn add_rule(&mut self, rule: Box<dyn LintRule>) {
+        self.remove_rule(rule.name());
+        self.rules.push(rule);
+    }
+
+    pub fn remove_rule(&mut self, name: &str) {
+        self.rules.retain(|rule| rule.name() != name);
+    }
+
+    /// The names of this linter's rules, in the order they run.
+    pub fn rule_names(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    /// Run every rule over _template_, returning problems in source order.
+    pub fn lint(&self, template: &Template) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        for rule in &self.rules {
+            rule.check(template, &mut lints);
+        }
+        lints.sort_by_key(|lint| (lint.span.start, lint.span.end));
+        lints
+    }
+}
+
+/// Report filters that are not known to the environment.
+pub struct UndefinedFilter {
+    pub filters: HashSet<String>,
+}
+
+impl Default for UndefinedFilter {
+    fn default() -> Self {
+        Self {
+            filters: STANDARD_FILTERS.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+}
+
+impl LintRule for UndefinedFilter {
+    fn name(&self) -> &'static str {
+        "undefined-filter"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        struct Filters<'a> {
+            rule: &'a UndefinedFilter,
+            lints: &'a mut Vec<Lint>,
+        }
+
+        impl Visitor for Filters<'_> {
+            fn visit_filter(&mut self, filter: &Filter) {
+                if !self.rule.filters.contains(&filter.name) {
+                    self.lints.push(Lint {
+                        rule: self.rule.name(),
+                        message: format!("unknown filter '{}'", filter.name),
+                        span: filter.span,
+                    });
+                }
+                walk_filter(self, filter);
+            }
+        }
+
+        Filters { rule: self, lints }.visit_template(template);
+    }
+}
+
+/// Report blocks nested more than `max_depth` levels deep.
+pub struct DeepNesting {
+    pub max_depth: usize,
+}
+
+impl Default for DeepNesting {
+    fn default() -> Self {
+        Self { max_depth: 5 }
+    }
+}
+
+impl LintRule for DeepNesting {
+    fn name(&self) -> &'static str {
+        "deep-nesting"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        walk(&template.nodes, 0, &mut |node, depth| {
+            // Report only the outermost block that is too deep.
+            if depth == self.max_depth && !blocks(node).is_empty() {
+                lints.push(Lint {
+                    rule: self.name(),
+                    message: format!("blocks are nested more than {} levels deep", self.max_depth),
+                    span: node.span(),
+                });
+            }
+        });
+    }
+}
+
+/// Report `capture` tags whose variable is never used.
+pub struct UnusedCapture;
+
+impl LintRule for UnusedCapture {
+    fn name(&self) -> &'static str {
+        "unused-capture"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        let analysis = template.analyze();
+        let used: HashSet<&str> = analysis.variables.iter().map(|v| v.name.as_str()).collect();
+        for identifier in &analysis.assigned {
+            if identifier.tag == "capture" && !used.contains(identifier.name.as_str()) {
+                lints.push(Lint {
+                    rule: self.name(),
+                    message: format!("'{}' is captured but never used", identifier.name),
+                    span: identifier.span,
+                });
+            }
+        }
+    }
+}
+
+/// Report assignments to keywords and names with a special meaning.
+pub struct ReservedAssign;
+
+impl LintRule for ReservedAssign {
+    fn name(&self) -> &'static str {
+        "reserved-assign"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        for identifier in template.analyze().assigned {
+            let name = identifier.name.as_str();
+            if RESERVED_WORDS.contains(&name) || SPECIAL_NAMES.contains(&name) {
+                lints.push(Lint {
+                    rule: self.name(),
+                    message: format!("'{name}' is a reserved name"),
+                    span: identifier.span,
+                });
+            }
+        }
+    }
+}
+
+/// Report whitespace control that can't trim anything, because the text
+/// next to it doesn't start or end with whitespace.
+pub struct IneffectiveWhitespaceControl;
+
+impl IneffectiveWhitespaceControl {
+    fn check_block(&self, nodes: &[Node], lints: &mut Vec<Lint>) {
+        for pair in nodes.windows(2) {
+            match pair {
+                [Node::Content { text, .. }, node] if !trims(leading_wc(node), text, true) => {
+                    lints.push(self.lint(node))
+                }
+                [node, Node::Content { text, .. }] if !trims(trailing_wc(node), text, false) => {
+                    lints.push(self.lint(node))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn lint(&self, node: &Node) -> Lint {
+        Lint {
+            rule: self.name(),
+            message: "whitespace control has no effect here".to_owned(),
+            span: node.span(),
+        }
+    }
+}
+
+impl LintRule for IneffectiveWhitespaceControl {
+    fn name(&self) -> &'static str {
+        "ineffective-whitespace-control"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        self.check_block(&template.nodes, lints);
+        walk(&template.nodes, 0, &mut |node, _| {
+            for block in blocks(node) {
+                self.check_block(block, lints);
+            }
+        });
+    }
+}
+
+/// Report variables read where the template has assigned them on some
+/// paths, but not others, like after an `assign` in one branch of an `if`.
+pub struct PossiblyUndefined;
+
+impl LintRule for PossiblyUndefined {
+    fn name(&self) -> &'static str {
+        "possibly-undefined"
+    }
+
+    fn check(&self, template: &Template, lints: &mut Vec<Lint>) {
+        for name_use in template.semantic_model().possibly_undefined() {
+            lints.push(Lint {
+                rule: self.name(),
+                message: format!("'{}' might not be defined here", name_use.name),
+                span: name_use.span,
+            });
+        }
+    }
+}
+
+/// Returns `false` if whitespace control _wc_ would leave _text_ unchanged.
+/// _before_ is `true` if _text_ comes before the markup.
+fn trims(wc: Whitespace, text: &str, before: bool) -> bool {
+    let edge = if before {
+        text.chars().last()
+    } else {
+        text.chars().next()
+    };
+
+    match wc {
+        Whitespace::Minus => edge.is_some_and(char::is_whitespace),
+        Whitespace::Smart => edge.is_some_and(|ch| ch == '\r' || ch == '\n'),
+        _ => true,
+    }
+}
+
+/// Call _f_ with every node in _nodes_ and their descendants, along with
+/// how many blocks deep each node is.
+fn walk<'a>(nodes: &'a [Node], depth: usize, f: &mut dyn FnMut(&'a Node, usize)) {
+    for node in nodes {
+        f(node, depth);
+        for block in blocks(node) {
+            walk(block, depth + 1, f);
+        }
+    }
+}
+
+/// Return the blocks directly inside _node_.
+pub(crate) fn blocks(node: &Node) -> Vec<&[Node]> {
+    match node {
+        Node::CaptureTag { block, .. }
+        | Node::LiquidTag { block, .. }
+        | Node::BlockTag { block, .. }
+        | Node::MacroTag { block, .. }
+        | Node::TablerowTag { block, .. }
+        | Node::IfchangedTag { block, .. } => vec![block],
+        Node::CaseTag { whens, default, .. } => whens
+            .iter()
+            .map(|when| when.block.as_slice())
+            .chain(default.iter().map(|default| default.block.as_slice()))
+            .collect(),
+        Node::ForTag { block, default, .. } => std::iter::once(block.as_slice())
+            .chain(default.iter().map(|default| default.block.as_slice()))
+            .collect(),
+        Node::IfTag {
+            block,
+            alternatives,
+            default,
+            ..
+        }
+        | Node::UnlessTag {
+            block,
+            alternatives,
+            default,
+            ..
+        } => std::iter::once(block.as_slice())
+            .chain(alternatives.iter().map(|alt| alt.block.as_slice()))
+            .chain(default.iter().map(|default| default.block.as_slice()))
+            .collect(),
+        Node::TranslateTag { block, plural, .. } => std::iter::once(block.as_slice())
+            .chain(plural.iter().map(|plural| plural.block.as_slice()))
+            .collect(),
+        Node::TagExtension {
+            block, branches, ..
+        } => std::iter::once(block.as_slice())
+            .chain(branches.iter().map(|branch| branch.block.as_slice()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,126 @@
+//! Resolve template names to template source text.
+//!
+//! Every `Loader` is also an `AsyncLoader`, so code that loads templates
+//! asynchronously accepts the built-in loaders too.
+
+use std::collections::HashMap;
+use std::fs;
+use std::future::{self, Future};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+
+use crate::errors::{LiquidError, LiquidErrorType};
+
+/// A source of template text, used to resolve the targets of `include` and
+/// `render` tags.
+pub trait Loader {
+    /// Return the source text of the template called _name_, or a
+    /// `TemplateNotFound` error.
+    fn get_source(&self, name: &str) -> Result<String, LiquidError>;
+}
+
+/// The result of `AsyncLoader::get_source_async`.
+pub type SourceFuture<'a> = Pin<Box<dyn Future<Output = Result<String, LiquidError>> + Send + 'a>>;
+
+/// A source of template text that might need to wait for it, like a
+/// database or a network service.
+pub trait AsyncLoader: Send + Sync {
+    /// Resolve to the source text of the template called _name_, or a
+    /// `TemplateNotFound` error.
+    fn get_source_async<'a>(&'a self, name: &'a str) -> SourceFuture<'a>;
+}
+
+impl<T: Loader + Send + Sync> AsyncLoader for T {
+    fn get_source_async<'a>(&'a self, name: &'a str) -> SourceFuture<'a> {
+        Box::pin(future::ready(self.get_source(name)))
+    }
+}
+
+/// A loader backed by a map of template names to source text.
+#[derive(Debug, Clone, Default)]
+pub struct DictLoader {
+    pub templates: HashMap<String, String>,
+}
+
+impl DictLoader {
+    pub fn new(templates: HashMap<String, String>) -> Self {
+        Self { templates }
+    }
+}
+
+impl Loader for DictLoader {
+    fn get_source(&self, name: &str) -> Result<String, LiquidError> {
+        self.templates
+            .get(name)
+            .cloned()
+            .ok_or_else(|| LiquidError::not_found(name))
+    }
+}
+
+/// A loader that reads templates from files, looking in each directory of
+/// its search path in turn.
+///
+/// Template names are relative paths. Names that are absolute or contain
+/// `..` are never found, so templates can't read files outside the search
+/// path.
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemLoader {
+    pub search_path: Vec<PathBuf>,
+    /// An extension, like `liquid`, added to names that don't have one.
+    pub default_extension: Option<String>,
+}
+
+impl FileSystemLoader {
+    pub fn new<P: Into<PathBuf>>(search_path: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            search_path: search_path.into_iter().map(Into::into).collect(),
+            default_extension: None,
+        }
+    }
+
+    pub fn with_default_extension(mut self, extension: &str) -> Self {
+        self.default_extension = Some(extension.trim_start_matches('.').to_owned());
+        self
+    }
+
+    /// Return the path of the file for the template called _name_, or
+    /// `None` if there isn't one.
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let mut relative = PathBuf::from(name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return None;
+        }
+
+        if let (None, Some(extension)) = (relative.extension(), &self.default_extension) {
+            relative.set_extension(extension);
+        }
+
+        self.search_path
+            .iter()
+            .map(|dir| dir.join(&relative))
+            .find(|path| path.is_file())
+    }
+}
+
+impl Loader for FileSystemLoader {
+    fn get_source(&self, name: &str) -> Result<String, LiquidError> {
+        let path = self
+            .resolve(name)
+            .ok_or_else(|| LiquidError::not_found(name))?;
+        read_template(name, &path)
+    }
+}
+
+fn read_template(name: &str, path: &Path) -> Result<String, LiquidError> {
+    fs::read_to_string(path).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => LiquidError::not_found(name),
+        _ => LiquidError::new(
+            LiquidErrorType::TemplateNotFound,
+            format!("can't read template '{name}' ({}): {err}", path.display()),
+        ),
+    })
+}
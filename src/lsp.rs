@@ -0,0 +1,360 @@
+//! Building blocks for a Language Server Protocol server.
+//!
+//! A `Document` tokenizes a template once, without stopping at syntax
+//! errors, and answers the requests an editor makes most often: document
+//! symbols, diagnostics, folding ranges and completions. Symbols and folding
+//! ranges come from the document's `outline`, so a half-written template
+//! still gets them.
+//!
+//! Results serialize to the JSON shapes defined by the protocol, with
+//! zero-based lines and columns counted in UTF-16 code units, ready to be
+//! used as the `result` of a response. Diagnostic messages come from the
+//! parser, so they don't repeat the template name or line number.
+
+use serde::Serialize;
+
+use crate::ast::tag_expression;
+use crate::completion::CompletionKind;
+use crate::diagnostics::code;
+use crate::errors::LiquidError;
+use crate::grammar::Grammar;
+use crate::markup::{CommentKind, Markup};
+use crate::outline::{outline, OutlineItem};
+use crate::parser::LiquidParser;
+use crate::span::{LineIndex, Span};
+
+/// A location in a document, as a zero-based line and a zero-based count of
+/// UTF-16 code units from the start of that line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(into = "u8")]
+pub enum SymbolKind {
+    Module = 2,
+    Function = 12,
+    Variable = 13,
+    Object = 19,
+}
+
+impl From<SymbolKind> for u8 {
+    fn from(kind: SymbolKind) -> Self {
+        kind as u8
+    }
+}
+
+/// A tag in the document outline. Block tags contain the tags in their
+/// blocks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    /// The variable, block or macro name for tags that define one, or the
+    /// tag's name otherwise.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    /// From the start of the tag to the end of its end tag, if it has one.
+    pub range: Range,
+    /// The defined name, or the tag itself.
+    pub selection_range: Range,
+    pub children: Vec<DocumentSymbol>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(into = "u8")]
+pub enum DiagnosticSeverity {
+    Error = 1,
+    Warning = 2,
+}
+
+impl From<DiagnosticSeverity> for u8 {
+    fn from(severity: DiagnosticSeverity) -> Self {
+        severity as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: DiagnosticSeverity,
+    /// The same codes as `diagnostics::Diagnostic`, like `syntax-error`, or
+    /// the name of a lint rule.
+    pub code: &'static str,
+    /// Always `liquid2`.
+    pub source: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoldingRangeKind {
+    Comment,
+    Region,
+}
+
+/// Lines that can be folded away, leaving _start_line_ visible.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRange {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub kind: FoldingRangeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(into = "u8")]
+pub enum CompletionItemKind {
+    Function = 3,
+    Keyword = 14,
+}
+
+impl From<CompletionItemKind> for u8 {
+    fn from(kind: CompletionItemKind) -> Self {
+        kind as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionItemKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// A template opened in an editor, tokenized with a configured parser.
+pub struct Document<'a> {
+    parser: &'a LiquidParser,
+    source: &'a str,
+    grammar: Grammar,
+    markup: Vec<Markup>,
+    errors: Vec<LiquidError>,
+    lines: LineIndex,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(parser: &'a LiquidParser, source: &'a str) -> Self {
+        let (markup, errors) = parser.lexer.tokenize_lenient(source);
+        Self {
+            parser,
+            source,
+            grammar: parser.grammar(),
+            markup,
+            errors,
+            lines: LineIndex::new(source),
+        }
+    }
+
+    pub fn markup(&self) -> &[Markup] {
+        &self.markup
+    }
+
+    /// The byte offset of _position_, clamped to the end of its line.
+    pub fn offset(&self, position: Position) -> usize {
+        self.lines
+            .utf16_offset(position.line as usize, position.character as usize)
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let (line, character) = self.lines.utf16_position(offset);
+        Position {
+            line: line as u32,
+            character: character as u32,
+        }
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        Range {
+            start: self.position(span.start),
+            end: self.position(span.end),
+        }
+    }
+
+    /// An outline of the document's tags. Intermediate tags like `else` and
+    /// end tags are not symbols of their own.
+    pub fn symbols(&self) -> Vec<DocumentSymbol> {
+        outline(&self.markup, &self.grammar)
+            .iter()
+            .map(|tag| self.symbol(tag))
+            .collect()
+    }
+
+    /// Every markup error, or, if the document tokenized cleanly, the first
+    /// syntax error reported by the parser along with any warnings.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        if !self.errors.is_empty() {
+            return self.errors.iter().map(|err| self.error(err)).collect();
+        }
+
+        match self.parser.parse_with_warnings(self.source) {
+            Ok((_, warnings)) => warnings
+                .iter()
+                .map(|lint| Diagnostic {
+                    range: self.range(lint.span),
+                    severity: DiagnosticSeverity::Warning,
+                    code: lint.rule,
+                    source: "liquid2",
+                    message: lint.message.clone(),
+                })
+                .collect(),
+            Err(err) => vec![self.error(&err)],
+        }
+    }
+
+    /// Folding ranges for block tags, which leave the end tag visible, and
+    /// for raw blocks and comments that span more than one line.
+    pub fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let mut ranges = Vec::new();
+
+        for markup in &self.markup {
+            let kind = match markup {
+                Markup::Comment {
+                    kind: CommentKind::Inline,
+                    ..
+                } => continue,
+                Markup::Comment { .. } => FoldingRangeKind::Comment,
+                Markup::Raw { .. } => FoldingRangeKind::Region,
+                _ => continue,
+            };
+            let span = markup.span().unwrap();
+            let start_line = self.position(span.start).line;
+            let end_line = self.position(span.end.saturating_sub(1)).line;
+            if end_line > start_line {
+                ranges.push(FoldingRange {
+                    start_line,
+                    end_line,
+                    kind,
+                });
+            }
+        }
+
+        let mut tags = outline(&self.markup, &self.grammar);
+        while let Some(tag) = tags.pop() {
+            if let Some(end) = tag.end {
+                let start_line = self.position(tag.span.start).line;
+                let end_line = self.position(end.start).line.saturating_sub(1);
+                if end_line > start_line {
+                    ranges.push(FoldingRange {
+                        start_line,
+                        end_line,
+                        kind: FoldingRangeKind::Region,
+                    });
+                }
+            }
+            tags.extend(tag.children);
+        }
+
+        ranges.sort_by_key(|range| (range.start_line, range.end_line));
+        ranges
+    }
+
+    /// Tag names at the start of a tag, or at the start of a line in a
+    /// `liquid` tag, and filter names after a pipe. Completions are not
+    /// filtered by the partial word at _position_, that's left to the editor.
+    pub fn completions(&self, position: Position) -> Vec<CompletionItem> {
+        let context = self
+            .parser
+            .lexer
+            .completion_context(self.source, self.offset(position));
+
+        match context.kind {
+            CompletionKind::TagName => self.tag_completions(),
+            CompletionKind::Filter => self.filter_completions(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn filter_completions(&self) -> Vec<CompletionItem> {
+        let mut filters: Vec<&str> = self
+            .parser
+            .filters
+            .signatures()
+            .into_iter()
+            .map(|signature| signature.name.as_str())
+            .collect();
+        filters.sort();
+        filters
+            .into_iter()
+            .map(|name| CompletionItem {
+                label: name.to_owned(),
+                kind: CompletionItemKind::Function,
+                detail: Some("filter".to_owned()),
+            })
+            .collect()
+    }
+
+    fn tag_completions(&self) -> Vec<CompletionItem> {
+        let mut items: Vec<CompletionItem> = self
+            .grammar
+            .tags
+            .iter()
+            .map(|info| CompletionItem {
+                label: info.name.clone(),
+                kind: CompletionItemKind::Keyword,
+                detail: Some(if info.block() { "block tag" } else { "tag" }.to_owned()),
+            })
+            .chain(
+                self.grammar
+                    .tag_aliases
+                    .iter()
+                    .map(|(alias, canonical)| CompletionItem {
+                        label: alias.clone(),
+                        kind: CompletionItemKind::Keyword,
+                        detail: Some(format!("alias for {canonical}")),
+                    }),
+            )
+            .collect();
+        items.sort_by(|a, b| a.label.cmp(&b.label));
+        items.dedup_by(|a, b| a.label == b.label);
+        items
+    }
+
+    fn error(&self, err: &LiquidError) -> Diagnostic {
+        Diagnostic {
+            range: self.range(err.span.unwrap_or_default()),
+            severity: DiagnosticSeverity::Error,
+            code: code(&err.kind),
+            source: "liquid2",
+            message: err.msg.clone(),
+        }
+    }
+
+    fn symbol(&self, tag: &OutlineItem) -> DocumentSymbol {
+        let kind = match tag.name.as_str() {
+            "block" => SymbolKind::Module,
+            "macro" => SymbolKind::Function,
+            _ => SymbolKind::Variable,
+        };
+        let (name, detail, kind, selection) = match tag.defines() {
+            Some((name, span)) => (name.to_owned(), Some(tag.name.clone()), kind, span),
+            None => {
+                let expression = tag_expression(&tag.expression);
+                let detail = (!expression.is_empty()).then(|| expression.trim_start().to_owned());
+                (tag.name.clone(), detail, SymbolKind::Object, tag.span)
+            }
+        };
+
+        DocumentSymbol {
+            name,
+            detail,
+            kind,
+            range: self.range(tag.range()),
+            selection_range: self.range(selection),
+            children: tag
+                .children
+                .iter()
+                .map(|child| self.symbol(child))
+                .collect(),
+        }
+    }
+}
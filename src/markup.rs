@@ -1,63 +1,226 @@
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self};
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
 
+#[cfg(feature = "python")]
+use crate::python::repr;
 use crate::query::Query;
+use crate::span::Span;
+pub use crate::whitespace::Whitespace;
 
-#[pyclass(frozen)]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(frozen, eq, hash))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Markup {
     Content {
         text: String,
-        span: (usize, usize),
+        span: Span,
     },
     Raw {
         wc: (Whitespace, Whitespace, Whitespace, Whitespace),
         text: String,
-        span: (usize, usize),
-    },
+        span: Span,
+        /// Spans of the parts of the block, only recorded when the lexer's
+        /// `raw_spans` option is set.
+        spans: Option<RawSpans>,
+    },
+    /// A comment. For block comments, _wc_ holds the outer whitespace
+    /// control markers of the `comment` and `endcomment` tags, and _hashes_
+    /// is empty.
     Comment {
         wc: (Whitespace, Whitespace),
         hashes: String,
         text: String,
-        span: (usize, usize),
+        kind: CommentKind,
+        span: Span,
     },
     Output {
         wc: (Whitespace, Whitespace),
         expression: Vec<Token>,
-        span: (usize, usize),
+        span: Span,
     },
     Tag {
         wc: (Whitespace, Whitespace),
         name: String,
         expression: Option<Vec<Token>>,
-        span: (usize, usize),
+        span: Span,
     },
     Lines {
         wc: (Whitespace, Whitespace),
         name: String,
         statements: Vec<Markup>,
-        span: (usize, usize),
+        span: Span,
+    },
+    /// Markup that could not be tokenized, only produced by
+    /// `Lexer::tokenize_lenient`.
+    Error {
+        text: String,
+        span: Span,
     },
     EOI {},
 }
 
+/// Markup is compared structurally, ignoring spans, so the same markup
+/// lexed from different positions is equal.
+impl PartialEq for Markup {
+    fn eq(&self, other: &Self) -> bool {
+        use Markup::*;
+        match (self, other) {
+            (Content { text: a, .. }, Content { text: b, .. })
+            | (Error { text: a, .. }, Error { text: b, .. }) => a == b,
+            (
+                Raw {
+                    wc: a_wc, text: a, ..
+                },
+                Raw {
+                    wc: b_wc, text: b, ..
+                },
+            ) => a_wc == b_wc && a == b,
+            (
+                Comment {
+                    wc: a_wc,
+                    hashes: a_hashes,
+                    text: a_text,
+                    kind: a_kind,
+                    ..
+                },
+                Comment {
+                    wc: b_wc,
+                    hashes: b_hashes,
+                    text: b_text,
+                    kind: b_kind,
+                    ..
+                },
+            ) => a_wc == b_wc && a_hashes == b_hashes && a_text == b_text && a_kind == b_kind,
+            (
+                Output {
+                    wc: a_wc,
+                    expression: a,
+                    ..
+                },
+                Output {
+                    wc: b_wc,
+                    expression: b,
+                    ..
+                },
+            ) => a_wc == b_wc && a == b,
+            (
+                Tag {
+                    wc: a_wc,
+                    name: a_name,
+                    expression: a,
+                    ..
+                },
+                Tag {
+                    wc: b_wc,
+                    name: b_name,
+                    expression: b,
+                    ..
+                },
+            ) => a_wc == b_wc && a_name == b_name && a == b,
+            (
+                Lines {
+                    wc: a_wc,
+                    name: a_name,
+                    statements: a,
+                    ..
+                },
+                Lines {
+                    wc: b_wc,
+                    name: b_name,
+                    statements: b,
+                    ..
+                },
+            ) => a_wc == b_wc && a_name == b_name && a == b,
+            (EOI {}, EOI {}) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Markup {}
+
+impl Hash for Markup {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+        match self {
+            Markup::Content { text, .. } | Markup::Error { text, .. } => text.hash(state),
+            Markup::Raw { wc, text, .. } => (wc, text).hash(state),
+            Markup::Comment {
+                wc,
+                hashes,
+                text,
+                kind,
+                ..
+            } => (wc, hashes, text, kind).hash(state),
+            Markup::Output { wc, expression, .. } => (wc, expression).hash(state),
+            Markup::Tag {
+                wc,
+                name,
+                expression,
+                ..
+            } => (wc, name, expression).hash(state),
+            Markup::Lines {
+                wc,
+                name,
+                statements,
+                ..
+            } => (wc, name, statements).hash(state),
+            Markup::EOI {} => (),
+        }
+    }
+}
+
+impl Markup {
+    /// The span of this markup, or `None` for the end of input.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Markup::Content { span, .. }
+            | Markup::Raw { span, .. }
+            | Markup::Comment { span, .. }
+            | Markup::Output { span, .. }
+            | Markup::Tag { span, .. }
+            | Markup::Lines { span, .. }
+            | Markup::Error { span, .. } => Some(*span),
+            Markup::EOI {} => None,
+        }
+    }
+}
+
 impl fmt::Display for Markup {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Markup::Content { text, .. } => f.write_str(text),
+            Markup::Content { text, .. } | Markup::Error { text, .. } => f.write_str(text),
             Markup::Raw { wc, text, .. } => write!(
                 f,
                 "{{%{} raw {}%}}{}{{%{} endraw {}%}}",
                 wc.0, wc.1, text, wc.2, wc.3
             ),
             Markup::Comment {
-                wc, hashes, text, ..
-            } => {
-                write!(f, "{{{}{}{}{}{}}}", hashes, wc.0, text, wc.1, hashes)
-            }
+                wc,
+                hashes,
+                text,
+                kind,
+                ..
+            } => match kind {
+                CommentKind::Hash => {
+                    write!(f, "{{{}{}{}{}{}}}", hashes, wc.0, text, wc.1, hashes)
+                }
+                CommentKind::Block => write!(
+                    f,
+                    "{{%{} comment %}}{}{{% endcomment {}%}}",
+                    wc.0, text, wc.1
+                ),
+                CommentKind::Inline => write!(f, "{{%{} #{} {}%}}", wc.0, text, wc.1),
+                CommentKind::Doc => {
+                    write!(f, "{{%{} doc %}}{}{{% enddoc {}%}}", wc.0, text, wc.1)
+                }
+            },
             Markup::Output { wc, expression, .. } => {
                 let expr = expression
-                    .into_iter()
+                    .iter()
                     .map(|e| e.to_string())
                     .collect::<Vec<String>>()
                     .join(" ");
@@ -88,12 +251,12 @@ impl fmt::Display for Markup {
             }
             Markup::Lines { wc, statements, .. } => {
                 let lines = statements
-                    .into_iter()
-                    .map(|t| tag_as_line_statement(t))
+                    .iter()
+                    .map(tag_as_line_statement)
                     .collect::<Vec<String>>()
                     .join("\n");
 
-                if lines.len() == 0 {
+                if lines.is_empty() {
                     write!(f, "{{%{} liquid {}%}}", wc.0, wc.1)
                 } else {
                     write!(f, "{{%{} liquid {} {}%}}", wc.0, lines, wc.1)
@@ -104,10 +267,36 @@ impl fmt::Display for Markup {
     }
 }
 
-fn tokens_string(tokens: &Vec<Token>) -> String {
+/// Byte spans of the parts of a `raw` block.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawSpans {
+    /// The `{% raw %}` tag.
+    pub open: Span,
+    /// The text between the tags.
+    pub content: Span,
+    /// The `{% endraw %}` tag.
+    pub close: Span,
+}
+
+/// The syntax a comment was written with.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommentKind {
+    /// `{# ... #}`, with any number of matching hashes.
+    Hash,
+    /// `{% comment %} ... {% endcomment %}`.
+    Block,
+    /// `{% # ... %}`, or a line starting with `#` in a `liquid` tag.
+    Inline,
+    /// `{% doc %} ... {% enddoc %}`, parsed to a `Node::DocTag`.
+    Doc,
+}
+
+fn tokens_string(tokens: &[Token]) -> String {
     // TODO: Smarter join. No space after some symbols
     tokens
-        .into_iter()
+        .iter()
         .map(|e| e.to_string())
         .collect::<Vec<String>>()
         .join(" ")
@@ -124,128 +313,193 @@ fn tag_as_line_statement(tag: &Markup) -> String {
                 name.to_owned()
             }
         }
+        Markup::Comment { text, .. } => format!("#{text}"),
         _ => String::new(),
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl Markup {
     fn __str__(&self) -> String {
         self.to_string()
     }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), this.span())
+    }
 }
 
-#[pyclass(frozen)]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(frozen, eq, hash))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Token {
     True_ {
-        span: (usize, usize),
+        span: Span,
     },
     False_ {
-        span: (usize, usize),
+        span: Span,
     },
     And {
-        span: (usize, usize),
+        span: Span,
     },
     Or {
-        span: (usize, usize),
+        span: Span,
     },
     In {
-        span: (usize, usize),
+        span: Span,
     },
     Not {
-        span: (usize, usize),
+        span: Span,
     },
     Contains {
-        span: (usize, usize),
+        span: Span,
     },
     Null {
-        span: (usize, usize),
+        span: Span,
     },
     If {
-        span: (usize, usize),
+        span: Span,
     },
     Else {
-        span: (usize, usize),
+        span: Span,
     },
     With {
-        span: (usize, usize),
+        span: Span,
     },
     Required {
-        span: (usize, usize),
+        span: Span,
     },
     As {
-        span: (usize, usize),
+        span: Span,
     },
     For {
-        span: (usize, usize),
+        span: Span,
     },
     Eq {
-        span: (usize, usize),
+        span: Span,
     },
     Ne {
-        span: (usize, usize),
+        span: Span,
     },
     Ge {
-        span: (usize, usize),
+        span: Span,
     },
     Gt {
-        span: (usize, usize),
+        span: Span,
     },
     Le {
-        span: (usize, usize),
+        span: Span,
     },
     Lt {
-        span: (usize, usize),
+        span: Span,
     },
     Colon {
-        span: (usize, usize),
+        span: Span,
     },
     Pipe {
-        span: (usize, usize),
+        span: Span,
     },
     DoublePipe {
-        span: (usize, usize),
+        span: Span,
+    },
+    Elvis {
+        span: Span,
+    },
+    Arrow {
+        span: Span,
     },
     Comma {
-        span: (usize, usize),
+        span: Span,
     },
     LeftParen {
-        span: (usize, usize),
+        span: Span,
     },
     RightParen {
-        span: (usize, usize),
+        span: Span,
     },
     Assign {
-        span: (usize, usize),
+        span: Span,
     },
     StringLiteral {
         value: String,
-        span: (usize, usize),
+        span: Span,
     },
     IntegerLiteral {
         value: i64,
-        span: (usize, usize),
+        span: Span,
     },
     FloatLiteral {
         value: f64,
-        span: (usize, usize),
+        span: Span,
     },
     Word {
         value: String,
-        span: (usize, usize),
+        span: Span,
     },
     RangeLiteral {
         start: RangeArgument,
         stop: RangeArgument,
-        span: (usize, usize),
+        step: Option<RangeArgument>,
+        span: Span,
     },
     Query {
         path: Query,
-        span: (usize, usize),
+        span: Span,
     },
 }
 
+/// Tokens are compared structurally, ignoring spans. Float literals are
+/// compared by their bit patterns, so equality is reflexive.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        use Token::*;
+        match (self, other) {
+            (StringLiteral { value: a, .. }, StringLiteral { value: b, .. })
+            | (Word { value: a, .. }, Word { value: b, .. }) => a == b,
+            (IntegerLiteral { value: a, .. }, IntegerLiteral { value: b, .. }) => a == b,
+            (FloatLiteral { value: a, .. }, FloatLiteral { value: b, .. }) => {
+                a.to_bits() == b.to_bits()
+            }
+            (
+                RangeLiteral {
+                    start: a_start,
+                    stop: a_stop,
+                    step: a_step,
+                    ..
+                },
+                RangeLiteral {
+                    start: b_start,
+                    stop: b_stop,
+                    step: b_step,
+                    ..
+                },
+            ) => a_start == b_start && a_stop == b_stop && a_step == b_step,
+            (Query { path: a, .. }, Query { path: b, .. }) => a == b,
+            // Every other kind of token has nothing but a span.
+            _ => discriminant(self) == discriminant(other),
+        }
+    }
+}
+
+impl Eq for Token {}
+
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+        match self {
+            Token::StringLiteral { value, .. } | Token::Word { value, .. } => value.hash(state),
+            Token::IntegerLiteral { value, .. } => value.hash(state),
+            Token::FloatLiteral { value, .. } => value.to_bits().hash(state),
+            Token::RangeLiteral {
+                start, stop, step, ..
+            } => (start, stop, step).hash(state),
+            Token::Query { path, .. } => path.hash(state),
+            _ => (),
+        }
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -272,6 +526,8 @@ impl fmt::Display for Token {
             Token::Colon { .. } => f.write_str(":"),
             Token::Pipe { .. } => f.write_str("|"),
             Token::DoublePipe { .. } => f.write_str("||"),
+            Token::Elvis { .. } => f.write_str("?:"),
+            Token::Arrow { .. } => f.write_str("=>"),
             Token::Comma { .. } => f.write_str(","),
             Token::LeftParen { .. } => f.write_str("("),
             Token::RightParen { .. } => f.write_str(")"),
@@ -280,6 +536,12 @@ impl fmt::Display for Token {
             Token::IntegerLiteral { value, .. } => write!(f, "{value}"),
             Token::FloatLiteral { value, .. } => write!(f, "{value}"),
             Token::Word { value, .. } => write!(f, "{value}"),
+            Token::RangeLiteral {
+                start,
+                stop,
+                step: Some(step),
+                ..
+            } => write!(f, "({start}..{stop}..{step})"),
             Token::RangeLiteral { start, stop, .. } => write!(f, "({start}..{stop})"),
             Token::Query { path, .. } => {
                 if let Some(word) = path.as_word() {
@@ -292,20 +554,206 @@ impl fmt::Display for Token {
     }
 }
 
+impl Token {
+    pub fn span(&self) -> Span {
+        match self {
+            Token::True_ { span, .. }
+            | Token::False_ { span, .. }
+            | Token::And { span, .. }
+            | Token::Or { span, .. }
+            | Token::In { span, .. }
+            | Token::Not { span, .. }
+            | Token::Contains { span, .. }
+            | Token::Null { span, .. }
+            | Token::If { span, .. }
+            | Token::Else { span, .. }
+            | Token::With { span, .. }
+            | Token::Required { span, .. }
+            | Token::As { span, .. }
+            | Token::For { span, .. }
+            | Token::Eq { span, .. }
+            | Token::Ne { span, .. }
+            | Token::Ge { span, .. }
+            | Token::Gt { span, .. }
+            | Token::Le { span, .. }
+            | Token::Lt { span, .. }
+            | Token::Colon { span, .. }
+            | Token::Pipe { span, .. }
+            | Token::DoublePipe { span, .. }
+            | Token::Elvis { span, .. }
+            | Token::Arrow { span, .. }
+            | Token::Comma { span, .. }
+            | Token::LeftParen { span, .. }
+            | Token::RightParen { span, .. }
+            | Token::Assign { span, .. }
+            | Token::StringLiteral { span, .. }
+            | Token::IntegerLiteral { span, .. }
+            | Token::FloatLiteral { span, .. }
+            | Token::Word { span, .. }
+            | Token::RangeLiteral { span, .. }
+            | Token::Query { span, .. } => *span,
+        }
+    }
+}
+
+/// Every kind of token, as returned by `Token::kind`, with a description for
+/// error messages.
+pub const TOKEN_KINDS: [(&str, &str); 35] = [
+    ("True_", "'true'"),
+    ("False_", "'false'"),
+    ("And", "'and'"),
+    ("Or", "'or'"),
+    ("In", "'in'"),
+    ("Not", "'not'"),
+    ("Contains", "'contains'"),
+    ("Null", "'null'"),
+    ("If", "'if'"),
+    ("Else", "'else'"),
+    ("With", "'with'"),
+    ("Required", "'required'"),
+    ("As", "'as'"),
+    ("For", "'for'"),
+    ("Eq", "'=='"),
+    ("Ne", "'!='"),
+    ("Ge", "'>='"),
+    ("Gt", "'>'"),
+    ("Le", "'<='"),
+    ("Lt", "'<'"),
+    ("Colon", "':'"),
+    ("Pipe", "'|'"),
+    ("DoublePipe", "'||'"),
+    ("Elvis", "'?:'"),
+    ("Arrow", "'=>'"),
+    ("Comma", "','"),
+    ("LeftParen", "'('"),
+    ("RightParen", "')'"),
+    ("Assign", "'='"),
+    ("StringLiteral", "a string literal"),
+    ("IntegerLiteral", "an integer"),
+    ("FloatLiteral", "a float"),
+    ("Word", "a name"),
+    ("RangeLiteral", "a range"),
+    ("Query", "a query"),
+];
+
+impl Token {
+    /// The name of this token's variant, like `"Word"` or `"Comma"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::True_ { .. } => "True_",
+            Token::False_ { .. } => "False_",
+            Token::And { .. } => "And",
+            Token::Or { .. } => "Or",
+            Token::In { .. } => "In",
+            Token::Not { .. } => "Not",
+            Token::Contains { .. } => "Contains",
+            Token::Null { .. } => "Null",
+            Token::If { .. } => "If",
+            Token::Else { .. } => "Else",
+            Token::With { .. } => "With",
+            Token::Required { .. } => "Required",
+            Token::As { .. } => "As",
+            Token::For { .. } => "For",
+            Token::Eq { .. } => "Eq",
+            Token::Ne { .. } => "Ne",
+            Token::Ge { .. } => "Ge",
+            Token::Gt { .. } => "Gt",
+            Token::Le { .. } => "Le",
+            Token::Lt { .. } => "Lt",
+            Token::Colon { .. } => "Colon",
+            Token::Pipe { .. } => "Pipe",
+            Token::DoublePipe { .. } => "DoublePipe",
+            Token::Elvis { .. } => "Elvis",
+            Token::Arrow { .. } => "Arrow",
+            Token::Comma { .. } => "Comma",
+            Token::LeftParen { .. } => "LeftParen",
+            Token::RightParen { .. } => "RightParen",
+            Token::Assign { .. } => "Assign",
+            Token::StringLiteral { .. } => "StringLiteral",
+            Token::IntegerLiteral { .. } => "IntegerLiteral",
+            Token::FloatLiteral { .. } => "FloatLiteral",
+            Token::Word { .. } => "Word",
+            Token::RangeLiteral { .. } => "RangeLiteral",
+            Token::Query { .. } => "Query",
+        }
+    }
+}
+
+/// The span from the start of the first of _tokens_ to the end of the last,
+/// or an empty span if there are no tokens.
+pub fn tokens_span(tokens: &[Token]) -> Span {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => Span::new(first.span().start, last.span().end),
+        _ => Span::default(),
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl Token {
+    #[getter(kind)]
+    fn py_kind(&self) -> &'static str {
+        self.kind()
+    }
+
     fn __str__(&self) -> String {
         self.to_string()
     }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span()))
+    }
 }
 
-#[pyclass(frozen)]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(frozen, eq, hash))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RangeArgument {
-    StringLiteral { value: String, span: (usize, usize) },
-    IntegerLiteral { value: i64, span: (usize, usize) },
-    FloatLiteral { value: f64, span: (usize, usize) },
-    Query { path: Query, span: (usize, usize) },
+    StringLiteral { value: String, span: Span },
+    IntegerLiteral { value: i64, span: Span },
+    FloatLiteral { value: f64, span: Span },
+    Query { path: Query, span: Span },
+}
+
+/// Range arguments are compared structurally, ignoring spans, like `Token`.
+impl PartialEq for RangeArgument {
+    fn eq(&self, other: &Self) -> bool {
+        use RangeArgument::*;
+        match (self, other) {
+            (StringLiteral { value: a, .. }, StringLiteral { value: b, .. }) => a == b,
+            (IntegerLiteral { value: a, .. }, IntegerLiteral { value: b, .. }) => a == b,
+            (FloatLiteral { value: a, .. }, FloatLiteral { value: b, .. }) => {
+                a.to_bits() == b.to_bits()
+            }
+            (Query { path: a, .. }, Query { path: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RangeArgument {}
+
+impl Hash for RangeArgument {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+        match self {
+            RangeArgument::StringLiteral { value, .. } => value.hash(state),
+            RangeArgument::IntegerLiteral { value, .. } => value.hash(state),
+            RangeArgument::FloatLiteral { value, .. } => value.to_bits().hash(state),
+            RangeArgument::Query { path, .. } => path.hash(state),
+        }
+    }
+}
+
+impl RangeArgument {
+    pub fn span(&self) -> Span {
+        match self {
+            RangeArgument::StringLiteral { span, .. }
+            | RangeArgument::IntegerLiteral { span, .. }
+            | RangeArgument::FloatLiteral { span, .. }
+            | RangeArgument::Query { span, .. } => *span,
+        }
+    }
 }
 
 impl fmt::Display for RangeArgument {
@@ -325,48 +773,15 @@ impl fmt::Display for RangeArgument {
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl RangeArgument {
     fn __str__(&self) -> String {
         self.to_string()
     }
-}
-
-#[pyclass(eq, eq_int)]
-#[derive(Debug, Clone, PartialEq)]
-pub enum Whitespace {
-    Plus,
-    Minus,
-    Smart,
-    Default,
-}
 
-impl Whitespace {
-    pub fn from_str(s: &str) -> Self {
-        match s {
-            "+" => Self::Plus,
-            "-" => Self::Minus,
-            "~" => Self::Smart,
-            "" => Self::Default,
-            _ => unreachable!("{:#?}", s),
-        }
-    }
-}
-
-impl fmt::Display for Whitespace {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Whitespace::Plus => write!(f, "+"),
-            Whitespace::Minus => write!(f, "-"),
-            Whitespace::Smart => write!(f, "~"),
-            Whitespace::Default => Ok(()),
-        }
-    }
-}
-
-#[pymethods]
-impl Whitespace {
-    fn __str__(&self) -> String {
-        self.to_string()
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let this = slf.get();
+        repr(slf.as_any(), &this.to_string(), Some(this.span()))
     }
 }
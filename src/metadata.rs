@@ -0,0 +1,191 @@
+//! Structured documentation extracted from a template's `{% doc %}` block or
+//! header comment.
+//!
+//! A template is documented with a `doc` block, like this, or by making its
+//! first markup, ignoring leading whitespace, a comment with the same
+//! contents:
+//!
+//! ```text
+//! {% doc %}
+//!   Renders a product card.
+//!
+//!   @param {string} title - The product title.
+//!   @param {number} [count] - An optional quantity.
+//!   @example
+//!   {% render 'card', title: product.title %}
+//! {% enddoc %}
+//! ```
+//!
+//! Free text before the first `@` line, or following `@description`, is the
+//! description. Lines that follow a `@param` or `@example` line, up to the
+//! next `@` line, continue it.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Node, Template};
+
+/// Documentation for a template, taken from its header comment or a
+/// `{% doc %}` block.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateMetadata {
+    pub description: String,
+    pub params: Vec<ParamDoc>,
+    pub examples: Vec<String>,
+}
+
+/// A documented template parameter.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamDoc {
+    pub name: String,
+    pub type_: Option<String>,
+    /// `false` if the name was written in square brackets.
+    pub required: bool,
+    pub description: String,
+}
+
+enum Section {
+    Description,
+    Param,
+    Example,
+    Unknown,
+}
+
+impl Template {
+    /// Return documentation from this template's top-level `doc` block or,
+    /// failing that, its header comment. `None` if the template has neither.
+    pub fn metadata(&self) -> Option<TemplateMetadata> {
+        let doc = self.nodes.iter().find_map(|node| match node {
+            Node::DocTag { doc, .. } => Some(doc.clone()),
+            _ => None,
+        });
+        if doc.is_some() {
+            return doc;
+        }
+
+        let header = self.nodes.iter().find(|node| match node {
+            Node::Content { text, .. } => !text.trim().is_empty(),
+            _ => true,
+        })?;
+
+        match header {
+            Node::Comment { text, .. } => Some(parse_doc(text)),
+            _ => None,
+        }
+    }
+}
+
+/// Parse documentation _text_, as found in a `doc` block or template header
+/// comment.
+pub fn parse_doc(text: &str) -> TemplateMetadata {
+    let mut metadata = TemplateMetadata::default();
+    let mut description: Vec<&str> = Vec::new();
+    let mut example: Vec<&str> = Vec::new();
+    let mut section = Section::Description;
+
+    for line in dedent(text) {
+        if let Some(rest) = line.trim_start().strip_prefix('@') {
+            if matches!(section, Section::Example) {
+                push_example(&mut metadata, &mut example);
+            }
+
+            let (tag, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            section = match tag {
+                "param" => {
+                    metadata.params.push(parse_param(rest));
+                    Section::Param
+                }
+                "description" => {
+                    if !rest.trim().is_empty() {
+                        description.push(rest.trim());
+                    }
+                    Section::Description
+                }
+                "example" => {
+                    if !rest.trim().is_empty() {
+                        example.push(rest.trim());
+                    }
+                    Section::Example
+                }
+                _ => Section::Unknown,
+            };
+            continue;
+        }
+
+        match section {
+            Section::Description => description.push(line.trim()),
+            Section::Param => {
+                let param = metadata.params.last_mut().unwrap();
+                if !line.trim().is_empty() {
+                    if !param.description.is_empty() {
+                        param.description.push(' ');
+                    }
+                    param.description.push_str(line.trim());
+                }
+            }
+            Section::Example => example.push(line),
+            Section::Unknown => (),
+        }
+    }
+
+    if matches!(section, Section::Example) {
+        push_example(&mut metadata, &mut example);
+    }
+
+    metadata.description = description.join("\n").trim().to_owned();
+    metadata
+}
+
+/// Parse the text following `@param`, like `{string} [name] - description`.
+fn parse_param(text: &str) -> ParamDoc {
+    let mut rest = text.trim();
+
+    let type_ = match rest.strip_prefix('{').and_then(|r| r.split_once('}')) {
+        Some((type_, tail)) => {
+            rest = tail.trim_start();
+            Some(type_.trim().to_owned())
+        }
+        None => None,
+    };
+
+    let (name, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (name, required) = match name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+        Some(name) => (name, false),
+        None => (name, true),
+    };
+
+    let tail = tail.trim_start();
+    let description = tail.strip_prefix('-').unwrap_or(tail).trim().to_owned();
+
+    ParamDoc {
+        name: name.to_owned(),
+        type_,
+        required,
+        description,
+    }
+}
+
+fn push_example(metadata: &mut TemplateMetadata, lines: &mut Vec<&str>) {
+    let example = lines.join("\n").trim().to_owned();
+    if !example.is_empty() {
+        metadata.examples.push(example);
+    }
+    lines.clear();
+}
+
+/// Split _text_ into lines with common leading whitespace removed.
+fn dedent(text: &str) -> Vec<&str> {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect()
+}
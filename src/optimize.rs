@@ -0,0 +1,349 @@
+//! Simplify syntax trees before rendering, without changing their output.
+//!
+//! The optimizer removes comments and `if` and `unless` branches whose
+//! conditions are constant, renders output statements that don't read any
+//! data to text, and merges adjacent text.
+//!
+//! Whitespace control makes markup trim the text beside it, so replacing or
+//! removing markup can change what neighbouring text renders as. We only
+//! change markup when neither it nor anything next to it trims whitespace.
+//!
+//! Template inheritance finds `block` and `extends` tags wherever they are,
+//! including in branches that are never rendered, so we keep any `if` or
+//! `unless` tag that contains them.
+
+use crate::ast::{walk_node, ElseTag, ElsifTag, Node, Template, Visitor};
+use crate::render::{eval_constant_condition, eval_constant_output, leading_wc, trailing_wc};
+use crate::shared::SharedVec;
+use crate::span::Span;
+use crate::whitespace::Whitespace;
+
+pub struct Optimizer {
+    /// Whitespace control applied to markup that does not specify its own.
+    /// This must match the renderer's setting.
+    pub trim: Whitespace,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self {
+            trim: Whitespace::Plus,
+        }
+    }
+}
+
+impl Template {
+    /// Return an optimized copy of this template, for a renderer with
+    /// default settings.
+    pub fn optimized(&self) -> Template {
+        Optimizer::default().optimize(self)
+    }
+}
+
+impl Optimizer {
+    pub fn optimize(&self, template: &Template) -> Template {
+        let mut nodes = template.nodes.clone();
+        self.block(&mut nodes, Whitespace::Default, Whitespace::Default);
+        Template { nodes }
+    }
+
+    /// Optimize _nodes_ in place. _left_ and _right_ are the whitespace
+    /// control markers either side of the block.
+    fn block(&self, nodes: &mut Vec<Node>, left: Whitespace, right: Whitespace) {
+        let mut i = 0;
+        while i < nodes.len() {
+            // Blocks inside markup that trims whitespace might have trimmed
+            // edges, so we treat their boundaries as trimming too.
+            let inner = if markers(&nodes[i]).into_iter().any(|wc| self.trims(wc)) {
+                Whitespace::Minus
+            } else {
+                Whitespace::Default
+            };
+            for block in blocks_mut(&mut nodes[i]) {
+                self.block(block, inner, inner);
+            }
+
+            let before = if i == 0 {
+                left
+            } else {
+                trailing_wc(&nodes[i - 1])
+            };
+            let after = nodes.get(i + 1).map_or(right, leading_wc);
+            let replaceable = !matches!(nodes[i], Node::Content { .. })
+                && !self.trims(before)
+                && !self.trims(after)
+                && !markers(&nodes[i]).into_iter().any(|wc| self.trims(wc));
+
+            // Markup at the edges of a replacement block would start trimming
+            // text outside it.
+            let fits = |nodes: &Vec<Node>| {
+                !nodes.first().is_some_and(|n| self.trims(leading_wc(n)))
+                    && !nodes.last().is_some_and(|n| self.trims(trailing_wc(n)))
+            };
+
+            match replacement(&nodes[i]).filter(|r| replaceable && fits(r)) {
+                Some(replacement) => {
+                    let count = replacement.len();
+                    nodes.splice(i..=i, replacement);
+                    i += count;
+                }
+                None => i += 1,
+            }
+        }
+
+        self.merge_content(nodes, left, right);
+    }
+
+    /// Merge adjacent text in _nodes_.
+    fn merge_content(&self, nodes: &mut Vec<Node>, left: Whitespace, right: Whitespace) {
+        if self.trims(Whitespace::Default) {
+            // Text is trimmed where it meets other text.
+            return;
+        }
+
+        let mut i = 1;
+        while i < nodes.len() {
+            let before = if i == 1 {
+                left
+            } else {
+                trailing_wc(&nodes[i - 2])
+            };
+            let after = nodes.get(i + 1).map_or(right, leading_wc);
+
+            match (&nodes[i - 1], &nodes[i]) {
+                (Node::Content { text: a, span: a_span }, Node::Content { text: b, span: b_span })
+                    // Whitespace-only text could be trimmed away, exposing
+                    // the other text to trimming it wasn't exposed to before.
+                    if (!self.trims(before) || !a.trim().is_empty())
+                        && (!self.trims(after) || !b.trim().is_empty()) =>
+                {
+                    let merged = Node::Content {
+                        text: format!("{a}{b}"),
+                        span: Span::new(a_span.start, b_span.end.max(a_span.end)),
+                    };
+                    nodes.splice(i - 1..=i, [merged]);
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    fn trims(&self, wc: Whitespace) -> bool {
+        let wc = if wc == Whitespace::Default {
+            self.trim
+        } else {
+            wc
+        };
+        wc != Whitespace::Plus
+    }
+}
+
+/// Return the nodes that _node_ can be replaced with, or `None` if it can't
+/// be simplified.
+fn replacement(node: &Node) -> Option<Vec<Node>> {
+    match node {
+        Node::Comment { .. } | Node::DocTag { .. } => Some(Vec::new()),
+        Node::Output {
+            expression, span, ..
+        }
+        | Node::EchoTag {
+            expression, span, ..
+        } => eval_constant_output(expression).map(|text| vec![Node::Content { text, span: *span }]),
+        Node::IfTag { .. } | Node::UnlessTag { .. } if has_inheritance_tags(node) => None,
+        Node::IfTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        } => {
+            let truthy = eval_constant_condition(condition)?;
+            select_branch(truthy, block, alternatives, default)
+        }
+        Node::UnlessTag {
+            condition,
+            block,
+            alternatives,
+            default,
+            ..
+        } => {
+            let truthy = !eval_constant_condition(condition)?;
+            select_branch(truthy, block, alternatives, default)
+        }
+        _ => None,
+    }
+}
+
+/// Return the block of an `if` or `unless` tag that would be rendered, if
+/// that can be known without any data.
+fn select_branch(
+    truthy: bool,
+    block: &[Node],
+    alternatives: &[ElsifTag],
+    default: &Option<ElseTag>,
+) -> Option<Vec<Node>> {
+    if truthy {
+        return Some(block.to_vec());
+    }
+
+    for alternative in alternatives {
+        if eval_constant_condition(&alternative.condition)? {
+            return Some(alternative.block.to_vec());
+        }
+    }
+
+    Some(default.as_ref().map_or_else(Vec::new, |d| d.block.to_vec()))
+}
+
+/// Return true if _node_ contains a `block` or `extends` tag at any depth.
+fn has_inheritance_tags(node: &Node) -> bool {
+    let mut finder = InheritanceTags(false);
+    walk_node(&mut finder, node);
+    finder.0
+}
+
+struct InheritanceTags(bool);
+
+impl Visitor for InheritanceTags {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::BlockTag { .. } | Node::ExtendsTag { .. } => self.0 = true,
+            _ => walk_node(self, node),
+        }
+    }
+}
+
+/// Return every whitespace control marker in _node_'s own markup, including
+/// its end tag and branch tags.
+fn markers(node: &Node) -> Vec<Whitespace> {
+    let pair = |wc: &(Whitespace, Whitespace)| [wc.0, wc.1];
+    match node {
+        Node::Content { .. } => Vec::new(),
+        Node::Raw { wc, .. } => vec![wc.0, wc.1, wc.2, wc.3],
+        Node::Comment { wc, .. }
+        | Node::DocTag { wc, .. }
+        | Node::Output { wc, .. }
+        | Node::AssignTag { wc, .. }
+        | Node::CycleTag { wc, .. }
+        | Node::DecrementTag { wc, .. }
+        | Node::IncrementTag { wc, .. }
+        | Node::EchoTag { wc, .. }
+        | Node::BreakTag { wc, .. }
+        | Node::ContinueTag { wc, .. }
+        | Node::IncludeTag { wc, .. }
+        | Node::RenderTag { wc, .. }
+        | Node::LiquidTag { wc, .. }
+        | Node::ExtendsTag { wc, .. }
+        | Node::CallTag { wc, .. } => pair(wc).to_vec(),
+        Node::CaptureTag { wc, end_wc, .. }
+        | Node::BlockTag { wc, end_wc, .. }
+        | Node::MacroTag { wc, end_wc, .. }
+        | Node::TablerowTag { wc, end_wc, .. }
+        | Node::IfchangedTag { wc, end_wc, .. } => [pair(wc), pair(end_wc)].concat(),
+        Node::CaseTag {
+            wc,
+            whens,
+            default,
+            end_wc,
+            ..
+        } => whens
+            .iter()
+            .map(|when| &when.wc)
+            .chain(default.iter().map(|d| &d.wc))
+            .chain([wc, end_wc])
+            .flat_map(pair)
+            .collect(),
+        Node::ForTag {
+            wc,
+            default,
+            end_wc,
+            ..
+        } => default
+            .iter()
+            .map(|d| &d.wc)
+            .chain([wc, end_wc])
+            .flat_map(pair)
+            .collect(),
+        Node::IfTag {
+            wc,
+            alternatives,
+            default,
+            end_wc,
+            ..
+        }
+        | Node::UnlessTag {
+            wc,
+            alternatives,
+            default,
+            end_wc,
+            ..
+        } => alternatives
+            .iter()
+            .map(|alt| &alt.wc)
+            .chain(default.iter().map(|d| &d.wc))
+            .chain([wc, end_wc])
+            .flat_map(pair)
+            .collect(),
+        Node::TranslateTag {
+            wc, plural, end_wc, ..
+        } => plural
+            .iter()
+            .map(|p| &p.wc)
+            .chain([wc, end_wc])
+            .flat_map(pair)
+            .collect(),
+        Node::TagExtension {
+            wc, branches, end, ..
+        } => branches
+            .iter()
+            .map(|branch| &branch.wc)
+            .chain(end.iter().map(|end| &end.wc))
+            .chain([wc])
+            .flat_map(pair)
+            .collect(),
+    }
+}
+
+/// Return the blocks directly inside _node_.
+fn blocks_mut(node: &mut Node) -> Vec<&mut SharedVec<Node>> {
+    match node {
+        Node::CaptureTag { block, .. }
+        | Node::LiquidTag { block, .. }
+        | Node::BlockTag { block, .. }
+        | Node::MacroTag { block, .. }
+        | Node::TablerowTag { block, .. }
+        | Node::IfchangedTag { block, .. } => vec![block],
+        Node::CaseTag { whens, default, .. } => whens
+            .iter_mut()
+            .map(|when| &mut when.block)
+            .chain(default.iter_mut().map(|d| &mut d.block))
+            .collect(),
+        Node::ForTag { block, default, .. } => std::iter::once(block)
+            .chain(default.iter_mut().map(|d| &mut d.block))
+            .collect(),
+        Node::IfTag {
+            block,
+            alternatives,
+            default,
+            ..
+        }
+        | Node::UnlessTag {
+            block,
+            alternatives,
+            default,
+            ..
+        } => std::iter::once(block)
+            .chain(alternatives.iter_mut().map(|alt| &mut alt.block))
+            .chain(default.iter_mut().map(|d| &mut d.block))
+            .collect(),
+        Node::TranslateTag { block, plural, .. } => std::iter::once(block)
+            .chain(plural.iter_mut().map(|p| &mut p.block))
+            .collect(),
+        Node::TagExtension {
+            block, branches, ..
+        } => std::iter::once(block)
+            .chain(branches.iter_mut().map(|branch| &mut branch.block))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,202 @@
+//! The tag structure of a template, worked out from its markup without
+//! building a syntax tree.
+//!
+//! An outline only needs to know which tags have blocks, so it is available
+//! for templates that would fail to parse, like a half-written template open
+//! in an editor. Blocks that are never closed end where their parent block
+//! ends, and end tags without a matching block tag are ignored.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::grammar::{Grammar, TagInfo};
+use crate::markup::{Markup, Token};
+use crate::parser::LiquidParser;
+use crate::span::Span;
+
+/// A tag, and the tags in its block if it has one.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineItem {
+    /// The canonical name of the tag.
+    pub name: String,
+    pub expression: Vec<Token>,
+    /// The span of the tag itself.
+    pub span: Span,
+    /// Whether this tag has a block, even if the block is never closed.
+    pub block: bool,
+    /// Intermediate tags in this tag's block, like `else`.
+    pub branches: Vec<Span>,
+    /// The span of the end tag, or `None` if there isn't one.
+    pub end: Option<Span>,
+    pub children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    /// From the start of this tag to the end of its end tag, or to the end
+    /// of the last thing in its block if it was never closed.
+    pub fn range(&self) -> Span {
+        let end = self
+            .children
+            .iter()
+            .map(|child| child.range().end)
+            .chain(self.branches.iter().map(|branch| branch.end))
+            .fold(self.end.unwrap_or(self.span).end, usize::max);
+        Span::new(self.span.start, end)
+    }
+
+    /// The name defined by this tag, for `assign`, `capture`, `increment`,
+    /// `decrement`, `block` and `macro` tags, and the span of that name.
+    pub fn defines(&self) -> Option<(&str, Span)> {
+        if !matches!(
+            self.name.as_str(),
+            "assign" | "capture" | "increment" | "decrement" | "block" | "macro"
+        ) {
+            return None;
+        }
+
+        match self.expression.first() {
+            Some(Token::Word { value, span } | Token::StringLiteral { value, span }) => {
+                Some((value, *span))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl OutlineItem {
+    #[getter(range)]
+    fn py_range(&self) -> Span {
+        self.range()
+    }
+
+    #[pyo3(name = "defines")]
+    fn py_defines(&self) -> Option<(String, Span)> {
+        self.defines().map(|(name, span)| (name.to_owned(), span))
+    }
+
+    fn __repr__(&self) -> String {
+        let range = self.range();
+        format!(
+            "OutlineItem({:?}, range=({}, {}), children={})",
+            self.name,
+            range.start,
+            range.end,
+            self.children.len()
+        )
+    }
+}
+
+/// Arrange the tags in _markup_ by block, using _grammar_ to find which tags
+/// have blocks. Tags in a `liquid` tag are children of that tag.
+///
+/// Content, output statements, raw blocks and comments are left out.
+pub fn outline(markup: &[Markup], grammar: &Grammar) -> Vec<OutlineItem> {
+    // Open block tags, innermost last, and the names of their end tags.
+    let mut open: Vec<(OutlineItem, &str)> = Vec::new();
+    let mut roots: Vec<OutlineItem> = Vec::new();
+
+    for item in markup {
+        let (name, expression, span, children) = match item {
+            Markup::Tag {
+                name,
+                expression,
+                span,
+                ..
+            } => (
+                canonical(grammar, name),
+                expression.clone().unwrap_or_default(),
+                *span,
+                Vec::new(),
+            ),
+            Markup::Lines {
+                name,
+                statements,
+                span,
+                ..
+            } => (
+                canonical(grammar, name),
+                Vec::new(),
+                *span,
+                outline(statements, grammar),
+            ),
+            _ => continue,
+        };
+
+        if let Some(depth) = open.iter().rposition(|(_, end)| *end == name) {
+            // Tags left open inside the closed block end with it.
+            while open.len() > depth + 1 {
+                let (item, _) = open.pop().unwrap();
+                adopt(&mut open, &mut roots, item);
+            }
+            let (mut item, _) = open.pop().unwrap();
+            item.end = Some(span);
+            adopt(&mut open, &mut roots, item);
+            continue;
+        }
+
+        if grammar
+            .tags
+            .iter()
+            .any(|info| info.end.as_deref() == Some(name))
+        {
+            continue;
+        }
+
+        if let Some((parent, _)) = open.last_mut() {
+            let intermediate = info(grammar, &parent.name)
+                .is_some_and(|info| info.intermediates.iter().any(|i| i == name));
+            if intermediate {
+                parent.branches.push(span);
+                continue;
+            }
+        }
+
+        let end = info(grammar, name).and_then(|info| info.end.as_deref());
+        let item = OutlineItem {
+            name: name.to_owned(),
+            expression,
+            span,
+            block: end.is_some(),
+            branches: Vec::new(),
+            end: None,
+            children,
+        };
+        match end {
+            Some(end) => open.push((item, end)),
+            None => adopt(&mut open, &mut roots, item),
+        }
+    }
+
+    while let Some((item, _)) = open.pop() {
+        adopt(&mut open, &mut roots, item);
+    }
+    roots
+}
+
+impl LiquidParser {
+    /// Tokenize _source_ without stopping at syntax errors, and return the
+    /// outline of the resulting markup.
+    pub fn outline(&self, source: &str) -> Vec<OutlineItem> {
+        let (markup, _) = self.lexer.tokenize_lenient(source);
+        outline(&markup, &self.grammar())
+    }
+}
+
+/// Add _item_ to the innermost open block, or to _roots_.
+fn adopt(open: &mut [(OutlineItem, &str)], roots: &mut Vec<OutlineItem>, item: OutlineItem) {
+    match open.last_mut() {
+        Some((parent, _)) => parent.children.push(item),
+        None => roots.push(item),
+    }
+}
+
+fn canonical<'a>(grammar: &'a Grammar, name: &'a str) -> &'a str {
+    grammar.tag_aliases.get(name).map_or(name, String::as_str)
+}
+
+fn info<'a>(grammar: &'a Grammar, name: &str) -> Option<&'a TagInfo> {
+    grammar.tags.iter().find(|info| info.name == name)
+}
@@ -0,0 +1,2104 @@
+//! Liquid template parser
+//!
+//! A recursive descent parser that turns the lexer's stream of `Markup` and
+//! `Token`s into a `Template` syntax tree.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::ast::{
+    BooleanExpression, BooleanOperator, CommonArgument, CompareOperator, ElseTag, ElsifTag,
+    ExtensionBranch, ExtensionEnd, Filter, FilteredExpression, InlineCondition, KeywordArgument,
+    Node, Parameter, PluralTag, Primitive, Template, WhenTag,
+};
+use crate::dialect::{disallowed, Dialect};
+use crate::errors::LiquidError;
+use crate::filters::FilterRegistry;
+use crate::grammar::{builtin_tags, Grammar, TagInfo, OPERATORS, RESERVED_WORDS};
+use crate::lexer::Lexer;
+use crate::lint::Lint;
+use crate::markup::{tokens_span, CommentKind, Markup, RangeArgument, Token};
+use crate::metadata::parse_doc;
+use crate::query::{Query, Segment, Selector};
+use crate::span::Span;
+use crate::tags::{TagDefinition, TagRegistry};
+use crate::whitespace::Whitespace;
+
+static EOI: Markup = Markup::EOI {};
+
+/// Grow the stack when less than this many bytes remain.
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// The size of each new stack segment.
+const STACK_SIZE: usize = 1024 * 1024;
+
+/// Call _f_, first allocating more stack if we're running low. Used at each
+/// level of recursion over nested blocks, so deeply nested templates don't
+/// overflow the stack.
+pub(crate) fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SIZE, f)
+}
+
+/// Builds the node for a simple block tag from its whitespace control, its
+/// block, its end tag's whitespace control and its span.
+type SimpleBlockTag =
+    fn((Whitespace, Whitespace), Vec<Node>, (Whitespace, Whitespace), Span) -> Node;
+
+/// Block tags that take no arguments and contain a single block, closed by
+/// `end` followed by the tag's name. Tags listed here are parsed with
+/// `parse_simple_block_tag` and included in `builtin_tags()`.
+pub(crate) const SIMPLE_BLOCK_TAGS: [(&str, SimpleBlockTag); 1] =
+    [("ifchanged", |wc, block, end_wc, span| Node::IfchangedTag {
+        wc,
+        block: block.into(),
+        end_wc,
+        span,
+    })];
+
+const PRECEDENCE_LOWEST: u8 = 1;
+pub(crate) const PRECEDENCE_LOGICAL_OR: u8 = 3;
+pub(crate) const PRECEDENCE_LOGICAL_AND: u8 = 4;
+pub(crate) const PRECEDENCE_RELATIONAL: u8 = 5;
+pub(crate) const PRECEDENCE_MEMBERSHIP: u8 = 6;
+
+/// A configured parser. Parsing never changes a parser, so one can be shared
+/// between threads, by reference or behind an `Arc`. Registries are
+/// reference counted, so cloning a parser to change a few options is cheap.
+#[derive(Clone)]
+pub struct LiquidParser {
+    pub lexer: Lexer,
+    /// Alternative tag names, mapped to the canonical name used by the parser.
+    pub tag_aliases: Arc<HashMap<String, String>>,
+    /// Custom tags, parsed into `Node::TagExtension`.
+    pub tags: TagRegistry,
+    /// Parse unknown tags into `Node::TagExtension` instead of failing. Their
+    /// expressions must use common argument syntax, like filter arguments.
+    pub common_tags: bool,
+    /// Filter signatures, checked as filters are parsed.
+    pub filters: FilterRegistry,
+    /// Reject templates with blocks nested more deeply than this.
+    pub max_depth: Option<usize>,
+    /// Reject templates with more nodes than this, counting nodes in every
+    /// block.
+    pub max_nodes: Option<usize>,
+    /// Reject template sources longer than this many bytes, before
+    /// tokenizing them.
+    pub max_bytes: Option<usize>,
+}
+
+// Fail to compile, rather than quietly stop being shareable, if a registry
+// ever holds something tied to one thread.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<LiquidParser>();
+};
+
+impl Default for LiquidParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiquidParser {
+    pub fn new() -> Self {
+        LiquidParser {
+            lexer: Lexer::new(),
+            tag_aliases: Arc::default(),
+            tags: TagRegistry::new(),
+            common_tags: false,
+            filters: FilterRegistry::standard(),
+            max_depth: None,
+            max_nodes: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Return a parser that accepts _dialect_.
+    pub fn with_dialect(dialect: Dialect) -> Self {
+        LiquidParser {
+            lexer: Lexer::with_dialect(dialect),
+            ..LiquidParser::new()
+        }
+    }
+
+    /// Accept _alias_ wherever the tag called _canonical_ is expected, for
+    /// example `elseif` for `elsif`. The syntax tree only ever uses canonical
+    /// names.
+    pub fn add_tag_alias(&mut self, alias: &str, canonical: &str) {
+        Arc::make_mut(&mut self.tag_aliases).insert(alias.to_owned(), canonical.to_owned());
+    }
+
+    /// Parse _source_ into a syntax tree. This is `Lexer::tokenize` followed
+    /// by `parse_markup`, so the tree is always built from the same markup
+    /// that `tokenize` exposes to Python.
+    pub fn parse(&self, source: &str) -> Result<Template, LiquidError> {
+        self.parse_with_warnings(source)
+            .map(|(template, _)| template)
+    }
+
+    /// Like `parse`, but also return a warning for each unreachable branch
+    /// accepted by the `unreachable_branches` dialect option. Without that
+    /// option, unreachable branches are syntax errors.
+    pub fn parse_with_warnings(&self, source: &str) -> Result<(Template, Vec<Lint>), LiquidError> {
+        if let Some(limit) = self.max_bytes {
+            if source.len() > limit {
+                return Err(LiquidError::syntax(format!(
+                    "template is {} bytes long, the limit is {limit}",
+                    source.len()
+                )));
+            }
+        }
+
+        let markup = self.lexer.tokenize(source)?;
+        self.parse_markup_with_warnings(&markup)
+    }
+
+    /// Parse _source_, attributing any error to the template called _name_.
+    pub fn parse_named(&self, source: &str, name: &str) -> Result<Template, LiquidError> {
+        self.parse(source)
+            .map_err(|err| err.with_source(name, source))
+    }
+
+    /// Parse each of _sources_ in parallel, returning results in the same
+    /// order as _sources_.
+    pub fn parse_many(&self, sources: &[String]) -> Vec<Result<Template, LiquidError>> {
+        sources.par_iter().map(|s| self.parse(s)).collect()
+    }
+
+    /// Describe the tags, keywords, operators and filter expression functions
+    /// understood by this parser.
+    pub fn grammar(&self) -> Grammar {
+        let mut functions: Vec<String> =
+            self.lexer.query_parser.functions.keys().cloned().collect();
+        functions.sort();
+
+        let mut tags = builtin_tags();
+        tags.extend(self.tags.definitions().into_iter().map(TagInfo::from));
+
+        Grammar {
+            tags,
+            tag_aliases: HashMap::clone(&self.tag_aliases),
+            reserved_words: RESERVED_WORDS.iter().map(|s| s.to_string()).collect(),
+            operators: OPERATORS.iter().map(|s| s.to_string()).collect(),
+            functions,
+        }
+    }
+
+    /// Parse a standalone filtered expression, like `user.name | downcase if
+    /// user else 'anon'`. Spans are relative to the start of _source_.
+    pub fn parse_expression(&self, source: &str) -> Result<FilteredExpression, LiquidError> {
+        let tokens = self.lexer.tokenize_expression(source)?;
+        let mut tokens = TokenStream::new(&tokens, Span::new(0, source.len()));
+        let expression = self.parse_filtered_expression(&mut tokens)?;
+        tokens.expect_eos()?;
+        Ok(expression)
+    }
+
+    /// Parse _tokens_, like the expression of a custom tag, as a filtered
+    /// expression. Every token must be used.
+    pub fn parse_filtered_expression_tokens(
+        &self,
+        tokens: &[Token],
+    ) -> Result<FilteredExpression, LiquidError> {
+        parse_all(tokens, |tokens| self.parse_filtered_expression(tokens))
+    }
+
+    /// Parse _tokens_ as a boolean expression, like the condition of an `if`
+    /// tag. Every token must be used.
+    pub fn parse_boolean_expression_tokens(
+        &self,
+        tokens: &[Token],
+    ) -> Result<BooleanExpression, LiquidError> {
+        parse_all(tokens, parse_boolean_expression)
+    }
+
+    /// Parse _tokens_ as comma separated keyword arguments, like the
+    /// arguments of an `include` tag.
+    pub fn parse_keyword_argument_tokens(
+        &self,
+        tokens: &[Token],
+    ) -> Result<Vec<KeywordArgument>, LiquidError> {
+        parse_all(tokens, parse_keyword_arguments)
+    }
+
+    /// Build a syntax tree from markup already produced by the lexer.
+    pub fn parse_markup(&self, markup: &[Markup]) -> Result<Template, LiquidError> {
+        self.parse_markup_with_warnings(markup)
+            .map(|(template, _)| template)
+    }
+
+    fn parse_markup_with_warnings(
+        &self,
+        markup: &[Markup],
+    ) -> Result<(Template, Vec<Lint>), LiquidError> {
+        let mut stream = MarkupStream::new(markup, &self.tag_aliases);
+        let nodes = self.parse_block(&mut stream, &[], Span::default())?;
+        Ok((
+            Template {
+                nodes: nodes.into(),
+            },
+            stream.warnings,
+        ))
+    }
+
+    /// Parse nodes from _stream_ until we find a tag with a name in _end_ or
+    /// the end of the stream. The terminating tag is not consumed. _start_ is
+    /// the span of the tag that opened the block, used in error notes.
+    fn parse_block(
+        &self,
+        stream: &mut MarkupStream,
+        end: &[&str],
+        start: Span,
+    ) -> Result<Vec<Node>, LiquidError> {
+        if let Some(limit) = self.max_depth.filter(|limit| stream.depth > *limit) {
+            return Err(
+                LiquidError::syntax(format!("blocks are nested more than {limit} deep"))
+                    .with_span(start),
+            );
+        }
+
+        stream.depth += 1;
+        let mut nodes = Vec::new();
+        loop {
+            match stream.current() {
+                Markup::EOI {} => break,
+                Markup::Tag { name, .. } if end.contains(&stream.canonical(name)) => break,
+                Markup::Tag { name, span, .. }
+                    if !end.is_empty() && self.is_delimiter(stream.canonical(name)) =>
+                {
+                    return Err(LiquidError::syntax(format!(
+                        "expected {}, found '{name}'",
+                        quoted_names(end)
+                    ))
+                    .with_span(*span)
+                    .with_note("the block was opened here", Some(start)));
+                }
+                markup => {
+                    stream.nodes += 1;
+                    if let Some(limit) = self.max_nodes.filter(|limit| stream.nodes > *limit) {
+                        return Err(LiquidError::syntax(format!(
+                            "template has more than {limit} nodes"
+                        ))
+                        .with_span(markup_span(markup)));
+                    }
+                    // Block tags recurse back into `parse_block`.
+                    nodes.push(grow_stack(|| self.parse_node(stream))?)
+                }
+            }
+        }
+        stream.depth -= 1;
+        Ok(nodes)
+    }
+
+    /// Returns `true` if _name_ is the end tag or an intermediate tag of a
+    /// built-in or registered block tag.
+    pub(crate) fn is_delimiter(&self, name: &str) -> bool {
+        is_block_delimiter(name) || self.tags.is_delimiter(name)
+    }
+
+    fn parse_node(&self, stream: &mut MarkupStream) -> Result<Node, LiquidError> {
+        match stream.next() {
+            Markup::Content { text, span } => Ok(Node::Content {
+                text: text.to_owned(),
+                span: *span,
+            }),
+            Markup::Raw { wc, text, span, .. } => Ok(Node::Raw {
+                wc: *wc,
+                text: text.to_owned(),
+                span: *span,
+            }),
+            Markup::Comment {
+                wc,
+                text,
+                kind: CommentKind::Doc,
+                span,
+                ..
+            } => Ok(Node::DocTag {
+                wc: *wc,
+                doc: parse_doc(text),
+                text: text.to_owned(),
+                span: *span,
+            }),
+            Markup::Comment {
+                wc,
+                hashes,
+                text,
+                kind,
+                span,
+            } => Ok(Node::Comment {
+                wc: *wc,
+                hashes: hashes.to_owned(),
+                text: text.to_owned(),
+                kind: *kind,
+                span: *span,
+            }),
+            Markup::Output {
+                wc,
+                expression,
+                span,
+            } => {
+                let mut tokens = TokenStream::new(expression, *span);
+                let expression = self.parse_filtered_expression(&mut tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::Output {
+                    wc: *wc,
+                    expression,
+                    span: *span,
+                })
+            }
+            Markup::Lines {
+                wc,
+                statements,
+                span,
+                ..
+            } => {
+                let mut lines = MarkupStream::new(statements, &self.tag_aliases);
+                lines.depth = stream.depth;
+                lines.nodes = stream.nodes;
+                let block = self.parse_block(&mut lines, &[], *span)?;
+                stream.nodes = lines.nodes;
+                stream.warnings.append(&mut lines.warnings);
+                Ok(Node::LiquidTag {
+                    wc: *wc,
+                    block: block.into(),
+                    span: *span,
+                })
+            }
+            Markup::Tag {
+                wc,
+                name,
+                expression,
+                span,
+            } => {
+                let mut tokens = TokenStream::new(expression.as_deref().unwrap_or(&[]), *span);
+                let name = stream.canonical(name);
+                self.parse_tag(stream, *wc, name, &mut tokens, *span)
+            }
+            Markup::Error { span, .. } => {
+                Err(LiquidError::syntax("invalid markup".to_string()).with_span(*span))
+            }
+            Markup::EOI {} => Err(LiquidError::syntax(
+                "unexpected end of template".to_string(),
+            )),
+        }
+    }
+
+    fn parse_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        name: &str,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        match name {
+            "assign" => {
+                let token = tokens.next();
+                let identifier = parse_identifier(token, tokens)?;
+                tokens.expect_assign()?;
+                let expression = self.parse_filtered_expression(tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::AssignTag {
+                    wc,
+                    identifier,
+                    identifier_span: tokens.span_of(token),
+                    expression,
+                    span,
+                })
+            }
+            "capture" => {
+                let token = tokens.next();
+                let identifier = parse_string_or_identifier(token, tokens)?;
+                tokens.expect_eos()?;
+                let block = self.parse_block(stream, &["endcapture"], span)?;
+                let (end_wc, end_span) = stream.expect_end_tag("endcapture", span)?;
+                Ok(Node::CaptureTag {
+                    wc,
+                    identifier,
+                    identifier_span: tokens.span_of(token),
+                    block: block.into(),
+                    end_wc,
+                    span: Span::new(span.start, end_span.end),
+                })
+            }
+            "case" => self.parse_case_tag(stream, wc, tokens, span),
+            "cycle" => self.parse_cycle_tag(wc, tokens, span),
+            "decrement" => {
+                let token = tokens.next();
+                let name = parse_string_or_identifier(token, tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::DecrementTag {
+                    wc,
+                    name,
+                    name_span: tokens.span_of(token),
+                    span,
+                })
+            }
+            "increment" => {
+                let token = tokens.next();
+                let name = parse_string_or_identifier(token, tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::IncrementTag {
+                    wc,
+                    name,
+                    name_span: tokens.span_of(token),
+                    span,
+                })
+            }
+            "echo" => {
+                let expression = self.parse_filtered_expression(tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::EchoTag {
+                    wc,
+                    expression,
+                    span,
+                })
+            }
+            "for" => self.parse_for_tag(stream, wc, tokens, span),
+            "tablerow" => self.parse_tablerow_tag(stream, wc, tokens, span),
+            "break" => {
+                tokens.expect_eos()?;
+                Ok(Node::BreakTag { wc, span })
+            }
+            "continue" => {
+                tokens.expect_eos()?;
+                Ok(Node::ContinueTag { wc, span })
+            }
+            "if" | "unless" => self.parse_conditional_tag(stream, wc, name, tokens, span),
+            "include" => {
+                let target = parse_primitive(tokens.next(), tokens)?;
+                let (repeat, variable, alias) = parse_partial_binding(tokens)?;
+                let args = parse_keyword_arguments(tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::IncludeTag {
+                    wc,
+                    target,
+                    repeat,
+                    variable,
+                    alias,
+                    args,
+                    span,
+                })
+            }
+            "render" => {
+                let (target, dynamic) = match tokens.next() {
+                    Some(Token::StringLiteral { value, span }) => (
+                        Primitive::StringLiteral {
+                            value: value.to_owned(),
+                            span: *span,
+                        },
+                        false,
+                    ),
+                    token @ Some(Token::Word { .. } | Token::Query { .. })
+                        if self.lexer.dialect.dynamic_render =>
+                    {
+                        (parse_primitive(token, tokens)?, true)
+                    }
+                    token => {
+                        return Err(LiquidError::syntax(format!(
+                        "expected the name of a template to render as a string literal, found {}",
+                        describe(token)
+                    ))
+                        .with_span(tokens.span_of(token)))
+                    }
+                };
+                let (repeat, variable, alias) = parse_partial_binding(tokens)?;
+                let args = parse_keyword_arguments(tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::RenderTag {
+                    wc,
+                    target,
+                    dynamic,
+                    repeat,
+                    variable,
+                    alias,
+                    args,
+                    span,
+                })
+            }
+            "liquid" => {
+                tokens.expect_eos()?;
+                Ok(Node::LiquidTag {
+                    wc,
+                    block: Vec::new().into(),
+                    span,
+                })
+            }
+            "extends" => {
+                let name = parse_string_or_identifier(tokens.next(), tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::ExtendsTag { wc, name, span })
+            }
+            "block" => self.parse_block_tag(stream, wc, tokens, span),
+            "macro" => {
+                let name = parse_string_or_identifier(tokens.next(), tokens)?;
+                let params = parse_parameters(tokens)?;
+                let block = self.parse_block(stream, &["endmacro"], span)?;
+                let (end_wc, end_span) = stream.expect_end_tag("endmacro", span)?;
+                Ok(Node::MacroTag {
+                    wc,
+                    name,
+                    params,
+                    block: block.into(),
+                    end_wc,
+                    span: Span::new(span.start, end_span.end),
+                })
+            }
+            "translate" | "t" => self.parse_translate_tag(stream, wc, name, tokens, span),
+            "call" => {
+                let name = parse_string_or_identifier(tokens.next(), tokens)?;
+                let args = parse_filter_arguments(tokens)?;
+                tokens.expect_eos()?;
+                Ok(Node::CallTag {
+                    wc,
+                    name,
+                    args,
+                    span,
+                })
+            }
+            // Balanced block comments and doc blocks are tokenized as
+            // `Markup::Comment`.
+            "comment" | "doc" => Err(LiquidError::syntax(format!(
+                "missing end tag, expected 'end{name}'"
+            ))
+            .with_span(span)),
+            _ if self.is_delimiter(name) || name == "endcomment" || name == "enddoc" => {
+                Err(LiquidError::syntax(format!("unexpected tag '{name}'")).with_span(span))
+            }
+            _ => match SIMPLE_BLOCK_TAGS.iter().find(|(tag, _)| *tag == name) {
+                Some((_, build)) => {
+                    self.parse_simple_block_tag(stream, wc, name, *build, tokens, span)
+                }
+                None => match self.tags.get(name) {
+                    Some(definition) => {
+                        self.parse_extension_tag(stream, wc, definition, None, tokens, span)
+                    }
+                    None if self.common_tags || self.lexer.dialect.unknown_tags => {
+                        self.parse_common_tag(stream, wc, name, tokens, span)
+                    }
+                    None => {
+                        Err(LiquidError::syntax(format!("unknown tag '{name}'")).with_span(span))
+                    }
+                },
+            },
+        }
+    }
+
+    fn parse_block_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let name = parse_string_or_identifier(tokens.next(), tokens)?;
+        let mut scoped = false;
+        let mut required = false;
+
+        loop {
+            match tokens.next() {
+                None => break,
+                Some(Token::Word { value, .. }) if value == "scoped" && !scoped => scoped = true,
+                Some(Token::Required { .. }) if !required => required = true,
+                token => {
+                    return Err(LiquidError::syntax(format!(
+                        "expected 'scoped' or 'required', found {}",
+                        describe(token)
+                    ))
+                    .with_span(tokens.span_of(token)))
+                }
+            }
+        }
+
+        let block = self.parse_block(stream, &["endblock"], span)?;
+        let (end_wc, end_tokens, end_span) = stream.expect_tag("endblock", span)?;
+        let mut end_tokens = TokenStream::new(end_tokens, end_span);
+        let named_end = match end_tokens.next() {
+            None => false,
+            token => {
+                let end_name = parse_string_or_identifier(token, &end_tokens)?;
+                if end_name != name {
+                    return Err(LiquidError::syntax(format!(
+                        "expected 'endblock {name}', found 'endblock {end_name}'"
+                    ))
+                    .with_span(end_span));
+                }
+                end_tokens.expect_eos()?;
+                true
+            }
+        };
+
+        Ok(Node::BlockTag {
+            wc,
+            name,
+            scoped,
+            required,
+            block: block.into(),
+            end_wc,
+            named_end,
+            span: Span::new(span.start, end_span.end),
+        })
+    }
+
+    fn parse_translate_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        name: &str,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let args = parse_keyword_arguments(tokens)?;
+        let end_tag = format!("end{name}");
+        let block = self.parse_block(stream, &["plural", &end_tag], span)?;
+
+        let plural = match stream.take_tag("plural") {
+            Some((wc, tokens, plural_span)) => {
+                TokenStream::new(tokens, plural_span).expect_eos()?;
+                let block = self.parse_block(stream, &[&end_tag], span)?;
+                Some(PluralTag {
+                    wc,
+                    block: block.into(),
+                    span: plural_span,
+                })
+            }
+            None => None,
+        };
+
+        let (end_wc, end_span) = stream.expect_end_tag(&end_tag, span)?;
+        Ok(Node::TranslateTag {
+            wc,
+            name: name.to_owned(),
+            args,
+            block: block.into(),
+            plural,
+            end_wc,
+            span: Span::new(span.start, end_span.end),
+        })
+    }
+
+    /// Parse a tag that isn't built in or registered. It's a block tag if it
+    /// is closed by a matching `end<name>` tag, otherwise it's an inline tag.
+    fn parse_simple_block_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        name: &str,
+        build: SimpleBlockTag,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        tokens.expect_eos()?;
+        let end_tag = format!("end{name}");
+        let block = self.parse_block(stream, &[&end_tag], span)?;
+        let (end_wc, end_span) = stream.expect_end_tag(&end_tag, span)?;
+        Ok(build(
+            wc,
+            block,
+            end_wc,
+            Span::new(span.start, end_span.end),
+        ))
+    }
+
+    fn parse_common_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        name: &str,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        // An end tag without its start tag is a mistake, not an inline tag.
+        if name.starts_with("end") {
+            return Err(LiquidError::syntax(format!("unexpected tag '{name}'")).with_span(span));
+        }
+
+        let mut arg_tokens = TokenStream::new(tokens.rest(), span);
+        let args = parse_filter_arguments(&mut arg_tokens)?;
+        arg_tokens.expect_eos()?;
+
+        let end_tag = format!("end{name}");
+        let end_tags = if stream.has_end_tag(name, &end_tag) {
+            vec![end_tag]
+        } else {
+            Vec::new()
+        };
+
+        let definition = TagDefinition {
+            name: name.to_owned(),
+            end_tags,
+            intermediates: Vec::new(),
+        };
+
+        let mut tokens = TokenStream::new(arg_tokens.tokens, span);
+        self.parse_extension_tag(stream, wc, &definition, Some(args), &mut tokens, span)
+    }
+
+    fn parse_extension_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        definition: &TagDefinition,
+        args: Option<Vec<CommonArgument>>,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let expression = tokens.rest().to_vec();
+
+        if !definition.is_block() {
+            return Ok(Node::TagExtension {
+                wc,
+                name: definition.name.clone(),
+                expression,
+                args,
+                block: Vec::new().into(),
+                branches: Vec::new(),
+                end: None,
+                span,
+            });
+        }
+
+        let delimiters: Vec<&str> = definition
+            .end_tags
+            .iter()
+            .chain(definition.intermediates.iter())
+            .map(|s| s.as_str())
+            .collect();
+
+        let block = self.parse_block(stream, &delimiters, span)?;
+        let mut branches = Vec::new();
+
+        while let Some(name) = definition
+            .intermediates
+            .iter()
+            .find(|name| stream.is_tag(name))
+        {
+            let (wc, tokens, branch_span) = stream.take_tag(name).unwrap();
+            branches.push(ExtensionBranch {
+                wc,
+                name: name.to_owned(),
+                expression: tokens.to_vec(),
+                block: self.parse_block(stream, &delimiters, span)?.into(),
+                span: branch_span,
+            });
+        }
+
+        let name = definition
+            .end_tags
+            .iter()
+            .find(|name| stream.is_tag(name))
+            .unwrap_or(&definition.end_tags[0]);
+        let (end_wc, end_span) = stream.expect_end_tag(name, span)?;
+
+        Ok(Node::TagExtension {
+            wc,
+            name: definition.name.clone(),
+            expression,
+            args,
+            block: block.into(),
+            branches,
+            end: Some(ExtensionEnd {
+                wc: end_wc,
+                name: name.to_owned(),
+                span: end_span,
+            }),
+            span,
+        })
+    }
+
+    fn parse_case_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let expression = parse_primitive(tokens.next(), tokens)?;
+        tokens.expect_eos()?;
+
+        // Content or markup between the `case` tag and the first `when` or
+        // `else` tag is not allowed.
+        match stream.current() {
+            Markup::Content { text, span } => {
+                if !text.trim().is_empty() {
+                    return Err(LiquidError::syntax(
+                        "unexpected text after 'case' tag".to_string(),
+                    )
+                    .with_span(*span));
+                }
+                stream.next();
+            }
+            Markup::Tag { .. } | Markup::EOI {} => (),
+            markup => {
+                return Err(
+                    LiquidError::syntax("unexpected markup after 'case' tag".to_string())
+                        .with_span(markup_span(markup)),
+                )
+            }
+        }
+
+        if let Markup::Tag { name, span, .. } = stream.current() {
+            if !matches!(stream.canonical(name), "when" | "else" | "endcase") {
+                return Err(
+                    LiquidError::syntax(format!("expected a 'when' tag, found '{name}'"))
+                        .with_span(*span),
+                );
+            }
+        }
+
+        let mut whens = Vec::new();
+        while let Some((wc, tokens, when_span)) = stream.take_tag("when") {
+            let mut tokens = TokenStream::new(tokens, when_span);
+            let mut args = vec![parse_primitive(tokens.next(), &tokens)?];
+            while matches!(
+                tokens.current(),
+                Some(Token::Comma { .. } | Token::Or { .. })
+            ) {
+                tokens.next();
+                args.push(parse_primitive(tokens.next(), &tokens)?);
+            }
+            tokens.expect_eos()?;
+
+            // Every matching `when` tag is rendered, but a `when` tag's block is
+            // only rendered once, so repeated values in the same tag are dead.
+            let mut values: Vec<(String, Span)> = Vec::new();
+            for arg in &args {
+                let value = arg.to_string();
+                match values.iter().find(|(v, _)| *v == value) {
+                    Some((_, first)) => self.unreachable(
+                        stream,
+                        format!("duplicate 'when' value {value}"),
+                        arg.span(),
+                        "the value first appears here",
+                        *first,
+                    )?,
+                    None => values.push((value, arg.span())),
+                }
+            }
+
+            let block = self.parse_block(stream, &["when", "else", "endcase"], span)?;
+            whens.push(WhenTag {
+                wc,
+                args,
+                block: block.into(),
+                span: when_span,
+            });
+        }
+
+        let default = self.parse_else_tag(stream, &[], &["endcase"], span)?;
+        let (end_wc, end_span) = stream.expect_end_tag("endcase", span)?;
+
+        Ok(Node::CaseTag {
+            wc,
+            expression,
+            whens,
+            default,
+            end_wc,
+            span: Span::new(span.start, end_span.end),
+        })
+    }
+
+    fn parse_cycle_tag(
+        &self,
+        wc: (Whitespace, Whitespace),
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        if tokens.current().is_none() {
+            return Err(
+                LiquidError::syntax("expected a group name or item list".to_string())
+                    .with_span(span),
+            );
+        }
+
+        // Does this cycle tag define a group name followed by a colon, before
+        // listing items to cycle through? Group names are evaluated, so they
+        // can be string literals or variables.
+        let name = if matches!(tokens.peek(), Some(Token::Colon { .. })) {
+            let name = parse_primitive(tokens.next(), tokens)?;
+            tokens.next();
+            Some(name)
+        } else {
+            None
+        };
+
+        // We must have at least one item.
+        let mut args = vec![parse_primitive(tokens.next(), tokens)?];
+
+        loop {
+            match tokens.next() {
+                None => break,
+                Some(Token::Comma { .. }) => {
+                    // Trailing commas are OK.
+                    if tokens.current().is_none() {
+                        break;
+                    }
+                    args.push(parse_primitive(tokens.next(), tokens)?);
+                }
+                token => {
+                    return Err(LiquidError::syntax(format!(
+                        "expected a comma separated list, found {}",
+                        describe(token)
+                    ))
+                    .with_span(tokens.span_of(token)))
+                }
+            }
+        }
+
+        Ok(Node::CycleTag {
+            wc,
+            name,
+            args,
+            span,
+        })
+    }
+
+    fn parse_for_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let token = tokens.next();
+        let name = parse_identifier(token, tokens)?;
+        let mut name_spans = vec![tokens.span_of(token)];
+        let value_name = match tokens.current() {
+            Some(Token::Comma { .. }) => {
+                tokens.next();
+                let token = tokens.next();
+                let value_name = parse_identifier(token, tokens)?;
+                name_spans.push(tokens.span_of(token));
+                Some(value_name)
+            }
+            _ => None,
+        };
+
+        match tokens.next() {
+            Some(Token::In { .. }) => (),
+            token => {
+                return Err(LiquidError::syntax(format!(
+                    "expected 'in', found {}",
+                    describe(token)
+                ))
+                .with_span(tokens.span_of(token)))
+            }
+        }
+
+        let iterable = parse_primitive(tokens.next(), tokens)?;
+        let mut limit = None;
+        let mut offset = None;
+        let mut reversed = false;
+
+        loop {
+            match tokens.next() {
+                None => break,
+                Some(Token::Comma { .. }) => continue,
+                Some(Token::Word { value, .. }) if value == "reversed" => reversed = true,
+                Some(Token::Word { value, .. }) if value == "limit" => {
+                    tokens.expect_argument_separator()?;
+                    limit = Some(parse_primitive(tokens.next(), tokens)?);
+                }
+                Some(Token::Word { value, .. }) if value == "offset" => {
+                    tokens.expect_argument_separator()?;
+                    offset = Some(match tokens.next() {
+                        Some(Token::Word { value, span }) if value == "continue" => {
+                            Primitive::StringLiteral {
+                                value: value.to_owned(),
+                                span: *span,
+                            }
+                        }
+                        token => parse_primitive(token, tokens)?,
+                    });
+                }
+                token => {
+                    return Err(LiquidError::syntax(format!(
+                        "expected 'reversed', 'offset' or 'limit', found {}",
+                        describe(token)
+                    ))
+                    .with_span(tokens.span_of(token)))
+                }
+            }
+        }
+
+        let block = self.parse_block(stream, &["else", "endfor"], span)?;
+        let default = self.parse_else_tag(stream, &[], &["endfor"], span)?;
+        let (end_wc, end_span) = stream.expect_end_tag("endfor", span)?;
+
+        Ok(Node::ForTag {
+            wc,
+            name,
+            value_name,
+            name_spans,
+            iterable,
+            limit,
+            offset,
+            reversed,
+            block: block.into(),
+            default,
+            end_wc,
+            span: Span::new(span.start, end_span.end),
+        })
+    }
+
+    fn parse_tablerow_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let token = tokens.next();
+        let name = parse_identifier(token, tokens)?;
+        let name_span = tokens.span_of(token);
+
+        match tokens.next() {
+            Some(Token::In { .. }) => (),
+            token => {
+                return Err(LiquidError::syntax(format!(
+                    "expected 'in', found {}",
+                    describe(token)
+                ))
+                .with_span(tokens.span_of(token)))
+            }
+        }
+
+        let iterable = parse_primitive(tokens.next(), tokens)?;
+        let mut cols = None;
+        let mut limit = None;
+        let mut offset = None;
+
+        loop {
+            let param = match tokens.next() {
+                None => break,
+                Some(Token::Comma { .. }) => continue,
+                Some(Token::Word { value, .. }) if value == "cols" => &mut cols,
+                Some(Token::Word { value, .. }) if value == "limit" => &mut limit,
+                Some(Token::Word { value, .. }) if value == "offset" => &mut offset,
+                token => {
+                    return Err(LiquidError::syntax(format!(
+                        "expected 'cols', 'limit' or 'offset', found {}",
+                        describe(token)
+                    ))
+                    .with_span(tokens.span_of(token)))
+                }
+            };
+            tokens.expect_argument_separator()?;
+            *param = Some(parse_primitive(tokens.next(), tokens)?);
+        }
+
+        let block = self.parse_block(stream, &["endtablerow"], span)?;
+        let (end_wc, end_span) = stream.expect_end_tag("endtablerow", span)?;
+
+        Ok(Node::TablerowTag {
+            wc,
+            name,
+            name_span,
+            iterable,
+            cols,
+            limit,
+            offset,
+            block: block.into(),
+            end_wc,
+            span: Span::new(span.start, end_span.end),
+        })
+    }
+
+    fn parse_conditional_tag(
+        &self,
+        stream: &mut MarkupStream,
+        wc: (Whitespace, Whitespace),
+        name: &str,
+        tokens: &mut TokenStream,
+        span: Span,
+    ) -> Result<Node, LiquidError> {
+        let end_tag = if name == "if" { "endif" } else { "endunless" };
+        let condition = parse_boolean_expression(tokens)?;
+        tokens.expect_eos()?;
+
+        let block = self.parse_block(stream, &["elsif", "else", end_tag], span)?;
+
+        let mut alternatives = Vec::new();
+        while let Some((wc, tokens, elsif_span)) = stream.take_tag("elsif") {
+            let mut tokens = TokenStream::new(tokens, elsif_span);
+            let condition = parse_boolean_expression(&mut tokens)?;
+            tokens.expect_eos()?;
+            let block = self.parse_block(stream, &["elsif", "else", end_tag], span)?;
+            alternatives.push(ElsifTag {
+                wc,
+                condition,
+                block: block.into(),
+                span: elsif_span,
+            });
+        }
+
+        let default = self.parse_else_tag(stream, &["elsif"], &[end_tag], span)?;
+        let (end_wc, end_span) = stream.expect_end_tag(end_tag, span)?;
+        let span = Span::new(span.start, end_span.end);
+
+        if name == "if" {
+            Ok(Node::IfTag {
+                wc,
+                condition,
+                block: block.into(),
+                alternatives,
+                default,
+                end_wc,
+                span,
+            })
+        } else {
+            Ok(Node::UnlessTag {
+                wc,
+                condition,
+                block: block.into(),
+                alternatives,
+                default,
+                end_wc,
+                span,
+            })
+        }
+    }
+
+    /// Parse an optional `else` tag and its block. _branches_ are the names
+    /// of other intermediate tags, like `elsif`, which can't be reached after
+    /// `else`.
+    fn parse_else_tag(
+        &self,
+        stream: &mut MarkupStream,
+        branches: &[&str],
+        end: &[&str],
+        start: Span,
+    ) -> Result<Option<ElseTag>, LiquidError> {
+        let Some((wc, tokens, span)) = stream.take_tag("else") else {
+            return Ok(None);
+        };
+
+        TokenStream::new(tokens, span).expect_eos()?;
+        let unreachable: Vec<&str> = std::iter::once("else")
+            .chain(branches.iter().copied())
+            .collect();
+        let delimiters: Vec<&str> = unreachable.iter().chain(end).copied().collect();
+        let block = self.parse_block(stream, &delimiters, start)?;
+
+        // Unreachable branches are still parsed, so they must be valid, but
+        // they are left out of the syntax tree.
+        while let Some(name) = unreachable.iter().find(|name| stream.is_tag(name)) {
+            let (_, tokens, branch_span) = stream.take_tag(name).unwrap();
+            let mut tokens = TokenStream::new(tokens, branch_span);
+            if *name == "elsif" {
+                parse_boolean_expression(&mut tokens)?;
+            }
+            tokens.expect_eos()?;
+
+            let msg = if *name == "else" {
+                "duplicate 'else' tag".to_owned()
+            } else {
+                format!("unexpected '{name}' tag after 'else'")
+            };
+            self.unreachable(stream, msg, branch_span, "the 'else' tag is here", span)?;
+            self.parse_block(stream, &delimiters, start)?;
+        }
+
+        Ok(Some(ElseTag {
+            wc,
+            block: block.into(),
+            span,
+        }))
+    }
+
+    /// Report a branch at _span_ that can never be reached, with a note
+    /// pointing at the branch that makes it unreachable. This is a syntax
+    /// error unless the `unreachable_branches` dialect option is enabled, in
+    /// which case it's a warning.
+    fn unreachable(
+        &self,
+        stream: &mut MarkupStream,
+        msg: String,
+        span: Span,
+        note: &str,
+        note_span: Span,
+    ) -> Result<(), LiquidError> {
+        if !self.lexer.dialect.unreachable_branches {
+            return Err(LiquidError::syntax(msg)
+                .with_span(span)
+                .with_note(note, Some(note_span)));
+        }
+
+        stream.warnings.push(Lint {
+            rule: "unreachable-branch",
+            message: msg,
+            span,
+        });
+        Ok(())
+    }
+
+    fn parse_filtered_expression(
+        &self,
+        tokens: &mut TokenStream,
+    ) -> Result<FilteredExpression, LiquidError> {
+        let left = parse_primitive(tokens.next(), tokens)?;
+        let filters = parse_filters(tokens, false, &self.filters)?;
+        let condition = match tokens.current() {
+            Some(token @ (Token::If { .. } | Token::Elvis { .. }))
+                if !self.lexer.dialect.inline_conditions =>
+            {
+                return Err(disallowed("inline_conditions", token.span()));
+            }
+            Some(Token::If { .. }) => Some(parse_inline_condition(tokens, &self.filters)?),
+            Some(Token::Elvis { .. }) => Some(parse_elvis(&left, tokens, &self.filters)?),
+            _ => None,
+        };
+
+        let span = Span::new(left.span().start, tokens.previous_end());
+        Ok(FilteredExpression {
+            left,
+            filters,
+            condition,
+            span,
+        })
+    }
+}
+
+/// Parse _tokens_ with _parse_, failing if any tokens are left over. Errors at
+/// the end of the expression point at the whole expression.
+fn parse_all<T>(
+    tokens: &[Token],
+    parse: impl FnOnce(&mut TokenStream) -> Result<T, LiquidError>,
+) -> Result<T, LiquidError> {
+    let mut tokens = TokenStream::new(tokens, tokens_span(tokens));
+    let rv = parse(&mut tokens)?;
+    tokens.expect_eos()?;
+    Ok(rv)
+}
+
+/// Returns `true` if _name_ is the end tag or an intermediate tag of a built-in
+/// block tag.
+fn is_block_delimiter(name: &str) -> bool {
+    builtin_tags()
+        .iter()
+        .any(|tag| tag.end.as_deref() == Some(name) || tag.intermediates.iter().any(|t| t == name))
+}
+
+/// Format tag names for an error message, like `'elsif', 'else' or 'endif'`.
+pub(crate) fn quoted_names(names: &[&str]) -> String {
+    let quoted: Vec<String> = names.iter().map(|name| format!("'{name}'")).collect();
+    match quoted.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        _ => quoted.join(""),
+    }
+}
+
+/// A tag's whitespace control, expression tokens and span.
+type TagParts<'a> = ((Whitespace, Whitespace), &'a [Token], Span);
+
+/// A cursor over a slice of markup.
+struct MarkupStream<'a> {
+    markup: &'a [Markup],
+    pos: usize,
+    aliases: &'a HashMap<String, String>,
+    /// The number of blocks currently being parsed.
+    depth: usize,
+    /// The number of nodes parsed so far.
+    nodes: usize,
+    /// Unreachable branches accepted by the `unreachable_branches` dialect
+    /// option.
+    warnings: Vec<Lint>,
+}
+
+impl<'a> MarkupStream<'a> {
+    fn new(markup: &'a [Markup], aliases: &'a HashMap<String, String>) -> Self {
+        Self {
+            markup,
+            pos: 0,
+            aliases,
+            depth: 0,
+            nodes: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Resolve a tag name through the alias table.
+    fn canonical(&self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, |s| s.as_str())
+    }
+
+    fn current(&self) -> &'a Markup {
+        self.markup.get(self.pos).unwrap_or(&EOI)
+    }
+
+    /// Return the current markup and advance the stream.
+    fn next(&mut self) -> &'a Markup {
+        let markup = self.current();
+        self.pos += 1;
+        markup
+    }
+
+    /// If the current markup is a tag called _name_, consume it and return its
+    /// whitespace control, expression tokens and span.
+    fn take_tag(&mut self, name: &str) -> Option<TagParts<'a>> {
+        match self.current() {
+            Markup::Tag {
+                wc,
+                name: tag_name,
+                expression,
+                span,
+            } if self.canonical(tag_name) == name => {
+                self.pos += 1;
+                Some((*wc, expression.as_deref().unwrap_or(&[]), *span))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return `true` if the current markup is a tag called _name_.
+    fn is_tag(&self, name: &str) -> bool {
+        matches!(self.current(), Markup::Tag { name: tag_name, .. } if self.canonical(tag_name) == name)
+    }
+
+    /// Return `true` if a tag called _end_ follows the current position,
+    /// skipping over any nested _name_ tags and their end tags.
+    fn has_end_tag(&self, name: &str, end: &str) -> bool {
+        let mut depth = 0;
+        for markup in self.markup.iter().skip(self.pos) {
+            if let Markup::Tag { name: tag_name, .. } = markup {
+                let tag_name = self.canonical(tag_name);
+                if tag_name == name {
+                    depth += 1;
+                } else if tag_name == end {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+        false
+    }
+
+    /// Consume the end tag called _name_, closing the block that started at
+    /// _start_.
+    fn expect_end_tag(
+        &mut self,
+        name: &str,
+        start: Span,
+    ) -> Result<((Whitespace, Whitespace), Span), LiquidError> {
+        let (wc, tokens, span) = self.expect_tag(name, start)?;
+        TokenStream::new(tokens, span).expect_eos()?;
+        Ok((wc, span))
+    }
+
+    /// Like `expect_end_tag`, but the end tag's expression tokens are
+    /// returned instead of being rejected.
+    fn expect_tag(&mut self, name: &str, start: Span) -> Result<TagParts<'a>, LiquidError> {
+        match self.take_tag(name) {
+            Some(tag) => Ok(tag),
+            None => match self.current() {
+                Markup::EOI {} => Err(LiquidError::syntax(format!(
+                    "missing end tag, expected '{name}'"
+                ))
+                .with_span(start)),
+                markup => Err(LiquidError::syntax(format!(
+                    "expected '{name}', found '{}'",
+                    markup_name(markup)
+                ))
+                .with_span(markup_span(markup))),
+            },
+        }
+    }
+}
+
+fn markup_name(markup: &Markup) -> &str {
+    match markup {
+        Markup::Tag { name, .. } | Markup::Lines { name, .. } => name,
+        Markup::Content { .. } => "content",
+        Markup::Error { .. } => "invalid markup",
+        Markup::Raw { .. } => "raw",
+        Markup::Comment { .. } => "comment",
+        Markup::Output { .. } => "output",
+        Markup::EOI {} => "end of input",
+    }
+}
+
+fn markup_span(markup: &Markup) -> Span {
+    match markup {
+        Markup::Content { span, .. }
+        | Markup::Raw { span, .. }
+        | Markup::Comment { span, .. }
+        | Markup::Output { span, .. }
+        | Markup::Tag { span, .. }
+        | Markup::Lines { span, .. }
+        | Markup::Error { span, .. } => *span,
+        Markup::EOI {} => Span::default(),
+    }
+}
+
+/// A cursor over the tokens of a single tag or output statement.
+struct TokenStream<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    /// The span of the enclosing markup, used for errors at the end of the
+    /// expression.
+    span: Span,
+}
+
+impl<'a> TokenStream<'a> {
+    fn new(tokens: &'a [Token], span: Span) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            span,
+        }
+    }
+
+    fn current(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consume and return all remaining tokens.
+    fn rest(&mut self) -> &'a [Token] {
+        let rest = &self.tokens[self.pos.min(self.tokens.len())..];
+        self.pos = self.tokens.len();
+        rest
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos + 1)
+    }
+
+    /// Return the current token and advance the stream.
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.current();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// The end of the most recently consumed token.
+    fn previous_end(&self) -> usize {
+        match self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(token) => token.span().end,
+            None => self.span.start,
+        }
+    }
+
+    fn span_of(&self, token: Option<&Token>) -> Span {
+        token.map_or(self.span, |t| t.span())
+    }
+
+    fn expect_eos(&self) -> Result<(), LiquidError> {
+        match self.current() {
+            None => Ok(()),
+            token => Err(LiquidError::syntax(format!(
+                "expected end of expression, found {}",
+                describe(token)
+            ))
+            .with_span(self.span_of(token))),
+        }
+    }
+
+    fn expect_assign(&mut self) -> Result<(), LiquidError> {
+        match self.next() {
+            Some(Token::Assign { .. }) => Ok(()),
+            token => Err(
+                LiquidError::syntax(format!("expected '=', found {}", describe(token)))
+                    .with_span(self.span_of(token)),
+            ),
+        }
+    }
+
+    /// Consume the `:` or `=` between an argument name and its value.
+    fn expect_argument_separator(&mut self) -> Result<(), LiquidError> {
+        match self.next() {
+            Some(Token::Colon { .. } | Token::Assign { .. }) => Ok(()),
+            token => Err(LiquidError::syntax(format!(
+                "expected ':' or '=', found {}",
+                describe(token)
+            ))
+            .with_span(self.span_of(token))),
+        }
+    }
+}
+
+pub(crate) fn describe(token: Option<&Token>) -> String {
+    match token {
+        Some(token) => format!("'{token}'"),
+        None => "end of expression".to_string(),
+    }
+}
+
+/// Return a query with a single name selector.
+fn word_query(name: &str, span: Span) -> Query {
+    Query {
+        segments: vec![Segment::Child {
+            selectors: vec![Selector::Name {
+                name: name.to_owned(),
+                span,
+            }]
+            .into(),
+            span,
+        }]
+        .into(),
+    }
+}
+
+fn parse_primitive(token: Option<&Token>, tokens: &TokenStream) -> Result<Primitive, LiquidError> {
+    match token {
+        Some(Token::True_ { span }) => Ok(Primitive::TrueLiteral { span: *span }),
+        Some(Token::False_ { span }) => Ok(Primitive::FalseLiteral { span: *span }),
+        Some(Token::Null { span }) => Ok(Primitive::NullLiteral { span: *span }),
+        Some(Token::Word { value, span }) => Ok(match value.as_str() {
+            "empty" => Primitive::Empty { span: *span },
+            "blank" => Primitive::Blank { span: *span },
+            _ => Primitive::Query {
+                path: word_query(value, *span),
+                span: *span,
+            },
+        }),
+        Some(Token::RangeLiteral {
+            start,
+            stop,
+            step,
+            span,
+        }) => Ok(Primitive::Range {
+            start: Box::new(range_argument(start)),
+            stop: Box::new(range_argument(stop)),
+            step: step.as_ref().map(|step| Box::new(range_argument(step))),
+            span: *span,
+        }),
+        Some(Token::StringLiteral { value, span }) => Ok(Primitive::StringLiteral {
+            value: value.to_owned(),
+            span: *span,
+        }),
+        Some(Token::IntegerLiteral { value, span }) => Ok(Primitive::Integer {
+            value: *value,
+            span: *span,
+        }),
+        Some(Token::FloatLiteral { value, span }) => Ok(Primitive::Float {
+            value: *value,
+            span: *span,
+        }),
+        Some(Token::Query { path, span }) => Ok(Primitive::Query {
+            path: path.clone(),
+            span: *span,
+        }),
+        token => Err(LiquidError::syntax(format!(
+            "expected a primitive expression, found {}",
+            describe(token)
+        ))
+        .with_span(tokens.span_of(token))),
+    }
+}
+
+fn range_argument(arg: &RangeArgument) -> Primitive {
+    match arg {
+        RangeArgument::StringLiteral { value, span } => Primitive::StringLiteral {
+            value: value.to_owned(),
+            span: *span,
+        },
+        RangeArgument::IntegerLiteral { value, span } => Primitive::Integer {
+            value: *value,
+            span: *span,
+        },
+        RangeArgument::FloatLiteral { value, span } => Primitive::Float {
+            value: *value,
+            span: *span,
+        },
+        RangeArgument::Query { path, span } => Primitive::Query {
+            path: path.clone(),
+            span: *span,
+        },
+    }
+}
+
+fn parse_identifier(token: Option<&Token>, tokens: &TokenStream) -> Result<String, LiquidError> {
+    match token {
+        Some(Token::Word { value, .. }) => Ok(value.to_owned()),
+        Some(Token::Query { path, span }) => path.as_word().ok_or_else(|| {
+            LiquidError::syntax("expected an identifier, found a path".to_string()).with_span(*span)
+        }),
+        token => Err(LiquidError::syntax(format!(
+            "expected an identifier, found {}",
+            describe(token)
+        ))
+        .with_span(tokens.span_of(token))),
+    }
+}
+
+fn parse_string_or_identifier(
+    token: Option<&Token>,
+    tokens: &TokenStream,
+) -> Result<String, LiquidError> {
+    match token {
+        Some(Token::StringLiteral { value, .. }) => Ok(value.to_owned()),
+        token => parse_identifier(token, tokens),
+    }
+}
+
+/// Parse as many filters as possible. When _tail_ is true, filters are
+/// introduced by `||` and subsequently delimited by `|` or `||`.
+/// Parse filters, checking each one against _registry_ as it is parsed.
+fn parse_filters(
+    tokens: &mut TokenStream,
+    tail: bool,
+    registry: &FilterRegistry,
+) -> Result<Option<Vec<Filter>>, LiquidError> {
+    let mut filters = Vec::new();
+
+    loop {
+        match tokens.current() {
+            Some(Token::Pipe { .. }) if !tail || !filters.is_empty() => (),
+            Some(Token::DoublePipe { .. }) if tail => (),
+            _ => break,
+        }
+        tokens.next();
+
+        let (name, name_span) = match tokens.next() {
+            Some(Token::Word { value, span }) => (value.to_owned(), *span),
+            token => {
+                return Err(LiquidError::syntax(format!(
+                    "expected a filter name, found {}",
+                    describe(token)
+                ))
+                .with_span(tokens.span_of(token)))
+            }
+        };
+
+        let mut args = None;
+        if matches!(tokens.current(), Some(Token::Colon { .. })) {
+            tokens.next();
+            args = Some(parse_filter_arguments(tokens)?);
+        }
+
+        let filter = Filter {
+            name,
+            args,
+            span: Span::new(name_span.start, tokens.previous_end()),
+        };
+        registry.check(&filter)?;
+        filters.push(filter);
+    }
+
+    if filters.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(filters))
+    }
+}
+
+fn parse_filter_arguments(tokens: &mut TokenStream) -> Result<Vec<CommonArgument>, LiquidError> {
+    let mut args = Vec::new();
+
+    loop {
+        match tokens.current() {
+            // Leading, trailing and duplicate commas are OK.
+            Some(Token::Comma { .. }) => {
+                tokens.next();
+            }
+            Some(Token::Word { .. }) if matches!(tokens.peek(), Some(Token::Arrow { .. })) => {
+                let value = parse_lambda(tokens)?;
+                args.push(CommonArgument {
+                    span: value.span(),
+                    value,
+                    name: None,
+                });
+            }
+            Some(Token::LeftParen { .. }) => {
+                let value = parse_lambda(tokens)?;
+                args.push(CommonArgument {
+                    span: value.span(),
+                    value,
+                    name: None,
+                });
+            }
+            Some(Token::Word { value, span })
+                if matches!(
+                    tokens.peek(),
+                    Some(Token::Colon { .. } | Token::Assign { .. })
+                ) =>
+            {
+                // A named or keyword argument
+                tokens.next();
+                tokens.next();
+                let value_ = parse_primitive(tokens.next(), tokens)?;
+                args.push(CommonArgument {
+                    span: Span::new(span.start, value_.span().end),
+                    value: value_,
+                    name: Some(value.to_owned()),
+                });
+            }
+            Some(
+                Token::Word { .. }
+                | Token::Query { .. }
+                | Token::IntegerLiteral { .. }
+                | Token::FloatLiteral { .. }
+                | Token::StringLiteral { .. }
+                | Token::True_ { .. }
+                | Token::False_ { .. }
+                | Token::Null { .. }
+                | Token::RangeLiteral { .. },
+            ) => {
+                let value = parse_primitive(tokens.next(), tokens)?;
+                args.push(CommonArgument {
+                    span: value.span(),
+                    value,
+                    name: None,
+                });
+            }
+            _ => break,
+        }
+    }
+
+    Ok(args)
+}
+
+/// Parse an arrow function argument, like `item => item.price > 10` or
+/// `(item, index) => index < 3`.
+fn parse_lambda(tokens: &mut TokenStream) -> Result<Primitive, LiquidError> {
+    let start = tokens
+        .current()
+        .map_or(tokens.span.start, |t| t.span().start);
+    let mut params = Vec::new();
+
+    if matches!(tokens.current(), Some(Token::LeftParen { .. })) {
+        tokens.next();
+        loop {
+            params.push(parse_identifier(tokens.next(), tokens)?);
+            match tokens.next() {
+                Some(Token::Comma { .. }) => (),
+                Some(Token::RightParen { .. }) => break,
+                token => {
+                    return Err(LiquidError::syntax(format!(
+                        "expected ',' or ')', found {}",
+                        describe(token)
+                    ))
+                    .with_span(tokens.span_of(token)))
+                }
+            }
+        }
+    } else {
+        params.push(parse_identifier(tokens.next(), tokens)?);
+    }
+
+    if params.len() > 2 {
+        return Err(LiquidError::syntax(format!(
+            "lambdas take at most 2 parameters, found {}",
+            params.len()
+        ))
+        .with_span(Span::new(start, tokens.previous_end())));
+    }
+
+    match tokens.next() {
+        Some(Token::Arrow { .. }) => (),
+        token => {
+            return Err(
+                LiquidError::syntax(format!("expected '=>', found {}", describe(token)))
+                    .with_span(tokens.span_of(token)),
+            )
+        }
+    }
+
+    let expr = parse_boolean_expression(tokens)?;
+    Ok(Primitive::Lambda {
+        span: Span::new(start, expr.span().end),
+        params,
+        expr: Box::new(expr),
+    })
+}
+
+fn parse_inline_condition(
+    tokens: &mut TokenStream,
+    registry: &FilterRegistry,
+) -> Result<InlineCondition, LiquidError> {
+    let start = tokens.next().map_or(tokens.span.start, |t| t.span().start);
+    let expr = parse_boolean_expression(tokens)?;
+
+    let mut alternative = None;
+    let mut alternative_filters = None;
+
+    if matches!(tokens.current(), Some(Token::Else { .. })) {
+        tokens.next();
+        alternative = Some(parse_primitive(tokens.next(), tokens)?);
+        alternative_filters = parse_filters(tokens, false, registry)?;
+    }
+
+    let tail_filters = parse_filters(tokens, true, registry)?;
+
+    Ok(InlineCondition {
+        expr,
+        alternative,
+        alternative_filters,
+        alternative_condition: None,
+        tail_filters,
+        elvis: false,
+        span: Span::new(start, tokens.previous_end()),
+    })
+}
+
+/// Parse `?: alternative`, following _left_, as an inline condition that
+/// tests _left_, so `a ?: b` is equivalent to `a if a else b`. Chained `?:`
+/// operators are right-associative, and tail filters apply to the whole
+/// chain.
+fn parse_elvis(
+    left: &Primitive,
+    tokens: &mut TokenStream,
+    registry: &FilterRegistry,
+) -> Result<InlineCondition, LiquidError> {
+    let mut condition = parse_elvis_alternative(left, tokens, registry)?;
+    condition.tail_filters = parse_filters(tokens, true, registry)?;
+    condition.span.end = tokens.previous_end();
+    Ok(condition)
+}
+
+fn parse_elvis_alternative(
+    left: &Primitive,
+    tokens: &mut TokenStream,
+    registry: &FilterRegistry,
+) -> Result<InlineCondition, LiquidError> {
+    let start = tokens.next().map_or(tokens.span.start, |t| t.span().start);
+    let alternative = parse_primitive(tokens.next(), tokens)?;
+    let alternative_filters = parse_filters(tokens, false, registry)?;
+    let alternative_condition = match tokens.current() {
+        Some(Token::Elvis { .. }) => Some(Box::new(parse_elvis_alternative(
+            &alternative,
+            tokens,
+            registry,
+        )?)),
+        _ => None,
+    };
+
+    Ok(InlineCondition {
+        expr: BooleanExpression::Primitive { expr: left.clone() },
+        alternative: Some(alternative),
+        alternative_filters,
+        alternative_condition,
+        tail_filters: None,
+        elvis: true,
+        span: Span::new(start, tokens.previous_end()),
+    })
+}
+
+fn parse_boolean_expression(tokens: &mut TokenStream) -> Result<BooleanExpression, LiquidError> {
+    parse_boolean_primitive(tokens, PRECEDENCE_LOWEST)
+}
+
+fn precedence(token: Option<&Token>) -> u8 {
+    match token {
+        Some(
+            Token::Eq { .. }
+            | Token::Ne { .. }
+            | Token::Lt { .. }
+            | Token::Gt { .. }
+            | Token::Le { .. }
+            | Token::Ge { .. },
+        ) => PRECEDENCE_RELATIONAL,
+        Some(Token::Contains { .. } | Token::In { .. }) => PRECEDENCE_MEMBERSHIP,
+        Some(Token::And { .. }) => PRECEDENCE_LOGICAL_AND,
+        Some(Token::Or { .. }) => PRECEDENCE_LOGICAL_OR,
+        _ => PRECEDENCE_LOWEST,
+    }
+}
+
+fn parse_boolean_primitive(
+    tokens: &mut TokenStream,
+    precedence_: u8,
+) -> Result<BooleanExpression, LiquidError> {
+    let mut left = match tokens.current() {
+        Some(Token::Not { span }) => {
+            let start = span.start;
+            tokens.next();
+            let expr = parse_boolean_primitive(tokens, PRECEDENCE_LOWEST)?;
+            BooleanExpression::LogicalNot {
+                span: Span::new(start, expr.span().end),
+                expr: Box::new(expr),
+            }
+        }
+        Some(Token::LeftParen { span }) => {
+            let start = span.start;
+            tokens.next();
+            let expr = parse_boolean_primitive(tokens, PRECEDENCE_LOWEST)?;
+            match tokens.next() {
+                Some(Token::RightParen { .. }) => (),
+                token => {
+                    return Err(LiquidError::syntax("unbalanced parentheses".to_string())
+                        .with_span(if token.is_some() {
+                            tokens.span_of(token)
+                        } else {
+                            Span::new(start, tokens.previous_end())
+                        }))
+                }
+            }
+            expr
+        }
+        _ => BooleanExpression::Primitive {
+            expr: parse_primitive(tokens.next(), tokens)?,
+        },
+    };
+
+    loop {
+        // `a not contains b` is `not (a contains b)`.
+        let negated = matches!(tokens.current(), Some(Token::Not { .. }))
+            && matches!(tokens.peek(), Some(Token::Contains { .. }));
+        if negated {
+            tokens.next();
+        }
+
+        let token = tokens.current();
+        if token.is_none() || precedence(token) < precedence_ {
+            break;
+        }
+
+        let operator = match token {
+            Some(Token::Eq { .. }) => Operator::Compare(CompareOperator::Eq),
+            Some(Token::Ne { .. }) => Operator::Compare(CompareOperator::Ne),
+            Some(Token::Ge { .. }) => Operator::Compare(CompareOperator::Ge),
+            Some(Token::Gt { .. }) => Operator::Compare(CompareOperator::Gt),
+            Some(Token::Le { .. }) => Operator::Compare(CompareOperator::Le),
+            Some(Token::Lt { .. }) => Operator::Compare(CompareOperator::Lt),
+            Some(Token::Contains { .. }) => Operator::Compare(CompareOperator::Contains),
+            Some(Token::In { .. }) => Operator::Compare(CompareOperator::In),
+            Some(Token::And { .. }) => Operator::Logical(BooleanOperator::And),
+            Some(Token::Or { .. }) => Operator::Logical(BooleanOperator::Or),
+            _ => break,
+        };
+
+        let precedence_ = precedence(tokens.next());
+        let right = parse_boolean_primitive(tokens, precedence_)?;
+        let span = Span::new(left.span().start, right.span().end);
+
+        let expr = match operator {
+            Operator::Compare(operator) => BooleanExpression::Comparison {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                span,
+            },
+            Operator::Logical(operator) => BooleanExpression::Logical {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                span,
+            },
+        };
+
+        left = if negated {
+            BooleanExpression::LogicalNot {
+                expr: Box::new(expr),
+                span,
+            }
+        } else {
+            expr
+        };
+    }
+
+    Ok(left)
+}
+
+enum Operator {
+    Compare(CompareOperator),
+    Logical(BooleanOperator),
+}
+
+/// Parse the optional `for`/`with` binding of an `include` or `render` tag.
+fn parse_partial_binding(
+    tokens: &mut TokenStream,
+) -> Result<(bool, Option<Primitive>, Option<String>), LiquidError> {
+    let repeat = match (tokens.current(), tokens.peek()) {
+        // `for` and `with` followed by `:` or `,` are keyword argument names.
+        (_, Some(Token::Colon { .. } | Token::Comma { .. })) => return Ok((false, None, None)),
+        (Some(Token::For { .. }), _) => true,
+        (Some(Token::With { .. }), _) => false,
+        _ => return Ok((false, None, None)),
+    };
+
+    tokens.next();
+    let variable = parse_primitive(tokens.next(), tokens)?;
+    let alias = if matches!(tokens.current(), Some(Token::As { .. })) {
+        tokens.next();
+        Some(parse_string_or_identifier(tokens.next(), tokens)?)
+    } else {
+        None
+    };
+
+    Ok((repeat, Some(variable), alias))
+}
+
+/// Parse keyword arguments. Names and values can be separated by a colon or
+/// an equals sign.
+/// Parse macro parameters, like `a, b: 'default'`.
+fn parse_parameters(tokens: &mut TokenStream) -> Result<Vec<Parameter>, LiquidError> {
+    let mut params: Vec<Parameter> = Vec::new();
+
+    loop {
+        match tokens.next() {
+            None => break,
+            // Leading and/or trailing commas are OK.
+            Some(Token::Comma { .. }) => continue,
+            token @ Some(Token::Word { span, .. }) => {
+                let name = parse_identifier(token, tokens)?;
+                if params.iter().any(|param| param.name == name) {
+                    return Err(LiquidError::syntax(format!("duplicate parameter '{name}'"))
+                        .with_span(*span));
+                }
+
+                let (default, end) = match tokens.current() {
+                    Some(Token::Colon { .. } | Token::Assign { .. }) => {
+                        tokens.next();
+                        let default = parse_primitive(tokens.next(), tokens)?;
+                        let end = default.span().end;
+                        (Some(default), end)
+                    }
+                    _ => (None, span.end),
+                };
+
+                params.push(Parameter {
+                    name,
+                    default,
+                    span: Span::new(span.start, end),
+                });
+            }
+            token => {
+                return Err(LiquidError::syntax(format!(
+                    "expected a parameter name, found {}",
+                    describe(token)
+                ))
+                .with_span(tokens.span_of(token)))
+            }
+        }
+    }
+
+    Ok(params)
+}
+
+fn parse_keyword_arguments(tokens: &mut TokenStream) -> Result<Vec<KeywordArgument>, LiquidError> {
+    let mut args = Vec::new();
+
+    loop {
+        match tokens.next() {
+            None => break,
+            // Leading and/or trailing commas are OK.
+            Some(Token::Comma { .. }) => continue,
+            token @ Some(Token::Word { span, .. } | Token::Query { span, .. }) => {
+                let name = parse_identifier(token, tokens)?;
+                tokens.expect_argument_separator()?;
+                let value = parse_primitive(tokens.next(), tokens)?;
+                args.push(KeywordArgument {
+                    name,
+                    span: Span::new(span.start, value.span().end),
+                    value,
+                });
+            }
+            token => {
+                return Err(LiquidError::syntax(format!(
+                    "expected a keyword argument, found {}",
+                    describe(token)
+                ))
+                .with_span(tokens.span_of(token)))
+            }
+        }
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,290 @@
+//! Eagerly parse a template along with every partial template it uses, and
+//! find the templates affected by a change to a partial template.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::ast::{Node, Primitive, Template};
+use crate::errors::LiquidError;
+use crate::loader::{AsyncLoader, Loader};
+use crate::parser::LiquidParser;
+
+/// An entry template and all of the partial templates it statically
+/// references, directly or indirectly.
+#[derive(Debug, Clone)]
+pub struct TemplateBundle {
+    pub entry: Template,
+    /// Parsed partial templates, keyed by name.
+    pub partials: HashMap<String, Template>,
+}
+
+impl LiquidParser {
+    /// Parse the template called _entry_, then load and parse every template
+    /// it includes, renders or extends by string literal name, recursively.
+    ///
+    /// Errors are tagged with the name of the template they occurred in.
+    /// Partials named by a variable can not be resolved statically and are
+    /// skipped.
+    pub fn parse_with_partials(
+        &self,
+        entry: &str,
+        loader: &dyn Loader,
+    ) -> Result<TemplateBundle, LiquidError> {
+        let entry_template = self.load(entry, loader)?;
+
+        let mut partials: HashMap<String, Template> = HashMap::new();
+        let mut pending: Vec<String> = Vec::new();
+        static_partial_names(&entry_template.nodes, &mut pending);
+
+        while let Some(name) = pending.pop() {
+            if name == entry || partials.contains_key(&name) {
+                continue;
+            }
+            let template = self.load(&name, loader)?;
+            static_partial_names(&template.nodes, &mut pending);
+            partials.insert(name, template);
+        }
+
+        Ok(TemplateBundle {
+            entry: entry_template,
+            partials,
+        })
+    }
+
+    /// Like `parse_with_partials`, but loads templates with an
+    /// `AsyncLoader`.
+    pub async fn parse_with_partials_async(
+        &self,
+        entry: &str,
+        loader: &dyn AsyncLoader,
+    ) -> Result<TemplateBundle, LiquidError> {
+        let entry_template = self.load_async(entry, loader).await?;
+
+        let mut partials: HashMap<String, Template> = HashMap::new();
+        let mut pending: Vec<String> = Vec::new();
+        static_partial_names(&entry_template.nodes, &mut pending);
+
+        while let Some(name) = pending.pop() {
+            if name == entry || partials.contains_key(&name) {
+                continue;
+            }
+            let template = self.load_async(&name, loader).await?;
+            static_partial_names(&template.nodes, &mut pending);
+            partials.insert(name, template);
+        }
+
+        Ok(TemplateBundle {
+            entry: entry_template,
+            partials,
+        })
+    }
+
+    /// Load and parse each of _entries_, then every template they include,
+    /// render or extend by string literal name, recursively, recording which
+    /// templates use which.
+    ///
+    /// Errors are tagged with the name of the template they occurred in.
+    /// Partials named by a variable can not be resolved statically. They are
+    /// not part of the graph, but the templates that use them are recorded
+    /// as unresolved.
+    pub fn dependency_graph(
+        &self,
+        entries: &[&str],
+        loader: &dyn Loader,
+    ) -> Result<DependencyGraph, LiquidError> {
+        let mut graph = DependencyGraph {
+            entries: entries.iter().map(|name| name.to_string()).collect(),
+            dependencies: BTreeMap::new(),
+            unresolved: BTreeSet::new(),
+        };
+
+        let mut pending: Vec<String> = graph.entries.iter().cloned().collect();
+        while let Some(name) = pending.pop() {
+            if graph.dependencies.contains_key(&name) {
+                continue;
+            }
+            let template = self.load(&name, loader)?;
+            let mut names = Vec::new();
+            if partial_names(&template.nodes, &mut names) {
+                graph.unresolved.insert(name.clone());
+            }
+            pending.extend(names.iter().cloned());
+            graph.dependencies.insert(name, names.into_iter().collect());
+        }
+
+        Ok(graph)
+    }
+
+    fn load(&self, name: &str, loader: &dyn Loader) -> Result<Template, LiquidError> {
+        loader
+            .get_source(name)
+            .map_err(|err| err.with_source_name(name))
+            .and_then(|source| self.parse_named(&source, name))
+    }
+
+    async fn load_async(
+        &self,
+        name: &str,
+        loader: &dyn AsyncLoader,
+    ) -> Result<Template, LiquidError> {
+        loader
+            .get_source_async(name)
+            .await
+            .map_err(|err| err.with_source_name(name))
+            .and_then(|source| self.parse_named(&source, name))
+    }
+}
+
+/// The partial templates used by a set of entry templates, for finding which
+/// entry templates need to be rendered again when a template changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    entries: BTreeSet<String>,
+    /// The names of the templates each loaded template includes, renders or
+    /// extends by string literal name.
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+    /// The names of loaded templates that include or render a partial
+    /// template named by a variable.
+    unresolved: BTreeSet<String>,
+}
+
+impl DependencyGraph {
+    /// Names of the entry templates, sorted.
+    pub fn entries(&self) -> Vec<&str> {
+        self.entries.iter().map(String::as_str).collect()
+    }
+
+    /// Names of the templates that _name_ uses directly, sorted.
+    pub fn dependencies(&self, name: &str) -> Vec<&str> {
+        self.dependencies
+            .get(name)
+            .map(|names| names.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Names of the templates that include or render a partial template
+    /// named by a variable, sorted. Any template could be one of their
+    /// dependencies.
+    pub fn unresolved(&self) -> Vec<&str> {
+        self.unresolved.iter().map(String::as_str).collect()
+    }
+
+    /// Names of the templates that use _name_, directly or indirectly
+    /// through other partial templates, sorted.
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        let mut found: BTreeSet<&str> = BTreeSet::new();
+        let mut pending = vec![name];
+        while let Some(target) = pending.pop() {
+            for (template, names) in &self.dependencies {
+                if names.contains(target) && found.insert(template) {
+                    pending.push(template);
+                }
+            }
+        }
+        // A template that uses itself through a cycle is not its own
+        // dependent.
+        found.remove(name);
+        found.into_iter().collect()
+    }
+
+    /// Names of the entry templates that need to be rendered again when the
+    /// template called _name_ changes, sorted. That's every entry template
+    /// that uses _name_, directly or indirectly, and _name_ itself if it is
+    /// an entry template.
+    ///
+    /// Entry templates that are or use an unresolved template might use any
+    /// template, so they are affected by every change.
+    pub fn affected_entries(&self, name: &str) -> Vec<&str> {
+        let mut dependents: BTreeSet<&str> = self.dependents(name).into_iter().collect();
+        for unresolved in &self.unresolved {
+            dependents.insert(unresolved);
+            dependents.extend(self.dependents(unresolved));
+        }
+        self.entries
+            .iter()
+            .map(String::as_str)
+            .filter(|entry| *entry == name || dependents.contains(entry))
+            .collect()
+    }
+}
+
+/// Append the names of partial templates referenced by string literal in
+/// _nodes_ to _names_.
+pub(crate) fn static_partial_names(nodes: &[Node], names: &mut Vec<String>) {
+    partial_names(nodes, names);
+}
+
+/// Like `static_partial_names`, but return `true` if _nodes_ include or
+/// render a partial template named by a variable.
+fn partial_names(nodes: &[Node], names: &mut Vec<String>) -> bool {
+    let mut dynamic = false;
+    for node in nodes {
+        match node {
+            Node::IncludeTag {
+                target: Primitive::StringLiteral { value, .. },
+                ..
+            }
+            | Node::RenderTag {
+                target: Primitive::StringLiteral { value, .. },
+                ..
+            } => names.push(value.to_owned()),
+            Node::ExtendsTag { name, .. } => names.push(name.to_owned()),
+            Node::IncludeTag { .. } | Node::RenderTag { .. } => dynamic = true,
+            Node::CaptureTag { block, .. }
+            | Node::LiquidTag { block, .. }
+            | Node::BlockTag { block, .. }
+            | Node::MacroTag { block, .. }
+            | Node::TablerowTag { block, .. }
+            | Node::IfchangedTag { block, .. } => dynamic |= partial_names(block, names),
+            Node::CaseTag { whens, default, .. } => {
+                for when in whens {
+                    dynamic |= partial_names(&when.block, names);
+                }
+                if let Some(default) = default {
+                    dynamic |= partial_names(&default.block, names);
+                }
+            }
+            Node::ForTag { block, default, .. } => {
+                dynamic |= partial_names(block, names);
+                if let Some(default) = default {
+                    dynamic |= partial_names(&default.block, names);
+                }
+            }
+            Node::IfTag {
+                block,
+                alternatives,
+                default,
+                ..
+            }
+            | Node::UnlessTag {
+                block,
+                alternatives,
+                default,
+                ..
+            } => {
+                dynamic |= partial_names(block, names);
+                for alternative in alternatives {
+                    dynamic |= partial_names(&alternative.block, names);
+                }
+                if let Some(default) = default {
+                    dynamic |= partial_names(&default.block, names);
+                }
+            }
+            Node::TranslateTag { block, plural, .. } => {
+                dynamic |= partial_names(block, names);
+                if let Some(plural) = plural {
+                    dynamic |= partial_names(&plural.block, names);
+                }
+            }
+            Node::TagExtension {
+                block, branches, ..
+            } => {
+                dynamic |= partial_names(block, names);
+                for branch in branches {
+                    dynamic |= partial_names(&branch.block, names);
+                }
+            }
+            _ => (),
+        }
+    }
+    dynamic
+}
@@ -0,0 +1,181 @@
+//! Describe pest parsing errors in Liquid terms.
+//!
+//! pest reports the grammar rules it expected at the failing position, like
+//! `expected symbol, reserved_word, range, word, int, or
+//! implicit_root_segment`. Rule names are an implementation detail of
+//! `markup.pest`, so we translate them to words a template author would
+//! recognize, and mention the closing delimiter of any unclosed markup.
+
+use pest::error::{Error, ErrorVariant, InputLocation};
+
+use crate::lexer::Rule;
+
+/// Return a human readable message for _err_, which occurred while parsing
+/// _source_.
+pub fn message(err: &Error<Rule>, source: &str) -> String {
+    let pos = match err.location {
+        InputLocation::Pos(pos) => pos,
+        InputLocation::Span((start, _)) => start,
+    };
+
+    match &err.variant {
+        ErrorVariant::CustomError { message } => message.to_owned(),
+        ErrorVariant::ParsingError {
+            positives,
+            negatives,
+        } => {
+            let closer = unclosed_markup(source, pos);
+            if closer == Some("#}") {
+                return "unclosed comment, expected '#}'".to_owned();
+            }
+
+            let mut expected: Vec<&str> = Vec::new();
+            for rule in positives {
+                let term = describe(rule);
+                if !expected.contains(&term) {
+                    expected.push(term);
+                }
+            }
+
+            if let Some(closer) = closer {
+                if positives.iter().any(is_expression_token) {
+                    expected.push(if closer == "}}" { "'}}'" } else { "'%}'" });
+                }
+            }
+
+            if expected.is_empty() {
+                let unexpected: Vec<&str> = negatives.iter().map(describe).collect();
+                if unexpected.is_empty() {
+                    format!("unexpected {}", found(source, pos))
+                } else {
+                    format!("unexpected {}", one_of(&unexpected))
+                }
+            } else {
+                format!(
+                    "expected {}, found {}",
+                    one_of(&expected),
+                    found(source, pos)
+                )
+            }
+        }
+    }
+}
+
+fn describe(rule: &Rule) -> &'static str {
+    match rule {
+        Rule::EOI => "end of input",
+        Rule::WC => "whitespace control",
+        Rule::raw | Rule::raw_content => "a raw block",
+        Rule::comment
+        | Rule::comment_hashes
+        | Rule::comment_text
+        | Rule::block_comment
+        | Rule::block_comment_text
+        | Rule::inline_comment
+        | Rule::inline_comment_text => "a comment",
+        Rule::doc | Rule::doc_content => "a doc block",
+        Rule::tag | Rule::liquid_tag | Rule::jekyll_include => "a tag",
+        Rule::jekyll_filename => "a file name",
+        Rule::tag_name => "a tag name",
+        Rule::output => "an output statement",
+        Rule::content => "template content",
+        Rule::line_statements | Rule::line_tag => "a tag",
+        Rule::line_comment | Rule::line_comment_text => "a comment",
+        Rule::symbol => "an operator",
+        Rule::comparison_op => "a comparison operator",
+        Rule::reserved_word => "a keyword",
+        Rule::range => "a range",
+        Rule::word
+        | Rule::query
+        | Rule::range_query
+        | Rule::implicit_root_segment
+        | Rule::member_name_shorthand
+        | Rule::implicit_root_name_segment => "a name",
+        Rule::int | Rule::number | Rule::frac | Rule::exp => "a number",
+        Rule::index_selector | Rule::index_segment => "an index",
+        Rule::start | Rule::stop | Rule::step | Rule::slice_selector => "a slice",
+        Rule::multiline_double_quoted
+        | Rule::multiline_single_quoted
+        | Rule::double_quoted
+        | Rule::single_quoted => "a string",
+        Rule::wildcard_selector => "'*'",
+        Rule::bracketed_selection => "'['",
+        Rule::child_segment | Rule::name_segment => "'.' or '['",
+        Rule::descendant_segment => "'..'",
+        Rule::filter_selector => "a filter selector",
+        Rule::singular_query_selector
+        | Rule::singular_query_segments
+        | Rule::singular_query_segment
+        | Rule::rel_singular_query
+        | Rule::abs_singular_query
+        | Rule::rel_query
+        | Rule::root_query => "a query",
+        Rule::true_literal => "'true'",
+        Rule::false_literal => "'false'",
+        Rule::null => "'null'",
+        Rule::function_name | Rule::function_expr => "a function call",
+        Rule::logical_not_op => "'!'",
+        Rule::logical_or_expr
+        | Rule::logical_and_expr
+        | Rule::paren_expr
+        | Rule::test_expr
+        | Rule::comparison_expr => "a filter expression",
+        _ => "valid syntax",
+    }
+}
+
+/// Rules that can start the next token of a tag or output expression.
+fn is_expression_token(rule: &Rule) -> bool {
+    matches!(
+        rule,
+        Rule::symbol
+            | Rule::reserved_word
+            | Rule::range
+            | Rule::word
+            | Rule::query
+            | Rule::int
+            | Rule::number
+            | Rule::implicit_root_segment
+    )
+}
+
+/// If _pos_ is inside markup that has not been closed, return the delimiter
+/// that would close it.
+fn unclosed_markup(source: &str, pos: usize) -> Option<&'static str> {
+    let before = &source[..floor_char_boundary(source, pos)];
+    let last = |pattern: &str| before.rfind(pattern).map_or(-1, |i| i as isize);
+
+    [("{{", "}}"), ("{%", "%}"), ("{#", "#}")]
+        .into_iter()
+        .map(|(open, close)| (last(open), close, last(close)))
+        .filter(|(open, _, close)| open > close)
+        .max_by_key(|(open, _, _)| *open)
+        .map(|(_, close, _)| close)
+}
+
+/// Describe the text at _pos_ for the end of an error message.
+fn found(source: &str, pos: usize) -> String {
+    match source[floor_char_boundary(source, pos)..].chars().next() {
+        None => "end of input".to_owned(),
+        Some('\n') => "a newline".to_owned(),
+        Some(ch) if ch.is_whitespace() => "whitespace".to_owned(),
+        Some(ch) => format!("'{ch}'"),
+    }
+}
+
+/// Format _terms_ like `a, b or c`.
+fn one_of(terms: &[&str]) -> String {
+    match terms.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        _ => terms.join(""),
+    }
+}
+
+/// Round _index_ down to the nearest character boundary in _source_.
+pub(crate) fn floor_char_boundary(source: &str, index: usize) -> usize {
+    let mut index = index.min(source.len());
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
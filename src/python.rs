@@ -0,0 +1,1453 @@
+//! Python bindings, built with the `python` feature.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+use pyo3::prelude::*;
+use pyo3::types::{PyString, PyTuple};
+
+use crate::ast::{self, BooleanExpression, FilteredExpression, KeywordArgument};
+use crate::dialect::Dialect;
+use crate::errors::{self, LiquidError};
+use crate::identifier::IdentifierPolicy;
+use crate::lexer::{self, ExpressionType, FunctionSignature, Lexer, QueryExtensions};
+use crate::loader::DictLoader;
+use crate::markup::{self, Markup, Token};
+use crate::query::cache::QueryCache;
+use crate::query::eval::{FunctionExtension, FunctionExtensions, FunctionValue};
+use crate::query::{self, Query};
+use crate::span::{self, Span};
+use crate::{
+    analysis, audit, completion, cst, diagnostics, estimate, grammar, json, lint, metadata,
+    outline, parser, render, semantic, source_map, token_stream, unescape, value, whitespace,
+};
+
+lazy_static! {
+    /// Filter functions registered from Python, shared by every lexer we
+    /// create on Python's behalf.
+    static ref FUNCTIONS: RwLock<HashMap<String, FunctionSignature>> =
+        RwLock::new(HashMap::new());
+
+    /// Implementations of the functions in `FUNCTIONS` that were registered
+    /// with one.
+    static ref FUNCTION_EXTENSIONS: RwLock<FunctionExtensions> = RwLock::new(HashMap::new());
+
+    /// The lexer and parser used by module functions called without
+    /// options. They are built on first use and dropped when a function is
+    /// registered, so they are rebuilt with it.
+    static ref DEFAULTS: RwLock<Option<(Arc<Lexer>, Arc<parser::LiquidParser>)>> =
+        RwLock::new(None);
+}
+
+fn lexer(extensions: Option<QueryExtensions>) -> Lexer {
+    let mut lexer = Lexer::with_query_extensions(extensions.unwrap_or_default());
+    let functions = FUNCTIONS.read().unwrap();
+    if !functions.is_empty() {
+        Arc::make_mut(&mut lexer.query_parser.functions).extend(
+            functions
+                .iter()
+                .map(|(name, signature)| (name.to_owned(), signature.clone())),
+        );
+    }
+    let extensions = FUNCTION_EXTENSIONS.read().unwrap();
+    if !extensions.is_empty() {
+        lexer.query_parser.function_extensions = Arc::new(extensions.clone());
+    }
+    lexer
+}
+
+fn parser(extensions: Option<QueryExtensions>) -> parser::LiquidParser {
+    parser::LiquidParser {
+        lexer: lexer(extensions),
+        ..parser::LiquidParser::new()
+    }
+}
+
+fn defaults() -> (Arc<Lexer>, Arc<parser::LiquidParser>) {
+    if let Some(defaults) = DEFAULTS.read().unwrap().as_ref() {
+        return defaults.clone();
+    }
+    DEFAULTS
+        .write()
+        .unwrap()
+        .get_or_insert_with(|| {
+            let mut parser = parser(None);
+            LexerOptions::default().configure(&mut parser.lexer);
+            (Arc::new(parser.lexer.clone()), Arc::new(parser))
+        })
+        .clone()
+}
+
+/// The shared parser with default options and registered functions.
+pub(crate) fn default_parser() -> Arc<parser::LiquidParser> {
+    defaults().1
+}
+
+/// Implementations of functions registered with `register_function`.
+pub(crate) fn function_extensions() -> Arc<FunctionExtensions> {
+    Arc::clone(&defaults().0.query_parser.function_extensions)
+}
+
+/// The number of queries cached by lexers built on Python's behalf, unless
+/// given.
+const QUERY_CACHE_SIZE: usize = 1024;
+
+/// Options for a lexer built on Python's behalf. Lexers with default
+/// options are shared.
+#[derive(PartialEq)]
+struct LexerOptions {
+    extensions: Option<QueryExtensions>,
+    identifiers: IdentifierPolicy,
+    raw_spans: bool,
+    dialect: Option<Dialect>,
+    lax_ranges: bool,
+    max_descendant_segments: Option<usize>,
+    max_filter_depth: Option<usize>,
+    query_cache_size: usize,
+}
+
+impl Default for LexerOptions {
+    fn default() -> Self {
+        Self {
+            extensions: None,
+            identifiers: IdentifierPolicy::default(),
+            raw_spans: false,
+            dialect: None,
+            lax_ranges: false,
+            max_descendant_segments: None,
+            max_filter_depth: None,
+            query_cache_size: QUERY_CACHE_SIZE,
+        }
+    }
+}
+
+impl LexerOptions {
+    fn extensions(extensions: Option<QueryExtensions>) -> Self {
+        Self {
+            extensions,
+            ..Self::default()
+        }
+    }
+
+    fn configure(&self, lexer: &mut Lexer) {
+        lexer.query_parser.identifiers = self.identifiers;
+        lexer.query_parser.max_descendant_segments = self.max_descendant_segments;
+        lexer.query_parser.max_filter_depth = self.max_filter_depth;
+        lexer.raw_spans = self.raw_spans;
+        lexer.dialect = self.dialect.unwrap_or_default();
+        lexer.dialect.lax_ranges |= self.lax_ranges;
+        lexer.query_parser.cache = Some(Arc::new(QueryCache::new(self.query_cache_size)));
+    }
+
+    fn build(self) -> PyLexer {
+        if self == Self::default() {
+            return PyLexer {
+                lexer: defaults().0,
+            };
+        }
+        let mut lexer = lexer(self.extensions);
+        self.configure(&mut lexer);
+        PyLexer {
+            lexer: Arc::new(lexer),
+        }
+    }
+}
+
+/// Options for a parser built on Python's behalf. Parsers with default
+/// options are shared.
+#[derive(PartialEq)]
+struct ParserOptions {
+    lexer: LexerOptions,
+    common_tags: bool,
+    strict_filters: bool,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    max_bytes: Option<usize>,
+    tag_aliases: HashMap<String, String>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            lexer: LexerOptions::default(),
+            common_tags: false,
+            strict_filters: true,
+            max_depth: None,
+            max_nodes: None,
+            max_bytes: None,
+            tag_aliases: HashMap::new(),
+        }
+    }
+}
+
+impl ParserOptions {
+    fn extensions(extensions: Option<QueryExtensions>) -> Self {
+        Self {
+            lexer: LexerOptions::extensions(extensions),
+            ..Self::default()
+        }
+    }
+
+    fn build(self) -> PyParser {
+        if self == Self::default() {
+            return PyParser {
+                parser: defaults().1,
+            };
+        }
+        let mut parser = parser::LiquidParser {
+            common_tags: self.common_tags,
+            max_depth: self.max_depth,
+            max_nodes: self.max_nodes,
+            max_bytes: self.max_bytes,
+            ..parser(self.lexer.extensions)
+        };
+        parser.filters.strict = self.strict_filters;
+        for (alias, canonical) in &self.tag_aliases {
+            parser.add_tag_alias(alias, canonical);
+        }
+        self.lexer.configure(&mut parser.lexer);
+        PyParser {
+            parser: Arc::new(parser),
+        }
+    }
+}
+
+/// A concise `repr()` for _obj_, like `Token.Word('a', span=(0, 1))`, built
+/// from its qualified class name, its _text_ and, if it has one, its _span_.
+///
+/// Complex enum variants are classes of their own, named like
+/// `Token_Word`, so the name includes the variant.
+pub(crate) fn repr(obj: &Bound<'_, PyAny>, text: &str, span: Option<Span>) -> PyResult<String> {
+    let name = obj.get_type().qualname()?.to_string().replacen('_', ".", 1);
+    if text.is_empty() && span.is_none() {
+        return Ok(format!("{name}()"));
+    }
+    let text = PyString::new_bound(obj.py(), text).repr()?;
+    Ok(match span {
+        Some(span) => format!("{name}({text}, span=({}, {}))", span.start, span.end),
+        None => format!("{name}({text})"),
+    })
+}
+
+/// Make a filter function called _name_ available to queries parsed from
+/// now on, by module functions and by lexers and parsers created from now
+/// on. Types are given by name, one of `"value"`, `"logical"` or
+/// `"nodes"`.
+///
+/// If _func_ is given, it is called with converted arguments when queries
+/// are evaluated. Nodes arguments are passed as a list of values and a
+/// missing value is passed as `None`. Exceptions raised by _func_ fail the
+/// evaluation with a `LiquidExtensionError`. Giving _func_ for one of the
+/// standard functions replaces the standard implementation.
+#[pyfunction]
+#[pyo3(signature = (name, param_types, return_type, func=None))]
+fn register_function(
+    name: &str,
+    param_types: Vec<String>,
+    return_type: &str,
+    func: Option<PyObject>,
+) -> Result<(), LiquidError> {
+    let param_types = param_types
+        .iter()
+        .map(|t| t.parse())
+        .collect::<Result<Vec<ExpressionType>, _>>()?;
+    let return_type = return_type.parse()?;
+
+    let mut extensions = FUNCTION_EXTENSIONS.write().unwrap();
+    match func {
+        Some(func) => {
+            let function_name = name.to_owned();
+            extensions.insert(
+                name.to_owned(),
+                FunctionExtension {
+                    param_types: param_types.clone(),
+                    return_type,
+                    func: Arc::new(move |args| {
+                        call_py_function(&function_name, &func, args, return_type)
+                    }),
+                },
+            );
+        }
+        None => {
+            extensions.remove(name);
+        }
+    }
+
+    FUNCTIONS.write().unwrap().insert(
+        name.to_owned(),
+        FunctionSignature {
+            param_types,
+            return_type,
+        },
+    );
+    *DEFAULTS.write().unwrap() = None;
+    Ok(())
+}
+
+/// Call _func_, the Python implementation of the function extension called
+/// _name_. Exceptions, including those raised converting its result, are
+/// returned as extension errors.
+fn call_py_function(
+    name: &str,
+    func: &PyObject,
+    args: Vec<FunctionValue>,
+    return_type: ExpressionType,
+) -> Result<FunctionValue, LiquidError> {
+    Python::with_gil(|py| {
+        let args: Vec<PyObject> = args
+            .into_iter()
+            .map(|arg| match arg {
+                FunctionValue::Nodes(values) => values
+                    .into_iter()
+                    .map(value::Value::from)
+                    .collect::<Vec<_>>()
+                    .into_py(py),
+                FunctionValue::Value(v) => value::Value::from(v).into_py(py),
+                FunctionValue::Logical(b) => b.into_py(py),
+                FunctionValue::Nothing => py.None(),
+            })
+            .collect();
+
+        let result = func
+            .bind(py)
+            .call1(PyTuple::new_bound(py, args))
+            .and_then(|rv| {
+                Ok(match return_type {
+                    ExpressionType::Logical => FunctionValue::Logical(rv.is_truthy()?),
+                    ExpressionType::Value => {
+                        FunctionValue::Value(rv.extract::<value::Value>()?.into())
+                    }
+                    ExpressionType::Nodes => FunctionValue::Nodes(
+                        rv.extract::<Vec<value::Value>>()?
+                            .into_iter()
+                            .map(serde_json::Value::from)
+                            .collect(),
+                    ),
+                })
+            });
+
+        result.map_err(|err| LiquidError::ext(format!("{name}() raised {err}")))
+    })
+}
+
+/// Attribute an error in _source_ to the template called _source_name_, if
+/// one was given.
+fn named<T>(
+    result: Result<T, LiquidError>,
+    source: &str,
+    source_name: Option<&str>,
+) -> Result<T, LiquidError> {
+    match source_name {
+        Some(name) => result.map_err(|err| err.with_source(name, source)),
+        None => result,
+    }
+}
+
+/// Tokenize _source_. _identifiers_ decides which characters are allowed in
+/// variable and member names. If _raw_spans_ is true, `Markup.Raw` records
+/// the spans of its tags and text. _dialect_ decides which non-standard
+/// syntax is accepted, and defaults to Liquid2. If _lax_ranges_ is true,
+/// float range arguments are truncated to integers instead of raising a type
+/// error, whatever the dialect.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    *,
+    extensions=None,
+    source_name=None,
+    identifiers=IdentifierPolicy::Unicode,
+    raw_spans=false,
+    dialect=None,
+    lax_ranges=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn tokenize(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    identifiers: IdentifierPolicy,
+    raw_spans: bool,
+    dialect: Option<Dialect>,
+    lax_ranges: bool,
+) -> Result<Vec<Markup>, LiquidError> {
+    LexerOptions {
+        extensions,
+        identifiers,
+        raw_spans,
+        dialect,
+        lax_ranges,
+        ..LexerOptions::default()
+    }
+    .build()
+    .tokenize(source, source_name)
+}
+
+/// Return an iterator over markup in _source_ that tokenizes one piece of
+/// markup at a time.
+#[pyfunction]
+#[pyo3(signature = (source, *, extensions=None))]
+fn iter_tokens(source: String, extensions: Option<QueryExtensions>) -> lexer::MarkupIterator {
+    LexerOptions::extensions(extensions)
+        .build()
+        .iter_tokens(source)
+}
+
+/// Tokenize _source_ without stopping at the first error, returning markup
+/// and a list of syntax errors. Markup that could not be tokenized is
+/// returned as `Markup.Error`. _raw_spans_ is as for `tokenize`.
+#[pyfunction]
+#[pyo3(signature = (source, *, extensions=None, source_name=None, raw_spans=false))]
+fn tokenize_lenient(
+    py: Python<'_>,
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    raw_spans: bool,
+) -> (Vec<Markup>, Vec<PyObject>) {
+    LexerOptions {
+        extensions,
+        raw_spans,
+        ..LexerOptions::default()
+    }
+    .build()
+    .tokenize_lenient(py, source, source_name)
+}
+
+/// Describe what could be typed at byte _offset_ in _source_, for
+/// autocompletion.
+#[pyfunction]
+#[pyo3(signature = (source, offset, *, extensions=None))]
+fn completion_context(
+    source: &str,
+    offset: usize,
+    extensions: Option<QueryExtensions>,
+) -> completion::CompletionContext {
+    LexerOptions {
+        extensions,
+        ..LexerOptions::default()
+    }
+    .build()
+    .lexer
+    .completion_context(source, offset)
+}
+
+/// Tokenize many templates on a thread pool, without holding the GIL.
+///
+/// Results are returned in the same order as _sources_. If any source fails
+/// to tokenize, the error for the first such source is raised.
+#[pyfunction]
+#[pyo3(signature = (sources, *, extensions=None))]
+fn tokenize_many(
+    py: Python<'_>,
+    sources: Vec<String>,
+    extensions: Option<QueryExtensions>,
+) -> Result<Vec<Vec<Markup>>, LiquidError> {
+    LexerOptions::extensions(extensions)
+        .build()
+        .tokenize_many(py, sources)
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    path,
+    *,
+    extensions=None,
+    source_name=None,
+    identifiers=IdentifierPolicy::Unicode,
+))]
+fn parse_query(
+    path: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    identifiers: IdentifierPolicy,
+) -> Result<Query, LiquidError> {
+    LexerOptions {
+        extensions,
+        identifiers,
+        ..LexerOptions::default()
+    }
+    .build()
+    .parse_query(path, source_name)
+}
+
+/// Parse _source_ into a syntax tree. If _common_tags_ is true, unknown tags
+/// are parsed into `TagExtension` nodes instead of raising an error. If
+/// _strict_filters_ is false, unknown filters are accepted too.
+///
+/// _max_depth_, _max_nodes_ and _max_bytes_ limit block nesting, the total
+/// number of nodes and the length of _source_, respectively. _identifiers_
+/// decides which characters are allowed in variable and member names, and
+/// _dialect_ and _lax_ranges_ are as for `tokenize`.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    *,
+    extensions=None,
+    source_name=None,
+    common_tags=false,
+    strict_filters=true,
+    max_depth=None,
+    max_nodes=None,
+    max_bytes=None,
+    identifiers=IdentifierPolicy::Unicode,
+    dialect=None,
+    lax_ranges=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn parse(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    common_tags: bool,
+    strict_filters: bool,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    max_bytes: Option<usize>,
+    identifiers: IdentifierPolicy,
+    dialect: Option<Dialect>,
+    lax_ranges: bool,
+) -> Result<ast::Template, LiquidError> {
+    ParserOptions {
+        lexer: LexerOptions {
+            extensions,
+            identifiers,
+            dialect,
+            lax_ranges,
+            ..LexerOptions::default()
+        },
+        common_tags,
+        strict_filters,
+        max_depth,
+        max_nodes,
+        max_bytes,
+        ..ParserOptions::default()
+    }
+    .build()
+    .parse(source, source_name)
+}
+
+/// Parse many templates on a thread pool, without holding the GIL. Options
+/// are as for `parse`.
+///
+/// Results are returned in the same order as _sources_. If any source fails
+/// to parse, the error for the first such source is raised.
+#[pyfunction]
+#[pyo3(signature = (
+    sources,
+    *,
+    extensions=None,
+    common_tags=false,
+    strict_filters=true,
+    max_depth=None,
+    max_nodes=None,
+    max_bytes=None,
+    identifiers=IdentifierPolicy::Unicode,
+    dialect=None,
+    lax_ranges=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn parse_many(
+    py: Python<'_>,
+    sources: Vec<String>,
+    extensions: Option<QueryExtensions>,
+    common_tags: bool,
+    strict_filters: bool,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    max_bytes: Option<usize>,
+    identifiers: IdentifierPolicy,
+    dialect: Option<Dialect>,
+    lax_ranges: bool,
+) -> Result<Vec<ast::Template>, LiquidError> {
+    ParserOptions {
+        lexer: LexerOptions {
+            extensions,
+            identifiers,
+            dialect,
+            lax_ranges,
+            ..LexerOptions::default()
+        },
+        common_tags,
+        strict_filters,
+        max_depth,
+        max_nodes,
+        max_bytes,
+        ..ParserOptions::default()
+    }
+    .build()
+    .parse_many(py, sources)
+}
+
+/// Parse _source_ into a `ConcreteTemplate`, which keeps the source text
+/// of every node and can reproduce _source_ exactly.
+#[pyfunction]
+#[pyo3(signature = (source, *, extensions=None, source_name=None))]
+fn parse_lossless(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+) -> Result<cst::ConcreteTemplate, LiquidError> {
+    ParserOptions::extensions(extensions)
+        .build()
+        .parse_lossless(source, source_name)
+}
+
+/// Parse _source_ and call _f_ with a renderer configured with the options
+/// shared by `render` and its variants, and with _data_ as JSON.
+#[allow(clippy::too_many_arguments)]
+fn render_source<T>(
+    source: &str,
+    data: Option<value::Value>,
+    templates: Option<HashMap<String, String>>,
+    source_name: Option<&str>,
+    trim: whitespace::Whitespace,
+    smart: whitespace::SmartTrim,
+    default_loop_limit: Option<usize>,
+    f: impl FnOnce(&render::Renderer, &serde_json::Value) -> Result<T, LiquidError>,
+) -> Result<T, LiquidError> {
+    let data = serde_json::Value::from(data.unwrap_or_default());
+    let template = named(default_parser().parse(source), source, source_name)?;
+    let loader = DictLoader::new(templates.unwrap_or_default());
+    let mut renderer = render::Renderer::new(&template).with_loader(&loader);
+    renderer.function_extensions = function_extensions();
+    renderer.trim = trim;
+    renderer.smart = smart;
+    renderer.default_loop_limit = default_loop_limit;
+    named(f(&renderer, &data), source, source_name)
+}
+
+/// Parse and render _source_ with the built-in renderer. _data_ is a mapping
+/// of global template variables, and _templates_ maps the names of partial
+/// templates used by `include` and `render` tags to their source text.
+///
+/// _trim_ is the whitespace control applied to markup that does not specify
+/// its own, and _smart_ is what `~` whitespace control does.
+/// _default_loop_limit_ is the most items a `for` or `tablerow` loop renders,
+/// unless it gives a smaller `limit`.
+#[pyfunction]
+#[pyo3(name = "render", signature = (
+    source,
+    data=None,
+    *,
+    templates=None,
+    source_name=None,
+    trim=whitespace::Whitespace::Plus,
+    smart=whitespace::SmartTrim::Newlines,
+    default_loop_limit=None,
+))]
+fn py_render(
+    source: &str,
+    data: Option<value::Value>,
+    templates: Option<HashMap<String, String>>,
+    source_name: Option<&str>,
+    trim: whitespace::Whitespace,
+    smart: whitespace::SmartTrim,
+    default_loop_limit: Option<usize>,
+) -> Result<String, LiquidError> {
+    render_source(
+        source,
+        data,
+        templates,
+        source_name,
+        trim,
+        smart,
+        default_loop_limit,
+        |renderer, data| renderer.render(data),
+    )
+}
+
+/// Like `render`, but also return a source map from the output back to the
+/// spans of the nodes that wrote it.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    data=None,
+    *,
+    templates=None,
+    source_name=None,
+    trim=whitespace::Whitespace::Plus,
+    smart=whitespace::SmartTrim::Newlines,
+    default_loop_limit=None,
+))]
+fn render_with_source_map(
+    source: &str,
+    data: Option<value::Value>,
+    templates: Option<HashMap<String, String>>,
+    source_name: Option<&str>,
+    trim: whitespace::Whitespace,
+    smart: whitespace::SmartTrim,
+    default_loop_limit: Option<usize>,
+) -> Result<(String, source_map::SourceMap), LiquidError> {
+    render_source(
+        source,
+        data,
+        templates,
+        source_name,
+        trim,
+        smart,
+        default_loop_limit,
+        |renderer, data| renderer.render_with_source_map(data),
+    )
+}
+
+/// Like `render`, but also return every loop that _default_loop_limit_ cut
+/// short.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    data=None,
+    *,
+    templates=None,
+    source_name=None,
+    trim=whitespace::Whitespace::Plus,
+    smart=whitespace::SmartTrim::Newlines,
+    default_loop_limit=None,
+))]
+fn render_with_truncated_loops(
+    source: &str,
+    data: Option<value::Value>,
+    templates: Option<HashMap<String, String>>,
+    source_name: Option<&str>,
+    trim: whitespace::Whitespace,
+    smart: whitespace::SmartTrim,
+    default_loop_limit: Option<usize>,
+) -> Result<(String, Vec<render::TruncatedLoop>), LiquidError> {
+    render_source(
+        source,
+        data,
+        templates,
+        source_name,
+        trim,
+        smart,
+        default_loop_limit,
+        |renderer, data| renderer.render_with_truncated_loops(data),
+    )
+}
+
+/// Tokenize _source_ and return its markup with the text of each
+/// `Markup.Content` replaced by the text that would be output, after
+/// whitespace control. _trim_ and _smart_ are as for `render`.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    *,
+    trim=whitespace::Whitespace::Plus,
+    smart=whitespace::SmartTrim::Newlines,
+))]
+fn resolve_whitespace(
+    source: &str,
+    trim: whitespace::Whitespace,
+    smart: whitespace::SmartTrim,
+) -> Result<Vec<Markup>, LiquidError> {
+    let markup = defaults().0.tokenize(source)?;
+    Ok(whitespace::resolve_whitespace(
+        &markup,
+        &whitespace::TrimPolicy {
+            default: trim,
+            smart,
+        },
+    ))
+}
+
+/// Parse _source_ and return a JSON array of diagnostics describing any
+/// error, or an empty array if the template is valid.
+#[pyfunction]
+#[pyo3(signature = (source, *, extensions=None, source_name=None))]
+fn diagnose(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+) -> Result<String, json::JsonError> {
+    ParserOptions::extensions(extensions)
+        .build()
+        .diagnose(source, source_name)
+}
+
+/// Check _source_ without building a syntax tree and return a JSON array of
+/// diagnostics, one for each error found. Block tags, tag names, filter
+/// names and query function calls are checked, but tag expressions are not
+/// parsed, so use `diagnose` for a complete check.
+#[pyfunction]
+#[pyo3(signature = (
+    source,
+    *,
+    extensions=None,
+    source_name=None,
+    common_tags=false,
+    strict_filters=true,
+    dialect=None,
+))]
+fn validate(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    common_tags: bool,
+    strict_filters: bool,
+    dialect: Option<Dialect>,
+) -> Result<String, json::JsonError> {
+    ParserOptions {
+        lexer: LexerOptions {
+            extensions,
+            dialect,
+            ..LexerOptions::default()
+        },
+        common_tags,
+        strict_filters,
+        ..ParserOptions::default()
+    }
+    .build()
+    .validate(source, source_name)
+}
+
+/// Parse _source_ and return a JSON array of diagnostics from the built-in
+/// lint rules, or a single error diagnostic if the template is not valid.
+///
+/// Rules named in _disable_ are skipped. _filters_ are accepted by the
+/// `undefined-filter` rule in addition to the standard filters. _dialect_ is
+/// as for `parse`, and unreachable branches accepted by its
+/// `unreachable_branches` option are reported by the `unreachable-branch`
+/// rule.
+#[pyfunction]
+#[pyo3(name = "lint", signature = (
+    source,
+    *,
+    extensions=None,
+    source_name=None,
+    disable=None,
+    filters=None,
+    dialect=None,
+))]
+fn lint_template(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    disable: Option<Vec<String>>,
+    filters: Option<Vec<String>>,
+    dialect: Option<Dialect>,
+) -> Result<String, json::JsonError> {
+    // Unknown filters are reported by the `undefined-filter` rule instead.
+    let mut parser = parser(extensions);
+    parser.filters.strict = false;
+    parser.lexer.dialect = dialect.unwrap_or_default();
+
+    let (template, warnings) = match named(parser.parse_with_warnings(source), source, source_name)
+    {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            return Ok(json::to_json(&[diagnostics::Diagnostic::from_error(
+                &err, source,
+            )])?)
+        }
+    };
+
+    let mut linter = lint::Linter::default();
+    if let Some(filters) = filters {
+        let mut rule = lint::UndefinedFilter::default();
+        rule.filters.extend(filters);
+        linter.add_rule(Box::new(rule));
+    }
+    for name in disable.iter().flatten() {
+        linter.remove_rule(name);
+    }
+
+    let mut lints = linter.lint(&template);
+    lints.extend(
+        warnings
+            .into_iter()
+            .filter(|lint| !disable.iter().flatten().any(|name| name == lint.rule)),
+    );
+    lints.sort_by_key(|lint| (lint.span.start, lint.span.end));
+
+    let diagnostics: Vec<diagnostics::Diagnostic> = lints
+        .iter()
+        .map(|lint| diagnostics::Diagnostic::from_lint(lint, source, source_name))
+        .collect();
+    Ok(json::to_json(&diagnostics)?)
+}
+
+/// Parse _source_ and return a JSON array of error diagnostics for anything
+/// the audit policy doesn't allow, or a single error diagnostic if the
+/// template is not valid. An empty array means the template passed.
+///
+/// Tags named in _denied_tags_ and filters named in _denied_filters_ are
+/// reported wherever they're used. _max_iterations_, _max_depth_ and
+/// _dynamic_partials_ default to the values of `AuditPolicy::default()`.
+#[pyfunction]
+#[pyo3(name = "audit", signature = (
+    source,
+    *,
+    extensions=None,
+    source_name=None,
+    denied_tags=None,
+    denied_filters=None,
+    max_iterations=None,
+    max_depth=None,
+    dynamic_partials=false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn audit_template(
+    source: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    denied_tags: Option<Vec<String>>,
+    denied_filters: Option<Vec<String>>,
+    max_iterations: Option<u64>,
+    max_depth: Option<usize>,
+    dynamic_partials: bool,
+) -> Result<String, json::JsonError> {
+    // Denied filters are reported even if the environment doesn't have them.
+    let mut parser = parser(extensions);
+    parser.filters.strict = false;
+
+    let template = match named(parser.parse(source), source, source_name) {
+        Ok(template) => template,
+        Err(err) => {
+            return Ok(json::to_json(&[diagnostics::Diagnostic::from_error(
+                &err, source,
+            )])?)
+        }
+    };
+
+    let default = audit::AuditPolicy::default();
+    let policy = audit::AuditPolicy {
+        denied_tags: denied_tags.into_iter().flatten().collect(),
+        denied_filters: denied_filters.into_iter().flatten().collect(),
+        max_iterations: max_iterations.unwrap_or(default.max_iterations),
+        max_depth: max_depth.unwrap_or(default.max_depth),
+        dynamic_partials,
+    };
+
+    let diagnostics: Vec<diagnostics::Diagnostic> = policy
+        .audit(&template)
+        .iter()
+        .map(|finding| diagnostics::Diagnostic::from_finding(finding, source, source_name))
+        .collect();
+    Ok(json::to_json(&diagnostics)?)
+}
+
+/// The source text of an expression, or tokens from the lexer, like the
+/// expression of a custom tag.
+#[derive(FromPyObject)]
+enum Expression {
+    Source(String),
+    Tokens(Vec<Token>),
+}
+
+impl Expression {
+    /// Tokenize source text with _parser_'s lexer, then call _parse_ with the
+    /// tokens. Errors are attributed to _source_name_ if the expression is
+    /// source text.
+    fn parse<T>(
+        &self,
+        parser: &parser::LiquidParser,
+        source_name: Option<&str>,
+        parse: impl FnOnce(&parser::LiquidParser, &[Token]) -> Result<T, LiquidError>,
+    ) -> Result<T, LiquidError> {
+        match self {
+            Expression::Source(source) => named(
+                parser
+                    .lexer
+                    .tokenize_expression(source)
+                    .and_then(|tokens| parse(parser, &tokens)),
+                source,
+                source_name,
+            ),
+            Expression::Tokens(tokens) => parse(parser, tokens),
+        }
+    }
+}
+
+/// Parse a filtered expression, as found in an output statement, from source
+/// text or a list of tokens.
+#[pyfunction]
+#[pyo3(signature = (source, *, source_name=None))]
+fn parse_filtered_expression(
+    source: Expression,
+    source_name: Option<&str>,
+) -> Result<FilteredExpression, LiquidError> {
+    ParserOptions::default()
+        .build()
+        .parse_filtered_expression(source, source_name)
+}
+
+/// Parse a boolean expression, as found in an `if` tag, from source text or
+/// a list of tokens.
+#[pyfunction]
+#[pyo3(signature = (source, *, source_name=None))]
+fn parse_boolean_expression(
+    source: Expression,
+    source_name: Option<&str>,
+) -> Result<BooleanExpression, LiquidError> {
+    ParserOptions::default()
+        .build()
+        .parse_boolean_expression(source, source_name)
+}
+
+/// Parse comma separated keyword arguments, like `a: 1, b: x`, from source
+/// text or a list of tokens.
+#[pyfunction]
+#[pyo3(signature = (source, *, source_name=None))]
+fn parse_keyword_arguments(
+    source: Expression,
+    source_name: Option<&str>,
+) -> Result<Vec<KeywordArgument>, LiquidError> {
+    ParserOptions::default()
+        .build()
+        .parse_keyword_arguments(source, source_name)
+}
+
+/// Parse a standalone JSONPath query. If _strict_ is true, every query
+/// extension is disabled and _extensions_ is ignored, so only RFC 9535
+/// queries are accepted. Queries with more descendant segments than
+/// _max_descendant_segments_, or filters nested deeper than
+/// _max_filter_depth_, are rejected.
+#[pyfunction]
+#[pyo3(signature = (
+    path,
+    *,
+    extensions=None,
+    source_name=None,
+    strict=false,
+    max_descendant_segments=None,
+    max_filter_depth=None,
+))]
+fn parse_jsonpath_query(
+    path: &str,
+    extensions: Option<QueryExtensions>,
+    source_name: Option<&str>,
+    strict: bool,
+    max_descendant_segments: Option<usize>,
+    max_filter_depth: Option<usize>,
+) -> Result<Query, LiquidError> {
+    let extensions = if strict {
+        Some(QueryExtensions::strict())
+    } else {
+        extensions
+    };
+    LexerOptions {
+        extensions,
+        max_descendant_segments,
+        max_filter_depth,
+        ..LexerOptions::default()
+    }
+    .build()
+    .parse_jsonpath_query(path, source_name)
+}
+
+/// A lexer, configured once and reused for many templates and queries.
+///
+/// Lexers are immutable and can be shared between threads. Query functions
+/// registered with `register_function` after a lexer is created are not
+/// known to it.
+#[pyclass(name = "Lexer", frozen)]
+struct PyLexer {
+    lexer: Arc<Lexer>,
+}
+
+#[pymethods]
+impl PyLexer {
+    /// Options are as for `tokenize` and `parse_jsonpath_query`. Up to
+    /// _query_cache_size_ queries parsed by `parse_query` are cached.
+    #[new]
+    #[pyo3(signature = (
+        *,
+        extensions=None,
+        identifiers=IdentifierPolicy::Unicode,
+        raw_spans=false,
+        dialect=None,
+        lax_ranges=false,
+        max_descendant_segments=None,
+        max_filter_depth=None,
+        query_cache_size=QUERY_CACHE_SIZE,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        extensions: Option<QueryExtensions>,
+        identifiers: IdentifierPolicy,
+        raw_spans: bool,
+        dialect: Option<Dialect>,
+        lax_ranges: bool,
+        max_descendant_segments: Option<usize>,
+        max_filter_depth: Option<usize>,
+        query_cache_size: usize,
+    ) -> Self {
+        LexerOptions {
+            extensions,
+            identifiers,
+            raw_spans,
+            dialect,
+            lax_ranges,
+            max_descendant_segments,
+            max_filter_depth,
+            query_cache_size,
+        }
+        .build()
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn tokenize(
+        &self,
+        source: &str,
+        source_name: Option<&str>,
+    ) -> Result<Vec<Markup>, LiquidError> {
+        named(self.lexer.tokenize(source), source, source_name)
+    }
+
+    fn iter_tokens(&self, source: String) -> lexer::MarkupIterator {
+        lexer::MarkupIterator::new(Arc::clone(&self.lexer), source)
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn tokenize_lenient(
+        &self,
+        py: Python<'_>,
+        source: &str,
+        source_name: Option<&str>,
+    ) -> (Vec<Markup>, Vec<PyObject>) {
+        let (markup, errors) = self.lexer.tokenize_lenient(source);
+        let errors = errors
+            .into_iter()
+            .map(|err| match source_name {
+                Some(name) => err.with_source(name, source),
+                None => err,
+            })
+            .map(|err| PyErr::from(err).into_value(py).into_any())
+            .collect();
+        (markup, errors)
+    }
+
+    fn tokenize_many(
+        &self,
+        py: Python<'_>,
+        sources: Vec<String>,
+    ) -> Result<Vec<Vec<Markup>>, LiquidError> {
+        let lexer = &self.lexer;
+        py.allow_threads(|| lexer.tokenize_many(&sources).into_iter().collect())
+    }
+
+    fn completion_context(&self, source: &str, offset: usize) -> completion::CompletionContext {
+        self.lexer.completion_context(source, offset)
+    }
+
+    #[pyo3(signature = (path, *, source_name=None))]
+    fn parse_query(&self, path: &str, source_name: Option<&str>) -> Result<Query, LiquidError> {
+        named(self.lexer.parse_query(path), path, source_name)
+    }
+
+    #[pyo3(signature = (path, *, source_name=None))]
+    fn parse_jsonpath_query(
+        &self,
+        path: &str,
+        source_name: Option<&str>,
+    ) -> Result<Query, LiquidError> {
+        named(self.lexer.parse_jsonpath_query(path), path, source_name)
+    }
+}
+
+/// A parser, configured once and reused for many templates.
+///
+/// Parsers are immutable and can be shared between threads. Query functions
+/// registered with `register_function` after a parser is created are not
+/// known to it.
+#[pyclass(name = "Parser", frozen)]
+struct PyParser {
+    parser: Arc<parser::LiquidParser>,
+}
+
+#[pymethods]
+impl PyParser {
+    /// Options are as for `parse`. _tag_aliases_ maps alternative tag names
+    /// to the canonical names of the tags they stand for, like `elseif` to
+    /// `elsif`.
+    #[new]
+    #[pyo3(signature = (
+        *,
+        extensions=None,
+        common_tags=false,
+        strict_filters=true,
+        max_depth=None,
+        max_nodes=None,
+        max_bytes=None,
+        identifiers=IdentifierPolicy::Unicode,
+        dialect=None,
+        lax_ranges=false,
+        tag_aliases=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        extensions: Option<QueryExtensions>,
+        common_tags: bool,
+        strict_filters: bool,
+        max_depth: Option<usize>,
+        max_nodes: Option<usize>,
+        max_bytes: Option<usize>,
+        identifiers: IdentifierPolicy,
+        dialect: Option<Dialect>,
+        lax_ranges: bool,
+        tag_aliases: Option<HashMap<String, String>>,
+    ) -> Self {
+        ParserOptions {
+            lexer: LexerOptions {
+                extensions,
+                identifiers,
+                dialect,
+                lax_ranges,
+                ..LexerOptions::default()
+            },
+            common_tags,
+            strict_filters,
+            max_depth,
+            max_nodes,
+            max_bytes,
+            tag_aliases: tag_aliases.unwrap_or_default(),
+        }
+        .build()
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn parse(&self, source: &str, source_name: Option<&str>) -> Result<ast::Template, LiquidError> {
+        named(self.parser.parse(source), source, source_name)
+    }
+
+    fn parse_many(
+        &self,
+        py: Python<'_>,
+        sources: Vec<String>,
+    ) -> Result<Vec<ast::Template>, LiquidError> {
+        let parser = &self.parser;
+        py.allow_threads(|| parser.parse_many(&sources).into_iter().collect())
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn parse_lossless(
+        &self,
+        source: &str,
+        source_name: Option<&str>,
+    ) -> Result<cst::ConcreteTemplate, LiquidError> {
+        named(self.parser.parse_lossless(source), source, source_name)
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn diagnose(&self, source: &str, source_name: Option<&str>) -> Result<String, json::JsonError> {
+        let diagnostics: Vec<diagnostics::Diagnostic> =
+            match named(self.parser.parse(source), source, source_name) {
+                Ok(_) => Vec::new(),
+                Err(err) => vec![diagnostics::Diagnostic::from_error(&err, source)],
+            };
+        Ok(json::to_json(&diagnostics)?)
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn validate(&self, source: &str, source_name: Option<&str>) -> Result<String, json::JsonError> {
+        let diagnostics: Vec<diagnostics::Diagnostic> = self
+            .parser
+            .validate(source)
+            .into_iter()
+            .map(|err| match source_name {
+                Some(name) => err.with_source(name, source),
+                None => err,
+            })
+            .map(|err| diagnostics::Diagnostic::from_error(&err, source))
+            .collect();
+        Ok(json::to_json(&diagnostics)?)
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn parse_filtered_expression(
+        &self,
+        source: Expression,
+        source_name: Option<&str>,
+    ) -> Result<FilteredExpression, LiquidError> {
+        source.parse(&self.parser, source_name, |parser, tokens| {
+            parser.parse_filtered_expression_tokens(tokens)
+        })
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn parse_boolean_expression(
+        &self,
+        source: Expression,
+        source_name: Option<&str>,
+    ) -> Result<BooleanExpression, LiquidError> {
+        source.parse(&self.parser, source_name, |parser, tokens| {
+            parser.parse_boolean_expression_tokens(tokens)
+        })
+    }
+
+    #[pyo3(signature = (source, *, source_name=None))]
+    fn parse_keyword_arguments(
+        &self,
+        source: Expression,
+        source_name: Option<&str>,
+    ) -> Result<Vec<KeywordArgument>, LiquidError> {
+        source.parse(&self.parser, source_name, |parser, tokens| {
+            parser.parse_keyword_argument_tokens(tokens)
+        })
+    }
+
+    fn grammar(&self) -> grammar::Grammar {
+        self.parser.grammar()
+    }
+
+    fn outline(&self, py: Python<'_>, source: &str) -> Vec<outline::OutlineItem> {
+        let parser = &self.parser;
+        py.allow_threads(|| parser.outline(source))
+    }
+}
+
+/// Return one-based `((line, column), (line, column))` pairs for the start
+/// and end of _span_ in _source_. Use `LineIndex` to convert many spans from
+/// the same source.
+#[pyfunction]
+fn span_to_line_col(source: &str, span: Span) -> ((usize, usize), (usize, usize)) {
+    span::LineIndex::new(source).span_line_col(span)
+}
+
+#[pyfunction]
+fn dump(source: &str) {
+    Lexer::new().dump(source);
+}
+
+#[pyfunction]
+fn dump_query(path: &str) {
+    Lexer::new().dump_query(path);
+}
+
+/// Decode escape sequences in _s_, the contents of a string literal quoted
+/// with _quote_.
+#[pyfunction]
+#[pyo3(signature = (s, quote='"'))]
+fn unescape_string(s: &str, quote: char) -> Result<String, LiquidError> {
+    unescape::unescape(s, quote, &Span::default())
+}
+
+/// Return the tags in _source_, nested by block, without parsing it.
+/// Markup that can not be tokenized is skipped.
+#[pyfunction]
+#[pyo3(name = "outline", signature = (source, *, extensions=None))]
+fn py_outline(source: &str, extensions: Option<QueryExtensions>) -> Vec<outline::OutlineItem> {
+    ParserOptions::extensions(extensions)
+        .build()
+        .parser
+        .outline(source)
+}
+
+#[pyfunction]
+#[pyo3(name = "grammar")]
+fn get_grammar() -> grammar::Grammar {
+    default_parser().grammar()
+}
+
+#[pymodule]
+fn _liquid2(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add(
+        "PyLiquidError",
+        m.py().get_type_bound::<errors::PyLiquidError>(),
+    )?;
+    m.add(
+        "LiquidTypeError",
+        m.py().get_type_bound::<errors::LiquidTypeError>(),
+    )?;
+    m.add(
+        "LiquidSyntaxError",
+        m.py().get_type_bound::<errors::LiquidSyntaxError>(),
+    )?;
+    m.add(
+        "LiquidNameError",
+        m.py().get_type_bound::<errors::LiquidNameError>(),
+    )?;
+    m.add(
+        "LiquidExtensionError",
+        m.py().get_type_bound::<errors::LiquidExtensionError>(),
+    )?;
+    m.add_function(wrap_pyfunction!(dump, m)?)?;
+    m.add_function(wrap_pyfunction!(tokenize, m)?)?;
+    m.add_function(wrap_pyfunction!(tokenize_many, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_many, m)?)?;
+    m.add_function(wrap_pyfunction!(tokenize_lenient, m)?)?;
+    m.add_function(wrap_pyfunction!(completion_context, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_tokens, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_lossless, m)?)?;
+    m.add_function(wrap_pyfunction!(py_render, m)?)?;
+    m.add_function(wrap_pyfunction!(render_with_source_map, m)?)?;
+    m.add_function(wrap_pyfunction!(render_with_truncated_loops, m)?)?;
+    m.add_function(wrap_pyfunction!(diagnose, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(lint_template, m)?)?;
+    m.add_function(wrap_pyfunction!(audit_template, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_query, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_jsonpath_query, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_filtered_expression, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_boolean_expression, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_keyword_arguments, m)?)?;
+    m.add_function(wrap_pyfunction!(unescape_string, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_whitespace, m)?)?;
+    m.add_function(wrap_pyfunction!(dump_query, m)?)?;
+    m.add_function(wrap_pyfunction!(get_grammar, m)?)?;
+    m.add_function(wrap_pyfunction!(py_outline, m)?)?;
+    m.add_function(wrap_pyfunction!(register_function, m)?)?;
+    m.add_function(wrap_pyfunction!(span_to_line_col, m)?)?;
+    m.add_class::<PyLexer>()?;
+    m.add_class::<PyParser>()?;
+    m.add_class::<QueryExtensions>()?;
+    m.add_class::<Dialect>()?;
+    m.add_class::<lexer::MarkupIterator>()?;
+    m.add_class::<token_stream::TokenStream>()?;
+    m.add_class::<query::Segment>()?;
+    m.add_class::<query::eval::QueryNode>()?;
+    m.add_class::<query::Selector>()?;
+    m.add_class::<query::ComparisonOperator>()?;
+    m.add_class::<query::LogicalOperator>()?;
+    m.add_class::<query::FilterExpression>()?;
+    m.add_class::<ast::Template>()?;
+    m.add_class::<cst::ConcreteTemplate>()?;
+    m.add_class::<ast::Node>()?;
+    m.add_class::<ast::ElsifTag>()?;
+    m.add_class::<ast::ElseTag>()?;
+    m.add_class::<ast::WhenTag>()?;
+    m.add_class::<ast::PluralTag>()?;
+    m.add_class::<ast::ExtensionBranch>()?;
+    m.add_class::<ast::ExtensionEnd>()?;
+    m.add_class::<ast::FilteredExpression>()?;
+    m.add_class::<ast::InlineCondition>()?;
+    m.add_class::<ast::Filter>()?;
+    m.add_class::<ast::CommonArgument>()?;
+    m.add_class::<ast::KeywordArgument>()?;
+    m.add_class::<ast::Parameter>()?;
+    m.add_class::<ast::Primitive>()?;
+    m.add_class::<ast::BooleanExpression>()?;
+    m.add_class::<ast::BooleanOperator>()?;
+    m.add_class::<ast::CompareOperator>()?;
+    m.add_class::<markup::Markup>()?;
+    m.add_class::<markup::CommentKind>()?;
+    m.add_class::<markup::RawSpans>()?;
+    m.add_class::<markup::Token>()?;
+    m.add_class::<markup::RangeArgument>()?;
+    m.add_class::<grammar::Grammar>()?;
+    m.add_class::<grammar::TagInfo>()?;
+    m.add_class::<outline::OutlineItem>()?;
+    m.add_class::<completion::CompletionKind>()?;
+    m.add_class::<completion::CompletionContext>()?;
+    m.add_class::<whitespace::Whitespace>()?;
+    m.add_class::<whitespace::SmartTrim>()?;
+    m.add_class::<IdentifierPolicy>()?;
+    m.add_class::<span::LineIndex>()?;
+    m.add_class::<source_map::Mapping>()?;
+    m.add_class::<source_map::SourceMap>()?;
+    m.add_class::<render::TruncatedLoop>()?;
+    m.add_class::<analysis::TemplateAnalysis>()?;
+    m.add_class::<metadata::TemplateMetadata>()?;
+    m.add_class::<metadata::ParamDoc>()?;
+    m.add_class::<analysis::Variable>()?;
+    m.add_class::<analysis::Identifier>()?;
+    m.add_class::<analysis::ReferenceKind>()?;
+    m.add_class::<analysis::Reference>()?;
+    m.add_class::<semantic::Definedness>()?;
+    m.add_class::<semantic::NameUse>()?;
+    m.add_class::<semantic::SemanticModel>()?;
+    m.add_class::<estimate::RenderEstimate>()?;
+    m.add_class::<estimate::LoopEstimate>()?;
+    Ok(())
+}
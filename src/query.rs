@@ -2,14 +2,26 @@
 //!
 
 use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
 
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
-#[derive(Debug, Clone)]
+use crate::grammar::RESERVED_WORDS;
+#[cfg(feature = "python")]
+use crate::python::repr;
+use crate::shared::SharedVec;
+use crate::span::Span;
+
+pub mod cache;
+pub mod eval;
+pub mod iregexp;
+
+#[cfg_attr(feature = "python", pyclass(get_all, eq))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Query {
-    #[pyo3(get)]
-    pub segments: Vec<Segment>,
+    pub segments: SharedVec<Segment>,
 }
 
 impl Query {
@@ -30,21 +42,18 @@ impl Query {
             false
         })
     }
-}
 
-#[pymethods]
-impl Query {
     pub fn as_word(&self) -> Option<String> {
         if self.segments.len() != 1 {
             return None;
         }
 
-        if let Some(Segment::Child { selectors, .. }) = self.segments.get(0) {
+        if let Some(Segment::Child { selectors, .. }) = self.segments.first() {
             if selectors.len() != 1 {
                 return None;
             }
 
-            if let Some(Selector::Name { name, .. }) = selectors.get(0) {
+            if let Some(Selector::Name { name, .. }) = selectors.first() {
                 Some(name.to_owned())
             } else {
                 None
@@ -53,100 +62,463 @@ impl Query {
             None
         }
     }
+
+    /// Return an equivalent query in a standard form. Slices get an explicit
+    /// step, negative zero float literals become zero, double negation is
+    /// removed from filters and the end of input marker is dropped. String
+    /// literal quoting is already discarded by the parser.
+    pub fn normalize(&self) -> Query {
+        Query {
+            segments: self
+                .segments
+                .iter()
+                .filter(|segment| !matches!(segment, Segment::Eoi {}))
+                .map(Segment::normalize)
+                .collect(),
+        }
+    }
+
+    /// Estimate the cost of evaluating this query, so hosts can reject
+    /// expensive user-supplied queries before evaluation.
+    ///
+    /// Names and indices cost one each. Wildcards, open-ended slices and
+    /// filters are assumed to visit many nodes, and everything in a
+    /// descendant segment is repeated for every node below the current one.
+    /// Each segment is applied to every node selected by the segment before
+    /// it, and queries embedded in filters are evaluated once per candidate
+    /// node, so costs multiply. The score is only useful relative to other
+    /// queries.
+    pub fn complexity(&self) -> u64 {
+        let mut total: u64 = 0;
+        let mut nodes: u64 = 1;
+        for segment in &self.segments {
+            let cost = segment.complexity();
+            total = total.saturating_add(nodes.saturating_mul(cost));
+            nodes = nodes.saturating_mul(cost.max(1));
+        }
+        total
+    }
+
+    /// The number of descendant segments in this query, including those in
+    /// queries embedded in filters.
+    pub fn descendant_segments(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Recursive { selectors, .. } => {
+                    1 + selectors
+                        .iter()
+                        .map(Selector::descendant_segments)
+                        .sum::<usize>()
+                }
+                Segment::Child { selectors, .. } => {
+                    selectors.iter().map(Selector::descendant_segments).sum()
+                }
+                Segment::Eoi {} => 0,
+            })
+            .sum()
+    }
+
+    /// How deeply filter selectors are nested in this query. A query without
+    /// filters has a depth of zero.
+    pub fn filter_depth(&self) -> usize {
+        self.selectors()
+            .map(|selector| match selector {
+                Selector::Filter { expression, .. } => 1 + expression.filter_depth(),
+                Selector::SingularQuery { query, .. } => query.filter_depth(),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn selectors(&self) -> impl Iterator<Item = &Selector> {
+        self.segments.iter().flat_map(|segment| match segment {
+            Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } => {
+                selectors.as_slice()
+            }
+            Segment::Eoi {} => &[],
+        })
+    }
+
+    /// This query in canonical, bracketed form, like `$['a']['b'][1]`.
+    pub fn canonical(&self) -> String {
+        self.to_string()
+    }
+
+    /// This query using shorthand notation where possible, like
+    /// `$.a.b[1]`.
+    pub fn to_shorthand(&self) -> String {
+        format!("{self:#}")
+    }
+
+    /// Write this query as it appears in template markup, in shorthand
+    /// notation and without the root identifier, like `a.b[c.d]`. A first
+    /// name that isn't an identifier, or that would be read back as a
+    /// keyword, is bracketed, like `['a b'].c`.
+    pub(crate) fn fmt_implicit_root(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (selectors, rest) = match self.segments.split_first() {
+            Some((Segment::Child { selectors, .. }, rest)) => (selectors, rest),
+            // Descendant segments can't follow an implicit root.
+            _ => return write!(f, "{self:#}"),
+        };
+
+        match selectors.as_slice() {
+            [Selector::Name { name, .. }]
+                if is_shorthand_name(name)
+                    && !RESERVED_WORDS.contains(&name.as_str())
+                    && !matches!(name.as_str(), "empty" | "blank") =>
+            {
+                f.write_str(name)?
+            }
+            _ => write_bracketed(f, "", selectors)?,
+        }
+        rest.iter().try_for_each(|segment| write!(f, "{segment:#}"))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Query {
+    #[pyo3(name = "as_word")]
+    fn py_as_word(&self) -> Option<String> {
+        self.as_word()
+    }
+
+    /// Apply this query to _data_, returning matching nodes in document
+    /// order. Function extensions registered with `register_function` are
+    /// called when needed.
+    #[pyo3(name = "find")]
+    fn py_find(
+        &self,
+        data: crate::value::Value,
+    ) -> Result<Vec<eval::QueryNode>, crate::errors::LiquidError> {
+        let data = serde_json::Value::from(data);
+        Ok(self
+            .find_with(&data, &crate::python::function_extensions())?
+            .iter()
+            .map(eval::Node::to_query_node)
+            .collect())
+    }
+
+    #[pyo3(name = "complexity")]
+    fn py_complexity(&self) -> u64 {
+        self.complexity()
+    }
+
+    #[pyo3(name = "normalize")]
+    fn py_normalize(&self) -> Query {
+        self.normalize()
+    }
+
+    #[pyo3(name = "canonical")]
+    fn py_canonical(&self) -> String {
+        self.canonical()
+    }
+
+    #[pyo3(name = "to_shorthand")]
+    fn py_to_shorthand(&self) -> String {
+        self.to_shorthand()
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        repr(slf.as_any(), &slf.borrow().to_string(), None)
+    }
 }
 
+/// Queries are formatted in canonical, bracketed form, like `$['a'][0]`.
+/// The alternate form, `{:#}`, uses shorthand notation where possible, like
+/// `$.a[0]`.
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "${}",
-            self.segments
-                .iter()
-                .map(|s| s.to_string())
-                .collect::<Vec<String>>()
-                .join("")
-        )
+        f.write_char('$')?;
+        self.segments.iter().try_for_each(|segment| segment.fmt(f))
     }
 }
 
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(eq))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Segment {
     Child {
-        selectors: Vec<Selector>,
-        span: (usize, usize),
+        selectors: SharedVec<Selector>,
+        span: Span,
     },
     Recursive {
-        selectors: Vec<Selector>,
-        span: (usize, usize),
+        selectors: SharedVec<Selector>,
+        span: Span,
     },
     Eoi {}, // Is this needed?
 }
 
-impl fmt::Display for Segment {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Segment {
+    fn normalize(&self) -> Segment {
         match self {
-            Segment::Child { selectors, .. } => {
-                write!(
-                    f,
-                    "[{}]",
-                    selectors
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                )
-            }
+            Segment::Child { selectors, span } => Segment::Child {
+                selectors: selectors.iter().map(Selector::normalize).collect(),
+                span: *span,
+            },
+            Segment::Recursive { selectors, span } => Segment::Recursive {
+                selectors: selectors.iter().map(Selector::normalize).collect(),
+                span: *span,
+            },
+            Segment::Eoi {} => Segment::Eoi {},
+        }
+    }
+
+    fn complexity(&self) -> u64 {
+        match self {
+            Segment::Child { selectors, .. } => selectors_complexity(selectors),
             Segment::Recursive { selectors, .. } => {
-                write!(
-                    f,
-                    "..[{}]",
-                    selectors
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                )
+                DESCENDANT_COST.saturating_mul(selectors_complexity(selectors))
             }
-            Segment::Eoi {} => Ok(()),
+            Segment::Eoi {} => 0,
         }
     }
 }
 
-#[pyclass]
-#[derive(Debug, Clone)]
+/// The assumed number of nodes visited by a wildcard, open-ended slice or
+/// filter.
+const FAN_OUT_COST: u64 = 10;
+
+/// The assumed number of nodes below the current node, visited by a
+/// descendant segment.
+const DESCENDANT_COST: u64 = 100;
+
+fn selectors_complexity(selectors: &[Selector]) -> u64 {
+    selectors
+        .iter()
+        .map(Selector::complexity)
+        .fold(0, u64::saturating_add)
+}
+
+/// Segments are compared structurally, ignoring their spans.
+/// Queries are hashed by their normalized, canonical form, which, like
+/// equality, ignores spans.
+impl Hash for Query {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalize().to_string().hash(state);
+    }
+}
+
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Segment::Child { selectors: a, .. }, Segment::Child { selectors: b, .. })
+            | (Segment::Recursive { selectors: a, .. }, Segment::Recursive { selectors: b, .. }) => {
+                a == b
+            }
+            (Segment::Eoi {}, Segment::Eoi {}) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Segment {
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        repr(slf.as_any(), &slf.borrow().to_string(), None)
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (prefix, selectors) = match self {
+            Segment::Child { selectors, .. } => ("", selectors),
+            Segment::Recursive { selectors, .. } => ("..", selectors),
+            Segment::Eoi {} => return Ok(()),
+        };
+
+        if f.alternate() {
+            match selectors.as_slice() {
+                [Selector::Name { name, .. }] if is_shorthand_name(name) => {
+                    let dot = if prefix.is_empty() { "." } else { "" };
+                    return write!(f, "{prefix}{dot}{name}");
+                }
+                [Selector::Wild { .. }] => {
+                    let dot = if prefix.is_empty() { "." } else { "" };
+                    return write!(f, "{prefix}{dot}*");
+                }
+                _ => (),
+            }
+        }
+
+        write_bracketed(f, prefix, selectors)
+    }
+}
+
+fn write_bracketed(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    selectors: &[Selector],
+) -> fmt::Result {
+    write!(f, "{prefix}[")?;
+    for (i, selector) in selectors.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        fmt::Display::fmt(selector, f)?;
+    }
+    f.write_char(']')
+}
+
+/// True if _name_ can be written as a member name shorthand, like `.a`,
+/// without any query extensions.
+pub(crate) fn is_shorthand_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || !ch.is_ascii())
+}
+
+/// Write _value_ surrounded by _quote_, escaping it like an RFC 9535
+/// normalized path.
+pub(crate) fn write_quoted(f: &mut fmt::Formatter<'_>, value: &str, quote: char) -> fmt::Result {
+    f.write_char(quote)?;
+    for ch in value.chars() {
+        match ch {
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\\' => f.write_str("\\\\")?,
+            ch if ch == quote => write!(f, "\\{ch}")?,
+            ch if ch < ' ' => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+#[cfg_attr(feature = "python", pyclass(eq))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Selector {
     Name {
         name: String,
-        span: (usize, usize),
+        span: Span,
     },
     Index {
         index: i64,
-        span: (usize, usize),
+        span: Span,
     },
     Slice {
         start: Option<i64>,
         stop: Option<i64>,
         step: Option<i64>,
-        span: (usize, usize),
+        span: Span,
     },
     Wild {
-        span: (usize, usize),
+        span: Span,
     },
     Filter {
         expression: Box<FilterExpression>,
-        span: (usize, usize),
+        span: Span,
     },
     SingularQuery {
         query: Box<Query>,
-        span: (usize, usize),
+        span: Span,
     },
 }
 
+impl Selector {
+    fn normalize(&self) -> Selector {
+        match self {
+            Selector::Slice {
+                start,
+                stop,
+                step,
+                span,
+            } => Selector::Slice {
+                start: *start,
+                stop: *stop,
+                step: Some(step.unwrap_or(1)),
+                span: *span,
+            },
+            Selector::Filter { expression, span } => Selector::Filter {
+                expression: Box::new(expression.normalize()),
+                span: *span,
+            },
+            Selector::SingularQuery { query, span } => Selector::SingularQuery {
+                query: Box::new(query.normalize()),
+                span: *span,
+            },
+            selector => selector.clone(),
+        }
+    }
+
+    fn complexity(&self) -> u64 {
+        match self {
+            Selector::Name { .. } | Selector::Index { .. } => 1,
+            Selector::Wild { .. } => FAN_OUT_COST,
+            Selector::Slice {
+                start: Some(start),
+                stop: Some(stop),
+                step,
+                ..
+            } if (*start < 0) == (*stop < 0) => {
+                let step = step.unwrap_or(1).unsigned_abs().max(1);
+                (start.abs_diff(*stop) / step).clamp(1, FAN_OUT_COST)
+            }
+            Selector::Slice { .. } => FAN_OUT_COST,
+            Selector::Filter { expression, .. } => {
+                FAN_OUT_COST.saturating_mul(1_u64.saturating_add(expression.complexity()))
+            }
+            Selector::SingularQuery { query, .. } => query.complexity(),
+        }
+    }
+
+    fn descendant_segments(&self) -> usize {
+        match self {
+            Selector::Filter { expression, .. } => expression.descendant_segments(),
+            Selector::SingularQuery { query, .. } => query.descendant_segments(),
+            _ => 0,
+        }
+    }
+}
+
+/// Selectors are compared structurally, ignoring their spans.
+impl PartialEq for Selector {
+    fn eq(&self, other: &Self) -> bool {
+        use Selector::*;
+        match (self, other) {
+            (Name { name: a, .. }, Name { name: b, .. }) => a == b,
+            (Index { index: a, .. }, Index { index: b, .. }) => a == b,
+            (
+                Slice {
+                    start: a_start,
+                    stop: a_stop,
+                    step: a_step,
+                    ..
+                },
+                Slice {
+                    start: b_start,
+                    stop: b_stop,
+                    step: b_step,
+                    ..
+                },
+            ) => a_start == b_start && a_stop == b_stop && a_step == b_step,
+            (Wild { .. }, Wild { .. }) => true,
+            (Filter { expression: a, .. }, Filter { expression: b, .. }) => a == b,
+            (SingularQuery { query: a, .. }, SingularQuery { query: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Selector {
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        repr(slf.as_any(), &slf.borrow().to_string(), None)
+    }
+}
+
 impl fmt::Display for Selector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Selector::Name { name, .. } => write!(f, "'{name}'"),
+            Selector::Name { name, .. } => write_quoted(f, name, '\''),
             Selector::Index {
                 index: array_index, ..
             } => write!(f, "{array_index}"),
@@ -166,68 +538,127 @@ impl fmt::Display for Selector {
                 )
             }
             Selector::Wild { .. } => f.write_char('*'),
-            Selector::Filter { expression, .. } => write!(f, "?{expression}"),
-            Selector::SingularQuery { query, .. } => write!(f, "{query}"),
+            Selector::Filter { expression, .. } => {
+                f.write_char('?')?;
+                expression.fmt(f)
+            }
+            // Embedded queries are rooted implicitly in shorthand notation,
+            // like `a[b.c]`.
+            Selector::SingularQuery { query, .. } if f.alternate() => query.fmt_implicit_root(f),
+            Selector::SingularQuery { query, .. } => query.fmt(f),
         }
     }
 }
 
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(eq))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FilterExpression {
     True_ {
-        span: (usize, usize),
+        span: Span,
     },
     False_ {
-        span: (usize, usize),
+        span: Span,
     },
     Null {
-        span: (usize, usize),
+        span: Span,
     },
     StringLiteral {
         value: String,
-        span: (usize, usize),
+        span: Span,
     },
     Int {
         value: i64,
-        span: (usize, usize),
+        span: Span,
     },
     Float {
         value: f64,
-        span: (usize, usize),
+        span: Span,
     },
     Not {
         expression: Box<FilterExpression>,
-        span: (usize, usize),
+        span: Span,
     },
     Logical {
         left: Box<FilterExpression>,
         operator: LogicalOperator,
         right: Box<FilterExpression>,
-        span: (usize, usize),
+        span: Span,
     },
     Comparison {
         left: Box<FilterExpression>,
         operator: ComparisonOperator,
         right: Box<FilterExpression>,
-        span: (usize, usize),
+        span: Span,
     },
     RelativeQuery {
         query: Box<Query>,
-        span: (usize, usize),
+        span: Span,
     },
     RootQuery {
         query: Box<Query>,
-        span: (usize, usize),
+        span: Span,
     },
     Function {
         name: String,
         args: Vec<FilterExpression>,
-        span: (usize, usize),
+        span: Span,
     },
 }
 
 impl FilterExpression {
+    fn normalize(&self) -> FilterExpression {
+        use FilterExpression::*;
+        match self {
+            Float { value, span } if *value == 0.0 => Float {
+                value: 0.0,
+                span: *span,
+            },
+            Not { expression, span } => match &**expression {
+                Not { expression, .. } => expression.normalize(),
+                expression => Not {
+                    expression: Box::new(expression.normalize()),
+                    span: *span,
+                },
+            },
+            Logical {
+                left,
+                operator,
+                right,
+                span,
+            } => Logical {
+                left: Box::new(left.normalize()),
+                operator: operator.clone(),
+                right: Box::new(right.normalize()),
+                span: *span,
+            },
+            Comparison {
+                left,
+                operator,
+                right,
+                span,
+            } => Comparison {
+                left: Box::new(left.normalize()),
+                operator: operator.clone(),
+                right: Box::new(right.normalize()),
+                span: *span,
+            },
+            RelativeQuery { query, span } => RelativeQuery {
+                query: Box::new(query.normalize()),
+                span: *span,
+            },
+            RootQuery { query, span } => RootQuery {
+                query: Box::new(query.normalize()),
+                span: *span,
+            },
+            Function { name, args, span } => Function {
+                name: name.clone(),
+                args: args.iter().map(FilterExpression::normalize).collect(),
+                span: *span,
+            },
+            expression => expression.clone(),
+        }
+    }
+
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
@@ -239,6 +670,114 @@ impl FilterExpression {
                 | FilterExpression::Float { .. }
         )
     }
+
+    /// Queries embedded in this expression, not including those nested in
+    /// the filters of embedded queries.
+    fn queries(&self) -> Vec<&Query> {
+        use FilterExpression::*;
+        match self {
+            RelativeQuery { query, .. } | RootQuery { query, .. } => vec![query],
+            Not { expression, .. } => expression.queries(),
+            Logical { left, right, .. } | Comparison { left, right, .. } => {
+                let mut queries = left.queries();
+                queries.extend(right.queries());
+                queries
+            }
+            Function { args, .. } => args.iter().flat_map(FilterExpression::queries).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn complexity(&self) -> u64 {
+        self.queries()
+            .iter()
+            .map(|query| query.complexity())
+            .fold(0, u64::saturating_add)
+    }
+
+    fn descendant_segments(&self) -> usize {
+        self.queries()
+            .iter()
+            .map(|query| query.descendant_segments())
+            .sum()
+    }
+
+    fn filter_depth(&self) -> usize {
+        self.queries()
+            .iter()
+            .map(|query| query.filter_depth())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Filter expressions are compared structurally, ignoring their spans.
+impl PartialEq for FilterExpression {
+    fn eq(&self, other: &Self) -> bool {
+        use FilterExpression::*;
+        match (self, other) {
+            (True_ { .. }, True_ { .. })
+            | (False_ { .. }, False_ { .. })
+            | (Null { .. }, Null { .. }) => true,
+            (StringLiteral { value: a, .. }, StringLiteral { value: b, .. }) => a == b,
+            (Int { value: a, .. }, Int { value: b, .. }) => a == b,
+            (Float { value: a, .. }, Float { value: b, .. }) => a == b,
+            (Not { expression: a, .. }, Not { expression: b, .. }) => a == b,
+            (
+                Logical {
+                    left: a_left,
+                    operator: a_op,
+                    right: a_right,
+                    ..
+                },
+                Logical {
+                    left: b_left,
+                    operator: b_op,
+                    right: b_right,
+                    ..
+                },
+            ) => a_op == b_op && a_left == b_left && a_right == b_right,
+            (
+                Comparison {
+                    left: a_left,
+                    operator: a_op,
+                    right: a_right,
+                    ..
+                },
+                Comparison {
+                    left: b_left,
+                    operator: b_op,
+                    right: b_right,
+                    ..
+                },
+            ) => a_op == b_op && a_left == b_left && a_right == b_right,
+            (RelativeQuery { query: a, .. }, RelativeQuery { query: b, .. })
+            | (RootQuery { query: a, .. }, RootQuery { query: b, .. }) => a == b,
+            (
+                Function {
+                    name: a_name,
+                    args: a_args,
+                    ..
+                },
+                Function {
+                    name: b_name,
+                    args: b_args,
+                    ..
+                },
+            ) => a_name == b_name && a_args == b_args,
+            _ => false,
+        }
+    }
+}
+
+// Nested expressions and queries are formatted with `fmt` rather than
+// `write!`, so they inherit the alternate flag.
+#[cfg(feature = "python")]
+#[pymethods]
+impl FilterExpression {
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        repr(slf.as_any(), &slf.borrow().to_string(), None)
+    }
 }
 
 impl fmt::Display for FilterExpression {
@@ -248,63 +787,56 @@ impl fmt::Display for FilterExpression {
             True_ { .. } => f.write_str("true"),
             False_ { .. } => f.write_str("false"),
             Null { .. } => f.write_str("null"),
-            StringLiteral { value, .. } => write!(f, "\"{value}\""),
+            StringLiteral { value, .. } => write_quoted(f, value, '"'),
             Int { value, .. } => write!(f, "{value}"),
             Float { value, .. } => write!(f, "{value}"),
-            Not { expression, .. } => write!(f, "!{expression}"),
+            Not { expression, .. } => {
+                f.write_char('!')?;
+                expression.fmt(f)
+            }
             Logical {
                 left,
                 operator,
                 right,
                 ..
-            } => write!(f, "({left} {operator} {right})"),
+            } => {
+                f.write_char('(')?;
+                left.fmt(f)?;
+                write!(f, " {operator} ")?;
+                right.fmt(f)?;
+                f.write_char(')')
+            }
             Comparison {
                 left,
                 operator,
                 right,
                 ..
-            } => write!(f, "{left} {operator} {right}"),
-            RelativeQuery { query, .. } => {
-                write!(
-                    f,
-                    "@{}",
-                    query
-                        .segments
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>()
-                        .join("")
-                )
+            } => {
+                left.fmt(f)?;
+                write!(f, " {operator} ")?;
+                right.fmt(f)
             }
-            RootQuery { query, .. } => {
-                write!(
-                    f,
-                    "${}",
-                    query
-                        .segments
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>()
-                        .join("")
-                )
+            RelativeQuery { query, .. } => {
+                f.write_char('@')?;
+                query.segments.iter().try_for_each(|s| s.fmt(f))
             }
+            RootQuery { query, .. } => query.fmt(f),
             Function { name, args, .. } => {
-                write!(
-                    f,
-                    "{}({})",
-                    name,
-                    args.iter()
-                        .map(|a| a.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                )
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    arg.fmt(f)?;
+                }
+                f.write_char(')')
             }
         }
     }
 }
 
-#[pyclass(eq, eq_int)]
-#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum LogicalOperator {
     And,
     Or,
@@ -319,6 +851,7 @@ impl fmt::Display for LogicalOperator {
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl LogicalOperator {
     fn __str__(&self) -> String {
@@ -326,8 +859,8 @@ impl LogicalOperator {
     }
 }
 
-#[pyclass(eq, eq_int)]
-#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ComparisonOperator {
     Eq,
     Ne,
@@ -350,6 +883,7 @@ impl fmt::Display for ComparisonOperator {
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl ComparisonOperator {
     fn __str__(&self) -> String {
@@ -357,24 +891,28 @@ impl ComparisonOperator {
     }
 }
 
+#[cfg(feature = "python")]
 impl<'py> pyo3::FromPyObject<'py> for Box<Query> {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         ob.extract::<Query>().map(Box::new)
     }
 }
 
+#[cfg(feature = "python")]
 impl pyo3::IntoPy<pyo3::PyObject> for Box<Query> {
     fn into_py(self, py: pyo3::Python<'_>) -> pyo3::PyObject {
         (*self).into_py(py)
     }
 }
 
+#[cfg(feature = "python")]
 impl<'py> pyo3::FromPyObject<'py> for Box<FilterExpression> {
     fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
         ob.extract::<FilterExpression>().map(Box::new)
     }
 }
 
+#[cfg(feature = "python")]
 impl pyo3::IntoPy<pyo3::PyObject> for Box<FilterExpression> {
     fn into_py(self, py: pyo3::Python<'_>) -> pyo3::PyObject {
         (*self).into_py(py)
@@ -0,0 +1,101 @@
+//! A least recently used cache of parsed queries.
+//!
+//! Templates and the Python engine parse the same few paths, like
+//! `product.title`, over and over. A `QueryParser` with a cache parses each
+//! path once and hands out shared copies of the result after that.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::query::Query;
+
+/// Parsed queries keyed by their source text, holding at most `capacity`
+/// queries and evicting the least recently used query when full.
+///
+/// A cache is shared between clones of the `QueryParser` that owns it, and
+/// between threads.
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Queries and the tick at which each was last used.
+    queries: HashMap<String, (Arc<Query>, u64)>,
+    /// Paths keyed by the tick at which they were last used, oldest first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl QueryCache {
+    /// A cache of at most _capacity_ queries. A cache with a capacity of
+    /// zero never holds anything.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of queries in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.queries.clear();
+        entries.recency.clear();
+    }
+
+    /// The query parsed from _path_, if it is in the cache, marking it as
+    /// the most recently used.
+    pub fn get(&self, path: &str) -> Option<Arc<Query>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.tick += 1;
+        let tick = entries.tick;
+
+        let Entries {
+            queries, recency, ..
+        } = &mut *entries;
+        let (query, used) = queries.get_mut(path)?;
+        let path = recency.remove(used).unwrap();
+        recency.insert(tick, path);
+        *used = tick;
+        Some(Arc::clone(query))
+    }
+
+    /// Add _query_, parsed from _path_, evicting the least recently used
+    /// query if the cache is full.
+    pub fn insert(&self, path: &str, query: Arc<Query>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.tick += 1;
+        let tick = entries.tick;
+
+        if let Some((_, used)) = entries.queries.remove(path) {
+            entries.recency.remove(&used);
+        }
+        while entries.queries.len() >= self.capacity {
+            let Some((_, oldest)) = entries.recency.pop_first() else {
+                break;
+            };
+            entries.queries.remove(&oldest);
+        }
+        entries.queries.insert(path.to_owned(), (query, tick));
+        entries.recency.insert(tick, path.to_owned());
+    }
+}
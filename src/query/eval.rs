@@ -0,0 +1,639 @@
+//! Evaluate JSONPath queries against JSON data.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::errors::LiquidError;
+use crate::lexer::ExpressionType;
+use crate::span::Span;
+
+use super::{
+    iregexp, write_quoted, ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment,
+    Selector,
+};
+
+/// A key or array index in the location of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathElement {
+    Name(String),
+    Index(usize),
+}
+
+/// A JSON value selected by a query, along with its location in the
+/// queried document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'v> {
+    pub value: &'v Value,
+    pub location: Vec<PathElement>,
+}
+
+impl<'v> Node<'v> {
+    fn new_child(&self, value: &'v Value, key: PathElement) -> Self {
+        let mut location = self.location.clone();
+        location.push(key);
+        Node { value, location }
+    }
+
+    pub fn value(&self) -> &'v Value {
+        self.value
+    }
+
+    /// Return the normalized path to this node, like `$['a'][0]`.
+    pub fn path(&self) -> String {
+        self.to_string()
+    }
+
+    /// Copy this node's value and location, so it can outlive the queried
+    /// document.
+    pub fn to_query_node(&self) -> QueryNode {
+        QueryNode {
+            value: self.value.clone(),
+            location: self.location.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_path(f, &self.location)
+    }
+}
+
+/// Write _location_ as an RFC 9535 normalized path.
+fn write_path(f: &mut fmt::Formatter<'_>, location: &[PathElement]) -> fmt::Result {
+    f.write_str("$")?;
+    for element in location {
+        match element {
+            PathElement::Name(name) => {
+                f.write_str("[")?;
+                write_quoted(f, name, '\'')?;
+                f.write_str("]")?;
+            }
+            PathElement::Index(index) => write!(f, "[{index}]")?,
+        }
+    }
+    Ok(())
+}
+
+/// A node that owns its value, as returned to Python by `Query.find`.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryNode {
+    pub value: Value,
+    pub location: Vec<PathElement>,
+}
+
+impl QueryNode {
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Return the normalized path to this node, like `$['a'][0]`.
+    pub fn path(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for QueryNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_path(f, &self.location)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl QueryNode {
+    #[pyo3(name = "value")]
+    fn py_value(&self) -> crate::value::Value {
+        self.value.clone().into()
+    }
+
+    #[pyo3(name = "path")]
+    fn py_path(&self) -> String {
+        self.path()
+    }
+
+    /// Keys and array indices from the root of the document to this node.
+    #[getter]
+    fn location(&self, py: Python<'_>) -> Vec<PyObject> {
+        self.location
+            .iter()
+            .map(|element| match element {
+                PathElement::Name(name) => name.into_py(py),
+                PathElement::Index(index) => index.into_py(py),
+            })
+            .collect()
+    }
+
+    fn __str__(&self) -> String {
+        self.path()
+    }
+}
+
+pub type NodeList<'v> = Vec<Node<'v>>;
+
+impl Query {
+    /// Apply this query to _value_, returning matching nodes in document
+    /// order. Calls to function extensions result in _Nothing_. Use
+    /// `find_with` to evaluate them.
+    pub fn find<'v>(&self, value: &'v Value) -> NodeList<'v> {
+        find(self, value)
+    }
+
+    /// Apply this query to _value_, calling function extensions from
+    /// _extensions_, like those registered with
+    /// `QueryParser::register_function_extension`. Fails with the first
+    /// error returned by an extension.
+    pub fn find_with<'v>(
+        &self,
+        value: &'v Value,
+        extensions: &FunctionExtensions,
+    ) -> Result<NodeList<'v>, LiquidError> {
+        let evaluator = Evaluator::new(Some(extensions));
+        let nodes = evaluator.find(self, value);
+        match evaluator.error.into_inner() {
+            Some(err) => Err(err),
+            None => Ok(nodes),
+        }
+    }
+}
+
+/// Apply _query_ to _value_, returning matching nodes in document order.
+pub fn find<'v>(query: &Query, value: &'v Value) -> NodeList<'v> {
+    Evaluator::new(None).find(query, value)
+}
+
+/// The state of one evaluation of a query.
+struct Evaluator<'f> {
+    extensions: Option<&'f FunctionExtensions>,
+    /// Compiled `match` and `search` patterns, keyed by the regular
+    /// expression they compile to, or `None` if it doesn't compile.
+    regexes: RefCell<HashMap<String, Option<Regex>>>,
+    /// The first error returned by a function extension. Extensions are not
+    /// called again once there is one.
+    error: RefCell<Option<LiquidError>>,
+}
+
+impl<'f> Evaluator<'f> {
+    fn new(extensions: Option<&'f FunctionExtensions>) -> Self {
+        Self {
+            extensions,
+            regexes: RefCell::new(HashMap::new()),
+            error: RefCell::new(None),
+        }
+    }
+
+    fn find<'v>(&self, query: &Query, value: &'v Value) -> NodeList<'v> {
+        let root = Node {
+            value,
+            location: Vec::new(),
+        };
+        self.resolve(query, root, value)
+    }
+
+    /// Apply _query_'s segments to _node_, where _root_ is the value `$`
+    /// refers to.
+    fn resolve<'v>(&self, query: &Query, node: Node<'v>, root: &'v Value) -> NodeList<'v> {
+        let mut nodes = vec![node];
+
+        for segment in &query.segments {
+            nodes = match segment {
+                Segment::Child { selectors, .. } => nodes
+                    .iter()
+                    .flat_map(|node| self.select_all(selectors, node, root))
+                    .collect(),
+                Segment::Recursive { selectors, .. } => nodes
+                    .iter()
+                    .flat_map(descendants)
+                    .flat_map(|node| self.select_all(selectors, &node, root))
+                    .collect(),
+                Segment::Eoi {} => nodes,
+            };
+        }
+
+        nodes
+    }
+
+    fn select_all<'v>(
+        &self,
+        selectors: &[Selector],
+        node: &Node<'v>,
+        root: &'v Value,
+    ) -> NodeList<'v> {
+        selectors
+            .iter()
+            .flat_map(|selector| self.select(selector, node, root))
+            .collect()
+    }
+
+    fn select<'v>(&self, selector: &Selector, node: &Node<'v>, root: &'v Value) -> NodeList<'v> {
+        match (selector, node.value) {
+            (Selector::Name { name, .. }, Value::Object(obj)) => obj
+                .get(name)
+                .map(|val| node.new_child(val, PathElement::Name(name.to_owned())))
+                .into_iter()
+                .collect(),
+            (Selector::Index { index, .. }, Value::Array(items)) => {
+                normalize_index(*index, items.len())
+                    .map(|i| node.new_child(&items[i], PathElement::Index(i)))
+                    .into_iter()
+                    .collect()
+            }
+            (
+                Selector::Slice {
+                    start, stop, step, ..
+                },
+                Value::Array(items),
+            ) => slice_indices(items.len(), *start, *stop, step.unwrap_or(1))
+                .into_iter()
+                .map(|i| node.new_child(&items[i], PathElement::Index(i)))
+                .collect(),
+            (Selector::Wild { .. }, Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| node.new_child(item, PathElement::Index(i)))
+                .collect(),
+            (Selector::Wild { .. }, Value::Object(obj)) => obj
+                .iter()
+                .map(|(key, val)| node.new_child(val, PathElement::Name(key.to_owned())))
+                .collect(),
+            (Selector::Filter { expression, .. }, Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| is_truthy(&self.evaluate(expression, item, root)))
+                .map(|(i, item)| node.new_child(item, PathElement::Index(i)))
+                .collect(),
+            (Selector::Filter { expression, .. }, Value::Object(obj)) => obj
+                .iter()
+                .filter(|(_, val)| is_truthy(&self.evaluate(expression, val, root)))
+                .map(|(key, val)| node.new_child(val, PathElement::Name(key.to_owned())))
+                .collect(),
+            (Selector::SingularQuery { query, .. }, _) => {
+                let key = match self.find(query, root).first().map(|n| n.value) {
+                    Some(Value::String(name)) => Selector::Name {
+                        name: name.to_owned(),
+                        span: Default::default(),
+                    },
+                    Some(Value::Number(n)) if n.is_i64() => Selector::Index {
+                        index: n.as_i64().unwrap(),
+                        span: Default::default(),
+                    },
+                    _ => return Vec::new(),
+                };
+                self.select(&key, node, root)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Return _node_ followed by all of its descendants, depth first.
+fn descendants<'v>(node: &Node<'v>) -> NodeList<'v> {
+    let mut nodes = vec![node.clone()];
+    match node.value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                nodes.extend(descendants(&node.new_child(item, PathElement::Index(i))));
+            }
+        }
+        Value::Object(obj) => {
+            for (key, val) in obj {
+                nodes.extend(descendants(
+                    &node.new_child(val, PathElement::Name(key.to_owned())),
+                ));
+            }
+        }
+        _ => (),
+    }
+    nodes
+}
+
+fn normalize_index(index: i64, length: usize) -> Option<usize> {
+    let index = if index < 0 {
+        length as i64 + index
+    } else {
+        index
+    };
+    (0..length as i64)
+        .contains(&index)
+        .then_some(index as usize)
+}
+
+/// Return array indices selected by a slice, as described in RFC 9535
+/// section 2.3.4.2.
+fn slice_indices(length: usize, start: Option<i64>, stop: Option<i64>, step: i64) -> Vec<usize> {
+    let len = length as i64;
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+
+    if step == 0 {
+        return Vec::new();
+    }
+
+    let mut indices = Vec::new();
+
+    if step > 0 {
+        let lower = start.map_or(0, normalize).clamp(0, len);
+        let upper = stop.map_or(len, normalize).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else {
+        let upper = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+        let lower = stop.map_or(-1, normalize).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+
+    indices
+}
+
+/// The result of evaluating a filter expression or function argument.
+enum Evaluated<'v> {
+    Nodes(NodeList<'v>),
+    /// Nodes returned from a function extension, which have no location.
+    Values(Vec<Value>),
+    Value(Value),
+    Logical(bool),
+    Nothing,
+}
+
+impl Evaluated<'_> {
+    /// Return the value of a singular query or literal, or `None` for
+    /// _Nothing_.
+    fn into_value(self) -> Option<Value> {
+        match self {
+            Evaluated::Value(value) => Some(value),
+            Evaluated::Nodes(nodes) if nodes.len() == 1 => Some(nodes[0].value.clone()),
+            Evaluated::Values(mut values) if values.len() == 1 => values.pop(),
+            Evaluated::Logical(b) => Some(Value::Bool(b)),
+            _ => None,
+        }
+    }
+}
+
+fn is_truthy(result: &Evaluated) -> bool {
+    match result {
+        Evaluated::Nodes(nodes) => !nodes.is_empty(),
+        Evaluated::Values(values) => !values.is_empty(),
+        Evaluated::Logical(b) => *b,
+        Evaluated::Value(_) => true,
+        Evaluated::Nothing => false,
+    }
+}
+
+impl Evaluator<'_> {
+    fn evaluate<'v>(
+        &self,
+        expression: &FilterExpression,
+        current: &'v Value,
+        root: &'v Value,
+    ) -> Evaluated<'v> {
+        match expression {
+            FilterExpression::True_ { .. } => Evaluated::Value(Value::Bool(true)),
+            FilterExpression::False_ { .. } => Evaluated::Value(Value::Bool(false)),
+            FilterExpression::Null { .. } => Evaluated::Value(Value::Null),
+            FilterExpression::StringLiteral { value, .. } => {
+                Evaluated::Value(Value::String(value.to_owned()))
+            }
+            FilterExpression::Int { value, .. } => Evaluated::Value(Value::from(*value)),
+            FilterExpression::Float { value, .. } => Evaluated::Value(Value::from(*value)),
+            FilterExpression::Not { expression, .. } => {
+                Evaluated::Logical(!is_truthy(&self.evaluate(expression, current, root)))
+            }
+            FilterExpression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => Evaluated::Logical(match operator {
+                LogicalOperator::And => {
+                    is_truthy(&self.evaluate(left, current, root))
+                        && is_truthy(&self.evaluate(right, current, root))
+                }
+                LogicalOperator::Or => {
+                    is_truthy(&self.evaluate(left, current, root))
+                        || is_truthy(&self.evaluate(right, current, root))
+                }
+            }),
+            FilterExpression::Comparison {
+                left,
+                operator,
+                right,
+                ..
+            } => Evaluated::Logical(compare(
+                self.evaluate(left, current, root).into_value(),
+                operator,
+                self.evaluate(right, current, root).into_value(),
+            )),
+            FilterExpression::RelativeQuery { query, .. } => Evaluated::Nodes(self.resolve(
+                query,
+                Node {
+                    value: current,
+                    location: Vec::new(),
+                },
+                root,
+            )),
+            FilterExpression::RootQuery { query, .. } => Evaluated::Nodes(self.find(query, root)),
+            FilterExpression::Function { name, args, span } => {
+                let args: Vec<Evaluated> = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg, current, root))
+                    .collect();
+                self.call_function(name, args, *span)
+            }
+        }
+    }
+
+    /// Call the function called _name_. Function extensions take precedence
+    /// over standard functions with the same name.
+    fn call_function<'v>(
+        &self,
+        name: &str,
+        mut args: Vec<Evaluated<'v>>,
+        span: Span,
+    ) -> Evaluated<'v> {
+        if let Some(extension) = self.extensions.and_then(|extensions| extensions.get(name)) {
+            return self.call_extension(extension, args, span);
+        }
+
+        match (name, args.len()) {
+            ("length", 1) => match args.pop().and_then(Evaluated::into_value) {
+                Some(Value::String(s)) => Evaluated::Value(Value::from(s.chars().count())),
+                Some(Value::Array(items)) => Evaluated::Value(Value::from(items.len())),
+                Some(Value::Object(obj)) => Evaluated::Value(Value::from(obj.len())),
+                _ => Evaluated::Nothing,
+            },
+            ("count", 1) => match args.pop() {
+                Some(Evaluated::Nodes(nodes)) => Evaluated::Value(Value::from(nodes.len())),
+                Some(Evaluated::Values(values)) => Evaluated::Value(Value::from(values.len())),
+                _ => Evaluated::Nothing,
+            },
+            ("value", 1) => match args.pop() {
+                Some(Evaluated::Nodes(nodes)) if nodes.len() == 1 => {
+                    Evaluated::Value(nodes[0].value.clone())
+                }
+                Some(Evaluated::Values(mut values)) if values.len() == 1 => {
+                    Evaluated::Value(values.pop().unwrap())
+                }
+                _ => Evaluated::Nothing,
+            },
+            ("match", 2) | ("search", 2) => {
+                let pattern = args.pop().and_then(Evaluated::into_value);
+                let value = args.pop().and_then(Evaluated::into_value);
+                Evaluated::Logical(match (value, pattern) {
+                    (Some(Value::String(value)), Some(Value::String(pattern))) => self
+                        .regex(&pattern, name == "match")
+                        .is_some_and(|re| re.is_match(&value)),
+                    _ => false,
+                })
+            }
+            // An extension that was registered without an implementation.
+            _ => Evaluated::Nothing,
+        }
+    }
+
+    /// Compile the I-Regexp _pattern_, matching the whole of a string if
+    /// _full_ is true, or return `None` if it isn't valid.
+    fn regex(&self, pattern: &str, full: bool) -> Option<Regex> {
+        let pattern = iregexp::to_regex(pattern).ok()?;
+        let pattern = if full {
+            format!(r"\A(?:{pattern})\z")
+        } else {
+            pattern
+        };
+        self.regexes
+            .borrow_mut()
+            .entry(pattern)
+            .or_insert_with_key(|pattern| Regex::new(pattern).ok())
+            .clone()
+    }
+
+    fn call_extension<'v>(
+        &self,
+        extension: &FunctionExtension,
+        args: Vec<Evaluated<'v>>,
+        span: Span,
+    ) -> Evaluated<'v> {
+        if self.error.borrow().is_some() {
+            return Evaluated::Nothing;
+        }
+
+        let args = args
+            .into_iter()
+            .zip(&extension.param_types)
+            .map(|(arg, typ)| match typ {
+                ExpressionType::Value => arg
+                    .into_value()
+                    .map_or(FunctionValue::Nothing, FunctionValue::Value),
+                ExpressionType::Logical => FunctionValue::Logical(is_truthy(&arg)),
+                ExpressionType::Nodes => FunctionValue::Nodes(match arg {
+                    Evaluated::Nodes(nodes) => nodes.iter().map(|n| n.value.clone()).collect(),
+                    Evaluated::Values(values) => values,
+                    _ => Vec::new(),
+                }),
+            })
+            .collect();
+
+        let rv = match (extension.func)(args) {
+            Ok(rv) => rv,
+            Err(err) if err.span.is_some() => {
+                *self.error.borrow_mut() = Some(err);
+                return Evaluated::Nothing;
+            }
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err.with_span(span));
+                return Evaluated::Nothing;
+            }
+        };
+
+        match (rv, extension.return_type) {
+            (FunctionValue::Logical(b), ExpressionType::Logical) => Evaluated::Logical(b),
+            (FunctionValue::Value(value), ExpressionType::Value) => Evaluated::Value(value),
+            (FunctionValue::Nodes(values), ExpressionType::Nodes) => Evaluated::Values(values),
+            (_, ExpressionType::Logical) => Evaluated::Logical(false),
+            _ => Evaluated::Nothing,
+        }
+    }
+}
+
+fn compare(left: Option<Value>, operator: &ComparisonOperator, right: Option<Value>) -> bool {
+    match operator {
+        ComparisonOperator::Eq => json_eq(&left, &right),
+        ComparisonOperator::Ne => !json_eq(&left, &right),
+        ComparisonOperator::Lt => json_lt(&left, &right),
+        ComparisonOperator::Gt => json_lt(&right, &left),
+        ComparisonOperator::Le => json_lt(&left, &right) || json_eq(&left, &right),
+        ComparisonOperator::Ge => json_lt(&right, &left) || json_eq(&left, &right),
+    }
+}
+
+fn json_eq(left: &Option<Value>, right: &Option<Value>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => values_equal(left, right),
+        _ => false,
+    }
+}
+
+/// Compare JSON values for equality, treating numbers with the same
+/// mathematical value as equal, regardless of representation.
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|other| values_equal(v, other)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn json_lt(left: &Option<Value>, right: &Option<Value>) -> bool {
+    match (left, right) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64() < b.as_f64(),
+        (Some(Value::String(a)), Some(Value::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+/// An argument passed to, or the result returned from, a function
+/// extension.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionValue {
+    /// The values of nodes selected by a query.
+    Nodes(Vec<Value>),
+    Value(Value),
+    Logical(bool),
+    /// The absence of a value, like an empty query result used where a
+    /// single value is expected.
+    Nothing,
+}
+
+/// The implementation of a function extension. Arguments are converted to
+/// _param_types_ before calling _func_, and its result is interpreted as
+/// _return_type_.
+#[derive(Clone)]
+pub struct FunctionExtension {
+    pub param_types: Vec<ExpressionType>,
+    pub return_type: ExpressionType,
+    pub func: Arc<dyn Fn(Vec<FunctionValue>) -> Result<FunctionValue, LiquidError> + Send + Sync>,
+}
+
+/// Function extension implementations, by function name.
+pub type FunctionExtensions = HashMap<String, FunctionExtension>;
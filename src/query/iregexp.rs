@@ -0,0 +1,282 @@
+//! Validate RFC 9485 I-Regexp patterns and translate them to the syntax
+//! understood by the `regex` crate.
+//!
+//! I-Regexp is a small, interoperable subset of regular expressions. It has
+//! no anchors, backreferences, lookaround, lazy quantifiers or shorthand
+//! classes like `\d`, and `.` never matches a line terminator.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::errors::LiquidError;
+
+/// Check that _pattern_ is a valid I-Regexp.
+pub fn validate(pattern: &str) -> Result<(), LiquidError> {
+    to_regex(pattern).map(|_| ())
+}
+
+/// Translate I-Regexp _pattern_ to an equivalent, unanchored `regex` crate
+/// pattern.
+pub fn to_regex(pattern: &str) -> Result<String, LiquidError> {
+    let mut translator = Translator {
+        chars: pattern.chars().peekable(),
+        out: String::with_capacity(pattern.len()),
+    };
+
+    translator
+        .regexp()
+        .and_then(|_| match translator.chars.next() {
+            None => Ok(()),
+            Some(c) => Err(format!("unexpected '{c}'")),
+        })
+        .map_err(|reason| {
+            LiquidError::typ(format!("invalid I-Regexp pattern `{pattern}`, {reason}"))
+        })?;
+
+    Ok(translator.out)
+}
+
+struct Translator<'p> {
+    chars: Peekable<Chars<'p>>,
+    out: String,
+}
+
+impl Translator<'_> {
+    fn regexp(&mut self) -> Result<(), String> {
+        self.branch()?;
+        while self.chars.next_if_eq(&'|').is_some() {
+            self.out.push('|');
+            self.branch()?;
+        }
+        Ok(())
+    }
+
+    fn branch(&mut self) -> Result<(), String> {
+        while !matches!(self.chars.peek(), None | Some('|') | Some(')')) {
+            self.atom()?;
+            self.quantifier()?;
+        }
+        Ok(())
+    }
+
+    fn atom(&mut self) -> Result<(), String> {
+        match self.chars.next() {
+            Some('(') => {
+                self.out.push_str("(?:");
+                self.regexp()?;
+                if self.chars.next() != Some(')') {
+                    return Err(String::from("unbalanced parentheses"));
+                }
+                self.out.push(')');
+            }
+            Some('.') => self.out.push_str(r"[^\n\r]"),
+            Some('[') => self.class_expr()?,
+            Some('\\') => match self.chars.peek() {
+                Some('p') | Some('P') => self.category_escape()?,
+                _ => {
+                    let c = self.single_char_escape()?;
+                    self.literal(c);
+                }
+            },
+            Some(c @ ('*' | '+' | '?' | '{')) => {
+                return Err(format!("nothing to repeat before '{c}'"))
+            }
+            Some(c @ (']' | '}')) => return Err(format!("unexpected '{c}'")),
+            Some(c) => self.literal(c),
+            None => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn quantifier(&mut self) -> Result<(), String> {
+        match self.chars.peek() {
+            Some(&c @ ('*' | '+' | '?')) => {
+                self.chars.next();
+                self.out.push(c);
+            }
+            Some('{') => {
+                self.chars.next();
+                let min = self.digits()?;
+                let max = if self.chars.next_if_eq(&',').is_some() {
+                    match self.chars.peek() {
+                        Some('}') => None,
+                        _ => Some(self.digits()?),
+                    }
+                } else {
+                    Some(min)
+                };
+
+                if self.chars.next() != Some('}') {
+                    return Err(String::from("unterminated range quantifier"));
+                }
+
+                match max {
+                    Some(max) if max < min => {
+                        return Err(format!("range quantifier {{{min},{max}}} is out of order"))
+                    }
+                    Some(max) if max == min => self.out.push_str(&format!("{{{min}}}")),
+                    Some(max) => self.out.push_str(&format!("{{{min},{max}}}")),
+                    None => self.out.push_str(&format!("{{{min},}}")),
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn digits(&mut self) -> Result<u32, String> {
+        let mut digits = String::new();
+        while let Some(d) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(d);
+        }
+
+        if digits.is_empty() {
+            return Err(String::from("expected a digit in range quantifier"));
+        }
+
+        digits
+            .parse::<u32>()
+            .map_err(|_| format!("range quantifier {digits} is too large"))
+    }
+
+    /// Character class expressions, after the opening `[`.
+    fn class_expr(&mut self) -> Result<(), String> {
+        self.out.push('[');
+        if self.chars.next_if_eq(&'^').is_some() {
+            self.out.push('^');
+        }
+
+        // A leading hyphen is a literal.
+        let mut empty = true;
+        if self.chars.next_if_eq(&'-').is_some() {
+            self.out.push_str(r"\-");
+            empty = false;
+        }
+
+        loop {
+            match self.chars.peek().copied() {
+                None => return Err(String::from("unterminated character class")),
+                Some(']') if !empty => {
+                    self.chars.next();
+                    break;
+                }
+                Some(']') => return Err(String::from("empty character class")),
+                Some('-') => {
+                    // A trailing hyphen is a literal.
+                    self.chars.next();
+                    if self.chars.next() != Some(']') || empty {
+                        return Err(String::from("unexpected '-' in character class"));
+                    }
+                    self.out.push_str(r"\-");
+                    break;
+                }
+                Some('\\') if self.lookahead_category() => {
+                    self.chars.next();
+                    self.category_escape()?;
+                }
+                _ => {
+                    let start = self.class_char()?;
+                    self.literal(start);
+
+                    // A hyphen followed by `]` is a trailing literal, not a range.
+                    let mut ahead = self.chars.clone();
+                    if ahead.next() == Some('-') && !matches!(ahead.next(), Some(']') | None) {
+                        self.chars.next();
+                        let end = self.class_char()?;
+                        if end < start {
+                            return Err(format!(
+                                "character class range {start}-{end} is out of order"
+                            ));
+                        }
+                        self.out.push('-');
+                        self.literal(end);
+                    }
+                }
+            }
+            empty = false;
+        }
+
+        self.out.push(']');
+        Ok(())
+    }
+
+    fn class_char(&mut self) -> Result<char, String> {
+        match self.chars.next() {
+            Some('\\') => self.single_char_escape(),
+            Some(c @ ('[' | ']' | '-')) => Err(format!("unescaped '{c}' in character class")),
+            Some(c) => Ok(c),
+            None => Err(String::from("unterminated character class")),
+        }
+    }
+
+    fn lookahead_category(&self) -> bool {
+        let mut ahead = self.chars.clone();
+        ahead.next() == Some('\\') && matches!(ahead.next(), Some('p') | Some('P'))
+    }
+
+    /// Single character escapes, after the backslash.
+    fn single_char_escape(&mut self) -> Result<char, String> {
+        match self.chars.next() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some(
+                c @ ('(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\' | ']' | '^' | '{' | '|'
+                | '}'),
+            ) => Ok(c),
+            Some(c) => Err(format!("invalid escape '\\{c}'")),
+            None => Err(String::from("trailing backslash")),
+        }
+    }
+
+    /// `\p{..}` and `\P{..}` Unicode category escapes, after the backslash.
+    fn category_escape(&mut self) -> Result<(), String> {
+        let p = self.chars.next().unwrap();
+        if self.chars.next() != Some('{') {
+            return Err(format!("expected '{{' after '\\{p}'"));
+        }
+
+        let mut category = String::new();
+        loop {
+            match self.chars.next() {
+                Some('}') => break,
+                Some(c) => category.push(c),
+                None => return Err(String::from("unterminated category escape")),
+            }
+        }
+
+        if !is_category(&category) {
+            return Err(format!("unknown character category '{category}'"));
+        }
+
+        self.out.push_str(&format!("\\{p}{{{category}}}"));
+        Ok(())
+    }
+
+    fn literal(&mut self, c: char) {
+        let mut buf = [0; 4];
+        self.out.push_str(&regex::escape(c.encode_utf8(&mut buf)));
+    }
+}
+
+/// Return `true` if _category_ is one of the Unicode general categories
+/// allowed by I-Regexp.
+fn is_category(category: &str) -> bool {
+    let mut chars = category.chars();
+    let subcategories = match chars.next() {
+        Some('L') => "lmotu",
+        Some('M') => "cen",
+        Some('N') => "dlo",
+        Some('P') => "cdefios",
+        Some('Z') => "lps",
+        Some('S') => "ckmo",
+        Some('C') => "cfno",
+        _ => return false,
+    };
+
+    match (chars.next(), chars.next()) {
+        (None, _) => true,
+        (Some(c), None) => subcategories.contains(c),
+        _ => false,
+    }
+}
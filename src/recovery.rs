@@ -0,0 +1,140 @@
+//! Tokenize templates that contain syntax errors, for editors and other
+//! tools that need to see the whole document.
+//!
+//! When the grammar fails to match, we find the first piece of markup that
+//! could not be matched, from its opening delimiter to the next matching
+//! closing delimiter, and blank it out with spaces before trying again.
+//! Blanking keeps every other byte offset in place, so spans from the final
+//! pass are valid for the original source. Blanked regions are then put back
+//! as `Markup::Error`.
+
+use pest::Parser;
+
+use crate::errors::LiquidError;
+use crate::lexer::{syntax_error, Lexer, Liquid, Rule};
+use crate::markup::Markup;
+use crate::pest_errors::floor_char_boundary;
+use crate::span::Span;
+
+const DELIMITERS: [(&str, &str); 3] = [("{{", "}}"), ("{%", "%}"), ("{#", "#}")];
+
+impl Lexer {
+    /// Tokenize _source_ without stopping at the first error.
+    ///
+    /// Markup that can not be tokenized is returned as `Markup::Error`, in
+    /// order with the rest of the template, and the errors that caused it are
+    /// returned alongside, also in order.
+    pub fn tokenize_lenient(&self, source: &str) -> (Vec<Markup>, Vec<LiquidError>) {
+        let mut blanked = source.to_owned();
+        let mut bad: Vec<Span> = Vec::new();
+        let mut errors: Vec<LiquidError> = Vec::new();
+
+        let pairs = loop {
+            let err = match Liquid::parse(Rule::markup, &blanked) {
+                Ok(pairs) => break pairs,
+                Err(err) => syntax_error(err, &blanked),
+            };
+
+            let span = bad_markup(&blanked, err.span.map_or(0, |span| span.start));
+            blanked.replace_range(span.start..span.end, &" ".repeat(span.end - span.start));
+            bad.push(span);
+            errors.push(err);
+        };
+
+        let mut markup = Vec::new();
+        for pair in pairs {
+            let span: Span = pair.as_span().into();
+            match self.markup(pair) {
+                Ok(item) => markup.push(item),
+                Err(err) => {
+                    errors.push(if err.span.is_none() {
+                        err.with_span(span)
+                    } else {
+                        err
+                    });
+                    markup.push(Markup::Error {
+                        text: source[span.start..span.end].to_owned(),
+                        span,
+                    });
+                }
+            }
+        }
+
+        bad.sort_by_key(|span| span.start);
+        errors.sort_by_key(|err| err.span.map_or(0, |span| span.start));
+        (restore(markup, source, &bad), errors)
+    }
+}
+
+/// Return the span of the first markup in _source_ that the grammar can't
+/// match. _position_ is where the grammar gave up.
+fn bad_markup(source: &str, position: usize) -> Span {
+    let start = Liquid::parse(Rule::_markup_prefix, source)
+        .ok()
+        .and_then(|pairs| pairs.last().map(|pair| pair.as_span().end()))
+        .unwrap_or(0);
+
+    let close = DELIMITERS
+        .iter()
+        .find(|(open, _)| source[start..].starts_with(open))
+        .map(|(_, close)| *close);
+
+    let end = match close {
+        Some(close) => {
+            let search_from = floor_char_boundary(source, position.max(start + 2));
+            source[search_from..]
+                .find(close)
+                .map_or(source.len(), |i| search_from + i + close.len())
+        }
+        None => source.len(),
+    };
+
+    if source[start..end].trim().is_empty() {
+        // We can't make progress. Give up on the whole template.
+        Span::new(0, source.len())
+    } else {
+        Span::new(start, end)
+    }
+}
+
+/// Split content markup around the blanked spans in _bad_, restoring the
+/// original text from _source_.
+fn restore(markup: Vec<Markup>, source: &str, bad: &[Span]) -> Vec<Markup> {
+    if bad.is_empty() {
+        return markup;
+    }
+
+    let mut rv = Vec::with_capacity(markup.len() + bad.len() * 2);
+    for item in markup {
+        match item {
+            Markup::Content { span, .. } => {
+                let mut start = span.start;
+                for b in bad
+                    .iter()
+                    .filter(|b| b.start >= span.start && b.end <= span.end)
+                {
+                    if b.start > start {
+                        rv.push(content(source, Span::new(start, b.start)));
+                    }
+                    rv.push(Markup::Error {
+                        text: source[b.start..b.end].to_owned(),
+                        span: *b,
+                    });
+                    start = b.end;
+                }
+                if start < span.end {
+                    rv.push(content(source, Span::new(start, span.end)));
+                }
+            }
+            item => rv.push(item),
+        }
+    }
+    rv
+}
+
+fn content(source: &str, span: Span) -> Markup {
+    Markup::Content {
+        text: source[span.start..span.end].to_owned(),
+        span,
+    }
+}
@@ -0,0 +1,1721 @@
+//! Render parsed templates from Rust, without the Python engine.
+//!
+//! Template data is given as a `serde_json::Value`. Undefined variables
+//! render as empty strings and are falsy, like the Python engine's default
+//! `Undefined` type.
+//!
+//! Range literals are output as `start..stop`, and `for` and `tablerow` loop
+//! over them without building them, so a loop over a huge range with a
+//! `limit` is cheap. Anywhere else, a range becomes an array of its items.
+//!
+//! `Renderer::default_loop_limit` caps the items rendered by loops that
+//! don't give a smaller `limit`, like the Python engine's
+//! `default_loop_limit`. `Renderer::render_with_truncated_loops` reports the
+//! loops it cut short.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde_json::{Map, Number, Value};
+
+use crate::ast::{
+    walk_filter, BooleanExpression, BooleanOperator, CommonArgument, CompareOperator, ElseTag,
+    Filter, FilteredExpression, InlineCondition, KeywordArgument, Node, Parameter, Primitive,
+    Template, Visitor,
+};
+use crate::errors::LiquidError;
+use crate::filters::{self, FilterArgs};
+use crate::loader::Loader;
+use crate::parser::{grow_stack, LiquidParser};
+use crate::query::eval::FunctionExtensions;
+use crate::query::{Query, Segment, Selector};
+use crate::shared::SharedVec;
+use crate::source_map::{Mapping, SourceMap};
+use crate::span::Span;
+use crate::whitespace::{SmartTrim, TrimPolicy, Whitespace};
+
+/// Maximum number of nested `include` and `render` tags before giving up,
+/// like the Python engine's `context_depth_limit`.
+const CONTEXT_DEPTH_LIMIT: usize = 30;
+
+/// Maximum number of items in a range that is built as an array, rather
+/// than looped over by `for` or `tablerow`.
+const RANGE_LENGTH_LIMIT: usize = 1_000_000;
+
+/// Renders a parsed template with JSON-like data.
+pub struct Renderer<'a> {
+    template: &'a Template,
+    loader: Option<&'a dyn Loader>,
+    /// Whitespace control applied to markup that does not specify its own.
+    pub trim: Whitespace,
+    /// What `~` whitespace control does to the text beside it.
+    pub smart: SmartTrim,
+    /// The most items a `for` or `tablerow` loop renders, unless it gives a
+    /// smaller `limit`.
+    pub default_loop_limit: Option<usize>,
+    /// Implementations of JSONPath function extensions called by queries in
+    /// the template, usually the parser's `QueryParser::function_extensions`.
+    pub function_extensions: Arc<FunctionExtensions>,
+    /// Output mappings, while rendering with a source map.
+    recording: RefCell<Option<Recording>>,
+    /// Partial templates being rendered, innermost last, where `None` is a
+    /// macro defined in the template being rendered.
+    templates: RefCell<Vec<Option<String>>>,
+    /// Loops cut short by _default_loop_limit_ during the current render.
+    truncated_loops: RefCell<Vec<TruncatedLoop>>,
+}
+
+/// A loop that rendered fewer items than it would have without
+/// `Renderer::default_loop_limit`.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncatedLoop {
+    /// The span of the `for` or `tablerow` tag, including its block.
+    pub span: Span,
+    /// The name of the partial template that the loop belongs to, or `None`
+    /// for the template being rendered.
+    pub template: Option<String>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TruncatedLoop {
+    fn __repr__(&self) -> String {
+        format!(
+            "TruncatedLoop(span=({}, {}), template={})",
+            self.span.start,
+            self.span.end,
+            self.template
+                .as_ref()
+                .map_or_else(|| "None".to_owned(), |name| format!("{name:?}"))
+        )
+    }
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(template: &'a Template) -> Self {
+        Self {
+            template,
+            loader: None,
+            trim: Whitespace::Plus,
+            smart: SmartTrim::Newlines,
+            default_loop_limit: None,
+            function_extensions: Arc::default(),
+            recording: RefCell::new(None),
+            templates: RefCell::new(Vec::new()),
+            truncated_loops: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Resolve `include` and `render` targets using _loader_. Without a
+    /// loader, those tags raise a `TemplateNotFound` error.
+    pub fn with_loader(mut self, loader: &'a dyn Loader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Render the template with _data_, which should be a JSON object. Any
+    /// other value is treated as an empty object.
+    pub fn render(&self, data: &Value) -> Result<String, LiquidError> {
+        let globals = data.as_object().cloned().unwrap_or_default();
+        let mut context = Context::new(globals, 0);
+        self.truncated_loops.borrow_mut().clear();
+        let mut out = String::new();
+        self.render_block(
+            &self.template.nodes,
+            &mut context,
+            &mut out,
+            Whitespace::Default,
+            Whitespace::Default,
+        )?;
+        Ok(out)
+    }
+
+    /// Render the template with _data_, along with a source map from the
+    /// output back to the spans of the nodes that wrote it.
+    ///
+    /// Output written inside `capture` and `ifchanged` blocks is buffered
+    /// before it reaches the output, so it maps to the `ifchanged` tag that
+    /// wrote it, not the nodes inside its block.
+    pub fn render_with_source_map(&self, data: &Value) -> Result<(String, SourceMap), LiquidError> {
+        self.recording.replace(Some(Recording::default()));
+        let result = self.render(data);
+        let recording = self.recording.take().unwrap_or_default();
+        Ok((result?, SourceMap::new(recording.mappings)))
+    }
+
+    /// Render the template with _data_, along with every loop that
+    /// _default_loop_limit_ cut short, in the order they were rendered.
+    pub fn render_with_truncated_loops(
+        &self,
+        data: &Value,
+    ) -> Result<(String, Vec<TruncatedLoop>), LiquidError> {
+        let output = self.render(data)?;
+        Ok((output, self.truncated_loops.take()))
+    }
+
+    /// Render _nodes_, where _left_ and _right_ are the whitespace control
+    /// markers of the markup either side of the block.
+    fn render_block(
+        &self,
+        nodes: &[Node],
+        context: &mut Context,
+        out: &mut String,
+        left: Whitespace,
+        right: Whitespace,
+    ) -> Result<Flow, LiquidError> {
+        for (i, node) in nodes.iter().enumerate() {
+            if let Node::Content { text, .. } = node {
+                let left_trim = if i == 0 {
+                    left
+                } else {
+                    trailing_wc(&nodes[i - 1])
+                };
+                let right_trim = nodes.get(i + 1).map_or(right, leading_wc);
+                let start = out.len();
+                out.push_str(&self.trim_text(text, left_trim, right_trim));
+                self.record(start, out, node.span());
+                continue;
+            }
+
+            let start = out.len();
+            let flow = grow_stack(|| self.render_node(node, context, out))?;
+            self.record(start, out, node.span());
+            match flow {
+                Flow::Normal => (),
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn render_node(
+        &self,
+        node: &Node,
+        context: &mut Context,
+        out: &mut String,
+    ) -> Result<Flow, LiquidError> {
+        match node {
+            Node::Content { text, .. } => out.push_str(text),
+            Node::Raw { text, .. } => out.push_str(text),
+            Node::Comment { .. } | Node::DocTag { .. } => (),
+            Node::Output { expression, .. } | Node::EchoTag { expression, .. } => {
+                match expression {
+                    // A range is output as its bounds, not its items.
+                    FilteredExpression {
+                        left: Primitive::Range { start, stop, .. },
+                        filters: None,
+                        condition: None,
+                        ..
+                    } => {
+                        let start = self.eval_int(start, context)?;
+                        let stop = self.eval_int(stop, context)?;
+                        out.push_str(&format!("{start}..{stop}"));
+                    }
+                    expression => {
+                        let value = self.eval_filtered(expression, context)?;
+                        out.push_str(&to_liquid_string(&value));
+                    }
+                }
+            }
+            Node::AssignTag {
+                identifier,
+                expression,
+                ..
+            } => {
+                let value = self.eval_filtered(expression, context)?;
+                context.locals.insert(identifier.to_owned(), value);
+            }
+            Node::CaptureTag {
+                wc,
+                identifier,
+                block,
+                end_wc,
+                ..
+            } => {
+                let mut buf = String::new();
+                let flow =
+                    self.buffered(|| self.render_block(block, context, &mut buf, wc.1, end_wc.0))?;
+                context
+                    .locals
+                    .insert(identifier.to_owned(), Value::String(buf));
+                return Ok(flow);
+            }
+            Node::IfchangedTag {
+                wc, block, end_wc, ..
+            } => {
+                let mut buf = String::new();
+                let flow =
+                    self.buffered(|| self.render_block(block, context, &mut buf, wc.1, end_wc.0))?;
+                if context.ifchanged.as_ref() != Some(&buf) {
+                    out.push_str(&buf);
+                    context.ifchanged = Some(buf);
+                }
+                return Ok(flow);
+            }
+            Node::CaseTag {
+                expression,
+                whens,
+                default,
+                end_wc,
+                ..
+            } => {
+                let value = self.eval_primitive(expression, context)?;
+                let mut matched = false;
+
+                for (i, when) in whens.iter().enumerate() {
+                    let right = whens
+                        .get(i + 1)
+                        .map(|w| w.wc.0)
+                        .or(default.as_ref().map(|d| d.wc.0))
+                        .unwrap_or(end_wc.0);
+
+                    for arg in &when.args {
+                        if liquid_eq(&value, &self.eval_primitive(arg, context)?) {
+                            matched = true;
+                            match self.render_block(&when.block, context, out, when.wc.1, right)? {
+                                Flow::Normal => break,
+                                flow => return Ok(flow),
+                            }
+                        }
+                    }
+                }
+
+                if !matched {
+                    return self.render_else(default, context, out, end_wc.0);
+                }
+            }
+            Node::CycleTag { name, args, .. } => {
+                if !args.is_empty() {
+                    // Groups with the same name but different items cycle
+                    // independently, like the Python engine. A variable that
+                    // isn't defined names the group as it's written, so
+                    // `{% cycle foo: ... %}` works like a literal name.
+                    let name = match name {
+                        Some(name) => Some(match self.eval_primitive(name, context)? {
+                            Value::Null => name.to_string(),
+                            value => to_liquid_string(&value),
+                        }),
+                        None => None,
+                    };
+                    let items = args
+                        .iter()
+                        .map(|a| a.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let key = (name, items);
+                    let index = context.cycles.entry(key).or_insert(0);
+                    let arg = &args[*index % args.len()];
+                    *index += 1;
+                    let value = self.eval_primitive(arg, context)?;
+                    out.push_str(&to_liquid_string(&value));
+                }
+            }
+            Node::IncrementTag { name, .. } => {
+                let counter = context.counters.entry(name.to_owned()).or_insert(0);
+                out.push_str(&counter.to_string());
+                *counter += 1;
+            }
+            Node::DecrementTag { name, .. } => {
+                let counter = context.counters.entry(name.to_owned()).or_insert(0);
+                *counter -= 1;
+                out.push_str(&counter.to_string());
+            }
+            Node::ForTag {
+                wc,
+                name,
+                value_name,
+                iterable,
+                limit,
+                offset,
+                reversed,
+                block,
+                default,
+                end_wc,
+                span,
+                ..
+            } => {
+                let items = self.eval_iterable(iterable, context)?;
+                let limit = self.eval_opt_int(limit, context)?;
+                let key = format!("{name}-{iterable}");
+
+                let offset = match offset {
+                    Some(Primitive::StringLiteral { value, .. }) if value == "continue" => {
+                        Some(context.stop_index.get(&key).copied().unwrap_or(0))
+                    }
+                    offset => self
+                        .eval_opt_int(offset, context)?
+                        .map(|i| i.max(0) as usize),
+                };
+
+                let (start, stop) = self.loop_bounds(items.len(), offset, limit, *span);
+                context.stop_index.insert(key.clone(), stop);
+                let length = stop - start;
+                let items = items.slice(start, stop, *reversed);
+
+                if length == 0 {
+                    return self.render_else(default, context, out, end_wc.0);
+                }
+
+                let right = default.as_ref().map_or(end_wc.0, |d| d.wc.0);
+                let parentloop = context.lookup("forloop").unwrap_or(Value::Null);
+                context.frames.push(Map::new());
+
+                for (index, item) in items.enumerate() {
+                    let frame = context.frames.last_mut().unwrap();
+                    match value_name {
+                        // Key/value pairs are unpacked. Any other item is the
+                        // key, with a null value.
+                        Some(value_name) => {
+                            let (key, value) = match item {
+                                Value::Array(pair) if pair.len() == 2 => {
+                                    let mut pair = pair.into_iter();
+                                    (pair.next().unwrap(), pair.next().unwrap())
+                                }
+                                item => (item, Value::Null),
+                            };
+                            frame.insert(name.to_owned(), key);
+                            frame.insert(value_name.to_owned(), value);
+                        }
+                        None => {
+                            frame.insert(name.to_owned(), item);
+                        }
+                    }
+                    frame.insert(
+                        "forloop".to_owned(),
+                        forloop(&key, index, length, parentloop.clone()),
+                    );
+
+                    match self.render_block(block, context, out, wc.1, right) {
+                        Ok(Flow::Break) => break,
+                        Ok(_) => (),
+                        Err(err) => {
+                            context.frames.pop();
+                            return Err(err);
+                        }
+                    }
+                }
+
+                context.frames.pop();
+            }
+            Node::TablerowTag {
+                wc,
+                name,
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                end_wc,
+                span,
+                ..
+            } => {
+                let items = self.eval_iterable(iterable, context)?;
+                let cols = self.eval_opt_int(cols, context)?;
+                let limit = self.eval_opt_int(limit, context)?;
+                let offset = self.eval_opt_int(offset, context)?;
+
+                let offset = offset.map(|i| i.max(0) as usize);
+                let (start, stop) = self.loop_bounds(items.len(), offset, limit, *span);
+                let length = stop - start;
+                let items = items.slice(start, stop, false);
+                let cols = match cols {
+                    Some(cols) if cols > 0 => cols as usize,
+                    _ => length,
+                };
+
+                out.push_str("<tr class=\"row1\">\n");
+                context.frames.push(Map::new());
+
+                for (index, item) in items.enumerate() {
+                    let frame = context.frames.last_mut().unwrap();
+                    frame.insert(name.to_owned(), item);
+                    frame.insert("tablerowloop".to_owned(), tablerowloop(index, length, cols));
+
+                    out.push_str(&format!("<td class=\"col{}\">", index % cols + 1));
+                    let flow = self.render_block(block, context, out, wc.1, end_wc.0);
+                    out.push_str("</td>");
+
+                    match flow {
+                        Ok(Flow::Break) => break,
+                        Ok(_) => (),
+                        Err(err) => {
+                            context.frames.pop();
+                            return Err(err);
+                        }
+                    }
+
+                    if (index + 1) % cols == 0 && index + 1 < length {
+                        out.push_str(&format!(
+                            "</tr>\n<tr class=\"row{}\">",
+                            (index + 1) / cols + 1
+                        ));
+                    }
+                }
+
+                context.frames.pop();
+                out.push_str("</tr>\n");
+            }
+            Node::BreakTag { .. } => return Ok(Flow::Break),
+            Node::ContinueTag { .. } => return Ok(Flow::Continue),
+            Node::IfTag {
+                wc,
+                condition,
+                block,
+                alternatives,
+                default,
+                end_wc,
+                ..
+            } => {
+                let truthy = self.eval_boolean(condition, context)?;
+                return self.render_conditional(
+                    truthy,
+                    wc.1,
+                    block,
+                    alternatives,
+                    default,
+                    end_wc.0,
+                    context,
+                    out,
+                );
+            }
+            Node::UnlessTag {
+                wc,
+                condition,
+                block,
+                alternatives,
+                default,
+                end_wc,
+                ..
+            } => {
+                let truthy = !self.eval_boolean(condition, context)?;
+                return self.render_conditional(
+                    truthy,
+                    wc.1,
+                    block,
+                    alternatives,
+                    default,
+                    end_wc.0,
+                    context,
+                    out,
+                );
+            }
+            Node::IncludeTag {
+                target,
+                variable,
+                alias,
+                args,
+                span,
+                ..
+            } => {
+                // Like the Python engine, templates rendered with `render`
+                // can't use `include`.
+                if context.isolated {
+                    return Err(LiquidError::ext(
+                        "include usage is not allowed in this context".to_owned(),
+                    )
+                    .with_span(*span));
+                }
+
+                let name = to_liquid_string(&self.eval_primitive(target, context)?);
+                let template = self.load(&name, context, *span)?;
+                let mut namespace = self.eval_arguments(args, context)?;
+                let key = alias.clone().unwrap_or_else(|| partial_key(&name));
+                let value = match variable {
+                    Some(variable) => Some(self.eval_primitive(variable, context)?),
+                    None => None,
+                };
+
+                context.depth += 1;
+                let result = match value {
+                    Some(Value::Array(items)) => {
+                        let mut flow = Ok(Flow::Normal);
+                        for item in items {
+                            namespace.insert(key.clone(), item);
+                            context.frames.push(namespace.clone());
+                            flow = self.render_partial(&name, &template, context, out);
+                            context.frames.pop();
+                            if !matches!(flow, Ok(Flow::Normal)) {
+                                break;
+                            }
+                        }
+                        flow
+                    }
+                    value => {
+                        if let Some(value) = value {
+                            namespace.insert(key, value);
+                        }
+                        context.frames.push(namespace);
+                        let flow = self.render_partial(&name, &template, context, out);
+                        context.frames.pop();
+                        flow
+                    }
+                };
+                context.depth -= 1;
+                return result;
+            }
+            Node::RenderTag {
+                target,
+                repeat,
+                variable,
+                alias,
+                args,
+                span,
+                ..
+            } => {
+                let name = to_liquid_string(&self.eval_primitive(target, context)?);
+                let template = self.load(&name, context, *span)?;
+                let mut globals = context.globals.clone();
+                globals.extend(self.eval_arguments(args, context)?);
+                let key = alias.clone().unwrap_or_else(|| partial_key(&name));
+                let value = match variable {
+                    Some(variable) => Some(self.eval_primitive(variable, context)?),
+                    None => None,
+                };
+
+                match value {
+                    Some(Value::Array(items)) if *repeat => {
+                        let length = items.len();
+                        for (index, item) in items.into_iter().enumerate() {
+                            let mut ctx = Context::new(globals.clone(), context.depth + 1);
+                            ctx.isolated = true;
+                            ctx.locals.insert(key.clone(), item);
+                            ctx.locals.insert(
+                                "forloop".to_owned(),
+                                forloop(&key, index, length, Value::Null),
+                            );
+                            self.render_partial(&name, &template, &mut ctx, out)?;
+                        }
+                    }
+                    value => {
+                        let mut ctx = Context::new(globals, context.depth + 1);
+                        ctx.isolated = true;
+                        if let Some(value) = value {
+                            ctx.locals.insert(key, value);
+                        }
+                        self.render_partial(&name, &template, &mut ctx, out)?;
+                    }
+                }
+            }
+            Node::LiquidTag { block, .. } => {
+                return self.render_block(
+                    block,
+                    context,
+                    out,
+                    Whitespace::Default,
+                    Whitespace::Default,
+                );
+            }
+            Node::ExtendsTag { span, .. } => {
+                return Err(LiquidError::ext(
+                    "template inheritance is not supported by this renderer".to_string(),
+                )
+                .with_span(*span));
+            }
+            Node::BlockTag {
+                wc, block, end_wc, ..
+            } => {
+                return self.render_block(block, context, out, wc.1, end_wc.0);
+            }
+            Node::MacroTag {
+                wc,
+                name,
+                params,
+                block,
+                end_wc,
+                ..
+            } => {
+                context.macros.insert(
+                    name.to_owned(),
+                    Macro {
+                        params: params.clone(),
+                        block: block.clone(),
+                        wc: (wc.1, end_wc.0),
+                        template: self.current_template(),
+                    },
+                );
+            }
+            Node::CallTag {
+                name, args, span, ..
+            } => {
+                self.render_call(name, args, *span, context, out)?;
+            }
+            Node::TranslateTag {
+                wc,
+                args,
+                block,
+                plural,
+                end_wc,
+                ..
+            } => {
+                // Without a message catalog, choose the plural form like
+                // `ngettext` does for English.
+                let namespace = self.eval_arguments(args, context)?;
+                let many = namespace
+                    .get("count")
+                    .and_then(Value::as_f64)
+                    .is_some_and(|n| n != 1.0);
+
+                context.frames.push(namespace);
+                let flow = match plural {
+                    Some(plural) if many => {
+                        self.render_block(&plural.block, context, out, plural.wc.1, end_wc.0)
+                    }
+                    _ => {
+                        let right = plural.as_ref().map_or(end_wc.0, |p| p.wc.0);
+                        self.render_block(block, context, out, wc.1, right)
+                    }
+                };
+                context.frames.pop();
+                return flow;
+            }
+            Node::TagExtension { name, span, .. } => {
+                return Err(
+                    LiquidError::ext(format!("no renderer for custom tag '{name}'"))
+                        .with_span(*span),
+                );
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_conditional(
+        &self,
+        truthy: bool,
+        left: Whitespace,
+        block: &[Node],
+        alternatives: &[crate::ast::ElsifTag],
+        default: &Option<ElseTag>,
+        end: Whitespace,
+        context: &mut Context,
+        out: &mut String,
+    ) -> Result<Flow, LiquidError> {
+        let next = |i: usize| {
+            alternatives
+                .get(i)
+                .map(|a| a.wc.0)
+                .or(default.as_ref().map(|d| d.wc.0))
+                .unwrap_or(end)
+        };
+
+        if truthy {
+            return self.render_block(block, context, out, left, next(0));
+        }
+
+        for (i, alternative) in alternatives.iter().enumerate() {
+            if self.eval_boolean(&alternative.condition, context)? {
+                return self.render_block(
+                    &alternative.block,
+                    context,
+                    out,
+                    alternative.wc.1,
+                    next(i + 1),
+                );
+            }
+        }
+
+        self.render_else(default, context, out, end)
+    }
+
+    fn render_else(
+        &self,
+        default: &Option<ElseTag>,
+        context: &mut Context,
+        out: &mut String,
+        end: Whitespace,
+    ) -> Result<Flow, LiquidError> {
+        match default {
+            Some(default) => self.render_block(&default.block, context, out, default.wc.1, end),
+            None => Ok(Flow::Normal),
+        }
+    }
+
+    fn render_partial(
+        &self,
+        name: &str,
+        template: &Template,
+        context: &mut Context,
+        out: &mut String,
+    ) -> Result<Flow, LiquidError> {
+        self.in_template(Some(name.to_owned()), || {
+            self.render_block(
+                &template.nodes,
+                context,
+                out,
+                Whitespace::Default,
+                Whitespace::Default,
+            )
+        })
+    }
+
+    /// Render the macro called _name_ with _args_. Parameters without a
+    /// matching argument take their default value, or `nil`. Keyword
+    /// arguments that don't match a parameter are still available to the
+    /// macro's block.
+    fn render_call(
+        &self,
+        name: &str,
+        args: &[CommonArgument],
+        span: Span,
+        context: &mut Context,
+        out: &mut String,
+    ) -> Result<(), LiquidError> {
+        check_depth(context, span)?;
+        let macro_ = context.macros.get(name).cloned().ok_or_else(|| {
+            LiquidError::name(format!("undefined macro '{name}'")).with_span(span)
+        })?;
+
+        let mut namespace = Map::new();
+        let mut positional = args.iter().filter(|arg| arg.name.is_none());
+        for param in &macro_.params {
+            let value = match (positional.next(), &param.default) {
+                (Some(arg), _) => self.eval_primitive(&arg.value, context)?,
+                (None, Some(default)) => self.eval_primitive(default, context)?,
+                (None, None) => Value::Null,
+            };
+            namespace.insert(param.name.to_owned(), value);
+        }
+        for arg in args {
+            if let Some(name) = &arg.name {
+                let value = self.eval_primitive(&arg.value, context)?;
+                namespace.insert(name.to_owned(), value);
+            }
+        }
+
+        let mut ctx = Context::new(context.globals.clone(), context.depth + 1);
+        ctx.macros = context.macros.clone();
+        ctx.isolated = context.isolated;
+        ctx.frames.push(namespace);
+        self.in_template(macro_.template.clone(), || {
+            self.render_block(&macro_.block, &mut ctx, out, macro_.wc.0, macro_.wc.1)
+        })?;
+        Ok(())
+    }
+
+    /// Map the output written since _start_ to the node at _source_, if
+    /// recording a source map.
+    fn record(&self, start: usize, out: &str, source: Span) {
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            if recording.buffers == 0 && out.len() > start {
+                let template = self.current_template();
+                recording.mappings.push(Mapping {
+                    output: Span::new(start, out.len()),
+                    source,
+                    template,
+                });
+            }
+        }
+    }
+
+    /// Call _f_, which renders into a buffer rather than the output.
+    fn buffered<T>(&self, f: impl FnOnce() -> T) -> T {
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            recording.buffers += 1;
+        }
+        let rv = f();
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            recording.buffers -= 1;
+        }
+        rv
+    }
+
+    /// Call _f_, which renders nodes from the partial template _name_, or
+    /// from the template being rendered if _name_ is `None`.
+    fn in_template<T>(&self, name: Option<String>, f: impl FnOnce() -> T) -> T {
+        self.templates.borrow_mut().push(name);
+        let rv = f();
+        self.templates.borrow_mut().pop();
+        rv
+    }
+
+    /// The name of the partial template being rendered.
+    fn current_template(&self) -> Option<String> {
+        self.templates.borrow().last().cloned().flatten()
+    }
+
+    /// Like `bounds`, but taking at most _default_loop_limit_ items unless
+    /// _limit_ is smaller, and recording the loop at _span_ if that leaves
+    /// out any items.
+    fn loop_bounds(
+        &self,
+        len: usize,
+        offset: Option<usize>,
+        limit: Option<i64>,
+        span: Span,
+    ) -> (usize, usize) {
+        let default = self
+            .default_loop_limit
+            .filter(|&default| limit.is_none_or(|limit| limit as i128 > default as i128));
+        let Some(default) = default else {
+            return bounds(len, offset, limit);
+        };
+
+        let (start, stop) = bounds(
+            len,
+            offset,
+            Some(i64::try_from(default).unwrap_or(i64::MAX)),
+        );
+        if len - start > default {
+            self.truncated_loops.borrow_mut().push(TruncatedLoop {
+                span,
+                template: self.current_template(),
+            });
+        }
+        (start, stop)
+    }
+
+    fn load(&self, name: &str, context: &Context, span: Span) -> Result<Template, LiquidError> {
+        check_depth(context, span)?;
+
+        let loader = self
+            .loader
+            .ok_or_else(|| LiquidError::not_found(name).with_span(span))?;
+
+        loader
+            .get_source(name)
+            .map_err(|err| err.with_source_name(name))
+            .and_then(|source| LiquidParser::new().parse_named(&source, name))
+    }
+
+    fn trim_text<'t>(&self, text: &'t str, left: Whitespace, right: Whitespace) -> Cow<'t, str> {
+        TrimPolicy {
+            default: self.trim,
+            smart: self.smart,
+        }
+        .trim(text, left, right)
+    }
+
+    fn eval_filtered(
+        &self,
+        expression: &FilteredExpression,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        let Some(condition) = &expression.condition else {
+            let value = self.eval_primitive(&expression.left, context)?;
+            return self.apply_filters(value, &expression.filters, context);
+        };
+
+        let value = if self.eval_boolean(&condition.expr, context)? {
+            let value = self.eval_primitive(&expression.left, context)?;
+            self.apply_filters(value, &expression.filters, context)?
+        } else {
+            self.eval_alternative(condition, context)?
+        };
+
+        self.apply_filters(value, &condition.tail_filters, context)
+    }
+
+    /// Evaluate the alternative of _condition_, or of the first chained `?:`
+    /// condition that doesn't hold.
+    fn eval_alternative(
+        &self,
+        condition: &InlineCondition,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        let Some(alternative) = &condition.alternative else {
+            return Ok(Value::Null);
+        };
+        match &condition.alternative_condition {
+            Some(next) if !self.eval_boolean(&next.expr, context)? => {
+                self.eval_alternative(next, context)
+            }
+            _ => {
+                let value = self.eval_primitive(alternative, context)?;
+                self.apply_filters(value, &condition.alternative_filters, context)
+            }
+        }
+    }
+
+    fn apply_filters(
+        &self,
+        mut value: Value,
+        filters: &Option<Vec<Filter>>,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        for filter in filters.as_deref().unwrap_or_default() {
+            if let Some(CommonArgument {
+                value: Primitive::Lambda { params, expr, .. },
+                ..
+            }) = filter.args.as_deref().and_then(<[_]>::first)
+            {
+                value = self.apply_lambda_filter(filter, value, params, expr, context)?;
+                continue;
+            }
+
+            let Some(function) = filters::standard_filter(&filter.name) else {
+                return Err(
+                    LiquidError::name(format!("unknown filter '{}'", filter.name))
+                        .with_span(filter.span),
+                );
+            };
+
+            let mut args = FilterArgs::default();
+            for arg in filter.args.as_deref().unwrap_or_default() {
+                let arg_value = self.eval_primitive(&arg.value, context)?;
+                match &arg.name {
+                    Some(name) => {
+                        args.keywords.insert(name.to_owned(), arg_value);
+                    }
+                    None => args.positional.push(arg_value),
+                }
+            }
+
+            value = function(value, &args).map_err(|err| match err.span {
+                Some(_) => err,
+                None => err.with_span(filter.span),
+            })?;
+        }
+        Ok(value)
+    }
+
+    /// Apply _filter_, whose first argument is a lambda with _params_ and
+    /// body _expr_, to _value_.
+    fn apply_lambda_filter(
+        &self,
+        filter: &Filter,
+        value: Value,
+        params: &[String],
+        expr: &BooleanExpression,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        let Some(function) = filters::standard_lambda_filter(&filter.name) else {
+            return Err(LiquidError::typ(format!(
+                "'{}' does not accept a lambda argument",
+                filter.name
+            ))
+            .with_span(filter.span));
+        };
+
+        let items = filters::array::sequence(value);
+        let mut results = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let mut frame = Map::new();
+            frame.insert(params[0].to_owned(), item.clone());
+            if let Some(name) = params.get(1) {
+                frame.insert(name.to_owned(), Value::from(index));
+            }
+
+            context.frames.push(frame);
+            let result = self.eval_operand(expr, context);
+            context.frames.pop();
+            results.push(result?);
+        }
+
+        function(items, results).map_err(|err| match err.span {
+            Some(_) => err,
+            None => err.with_span(filter.span),
+        })
+    }
+
+    fn eval_arguments(
+        &self,
+        args: &[KeywordArgument],
+        context: &mut Context,
+    ) -> Result<Map<String, Value>, LiquidError> {
+        let mut namespace = Map::new();
+        for arg in args {
+            let value = self.eval_primitive(&arg.value, context)?;
+            namespace.insert(arg.name.to_owned(), value);
+        }
+        Ok(namespace)
+    }
+
+    fn eval_boolean(
+        &self,
+        expression: &BooleanExpression,
+        context: &mut Context,
+    ) -> Result<bool, LiquidError> {
+        match expression {
+            BooleanExpression::Primitive { expr } => {
+                Ok(is_truthy(&self.eval_primitive(expr, context)?))
+            }
+            BooleanExpression::LogicalNot { expr, .. } => Ok(!self.eval_boolean(expr, context)?),
+            BooleanExpression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => Ok(match operator {
+                BooleanOperator::And => {
+                    self.eval_boolean(left, context)? && self.eval_boolean(right, context)?
+                }
+                BooleanOperator::Or => {
+                    self.eval_boolean(left, context)? || self.eval_boolean(right, context)?
+                }
+            }),
+            BooleanExpression::Comparison {
+                left,
+                operator,
+                right,
+                span,
+            } => self.eval_comparison(left, *operator, right, *span, context),
+        }
+    }
+
+    fn eval_comparison(
+        &self,
+        left: &BooleanExpression,
+        operator: CompareOperator,
+        right: &BooleanExpression,
+        span: Span,
+        context: &mut Context,
+    ) -> Result<bool, LiquidError> {
+        // `empty` and `blank` are only meaningful in equality tests.
+        let special = |expr: &BooleanExpression| match expr {
+            BooleanExpression::Primitive {
+                expr: Primitive::Empty { .. },
+            } => Some(is_empty as fn(&Value) -> bool),
+            BooleanExpression::Primitive {
+                expr: Primitive::Blank { .. },
+            } => Some(is_blank as fn(&Value) -> bool),
+            _ => None,
+        };
+
+        if let Some(test) = special(right).or(special(left)) {
+            let other = if special(right).is_some() {
+                left
+            } else {
+                right
+            };
+            let value = self.eval_operand(other, context)?;
+            return Ok(match operator {
+                CompareOperator::Eq => test(&value),
+                CompareOperator::Ne => !test(&value),
+                _ => false,
+            });
+        }
+
+        let left = self.eval_operand(left, context)?;
+        let right = self.eval_operand(right, context)?;
+
+        match operator {
+            CompareOperator::Eq => Ok(liquid_eq(&left, &right)),
+            CompareOperator::Ne => Ok(!liquid_eq(&left, &right)),
+            CompareOperator::Lt => liquid_lt(&left, &right, span),
+            CompareOperator::Gt => liquid_lt(&right, &left, span),
+            CompareOperator::Le => Ok(liquid_eq(&left, &right) || liquid_lt(&left, &right, span)?),
+            CompareOperator::Ge => Ok(liquid_eq(&left, &right) || liquid_lt(&right, &left, span)?),
+            CompareOperator::Contains => liquid_contains(&left, &right, span),
+            CompareOperator::In => liquid_contains(&right, &left, span),
+        }
+    }
+
+    fn eval_operand(
+        &self,
+        expression: &BooleanExpression,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        match expression {
+            BooleanExpression::Primitive { expr } => self.eval_primitive(expr, context),
+            expr => Ok(Value::Bool(self.eval_boolean(expr, context)?)),
+        }
+    }
+
+    fn eval_primitive(
+        &self,
+        primitive: &Primitive,
+        context: &mut Context,
+    ) -> Result<Value, LiquidError> {
+        Ok(match primitive {
+            Primitive::TrueLiteral { .. } => Value::Bool(true),
+            Primitive::FalseLiteral { .. } => Value::Bool(false),
+            Primitive::NullLiteral { .. } => Value::Null,
+            Primitive::Empty { .. } | Primitive::Blank { .. } => Value::String(String::new()),
+            Primitive::Integer { value, .. } => Value::from(*value),
+            Primitive::Float { value, .. } => {
+                Number::from_f64(*value).map_or(Value::Null, Value::Number)
+            }
+            Primitive::StringLiteral { value, .. } => Value::String(value.to_owned()),
+            Primitive::Range { span, .. } => {
+                let range = self.eval_range(primitive, context)?;
+                if range.len > RANGE_LENGTH_LIMIT {
+                    return Err(LiquidError::typ(format!(
+                        "range has {} items, the limit is {RANGE_LENGTH_LIMIT}",
+                        range.len
+                    ))
+                    .with_span(*span));
+                }
+                Value::Array(range.slice(0, range.len, false).collect())
+            }
+            Primitive::Query { path, span } => {
+                self.resolve(path, context).map_err(|err| match err.span {
+                    Some(_) => err,
+                    None => err.with_span(*span),
+                })?
+            }
+            Primitive::Lambda { span, .. } => {
+                return Err(LiquidError::typ(
+                    "lambda expressions are only allowed as filter arguments".to_string(),
+                )
+                .with_span(*span))
+            }
+        })
+    }
+
+    fn eval_int(&self, primitive: &Primitive, context: &mut Context) -> Result<i64, LiquidError> {
+        match self.eval_primitive(primitive, context)? {
+            Value::Number(n) if n.is_i64() => Ok(n.as_i64().unwrap()),
+            Value::Number(n) => Ok(n.as_f64().unwrap_or_default() as i64),
+            Value::String(s) => s.trim().parse::<i64>().map_err(|_| {
+                LiquidError::typ(format!("expected an integer, found '{s}'"))
+                    .with_span(primitive.span())
+            }),
+            value => Err(LiquidError::typ(format!(
+                "expected an integer, found {}",
+                type_name(&value)
+            ))
+            .with_span(primitive.span())),
+        }
+    }
+
+    fn eval_opt_int(
+        &self,
+        primitive: &Option<Primitive>,
+        context: &mut Context,
+    ) -> Result<Option<i64>, LiquidError> {
+        match primitive {
+            Some(primitive) => self.eval_int(primitive, context).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Evaluate the bounds of _primitive_, which must be a range, without
+    /// building its items.
+    fn eval_range(
+        &self,
+        primitive: &Primitive,
+        context: &mut Context,
+    ) -> Result<IntRange, LiquidError> {
+        let Primitive::Range {
+            start, stop, step, ..
+        } = primitive
+        else {
+            unreachable!("expected a range")
+        };
+
+        let start = self.eval_int(start, context)?;
+        let stop = self.eval_int(stop, context)?;
+        let step = match step {
+            Some(step) => self.eval_primitive(step, context)?.as_i64().unwrap_or(1),
+            None => 1,
+        };
+        Ok(IntRange::new(start, stop, step))
+    }
+
+    /// Evaluate the collection a `for` or `tablerow` tag loops over. Range
+    /// literals aren't built until the loop's `offset` and `limit` are
+    /// known.
+    fn eval_iterable(
+        &self,
+        primitive: &Primitive,
+        context: &mut Context,
+    ) -> Result<Items, LiquidError> {
+        if let Primitive::Range { .. } = primitive {
+            return Ok(Items::Range(self.eval_range(primitive, context)?));
+        }
+
+        Ok(Items::Values(
+            match self.eval_primitive(primitive, context)? {
+                Value::Array(items) => items,
+                Value::Object(obj) => obj
+                    .into_iter()
+                    .map(|(k, v)| Value::Array(vec![Value::String(k), v]))
+                    .collect(),
+                Value::String(s) => s.chars().map(|c| Value::String(c.to_string())).collect(),
+                Value::Null => Vec::new(),
+                value => {
+                    return Err(LiquidError::typ(format!(
+                        "expected an iterable at '{primitive}', found {}",
+                        type_name(&value)
+                    ))
+                    .with_span(primitive.span()))
+                }
+            },
+        ))
+    }
+
+    /// Resolve _query_ against the render context, returning `null` if it
+    /// does not match anything, or an array if it matches more than one node.
+    ///
+    /// Queries made up of single name and index selectors are resolved
+    /// directly. Others are evaluated against a copy of every variable in
+    /// scope.
+    fn resolve(&self, query: &Query, context: &Context) -> Result<Value, LiquidError> {
+        if !query.is_singular() || query.is_empty() {
+            let scope = Value::Object(context.scope());
+            let mut nodes = query.find_with(&scope, &self.function_extensions)?;
+            return Ok(match nodes.len() {
+                0 => Value::Null,
+                1 => nodes.pop().unwrap().value.clone(),
+                _ => Value::Array(nodes.into_iter().map(|n| n.value.clone()).collect()),
+            });
+        }
+
+        let mut keys = query.segments.iter().filter_map(|segment| match segment {
+            Segment::Child { selectors, .. } => match &selectors[0] {
+                Selector::Name { name, .. } => Some(Key::Name(name)),
+                Selector::Index { index, .. } => Some(Key::Index(*index)),
+                _ => None,
+            },
+            _ => None,
+        });
+
+        let mut value = match keys.next() {
+            Some(Key::Name(name)) => context.lookup(name),
+            _ => None,
+        };
+
+        for key in keys {
+            let Some(current) = value else { break };
+            value = select(&current, &key);
+        }
+
+        Ok(value.unwrap_or(Value::Null))
+    }
+}
+
+/// Control flow signalled by `break` and `continue` tags.
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+}
+
+/// The items a `for` or `tablerow` tag loops over.
+enum Items {
+    Values(Vec<Value>),
+    Range(IntRange),
+}
+
+impl Items {
+    fn len(&self) -> usize {
+        match self {
+            Items::Values(items) => items.len(),
+            Items::Range(range) => range.len,
+        }
+    }
+
+    /// The items from _start_ up to _stop_, which must be in bounds,
+    /// optionally in reverse order.
+    fn slice(self, start: usize, stop: usize, reversed: bool) -> Box<dyn Iterator<Item = Value>> {
+        match self {
+            Items::Values(mut items) => {
+                items.truncate(stop);
+                let items = items.into_iter().skip(start);
+                if reversed {
+                    Box::new(items.rev())
+                } else {
+                    Box::new(items)
+                }
+            }
+            Items::Range(range) => range.slice(start, stop, reversed),
+        }
+    }
+}
+
+/// The integers from _start_ to _stop_ inclusive, counting in steps of
+/// _step_, without building them.
+#[derive(Debug, Clone, Copy)]
+struct IntRange {
+    start: i64,
+    step: i64,
+    len: usize,
+}
+
+impl IntRange {
+    /// A range with no items if _step_ is less than one or _stop_ is less
+    /// than _start_.
+    fn new(start: i64, stop: i64, step: i64) -> Self {
+        let len = if step < 1 || stop < start {
+            0
+        } else {
+            let len = (i128::from(stop) - i128::from(start)) / i128::from(step) + 1;
+            usize::try_from(len).unwrap_or(usize::MAX)
+        };
+        Self { start, step, len }
+    }
+
+    /// Like `Items::slice`.
+    fn slice(self, start: usize, stop: usize, reversed: bool) -> Box<dyn Iterator<Item = Value>> {
+        // Every index is less than `len`, so every item fits in an `i64`.
+        let item = move |index: usize| {
+            Value::from((i128::from(self.start) + index as i128 * i128::from(self.step)) as i64)
+        };
+        if reversed {
+            Box::new((start..stop).rev().map(item))
+        } else {
+            Box::new((start..stop).map(item))
+        }
+    }
+}
+
+enum Key<'q> {
+    Name(&'q str),
+    Index(i64),
+}
+
+/// Render state for one template, or one partial template with its own scope.
+struct Context {
+    globals: Map<String, Value>,
+    /// Variables set by `assign` and `capture`.
+    locals: Map<String, Value>,
+    /// Loop variables and `include` arguments, innermost last.
+    frames: Vec<Map<String, Value>>,
+    counters: HashMap<String, i64>,
+    /// The next index of each `cycle` group, keyed by its name and items.
+    cycles: HashMap<(Option<String>, String), usize>,
+    /// Where each loop stopped, for `offset: continue`.
+    stop_index: HashMap<String, usize>,
+    /// The last output of any `ifchanged` block.
+    ifchanged: Option<String>,
+    /// Macros defined by `macro` tags rendered so far.
+    macros: HashMap<String, Macro>,
+    depth: usize,
+    /// Whether this is the scope of a template rendered with `render`.
+    isolated: bool,
+}
+
+/// A macro defined with the `macro` tag.
+#[derive(Clone)]
+struct Macro {
+    params: Vec<Parameter>,
+    block: SharedVec<Node>,
+    /// Whitespace control either side of the macro's block.
+    wc: (Whitespace, Whitespace),
+    /// The partial template that defined the macro, if recording a source
+    /// map.
+    template: Option<String>,
+}
+
+/// Source map state for `Renderer::render_with_source_map`.
+#[derive(Default)]
+struct Recording {
+    mappings: Vec<Mapping>,
+    /// The number of buffers being rendered into.
+    buffers: usize,
+}
+
+impl Context {
+    fn new(globals: Map<String, Value>, depth: usize) -> Self {
+        Self {
+            globals,
+            locals: Map::new(),
+            frames: Vec::new(),
+            counters: HashMap::new(),
+            cycles: HashMap::new(),
+            stop_index: HashMap::new(),
+            ifchanged: None,
+            macros: HashMap::new(),
+            depth,
+            isolated: false,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(name))
+            .or_else(|| self.locals.get(name))
+            .or_else(|| self.globals.get(name))
+            .cloned()
+            .or_else(|| self.counters.get(name).map(|&n| Value::from(n)))
+    }
+
+    /// Return every variable in scope, as if it were one object.
+    fn scope(&self) -> Map<String, Value> {
+        let mut scope: Map<String, Value> = self
+            .counters
+            .iter()
+            .map(|(k, &v)| (k.to_owned(), Value::from(v)))
+            .collect();
+        scope.extend(self.globals.clone());
+        scope.extend(self.locals.clone());
+        for frame in &self.frames {
+            scope.extend(frame.clone());
+        }
+        scope
+    }
+}
+
+fn forloop(name: &str, index: usize, length: usize, parentloop: Value) -> Value {
+    let mut obj = Map::new();
+    obj.insert("name".to_owned(), Value::from(name));
+    obj.insert("length".to_owned(), Value::from(length));
+    obj.insert("index".to_owned(), Value::from(index + 1));
+    obj.insert("index0".to_owned(), Value::from(index));
+    obj.insert("rindex".to_owned(), Value::from(length - index));
+    obj.insert("rindex0".to_owned(), Value::from(length - index - 1));
+    obj.insert("first".to_owned(), Value::Bool(index == 0));
+    obj.insert("last".to_owned(), Value::Bool(index == length - 1));
+    obj.insert("parentloop".to_owned(), parentloop);
+    Value::Object(obj)
+}
+
+fn tablerowloop(index: usize, length: usize, cols: usize) -> Value {
+    let col0 = index % cols;
+    let mut obj = Map::new();
+    obj.insert("length".to_owned(), Value::from(length));
+    obj.insert("index".to_owned(), Value::from(index + 1));
+    obj.insert("index0".to_owned(), Value::from(index));
+    obj.insert("rindex".to_owned(), Value::from(length - index));
+    obj.insert("rindex0".to_owned(), Value::from(length - index - 1));
+    obj.insert("first".to_owned(), Value::Bool(index == 0));
+    obj.insert("last".to_owned(), Value::Bool(index == length - 1));
+    obj.insert("col".to_owned(), Value::from(col0 + 1));
+    obj.insert("col0".to_owned(), Value::from(col0));
+    obj.insert("col_first".to_owned(), Value::Bool(col0 == 0));
+    obj.insert("col_last".to_owned(), Value::Bool(col0 + 1 == cols));
+    obj.insert("row".to_owned(), Value::from(index / cols + 1));
+    Value::Object(obj)
+}
+
+/// The start and stop indices of the items a loop over _len_ items renders,
+/// after skipping _offset_ items and taking at most _limit_.
+fn bounds(len: usize, offset: Option<usize>, limit: Option<i64>) -> (usize, usize) {
+    let start = offset.unwrap_or(0).min(len);
+    let stop = match limit {
+        Some(limit) => start
+            .saturating_add(usize::try_from(limit).unwrap_or(0))
+            .min(len),
+        None => len,
+    };
+    (start, stop)
+}
+
+fn check_depth(context: &Context, span: Span) -> Result<(), LiquidError> {
+    if context.depth >= CONTEXT_DEPTH_LIMIT {
+        return Err(LiquidError::typ(
+            "maximum context depth reached, possible recursive render".to_owned(),
+        )
+        .with_span(span));
+    }
+    Ok(())
+}
+
+/// Evaluate _expression_ without any template data. Returns `None` if the
+/// expression reads a variable, contains a range, or can't be evaluated.
+pub(crate) fn eval_constant_condition(expression: &BooleanExpression) -> Option<bool> {
+    let mut constant = IsConstant(true);
+    constant.visit_boolean_expression(expression);
+    if !constant.0 {
+        return None;
+    }
+
+    let template = Template {
+        nodes: Vec::new().into(),
+    };
+    Renderer::new(&template)
+        .eval_boolean(expression, &mut Context::new(Map::new(), 0))
+        .ok()
+}
+
+/// Render _expression_ without any template data, like
+/// `eval_constant_condition`.
+pub(crate) fn eval_constant_output(expression: &FilteredExpression) -> Option<String> {
+    let mut constant = IsConstant(true);
+    constant.visit_filtered_expression(expression);
+    if !constant.0 {
+        return None;
+    }
+
+    let template = Template {
+        nodes: Vec::new().into(),
+    };
+    Renderer::new(&template)
+        .eval_filtered(expression, &mut Context::new(Map::new(), 0))
+        .ok()
+        .map(|value| to_liquid_string(&value))
+}
+
+/// Cleared by expressions that read data. Ranges clear it too, so we don't
+/// expand huge ranges into the syntax tree, as does the `date` filter, which
+/// can depend on the current time.
+struct IsConstant(bool);
+
+impl Visitor for IsConstant {
+    fn visit_filter(&mut self, filter: &Filter) {
+        if filter.name == "date" {
+            self.0 = false;
+        }
+        walk_filter(self, filter);
+    }
+
+    fn visit_primitive(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Query { .. } | Primitive::Range { .. } => self.0 = false,
+            _ => (),
+        }
+    }
+}
+
+/// The variable name a partial template's bound value is given when no alias
+/// is given, like `product` for `'product.liquid'`.
+fn partial_key(name: &str) -> String {
+    name.split('.').next().unwrap_or(name).to_owned()
+}
+
+pub(crate) fn leading_wc(node: &Node) -> Whitespace {
+    match node {
+        Node::Content { .. } => Whitespace::Default,
+        Node::Raw { wc, .. } => wc.0,
+        Node::Comment { wc, .. }
+        | Node::DocTag { wc, .. }
+        | Node::Output { wc, .. }
+        | Node::AssignTag { wc, .. }
+        | Node::CaptureTag { wc, .. }
+        | Node::CaseTag { wc, .. }
+        | Node::CycleTag { wc, .. }
+        | Node::DecrementTag { wc, .. }
+        | Node::IncrementTag { wc, .. }
+        | Node::EchoTag { wc, .. }
+        | Node::ForTag { wc, .. }
+        | Node::TablerowTag { wc, .. }
+        | Node::IfchangedTag { wc, .. }
+        | Node::BreakTag { wc, .. }
+        | Node::ContinueTag { wc, .. }
+        | Node::IfTag { wc, .. }
+        | Node::UnlessTag { wc, .. }
+        | Node::IncludeTag { wc, .. }
+        | Node::RenderTag { wc, .. }
+        | Node::LiquidTag { wc, .. }
+        | Node::ExtendsTag { wc, .. }
+        | Node::BlockTag { wc, .. }
+        | Node::MacroTag { wc, .. }
+        | Node::CallTag { wc, .. }
+        | Node::TranslateTag { wc, .. }
+        | Node::TagExtension { wc, .. } => wc.0,
+    }
+}
+
+pub(crate) fn trailing_wc(node: &Node) -> Whitespace {
+    match node {
+        Node::Content { .. } => Whitespace::Default,
+        Node::Raw { wc, .. } => wc.3,
+        Node::CaptureTag { end_wc, .. }
+        | Node::CaseTag { end_wc, .. }
+        | Node::ForTag { end_wc, .. }
+        | Node::TablerowTag { end_wc, .. }
+        | Node::IfchangedTag { end_wc, .. }
+        | Node::IfTag { end_wc, .. }
+        | Node::UnlessTag { end_wc, .. }
+        | Node::BlockTag { end_wc, .. }
+        | Node::MacroTag { end_wc, .. }
+        | Node::TranslateTag { end_wc, .. } => end_wc.1,
+        Node::Comment { wc, .. }
+        | Node::DocTag { wc, .. }
+        | Node::Output { wc, .. }
+        | Node::AssignTag { wc, .. }
+        | Node::CycleTag { wc, .. }
+        | Node::DecrementTag { wc, .. }
+        | Node::IncrementTag { wc, .. }
+        | Node::EchoTag { wc, .. }
+        | Node::BreakTag { wc, .. }
+        | Node::ContinueTag { wc, .. }
+        | Node::IncludeTag { wc, .. }
+        | Node::RenderTag { wc, .. }
+        | Node::LiquidTag { wc, .. }
+        | Node::ExtendsTag { wc, .. }
+        | Node::CallTag { wc, .. } => wc.1,
+        Node::TagExtension { wc, end, .. } => end.as_ref().map_or(wc.1, |end| end.wc.1),
+    }
+}
+
+fn select(value: &Value, key: &Key) -> Option<Value> {
+    match (value, key) {
+        (Value::Object(obj), Key::Name(name)) => obj.get(*name).cloned(),
+        (Value::Array(items), Key::Index(index)) => {
+            let index = if *index < 0 {
+                items.len().checked_sub(index.unsigned_abs() as usize)?
+            } else {
+                *index as usize
+            };
+            items.get(index).cloned()
+        }
+        _ => None,
+    }
+}
+
+/// Format _value_ for output, like the Python engine's `to_liquid_string`.
+pub(crate) fn to_liquid_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_owned(),
+        Value::Array(items) => items.iter().map(to_liquid_string).collect(),
+        value => value.to_string(),
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+pub(crate) fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(obj) => obj.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.trim().is_empty(),
+        value => is_empty(value),
+    }
+}
+
+pub(crate) fn liquid_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| liquid_eq(a, b))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn liquid_lt(left: &Value, right: &Value, span: Span) -> Result<bool, LiquidError> {
+    match (left, right) {
+        (Value::String(a), Value::String(b)) => Ok(a < b),
+        (Value::Number(a), Value::Number(b)) => Ok(a.as_f64() < b.as_f64()),
+        (Value::Bool(_), _) | (_, Value::Bool(_)) => Ok(false),
+        (a, b) => Err(LiquidError::typ(format!(
+            "'<' and '>' are not supported between '{}' and '{}'",
+            type_name(a),
+            type_name(b)
+        ))
+        .with_span(span)),
+    }
+}
+
+fn liquid_contains(left: &Value, right: &Value, span: Span) -> Result<bool, LiquidError> {
+    match left {
+        Value::String(s) => Ok(s.contains(&to_liquid_string(right))),
+        Value::Array(items) => Ok(items.iter().any(|item| liquid_eq(item, right))),
+        Value::Object(obj) => Ok(right.as_str().is_some_and(|key| obj.contains_key(key))),
+        _ => Err(LiquidError::typ(format!(
+            "'in' and 'contains' are not supported between '{}' and '{}'",
+            type_name(left),
+            type_name(right)
+        ))
+        .with_span(span)),
+    }
+}
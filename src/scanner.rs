@@ -0,0 +1,156 @@
+//! A hand-written scanner for the outer structure of a template.
+//!
+//! The grammar matches text content and the contents of `raw` blocks one
+//! character at a time, checking for markup at every position. The scanner
+//! finds the end of text, and the end of `raw` blocks, by searching for `{`
+//! instead. It also matches tags without an expression, like `{% endif %}`,
+//! leaving the grammar to tokenize output statements, comments and tags
+//! with expressions.
+//!
+//! It accepts exactly what the grammar accepts in each case, so
+//! `Lexer::tokenize` produces the same markup either way.
+
+use crate::span::Span;
+use crate::whitespace::Whitespace;
+
+/// The delimiters and content of a `raw` block.
+pub(crate) struct RawBlock {
+    /// Whitespace control markers of the `raw` and `endraw` tags, in order.
+    pub wc: (Whitespace, Whitespace, Whitespace, Whitespace),
+    /// The text between the tags.
+    pub content: Span,
+    /// The whole block, including its tags.
+    pub span: Span,
+}
+
+/// A tag without an expression.
+pub(crate) struct BareTag {
+    pub wc: (Whitespace, Whitespace),
+    pub name: Span,
+    pub span: Span,
+}
+
+/// The end of the text content starting at byte _start_ of _source_, which
+/// is the next `{{`, `{%` or `{#`, or the end of _source_.
+pub(crate) fn content_end(source: &str, start: usize) -> usize {
+    let bytes = source.as_bytes();
+    let mut pos = start;
+    while let Some(i) = source[pos..].find('{') {
+        let brace = pos + i;
+        if matches!(bytes.get(brace + 1), Some(b'{' | b'%' | b'#')) {
+            return brace;
+        }
+        pos = brace + 1;
+    }
+    source.len()
+}
+
+/// The `raw` block starting at byte _start_ of _source_, or `None` if
+/// there isn't one, including if its `endraw` tag is missing.
+pub(crate) fn raw_block(source: &str, start: usize) -> Option<RawBlock> {
+    let (wc_left, wc_right, content_start) = tag(source, start, "raw")?;
+
+    let mut pos = content_start;
+    while let Some(i) = source[pos..].find("{%") {
+        let tag_start = pos + i;
+        if let Some((end_wc_left, end_wc_right, end)) = tag(source, tag_start, "endraw") {
+            return Some(RawBlock {
+                wc: (wc_left, wc_right, end_wc_left, end_wc_right),
+                content: Span::new(content_start, tag_start),
+                span: Span::new(start, end),
+            });
+        }
+        pos = tag_start + 2;
+    }
+    None
+}
+
+/// A tag without an expression, like `{% endif %}`, starting at byte
+/// _start_ of _source_, or `None` if there isn't one.
+///
+/// Tags that the grammar could match as something other than a plain tag,
+/// like `{% comment %}` or `{% liquid %}`, are never matched.
+pub(crate) fn bare_tag(source: &str, start: usize) -> Option<BareTag> {
+    let bytes = source.as_bytes();
+    let (wc_left, name_start) = tag_start(source, start)?;
+
+    let mut pos = name_start;
+    if !bytes.get(pos).is_some_and(u8::is_ascii_lowercase) {
+        return None;
+    }
+    while bytes
+        .get(pos)
+        .is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'_')
+    {
+        pos += 1;
+    }
+    let name = Span::new(name_start, pos);
+    if matches!(&source[name_start..pos], "comment" | "doc" | "liquid") {
+        return None;
+    }
+
+    let (wc_right, end) = tag_end(source, pos)?;
+    Some(BareTag {
+        wc: (wc_left, wc_right),
+        name,
+        span: Span::new(start, end),
+    })
+}
+
+/// Match a tag called _name_ without an expression, like `{%- raw %}`, at
+/// byte _start_ of _source_, returning its whitespace control markers and
+/// the offset following it.
+fn tag(source: &str, start: usize, name: &str) -> Option<(Whitespace, Whitespace, usize)> {
+    let (wc_left, pos) = tag_start(source, start)?;
+    if !source[pos..].starts_with(name) {
+        return None;
+    }
+    let (wc_right, end) = tag_end(source, pos + name.len())?;
+    Some((wc_left, wc_right, end))
+}
+
+/// Match `{%`, a whitespace control marker and whitespace at _pos_,
+/// returning the marker and the offset following it.
+fn tag_start(source: &str, pos: usize) -> Option<(Whitespace, usize)> {
+    if !source[pos..].starts_with("{%") {
+        return None;
+    }
+    let bytes = source.as_bytes();
+    let mut pos = pos + 2;
+    let wc = whitespace_control(bytes, &mut pos);
+    skip_whitespace(bytes, &mut pos);
+    Some((wc, pos))
+}
+
+/// Match whitespace, a whitespace control marker and `%}` at _pos_,
+/// returning the marker and the offset following it.
+fn tag_end(source: &str, pos: usize) -> Option<(Whitespace, usize)> {
+    let bytes = source.as_bytes();
+    let mut pos = pos;
+    skip_whitespace(bytes, &mut pos);
+    let wc = whitespace_control(bytes, &mut pos);
+    if !source[pos..].starts_with("%}") {
+        return None;
+    }
+    Some((wc, pos + 2))
+}
+
+/// Consume an optional whitespace control marker at _pos_.
+fn whitespace_control(bytes: &[u8], pos: &mut usize) -> Whitespace {
+    let wc = match bytes.get(*pos) {
+        Some(b'+') => Whitespace::Plus,
+        Some(b'-') => Whitespace::Minus,
+        Some(b'~') => Whitespace::Smart,
+        _ => return Whitespace::Default,
+    };
+    *pos += 1;
+    wc
+}
+
+/// Consume the grammar's whitespace, spaces, tabs, carriage returns and
+/// newlines, at _pos_.
+fn skip_whitespace(bytes: &[u8], pos: &mut usize) {
+    while matches!(bytes.get(*pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+        *pos += 1;
+    }
+}
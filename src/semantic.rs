@@ -0,0 +1,528 @@
+//! A flow-sensitive model of the names a template defines, for warning about
+//! variables that might not be defined when they're read.
+//!
+//! Scoping follows the renderer. Variables set with `assign` and `capture`
+//! are visible to the rest of the template from the point they're rendered.
+//! Counters from `increment` and `decrement` live in their own namespace,
+//! which is only read when there's no variable of the same name. Loop
+//! variables and translation arguments are local to their blocks. Macro
+//! blocks, like templates loaded with the `render` tag, start with a fresh
+//! scope that sees only global variables and their own arguments, so names
+//! assigned in a macro or a rendered template never leak out.
+//!
+//! A name assigned in only some branches of an `if`, `unless` or `case` tag,
+//! or only in a loop's block, which might not run at all, is possibly
+//! undefined after that tag. Templates loaded with `include` share their
+//! caller's scope, but their contents are unknown, so their assignments are
+//! not modelled.
+
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::analysis::root_name;
+use crate::ast::{
+    walk_filter_expression, walk_node, walk_primitive, walk_query, Node, Primitive, Template,
+    Visitor,
+};
+use crate::query::{FilterExpression, Query};
+use crate::span::Span;
+
+/// Where the value of a name comes from at the point it's read.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Definedness {
+    /// A loop variable, macro parameter or other name local to a block.
+    Local,
+    /// A variable assigned on every path to the read.
+    Assigned,
+    /// A counter created on every path to the read, where a variable of the
+    /// same name is not assigned on every path.
+    Counter,
+    /// Assigned or counted on some paths to the read, but not all of them.
+    PossiblyUndefined,
+    /// Never assigned before the read, so it must be provided by the caller.
+    Global,
+}
+
+/// A variable read by a template, and how it is defined at that point.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameUse {
+    /// The name at the root of the query, like `user` in `user.name`.
+    pub name: String,
+    /// The span of the name itself.
+    pub span: Span,
+    pub definedness: Definedness,
+    /// The spans of assigned names and counters that might provide the value
+    /// read here, in source order.
+    pub definitions: Vec<Span>,
+}
+
+/// The variables read by a template, in order of appearance.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SemanticModel {
+    uses: Vec<NameUse>,
+}
+
+impl SemanticModel {
+    pub fn new(template: &Template) -> Self {
+        let mut builder = Builder::default();
+        builder.visit_template(template);
+        let mut uses = builder.uses;
+        uses.sort_by_key(|name_use| name_use.span.start);
+        Self { uses }
+    }
+
+    pub fn uses(&self) -> &[NameUse] {
+        &self.uses
+    }
+
+    /// Reads of names that the template defines on some paths to the read,
+    /// but not on others.
+    pub fn possibly_undefined(&self) -> impl Iterator<Item = &NameUse> {
+        self.uses
+            .iter()
+            .filter(|name_use| name_use.definedness == Definedness::PossiblyUndefined)
+    }
+
+    /// The read of a name at byte _offset_, or `None` if no name is read
+    /// there.
+    pub fn use_at(&self, offset: usize) -> Option<&NameUse> {
+        self.uses
+            .iter()
+            .find(|name_use| name_use.span.start <= offset && offset <= name_use.span.end)
+    }
+}
+
+impl Template {
+    /// Model where each variable read by this template gets its value.
+    pub fn semantic_model(&self) -> SemanticModel {
+        SemanticModel::new(self)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl NameUse {
+    fn __repr__(&self) -> String {
+        format!(
+            "NameUse({:?}, definedness={:?}, span=({}, {}))",
+            self.name, self.definedness, self.span.start, self.span.end
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl SemanticModel {
+    #[getter(uses)]
+    fn py_uses(&self) -> Vec<NameUse> {
+        self.uses.clone()
+    }
+
+    #[pyo3(name = "possibly_undefined")]
+    fn py_possibly_undefined(&self) -> Vec<NameUse> {
+        self.possibly_undefined().cloned().collect()
+    }
+
+    #[pyo3(name = "use_at")]
+    fn py_use_at(&self, offset: usize) -> Option<NameUse> {
+        self.use_at(offset).cloned()
+    }
+
+    fn __len__(&self) -> usize {
+        self.uses.len()
+    }
+}
+
+/// Names that might have been defined in one namespace, and those that
+/// certainly have.
+#[derive(Debug, Clone, Default)]
+struct Namespace {
+    /// Names defined on every path.
+    definite: HashSet<String>,
+    /// Names defined on any path, including every definite name, with the
+    /// spans of the definitions that might still be in effect.
+    possible: HashMap<String, Vec<Span>>,
+}
+
+impl Namespace {
+    fn define(&mut self, name: &str, span: Span) {
+        self.definite.insert(name.to_owned());
+        self.possible.insert(name.to_owned(), vec![span]);
+    }
+
+    /// Add definitions that might be in effect, without making any name
+    /// definite.
+    fn add_possible(&mut self, name: &str, span: Span) {
+        let spans = self.possible.entry(name.to_owned()).or_default();
+        if !spans.contains(&span) {
+            spans.push(span);
+        }
+    }
+
+    fn merge(&mut self, other: &Namespace) {
+        self.definite.retain(|name| other.definite.contains(name));
+        for (name, spans) in &other.possible {
+            for span in spans {
+                self.add_possible(name, *span);
+            }
+        }
+    }
+}
+
+/// Which names are defined at a point in a template.
+#[derive(Debug, Clone)]
+struct State {
+    /// False after a `break` or `continue` tag, until the end of the block.
+    reachable: bool,
+    variables: Namespace,
+    counters: Namespace,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            reachable: true,
+            variables: Namespace::default(),
+            counters: Namespace::default(),
+        }
+    }
+}
+
+impl State {
+    /// Combine the states at the end of two paths that join.
+    fn merge(&mut self, other: &State) {
+        if !other.reachable {
+            return;
+        }
+        if !self.reachable {
+            *self = other.clone();
+            return;
+        }
+        self.variables.merge(&other.variables);
+        self.counters.merge(&other.counters);
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    state: State,
+    /// Names local to the blocks we're in, innermost last.
+    scopes: Vec<Vec<String>>,
+    /// States at `break` and `continue` tags in the loops we're in,
+    /// innermost last.
+    jumps: Vec<Vec<State>>,
+    uses: Vec<NameUse>,
+}
+
+impl Builder {
+    /// Visit each of _blocks_ starting from the current state, then continue
+    /// with the states at their ends combined. If _optional_ is true, none
+    /// of the blocks might run.
+    fn branches<'a>(&mut self, blocks: impl IntoIterator<Item = &'a [Node]>, optional: bool) {
+        let entry = self.state.clone();
+        let mut exit: Option<State> = optional.then(|| entry.clone());
+        for block in blocks {
+            self.state = entry.clone();
+            self.visit_block(block);
+            match &mut exit {
+                Some(exit) => exit.merge(&self.state),
+                None => exit = Some(self.state.clone()),
+            }
+        }
+        self.state = exit.unwrap_or(entry);
+    }
+
+    /// Visit a loop's _block_, with _names_ local to it, and return the state
+    /// after the loop has run at least once.
+    fn loop_block(&mut self, names: Vec<String>, block: &[Node]) -> State {
+        // Names assigned anywhere in the block might have been assigned by a
+        // previous iteration.
+        let mut assignments = Assignments::default();
+        assignments.visit_block(block);
+        let entry = self.state.clone();
+        for (name, span) in &assignments.variables {
+            self.state.variables.add_possible(name, *span);
+        }
+        for (name, span) in &assignments.counters {
+            self.state.counters.add_possible(name, *span);
+        }
+
+        self.scopes.push(names);
+        self.jumps.push(Vec::new());
+        self.visit_block(block);
+        let jumps = self.jumps.pop().unwrap_or_default();
+        self.scopes.pop();
+
+        let mut exit = std::mem::replace(&mut self.state, entry);
+        for jump in &jumps {
+            exit.merge(jump);
+        }
+        exit
+    }
+
+    fn jump(&mut self) {
+        if let Some(jumps) = self.jumps.last_mut() {
+            jumps.push(self.state.clone());
+        }
+        self.state.reachable = false;
+    }
+
+    /// Visit a macro's _block_ with a fresh scope containing only _params_.
+    fn isolated(&mut self, params: Vec<String>, block: &[Node]) {
+        let state = std::mem::take(&mut self.state);
+        let scopes = std::mem::replace(&mut self.scopes, vec![params]);
+        let jumps = std::mem::take(&mut self.jumps);
+        self.visit_block(block);
+        self.state = state;
+        self.scopes = scopes;
+        self.jumps = jumps;
+    }
+
+    fn read(&mut self, name: &str, span: Span) {
+        let local = self.scopes.iter().flatten().any(|local| local == name);
+        let variables = &self.state.variables;
+        let counters = &self.state.counters;
+
+        let definedness = if local {
+            Definedness::Local
+        } else if variables.definite.contains(name) {
+            Definedness::Assigned
+        } else if counters.definite.contains(name) {
+            Definedness::Counter
+        } else if variables.possible.contains_key(name) || counters.possible.contains_key(name) {
+            Definedness::PossiblyUndefined
+        } else {
+            Definedness::Global
+        };
+
+        // A variable hides a counter of the same name.
+        let mut definitions = match definedness {
+            Definedness::Local | Definedness::Global => Vec::new(),
+            Definedness::Assigned => spans(variables, name),
+            _ => [spans(variables, name), spans(counters, name)].concat(),
+        };
+        definitions.sort_by_key(|span| span.start);
+        self.uses.push(NameUse {
+            name: name.to_owned(),
+            span,
+            definedness,
+            definitions,
+        });
+    }
+}
+
+fn spans(namespace: &Namespace, name: &str) -> Vec<Span> {
+    namespace.possible.get(name).cloned().unwrap_or_default()
+}
+
+impl Visitor for Builder {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::AssignTag {
+                identifier,
+                identifier_span,
+                expression,
+                ..
+            } => {
+                self.visit_filtered_expression(expression);
+                self.state.variables.define(identifier, *identifier_span);
+            }
+            Node::CaptureTag {
+                identifier,
+                identifier_span,
+                block,
+                ..
+            } => {
+                self.visit_block(block);
+                self.state.variables.define(identifier, *identifier_span);
+            }
+            Node::IncrementTag {
+                name, name_span, ..
+            }
+            | Node::DecrementTag {
+                name, name_span, ..
+            } => self.state.counters.define(name, *name_span),
+            Node::BreakTag { .. } | Node::ContinueTag { .. } => self.jump(),
+            Node::CaseTag {
+                expression,
+                whens,
+                default,
+                ..
+            } => {
+                self.visit_primitive(expression);
+                for when in whens {
+                    when.args.iter().for_each(|arg| self.visit_primitive(arg));
+                }
+                let blocks = whens
+                    .iter()
+                    .map(|when| when.block.as_slice())
+                    .chain(default.iter().map(|default| default.block.as_slice()));
+                self.branches(blocks, default.is_none());
+            }
+            Node::IfTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                ..
+            }
+            | Node::UnlessTag {
+                condition,
+                block,
+                alternatives,
+                default,
+                ..
+            } => {
+                self.visit_boolean_expression(condition);
+                for alternative in alternatives {
+                    self.visit_boolean_expression(&alternative.condition);
+                }
+                let blocks = std::iter::once(block.as_slice())
+                    .chain(alternatives.iter().map(|a| a.block.as_slice()))
+                    .chain(default.iter().map(|default| default.block.as_slice()));
+                self.branches(blocks, default.is_none());
+            }
+            Node::ForTag {
+                name,
+                value_name,
+                iterable,
+                limit,
+                offset,
+                block,
+                default,
+                ..
+            } => {
+                self.visit_primitive(iterable);
+                limit.iter().for_each(|p| self.visit_primitive(p));
+                offset.iter().for_each(|p| self.visit_primitive(p));
+
+                let mut names = vec![name.to_owned(), "forloop".to_owned()];
+                names.extend(value_name.iter().cloned());
+                let looped = self.loop_block(names, block);
+                if let Some(default) = default {
+                    self.visit_block(&default.block);
+                }
+                self.state.merge(&looped);
+            }
+            Node::TablerowTag {
+                name,
+                iterable,
+                cols,
+                limit,
+                offset,
+                block,
+                ..
+            } => {
+                self.visit_primitive(iterable);
+                cols.iter().for_each(|p| self.visit_primitive(p));
+                limit.iter().for_each(|p| self.visit_primitive(p));
+                offset.iter().for_each(|p| self.visit_primitive(p));
+                let looped =
+                    self.loop_block(vec![name.to_owned(), "tablerowloop".to_owned()], block);
+                self.state.merge(&looped);
+            }
+            Node::MacroTag { params, block, .. } => {
+                for param in params {
+                    param.default.iter().for_each(|p| self.visit_primitive(p));
+                }
+                self.isolated(params.iter().map(|p| p.name.to_owned()).collect(), block);
+            }
+            Node::TranslateTag {
+                args,
+                block,
+                plural,
+                ..
+            } => {
+                args.iter().for_each(|arg| self.visit_primitive(&arg.value));
+                self.scopes
+                    .push(args.iter().map(|arg| arg.name.to_owned()).collect());
+                let blocks = std::iter::once(block.as_slice())
+                    .chain(plural.iter().map(|plural| plural.block.as_slice()));
+                self.branches(blocks, false);
+                self.scopes.pop();
+            }
+            Node::TagExtension {
+                args,
+                block,
+                branches,
+                ..
+            } => {
+                // Custom tags could render their blocks any number of times.
+                for arg in args.iter().flatten() {
+                    self.visit_primitive(&arg.value);
+                }
+                let blocks = std::iter::once(block.as_slice())
+                    .chain(branches.iter().map(|branch| branch.block.as_slice()));
+                self.branches(blocks, true);
+            }
+            _ => walk_node(self, node),
+        }
+    }
+
+    fn visit_primitive(&mut self, primitive: &Primitive) {
+        if let Primitive::Lambda { params, expr, .. } = primitive {
+            self.scopes.push(params.clone());
+            self.visit_boolean_expression(expr);
+            self.scopes.pop();
+        } else {
+            walk_primitive(self, primitive);
+        }
+    }
+
+    fn visit_query(&mut self, query: &Query) {
+        if let Some((name, span)) = root_name(query) {
+            self.read(name, span);
+        }
+        walk_query(self, query);
+    }
+
+    fn visit_filter_expression(&mut self, expression: &FilterExpression) {
+        match expression {
+            // Relative queries start at the current node, not at a name.
+            FilterExpression::RelativeQuery { query, .. } => walk_query(self, query),
+            _ => walk_filter_expression(self, expression),
+        }
+    }
+}
+
+/// Every variable and counter defined in a block, with the span of each
+/// definition. Macro blocks are skipped, as they can't define anything
+/// outside of the macro.
+#[derive(Default)]
+struct Assignments {
+    variables: Vec<(String, Span)>,
+    counters: Vec<(String, Span)>,
+}
+
+impl Visitor for Assignments {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::AssignTag {
+                identifier,
+                identifier_span,
+                ..
+            }
+            | Node::CaptureTag {
+                identifier,
+                identifier_span,
+                ..
+            } => self
+                .variables
+                .push((identifier.to_owned(), *identifier_span)),
+            Node::IncrementTag {
+                name, name_span, ..
+            }
+            | Node::DecrementTag {
+                name, name_span, ..
+            } => self.counters.push((name.to_owned(), *name_span)),
+            Node::MacroTag { .. } => return,
+            _ => (),
+        }
+        walk_node(self, node);
+    }
+}
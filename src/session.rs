@@ -0,0 +1,174 @@
+//! Compile many templates with one parser configuration.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::ast::Template;
+use crate::errors::LiquidError;
+use crate::parser::LiquidParser;
+use crate::partials::static_partial_names;
+use crate::query::cache::QueryCache;
+
+/// The number of queries cached by a session's parser, unless the parser
+/// already has a cache.
+const QUERY_CACHE_SIZE: usize = 1024;
+
+/// Parses a set of named templates, sharing one `LiquidParser` (and its tag
+/// aliases, filter expression functions and query cache) between them.
+///
+/// Compiled templates are reference counted. Templates with identical source
+/// text share a single syntax tree. Template names and the names of partial
+/// templates they use are interned, so a theme with hundreds of templates
+/// rendering the same few snippets holds one copy of each name.
+pub struct CompileSession {
+    pub parser: LiquidParser,
+    names: HashSet<Arc<str>>,
+    templates: HashMap<Arc<str>, Arc<str>>,
+    by_source: HashMap<Arc<str>, Compiled>,
+}
+
+/// A syntax tree shared by every template name compiled from the same
+/// source text.
+struct Compiled {
+    template: Arc<Template>,
+    /// Interned names of the partial templates included, rendered or
+    /// extended by string literal name.
+    partials: Vec<Arc<str>>,
+    /// The number of template names compiled from this source.
+    users: usize,
+}
+
+impl Default for CompileSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompileSession {
+    pub fn new() -> Self {
+        Self::with_parser(LiquidParser::new())
+    }
+
+    /// A session compiling with _parser_. A parser without a query cache is
+    /// given one.
+    pub fn with_parser(parser: LiquidParser) -> Self {
+        let cache = parser
+            .lexer
+            .query_parser
+            .cache
+            .clone()
+            .unwrap_or_else(|| Arc::new(QueryCache::new(QUERY_CACHE_SIZE)));
+        Self::with_query_cache(parser, cache)
+    }
+
+    /// A session compiling with _parser_, which parses queries using
+    /// _cache_. Sessions can share a cache with each other and with other
+    /// parsers.
+    pub fn with_query_cache(mut parser: LiquidParser, cache: Arc<QueryCache>) -> Self {
+        parser.lexer.query_parser.cache = Some(cache);
+        CompileSession {
+            parser,
+            names: HashSet::new(),
+            templates: HashMap::new(),
+            by_source: HashMap::new(),
+        }
+    }
+
+    /// The query cache used by this session's parser.
+    pub fn query_cache(&self) -> Option<&Arc<QueryCache>> {
+        self.parser.lexer.query_parser.cache.as_ref()
+    }
+
+    /// Parse _source_ and store the result under _name_, replacing any
+    /// template previously compiled with that name.
+    pub fn compile(&mut self, name: &str, source: &str) -> Result<Arc<Template>, LiquidError> {
+        if let Some((source, _)) = self.by_source.get_key_value(source) {
+            let source = Arc::clone(source);
+            return Ok(self.replace(name, source));
+        }
+
+        let template = self.parser.parse(source)?;
+        let mut names = Vec::new();
+        static_partial_names(&template.nodes, &mut names);
+        let partials = names.iter().map(|name| self.intern(name)).collect();
+
+        let source: Arc<str> = Arc::from(source);
+        self.by_source.insert(
+            Arc::clone(&source),
+            Compiled {
+                template: Arc::new(template),
+                partials,
+                users: 0,
+            },
+        );
+        Ok(self.replace(name, source))
+    }
+
+    /// Point _name_ at the template compiled from _source_, forgetting the
+    /// template it replaces if no other name uses it.
+    fn replace(&mut self, name: &str, source: Arc<str>) -> Arc<Template> {
+        let name = self.intern(name);
+        let compiled = self.by_source.get_mut(&source).unwrap();
+        compiled.users += 1;
+        let template = Arc::clone(&compiled.template);
+
+        if let Some(old) = self.templates.insert(name, source) {
+            let compiled = self.by_source.get_mut(&old).unwrap();
+            compiled.users -= 1;
+            if compiled.users == 0 {
+                self.by_source.remove(&old);
+            }
+        }
+        template
+    }
+
+    fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return Arc::clone(name);
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(Arc::clone(&name));
+        name
+    }
+
+    /// Return the template compiled under _name_, if any.
+    pub fn get(&self, name: &str) -> Option<Arc<Template>> {
+        self.compiled(name)
+            .map(|compiled| Arc::clone(&compiled.template))
+    }
+
+    /// The names of partial templates that the template compiled under
+    /// _name_ includes, renders or extends by string literal name, in the
+    /// order they appear.
+    pub fn partials(&self, name: &str) -> &[Arc<str>] {
+        self.compiled(name)
+            .map(|compiled| compiled.partials.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn compiled(&self, name: &str) -> Option<&Compiled> {
+        self.templates
+            .get(name)
+            .and_then(|source| self.by_source.get(source))
+    }
+
+    /// Iterate over the names and syntax trees of all compiled templates.
+    pub fn templates(&self) -> impl Iterator<Item = (&str, &Arc<Template>)> {
+        self.templates
+            .iter()
+            .map(|(name, source)| (name.as_ref(), &self.by_source[source].template))
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// The number of distinct syntax trees held by this session.
+    pub fn unique_templates(&self) -> usize {
+        self.by_source.len()
+    }
+}
@@ -0,0 +1,108 @@
+//! Source maps, from the bytes of rendered or serialized output back to the
+//! nodes that produced them.
+//!
+//! `Renderer::render_with_source_map` and
+//! `Template::to_string_with_source_map` record a mapping for every node
+//! that writes any output, including block tags, whose output contains the
+//! output of their blocks. Tools can use a source map to jump from a
+//! position in rendered HTML to the template markup that produced it.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::span::Span;
+
+/// Output written by one node.
+#[cfg_attr(feature = "python", pyclass(name = "SourceMapping", frozen, get_all))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// The range of output bytes.
+    pub output: Span,
+    /// The span of the node that wrote them.
+    pub source: Span,
+    /// The name of the partial template that the node belongs to, or `None`
+    /// for the template being rendered.
+    pub template: Option<String>,
+}
+
+/// Mappings from output to nodes, in the order that nodes finished writing
+/// their output, so a node's mapping follows the mappings of nodes in its
+/// blocks.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn new(mappings: Vec<Mapping>) -> Self {
+        Self { mappings }
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// The innermost node that wrote the output byte at _offset_, or `None`
+    /// if nothing did.
+    pub fn source_at(&self, offset: usize) -> Option<&Mapping> {
+        self.mappings
+            .iter()
+            .filter(|m| m.output.start <= offset && offset < m.output.end)
+            .min_by_key(|m| m.output.end - m.output.start)
+    }
+
+    /// Ranges of output written by the node at _span_ in _template_, where a
+    /// _template_ of `None` is the template being rendered. Nodes inside
+    /// loops write more than one range.
+    pub fn outputs<'a>(
+        &'a self,
+        template: Option<&'a str>,
+        span: Span,
+    ) -> impl Iterator<Item = Span> + 'a {
+        self.mappings
+            .iter()
+            .filter(move |m| m.source == span && m.template.as_deref() == template)
+            .map(|m| m.output)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Mapping {
+    fn __repr__(&self) -> String {
+        format!(
+            "SourceMapping(output=({}, {}), source=({}, {}), template={})",
+            self.output.start,
+            self.output.end,
+            self.source.start,
+            self.source.end,
+            self.template
+                .as_ref()
+                .map_or_else(|| "None".to_owned(), |name| format!("{name:?}"))
+        )
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl SourceMap {
+    #[getter(mappings)]
+    fn py_mappings(&self) -> Vec<Mapping> {
+        self.mappings.clone()
+    }
+
+    #[pyo3(name = "source_at")]
+    fn py_source_at(&self, offset: usize) -> Option<Mapping> {
+        self.source_at(offset).cloned()
+    }
+
+    #[pyo3(name = "outputs", signature = (span, template=None))]
+    fn py_outputs(&self, span: Span, template: Option<&str>) -> Vec<Span> {
+        self.outputs(template, span).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.mappings.len()
+    }
+}
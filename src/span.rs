@@ -0,0 +1,190 @@
+//! Source locations shared by markup, queries and errors.
+
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::{prelude::*, types::PyTuple};
+use serde::{Deserialize, Serialize};
+
+use crate::pest_errors::floor_char_boundary;
+
+/// A half-open byte range into template or query source text.
+///
+/// Spans are exposed to Python as `(start, end)` tuples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Return this span moved _delta_ bytes through the source text.
+    pub fn shifted(self, delta: isize) -> Self {
+        Self {
+            start: self.start.saturating_add_signed(delta),
+            end: self.end.saturating_add_signed(delta),
+        }
+    }
+
+    /// Return the one-based line and column numbers of the start of this span.
+    ///
+    /// Columns count characters, not bytes.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let mut offset = self.start.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let col = before[line_start..].chars().count() + 1;
+        (line, col)
+    }
+}
+
+/// Line start offsets for some source text, for converting many byte offsets
+/// to line and column numbers without rescanning the source each time.
+///
+/// Lines and columns start at 1, and columns count characters, not bytes.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    source: String,
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source: source.to_owned(),
+            line_starts,
+        }
+    }
+
+    /// Return the one-based line and column numbers of _offset_.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = floor_char_boundary(&self.source, offset);
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let col = self.source[line_start..offset].chars().count() + 1;
+        (line, col)
+    }
+
+    /// Return line and column numbers for the start and end of _span_.
+    pub fn span_line_col(&self, span: Span) -> ((usize, usize), (usize, usize)) {
+        (self.line_col(span.start), self.line_col(span.end))
+    }
+
+    /// The number of lines in the source text.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Return the zero-based line number of _offset_ and its zero-based
+    /// column in UTF-16 code units, as positions are given in the Language
+    /// Server Protocol.
+    pub fn utf16_position(&self, offset: usize) -> (usize, usize) {
+        let offset = floor_char_boundary(&self.source, offset);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let col = self.source[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+        (line, col)
+    }
+
+    /// Return the byte offset of a zero-based _line_ and _col_, counted in
+    /// UTF-16 code units. Columns past the end of a line are clamped to the
+    /// end of the line, and lines past the end of the source to the end of
+    /// the source.
+    pub fn utf16_offset(&self, line: usize, col: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.source.len();
+        };
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.source.len(), |&next| next - 1);
+
+        let mut units = 0;
+        for (i, c) in self.source[start..end].char_indices() {
+            if units >= col {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl LineIndex {
+    #[new]
+    fn py_new(source: &str) -> Self {
+        Self::new(source)
+    }
+
+    #[pyo3(name = "line_col")]
+    fn py_line_col(&self, offset: usize) -> (usize, usize) {
+        self.line_col(offset)
+    }
+
+    #[pyo3(name = "span_to_line_col")]
+    fn py_span_to_line_col(&self, span: Span) -> ((usize, usize), (usize, usize)) {
+        self.span_line_col(span)
+    }
+
+    fn __len__(&self) -> usize {
+        self.line_count()
+    }
+}
+
+impl From<pest::Span<'_>> for Span {
+    fn from(span: pest::Span<'_>) -> Self {
+        Self {
+            start: span.start(),
+            end: span.end(),
+        }
+    }
+}
+
+impl From<(usize, usize)> for Span {
+    fn from((start, end): (usize, usize)) -> Self {
+        Self { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[cfg(feature = "python")]
+impl IntoPy<PyObject> for Span {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyTuple::new_bound(py, [self.start, self.end]).into_py(py)
+    }
+}
+
+#[cfg(feature = "python")]
+impl ToPyObject for Span {
+    fn to_object(&self, py: Python<'_>) -> PyObject {
+        self.into_py(py)
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'py> FromPyObject<'py> for Span {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        ob.extract::<(usize, usize)>().map(Span::from)
+    }
+}
@@ -0,0 +1,109 @@
+//! Custom tags, registered with a parser in addition to the built-in tags.
+//!
+//! The parser knows nothing about what a custom tag means. It checks that
+//! block tags are closed and that interior tags appear only inside their
+//! block, then hands the tag's tokens and nested blocks to the caller as a
+//! `Node::TagExtension`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::grammar::TagInfo;
+
+/// The shape of a custom tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagDefinition {
+    pub name: String,
+    /// Names of tags that can close this tag's block. Empty for inline tags.
+    pub end_tags: Vec<String>,
+    /// Tags that may appear between this tag and its end tag, like `else`.
+    pub intermediates: Vec<String>,
+}
+
+impl TagDefinition {
+    pub fn is_block(&self) -> bool {
+        !self.end_tags.is_empty()
+    }
+}
+
+impl From<&TagDefinition> for TagInfo {
+    fn from(definition: &TagDefinition) -> Self {
+        TagInfo {
+            name: definition.name.clone(),
+            end: definition.end_tags.first().cloned(),
+            intermediates: definition.intermediates.clone(),
+        }
+    }
+}
+
+/// Custom tags known to a parser, keyed by name.
+///
+/// Built-in tags always take priority, so registering a tag with the same
+/// name as a built-in tag has no effect on parsing.
+///
+/// Definitions are shared between clones of a registry, and copied the first
+/// time a clone registers or removes a tag.
+#[derive(Debug, Clone, Default)]
+pub struct TagRegistry {
+    tags: Arc<HashMap<String, TagDefinition>>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an inline tag called _name_, replacing any existing custom
+    /// tag with the same name.
+    pub fn register_inline(&mut self, name: &str) {
+        self.register(TagDefinition {
+            name: name.to_owned(),
+            end_tags: Vec::new(),
+            intermediates: Vec::new(),
+        });
+    }
+
+    /// Register a block tag called _name_, closed by any of _end_tags_ and
+    /// divided by any of _intermediates_. If _end_tags_ is empty, the block
+    /// is closed by `end<name>`.
+    pub fn register_block(&mut self, name: &str, end_tags: &[&str], intermediates: &[&str]) {
+        let end_tags = if end_tags.is_empty() {
+            vec![format!("end{name}")]
+        } else {
+            end_tags.iter().map(|s| s.to_string()).collect()
+        };
+
+        self.register(TagDefinition {
+            name: name.to_owned(),
+            end_tags,
+            intermediates: intermediates.iter().map(|s| s.to_string()).collect(),
+        });
+    }
+
+    pub fn register(&mut self, definition: TagDefinition) {
+        Arc::make_mut(&mut self.tags).insert(definition.name.clone(), definition);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<TagDefinition> {
+        Arc::make_mut(&mut self.tags).remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TagDefinition> {
+        self.tags.get(name)
+    }
+
+    /// Return `true` if _name_ is the end tag or an intermediate tag of a
+    /// registered block tag.
+    pub fn is_delimiter(&self, name: &str) -> bool {
+        self.tags.values().any(|tag| {
+            tag.end_tags.iter().any(|t| t == name) || tag.intermediates.iter().any(|t| t == name)
+        })
+    }
+
+    /// Registered tags, sorted by name.
+    pub fn definitions(&self) -> Vec<&TagDefinition> {
+        let mut definitions: Vec<&TagDefinition> = self.tags.values().collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+}
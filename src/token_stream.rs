@@ -0,0 +1,167 @@
+//! A cursor over tag expression tokens, for custom tags implemented outside
+//! of the parser.
+//!
+//! The parser has its own, borrowing, token stream. This one owns its tokens
+//! so it can be handed to Python, where custom tags would otherwise have to
+//! track indexes into a list of tokens and build their own error messages.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::errors::LiquidError;
+use crate::markup::{tokens_span, Token, TOKEN_KINDS};
+use crate::parser::describe;
+use crate::span::Span;
+
+#[cfg_attr(feature = "python", pyclass)]
+#[derive(Debug, Clone)]
+pub struct TokenStream {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The span of the enclosing markup, used for errors at the end of the
+    /// stream.
+    span: Span,
+}
+
+impl TokenStream {
+    /// A stream over _tokens_. _span_ is used for errors at the end of the
+    /// stream, and defaults to the span of the tokens themselves.
+    pub fn new(tokens: Vec<Token>, span: Option<Span>) -> Self {
+        let span = span.unwrap_or_else(|| tokens_span(&tokens));
+        Self {
+            tokens,
+            pos: 0,
+            span,
+        }
+    }
+
+    /// The index of the next token.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The token _offset_ places after the next token, without consuming
+    /// anything.
+    pub fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    /// Consume the next token, which must be of kind _kind_, like `"Word"`.
+    pub fn expect(&mut self, kind: &str) -> Result<Token, LiquidError> {
+        let description = describe_kind(kind)?;
+        match self.peek(0) {
+            Some(token) if token.kind() == kind => Ok(self.next().unwrap()),
+            token => Err(LiquidError::syntax(format!(
+                "expected {description}, found {}",
+                describe(token)
+            ))
+            .with_span(self.span_of(token))),
+        }
+    }
+
+    /// Consume and return the next token if it is one of _kinds_.
+    pub fn eat_one_of(&mut self, kinds: &[&str]) -> Result<Option<Token>, LiquidError> {
+        for kind in kinds {
+            describe_kind(kind)?;
+        }
+        match self.peek(0) {
+            Some(token) if kinds.contains(&token.kind()) => Ok(self.next()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Fail unless every token has been consumed.
+    pub fn expect_eos(&self) -> Result<(), LiquidError> {
+        match self.peek(0) {
+            None => Ok(()),
+            token => Err(LiquidError::syntax(format!(
+                "expected end of expression, found {}",
+                describe(token)
+            ))
+            .with_span(self.span_of(token))),
+        }
+    }
+
+    fn span_of(&self, token: Option<&Token>) -> Span {
+        token.map_or(self.span, |t| t.span())
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+}
+
+/// Describe the token kind called _kind_, or fail if there is no such kind.
+fn describe_kind(kind: &str) -> Result<&'static str, LiquidError> {
+    TOKEN_KINDS
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, description)| *description)
+        .ok_or_else(|| LiquidError::typ(format!("unknown token kind '{kind}'")))
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl TokenStream {
+    #[new]
+    #[pyo3(signature = (tokens, span=None))]
+    fn py_new(tokens: Vec<Token>, span: Option<Span>) -> Self {
+        Self::new(tokens, span)
+    }
+
+    #[getter(position)]
+    fn py_position(&self) -> usize {
+        self.position()
+    }
+
+    #[getter(span)]
+    fn py_span(&self) -> Span {
+        self.span
+    }
+
+    #[pyo3(name = "peek", signature = (offset=0))]
+    fn py_peek(&self, offset: usize) -> Option<Token> {
+        self.peek(offset).cloned()
+    }
+
+    #[pyo3(name = "next")]
+    fn py_next(&mut self) -> Option<Token> {
+        self.next()
+    }
+
+    #[pyo3(name = "expect")]
+    fn py_expect(&mut self, kind: &str) -> Result<Token, LiquidError> {
+        self.expect(kind)
+    }
+
+    #[pyo3(name = "eat_one_of")]
+    fn py_eat_one_of(&mut self, kinds: Vec<String>) -> Result<Option<Token>, LiquidError> {
+        let kinds: Vec<&str> = kinds.iter().map(String::as_str).collect();
+        self.eat_one_of(&kinds)
+    }
+
+    #[pyo3(name = "expect_eos")]
+    fn py_expect_eos(&self) -> Result<(), LiquidError> {
+        self.expect_eos()
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Token> {
+        self.next()
+    }
+
+    fn __len__(&self) -> usize {
+        self.tokens.len() - self.pos
+    }
+}
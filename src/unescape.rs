@@ -1,105 +1,133 @@
-use core::str;
+//! Decode escape sequences in string literals.
 
-use crate::errors::LiquidError;
+use std::iter::Peekable;
+use std::str::CharIndices;
 
-// TODO: pass span or line/col to errors
-
-pub fn unescape(value: &str, span: &(usize, usize)) -> Result<String, LiquidError> {
-    let bytes = value.as_bytes();
-    let length = bytes.len();
-    let mut rv: Vec<u8> = Vec::new();
-    let mut index: usize = 0;
-    let mut code_point: u32;
-
-    while index < length {
-        let b = bytes[index];
-        if b == b'\\' {
-            index += 1;
-            match bytes[index] {
-                b'"' => rv.push(b'"'),
-                b'\\' => rv.push(b'\\'),
-                b'/' => rv.push(b'/'),
-                b'b' => rv.push(b'\x08'),
-                b'f' => rv.push(b'\x0C'),
-                b'n' => rv.push(b'\n'),
-                b'r' => rv.push(b'\r'),
-                b't' => rv.push(b'\t'),
-                b'u' => {
-                    (code_point, index) = decode_hex_char(bytes, index, span)?;
-                    let mut x = encode_code_point(code_point, span)?;
-                    rv.append(&mut x);
-                }
-                _ => return Err(LiquidError::syntax("unknown escape sequence".to_owned())),
-            }
-        } else {
-            rv.push(b);
-        }
-        index += 1;
+use crate::errors::LiquidError;
+use crate::span::Span;
+
+/// Replace escape sequences in _value_, the contents of a string literal
+/// delimited by _quote_, with the characters they represent. _span_ is the
+/// location of _value_ in its source.
+///
+/// Errors point at the offending escape sequence rather than the whole
+/// literal.
+pub fn unescape(value: &str, quote: char, span: &Span) -> Result<String, LiquidError> {
+    Unescaper {
+        value,
+        chars: value.char_indices().peekable(),
+        offset: span.start,
     }
+    .unescape(quote)
+}
 
-    return Ok(String::from_utf8(rv).unwrap());
+struct Unescaper<'a> {
+    value: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    offset: usize,
 }
 
-fn decode_hex_char(
-    bytes: &[u8],
-    index: usize,
-    span: &(usize, usize),
-) -> Result<(u32, usize), LiquidError> {
-    let length = bytes.len();
-    let mut index = index;
+impl Unescaper<'_> {
+    fn unescape(&mut self, quote: char) -> Result<String, LiquidError> {
+        let mut rv = String::with_capacity(self.value.len());
 
-    if index + 4 >= length {
-        return Err(LiquidError::syntax("incomplete escape sequence".to_owned()));
-    }
+        while let Some((start, ch)) = self.chars.next() {
+            if ch != '\\' {
+                rv.push(ch);
+                continue;
+            }
 
-    index = index + 1; // move past 'u'
-    let mut code_point = parse_hex_digits(&bytes[index..index + 4], span)?;
+            match self.chars.next() {
+                Some((_, ch)) if ch == quote => rv.push(ch),
+                Some((_, '\\')) => rv.push('\\'),
+                Some((_, '/')) => rv.push('/'),
+                Some((_, 'b')) => rv.push('\x08'),
+                Some((_, 'f')) => rv.push('\x0C'),
+                Some((_, 'n')) => rv.push('\n'),
+                Some((_, 'r')) => rv.push('\r'),
+                Some((_, 't')) => rv.push('\t'),
+                Some((_, 'u')) => rv.push(self.decode_hex_char(start)?),
+                Some((_, ch)) => {
+                    return Err(self.error(&format!("unknown escape sequence '\\{ch}'"), start))
+                }
+                None => return Err(self.error("incomplete escape sequence", start)),
+            }
+        }
 
-    if is_low_surrogate(code_point) {
-        return Err(LiquidError::syntax(
-            "unexpected low surrogate code point".to_owned(),
-        ));
+        Ok(rv)
     }
 
-    if is_high_surrogate(code_point) {
-        if !(index + 9 < length && bytes[index + 4] == b'\\' && bytes[index + 5] == b'u') {
-            return Err(LiquidError::syntax("incomplete escape sequence".to_owned()));
+    /// Decode a `\uXXXX` escape, or a surrogate pair of them, where _start_
+    /// is the index of the first backslash and `\u` has been consumed.
+    fn decode_hex_char(&mut self, start: usize) -> Result<char, LiquidError> {
+        let code_point = self.hex_digits(start)?;
+
+        if is_low_surrogate(code_point) {
+            return Err(self.error("unexpected low surrogate code point", start));
         }
 
-        let low_surrogate = parse_hex_digits(&bytes[index + 6..index + 10], span)?;
+        if !is_high_surrogate(code_point) {
+            if code_point < 0x1F {
+                return Err(self.error("invalid character", start));
+            }
+            // Not a surrogate, so always a valid char.
+            return Ok(char::from_u32(code_point).unwrap());
+        }
+
+        if self.chars.next_if(|(_, ch)| *ch == '\\').is_none()
+            || self.chars.next_if(|(_, ch)| *ch == 'u').is_none()
+        {
+            return Err(self.error(
+                "incomplete escape sequence, expected a low surrogate",
+                start,
+            ));
+        }
 
+        let low_surrogate = self.hex_digits(start)?;
         if !is_low_surrogate(low_surrogate) {
-            return Err(LiquidError::syntax("unexpected code point".to_owned()));
+            return Err(self.error("unexpected code point, expected a low surrogate", start));
         }
 
-        code_point = 0x10000 + (((code_point & 0x03FF) << 10) | (low_surrogate & 0x03FF));
-        return Ok((code_point, index + 9));
+        let code_point = 0x10000 + (((code_point & 0x03FF) << 10) | (low_surrogate & 0x03FF));
+        Ok(char::from_u32(code_point).unwrap())
     }
 
-    Ok((code_point, index + 3))
-}
+    /// Consume exactly four hex digits.
+    fn hex_digits(&mut self, start: usize) -> Result<u32, LiquidError> {
+        let mut code_point = 0;
+        for _ in 0..4 {
+            match self.chars.next_if(|(_, ch)| ch.is_ascii_hexdigit()) {
+                Some((_, ch)) => code_point = code_point * 16 + ch.to_digit(16).unwrap(),
+                None if self.chars.peek().is_none() => {
+                    return Err(self.error("incomplete escape sequence", start))
+                }
+                None => {
+                    // Include the offending character in the span.
+                    self.chars.next();
+                    return Err(self.error("invalid escape sequence", start));
+                }
+            }
+        }
+        Ok(code_point)
+    }
 
-fn parse_hex_digits(digits: &[u8], span: &(usize, usize)) -> Result<u32, LiquidError> {
-    let s = str::from_utf8(digits).unwrap();
-    u32::from_str_radix(s, 16)
-        .map_err(|_| LiquidError::syntax("invalid escape sequence".to_owned()))
-}
+    /// The index of the next character in the value.
+    fn pos(&mut self) -> usize {
+        self.chars.peek().map_or(self.value.len(), |(i, _)| *i)
+    }
 
-fn encode_code_point(code_point: u32, span: &(usize, usize)) -> Result<Vec<u8>, LiquidError> {
-    if code_point < 0x1F {
-        Err(LiquidError::syntax("invalid character".to_owned()))
-    } else {
-        // TODO: better
-        let mut buf = [0; 4];
-        let rv = char::from_u32(code_point).unwrap().encode_utf8(&mut buf);
-        Ok(rv.as_bytes().to_owned())
+    /// An error spanning from _start_ to the next character in the value.
+    fn error(&mut self, msg: &str, start: usize) -> LiquidError {
+        let end = self.pos();
+        LiquidError::syntax(msg.to_owned())
+            .with_span(Span::new(self.offset + start, self.offset + end))
     }
 }
 
 fn is_high_surrogate(code_point: u32) -> bool {
-    code_point >= 0xD800 && code_point <= 0xDBFF
+    (0xD800..=0xDBFF).contains(&code_point)
 }
 
 fn is_low_surrogate(code_point: u32) -> bool {
-    code_point >= 0xDC00 && code_point <= 0xDFFF
+    (0xDC00..=0xDFFF).contains(&code_point)
 }
@@ -0,0 +1,379 @@
+//! Check templates without building a syntax tree.
+//!
+//! `LiquidParser::validate` runs the grammar over a template and walks the
+//! resulting pairs directly, without producing `Markup` or `Node`s. It checks
+//! that block tags are balanced, that tags and filters are known, and that
+//! filters and filter functions in queries are called with the right
+//! arguments, reporting every problem it finds rather than stopping at the
+//! first one.
+//!
+//! Tag expressions are not parsed, so a template that validates can still
+//! fail to parse. Use `LiquidParser::parse` for a complete check.
+
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
+
+use crate::errors::LiquidError;
+use crate::grammar::{builtin_tags, TagInfo};
+use crate::lexer::{syntax_error, Liquid, Rule};
+use crate::parser::{quoted_names, LiquidParser};
+use crate::span::Span;
+
+/// The name and span of a tag, in the order they appear in a template.
+struct TagRef<'a> {
+    name: &'a str,
+    span: Span,
+}
+
+/// A block that has been opened but not yet closed.
+struct OpenBlock {
+    end_tags: Vec<String>,
+    intermediates: Vec<String>,
+    span: Span,
+}
+
+impl OpenBlock {
+    fn delimiters(&self) -> Vec<&str> {
+        self.intermediates
+            .iter()
+            .chain(self.end_tags.iter())
+            .map(|s| s.as_str())
+            .collect()
+    }
+}
+
+impl LiquidParser {
+    /// Check _source_ for errors without building a syntax tree, returning
+    /// every error found, or an empty vec if none were found.
+    ///
+    /// Syntax errors from the grammar stop validation, so they are always
+    /// reported on their own.
+    pub fn validate(&self, source: &str) -> Vec<LiquidError> {
+        if let Some(limit) = self.max_bytes {
+            if source.len() > limit {
+                return vec![LiquidError::syntax(format!(
+                    "template is {} bytes long, the limit is {limit}",
+                    source.len()
+                ))];
+            }
+        }
+
+        let pairs = match Liquid::parse(Rule::markup, source) {
+            Ok(pairs) => pairs,
+            Err(err) => return vec![syntax_error(err, source)],
+        };
+
+        let builtin = builtin_tags();
+        let mut errors = Vec::new();
+        let mut tags = Vec::new();
+
+        for pair in pairs {
+            match pair.as_rule() {
+                Rule::tag | Rule::jekyll_include => {
+                    if let Some(tag) = self.validate_tag(pair, &builtin, &mut errors) {
+                        tags.push(tag);
+                    }
+                }
+                Rule::output => self.validate_tokens(pair.into_inner(), true, &mut errors),
+                // Line statements are a block of their own, so blocks
+                // can't be opened in a `liquid` tag and closed outside it.
+                Rule::liquid_tag => {
+                    let mut lines = Vec::new();
+                    for statement in pair
+                        .into_inner()
+                        .filter(|p| p.as_rule() == Rule::line_statements)
+                    {
+                        for line in statement.into_inner() {
+                            if line.as_rule() == Rule::line_tag {
+                                lines.extend(self.validate_tag(line, &builtin, &mut errors));
+                            }
+                        }
+                    }
+                    self.validate_blocks(&lines, &builtin, &mut errors);
+                }
+                _ => (),
+            }
+        }
+
+        self.validate_blocks(&tags, &builtin, &mut errors);
+        errors.sort_by_key(|err| err.span.map(|span| span.start));
+        errors
+    }
+
+    /// Check the expression of the tag or line tag _pair_, returning its
+    /// canonical name and span, or `None` if the tag was rejected outright.
+    fn validate_tag<'a>(
+        &'a self,
+        pair: Pair<'a, Rule>,
+        builtin: &[TagInfo],
+        errors: &mut Vec<LiquidError>,
+    ) -> Option<TagRef<'a>> {
+        let span: Span = pair.as_span().into();
+
+        if pair.as_rule() == Rule::jekyll_include {
+            if !self.lexer.dialect.jekyll_include {
+                errors.push(crate::dialect::disallowed("jekyll_include", span));
+                return None;
+            }
+            self.validate_tokens(pair.into_inner(), true, errors);
+            return Some(TagRef {
+                name: "include",
+                span,
+            });
+        }
+
+        let mut it = pair.into_inner();
+        let name = it.find(|p| p.as_rule() == Rule::tag_name)?.as_str();
+        let name = self.tag_aliases.get(name).map_or(name, |s| s.as_str());
+
+        // Expressions of registered and unknown tags are not parsed as
+        // filtered expressions, so their filters are never checked.
+        let filters = builtin.iter().any(|tag| tag.name == name);
+        self.validate_tokens(it, filters, errors);
+        Some(TagRef { name, span })
+    }
+
+    /// Check the expression tokens in _pairs_. If _filters_ is true, each
+    /// filter following a `|` or `||` is checked against the filter
+    /// registry, along with the number of arguments it is given.
+    fn validate_tokens(&self, pairs: Pairs<Rule>, filters: bool, errors: &mut Vec<LiquidError>) {
+        let tokens: Vec<Pair<Rule>> = pairs.collect();
+        let mut pipe = false;
+        for (i, pair) in tokens.iter().enumerate() {
+            match pair.as_rule() {
+                Rule::word if pipe && filters => {
+                    if let Err(err) = self.validate_filter(&tokens[i..]) {
+                        errors.push(err);
+                    }
+                }
+                Rule::query => self.validate_query(pair.clone(), errors),
+                Rule::range => pair
+                    .clone()
+                    .into_inner()
+                    .filter(|p| p.as_rule() == Rule::range_query)
+                    .for_each(|p| self.validate_query(p, errors)),
+                _ => (),
+            }
+            pipe = is_symbol(pair, "|") || is_symbol(pair, "||");
+        }
+    }
+
+    /// Check the filter named by the first of _tokens_, counting its
+    /// arguments the way `parse_filter_arguments` would.
+    fn validate_filter(&self, tokens: &[Pair<Rule>]) -> Result<(), LiquidError> {
+        let name = &tokens[0];
+        let start = name.as_span().start();
+        let mut end = name.as_span().end();
+        let mut positional = 0;
+        let mut keywords = Vec::new();
+
+        // Arguments follow a colon.
+        let mut rest: &[Pair<Rule>] = match tokens.get(1) {
+            Some(colon) if is_symbol(colon, ":") => {
+                end = colon.as_span().end();
+                &tokens[2..]
+            }
+            _ => &[],
+        };
+        loop {
+            let consumed = match rest {
+                [comma, ..] if is_symbol(comma, ",") => 1,
+                [param, arrow, ..] if param.as_rule() == Rule::word && is_symbol(arrow, "=>") => {
+                    positional += 1;
+                    2 + lambda_body(&rest[2..])
+                }
+                [paren, ..] if is_symbol(paren, "(") => {
+                    positional += 1;
+                    let params = rest
+                        .iter()
+                        .position(|p| is_symbol(p, ")"))
+                        .map_or(rest.len(), |i| i + 1);
+                    let arrow = rest[params..].first().is_some_and(|p| is_symbol(p, "=>"));
+                    let params = params + usize::from(arrow);
+                    params + lambda_body(&rest[params..])
+                }
+                [keyword, separator, _, ..]
+                    if keyword.as_rule() == Rule::word
+                        && (is_symbol(separator, ":") || is_symbol(separator, "=")) =>
+                {
+                    keywords.push(keyword.as_str());
+                    3
+                }
+                [value, ..] if is_primitive(value) => {
+                    positional += 1;
+                    1
+                }
+                _ => break,
+            };
+            end = rest[consumed - 1].as_span().end();
+            rest = &rest[consumed..];
+        }
+
+        self.filters.check_call(
+            name.as_str(),
+            positional,
+            keywords.into_iter(),
+            Span::new(start, end),
+        )
+    }
+
+    fn validate_query(&self, pair: Pair<Rule>, errors: &mut Vec<LiquidError>) {
+        if let Err(err) = self.lexer.query_parser.parse(pair.into_inner()) {
+            errors.push(err);
+        }
+    }
+
+    /// Check that the block tags in _tags_ are balanced.
+    fn validate_blocks(&self, tags: &[TagRef], builtin: &[TagInfo], errors: &mut Vec<LiquidError>) {
+        let mut stack: Vec<OpenBlock> = Vec::new();
+
+        for (i, tag) in tags.iter().enumerate() {
+            let name = tag.name;
+
+            if let Some(open) = stack.last() {
+                if open.end_tags.iter().any(|t| t == name) {
+                    stack.pop();
+                    continue;
+                }
+                if open.intermediates.iter().any(|t| t == name) {
+                    continue;
+                }
+            }
+
+            if self.is_delimiter(name) || name == "endcomment" || name == "enddoc" {
+                errors.push(match stack.last() {
+                    Some(open) => LiquidError::syntax(format!(
+                        "expected {}, found '{name}'",
+                        quoted_names(&open.delimiters())
+                    ))
+                    .with_span(tag.span)
+                    .with_note("the block was opened here", Some(open.span)),
+                    None => {
+                        LiquidError::syntax(format!("unexpected tag '{name}'")).with_span(tag.span)
+                    }
+                });
+                continue;
+            }
+
+            // Balanced raw blocks, block comments and doc blocks are matched
+            // by the grammar.
+            if matches!(name, "raw" | "comment" | "doc") {
+                errors.push(
+                    LiquidError::syntax(format!("missing end tag, expected 'end{name}'"))
+                        .with_span(tag.span),
+                );
+                continue;
+            }
+
+            let (end_tags, intermediates) = if let Some(info) =
+                builtin.iter().find(|info| info.name == name)
+            {
+                (
+                    info.end.iter().cloned().collect(),
+                    info.intermediates.clone(),
+                )
+            } else if let Some(definition) = self.tags.get(name) {
+                (
+                    definition.end_tags.clone(),
+                    definition.intermediates.clone(),
+                )
+            } else if self.common_tags || self.lexer.dialect.unknown_tags {
+                if name.starts_with("end") {
+                    errors.push(
+                        LiquidError::syntax(format!("unexpected tag '{name}'")).with_span(tag.span),
+                    );
+                    continue;
+                }
+                // Like `parse_common_tag`, an unknown tag is a block tag
+                // if it is closed by a matching end tag.
+                let end_tag = format!("end{name}");
+                if has_end_tag(&tags[i + 1..], name, &end_tag) {
+                    (vec![end_tag], Vec::new())
+                } else {
+                    (Vec::new(), Vec::new())
+                }
+            } else {
+                errors
+                    .push(LiquidError::syntax(format!("unknown tag '{name}'")).with_span(tag.span));
+                continue;
+            };
+
+            if !end_tags.is_empty() {
+                if let Some(limit) = self.max_depth.filter(|limit| stack.len() >= *limit) {
+                    errors.push(
+                        LiquidError::syntax(format!("blocks are nested more than {limit} deep"))
+                            .with_span(tag.span),
+                    );
+                }
+                stack.push(OpenBlock {
+                    end_tags,
+                    intermediates,
+                    span: tag.span,
+                });
+            }
+        }
+
+        for open in stack.iter().rev() {
+            errors.push(
+                LiquidError::syntax(format!("missing end tag, expected '{}'", open.end_tags[0]))
+                    .with_span(open.span),
+            );
+        }
+    }
+}
+
+fn is_symbol(pair: &Pair<Rule>, symbol: &str) -> bool {
+    pair.as_rule() == Rule::symbol && pair.as_str() == symbol
+}
+
+/// Returns `true` if _pair_ is a token that `parse_primitive` accepts.
+fn is_primitive(pair: &Pair<Rule>) -> bool {
+    match pair.as_rule() {
+        Rule::word
+        | Rule::query
+        | Rule::number
+        | Rule::range
+        | Rule::double_quoted
+        | Rule::single_quoted
+        | Rule::multiline_double_quoted
+        | Rule::multiline_single_quoted => true,
+        Rule::reserved_word => matches!(pair.as_str(), "true" | "false" | "null" | "nil"),
+        _ => false,
+    }
+}
+
+/// The number of _tokens_ that make up the boolean expression at the start
+/// of _tokens_, the body of a lambda. The expression ends at the first
+/// token that can't continue it, like a comma or a pipe.
+fn lambda_body(tokens: &[Pair<Rule>]) -> usize {
+    tokens
+        .iter()
+        .position(|pair| match pair.as_rule() {
+            Rule::symbol => matches!(pair.as_str(), "," | "|" | "||" | ":" | "=" | "=>" | "?:"),
+            Rule::reserved_word => {
+                matches!(
+                    pair.as_str(),
+                    "if" | "else" | "with" | "as" | "for" | "required"
+                )
+            }
+            _ => false,
+        })
+        .unwrap_or(tokens.len())
+}
+
+/// Returns `true` if a tag called _end_ closes a tag called _name_ in
+/// _tags_, accounting for nested tags with the same name.
+fn has_end_tag(tags: &[TagRef], name: &str, end: &str) -> bool {
+    let mut depth = 0;
+    for tag in tags {
+        if tag.name == name {
+            depth += 1;
+        } else if tag.name == end {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+    }
+    false
+}
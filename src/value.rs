@@ -0,0 +1,385 @@
+//! A dynamic value type for render-time data.
+//!
+//! `Value` is what template data looks like once it has been converted from
+//! JSON or Python objects. It implements Liquid's truthiness, equality and
+//! ordering rules, so every part of the renderer agrees on them. Integers
+//! and floats compare equal by value, `nil` and `false` are the only falsy
+//! values, and comparisons between unrelated types are undefined.
+//!
+//! Drops are values whose properties are computed on demand. Python objects
+//! that aren't primitives, lists or dicts are converted to drops, so their
+//! attributes are only read if a template asks for them.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+
+/// An object's properties, in insertion order.
+pub type Object = IndexMap<String, Value>;
+
+#[derive(Debug, Clone, Default)]
+pub enum Value {
+    #[default]
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Object),
+    Drop(Arc<dyn LiquidDrop>),
+}
+
+/// A value with properties that are computed when they are read.
+pub trait LiquidDrop: fmt::Debug + Send + Sync {
+    /// Return the property called _key_, or `None` if there isn't one.
+    fn get(&self, key: &str) -> Option<Value>;
+
+    /// The concrete value this drop stands for when it is output, compared
+    /// or converted to JSON.
+    fn to_value(&self) -> Value;
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) | Value::Float(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+            Value::Drop(_) => "drop",
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    /// Only `nil` and `false` are falsy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Nil | Value::Bool(false) => false,
+            Value::Drop(drop) => drop.to_value().is_truthy(),
+            _ => true,
+        }
+    }
+
+    /// True for empty strings, arrays and objects, as tested by the `empty`
+    /// keyword.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Value::String(s) => s.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            Value::Object(obj) => obj.is_empty(),
+            Value::Drop(drop) => drop.to_value().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Like `is_empty`, but strings containing only whitespace are blank
+    /// too.
+    pub fn is_blank(&self) -> bool {
+        match self {
+            Value::String(s) => s.trim().is_empty(),
+            Value::Drop(drop) => drop.to_value().is_blank(),
+            value => value.is_empty(),
+        }
+    }
+
+    /// Return the property called _key_ of an object or drop.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        match self {
+            Value::Object(obj) => obj.get(key).cloned(),
+            Value::Drop(drop) => drop.get(key),
+            _ => None,
+        }
+    }
+
+    /// Return the array item at _index_, counting from the end if _index_
+    /// is negative.
+    pub fn index(&self, index: i64) -> Option<Value> {
+        match self {
+            Value::Array(items) => {
+                let index = if index < 0 {
+                    items.len().checked_sub(index.unsigned_abs() as usize)?
+                } else {
+                    index as usize
+                };
+                items.get(index).cloned()
+            }
+            Value::Drop(drop) => drop.to_value().index(index),
+            _ => None,
+        }
+    }
+
+    /// The `contains` operator. Strings contain substrings, arrays contain
+    /// equal items and objects contain keys.
+    pub fn contains(&self, item: &Value) -> bool {
+        match self {
+            Value::String(s) => s.contains(&item.to_string()),
+            Value::Array(items) => items.iter().any(|other| other == item),
+            Value::Object(obj) => matches!(item, Value::String(key) if obj.contains_key(key)),
+            Value::Drop(drop) => drop.to_value().contains(item),
+            _ => false,
+        }
+    }
+
+    /// Replace drops with the values they stand for, recursively.
+    pub fn resolve(self) -> Value {
+        match self {
+            Value::Drop(drop) => drop.to_value().resolve(),
+            Value::Array(items) => Value::Array(items.into_iter().map(Value::resolve).collect()),
+            Value::Object(obj) => {
+                Value::Object(obj.into_iter().map(|(k, v)| (k, v.resolve())).collect())
+            }
+            value => value,
+        }
+    }
+}
+
+/// Liquid equality. Integers and floats are equal if their values are, and
+/// objects are equal if they have the same keys and values, in any order.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => *a as f64 == *b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Object(a), Value::Object(b)) => a == b,
+            (Value::Drop(a), Value::Drop(b)) if Arc::ptr_eq(a, b) => true,
+            (Value::Drop(drop), other) | (other, Value::Drop(drop)) => drop.to_value() == *other,
+            _ => false,
+        }
+    }
+}
+
+/// Liquid ordering, as used by `<`, `>`, `<=` and `>=`. Only numbers and
+/// strings can be ordered.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+            (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+            (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+            (Value::Drop(drop), other) => drop.to_value().partial_cmp(other),
+            (value, Value::Drop(drop)) => value.partial_cmp(&drop.to_value()),
+            _ => None,
+        }
+    }
+}
+
+/// Format a value for output. `nil` is an empty string, arrays are their
+/// items concatenated and objects are formatted as JSON.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => Ok(()),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(n) => write!(f, "{n}"),
+            Value::Float(n) => match serde_json::Number::from_f64(*n) {
+                Some(n) => write!(f, "{n}"),
+                None => write!(f, "{n}"),
+            },
+            Value::String(s) => f.write_str(s),
+            Value::Array(items) => items.iter().try_for_each(|item| write!(f, "{item}")),
+            Value::Object(_) => write!(f, "{}", serde_json::Value::from(self.clone())),
+            Value::Drop(drop) => write!(f, "{}", drop.to_value()),
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(n) => Value::Int(n),
+                None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(items) => {
+                Value::Array(items.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(obj) => {
+                Value::Object(obj.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+impl From<&serde_json::Value> for Value {
+    fn from(value: &serde_json::Value) -> Self {
+        value.clone().into()
+    }
+}
+
+/// Drops are resolved. Floats that JSON can't represent, like `NaN`,
+/// become `null`.
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Nil => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Int(n) => serde_json::Value::from(n),
+            Value::Float(n) => serde_json::Number::from_f64(n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(items) => {
+                serde_json::Value::Array(items.into_iter().map(Into::into).collect())
+            }
+            Value::Object(obj) => {
+                serde_json::Value::Object(obj.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            Value::Drop(drop) => drop.to_value().into(),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Value::Array(items)
+    }
+}
+
+impl From<Object> for Value {
+    fn from(obj: Object) -> Self {
+        Value::Object(obj)
+    }
+}
+
+/// A Python object that isn't a primitive, list or dict. Properties are
+/// read with `__getitem__`, falling back to attributes.
+#[cfg(feature = "python")]
+#[derive(Debug)]
+pub struct PyDrop(Py<PyAny>);
+
+#[cfg(feature = "python")]
+impl LiquidDrop for PyDrop {
+    fn get(&self, key: &str) -> Option<Value> {
+        Python::with_gil(|py| {
+            let obj = self.0.bind(py);
+            let item = obj.get_item(key).or_else(|_| obj.getattr(key)).ok()?;
+            item.extract().ok()
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        Python::with_gil(|py| {
+            let obj = self.0.bind(py);
+            obj.str().map_or(Value::Nil, |s| {
+                Value::String(s.to_string_lossy().into_owned())
+            })
+        })
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'py> FromPyObject<'py> for Value {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if ob.is_none() {
+            Ok(Value::Nil)
+        } else if let Ok(b) = ob.downcast::<PyBool>() {
+            Ok(Value::Bool(b.is_true()))
+        } else if ob.is_instance_of::<PyLong>() {
+            // Integers too big for an i64 are approximated.
+            Ok(ob.extract::<i64>().map_or_else(
+                |_| Value::Float(ob.extract().unwrap_or(f64::NAN)),
+                Value::Int,
+            ))
+        } else if let Ok(n) = ob.downcast::<PyFloat>() {
+            Ok(Value::Float(n.value()))
+        } else if let Ok(s) = ob.downcast::<PyString>() {
+            Ok(Value::String(s.to_string_lossy().into_owned()))
+        } else if let Ok(items) = ob.downcast::<PyList>() {
+            items
+                .iter()
+                .map(|item| item.extract())
+                .collect::<PyResult<_>>()
+                .map(Value::Array)
+        } else if let Ok(items) = ob.downcast::<PyTuple>() {
+            items
+                .iter()
+                .map(|item| item.extract())
+                .collect::<PyResult<_>>()
+                .map(Value::Array)
+        } else if let Ok(dict) = ob.downcast::<PyDict>() {
+            dict.iter()
+                .map(|(k, v)| Ok((k.str()?.to_string_lossy().into_owned(), v.extract()?)))
+                .collect::<PyResult<_>>()
+                .map(Value::Object)
+        } else {
+            Ok(Value::Drop(Arc::new(PyDrop(ob.clone().unbind()))))
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl IntoPy<PyObject> for Value {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Value::Nil => py.None(),
+            Value::Bool(b) => b.into_py(py),
+            Value::Int(n) => n.into_py(py),
+            Value::Float(n) => n.into_py(py),
+            Value::String(s) => s.into_py(py),
+            Value::Array(items) => items.into_py(py),
+            Value::Object(obj) => {
+                let dict = PyDict::new_bound(py);
+                for (k, v) in obj {
+                    // Setting a str key on a new dict can't fail.
+                    let _ = dict.set_item(k, v.into_py(py));
+                }
+                dict.into_py(py)
+            }
+            Value::Drop(drop) => match drop.to_value() {
+                Value::Drop(_) => py.None(),
+                value => value.into_py(py),
+            },
+        }
+    }
+}
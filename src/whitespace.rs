@@ -0,0 +1,199 @@
+//! Whitespace control markers, shared by all markup, and the rules for
+//! trimming text beside them.
+
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::markup::Markup;
+
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Whitespace {
+    Plus,
+    Minus,
+    Smart,
+    Default,
+}
+
+impl Whitespace {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "+" => Self::Plus,
+            "-" => Self::Minus,
+            "~" => Self::Smart,
+            "" => Self::Default,
+            _ => unreachable!("{:#?}", s),
+        }
+    }
+}
+
+impl fmt::Display for Whitespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Whitespace::Plus => write!(f, "+"),
+            Whitespace::Minus => write!(f, "-"),
+            Whitespace::Smart => write!(f, "~"),
+            Whitespace::Default => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Whitespace {
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// What `~` does to the text beside it.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SmartTrim {
+    /// Remove carriage returns and newlines, leaving other whitespace.
+    #[default]
+    Newlines,
+    /// Replace whitespace containing a newline with a single newline.
+    /// Whitespace without a newline is left alone.
+    Collapse,
+    /// Behave like `-`, for compatibility with Shopify/Liquid, which has
+    /// no smart whitespace control.
+    Shopify,
+}
+
+/// How text beside markup is trimmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrimPolicy {
+    /// Whitespace control applied to markup that does not specify its own.
+    pub default: Whitespace,
+    pub smart: SmartTrim,
+}
+
+impl Default for TrimPolicy {
+    fn default() -> Self {
+        Self {
+            default: Whitespace::Plus,
+            smart: SmartTrim::Newlines,
+        }
+    }
+}
+
+impl TrimPolicy {
+    /// Return _text_ as it would be output between markup with trailing
+    /// whitespace control _left_ and leading whitespace control _right_.
+    pub fn trim<'t>(&self, text: &'t str, left: Whitespace, right: Whitespace) -> Cow<'t, str> {
+        let text = match self.resolve(left) {
+            Whitespace::Minus => Cow::Borrowed(text.trim_start()),
+            Whitespace::Smart => match self.smart {
+                SmartTrim::Collapse => collapse_start(text),
+                _ => Cow::Borrowed(text.trim_start_matches(['\r', '\n'])),
+            },
+            _ => Cow::Borrowed(text),
+        };
+
+        match self.resolve(right) {
+            Whitespace::Minus => trim_cow(text, str::trim_end),
+            Whitespace::Smart => match self.smart {
+                SmartTrim::Collapse => match text {
+                    Cow::Borrowed(text) => collapse_end(text),
+                    Cow::Owned(text) => Cow::Owned(collapse_end(&text).into_owned()),
+                },
+                _ => trim_cow(text, |t| t.trim_end_matches(['\r', '\n'])),
+            },
+            _ => text,
+        }
+    }
+
+    /// The effective behavior of whitespace control _wc_, which is one of
+    /// `Plus`, `Minus` or `Smart`.
+    fn resolve(&self, wc: Whitespace) -> Whitespace {
+        let wc = match wc {
+            Whitespace::Default => self.default,
+            wc => wc,
+        };
+
+        match wc {
+            Whitespace::Smart if self.smart == SmartTrim::Shopify => Whitespace::Minus,
+            // A default of `Default` means no trimming.
+            Whitespace::Default => Whitespace::Plus,
+            wc => wc,
+        }
+    }
+}
+
+fn trim_cow<'t>(text: Cow<'t, str>, f: impl Fn(&str) -> &str) -> Cow<'t, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(f(text)),
+        Cow::Owned(text) => Cow::Owned(f(&text).to_owned()),
+    }
+}
+
+fn collapse_start(text: &str) -> Cow<'_, str> {
+    let rest = text.trim_start();
+    if text[..text.len() - rest.len()].contains('\n') {
+        Cow::Owned(format!("\n{rest}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn collapse_end(text: &str) -> Cow<'_, str> {
+    let rest = text.trim_end();
+    if text[rest.len()..].contains('\n') {
+        Cow::Owned(format!("{rest}\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Return a copy of _markup_ with the text of each `Markup::Content`
+/// replaced by the text that would actually be output under _policy_.
+///
+/// Spans are unchanged, so they still point at the untrimmed text in the
+/// source. Content that trims away to nothing is kept, with empty text.
+pub fn resolve_whitespace(markup: &[Markup], policy: &TrimPolicy) -> Vec<Markup> {
+    markup
+        .iter()
+        .enumerate()
+        .map(|(i, m)| match m {
+            Markup::Content { text, span } => {
+                let left = i
+                    .checked_sub(1)
+                    .map_or(Whitespace::Default, |i| trailing_wc(&markup[i]));
+                let right = markup.get(i + 1).map_or(Whitespace::Default, leading_wc);
+                Markup::Content {
+                    text: policy.trim(text, left, right).into_owned(),
+                    span: *span,
+                }
+            }
+            m => m.clone(),
+        })
+        .collect()
+}
+
+fn leading_wc(markup: &Markup) -> Whitespace {
+    match markup {
+        Markup::Raw { wc, .. } => wc.0,
+        Markup::Comment { wc, .. }
+        | Markup::Output { wc, .. }
+        | Markup::Tag { wc, .. }
+        | Markup::Lines { wc, .. } => wc.0,
+        Markup::Content { .. } | Markup::Error { .. } | Markup::EOI {} => Whitespace::Default,
+    }
+}
+
+fn trailing_wc(markup: &Markup) -> Whitespace {
+    match markup {
+        Markup::Raw { wc, .. } => wc.3,
+        Markup::Comment { wc, .. }
+        | Markup::Output { wc, .. }
+        | Markup::Tag { wc, .. }
+        | Markup::Lines { wc, .. } => wc.1,
+        Markup::Content { .. } | Markup::Error { .. } | Markup::EOI {} => Whitespace::Default,
+    }
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use _liquid2::ast::Template;
+use _liquid2::cache::{CacheError, CacheKey, TemplateCache};
+use _liquid2::loader::FileSystemLoader;
+use _liquid2::parser::LiquidParser;
+
+const SOURCE: &str = "{% for x in (1..3) %}{{ x | plus: 1 }}{% endfor %}{% cycle a: 'b' %}";
+
+/// A path in the system's temporary directory that is unique to this
+/// process and _name_.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("liquid2-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn save_and_load() {
+    let template = LiquidParser::new().parse(SOURCE).unwrap();
+    let path = temp_path("save-and-load.bin");
+    template.save(&path).unwrap();
+    let loaded = Template::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap().to_string(), template.to_string());
+}
+
+#[test]
+fn stale_header() {
+    let template = LiquidParser::new().parse(SOURCE).unwrap();
+    let mut bytes = template.to_bytes().unwrap();
+
+    // The schema fingerprint is the first string in the header, after the
+    // magic number and an eight byte length.
+    let first = 4 + 8;
+    bytes[first] = if bytes[first] == b'0' { b'1' } else { b'0' };
+
+    match Template::from_bytes(&bytes) {
+        Err(CacheError::Version { found, expected }) => assert_ne!(found, expected),
+        other => panic!("expected a version error, found {other:?}"),
+    }
+}
+
+#[test]
+fn missing_magic_number() {
+    assert!(matches!(
+        Template::from_bytes(b"not a cache file"),
+        Err(CacheError::Format(_))
+    ));
+}
+
+#[test]
+fn truncated_syntax_tree() {
+    let template = LiquidParser::new().parse(SOURCE).unwrap();
+    let bytes = template.to_bytes().unwrap();
+    assert!(matches!(
+        Template::from_bytes(&bytes[..bytes.len() - 4]),
+        Err(CacheError::Format(_))
+    ));
+}
+
+#[test]
+fn least_recently_used_templates_are_evicted() {
+    let parser = LiquidParser::new();
+    let mut cache = TemplateCache::new(2);
+    cache.parse(&parser, "a").unwrap();
+    cache.parse(&parser, "b").unwrap();
+    // Using "a" makes "b" the least recently used template.
+    cache.parse(&parser, "a").unwrap();
+    cache.parse(&parser, "c").unwrap();
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&CacheKey::source("a")).is_some());
+    assert!(cache.get(&CacheKey::source("b")).is_none());
+    assert!(cache.get(&CacheKey::source("c")).is_some());
+}
+
+#[test]
+fn templates_are_cached_by_their_whole_source() {
+    let parser = LiquidParser::new();
+    let mut cache = TemplateCache::new(10);
+    let a = cache.parse(&parser, "{{ a }}").unwrap();
+    let b = cache.parse(&parser, "{{ b }}").unwrap();
+    assert_eq!(a.template.to_string(), "{{ a }}");
+    assert_eq!(b.template.to_string(), "{{ b }}");
+    assert_eq!(cache.stats(), (0, 2));
+
+    let again = cache.parse(&parser, "{{ a }}").unwrap();
+    assert_eq!(again.template.to_string(), "{{ a }}");
+    assert_eq!(cache.stats(), (1, 2));
+}
+
+#[test]
+fn zero_capacity() {
+    let parser = LiquidParser::new();
+    let mut cache = TemplateCache::new(0);
+    let first = cache.parse(&parser, SOURCE).unwrap();
+    let second = cache.parse(&parser, SOURCE).unwrap();
+    assert!(cache.is_empty());
+    assert_eq!(cache.stats(), (0, 2));
+    assert_eq!(first.template.to_string(), second.template.to_string());
+}
+
+#[test]
+fn edited_files_are_loaded_again() {
+    let dir = temp_path("templates");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("page.liquid");
+    fs::write(&path, "old").unwrap();
+
+    let parser = LiquidParser::new();
+    let loader = FileSystemLoader::new([&dir]);
+    let mut cache = TemplateCache::new(10);
+    let before = cache.load(&parser, &loader, "page.liquid").unwrap();
+    let unchanged = cache.load(&parser, &loader, "page.liquid").unwrap();
+
+    // Some file systems only record modification times to the second.
+    fs::write(&path, "new").unwrap();
+    let mtime = SystemTime::now() + Duration::from_secs(10);
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+    let after = cache.load(&parser, &loader, "page.liquid").unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(before.template.to_string(), "old");
+    assert_eq!(unchanged.template.to_string(), "old");
+    assert_eq!(after.template.to_string(), "new");
+    assert_eq!(cache.stats(), (1, 2));
+}
@@ -0,0 +1,67 @@
+use _liquid2::parser::LiquidParser;
+
+fn equivalent(a: &str, b: &str) -> bool {
+    let parser = LiquidParser::new();
+    parser
+        .parse(a)
+        .unwrap()
+        .is_equivalent(&parser.parse(b).unwrap())
+}
+
+#[test]
+fn whitespace_inside_markup() {
+    assert!(equivalent("{{x|upcase}}", "{{ x | upcase }}"));
+    assert!(equivalent(
+        "{%if a%}b{%endif%}",
+        "{%   if   a   %}b{% endif %}"
+    ));
+    assert!(equivalent(
+        "{% liquid\n  assign x = 1\n  echo x\n%}",
+        "{% liquid assign x = 1\necho x %}"
+    ));
+}
+
+#[test]
+fn comment_content() {
+    assert!(equivalent("a{# one #}b", "a{# two #}b"));
+    assert!(equivalent("a{% comment %}one{% endcomment %}b", "ab"));
+    assert!(equivalent(
+        "{% if x %}a{# c #}b{% endif %}",
+        "{% if x %}ab{% endif %}"
+    ));
+}
+
+#[test]
+fn quote_style() {
+    assert!(equivalent(r#"{{ "a" }}"#, "{{ 'a' }}"));
+    assert!(equivalent(r#"{{ "it's" }}"#, r"{{ 'it\'s' }}"));
+    assert!(equivalent(r#"{{ a["b"] }}"#, "{{ a['b'] }}"));
+    assert!(equivalent(r#"{{ a["b"] }}"#, "{{ a.b }}"));
+}
+
+#[test]
+fn different_literal_types() {
+    assert!(!equivalent("{{ 1.0 }}", "{{ 1 }}"));
+    assert!(!equivalent("{{ '1' }}", "{{ 1 }}"));
+    assert!(!equivalent("{{ true }}", "{{ 'true' }}"));
+}
+
+#[test]
+fn different_whitespace_control() {
+    assert!(!equivalent("a {{- x }}", "a {{ x }}"));
+    assert!(!equivalent(
+        "{% if x ~%} a{% endif %}",
+        "{% if x %} a{% endif %}"
+    ));
+}
+
+#[test]
+fn different_content() {
+    assert!(!equivalent("a b", "a  b"));
+    assert!(!equivalent("{{ x }}", "{{ x | upcase }}"));
+    assert!(!equivalent("{{ a.b }}", "{{ a.c }}"));
+    assert!(!equivalent(
+        "{% for x in y %}{% endfor %}",
+        "{% for x in y reversed %}{% endfor %}"
+    ));
+}
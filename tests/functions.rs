@@ -0,0 +1,92 @@
+use serde_json::json;
+
+use _liquid2::errors::LiquidError;
+use _liquid2::lexer::{ExpressionType, Lexer};
+use _liquid2::query::eval::FunctionValue;
+use _liquid2::render::Renderer;
+
+/// A lexer with a `pick` function that returns _value_.
+fn lexer_with_pick(value: i64) -> Lexer {
+    let mut lexer = Lexer::new();
+    lexer.query_parser.register_function_extension(
+        "pick",
+        vec![ExpressionType::Value],
+        ExpressionType::Value,
+        move |_| Ok(FunctionValue::Value(json!(value))),
+    );
+    lexer
+}
+
+fn values(lexer: &Lexer, path: &str, data: &serde_json::Value) -> Vec<serde_json::Value> {
+    let query = lexer.parse_query(path).unwrap();
+    query
+        .find_with(data, &lexer.query_parser.function_extensions)
+        .unwrap()
+        .iter()
+        .map(|node| node.value().clone())
+        .collect()
+}
+
+#[test]
+fn implementations_belong_to_their_parser() {
+    let one = lexer_with_pick(1);
+    let two = lexer_with_pick(2);
+    let data = json!([1, 2, 3]);
+    assert_eq!(values(&one, "$[?@ == pick(@)]", &data), vec![json!(1)]);
+    assert_eq!(values(&two, "$[?@ == pick(@)]", &data), vec![json!(2)]);
+}
+
+#[test]
+fn find_without_extensions_gives_nothing() {
+    let lexer = lexer_with_pick(1);
+    let query = lexer.parse_query("$[?pick(@) == 1]").unwrap();
+    assert!(query.find(&json!([1, 2])).is_empty());
+}
+
+#[test]
+fn errors_from_extensions_are_returned() {
+    let mut lexer = Lexer::new();
+    lexer.query_parser.register_function_extension(
+        "fail",
+        vec![ExpressionType::Value],
+        ExpressionType::Logical,
+        |_| Err(LiquidError::ext("fail() failed".to_owned())),
+    );
+    let query = lexer.parse_query("$[?fail(@)]").unwrap();
+    let err = query
+        .find_with(&json!([1, 2]), &lexer.query_parser.function_extensions)
+        .unwrap_err();
+    assert_eq!(err.msg, "fail() failed");
+    assert_eq!(err.span.map(|span| span.start), Some(3));
+}
+
+#[test]
+fn extensions_replace_standard_functions() {
+    let mut lexer = Lexer::new();
+    let data = json!(["a", "bb"]);
+    assert_eq!(
+        values(&lexer, "$[?length(@) == 2]", &data),
+        vec![json!("bb")]
+    );
+    lexer.query_parser.register_function_extension(
+        "length",
+        vec![ExpressionType::Value],
+        ExpressionType::Value,
+        |_| Ok(FunctionValue::Value(json!(2))),
+    );
+    assert_eq!(
+        values(&lexer, "$[?length(@) == 2]", &data),
+        vec![json!("a"), json!("bb")]
+    );
+}
+
+#[test]
+fn renderers_call_extensions() {
+    let mut parser = _liquid2::parser::LiquidParser::new();
+    parser.lexer = lexer_with_pick(2);
+    let template = parser.parse("{{ a[?@ == pick(@)] }}").unwrap();
+    let mut renderer = Renderer::new(&template);
+    assert_eq!(renderer.render(&json!({"a": [1, 2]})).unwrap(), "");
+    renderer.function_extensions = parser.lexer.query_parser.function_extensions.clone();
+    assert_eq!(renderer.render(&json!({"a": [1, 2]})).unwrap(), "2");
+}
@@ -0,0 +1,116 @@
+use _liquid2::incremental::Edit;
+use _liquid2::parser::LiquidParser;
+use _liquid2::span::Span;
+
+/// Replace the first occurrence of _old_ in _source_ with _new_, then check
+/// that reparsing gives the same result as parsing the new source from
+/// scratch.
+fn assert_reparse(source: &str, old: &str, new: &str) {
+    let start = source.find(old).expect("old text is in source");
+    assert_reparse_at(source, Span::new(start, start + old.len()), new);
+}
+
+fn assert_reparse_at(source: &str, span: Span, text: &str) {
+    let parser = LiquidParser::new();
+    let template = parser.parse(source).unwrap();
+    let edit = Edit {
+        span,
+        text: text.to_owned(),
+    };
+    let new_source = edit.apply(source);
+    let reparsed = parser.reparse(&template, source, &edit);
+    let parsed = parser.parse(&new_source);
+    match (reparsed, parsed) {
+        (Ok(reparsed), Ok(parsed)) => assert_eq!(
+            format!("{reparsed:?}"),
+            format!("{parsed:?}"),
+            "reparsing {new_source:?}"
+        ),
+        (Err(reparsed), Err(parsed)) => assert_eq!(reparsed.to_string(), parsed.to_string()),
+        (reparsed, parsed) => panic!("reparsing {new_source:?}: {reparsed:?} != {parsed:?}"),
+    }
+}
+
+const SOURCE: &str = "Hello, {{ you }}!\n\
+    {% if a %}{{ a | upcase }}{% else %}b{% endif %}\n\
+    {% for x in y %}{{ x }}, {% endfor %}\n\
+    {% raw %}{{ raw }}{% endraw %} {% comment %}c{% endcomment %} end";
+
+#[test]
+fn edits_at_node_boundaries() {
+    // Insertions immediately before, between and after nodes.
+    let boundaries = [0, 7, 16, 18, SOURCE.len()];
+    for offset in boundaries {
+        assert_reparse_at(SOURCE, Span::new(offset, offset), "{{ z }}");
+        assert_reparse_at(SOURCE, Span::new(offset, offset), "text");
+    }
+    // Replacing and deleting whole nodes.
+    assert_reparse(SOURCE, "{{ you }}", "{{ me }}");
+    assert_reparse(SOURCE, "{{ you }}", "");
+    assert_reparse(SOURCE, "Hello, ", "");
+    assert_reparse(SOURCE, " end", "");
+    // Joining two nodes into one.
+    assert_reparse(SOURCE, "{{ you }}!\n", "you!\n");
+}
+
+#[test]
+fn edits_inside_blocks() {
+    assert_reparse(SOURCE, "{{ a | upcase }}", "{{ a | downcase }}");
+    assert_reparse(SOURCE, "{% else %}b", "{% elsif c %}d{% else %}b");
+    assert_reparse(SOURCE, "{{ x }}, ", "{% if x %}{{ x }}{% endif %}");
+    assert_reparse(SOURCE, "{% endif %}", "");
+    assert_reparse(SOURCE, "{% for x in y %}", "{% for x in y reversed %}");
+    assert_reparse(SOURCE, "{% endfor %}", "{% endfor %}{% endfor %}");
+}
+
+#[test]
+fn edits_that_open_or_close_raw_and_comment_blocks() {
+    // Opening a raw block swallows the markup after it.
+    assert_reparse(SOURCE, "{{ you }}", "{% raw %}{{ you }}");
+    assert_reparse(SOURCE, "Hello, ", "{% raw %}");
+    // Closing it early turns the rest of the block into markup.
+    assert_reparse(SOURCE, "{{ raw }}", "{% endraw %}{{ raw }}");
+    // Removing the end of a raw block.
+    assert_reparse(SOURCE, "{% endraw %}", "");
+    assert_reparse(
+        "{% raw %}a{% endraw %}{{ b }}{% raw %}c{% endraw %}",
+        "{% endraw %}",
+        "",
+    );
+
+    assert_reparse(SOURCE, "{{ you }}", "{% comment %}{{ you }}");
+    assert_reparse(SOURCE, "{% comment %}c", "{% comment %}{% endcomment %}c");
+    assert_reparse(SOURCE, "{% endcomment %}", "");
+    assert_reparse(SOURCE, "!\n", "{% comment %}");
+    assert_reparse(
+        "{% comment %}a{% endcomment %} {{ b }} {% comment %}c{% endcomment %}",
+        "{% endcomment %}",
+        "",
+    );
+    assert_reparse("{{ a }} {{ b }} {{ c }}", "{{ b }}", "{% # b %}");
+}
+
+#[test]
+fn edits_that_change_whitespace_control() {
+    assert_reparse(SOURCE, "{{ you }}", "{{- you -}}");
+    assert_reparse(SOURCE, "{% if a %}", "{%- if a -%}");
+    assert_reparse(SOURCE, "{% endif %}", "{%- endif -%}");
+    assert_reparse(SOURCE, "{% raw %}", "{%- raw -%}");
+    assert_reparse(SOURCE, "{% endraw %}", "{%- endraw %}");
+    assert_reparse(SOURCE, "{% comment %}", "{%- comment %}");
+    assert_reparse(SOURCE, "{{ x }}", "{{~ x ~}}");
+
+    // Adding and removing single markers.
+    let source = "a \n {{- b }} \n c {% if d -%} \n e {% endif %}";
+    assert_reparse(source, "{{-", "{{");
+    assert_reparse(source, "-%}", "%}");
+    assert_reparse(source, "{% endif", "{%- endif");
+    assert_reparse(source, "}} \n c", "-}} \n c");
+}
+
+#[test]
+fn edits_that_break_the_template() {
+    assert_reparse(SOURCE, "{% endif %}", "{% endfor %}");
+    assert_reparse(SOURCE, "{{ you }}", "{{ you");
+    assert_reparse(SOURCE, "{% if a %}", "{% if %}");
+}
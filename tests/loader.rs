@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use _liquid2::loader::{FileSystemLoader, Loader};
+
+/// A directory of templates in the system's temporary directory, unique to
+/// this process and _name_, removed when dropped.
+struct Templates(PathBuf);
+
+impl Templates {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("liquid2-{}-{name}", std::process::id()));
+        for (path, source) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Templates {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn names_are_relative_to_the_search_path() {
+    let templates = Templates::new(
+        "relative",
+        &[("page.liquid", "page"), ("partials/nav.liquid", "nav")],
+    );
+    let loader =
+        FileSystemLoader::new([templates.path().join("partials"), templates.path().into()]);
+
+    assert_eq!(loader.get_source("nav.liquid").unwrap(), "nav");
+    assert_eq!(loader.get_source("page.liquid").unwrap(), "page");
+    assert_eq!(loader.get_source("partials/nav.liquid").unwrap(), "nav");
+    assert_eq!(loader.get_source("./page.liquid").unwrap(), "page");
+    assert_eq!(loader.get_source("partials/./nav.liquid").unwrap(), "nav");
+    assert!(loader.resolve("partials").is_none());
+    assert!(loader.resolve("nosuchthing.liquid").is_none());
+}
+
+#[test]
+fn names_cannot_leave_the_search_path() {
+    let templates = Templates::new(
+        "parent",
+        &[("secret.liquid", "secret"), ("public/page.liquid", "page")],
+    );
+    let loader = FileSystemLoader::new([templates.path().join("public")]);
+
+    assert!(loader.resolve("page.liquid").is_some());
+    assert!(loader.resolve("../secret.liquid").is_none());
+    assert!(loader.resolve("../public/page.liquid").is_none());
+    assert!(loader.resolve("./../secret.liquid").is_none());
+
+    let absolute = templates.path().join("secret.liquid");
+    assert!(absolute.is_file());
+    assert!(loader.resolve(absolute.to_str().unwrap()).is_none());
+    assert!(loader.get_source("../secret.liquid").is_err());
+}
+
+#[test]
+fn default_extension() {
+    let templates = Templates::new(
+        "extension",
+        &[
+            ("page.liquid", "page"),
+            ("data.json", "data"),
+            ("v1.0/page.liquid", "v1"),
+        ],
+    );
+    let loader = FileSystemLoader::new([templates.path()]).with_default_extension(".liquid");
+    assert_eq!(loader.default_extension.as_deref(), Some("liquid"));
+
+    assert_eq!(loader.get_source("page").unwrap(), "page");
+    assert_eq!(loader.get_source("page.liquid").unwrap(), "page");
+    assert_eq!(loader.get_source("v1.0/page").unwrap(), "v1");
+    // Names that already have an extension are left alone.
+    assert_eq!(loader.get_source("data.json").unwrap(), "data");
+    assert!(loader.resolve("data").is_none());
+
+    let without = FileSystemLoader::new([templates.path()]);
+    assert!(without.resolve("page").is_none());
+}
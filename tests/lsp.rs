@@ -0,0 +1,131 @@
+use _liquid2::dialect::Dialect;
+use _liquid2::lsp::{
+    DiagnosticSeverity, Document, DocumentSymbol, FoldingRange, FoldingRangeKind, Position,
+    SymbolKind,
+};
+use _liquid2::parser::LiquidParser;
+
+fn position(line: u32, character: u32) -> Position {
+    Position { line, character }
+}
+
+/// The name, kind and number of children of each symbol.
+fn outline(symbols: &[DocumentSymbol]) -> Vec<(String, SymbolKind, usize)> {
+    symbols
+        .iter()
+        .map(|symbol| (symbol.name.clone(), symbol.kind, symbol.children.len()))
+        .collect()
+}
+
+#[test]
+fn positions_count_utf16_code_units() {
+    let parser = LiquidParser::new();
+    // "é" is two bytes and one code unit, "😀" is four bytes and two.
+    let source = "é😀x\n{{ y }}";
+    let document = Document::new(&parser, source);
+
+    assert_eq!(document.position(0), position(0, 0));
+    assert_eq!(document.position(2), position(0, 1));
+    assert_eq!(document.position(6), position(0, 3));
+    assert_eq!(document.position(7), position(0, 4));
+    assert_eq!(document.position(8), position(1, 0));
+    // Offsets inside a character belong to that character.
+    assert_eq!(document.position(4), position(0, 1));
+    assert_eq!(document.position(source.len()), position(1, 7));
+
+    assert_eq!(document.offset(position(0, 3)), 6);
+    assert_eq!(document.offset(position(1, 3)), 11);
+    // Columns are clamped to the end of their line, and lines to the end of
+    // the document.
+    assert_eq!(document.offset(position(0, 99)), 7);
+    assert_eq!(document.offset(position(9, 0)), source.len());
+}
+
+#[test]
+fn symbols() {
+    let parser = LiquidParser::new();
+    let source = "{% assign x = 1 %}\n{% for y in z %}\n  {% capture c %}{% endcapture %}\n{% endfor %}\n{% macro 'm' %}{% endmacro %}";
+    let symbols = Document::new(&parser, source).symbols();
+
+    assert_eq!(
+        outline(&symbols),
+        [
+            ("x".to_owned(), SymbolKind::Variable, 0),
+            ("for".to_owned(), SymbolKind::Object, 1),
+            ("m".to_owned(), SymbolKind::Function, 0),
+        ]
+    );
+    assert_eq!(symbols[0].detail.as_deref(), Some("assign"));
+    assert_eq!(symbols[1].detail.as_deref(), Some("y in z"));
+    assert_eq!(symbols[1].range.start, position(1, 0));
+    assert_eq!(symbols[1].range.end, position(3, 12));
+    assert_eq!(
+        outline(&symbols[1].children),
+        [("c".to_owned(), SymbolKind::Variable, 0)]
+    );
+    let capture = &symbols[1].children[0];
+    assert_eq!(capture.selection_range.start, position(2, 13));
+    assert_eq!(capture.selection_range.end, position(2, 14));
+}
+
+#[test]
+fn folding_ranges() {
+    let parser = LiquidParser::new();
+    let source = "{% if a %}\nb\nc\n{% endif %}\n{% comment %}\nd\n{% endcomment %}\n{% raw %}e{% endraw %}\n{% for x in y %}{% endfor %}";
+    let ranges = Document::new(&parser, source).folding_ranges();
+
+    assert_eq!(
+        ranges,
+        [
+            FoldingRange {
+                start_line: 0,
+                end_line: 2,
+                kind: FoldingRangeKind::Region,
+            },
+            FoldingRange {
+                start_line: 4,
+                end_line: 6,
+                kind: FoldingRangeKind::Comment,
+            },
+        ]
+    );
+}
+
+#[test]
+fn diagnostics() {
+    let parser = LiquidParser::new();
+    assert!(Document::new(&parser, "{{ x }}").diagnostics().is_empty());
+
+    let diagnostics = Document::new(&parser, "a\n{% if x %}b").diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+    assert_eq!(diagnostic.code, "syntax-error");
+    assert_eq!(diagnostic.source, "liquid2");
+    assert_eq!(diagnostic.range.start, position(1, 0));
+    assert!(
+        diagnostic.message.contains("endif"),
+        "{}",
+        diagnostic.message
+    );
+
+    // Markup errors are all reported, without parsing.
+    let diagnostics = Document::new(&parser, "{{ x \n{% if %}").diagnostics();
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error));
+}
+
+#[test]
+fn warnings() {
+    let mut dialect = Dialect::liquid2();
+    dialect.unreachable_branches = true;
+    let parser = LiquidParser::with_dialect(dialect);
+    let source = "{% if x %}{% else %}a{% else %}b{% endif %}";
+    let diagnostics = Document::new(&parser, source).diagnostics();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(diagnostics[0].range.start, position(0, 21));
+}
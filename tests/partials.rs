@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use _liquid2::loader::DictLoader;
+use _liquid2::parser::LiquidParser;
+
+fn loader(templates: &[(&str, &str)]) -> DictLoader {
+    DictLoader::new(
+        templates
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.to_string()))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+const SITE: [(&str, &str); 8] = [
+    (
+        "index",
+        "{% extends 'base' %}{% block body %}{% render 'card' %}{% endblock %}",
+    ),
+    (
+        "about",
+        "{% extends 'base' %}{% if x %}{% include 'team' %}{% endif %}",
+    ),
+    (
+        "feed",
+        "{% for item in items %}{% render 'card' %}{% endfor %}",
+    ),
+    ("base", "{% include 'nav' %}{% block body %}{% endblock %}"),
+    ("nav", "{% for link in links %}{{ link }}{% endfor %}"),
+    ("card", "{% render 'icon' %}"),
+    ("team", "{% render 'card' %}{% include name %}"),
+    ("icon", "<svg></svg>"),
+];
+
+#[test]
+fn affected_entries() {
+    let parser = LiquidParser::new();
+    let graph = parser
+        .dependency_graph(&["index", "about", "feed"], &loader(&SITE))
+        .unwrap();
+
+    assert_eq!(graph.entries(), ["about", "feed", "index"]);
+    assert_eq!(graph.affected_entries("nav"), ["about", "index"]);
+    assert_eq!(graph.affected_entries("base"), ["about", "index"]);
+    assert_eq!(graph.affected_entries("icon"), ["about", "feed", "index"]);
+    assert_eq!(graph.affected_entries("team"), ["about"]);
+    // "about" includes "team", which includes a partial named by a variable,
+    // so "about" might use any template.
+    assert_eq!(graph.affected_entries("feed"), ["about", "feed"]);
+    assert_eq!(graph.affected_entries("unused"), ["about"]);
+}
+
+#[test]
+fn unresolved_partials() {
+    let parser = LiquidParser::new();
+    let templates = loader(&[
+        ("page", "{% include 'list' %}"),
+        (
+            "list",
+            "{% for x in y %}{% include x.partial %}{% endfor %}",
+        ),
+        ("post", "{% if a %}{% include 'footer' %}{% endif %}"),
+        ("dynamic", "{% include name %}"),
+        ("footer", "bye"),
+    ]);
+    let graph = parser
+        .dependency_graph(&["page", "post", "dynamic"], &templates)
+        .unwrap();
+
+    assert_eq!(graph.unresolved(), ["dynamic", "list"]);
+    assert!(graph.dependencies("list").is_empty());
+    assert_eq!(
+        graph.affected_entries("footer"),
+        ["dynamic", "page", "post"]
+    );
+    assert_eq!(graph.affected_entries("anything"), ["dynamic", "page"]);
+}
+
+#[test]
+fn dependencies_and_dependents() {
+    let parser = LiquidParser::new();
+    let graph = parser
+        .dependency_graph(&["index", "about", "feed"], &loader(&SITE))
+        .unwrap();
+
+    assert_eq!(graph.dependencies("about"), ["base", "team"]);
+    // Partials named by a variable can't be followed.
+    assert_eq!(graph.dependencies("team"), ["card"]);
+    assert!(graph.dependencies("nav").is_empty());
+    assert_eq!(graph.dependents("card"), ["about", "feed", "index", "team"]);
+    assert_eq!(graph.dependents("base"), ["about", "index"]);
+    assert!(graph.dependents("index").is_empty());
+}
+
+#[test]
+fn cycles() {
+    let parser = LiquidParser::new();
+    let templates = loader(&[
+        ("page", "{% include 'a' %}"),
+        ("a", "{% include 'b' %}"),
+        ("b", "{% if x %}{% include 'a' %}{% endif %}"),
+    ]);
+    let graph = parser.dependency_graph(&["page"], &templates).unwrap();
+    assert_eq!(graph.dependents("a"), ["b", "page"]);
+    assert_eq!(graph.affected_entries("b"), ["page"]);
+}
+
+#[test]
+fn missing_partials_are_errors() {
+    let parser = LiquidParser::new();
+    let templates = loader(&[("page", "{% render 'missing' %}")]);
+    let err = parser.dependency_graph(&["page"], &templates).unwrap_err();
+    assert!(err.to_string().contains("missing"), "{err}");
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use _liquid2::parser::LiquidParser;
+use _liquid2::query::cache::QueryCache;
+use _liquid2::session::CompileSession;
+
+#[test]
+fn identical_sources_share_a_syntax_tree() {
+    let mut session = CompileSession::new();
+    let a = session.compile("a", "{{ x }}").unwrap();
+    let b = session.compile("b", "{{ x }}").unwrap();
+    assert!(Arc::ptr_eq(&a, &b));
+    assert_eq!(session.len(), 2);
+    assert_eq!(session.unique_templates(), 1);
+}
+
+#[test]
+fn replaced_templates_are_forgotten() {
+    let mut session = CompileSession::new();
+    session.compile("a", "{{ x }}").unwrap();
+    session.compile("b", "{{ x }}").unwrap();
+    session.compile("a", "{{ y }}").unwrap();
+    assert_eq!(session.unique_templates(), 2);
+
+    session.compile("b", "{{ z }}").unwrap();
+    assert_eq!(session.unique_templates(), 2);
+    assert_eq!(session.get("a").unwrap().to_string(), "{{ y }}");
+    assert_eq!(session.get("b").unwrap().to_string(), "{{ z }}");
+
+    session.compile("a", "{{ y }}").unwrap();
+    assert_eq!(session.unique_templates(), 2);
+}
+
+#[test]
+fn partial_names_are_interned() {
+    let mut session = CompileSession::new();
+    session
+        .compile("a", "{% render 'card' %}{% include name %}")
+        .unwrap();
+    session
+        .compile("b", "{% for x in y %}{% render 'card' %}{% endfor %}")
+        .unwrap();
+
+    let a = session.partials("a");
+    let b = session.partials("b");
+    assert_eq!(a.len(), 1);
+    assert_eq!(&*a[0], "card");
+    assert!(Arc::ptr_eq(&a[0], &b[0]));
+    assert!(session.partials("c").is_empty());
+}
+
+#[test]
+fn sessions_share_a_query_cache() {
+    let cache = Arc::new(QueryCache::new(10));
+    let one = CompileSession::with_query_cache(LiquidParser::new(), Arc::clone(&cache));
+    let two = CompileSession::with_query_cache(LiquidParser::new(), Arc::clone(&cache));
+
+    let query = one.parser.lexer.parse_query_shared("a.b").unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(Arc::ptr_eq(
+        &query,
+        &two.parser.lexer.parse_query_shared("a.b").unwrap()
+    ));
+}
+
+#[test]
+fn parsers_without_a_cache_are_given_one() {
+    let session = CompileSession::new();
+    assert!(session.query_cache().is_some());
+}
+
+#[test]
+fn failed_compiles_keep_the_previous_template() {
+    let mut session = CompileSession::new();
+    session.compile("a", "{{ x }}").unwrap();
+    assert!(session.compile("a", "{% if %}").is_err());
+    assert_eq!(session.get("a").unwrap().to_string(), "{{ x }}");
+    assert_eq!(session.unique_templates(), 1);
+}
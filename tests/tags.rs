@@ -0,0 +1,131 @@
+use _liquid2::ast::{Node, Template};
+use _liquid2::parser::LiquidParser;
+
+fn parser() -> LiquidParser {
+    let mut parser = LiquidParser::new();
+    parser.tags.register_inline("stamp");
+    parser.tags.register_block("box", &[], &[]);
+    parser
+        .tags
+        .register_block("switch", &["done", "stop"], &["on", "otherwise"]);
+    parser
+}
+
+fn parse(source: &str) -> Template {
+    parser().parse(source).unwrap()
+}
+
+fn error(source: &str) -> String {
+    match parser().parse(source) {
+        Ok(template) => panic!("expected an error, parsed {template}"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn inline_tag() {
+    let template = parse("a{% stamp x, 'y' %}b");
+    let Node::TagExtension {
+        name,
+        expression,
+        block,
+        branches,
+        end,
+        span,
+        ..
+    } = &template.nodes[1]
+    else {
+        panic!("expected a tag extension, found {:?}", template.nodes[1]);
+    };
+    assert_eq!(name, "stamp");
+    assert_eq!(expression.len(), 3);
+    assert!(block.is_empty());
+    assert!(branches.is_empty());
+    assert!(end.is_none());
+    assert_eq!((span.start, span.end), (1, 19));
+    assert_eq!(template.to_string(), "a{% stamp x, 'y' %}b");
+}
+
+#[test]
+fn block_tag_with_default_end_tag() {
+    let template = parse("{% box %}a{{ b }}{% endbox %}c");
+    assert_eq!(template.nodes.len(), 2);
+    let Node::TagExtension { block, end, .. } = &template.nodes[0] else {
+        panic!("expected a tag extension");
+    };
+    assert_eq!(block.len(), 2);
+    assert_eq!(end.as_ref().unwrap().name, "endbox");
+    assert_eq!(template.to_string(), "{% box %}a{{ b }}{% endbox %}c");
+}
+
+#[test]
+fn block_tag_with_custom_end_tags() {
+    for end_tag in ["done", "stop"] {
+        let source = format!("{{% switch %}}a{{% {end_tag} %}}");
+        let template = parse(&source);
+        let Node::TagExtension { block, end, .. } = &template.nodes[0] else {
+            panic!("expected a tag extension");
+        };
+        assert_eq!(block.len(), 1);
+        assert_eq!(end.as_ref().unwrap().name, end_tag);
+        assert_eq!(template.to_string(), source);
+    }
+    assert!(error("{% switch %}a{% endswitch %}").contains("unknown tag 'endswitch'"));
+}
+
+#[test]
+fn intermediate_tags() {
+    let source = "{% switch x %}a{% on 1 %}b{% on 2 %}{% otherwise %}c{% done %}";
+    let template = parse(source);
+    let Node::TagExtension {
+        block, branches, ..
+    } = &template.nodes[0]
+    else {
+        panic!("expected a tag extension");
+    };
+    assert_eq!(block.len(), 1);
+    let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, ["on", "on", "otherwise"]);
+    assert_eq!(branches[0].expression.len(), 1);
+    assert!(branches[1].block.is_empty());
+    assert_eq!(branches[2].block.len(), 1);
+    assert_eq!(template.to_string(), source);
+}
+
+#[test]
+fn nested_block_tag_with_the_same_name() {
+    let source = "{% box %}a{% box %}b{% endbox %}c{% endbox %}d";
+    let template = parse(source);
+    assert_eq!(template.nodes.len(), 2);
+    let Node::TagExtension { block, .. } = &template.nodes[0] else {
+        panic!("expected a tag extension");
+    };
+    assert_eq!(block.len(), 3);
+    let Node::TagExtension { block: inner, .. } = &block[1] else {
+        panic!("expected a nested tag extension, found {:?}", block[1]);
+    };
+    assert_eq!(inner.len(), 1);
+    assert_eq!(template.to_string(), source);
+}
+
+#[test]
+fn missing_end_tag() {
+    assert!(error("{% box %}a").contains("expected 'endbox'"));
+    assert!(error("{% switch %}a{% on %}b").contains("'done'"));
+}
+
+#[test]
+fn stray_delimiters() {
+    assert!(error("{% endbox %}").contains("unexpected tag 'endbox'"));
+    assert!(error("a{% on 1 %}b").contains("unexpected tag 'on'"));
+    assert!(error("{% box %}{% on 1 %}{% endbox %}").contains("found 'on'"));
+    assert!(error("{% if x %}{% done %}{% endif %}").contains("found 'done'"));
+}
+
+#[test]
+fn unregistered_tags() {
+    let mut parser = parser();
+    parser.tags.remove("stamp");
+    assert!(parser.parse("{% stamp %}").is_err());
+    assert!(LiquidParser::new().parse("{% box %}{% endbox %}").is_err());
+}